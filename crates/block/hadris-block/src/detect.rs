@@ -11,6 +11,8 @@
 pub enum BlockFormat {
     /// A FAT filesystem occupying the probed device or bounded partition.
     Fat(FatVariant),
+    /// An NTFS filesystem, opened by the unified opener with the `ntfs` feature.
+    Ntfs,
     /// A disk partition table.
     PartitionTable(PartitionTableKind),
 }
@@ -25,7 +27,7 @@ pub enum FatVariant {
     Fat16,
     /// A FAT32 filesystem.
     Fat32,
-    /// exFAT was recognized; the stable unified opener does not open it.
+    /// An exFAT filesystem, opened by the unified opener with the `exfat` feature.
     ExFat,
 }
 
@@ -46,6 +48,11 @@ pub enum PartitionTableKind {
 /// A protective MBR is reported as GPT based on its partition entries. The
 /// stream-based detectors additionally check for the GPT header signature.
 pub fn detect_sector(sector: &[u8; 512]) -> Option<BlockFormat> {
+    // NTFS boot code runs into the MBR partition-entry area, so its OEM ID
+    // has to be checked before those bytes are read as partitions.
+    if is_ntfs(sector) {
+        return Some(BlockFormat::Ntfs);
+    }
     if let Some(kind) = partition_kind(sector) {
         return Some(BlockFormat::PartitionTable(kind));
     }
@@ -85,6 +92,10 @@ fn partition_kind(sector: &[u8; 512]) -> Option<PartitionTableKind> {
     }
 }
 
+fn is_ntfs(sector: &[u8; 512]) -> bool {
+    sector[510..512] == [0x55, 0xaa] && &sector[3..11] == b"NTFS    "
+}
+
 fn fat_variant(sector: &[u8; 512]) -> Option<FatVariant> {
     if sector[510..512] != [0x55, 0xaa] {
         return None;
@@ -281,6 +292,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn recognizes_ntfs_before_boot_code_in_partition_entries() {
+        let mut sector = [0u8; 512];
+        sector[3..11].copy_from_slice(b"NTFS    ");
+        sector[446 + 4] = 0x83;
+        sector[446 + 12..446 + 16].copy_from_slice(&100u32.to_le_bytes());
+        sector[510..512].copy_from_slice(&[0x55, 0xaa]);
+        assert_eq!(detect_sector(&sector), Some(BlockFormat::Ntfs));
+
+        sector[510..512].fill(0);
+        assert_eq!(detect_sector(&sector), None);
+    }
+
     #[cfg(feature = "sync")]
     #[test]
     fn stream_probe_validates_gpt_signature_and_restores_position() {
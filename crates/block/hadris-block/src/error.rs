@@ -22,7 +22,11 @@ pub enum Error {
         opened: FatVariant,
     },
     /// FAT validation failed.
+    #[cfg(feature = "fat")]
     Fat(hadris_fat::Error),
+    /// NTFS validation failed.
+    #[cfg(feature = "ntfs")]
+    Ntfs(hadris_ntfs::NtfsError),
 }
 
 /// Result type for category-level block operations.
@@ -44,7 +48,10 @@ impl fmt::Display for Error {
                 formatter,
                 "detected {detected:?}, but full validation opened {opened:?}"
             ),
+            #[cfg(feature = "fat")]
             Self::Fat(error) => write!(formatter, "FAT open failed: {error}"),
+            #[cfg(feature = "ntfs")]
+            Self::Ntfs(error) => write!(formatter, "NTFS open failed: {error}"),
         }
     }
 }
@@ -58,8 +65,16 @@ impl From<hadris_io::Error> for Error {
     }
 }
 
+#[cfg(feature = "fat")]
 impl From<hadris_fat::Error> for Error {
     fn from(error: hadris_fat::Error) -> Self {
         Self::Fat(error)
     }
 }
+
+#[cfg(feature = "ntfs")]
+impl From<hadris_ntfs::NtfsError> for Error {
+    fn from(error: hadris_ntfs::NtfsError) -> Self {
+        Self::Ntfs(error)
+    }
+}
@@ -3,7 +3,8 @@
 //!
 //! This `no_std`-compatible facade groups format-neutral sector and block-device
 //! interfaces, GPT and MBR partition tables, filesystem detection, and
-//! FAT12/16/32 without hiding their concrete APIs.
+//! FAT12/16/32 without hiding their concrete APIs. The experimental `ntfs`
+//! feature adds an NTFS-aware volume opener, `AnyVolume::open`.
 
 #![no_std]
 #![deny(missing_docs)]
@@ -14,21 +15,29 @@ extern crate std;
 #[cfg(feature = "detect")]
 /// Lightweight, non-destructive block-format detection.
 pub mod detect;
-#[cfg(all(feature = "detect", feature = "fat"))]
+#[cfg(all(feature = "detect", any(feature = "fat", feature = "ntfs")))]
 mod error;
 #[cfg(all(feature = "detect", feature = "part", feature = "storage"))]
 /// Checked partition views for opening filesystems inside partitioned disks.
 pub mod partition;
 
-#[cfg(all(feature = "detect", feature = "fat"))]
+#[cfg(all(feature = "detect", any(feature = "fat", feature = "ntfs")))]
 pub use error::{Error, Result};
 
-#[cfg(all(feature = "detect", feature = "fat", feature = "sync"))]
+#[cfg(all(
+    feature = "detect",
+    any(feature = "fat", feature = "ntfs"),
+    feature = "sync"
+))]
 #[path = "volume_sync.rs"]
 /// Synchronous detection and unified volume opening.
 pub mod sync;
 
-#[cfg(all(feature = "detect", feature = "fat", feature = "async"))]
+#[cfg(all(
+    feature = "detect",
+    any(feature = "fat", feature = "ntfs"),
+    feature = "async"
+))]
 #[path = "volume_async.rs"]
 /// Asynchronous detection and unified volume opening.
 pub mod r#async;
@@ -37,13 +46,19 @@ pub mod r#async;
 #[cfg(feature = "storage")]
 pub use hadris_storage as storage;
 
-/// FAT12/16/32 filesystem support and exFAT format detection.
+/// FAT12/16/32 filesystem support, plus the exFAT preview behind `exfat`.
 ///
-/// The unified volume opener supports FAT12/16/32. The leaf `hadris-fat`
-/// crate carries a separate unstable exFAT preview.
+/// The unified volume opener supports FAT12/16/32, and exFAT when the
+/// unstable `exfat` feature enables the `hadris-fat` exFAT preview.
 #[cfg(feature = "fat")]
 pub use hadris_fat as fat;
 
+/// Experimental NTFS filesystem support.
+///
+/// The unified volume opener supports NTFS when this feature is enabled.
+#[cfg(feature = "ntfs")]
+pub use hadris_ntfs as ntfs;
+
 /// MBR, GPT, and hybrid partition-table support.
 #[cfg(feature = "part")]
 pub use hadris_part as part;
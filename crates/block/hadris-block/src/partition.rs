@@ -29,6 +29,21 @@ pub fn mbr_partition_view<'a, S>(
     )
 }
 
+/// Creates a bounded stream for partition `index` of an MBR disk.
+///
+/// Indices 0-3 are the primary slots and 4 onwards the logical partitions
+/// in EBR chain order, matching [`hadris_part::MbrDisk::partitions`].
+/// Returns `None` if the disk has no partition at `index`.
+#[cfg(feature = "alloc")]
+pub fn mbr_partition_view_at<'a, S>(
+    source: &'a mut S,
+    disk: &hadris_part::MbrDisk,
+    index: usize,
+) -> Option<hadris_storage::Result<PartitionView<'a, S>, hadris_io::ErrorKind>> {
+    let entry = disk.partition(index)?;
+    Some(mbr_partition_view(source, &entry, disk.block_size))
+}
+
 /// Creates a bounded stream for a GPT partition entry.
 pub fn gpt_partition_view<'a, S>(
     source: &'a mut S,
@@ -1,11 +1,20 @@
-use crate::detect::{BlockFormat, FatVariant};
+use crate::detect::BlockFormat;
+#[cfg(feature = "fat")]
+use crate::detect::FatVariant;
 use crate::{Error, Result};
+#[cfg(feature = "exfat")]
+use hadris_fat::r#async::exfat::ExFatVolume;
+#[cfg(feature = "fat")]
 use hadris_fat::r#async::fat_table::FatType;
+#[cfg(feature = "fat")]
 use hadris_fat::r#async::fs::FatVolume;
 use hadris_io::SeekFrom;
 use hadris_io::r#async::{Borrowed, Read, Seek};
+#[cfg(feature = "ntfs")]
+use hadris_ntfs::r#async::NtfsFs;
 
 /// An asynchronously opened block filesystem with concrete-format access.
+#[cfg(feature = "fat")]
 #[non_exhaustive]
 pub enum OpenVolume<'a, S>
 where
@@ -13,8 +22,12 @@ where
 {
     /// An opened FAT12, FAT16, or FAT32 filesystem.
     Fat(FatVolume<Borrowed<'a, S>>),
+    /// An opened exFAT filesystem (unstable `hadris-fat` exFAT preview).
+    #[cfg(feature = "exfat")]
+    ExFat(ExFatVolume<Borrowed<'a, S>>),
 }
 
+#[cfg(feature = "fat")]
 impl<'a, S> OpenVolume<'a, S>
 where
     S: Read + Seek<Error = <S as Read>::Error>,
@@ -25,6 +38,7 @@ where
     pub async fn open(source: &'a mut S, logical_block_size: u32) -> Result<Self> {
         match crate::detect::r#async::detect(source, logical_block_size).await? {
             Some(BlockFormat::Fat(format)) => Self::open_detected(source, format).await,
+            Some(BlockFormat::Ntfs) => Err(Error::UnsupportedFormat(BlockFormat::Ntfs)),
             Some(BlockFormat::PartitionTable(kind)) => Err(Error::PartitionedDisk(kind)),
             None => Err(Error::UnknownFormat),
         }
@@ -33,7 +47,7 @@ where
     /// Asynchronously opens a previously detected FAT variant.
     pub async fn open_detected(source: &'a mut S, detected: FatVariant) -> Result<Self> {
         if detected == FatVariant::ExFat {
-            return Err(Error::UnsupportedFormat(BlockFormat::Fat(detected)));
+            return Self::open_exfat(source).await;
         }
         source
             .seek(SeekFrom::Start(0))
@@ -47,10 +61,25 @@ where
         Ok(Self::Fat(fat))
     }
 
+    #[cfg(feature = "exfat")]
+    async fn open_exfat(source: &'a mut S) -> Result<Self> {
+        let exfat = ExFatVolume::open(Borrowed::new(source)).await?;
+        Ok(Self::ExFat(exfat))
+    }
+
+    #[cfg(not(feature = "exfat"))]
+    async fn open_exfat(_source: &'a mut S) -> Result<Self> {
+        Err(Error::UnsupportedFormat(BlockFormat::Fat(
+            FatVariant::ExFat,
+        )))
+    }
+
     /// Returns the concrete FAT variant of the opened filesystem.
     pub fn format(&self) -> FatVariant {
         match self {
             Self::Fat(fat) => fat_variant(fat.fat_type()),
+            #[cfg(feature = "exfat")]
+            Self::ExFat(_) => FatVariant::ExFat,
         }
     }
 
@@ -58,6 +87,8 @@ where
     pub fn as_fat(&self) -> Option<&FatVolume<Borrowed<'a, S>>> {
         match self {
             Self::Fat(fat) => Some(fat),
+            #[cfg(feature = "exfat")]
+            Self::ExFat(_) => None,
         }
     }
 
@@ -65,6 +96,8 @@ where
     pub fn as_fat_mut(&mut self) -> Option<&mut FatVolume<Borrowed<'a, S>>> {
         match self {
             Self::Fat(fat) => Some(fat),
+            #[cfg(feature = "exfat")]
+            Self::ExFat(_) => None,
         }
     }
 
@@ -73,6 +106,36 @@ where
     pub fn into_fat(self) -> core::result::Result<FatVolume<Borrowed<'a, S>>, Self> {
         match self {
             Self::Fat(fat) => Ok(fat),
+            #[cfg(feature = "exfat")]
+            other @ Self::ExFat(_) => Err(other),
+        }
+    }
+
+    /// Borrows the opened exFAT filesystem.
+    #[cfg(feature = "exfat")]
+    pub fn as_exfat(&self) -> Option<&ExFatVolume<Borrowed<'a, S>>> {
+        match self {
+            Self::ExFat(exfat) => Some(exfat),
+            Self::Fat(_) => None,
+        }
+    }
+
+    /// Mutably borrows the opened exFAT filesystem.
+    #[cfg(feature = "exfat")]
+    pub fn as_exfat_mut(&mut self) -> Option<&mut ExFatVolume<Borrowed<'a, S>>> {
+        match self {
+            Self::ExFat(exfat) => Some(exfat),
+            Self::Fat(_) => None,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    /// Extracts the exFAT filesystem, returning `self` if its format differs.
+    #[cfg(feature = "exfat")]
+    pub fn into_exfat(self) -> core::result::Result<ExFatVolume<Borrowed<'a, S>>, Self> {
+        match self {
+            Self::ExFat(exfat) => Ok(exfat),
+            other @ Self::Fat(_) => Err(other),
         }
     }
 
@@ -80,10 +143,110 @@ where
     pub fn into_inner(self) -> &'a mut S {
         match self {
             Self::Fat(fat) => fat.into_inner().0,
+            #[cfg(feature = "exfat")]
+            Self::ExFat(exfat) => exfat.into_inner().0,
+        }
+    }
+}
+
+/// An asynchronously opened block filesystem of any enabled format, including
+/// NTFS.
+///
+/// [`AnyVolume::open`] is the NTFS-aware counterpart of `OpenVolume::open`,
+/// which keeps reporting NTFS as unsupported so that FAT-only callers never
+/// see it.
+#[cfg(feature = "ntfs")]
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum AnyVolume<'a, S>
+where
+    S: Seek,
+{
+    /// An opened FAT12, FAT16, FAT32, or exFAT filesystem.
+    #[cfg(feature = "fat")]
+    Fat(OpenVolume<'a, S>),
+    /// An opened NTFS filesystem (experimental `hadris-ntfs` reader).
+    Ntfs(NtfsFs<Borrowed<'a, S>>),
+}
+
+#[cfg(feature = "ntfs")]
+impl<'a, S> AnyVolume<'a, S>
+where
+    S: Read + Seek<Error = <S as Read>::Error>,
+{
+    /// Asynchronously detects and opens a FAT-family or NTFS filesystem at the
+    /// start of `source`.
+    ///
+    /// Partitioned disks must first be narrowed to a partition view.
+    pub async fn open(source: &'a mut S, logical_block_size: u32) -> Result<Self> {
+        match crate::detect::r#async::detect(source, logical_block_size).await? {
+            #[cfg(feature = "fat")]
+            Some(BlockFormat::Fat(format)) => OpenVolume::open_detected(source, format)
+                .await
+                .map(Self::Fat),
+            #[cfg(not(feature = "fat"))]
+            Some(format @ BlockFormat::Fat(_)) => Err(Error::UnsupportedFormat(format)),
+            Some(BlockFormat::Ntfs) => {
+                source
+                    .seek(SeekFrom::Start(0))
+                    .await
+                    .map_err(hadris_io::Error::erase)?;
+                let ntfs = NtfsFs::open(Borrowed::new(source)).await?;
+                Ok(Self::Ntfs(ntfs))
+            }
+            Some(BlockFormat::PartitionTable(kind)) => Err(Error::PartitionedDisk(kind)),
+            None => Err(Error::UnknownFormat),
+        }
+    }
+
+    /// Returns the format of the opened filesystem.
+    pub fn block_format(&self) -> BlockFormat {
+        match self {
+            #[cfg(feature = "fat")]
+            Self::Fat(volume) => BlockFormat::Fat(volume.format()),
+            Self::Ntfs(_) => BlockFormat::Ntfs,
+        }
+    }
+
+    /// Borrows the opened NTFS filesystem.
+    pub fn as_ntfs(&self) -> Option<&NtfsFs<Borrowed<'a, S>>> {
+        match self {
+            Self::Ntfs(ntfs) => Some(ntfs),
+            #[cfg(feature = "fat")]
+            Self::Fat(_) => None,
+        }
+    }
+
+    /// Mutably borrows the opened NTFS filesystem.
+    pub fn as_ntfs_mut(&mut self) -> Option<&mut NtfsFs<Borrowed<'a, S>>> {
+        match self {
+            Self::Ntfs(ntfs) => Some(ntfs),
+            #[cfg(feature = "fat")]
+            Self::Fat(_) => None,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    /// Extracts the NTFS filesystem, returning `self` if its format differs.
+    pub fn into_ntfs(self) -> core::result::Result<NtfsFs<Borrowed<'a, S>>, Self> {
+        match self {
+            Self::Ntfs(ntfs) => Ok(ntfs),
+            #[cfg(feature = "fat")]
+            other @ Self::Fat(_) => Err(other),
+        }
+    }
+
+    /// Closes the filesystem and returns the borrowed source.
+    pub fn into_inner(self) -> &'a mut S {
+        match self {
+            #[cfg(feature = "fat")]
+            Self::Fat(volume) => volume.into_inner(),
+            Self::Ntfs(ntfs) => ntfs.into_inner().0,
         }
     }
 }
 
+#[cfg(feature = "fat")]
 fn fat_variant(format: FatType) -> FatVariant {
     match format {
         FatType::Fat12 => FatVariant::Fat12,
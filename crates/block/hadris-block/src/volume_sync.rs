@@ -1,9 +1,12 @@
-use crate::detect::{BlockFormat, FatVariant};
+use crate::detect::BlockFormat;
+#[cfg(feature = "fat")]
+use crate::detect::FatVariant;
 use crate::{Error, Result};
 use hadris_io::SeekFrom;
 use hadris_io::sync::{Borrowed, Read, Seek};
 
 /// An opened block filesystem with lossless access to its concrete handle.
+#[cfg(feature = "fat")]
 #[non_exhaustive]
 pub enum OpenVolume<'a, S>
 where
@@ -11,8 +14,12 @@ where
 {
     /// An opened FAT12, FAT16, or FAT32 filesystem.
     Fat(hadris_fat::sync::FatVolume<Borrowed<'a, S>>),
+    /// An opened exFAT filesystem (unstable `hadris-fat` exFAT preview).
+    #[cfg(feature = "exfat")]
+    ExFat(hadris_fat::sync::exfat::ExFatVolume<Borrowed<'a, S>>),
 }
 
+#[cfg(feature = "fat")]
 impl<'a, S> OpenVolume<'a, S>
 where
     S: Read + Seek<Error = <S as Read>::Error>,
@@ -23,6 +30,7 @@ where
     pub fn open(source: &'a mut S, logical_block_size: u32) -> Result<Self> {
         match crate::detect::sync::detect(source, logical_block_size)? {
             Some(BlockFormat::Fat(format)) => Self::open_detected(source, format),
+            Some(BlockFormat::Ntfs) => Err(Error::UnsupportedFormat(BlockFormat::Ntfs)),
             Some(BlockFormat::PartitionTable(kind)) => Err(Error::PartitionedDisk(kind)),
             None => Err(Error::UnknownFormat),
         }
@@ -31,7 +39,7 @@ where
     /// Opens a filesystem using a previously detected FAT variant.
     pub fn open_detected(source: &'a mut S, detected: FatVariant) -> Result<Self> {
         if detected == FatVariant::ExFat {
-            return Err(Error::UnsupportedFormat(BlockFormat::Fat(detected)));
+            return Self::open_exfat(source);
         }
         source
             .seek(SeekFrom::Start(0))
@@ -44,10 +52,25 @@ where
         Ok(Self::Fat(fat))
     }
 
+    #[cfg(feature = "exfat")]
+    fn open_exfat(source: &'a mut S) -> Result<Self> {
+        let exfat = hadris_fat::sync::exfat::ExFatVolume::open(Borrowed::new(source))?;
+        Ok(Self::ExFat(exfat))
+    }
+
+    #[cfg(not(feature = "exfat"))]
+    fn open_exfat(_source: &'a mut S) -> Result<Self> {
+        Err(Error::UnsupportedFormat(BlockFormat::Fat(
+            FatVariant::ExFat,
+        )))
+    }
+
     /// Returns the concrete FAT variant of the opened filesystem.
     pub fn format(&self) -> FatVariant {
         match self {
             Self::Fat(fat) => fat_variant(fat.fat_type()),
+            #[cfg(feature = "exfat")]
+            Self::ExFat(_) => FatVariant::ExFat,
         }
     }
 
@@ -55,6 +78,8 @@ where
     pub fn as_fat(&self) -> Option<&hadris_fat::sync::FatVolume<Borrowed<'a, S>>> {
         match self {
             Self::Fat(fat) => Some(fat),
+            #[cfg(feature = "exfat")]
+            Self::ExFat(_) => None,
         }
     }
 
@@ -62,6 +87,8 @@ where
     pub fn as_fat_mut(&mut self) -> Option<&mut hadris_fat::sync::FatVolume<Borrowed<'a, S>>> {
         match self {
             Self::Fat(fat) => Some(fat),
+            #[cfg(feature = "exfat")]
+            Self::ExFat(_) => None,
         }
     }
 
@@ -72,6 +99,40 @@ where
     ) -> core::result::Result<hadris_fat::sync::FatVolume<Borrowed<'a, S>>, Self> {
         match self {
             Self::Fat(fat) => Ok(fat),
+            #[cfg(feature = "exfat")]
+            other @ Self::ExFat(_) => Err(other),
+        }
+    }
+
+    /// Borrows the opened exFAT filesystem.
+    #[cfg(feature = "exfat")]
+    pub fn as_exfat(&self) -> Option<&hadris_fat::sync::exfat::ExFatVolume<Borrowed<'a, S>>> {
+        match self {
+            Self::ExFat(exfat) => Some(exfat),
+            Self::Fat(_) => None,
+        }
+    }
+
+    /// Mutably borrows the opened exFAT filesystem.
+    #[cfg(feature = "exfat")]
+    pub fn as_exfat_mut(
+        &mut self,
+    ) -> Option<&mut hadris_fat::sync::exfat::ExFatVolume<Borrowed<'a, S>>> {
+        match self {
+            Self::ExFat(exfat) => Some(exfat),
+            Self::Fat(_) => None,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    /// Extracts the exFAT filesystem, returning `self` if its format differs.
+    #[cfg(feature = "exfat")]
+    pub fn into_exfat(
+        self,
+    ) -> core::result::Result<hadris_fat::sync::exfat::ExFatVolume<Borrowed<'a, S>>, Self> {
+        match self {
+            Self::ExFat(exfat) => Ok(exfat),
+            other @ Self::Fat(_) => Err(other),
         }
     }
 
@@ -79,10 +140,110 @@ where
     pub fn into_inner(self) -> &'a mut S {
         match self {
             Self::Fat(fat) => fat.into_inner().0,
+            #[cfg(feature = "exfat")]
+            Self::ExFat(exfat) => exfat.into_inner().0,
+        }
+    }
+}
+
+/// An opened block filesystem of any enabled format, including NTFS.
+///
+/// [`AnyVolume::open`] is the NTFS-aware counterpart of `OpenVolume::open`,
+/// which keeps reporting NTFS as unsupported so that FAT-only callers never
+/// see it.
+#[cfg(feature = "ntfs")]
+#[allow(clippy::large_enum_variant)]
+#[non_exhaustive]
+pub enum AnyVolume<'a, S>
+where
+    S: Seek,
+{
+    /// An opened FAT12, FAT16, FAT32, or exFAT filesystem.
+    #[cfg(feature = "fat")]
+    Fat(OpenVolume<'a, S>),
+    /// An opened NTFS filesystem (experimental `hadris-ntfs` reader).
+    Ntfs(hadris_ntfs::sync::NtfsFs<Borrowed<'a, S>>),
+}
+
+#[cfg(feature = "ntfs")]
+impl<'a, S> AnyVolume<'a, S>
+where
+    S: Read + Seek<Error = <S as Read>::Error>,
+{
+    /// Detects and opens a FAT-family or NTFS filesystem at the beginning of
+    /// `source`.
+    ///
+    /// Partitioned disks must first be narrowed to a partition view.
+    pub fn open(source: &'a mut S, logical_block_size: u32) -> Result<Self> {
+        match crate::detect::sync::detect(source, logical_block_size)? {
+            #[cfg(feature = "fat")]
+            Some(BlockFormat::Fat(format)) => {
+                OpenVolume::open_detected(source, format).map(Self::Fat)
+            }
+            #[cfg(not(feature = "fat"))]
+            Some(format @ BlockFormat::Fat(_)) => Err(Error::UnsupportedFormat(format)),
+            Some(BlockFormat::Ntfs) => {
+                source
+                    .seek(SeekFrom::Start(0))
+                    .map_err(hadris_io::Error::erase)?;
+                let ntfs = hadris_ntfs::sync::NtfsFs::open(Borrowed::new(source))?;
+                Ok(Self::Ntfs(ntfs))
+            }
+            Some(BlockFormat::PartitionTable(kind)) => Err(Error::PartitionedDisk(kind)),
+            None => Err(Error::UnknownFormat),
+        }
+    }
+
+    /// Returns the format of the opened filesystem.
+    pub fn block_format(&self) -> BlockFormat {
+        match self {
+            #[cfg(feature = "fat")]
+            Self::Fat(volume) => BlockFormat::Fat(volume.format()),
+            Self::Ntfs(_) => BlockFormat::Ntfs,
+        }
+    }
+
+    /// Borrows the opened NTFS filesystem.
+    pub fn as_ntfs(&self) -> Option<&hadris_ntfs::sync::NtfsFs<Borrowed<'a, S>>> {
+        match self {
+            Self::Ntfs(ntfs) => Some(ntfs),
+            #[cfg(feature = "fat")]
+            Self::Fat(_) => None,
+        }
+    }
+
+    /// Mutably borrows the opened NTFS filesystem.
+    pub fn as_ntfs_mut(&mut self) -> Option<&mut hadris_ntfs::sync::NtfsFs<Borrowed<'a, S>>> {
+        match self {
+            Self::Ntfs(ntfs) => Some(ntfs),
+            #[cfg(feature = "fat")]
+            Self::Fat(_) => None,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    /// Extracts the NTFS filesystem, returning `self` if its format differs.
+    pub fn into_ntfs(
+        self,
+    ) -> core::result::Result<hadris_ntfs::sync::NtfsFs<Borrowed<'a, S>>, Self> {
+        match self {
+            Self::Ntfs(ntfs) => Ok(ntfs),
+            #[cfg(feature = "fat")]
+            other @ Self::Fat(_) => Err(other),
+        }
+    }
+
+    /// Closes the filesystem and returns the borrowed source.
+    pub fn into_inner(self) -> &'a mut S {
+        match self {
+            #[cfg(feature = "fat")]
+            Self::Fat(volume) => volume.into_inner(),
+            Self::Ntfs(ntfs) => ntfs.into_inner().0,
         }
     }
 }
 
+#[cfg(feature = "fat")]
 fn fat_variant(format: hadris_fat::sync::FatType) -> FatVariant {
     match format {
         hadris_fat::sync::FatType::Fat12 => FatVariant::Fat12,
@@ -150,6 +150,7 @@ fn async_partition_view_enforces_relative_bounds() {
     });
 }
 
+#[cfg(not(feature = "exfat"))]
 #[test]
 fn async_detects_exfat_but_rejects_unified_opening() {
     block_on(async {
@@ -178,10 +179,7 @@ fn async_detection_and_open_restore_and_release_source() {
         let detected = hadris_block::detect::r#async::detect(&mut source, 512)
             .await
             .unwrap();
-        assert_eq!(
-            detected,
-            Some(hadris_block::detect::BlockFormat::Fat(FatVariant::Fat12))
-        );
+        assert_eq!(detected, Some(BlockFormat::Fat(FatVariant::Fat12)));
         assert_eq!(source.stream_position().await.unwrap(), 23);
 
         let volume = OpenVolume::open(&mut source, 512).await.unwrap();
@@ -424,3 +422,153 @@ fn async_unknown_block_input_is_non_destructive_and_category_typed() {
         ));
     });
 }
+
+#[cfg(feature = "exfat")]
+#[test]
+fn async_opens_detected_exfat() {
+    use hadris_fat::exfat::{ExFatFormatOptions, format_exfat};
+    const LEN: usize = 8 * 1024 * 1024;
+    let options = ExFatFormatOptions::default();
+    let fs = format_exfat(std::io::Cursor::new(vec![0_u8; LEN]), LEN as u64, &options).unwrap();
+    fs.create_dir(&fs.root_dir(), "DCIM").unwrap();
+    let image = fs.into_inner().into_inner();
+
+    block_on(async {
+        let mut source = AsyncCursor::new(image);
+        let volume = OpenVolume::open(&mut source, 512).await.unwrap();
+        assert_eq!(volume.format(), FatVariant::ExFat);
+        assert!(volume.as_fat().is_none());
+        let exfat = volume.as_exfat().unwrap();
+        assert!(exfat.open_path("/dcim").await.unwrap().is_directory());
+        assert_eq!(volume.into_inner().bytes.len(), LEN);
+    });
+}
+
+/// A 256 KiB NTFS volume holding `$MFT`, an empty root directory, and a
+/// zero-filled `$UpCase`.
+#[cfg(feature = "ntfs")]
+fn minimal_ntfs() -> Vec<u8> {
+    const REC: usize = 1024;
+    let mut image = vec![0_u8; 256 * 1024];
+    image[3..11].copy_from_slice(b"NTFS    ");
+    image[11..13].copy_from_slice(&512_u16.to_le_bytes());
+    image[13] = 1; // sectors per cluster
+    image[40..48].copy_from_slice(&512_u64.to_le_bytes()); // total sectors
+    image[48..56].copy_from_slice(&4_u64.to_le_bytes()); // $MFT LCN
+    image[56..64].copy_from_slice(&2_u64.to_le_bytes()); // $MFTMirr LCN
+    image[64] = (-10_i8) as u8; // 1024-byte MFT records
+    image[68] = (-10_i8) as u8; // 1024-byte index records
+    image[72..80].copy_from_slice(&0x0123_4567_89AB_CDEF_u64.to_le_bytes());
+    image[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+    let data = |last_vcn: u64, size: u64, runs: &[u8]| {
+        let mut a = vec![0_u8; 0x48];
+        a[0..4].copy_from_slice(&0x80_u32.to_le_bytes());
+        a[4..8].copy_from_slice(&0x48_u32.to_le_bytes());
+        a[8] = 1;
+        a[0x18..0x20].copy_from_slice(&last_vcn.to_le_bytes());
+        a[0x20..0x22].copy_from_slice(&0x40_u16.to_le_bytes());
+        a[0x28..0x30].copy_from_slice(&((last_vcn + 1) * 512).to_le_bytes());
+        a[0x30..0x38].copy_from_slice(&size.to_le_bytes());
+        a[0x38..0x40].copy_from_slice(&size.to_le_bytes());
+        a[0x40..0x40 + runs.len()].copy_from_slice(runs);
+        a
+    };
+    let mut root = vec![0_u8; 0x50];
+    root[0..4].copy_from_slice(&0x90_u32.to_le_bytes());
+    root[4..8].copy_from_slice(&0x50_u32.to_le_bytes());
+    root[9] = 4; // "$I30"
+    root[0x0A..0x0C].copy_from_slice(&0x18_u16.to_le_bytes());
+    root[0x10..0x14].copy_from_slice(&0x30_u32.to_le_bytes());
+    root[0x14..0x16].copy_from_slice(&0x20_u16.to_le_bytes());
+    root[0x18..0x20].copy_from_slice(&[0x24, 0, 0x49, 0, 0x33, 0, 0x30, 0]);
+    root[0x20..0x24].copy_from_slice(&0x30_u32.to_le_bytes()); // indexes $FILE_NAME
+    root[0x24..0x28].copy_from_slice(&1_u32.to_le_bytes());
+    root[0x28..0x2C].copy_from_slice(&(REC as u32).to_le_bytes());
+    root[0x2C] = 2;
+    root[0x30..0x34].copy_from_slice(&16_u32.to_le_bytes());
+    root[0x34..0x38].copy_from_slice(&32_u32.to_le_bytes());
+    root[0x38..0x3C].copy_from_slice(&32_u32.to_le_bytes());
+    root[0x48..0x4A].copy_from_slice(&16_u16.to_le_bytes()); // last entry
+    root[0x4C..0x50].copy_from_slice(&2_u32.to_le_bytes());
+
+    let records = [
+        (0, 1_u16, data(31, 16 * REC as u64, &[0x11, 0x20, 0x04])),
+        (5, 3, root),
+        (10, 1, data(255, 131072, &[0x02, 0x00, 0x01])),
+    ];
+    for (index, flags, attr) in records {
+        // An all-zero update sequence number matches the zeroed sector tails.
+        let r = &mut image[2048 + index * REC..2048 + (index + 1) * REC];
+        r[0..4].copy_from_slice(b"FILE");
+        r[4..6].copy_from_slice(&0x30_u16.to_le_bytes());
+        r[6..8].copy_from_slice(&3_u16.to_le_bytes());
+        r[0x10..0x12].copy_from_slice(&1_u16.to_le_bytes());
+        r[0x14..0x16].copy_from_slice(&0x38_u16.to_le_bytes());
+        r[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+        let end = 0x38 + attr.len();
+        r[0x38..end].copy_from_slice(&attr);
+        r[end..end + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        r[0x18..0x1C].copy_from_slice(&((end + 8) as u32).to_le_bytes());
+    }
+    image
+}
+
+#[cfg(feature = "ntfs")]
+#[test]
+fn async_opens_detected_ntfs() {
+    use hadris_block::r#async::AnyVolume;
+
+    block_on(async {
+        let mut source = AsyncCursor::new(minimal_ntfs());
+        source.seek(SeekFrom::Start(11)).await.unwrap();
+
+        assert!(matches!(
+            OpenVolume::open(&mut source, 512).await,
+            Err(Error::UnsupportedFormat(BlockFormat::Ntfs))
+        ));
+        let volume = AnyVolume::open(&mut source, 512).await.unwrap();
+        assert_eq!(volume.block_format(), BlockFormat::Ntfs);
+        let ntfs = volume.as_ntfs().unwrap();
+        assert_eq!(ntfs.volume_serial(), 0x0123_4567_89AB_CDEF);
+        assert!(ntfs.root_dir().entries().await.unwrap().is_empty());
+        assert_eq!(volume.into_inner().bytes.len(), 256 * 1024);
+    });
+}
+
+#[cfg(feature = "ntfs")]
+#[test]
+fn async_ntfs_aware_opener_keeps_fat_volumes_as_open_volume() {
+    use hadris_block::r#async::AnyVolume;
+
+    let image = formatted_fat12();
+    block_on(async {
+        let mut source = AsyncCursor::new(image);
+        let volume = AnyVolume::open(&mut source, 512).await.unwrap();
+        assert_eq!(volume.block_format(), BlockFormat::Fat(FatVariant::Fat12));
+        assert!(volume.as_ntfs().is_none());
+        let AnyVolume::Fat(volume) = volume else {
+            panic!("FAT volume opened as another format");
+        };
+        assert_eq!(volume.format(), FatVariant::Fat12);
+        assert!(volume.as_fat().is_some());
+    });
+}
+
+#[cfg(not(feature = "ntfs"))]
+#[test]
+fn async_detects_ntfs_but_rejects_unified_opening() {
+    block_on(async {
+        let mut image = vec![0_u8; 512];
+        image[3..11].copy_from_slice(b"NTFS    ");
+        image[510..512].copy_from_slice(&[0x55, 0xaa]);
+        let mut source = AsyncCursor::new(image);
+        source.seek(SeekFrom::Start(11)).await.unwrap();
+
+        assert!(matches!(
+            OpenVolume::open(&mut source, 512).await,
+            Err(Error::UnsupportedFormat(BlockFormat::Ntfs))
+        ));
+        assert_eq!(source.position, 11);
+    });
+}
@@ -1,5 +1,5 @@
 use hadris_block::detect::{BlockFormat, FatVariant, PartitionTableKind};
-use hadris_block::partition::{gpt_partition_view, mbr_partition_view};
+use hadris_block::partition::{gpt_partition_view, mbr_partition_view, mbr_partition_view_at};
 use hadris_block::sync::OpenVolume;
 use hadris_block::{Error, part};
 use hadris_fat::format::{FatFormatOptions, FatTypeSelection, FatVolumeFormatter};
@@ -56,6 +56,34 @@ fn opens_fat_inside_mbr_partition_view() {
     assert_eq!(volume.format(), FatVariant::Fat12);
 }
 
+#[test]
+fn opens_fat_inside_logical_partition_view() {
+    let sector_count = (VOLUME_LEN / 512) as u32;
+    let mut disk = part::MbrDisk::new(512);
+    disk.create_extended(0, 1, sector_count + 2).unwrap();
+    let index = disk
+        .add_logical(part::MbrPartitionType::Fat12, 3, sector_count)
+        .unwrap();
+    assert_eq!(index, 4);
+
+    let mut image = std::io::Cursor::new(vec![0_u8; VOLUME_LEN + 3 * 512]);
+    part::MbrDiskWriteExt::write_to(&disk, &mut image).unwrap();
+    {
+        let view = mbr_partition_view_at(&mut image, &disk, index)
+            .unwrap()
+            .unwrap();
+        format_fat12(view);
+    }
+
+    let disk = <part::MbrDisk as part::MbrDiskReadExt>::read_from(&mut image, 512).unwrap();
+    assert!(mbr_partition_view_at(&mut image, &disk, index + 1).is_none());
+    let mut view = mbr_partition_view_at(&mut image, &disk, index)
+        .unwrap()
+        .unwrap();
+    let volume = OpenVolume::open(&mut view, 512).unwrap();
+    assert_eq!(volume.format(), FatVariant::Fat12);
+}
+
 #[test]
 fn opens_fat_inside_gpt_partition_view() {
     let start_lba = 2048_u64;
@@ -95,6 +123,7 @@ fn rejects_unknown_and_mismatched_formats_without_consuming_source() {
     image.set_position(9);
 }
 
+#[cfg(not(feature = "exfat"))]
 #[test]
 fn detects_exfat_but_rejects_unified_opening() {
     let mut image = vec![0_u8; 512];
@@ -111,3 +140,171 @@ fn detects_exfat_but_rejects_unified_opening() {
     ));
     assert_eq!(image.position(), 11);
 }
+
+#[cfg(feature = "exfat")]
+fn formatted_exfat() -> Vec<u8> {
+    use hadris_fat::exfat::{ExFatFormatOptions, format_exfat};
+    const LEN: usize = 8 * 1024 * 1024;
+    let options = ExFatFormatOptions::default().volume_label("CARD");
+    let fs = format_exfat(std::io::Cursor::new(vec![0_u8; LEN]), LEN as u64, &options).unwrap();
+    let entry = fs.create_file(&fs.root_dir(), "hello.txt").unwrap();
+    let mut writer = fs.write_file(&entry).unwrap();
+    hadris_io::sync::Write::write_all(&mut writer, b"hello exfat").unwrap();
+    writer.finish().unwrap();
+    fs.into_inner().into_inner()
+}
+
+#[cfg(feature = "exfat")]
+#[test]
+fn opens_detected_exfat() {
+    let mut image = std::io::Cursor::new(formatted_exfat());
+    image.set_position(11);
+
+    let volume = OpenVolume::open(&mut image, 512).unwrap();
+    assert_eq!(volume.format(), FatVariant::ExFat);
+    assert!(volume.as_fat().is_none());
+    let exfat = volume.as_exfat().unwrap();
+    let mut reader = exfat.open_file("/HELLO.TXT").unwrap();
+    let mut contents = vec![0_u8; reader.size() as usize];
+    hadris_io::sync::Read::read_exact(&mut reader, &mut contents).unwrap();
+    assert_eq!(contents, b"hello exfat");
+
+    let volume = match volume.into_fat() {
+        Ok(_) => panic!("exFAT volume extracted as FAT"),
+        Err(volume) => volume,
+    };
+    assert!(volume.into_exfat().is_ok());
+}
+
+/// A 256 KiB NTFS volume holding `$MFT`, an empty root directory, and a
+/// zero-filled `$UpCase`.
+#[cfg(feature = "ntfs")]
+fn minimal_ntfs() -> Vec<u8> {
+    const REC: usize = 1024;
+    let mut image = vec![0_u8; 256 * 1024];
+    image[3..11].copy_from_slice(b"NTFS    ");
+    image[11..13].copy_from_slice(&512_u16.to_le_bytes());
+    image[13] = 1; // sectors per cluster
+    image[40..48].copy_from_slice(&512_u64.to_le_bytes()); // total sectors
+    image[48..56].copy_from_slice(&4_u64.to_le_bytes()); // $MFT LCN
+    image[56..64].copy_from_slice(&2_u64.to_le_bytes()); // $MFTMirr LCN
+    image[64] = (-10_i8) as u8; // 1024-byte MFT records
+    image[68] = (-10_i8) as u8; // 1024-byte index records
+    image[72..80].copy_from_slice(&0x0123_4567_89AB_CDEF_u64.to_le_bytes());
+    image[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+    let data = |last_vcn: u64, size: u64, runs: &[u8]| {
+        let mut a = vec![0_u8; 0x48];
+        a[0..4].copy_from_slice(&0x80_u32.to_le_bytes());
+        a[4..8].copy_from_slice(&0x48_u32.to_le_bytes());
+        a[8] = 1;
+        a[0x18..0x20].copy_from_slice(&last_vcn.to_le_bytes());
+        a[0x20..0x22].copy_from_slice(&0x40_u16.to_le_bytes());
+        a[0x28..0x30].copy_from_slice(&((last_vcn + 1) * 512).to_le_bytes());
+        a[0x30..0x38].copy_from_slice(&size.to_le_bytes());
+        a[0x38..0x40].copy_from_slice(&size.to_le_bytes());
+        a[0x40..0x40 + runs.len()].copy_from_slice(runs);
+        a
+    };
+    let mut root = vec![0_u8; 0x50];
+    root[0..4].copy_from_slice(&0x90_u32.to_le_bytes());
+    root[4..8].copy_from_slice(&0x50_u32.to_le_bytes());
+    root[9] = 4; // "$I30"
+    root[0x0A..0x0C].copy_from_slice(&0x18_u16.to_le_bytes());
+    root[0x10..0x14].copy_from_slice(&0x30_u32.to_le_bytes());
+    root[0x14..0x16].copy_from_slice(&0x20_u16.to_le_bytes());
+    root[0x18..0x20].copy_from_slice(&[0x24, 0, 0x49, 0, 0x33, 0, 0x30, 0]);
+    root[0x20..0x24].copy_from_slice(&0x30_u32.to_le_bytes()); // indexes $FILE_NAME
+    root[0x24..0x28].copy_from_slice(&1_u32.to_le_bytes());
+    root[0x28..0x2C].copy_from_slice(&(REC as u32).to_le_bytes());
+    root[0x2C] = 2;
+    root[0x30..0x34].copy_from_slice(&16_u32.to_le_bytes());
+    root[0x34..0x38].copy_from_slice(&32_u32.to_le_bytes());
+    root[0x38..0x3C].copy_from_slice(&32_u32.to_le_bytes());
+    root[0x48..0x4A].copy_from_slice(&16_u16.to_le_bytes()); // last entry
+    root[0x4C..0x50].copy_from_slice(&2_u32.to_le_bytes());
+
+    let records = [
+        (0, 1_u16, data(31, 16 * REC as u64, &[0x11, 0x20, 0x04])),
+        (5, 3, root),
+        (10, 1, data(255, 131072, &[0x02, 0x00, 0x01])),
+    ];
+    for (index, flags, attr) in records {
+        // An all-zero update sequence number matches the zeroed sector tails.
+        let r = &mut image[2048 + index * REC..2048 + (index + 1) * REC];
+        r[0..4].copy_from_slice(b"FILE");
+        r[4..6].copy_from_slice(&0x30_u16.to_le_bytes());
+        r[6..8].copy_from_slice(&3_u16.to_le_bytes());
+        r[0x10..0x12].copy_from_slice(&1_u16.to_le_bytes());
+        r[0x14..0x16].copy_from_slice(&0x38_u16.to_le_bytes());
+        r[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+        let end = 0x38 + attr.len();
+        r[0x38..end].copy_from_slice(&attr);
+        r[end..end + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        r[0x18..0x1C].copy_from_slice(&((end + 8) as u32).to_le_bytes());
+    }
+    image
+}
+
+#[cfg(feature = "ntfs")]
+#[test]
+fn opens_detected_ntfs() {
+    use hadris_block::sync::AnyVolume;
+
+    let mut image = std::io::Cursor::new(minimal_ntfs());
+    image.set_position(11);
+
+    assert!(matches!(
+        OpenVolume::open(&mut image, 512),
+        Err(Error::UnsupportedFormat(BlockFormat::Ntfs))
+    ));
+    let volume = AnyVolume::open(&mut image, 512).unwrap();
+    assert_eq!(volume.block_format(), BlockFormat::Ntfs);
+    let ntfs = volume.as_ntfs().unwrap();
+    assert_eq!(ntfs.volume_serial(), 0x0123_4567_89AB_CDEF);
+    assert!(ntfs.root_dir().entries().unwrap().is_empty());
+
+    let ntfs = volume.into_ntfs().ok().unwrap();
+    assert_eq!(ntfs.into_inner().0.get_ref().len(), 256 * 1024);
+}
+
+#[cfg(feature = "ntfs")]
+#[test]
+fn ntfs_aware_opener_keeps_fat_volumes_as_open_volume() {
+    use hadris_block::sync::AnyVolume;
+
+    let mut image = std::io::Cursor::new(vec![0_u8; VOLUME_LEN]);
+    format_fat12(hadris_io::sync::Borrowed::new(&mut image));
+
+    let volume = AnyVolume::open(&mut image, 512).unwrap();
+    assert_eq!(volume.block_format(), BlockFormat::Fat(FatVariant::Fat12));
+    assert!(volume.as_ntfs().is_none());
+    let volume = match volume.into_ntfs() {
+        Ok(_) => panic!("FAT volume extracted as NTFS"),
+        Err(AnyVolume::Fat(volume)) => volume,
+        Err(_) => panic!("FAT volume opened as another format"),
+    };
+    assert_eq!(volume.format(), FatVariant::Fat12);
+    assert!(volume.as_fat().is_some());
+    assert_eq!(volume.into_inner().get_ref().len(), VOLUME_LEN);
+}
+
+#[cfg(not(feature = "ntfs"))]
+#[test]
+fn detects_ntfs_but_rejects_unified_opening() {
+    let mut image = vec![0_u8; 512];
+    image[3..11].copy_from_slice(b"NTFS    ");
+    image[510..512].copy_from_slice(&[0x55, 0xaa]);
+    let mut image = std::io::Cursor::new(image);
+    image.set_position(11);
+
+    assert_eq!(
+        hadris_block::detect::sync::detect(&mut image, 512).unwrap(),
+        Some(BlockFormat::Ntfs)
+    );
+    assert!(matches!(
+        OpenVolume::open(&mut image, 512),
+        Err(Error::UnsupportedFormat(BlockFormat::Ntfs))
+    ));
+    assert_eq!(image.position(), 11);
+}
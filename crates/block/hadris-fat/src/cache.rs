@@ -195,6 +195,12 @@ impl FatSectorCache {
         Ok(())
     }
 
+    /// Maximum number of sectors this cache holds.
+    #[cfg(feature = "write")]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     /// Get the number of cached sectors.
     pub fn len(&self) -> usize {
         self.entries.len()
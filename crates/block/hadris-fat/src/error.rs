@@ -107,6 +107,16 @@ pub enum Error {
         bit: &'static str,
     },
 
+    /// A journal transaction needs more room than the journal file has. The
+    /// operation was discarded and the volume is unchanged.
+    #[cfg(feature = "write")]
+    JournalFull {
+        /// Bytes of records the transaction needed.
+        needed: usize,
+        /// Bytes of records the journal holds.
+        capacity: usize,
+    },
+
     /// A read-only [`crate::cache::FatSectorCache`] operation needed to evict
     /// a sector to make room for a new one, but every cached sector is
     /// dirty. The caller must call [`crate::cache::FatSectorCache::flush`]
@@ -259,6 +269,13 @@ impl fmt::Display for Error {
             Self::InvalidAttributeChange { bit } => {
                 write!(f, "cannot change immutable attribute bit `{bit}` in place")
             }
+            #[cfg(feature = "write")]
+            Self::JournalFull { needed, capacity } => {
+                write!(
+                    f,
+                    "journal transaction needs {needed} bytes but the journal holds {capacity}"
+                )
+            }
             #[cfg(feature = "cache")]
             Self::CacheDirtyEviction { sector } => {
                 write!(
@@ -393,6 +410,13 @@ impl defmt::Format for Error {
             Self::InvalidAttributeChange { bit } => {
                 defmt::write!(f, "cannot change immutable attribute bit `{=str}`", *bit)
             }
+            #[cfg(feature = "write")]
+            Self::JournalFull { needed, capacity } => defmt::write!(
+                f,
+                "journal transaction needs {=usize} bytes, journal holds {=usize}",
+                *needed,
+                *capacity
+            ),
             #[cfg(feature = "cache")]
             Self::CacheDirtyEviction { sector } => defmt::write!(
                 f,
@@ -6,14 +6,17 @@
 //! The allocation bitmap is stored in the cluster heap as a special file
 //! identified by the Allocation Bitmap directory entry (type 0x81).
 
+io_transform! {
+
 use alloc::vec::Vec;
 
-use crate::error::{Error, Result};
 #[cfg(feature = "write")]
-use crate::io::Write;
-use crate::io::{Read, Seek, SeekFrom};
+use super::super::io::Write;
+use super::super::io::{Read, Seek, SeekFrom};
+use crate::error::{Error, Result};
 
 use super::ExFatInfo;
+use super::fat::ExFatTable;
 
 /// Allocation bitmap for tracking cluster usage.
 ///
@@ -30,6 +33,8 @@ pub struct AllocationBitmap {
     cluster_count: u32,
     /// Whether the bitmap is contiguous (NoFatChain flag)
     is_contiguous: bool,
+    /// Clusters holding the bitmap, in order (filled in by `load`)
+    clusters: Vec<u32>,
 }
 
 impl AllocationBitmap {
@@ -57,11 +62,16 @@ impl AllocationBitmap {
             data: Vec::new(),
             cluster_count,
             is_contiguous,
+            clusters: Vec::new(),
         }
     }
 
     /// Load the bitmap data from disk.
-    pub fn load<DATA: Read + Seek>(&mut self, data: &mut DATA, info: &ExFatInfo) -> Result<()> {
+    pub async fn load<DATA: Read + Seek>(
+        &mut self,
+        data: &mut DATA,
+        info: &ExFatInfo,
+    ) -> Result<()> {
         // `size` is the untrusted `data_length` of the bitmap directory entry
         // — a full u64. Bound it against the volume's cluster heap before
         // allocating, mirroring the up-case table loader.
@@ -72,19 +82,18 @@ impl AllocationBitmap {
             });
         }
 
-        if self.is_contiguous {
-            // Read contiguous bitmap
-            let offset = info.cluster_to_offset(self.first_cluster);
-            data.seek(SeekFrom::Start(offset))?;
-
-            self.data.resize(self.size as usize, 0);
-            data.read_exact(&mut self.data)?;
-        } else {
-            // Fragmented bitmaps require following the FAT chain, which is not yet
-            // implemented. Return an error rather than silently reading wrong data.
-            return Err(Error::UnsupportedFatType(
-                "fragmented exFAT allocation bitmap not yet supported",
-            ));
+        // A fragmented bitmap is read cluster by cluster along its FAT chain.
+        let cluster_size = info.bytes_per_cluster;
+        let count = self.size.div_ceil(cluster_size as u64) as u32;
+        self.clusters = ExFatTable::new(info)
+            .chain_clusters(data, self.first_cluster, count, self.is_contiguous)
+            .await?;
+
+        self.data.resize(self.size as usize, 0);
+        for (chunk, &cluster) in self.data.chunks_mut(cluster_size).zip(&self.clusters) {
+            data.seek(SeekFrom::Start(info.cluster_to_offset(cluster)))
+                .await?;
+            data.read_exact(chunk).await?;
         }
 
         Ok(())
@@ -191,14 +200,28 @@ impl AllocationBitmap {
 
     /// Write the bitmap back to disk.
     #[cfg(feature = "write")]
-    pub fn flush<DATA: Read + Write + Seek>(
+    pub async fn flush<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         info: &ExFatInfo,
     ) -> Result<()> {
-        let offset = info.cluster_to_offset(self.first_cluster);
-        data.seek(SeekFrom::Start(offset))?;
-        data.write_all(&self.data)?;
+        self.flush_to(data, info, &self.clusters).await
+    }
+
+    /// Write the bitmap to `clusters`, such as those of the second bitmap
+    /// on a volume with two FATs.
+    #[cfg(feature = "write")]
+    pub(crate) async fn flush_to<DATA: Read + Write + Seek>(
+        &self,
+        data: &mut DATA,
+        info: &ExFatInfo,
+        clusters: &[u32],
+    ) -> Result<()> {
+        for (chunk, &cluster) in self.data.chunks(info.bytes_per_cluster).zip(clusters) {
+            data.seek(SeekFrom::Start(info.cluster_to_offset(cluster)))
+                .await?;
+            data.write_all(chunk).await?;
+        }
         Ok(())
     }
 
@@ -253,8 +276,15 @@ impl AllocationBitmap {
     pub fn size(&self) -> u64 {
         self.size
     }
+
+    /// Clusters holding the bitmap, in order (empty until loaded)
+    pub fn clusters(&self) -> &[u32] {
+        &self.clusters
+    }
 }
 
+} // end io_transform!
+
 #[cfg(test)]
 mod tests {
     use super::*;
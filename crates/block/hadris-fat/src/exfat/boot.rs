@@ -9,6 +9,8 @@
 //!
 //! There is also a backup boot region at sectors 12-23.
 
+io_transform! {
+
 use core::mem::size_of;
 
 use hadris_common::types::{
@@ -16,8 +18,10 @@ use hadris_common::types::{
     number::{U16, U32, U64},
 };
 
+#[cfg(feature = "write")]
+use super::super::io::Write;
+use super::super::io::{Read, ReadExt, Seek, SeekFrom};
 use crate::error::{Error, Result};
-use crate::io::{Read, ReadExt, Seek, SeekFrom};
 
 /// Size of the boot region in sectors
 #[cfg(feature = "write")]
@@ -29,6 +33,17 @@ pub const EXFAT_SIGNATURE: [u8; 8] = *b"EXFAT   ";
 /// Boot sector signature (0xAA55)
 pub const BOOT_SIGNATURE: u16 = 0xAA55;
 
+/// VolumeFlags bit selecting the active FAT and allocation bitmap
+/// (0 = first, 1 = second)
+pub const VOLUME_FLAG_ACTIVE_FAT: u16 = 0x0001;
+
+/// VolumeFlags bit set while metadata updates are in progress
+pub const VOLUME_FLAG_DIRTY: u16 = 0x0002;
+
+/// Byte offset of VolumeFlags in the main boot sector
+#[cfg(feature = "write")]
+const VOLUME_FLAGS_OFFSET: u64 = 106;
+
 /// Raw exFAT Boot Sector structure (512 bytes minimum, but can be larger).
 ///
 /// This represents the Main Boot Sector (sector 0) and Backup Boot Sector (sector 12).
@@ -227,9 +242,9 @@ impl core::fmt::Debug for ExFatBootSector {
 
 impl ExFatBootSector {
     /// Read and validate the boot sector from a data source.
-    pub fn read<DATA: Read + Seek>(data: &mut DATA) -> Result<Self> {
-        data.seek(SeekFrom::Start(0))?;
-        let raw: RawExFatBootSector = data.read_struct()?;
+    pub async fn read<DATA: Read + Seek>(data: &mut DATA) -> Result<Self> {
+        data.seek(SeekFrom::Start(0)).await?;
+        let raw: RawExFatBootSector = data.read_struct().await?;
         raw.validate()?;
 
         let info = ExFatInfo::from_boot_sector(&raw);
@@ -240,13 +255,17 @@ impl ExFatBootSector {
     ///
     /// The checksum is computed over sectors 0-10 and stored in sector 11.
     /// This validates both the main boot region and optionally the backup.
-    pub fn validate_checksum<DATA: Read + Seek>(data: &mut DATA, sector_size: usize) -> Result<()> {
+    pub async fn validate_checksum<DATA: Read + Seek>(
+        data: &mut DATA,
+        sector_size: usize,
+    ) -> Result<()> {
         let mut checksum: u32 = 0;
         let mut sector_data = [0_u8; 4096];
 
         for sector in 0..11 {
-            data.seek(SeekFrom::Start(sector as u64 * sector_size as u64))?;
-            data.read_exact(&mut sector_data[..sector_size])?;
+            data.seek(SeekFrom::Start(sector as u64 * sector_size as u64))
+                .await?;
+            data.read_exact(&mut sector_data[..sector_size]).await?;
             for (byte_idx, &byte) in sector_data[..sector_size].iter().enumerate() {
                 // Skip VolumeFlags (bytes 106-107) and PercentInUse (byte 112)
                 // in sector 0 as they may change
@@ -260,13 +279,13 @@ impl ExFatBootSector {
         }
 
         // Read checksum sector (sector 11)
-        data.seek(SeekFrom::Start(11 * sector_size as u64))?;
+        data.seek(SeekFrom::Start(11 * sector_size as u64)).await?;
 
         // The checksum sector contains the checksum repeated to fill the sector
         let expected_count = sector_size / size_of::<u32>();
         for _ in 0..expected_count {
             let mut stored = [0u8; 4];
-            data.read_exact(&mut stored)?;
+            data.read_exact(&mut stored).await?;
             let stored_checksum = u32::from_le_bytes(stored);
 
             if stored_checksum != checksum {
@@ -280,6 +299,17 @@ impl ExFatBootSector {
         Ok(())
     }
 
+    /// Write `flags` to the VolumeFlags field of the main boot sector.
+    ///
+    /// VolumeFlags is left out of the boot checksum and is not kept in the
+    /// backup boot sector, so this is a two-byte update within one sector.
+    #[cfg(feature = "write")]
+    pub async fn write_volume_flags<DATA: Write + Seek>(data: &mut DATA, flags: u16) -> Result<()> {
+        data.seek(SeekFrom::Start(VOLUME_FLAGS_OFFSET)).await?;
+        data.write_all(&flags.to_le_bytes()).await?;
+        Ok(())
+    }
+
     /// Get the raw boot sector
     pub fn raw(&self) -> &RawExFatBootSector {
         &self.raw
@@ -291,6 +321,8 @@ impl ExFatBootSector {
     }
 }
 
+} // end io_transform!
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,13 +2,17 @@
 //!
 //! Provides directory traversal for exFAT filesystems.
 
+io_transform! {
+
 use alloc::vec::Vec;
 use core::mem::size_of;
 
+use super::super::io::{Read, Seek};
 use crate::error::{Error, Result};
-use crate::io::{Read, Seek};
 
-use super::entry::{ExFatFileEntry, RawDirectoryEntry, entry_type, parse_entry_set};
+use super::entry::{
+    EntrySetLocation, ExFatFileEntry, RawDirectoryEntry, entry_type, parse_entry_set,
+};
 use super::fs::ExFatVolume;
 
 /// A directory in an exFAT filesystem.
@@ -21,9 +25,24 @@ pub struct ExFatDir<'a, DATA: Read + Seek> {
     pub(crate) is_contiguous: bool,
     /// Size of the directory in bytes (for contiguous dirs)
     pub(crate) size: u64,
+    /// The directory's own entry set, or `None` for the root. Growing a
+    /// directory rewrites its stream extension, so iteration re-reads the
+    /// geometry from here rather than trusting the fields above.
+    pub(crate) location: Option<EntrySetLocation>,
 }
 
 impl<'a, DATA: Read + Seek> ExFatDir<'a, DATA> {
+    /// The directory described by `entry`.
+    pub(crate) fn from_entry(fs: &'a ExFatVolume<DATA>, entry: &ExFatFileEntry) -> Self {
+        ExFatDir {
+            fs,
+            first_cluster: entry.first_cluster,
+            is_contiguous: entry.no_fat_chain,
+            size: entry.data_length,
+            location: Some(entry.location()),
+        }
+    }
+
     /// Create an iterator over directory entries.
     pub fn entries(&self) -> ExFatDirIter<'a, DATA> {
         ExFatDirIter {
@@ -31,6 +50,7 @@ impl<'a, DATA: Read + Seek> ExFatDir<'a, DATA> {
             first_cluster: self.first_cluster,
             is_contiguous: self.is_contiguous,
             dir_size: self.size,
+            location: self.location,
             current_cluster: self.first_cluster,
             cluster_offset: 0,
             dir_offset: 0,
@@ -41,8 +61,9 @@ impl<'a, DATA: Read + Seek> ExFatDir<'a, DATA> {
     /// Find an entry by name.
     ///
     /// Performs case-insensitive comparison using the up-case table.
-    pub fn find(&self, name: &str) -> Result<Option<ExFatFileEntry>> {
-        for entry in self.entries() {
+    pub async fn find(&self, name: &str) -> Result<Option<ExFatFileEntry>> {
+        let mut entries = self.entries();
+        while let Some(entry) = entries.next_entry().await {
             let entry = entry?;
 
             // Perform case-insensitive comparison
@@ -54,19 +75,14 @@ impl<'a, DATA: Read + Seek> ExFatDir<'a, DATA> {
     }
 
     /// Open a subdirectory by name.
-    pub fn open_dir(&self, name: &str) -> Result<ExFatDir<'a, DATA>> {
-        let entry = self.find(name)?.ok_or(Error::EntryNotFound)?;
+    pub async fn open_dir(&self, name: &str) -> Result<ExFatDir<'a, DATA>> {
+        let entry = self.find(name).await?.ok_or(Error::EntryNotFound)?;
 
         if !entry.is_directory() {
             return Err(Error::NotADirectory);
         }
 
-        Ok(ExFatDir {
-            fs: self.fs,
-            first_cluster: entry.first_cluster,
-            is_contiguous: entry.no_fat_chain,
-            size: entry.data_length,
-        })
+        Ok(ExFatDir::from_entry(self.fs, &entry))
     }
 }
 
@@ -80,6 +96,9 @@ pub struct ExFatDirIter<'a, DATA: Read + Seek> {
     is_contiguous: bool,
     /// Total directory size (for contiguous dirs, or 0 for unknown)
     dir_size: u64,
+    /// Entry set to refresh the geometry from before the first read; taken
+    /// once it has been applied
+    location: Option<EntrySetLocation>,
     /// Current cluster being read
     current_cluster: u32,
     /// Byte offset within current cluster
@@ -91,30 +110,26 @@ pub struct ExFatDirIter<'a, DATA: Read + Seek> {
     cluster_steps: u32,
 }
 
-impl<DATA: Read + Seek> Iterator for ExFatDirIter<'_, DATA> {
-    type Item = Result<ExFatFileEntry>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.read_next_entry() {
-            Ok(Some(entry)) => Some(Ok(entry)),
-            Ok(None) => None,
-            Err(error) => Some(Err(error)),
-        }
+impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
+    /// Read the next file entry.
+    ///
+    /// In the sync API the iterator's `next` calls this; the async API has
+    /// no iterator and uses it directly.
+    pub async fn next_entry(&mut self) -> Option<Result<ExFatFileEntry>> {
+        self.read_next_entry().await.transpose()
     }
-}
 
-impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
     /// Advance to the next cluster of a FAT-chained directory. Returns `false`
     /// at end of chain. A chain longer than the volume's cluster count must
     /// contain a loop, so the walk is bounded and reports `ClusterLoop`.
-    fn follow_chain(&mut self) -> Result<bool> {
+    async fn follow_chain(&mut self) -> Result<bool> {
         self.cluster_steps = self.cluster_steps.saturating_add(1);
         if self.cluster_steps > self.fs.info().cluster_count {
             return Err(Error::ClusterLoop {
                 cluster: self.current_cluster,
             });
         }
-        match self.fs.next_cluster(self.current_cluster)? {
+        match self.fs.next_cluster(self.current_cluster).await? {
             Some(next) => {
                 self.current_cluster = next;
                 Ok(true)
@@ -124,11 +139,19 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
     }
 
     /// Read the next file entry from the directory.
-    fn read_next_entry(&mut self) -> Result<Option<ExFatFileEntry>> {
+    async fn read_next_entry(&mut self) -> Result<Option<ExFatFileEntry>> {
         let info = self.fs.info();
         let entry_size = size_of::<RawDirectoryEntry>();
         let cluster_size = info.bytes_per_cluster;
 
+        if let Some(location) = self.location.take() {
+            let (first_cluster, is_contiguous, size) = self.fs.dir_stream(location).await?;
+            self.first_cluster = first_cluster;
+            self.current_cluster = first_cluster;
+            self.is_contiguous = is_contiguous;
+            self.dir_size = size;
+        }
+
         loop {
             // Check if we've reached a size limit (for contiguous directories)
             if self.dir_size > 0 && self.dir_offset >= self.dir_size {
@@ -145,7 +168,7 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
                     }
                 } else {
                     // Follow FAT chain
-                    if !self.follow_chain()? {
+                    if !self.follow_chain().await? {
                         return Ok(None);
                     }
                 }
@@ -155,7 +178,7 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
             // Read a single entry. Capture the absolute disk offset so callers
             // (e.g. delete, update_entry_size) can write back to the right place.
             let offset = info.cluster_to_offset(self.current_cluster) + self.cluster_offset as u64;
-            let raw_entry = self.fs.read_entry_at(offset)?;
+            let raw_entry = self.fs.read_entry_at(offset).await?;
 
             let entry_type = unsafe { raw_entry.entry_type };
 
@@ -185,7 +208,7 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
 
             // File directory entry - need to read the complete entry set
             if entry_type == entry_type::FILE_DIRECTORY {
-                return self.read_entry_set(raw_entry, offset);
+                return self.read_entry_set(raw_entry, offset).await;
             }
 
             // Skip any other entry types
@@ -193,7 +216,7 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
     }
 
     /// Read a complete file entry set starting from a File Directory Entry.
-    fn read_entry_set(
+    async fn read_entry_set(
         &mut self,
         primary: RawDirectoryEntry,
         entry_offset: u64,
@@ -227,14 +250,14 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
                     if !info.is_valid_cluster(self.current_cluster) {
                         return Ok(None);
                     }
-                } else if !self.follow_chain()? {
+                } else if !self.follow_chain().await? {
                     return Ok(None);
                 }
                 self.cluster_offset = 0;
             }
 
             let offset = info.cluster_to_offset(self.current_cluster) + self.cluster_offset as u64;
-            let entry = self.fs.read_entry_at(offset)?;
+            let entry = self.fs.read_entry_at(offset).await?;
             entries.push(entry);
 
             self.cluster_offset += entry_size;
@@ -245,6 +268,7 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
         if let Some((mut file_entry, _)) = parse_entry_set(&entries) {
             file_entry.parent_cluster = self.first_cluster;
             file_entry.entry_offset = entry_offset;
+            file_entry.parent_contiguous = self.is_contiguous;
             Ok(Some(file_entry))
         } else {
             // Failed to parse, skip
@@ -252,3 +276,17 @@ impl<DATA: Read + Seek> ExFatDirIter<'_, DATA> {
         }
     }
 }
+
+} // end io_transform!
+
+sync_only! {
+
+impl<DATA: Read + Seek> Iterator for ExFatDirIter<'_, DATA> {
+    type Item = Result<ExFatFileEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry()
+    }
+}
+
+} // end sync_only!
@@ -273,6 +273,9 @@ pub struct ExFatFileEntry {
     pub(crate) parent_cluster: u32,
     /// Byte offset of this entry set within the parent directory
     pub(crate) entry_offset: u64,
+    /// Whether the parent directory is contiguous (NoFatChain), which decides
+    /// how an entry set crossing a cluster boundary continues
+    pub(crate) parent_contiguous: bool,
 }
 
 impl ExFatFileEntry {
@@ -290,6 +293,23 @@ impl ExFatFileEntry {
     pub fn size(&self) -> u64 {
         self.valid_data_length
     }
+
+    /// Where this entry set lives in its parent directory.
+    pub(crate) fn location(&self) -> EntrySetLocation {
+        EntrySetLocation {
+            offset: self.entry_offset,
+            parent_contiguous: self.parent_contiguous,
+        }
+    }
+}
+
+/// Position of an entry set within its parent directory.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EntrySetLocation {
+    /// Absolute byte offset of the File Directory entry
+    pub(crate) offset: u64,
+    /// Whether the parent directory is contiguous (NoFatChain)
+    pub(crate) parent_contiguous: bool,
 }
 
 /// Compute the checksum for a file entry set.
@@ -404,8 +424,9 @@ pub fn parse_entry_set(entries: &[RawDirectoryEntry]) -> Option<(ExFatFileEntry,
         created,
         modified,
         accessed,
-        parent_cluster: 0, // Set by caller
-        entry_offset: 0,   // Set by caller
+        parent_cluster: 0,       // Set by caller
+        entry_offset: 0,         // Set by caller
+        parent_contiguous: true, // Set by caller
     };
 
     Some((entry, total_entries))
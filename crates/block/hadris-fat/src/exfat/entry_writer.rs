@@ -235,8 +235,8 @@ fn to_raw_entry<T: bytemuck::NoUninit>(entry: &T) -> RawDirectoryEntry {
 
 #[cfg(test)]
 mod tests {
+    use super::super::entry::parse_entry_set;
     use super::*;
-    use crate::exfat::entry::parse_entry_set;
 
     #[test]
     fn test_entry_set_builder() {
@@ -4,12 +4,19 @@
 //! NoFatChain flag set). Contiguous files can be read directly from the
 //! bitmap without consulting the FAT.
 
+io_transform! {
+
+#[cfg(feature = "write")]
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
 use core::mem::size_of;
 
-use crate::error::{Error, Result};
+use spin::Mutex;
+
 #[cfg(feature = "write")]
-use crate::io::Write;
-use crate::io::{Read, Seek, SeekFrom};
+use super::super::io::Write;
+use super::super::io::{Read, Seek, SeekFrom};
+use crate::error::{Error, Result};
 
 use super::ExFatInfo;
 
@@ -18,15 +25,30 @@ pub struct ExFatTable {
     /// FAT offset in bytes from start of volume
     fat_offset: u64,
     /// FAT length in bytes
-    #[cfg(feature = "write")]
     fat_length: u64,
     /// Number of FAT copies (1 or 2)
-    #[cfg(feature = "write")]
     fat_count: u8,
+    /// Which copies reads and writes go to
+    copies: Mutex<FatCopies>,
+    /// Bytes per sector, the unit dirty FAT regions are tracked in
+    #[cfg(feature = "write")]
+    bytes_per_sector: u64,
     /// Maximum valid cluster number
     max_cluster: u32,
 }
 
+/// Copy selection for a volume with one or two FATs.
+struct FatCopies {
+    /// The copy reads come from
+    working: u8,
+    /// Whether writes go only to the working copy (transaction-safe mode)
+    #[cfg(feature = "write")]
+    shadowed: bool,
+    /// Sectors of the working copy written since the last commit
+    #[cfg(feature = "write")]
+    dirty: BTreeSet<u64>,
+}
+
 impl ExFatTable {
     /// Free cluster marker
     pub const FREE_CLUSTER: u32 = 0x00000000;
@@ -43,10 +65,17 @@ impl ExFatTable {
     pub fn new(info: &ExFatInfo) -> Self {
         Self {
             fat_offset: info.fat_offset,
-            #[cfg(feature = "write")]
             fat_length: info.fat_length,
-            #[cfg(feature = "write")]
             fat_count: info.fat_count,
+            copies: Mutex::new(FatCopies {
+                working: 0,
+                #[cfg(feature = "write")]
+                shadowed: false,
+                #[cfg(feature = "write")]
+                dirty: BTreeSet::new(),
+            }),
+            #[cfg(feature = "write")]
+            bytes_per_sector: info.bytes_per_sector as u64,
             // Cluster count + 2 (for reserved entries 0 and 1)
             max_cluster: info
                 .cluster_count
@@ -55,21 +84,26 @@ impl ExFatTable {
     }
 
     /// Read the FAT entry for a cluster.
-    pub fn read_entry<DATA: Read + Seek>(&self, data: &mut DATA, cluster: u32) -> Result<u32> {
+    pub async fn read_entry<DATA: Read + Seek>(
+        &self,
+        data: &mut DATA,
+        cluster: u32,
+    ) -> Result<u32> {
         self.validate_cluster(cluster)?;
 
-        let offset = self.fat_offset + (cluster as u64) * (size_of::<u32>() as u64);
-        data.seek(SeekFrom::Start(offset))?;
+        let working = self.copies.lock().working;
+        let offset = self.entry_offset(working, cluster);
+        data.seek(SeekFrom::Start(offset)).await?;
 
         let mut buf = [0u8; 4];
-        data.read_exact(&mut buf)?;
+        data.read_exact(&mut buf).await?;
 
         Ok(u32::from_le_bytes(buf))
     }
 
     /// Write a FAT entry for a cluster.
     #[cfg(feature = "write")]
-    pub fn write_entry<DATA: Read + Write + Seek>(
+    pub async fn write_entry<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         cluster: u32,
@@ -77,27 +111,117 @@ impl ExFatTable {
     ) -> Result<()> {
         self.validate_cluster(cluster)?;
 
-        // Write to all FAT copies
-        for fat_idx in 0..self.fat_count {
-            let offset = self.fat_offset
-                + (fat_idx as u64) * self.fat_length
-                + (cluster as u64) * (size_of::<u32>() as u64);
-            data.seek(SeekFrom::Start(offset))?;
-            data.write_all(&value.to_le_bytes())?;
+        // A transaction writes only its working copy; otherwise every copy
+        // is updated in place
+        let targets = {
+            let mut copies = self.copies.lock();
+            if copies.shadowed {
+                let sector = (cluster as u64 * size_of::<u32>() as u64) / self.bytes_per_sector;
+                copies.dirty.insert(sector);
+                copies.working..copies.working + 1
+            } else {
+                0..self.fat_count
+            }
+        };
+        for fat_idx in targets {
+            let offset = self.entry_offset(fat_idx, cluster);
+            data.seek(SeekFrom::Start(offset)).await?;
+            data.write_all(&value.to_le_bytes()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Byte offset of the entry for `cluster` in FAT copy `copy`.
+    fn entry_offset(&self, copy: u8, cluster: u32) -> u64 {
+        self.fat_offset + copy as u64 * self.fat_length + cluster as u64 * size_of::<u32>() as u64
+    }
+
+    /// The FAT copy reads come from.
+    pub fn working_copy(&self) -> u8 {
+        self.copies.lock().working
+    }
+
+    /// Read from FAT copy `copy`; writes still go to every copy.
+    ///
+    /// A volume with two FATs reads the one its VolumeFlags mark active.
+    pub(crate) fn set_working_copy(&self, copy: u8) {
+        self.copies.lock().working = copy.min(self.fat_count - 1);
+    }
+
+    /// Shadow updates into FAT copy `copy`: reads and writes go to it
+    /// alone, and the sectors written are remembered for
+    /// [`mirror_dirty`](Self::mirror_dirty).
+    #[cfg(feature = "write")]
+    pub(crate) fn shadow_into(&self, copy: u8) {
+        let mut copies = self.copies.lock();
+        copies.working = copy;
+        copies.shadowed = true;
+    }
+
+    /// Copy the sectors written since the last call from FAT copy `from`
+    /// to `to`, so both describe the same chains again.
+    #[cfg(feature = "write")]
+    pub(crate) async fn mirror_dirty<DATA: Read + Write + Seek>(
+        &self,
+        data: &mut DATA,
+        from: u8,
+        to: u8,
+    ) -> Result<()> {
+        let dirty = core::mem::take(&mut self.copies.lock().dirty);
+        let mut sector = alloc::vec![0u8; self.bytes_per_sector as usize];
+        for index in dirty {
+            let offset = index * self.bytes_per_sector;
+            data.seek(SeekFrom::Start(self.copy_offset(from) + offset))
+                .await?;
+            data.read_exact(&mut sector).await?;
+            data.seek(SeekFrom::Start(self.copy_offset(to) + offset))
+                .await?;
+            data.write_all(&sector).await?;
         }
+        Ok(())
+    }
 
+    /// Overwrite FAT copy `to` with copy `from`.
+    #[cfg(feature = "write")]
+    pub(crate) async fn mirror_all<DATA: Read + Write + Seek>(
+        &self,
+        data: &mut DATA,
+        from: u8,
+        to: u8,
+    ) -> Result<()> {
+        const CHUNK: u64 = 64 * 1024;
+        let mut buf = alloc::vec![0u8; self.fat_length.min(CHUNK) as usize];
+        let mut done = 0;
+        while done < self.fat_length {
+            let len = (self.fat_length - done).min(CHUNK) as usize;
+            data.seek(SeekFrom::Start(self.copy_offset(from) + done))
+                .await?;
+            data.read_exact(&mut buf[..len]).await?;
+            data.seek(SeekFrom::Start(self.copy_offset(to) + done))
+                .await?;
+            data.write_all(&buf[..len]).await?;
+            done += len as u64;
+        }
+        self.copies.lock().dirty.clear();
         Ok(())
     }
 
+    /// Byte offset of FAT copy `copy`.
+    #[cfg(feature = "write")]
+    fn copy_offset(&self, copy: u8) -> u64 {
+        self.fat_offset + copy as u64 * self.fat_length
+    }
+
     /// Get the next cluster in a chain.
     ///
     /// Returns `None` if this is the end of the chain.
-    pub fn next_cluster<DATA: Read + Seek>(
+    pub async fn next_cluster<DATA: Read + Seek>(
         &self,
         data: &mut DATA,
         cluster: u32,
     ) -> Result<Option<u32>> {
-        let entry = self.read_entry(data, cluster)?;
+        let entry = self.read_entry(data, cluster).await?;
 
         // Check for end of chain
         if entry == Self::END_OF_CHAIN || entry >= Self::MEDIA_DESCRIPTOR {
@@ -120,12 +244,55 @@ impl ExFatTable {
         Ok(Some(entry))
     }
 
+    /// Collect the first `count` clusters of a chain starting at `first`.
+    ///
+    /// A contiguous (NoFatChain) chain is consecutive and the FAT is not
+    /// consulted. A FAT chain that ends early is reported as
+    /// `UnexpectedEndOfChain`; the walk stops after `count` clusters, so a
+    /// looping chain cannot hang it.
+    pub async fn chain_clusters<DATA: Read + Seek>(
+        &self,
+        data: &mut DATA,
+        first: u32,
+        count: u32,
+        is_contiguous: bool,
+    ) -> Result<Vec<u32>> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        self.validate_cluster(first)?;
+
+        if is_contiguous {
+            let last = first
+                .checked_add(count - 1)
+                .filter(|&last| last <= self.max_cluster)
+                .ok_or(Error::ClusterOutOfBounds {
+                    cluster: first,
+                    max: self.max_cluster,
+                })?;
+            return Ok((first..=last).collect());
+        }
+
+        let mut clusters = Vec::with_capacity(count as usize);
+        let mut current = first;
+        loop {
+            clusters.push(current);
+            if clusters.len() == count as usize {
+                return Ok(clusters);
+            }
+            current = self
+                .next_cluster(data, current)
+                .await?
+                .ok_or(Error::UnexpectedEndOfChain { cluster: current })?;
+        }
+    }
+
     /// Allocate a single cluster.
     ///
     /// The cluster is marked as end-of-chain.
     /// Returns the allocated cluster number.
     #[cfg(feature = "write")]
-    pub fn allocate_cluster<DATA: Read + Write + Seek>(
+    pub async fn allocate_cluster<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         hint: u32,
@@ -138,18 +305,18 @@ impl ExFatTable {
 
         // Search from hint to end
         for cluster in start..=self.max_cluster {
-            let entry = self.read_entry(data, cluster)?;
+            let entry = self.read_entry(data, cluster).await?;
             if entry == Self::FREE_CLUSTER {
-                self.write_entry(data, cluster, Self::END_OF_CHAIN)?;
+                self.write_entry(data, cluster, Self::END_OF_CHAIN).await?;
                 return Ok(cluster);
             }
         }
 
         // Wrap around: search from beginning to hint
         for cluster in Self::FIRST_DATA_CLUSTER..start {
-            let entry = self.read_entry(data, cluster)?;
+            let entry = self.read_entry(data, cluster).await?;
             if entry == Self::FREE_CLUSTER {
-                self.write_entry(data, cluster, Self::END_OF_CHAIN)?;
+                self.write_entry(data, cluster, Self::END_OF_CHAIN).await?;
                 return Ok(cluster);
             }
         }
@@ -161,7 +328,7 @@ impl ExFatTable {
     ///
     /// Returns the first cluster of the chain.
     #[cfg(feature = "write")]
-    pub fn allocate_chain<DATA: Read + Write + Seek>(
+    pub async fn allocate_chain<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         count: u32,
@@ -171,12 +338,12 @@ impl ExFatTable {
             return Err(Error::NoFreeSpace);
         }
 
-        let first = self.allocate_cluster(data, hint)?;
+        let first = self.allocate_cluster(data, hint).await?;
         let mut prev = first;
 
         for _ in 1..count {
-            let next = self.allocate_cluster(data, prev + 1)?;
-            self.write_entry(data, prev, next)?;
+            let next = self.allocate_cluster(data, prev + 1).await?;
+            self.write_entry(data, prev, next).await?;
             prev = next;
         }
 
@@ -187,7 +354,7 @@ impl ExFatTable {
     ///
     /// Returns the number of clusters freed.
     #[cfg(feature = "write")]
-    pub fn free_chain<DATA: Read + Write + Seek>(
+    pub async fn free_chain<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         start: u32,
@@ -200,8 +367,8 @@ impl ExFatTable {
                 break;
             }
 
-            let next = self.read_entry(data, current)?;
-            self.write_entry(data, current, Self::FREE_CLUSTER)?;
+            let next = self.read_entry(data, current).await?;
+            self.write_entry(data, current, Self::FREE_CLUSTER).await?;
             count += 1;
 
             if next == Self::END_OF_CHAIN
@@ -222,7 +389,7 @@ impl ExFatTable {
     ///
     /// Returns the first cluster of the newly allocated portion.
     #[cfg(feature = "write")]
-    pub fn extend_chain<DATA: Read + Write + Seek>(
+    pub async fn extend_chain<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         last: u32,
@@ -233,8 +400,8 @@ impl ExFatTable {
             return Ok(last);
         }
 
-        let first_new = self.allocate_chain(data, count, hint)?;
-        self.write_entry(data, last, first_new)?;
+        let first_new = self.allocate_chain(data, count, hint).await?;
+        self.write_entry(data, last, first_new).await?;
         Ok(first_new)
     }
 
@@ -245,7 +412,7 @@ impl ExFatTable {
     ///
     /// Returns the number of clusters freed.
     #[cfg(feature = "write")]
-    pub fn truncate_chain<DATA: Read + Write + Seek>(
+    pub async fn truncate_chain<DATA: Read + Write + Seek>(
         &self,
         data: &mut DATA,
         cluster: u32,
@@ -254,14 +421,14 @@ impl ExFatTable {
             return Ok(0);
         }
 
-        let next = self.read_entry(data, cluster)?;
-        self.write_entry(data, cluster, Self::END_OF_CHAIN)?;
+        let next = self.read_entry(data, cluster).await?;
+        self.write_entry(data, cluster, Self::END_OF_CHAIN).await?;
 
         if next != Self::END_OF_CHAIN
             && (Self::FIRST_DATA_CLUSTER..Self::MEDIA_DESCRIPTOR).contains(&next)
             && next <= self.max_cluster
         {
-            self.free_chain(data, next)
+            self.free_chain(data, next).await
         } else {
             Ok(0)
         }
@@ -289,3 +456,5 @@ impl ExFatTable {
         self.max_cluster
     }
 }
+
+} // end io_transform!
@@ -3,12 +3,14 @@
 //! Provides streaming access to file contents, handling both
 //! contiguous and fragmented files.
 
+io_transform! {
+
 use core::cmp::min;
 
-use crate::error::{Error, Result};
 #[cfg(feature = "write")]
-use crate::io::Write;
-use crate::io::{ErrorKind, Read, Seek, SeekFrom, error_from_kind};
+use super::super::io::Write;
+use super::super::io::{ErrorKind, IoResult, Read, Seek, SeekFrom, error_from_kind};
+use crate::error::{Error, Result};
 
 use super::entry::ExFatFileEntry;
 use super::fs::ExFatVolume;
@@ -76,12 +78,12 @@ impl<'a, DATA: Read + Seek> ExFatFileReader<'a, DATA> {
 impl<DATA: Read + Seek> ExFatFileReader<'_, DATA> {
     /// Follow the FAT chain one hop. A chain longer than the volume's cluster
     /// count must contain a loop; report an error instead of hanging.
-    fn follow_chain(&mut self) -> crate::io::IoResult<bool> {
+    async fn follow_chain(&mut self) -> IoResult<bool> {
         self.cluster_steps = self.cluster_steps.saturating_add(1);
         if self.cluster_steps > self.fs.info().cluster_count {
             return Err(error_from_kind(ErrorKind::Other));
         }
-        match self.fs.next_cluster(self.current_cluster) {
+        match self.fs.next_cluster(self.current_cluster).await {
             Ok(Some(next)) => {
                 self.current_cluster = next;
                 Ok(true)
@@ -95,7 +97,7 @@ impl<DATA: Read + Seek> ExFatFileReader<'_, DATA> {
 impl<DATA: Read + Seek> Read for ExFatFileReader<'_, DATA> {
     type Error = ErrorKind;
 
-    fn read(&mut self, buf: &mut [u8]) -> crate::io::IoResult<usize> {
+    async fn read(&mut self, buf: &mut [u8]) -> IoResult<usize> {
         if self.position >= self.valid_length {
             return Ok(0);
         }
@@ -113,7 +115,7 @@ impl<DATA: Read + Seek> Read for ExFatFileReader<'_, DATA> {
                     self.current_cluster = self.first_cluster + self.cluster_index;
                 } else {
                     // Fragmented file: follow FAT chain
-                    if !self.follow_chain()? {
+                    if !self.follow_chain().await? {
                         break; // End of chain
                     }
                 }
@@ -137,6 +139,7 @@ impl<DATA: Read + Seek> Read for ExFatFileReader<'_, DATA> {
             let offset = info.cluster_to_offset(self.current_cluster) + self.cluster_offset as u64;
             self.fs
                 .read_at(offset, &mut buf[total_read..total_read + to_read])
+                .await
                 .map_err(|_| error_from_kind(ErrorKind::Other))?;
 
             total_read += to_read;
@@ -147,10 +150,10 @@ impl<DATA: Read + Seek> Read for ExFatFileReader<'_, DATA> {
         Ok(total_read)
     }
 
-    fn read_exact(&mut self, buf: &mut [u8]) -> crate::io::IoResult<()> {
+    async fn read_exact(&mut self, buf: &mut [u8]) -> IoResult<()> {
         let mut total_read = 0;
         while total_read < buf.len() {
-            match self.read(&mut buf[total_read..])? {
+            match self.read(&mut buf[total_read..]).await? {
                 0 => return Err(error_from_kind(ErrorKind::UnexpectedEof)),
                 n => total_read += n,
             }
@@ -162,7 +165,7 @@ impl<DATA: Read + Seek> Read for ExFatFileReader<'_, DATA> {
 impl<DATA: Read + Seek> Seek for ExFatFileReader<'_, DATA> {
     type Error = ErrorKind;
 
-    fn seek(&mut self, pos: SeekFrom) -> crate::io::IoResult<u64> {
+    async fn seek(&mut self, pos: SeekFrom) -> IoResult<u64> {
         let new_pos = match pos {
             SeekFrom::Start(offset) => offset as i64,
             SeekFrom::End(offset) => self.valid_length as i64 + offset,
@@ -194,7 +197,7 @@ impl<DATA: Read + Seek> Seek for ExFatFileReader<'_, DATA> {
             self.current_cluster = self.first_cluster;
             self.cluster_steps = 0;
             for _ in 0..cluster_index {
-                if !self.follow_chain()? {
+                if !self.follow_chain().await? {
                     return Err(error_from_kind(ErrorKind::InvalidInput));
                 }
             }
@@ -206,7 +209,7 @@ impl<DATA: Read + Seek> Seek for ExFatFileReader<'_, DATA> {
         Ok(new_pos)
     }
 
-    fn stream_position(&mut self) -> crate::io::IoResult<u64> {
+    async fn stream_position(&mut self) -> IoResult<u64> {
         Ok(self.position)
     }
 }
@@ -222,8 +225,6 @@ pub struct ExFatFileWriter<'a, DATA: Read + Write + Seek> {
     first_cluster: u32,
     /// Current cluster being written
     current_cluster: u32,
-    /// Previous cluster (for linking in FAT)
-    prev_cluster: Option<u32>,
     /// Byte offset within current cluster
     cluster_offset: usize,
     /// Current position within the file
@@ -250,7 +251,6 @@ impl<'a, DATA: Read + Write + Seek> ExFatFileWriter<'a, DATA> {
             fs,
             first_cluster: entry.first_cluster,
             current_cluster: entry.first_cluster,
-            prev_cluster: None,
             cluster_offset: 0,
             position: 0,
             is_contiguous: entry.no_fat_chain,
@@ -271,17 +271,25 @@ impl<'a, DATA: Read + Write + Seek> ExFatFileWriter<'a, DATA> {
         self.new_length
     }
 
-    /// Allocate a new cluster, linking it to the previous one if needed.
-    fn allocate_next_cluster(&mut self) -> Result<u32> {
+    /// Allocate a new cluster after the current one and link it in.
+    ///
+    /// A contiguous file that can't take the adjacent cluster becomes a FAT
+    /// chain: its existing run is recorded in the FAT before the new cluster
+    /// is linked to the end.
+    async fn allocate_next_cluster(&mut self) -> Result<u32> {
         let hint = self.current_cluster.saturating_add(1);
-        let new_cluster = self.fs.allocate_cluster(hint)?;
+        let new_cluster = self.fs.allocate_cluster(hint).await?;
 
-        // If we have a previous cluster, we're no longer contiguous
-        // (unless the new cluster is adjacent)
-        if let Some(prev) = self.prev_cluster {
-            if new_cluster != prev + 1 {
+        if !(self.is_contiguous && new_cluster == hint) {
+            if self.is_contiguous {
+                for cluster in self.first_cluster..self.current_cluster {
+                    self.fs.link_cluster(cluster, cluster + 1).await?;
+                }
                 self.is_contiguous = false;
             }
+            self.fs
+                .link_cluster(self.current_cluster, new_cluster)
+                .await?;
         }
 
         // Update allocated length
@@ -295,16 +303,19 @@ impl<'a, DATA: Read + Write + Seek> ExFatFileWriter<'a, DATA> {
     ///
     /// This must be called after writing to update the file's metadata
     /// (size, data length, first cluster) and recalculate the entry set checksum.
-    pub fn finish(self) -> Result<()> {
-        self.fs.update_entry_size(
-            &self.entry,
-            self.new_length,
-            self.allocated_length,
-            self.first_cluster,
-            self.is_contiguous,
-        )?;
-        self.fs.sync_bitmap()?;
-        let _ = self.fs.flush();
+    pub async fn finish(self) -> Result<()> {
+        // Commit the allocation before the entry set references it
+        self.fs.sync_bitmap().await?;
+        self.fs
+            .update_entry_size(
+                self.entry.location(),
+                self.new_length,
+                self.allocated_length,
+                self.first_cluster,
+                self.is_contiguous,
+            )
+            .await?;
+        let _ = self.fs.flush().await;
         Ok(())
     }
 }
@@ -313,7 +324,7 @@ impl<'a, DATA: Read + Write + Seek> ExFatFileWriter<'a, DATA> {
 impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
     type Error = ErrorKind;
 
-    fn write(&mut self, buf: &[u8]) -> crate::io::IoResult<usize> {
+    async fn write(&mut self, buf: &[u8]) -> IoResult<usize> {
         if buf.is_empty() {
             return Ok(0);
         }
@@ -328,7 +339,7 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
                 // Allocate or get next cluster
                 let new_cluster = if self.current_cluster == 0 {
                     // Empty file - allocate first cluster
-                    match self.fs.allocate_cluster(2) {
+                    match self.fs.allocate_cluster(2).await {
                         Ok(c) => {
                             self.first_cluster = c;
                             self.allocated_length = cluster_size as u64;
@@ -344,15 +355,14 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
                         match self.fs.is_cluster_allocated(next) {
                             Ok(false) => {
                                 // Allocate it
-                                match self.fs.allocate_cluster(next) {
+                                match self.fs.allocate_cluster(next).await {
                                     Ok(c) if c == next => {
                                         self.allocated_length += cluster_size as u64;
                                         c
                                     }
                                     Ok(_) | Err(_) => {
                                         // Not contiguous anymore - need to convert to FAT chain
-                                        self.is_contiguous = false;
-                                        match self.allocate_next_cluster() {
+                                        match self.allocate_next_cluster().await {
                                             Ok(c) => c,
                                             Err(_) => return Ok(total_written),
                                         }
@@ -361,8 +371,7 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
                             }
                             _ => {
                                 // Already allocated - convert to FAT chain
-                                self.is_contiguous = false;
-                                match self.allocate_next_cluster() {
+                                match self.allocate_next_cluster().await {
                                     Ok(c) => c,
                                     Err(_) => return Ok(total_written),
                                 }
@@ -374,20 +383,15 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
                     }
                 } else {
                     // Follow FAT chain or allocate new
-                    match self.fs.next_cluster(self.current_cluster) {
+                    match self.fs.next_cluster(self.current_cluster).await {
                         Ok(Some(next)) => next,
-                        Ok(None) | Err(_) => match self.allocate_next_cluster() {
+                        Ok(None) | Err(_) => match self.allocate_next_cluster().await {
                             Ok(c) => c,
                             Err(_) => return Ok(total_written),
                         },
                     }
                 };
 
-                self.prev_cluster = if self.current_cluster != 0 {
-                    Some(self.current_cluster)
-                } else {
-                    None
-                };
                 self.current_cluster = new_cluster;
                 self.cluster_index += 1;
                 self.cluster_offset = 0;
@@ -406,6 +410,7 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
             let offset = info.cluster_to_offset(self.current_cluster) + self.cluster_offset as u64;
             self.fs
                 .write_at(offset, &buf[total_written..total_written + to_write])
+                .await
                 .map_err(|_| error_from_kind(ErrorKind::Other))?;
 
             total_written += to_write;
@@ -420,14 +425,14 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
         Ok(total_written)
     }
 
-    fn flush(&mut self) -> crate::io::IoResult<()> {
-        self.fs.flush()
+    async fn flush(&mut self) -> IoResult<()> {
+        self.fs.flush().await
     }
 
-    fn write_all(&mut self, buf: &[u8]) -> crate::io::IoResult<()> {
+    async fn write_all(&mut self, buf: &[u8]) -> IoResult<()> {
         let mut written = 0;
         while written < buf.len() {
-            match self.write(&buf[written..])? {
+            match self.write(&buf[written..]).await? {
                 0 => return Err(error_from_kind(ErrorKind::WriteZero)),
                 n => written += n,
             }
@@ -435,3 +440,5 @@ impl<DATA: Read + Write + Seek> Write for ExFatFileWriter<'_, DATA> {
         Ok(())
     }
 }
+
+} // end io_transform!
@@ -11,6 +11,8 @@
 //!   - Sector 11: Boot Checksum
 //! - Sectors 12-23: Backup Boot Region (identical to main)
 
+io_transform! {
+
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -20,8 +22,8 @@ use hadris_common::types::{
     number::{U16, U32, U64},
 };
 
+use super::super::io::{Read, Seek, SeekFrom, Write};
 use crate::error::{Error, Result};
-use crate::io::{Read, Seek, SeekFrom, Write};
 
 use super::boot::{BOOT_REGION_SECTORS, BOOT_SIGNATURE, EXFAT_SIGNATURE, RawExFatBootSector};
 use super::entry::{
@@ -40,6 +42,12 @@ const MAX_VOLUME_SIZE: u64 = 256 * 1024 * 1024 * 1024 * 1024;
 /// Default boundary alignment (1 MB)
 const DEFAULT_BOUNDARY_ALIGNMENT: u64 = 1024 * 1024;
 
+/// Maximum cluster size (32 MB)
+const MAX_CLUSTER_SIZE: usize = 32 * 1024 * 1024;
+
+/// Maximum cluster count (2^32 - 11)
+const MAX_CLUSTER_COUNT: u64 = 0xFFFF_FFF5;
+
 /// Configuration options for formatting an exFAT volume.
 #[derive(Debug, Clone)]
 pub struct ExFatFormatOptions {
@@ -47,16 +55,25 @@ pub struct ExFatFormatOptions {
     pub label: Option<String>,
     /// Bytes per sector (512, 1024, 2048, or 4096)
     pub bytes_per_sector: usize,
-    /// Sectors per cluster (cluster size must not exceed 32 MB)
+    /// Sectors per cluster (cluster size must not exceed 32 MB, 0 = automatic)
     pub sectors_per_cluster: usize,
-    /// Number of FATs (1 or 2)
+    /// Cluster size in bytes (overrides `sectors_per_cluster`, 0 = unset)
+    pub cluster_size: usize,
+    /// Number of FATs (1 or 2). Two FATs come with two allocation bitmaps,
+    /// the layout transaction-safe (TexFAT) updates need.
     pub fat_count: u8,
-    /// Boundary alignment for cluster heap (typically 1 MB for flash drives)
+    /// Boundary alignment for the FAT and cluster heap (typically 1 MB for
+    /// flash drives). The FAT starts on a half-boundary and the cluster heap
+    /// on a boundary, measured from the start of the media.
     pub boundary_alignment: Option<u64>,
     /// Volume serial number (random if None)
     pub volume_serial: Option<u32>,
-    /// Partition offset (for media with partition table)
+    /// Partition offset in bytes (for media with partition table)
     pub partition_offset: u64,
+    /// Use the SD Card Association's recommended cluster size and boundary
+    /// unit for the volume size. An explicit cluster size still wins; the
+    /// boundary unit replaces `boundary_alignment`.
+    pub sd_card_layout: bool,
 }
 
 impl Default for ExFatFormatOptions {
@@ -65,10 +82,12 @@ impl Default for ExFatFormatOptions {
             label: None,
             bytes_per_sector: 512,
             sectors_per_cluster: 0, // Auto-calculate
+            cluster_size: 0,
             fat_count: 1,
             boundary_alignment: Some(DEFAULT_BOUNDARY_ALIGNMENT),
             volume_serial: None,
             partition_offset: 0,
+            sd_card_layout: false,
         }
     }
 }
@@ -97,12 +116,48 @@ impl ExFatFormatOptions {
         self
     }
 
+    /// Set the cluster size in bytes (a power of two from the sector size up
+    /// to 32 MB).
+    pub fn cluster_size(mut self, bytes: usize) -> Self {
+        self.cluster_size = bytes;
+        self
+    }
+
     /// Set the number of FATs.
+    ///
+    /// A volume with two FATs can be opened with
+    /// [`ExFatVolume::open_transaction_safe`].
     pub fn fat_count(mut self, count: u8) -> Self {
         self.fat_count = count;
         self
     }
 
+    /// Set the boundary alignment in bytes, or `None` to pack the FAT and
+    /// cluster heap directly after the boot regions.
+    pub fn boundary_alignment(mut self, alignment: Option<u64>) -> Self {
+        self.boundary_alignment = alignment;
+        self
+    }
+
+    /// Set the volume serial number.
+    pub fn volume_serial(mut self, serial: u32) -> Self {
+        self.volume_serial = Some(serial);
+        self
+    }
+
+    /// Set the byte offset of the volume on its media.
+    pub fn partition_offset(mut self, offset: u64) -> Self {
+        self.partition_offset = offset;
+        self
+    }
+
+    /// Lay the volume out as the SD Card Association recommends for a card
+    /// of this size.
+    pub fn sd_card_layout(mut self, enabled: bool) -> Self {
+        self.sd_card_layout = enabled;
+        self
+    }
+
     fn validate(&self) -> Result<()> {
         // Validate sector size (must be power of 2, 512-4096)
         if !matches!(self.bytes_per_sector, 512 | 1024 | 2048 | 4096) {
@@ -120,6 +175,40 @@ impl ExFatFormatOptions {
             });
         }
 
+        // Validate cluster geometry (zero means automatic)
+        if self.sectors_per_cluster != 0 && !self.sectors_per_cluster.is_power_of_two() {
+            return Err(Error::InvalidFormatOption {
+                option: "sectors_per_cluster",
+                reason: "must be a power of two",
+            });
+        }
+        if self.cluster_size != 0
+            && (!self.cluster_size.is_power_of_two()
+                || self.cluster_size < self.bytes_per_sector
+                || self.cluster_size > MAX_CLUSTER_SIZE)
+        {
+            return Err(Error::InvalidFormatOption {
+                option: "cluster_size",
+                reason: "must be a power of two between the sector size and 32 MB",
+            });
+        }
+
+        // Validate alignment
+        if let Some(alignment) = self.boundary_alignment
+            && (!alignment.is_power_of_two() || alignment < self.bytes_per_sector as u64)
+        {
+            return Err(Error::InvalidFormatOption {
+                option: "boundary_alignment",
+                reason: "must be a power of two no smaller than the sector size",
+            });
+        }
+        if self.partition_offset % self.bytes_per_sector as u64 != 0 {
+            return Err(Error::InvalidFormatOption {
+                option: "partition_offset",
+                reason: "must be a multiple of the sector size",
+            });
+        }
+
         // Validate label length
         if let Some(ref label) = self.label {
             let utf16_len = label.encode_utf16().count();
@@ -146,6 +235,8 @@ pub struct ExFatLayoutParams {
     pub sectors_per_cluster: usize,
     /// Log2 of sectors per cluster
     pub sectors_per_cluster_shift: u8,
+    /// Partition offset in sectors
+    pub partition_offset: u64,
     /// Total volume size in sectors
     pub volume_length: u64,
     /// FAT offset in sectors
@@ -185,20 +276,29 @@ pub fn calculate_layout(
 
     let bytes_per_sector = options.bytes_per_sector;
     let bytes_per_sector_shift = (bytes_per_sector as u32).trailing_zeros() as u8;
+    let sector_size = bytes_per_sector as u64;
 
     // Calculate total sectors
-    let volume_length = volume_size / bytes_per_sector as u64;
-
-    // Calculate sectors per cluster (auto if not specified)
-    let sectors_per_cluster = if options.sectors_per_cluster == 0 {
-        calculate_sectors_per_cluster(volume_size, bytes_per_sector)
+    let volume_length = volume_size / sector_size;
+
+    let sd_card = options
+        .sd_card_layout
+        .then(|| sd_card_parameters(volume_size));
+
+    // Calculate cluster size (auto if not specified)
+    let bytes_per_cluster = if options.cluster_size != 0 {
+        options.cluster_size
+    } else if options.sectors_per_cluster != 0 {
+        options.sectors_per_cluster * bytes_per_sector
+    } else if let Some((cluster_size, _)) = sd_card {
+        cluster_size
     } else {
-        options.sectors_per_cluster
+        calculate_sectors_per_cluster(volume_size, bytes_per_sector) * bytes_per_sector
     };
+    let sectors_per_cluster = (bytes_per_cluster / bytes_per_sector).max(1);
 
     // Validate cluster size (max 32 MB)
-    let bytes_per_cluster = bytes_per_sector * sectors_per_cluster;
-    if bytes_per_cluster > 32 * 1024 * 1024 {
+    if sectors_per_cluster * bytes_per_sector > MAX_CLUSTER_SIZE {
         return Err(Error::InvalidFormatOption {
             option: "sectors_per_cluster",
             reason: "cluster size exceeds 32 MB maximum",
@@ -207,25 +307,21 @@ pub fn calculate_layout(
 
     let sectors_per_cluster_shift = (sectors_per_cluster as u32).trailing_zeros() as u8;
 
-    // FAT offset: after the main and backup boot regions (24 sectors)
-    // Align to boundary if specified
-    let min_fat_offset = (2 * BOOT_REGION_SECTORS) as u32;
-    let fat_offset = if let Some(alignment) = options.boundary_alignment {
-        let align_sectors = (alignment / bytes_per_sector as u64) as u32;
-        if align_sectors > min_fat_offset {
-            align_sectors
-        } else {
-            min_fat_offset
-        }
-    } else {
-        min_fat_offset
-    };
+    // Boundary alignment in sectors; the SD boundary unit replaces the
+    // configured alignment
+    let alignment = match sd_card {
+        Some((_, boundary_unit)) => Some(boundary_unit),
+        None => options.boundary_alignment,
+    }
+    .map(|alignment| alignment / sector_size)
+    .filter(|&sectors| sectors > 1);
 
-    // Calculate cluster heap offset and cluster count
-    // We need to iterate because FAT size depends on cluster count
-    let (cluster_heap_offset, fat_length, cluster_count) = calculate_fat_and_heap(
+    // Calculate FAT placement, cluster heap offset and cluster count
+    let partition_offset = options.partition_offset / sector_size;
+    let (fat_offset, fat_length, cluster_heap_offset, cluster_count) = calculate_fat_and_heap(
         volume_length,
-        fat_offset,
+        partition_offset,
+        alignment,
         bytes_per_sector,
         sectors_per_cluster,
         options.fat_count,
@@ -239,6 +335,7 @@ pub fn calculate_layout(
         bytes_per_sector_shift,
         sectors_per_cluster,
         sectors_per_cluster_shift,
+        partition_offset,
         volume_length,
         fat_offset,
         fat_length,
@@ -269,50 +366,102 @@ fn calculate_sectors_per_cluster(volume_size: u64, bytes_per_sector: usize) -> u
     (bytes_per_cluster / bytes_per_sector).max(1)
 }
 
+/// Cluster size and boundary unit, in bytes, recommended by the SD Card
+/// Association's file system specification for a card of `volume_size`
+/// bytes.
+///
+/// Cards up to 32 GB are SDHC-sized and use the 4 MB boundary unit; SDXC and
+/// SDUC capacities use 128 KB clusters with a 16 MB (up to 64 GB) or 32 MB
+/// boundary unit.
+fn sd_card_parameters(volume_size: u64) -> (usize, u64) {
+    let mb = 1024 * 1024u64;
+    let gb = 1024 * mb;
+
+    if volume_size <= 32 * gb {
+        (32 * 1024, 4 * mb)
+    } else if volume_size <= 64 * gb {
+        (128 * 1024, 16 * mb)
+    } else {
+        (128 * 1024, 32 * mb)
+    }
+}
+
 /// Calculate FAT and cluster heap parameters.
+///
+/// Returns (fat_offset, fat_length, cluster_heap_offset, cluster_count), with
+/// offsets and lengths in sectors. With an `alignment` (in sectors), the FAT
+/// starts on a half-boundary and the cluster heap on a boundary, both
+/// measured from the start of the media (`partition_offset` sectors before
+/// the volume).
 fn calculate_fat_and_heap(
     volume_length: u64,
-    fat_offset: u32,
+    partition_offset: u64,
+    alignment: Option<u64>,
     bytes_per_sector: usize,
     sectors_per_cluster: usize,
     fat_count: u8,
-) -> Result<(u32, u32, u32)> {
-    // Each FAT entry is 4 bytes
-    // FAT length = ceil(cluster_count / entries_per_sector) where entries_per_sector = bytes_per_sector / 4
-    let entries_per_sector = bytes_per_sector / 4;
-
-    // Initial estimate: all remaining space for data
-    let total_fat_sectors_estimate = 1u32;
-    let cluster_heap_offset_estimate = fat_offset + total_fat_sectors_estimate * fat_count as u32;
-
-    // Available sectors for cluster heap
-    let available_sectors = volume_length as u32 - cluster_heap_offset_estimate;
-    let cluster_count_estimate = available_sectors / sectors_per_cluster as u32;
-
-    // Calculate FAT size needed for this many clusters (+2 for reserved entries)
-    let fat_entries_needed = cluster_count_estimate + 2;
-    let fat_length = (fat_entries_needed as usize).div_ceil(entries_per_sector) as u32;
-
-    // Recalculate with actual FAT size
-    let cluster_heap_offset = fat_offset + fat_length * fat_count as u32;
-    let available_sectors = if volume_length as u32 > cluster_heap_offset {
-        volume_length as u32 - cluster_heap_offset
-    } else {
-        return Err(Error::VolumeTooSmall {
-            size: volume_length * bytes_per_sector as u64,
-            min_size: MIN_VOLUME_SIZE,
-        });
+) -> Result<(u32, u32, u32, u32)> {
+    let too_small = || Error::VolumeTooSmall {
+        size: volume_length * bytes_per_sector as u64,
+        min_size: MIN_VOLUME_SIZE,
+    };
+    let align_up = |sector: u64, unit: u64| {
+        (partition_offset + sector).next_multiple_of(unit) - partition_offset
     };
-    let cluster_count = available_sectors / sectors_per_cluster as u32;
 
-    if cluster_count < 1 {
-        return Err(Error::VolumeTooSmall {
-            size: volume_length * bytes_per_sector as u64,
-            min_size: MIN_VOLUME_SIZE,
-        });
-    }
+    // FAT offset: after the main and backup boot regions (24 sectors)
+    let min_fat_offset = (2 * BOOT_REGION_SECTORS) as u64;
+    let fat_offset = match alignment {
+        Some(unit) => align_up(min_fat_offset, (unit / 2).max(1)),
+        None => min_fat_offset,
+    };
 
-    Ok((cluster_heap_offset, fat_length, cluster_count))
+    // Each FAT entry is 4 bytes, and the FAT covers cluster_count + 2
+    // entries. The FAT size depends on the cluster count, which depends on
+    // the FAT size: grow the FAT until it covers the heap that remains.
+    let entries_per_sector = (bytes_per_sector / 4) as u64;
+    let mut fat_length = 1u64;
+    loop {
+        let fat_end = fat_offset + fat_length * fat_count as u64;
+        let cluster_heap_offset = match alignment {
+            Some(unit) => align_up(fat_end, unit),
+            None => fat_end,
+        };
+        let cluster_count = volume_length
+            .checked_sub(cluster_heap_offset)
+            .ok_or_else(too_small)?
+            / sectors_per_cluster as u64;
+
+        let fat_entries_needed = cluster_count + 2;
+        let needed = fat_entries_needed.div_ceil(entries_per_sector);
+        if needed > fat_length {
+            fat_length = needed;
+            continue;
+        }
+
+        if cluster_count < 1 {
+            return Err(too_small());
+        }
+        if cluster_count > MAX_CLUSTER_COUNT {
+            return Err(Error::InvalidFormatOption {
+                option: "sectors_per_cluster",
+                reason: "clusters too small for this volume (over 2^32 - 11 clusters)",
+            });
+        }
+        // Boot-sector fields are 32-bit sector counts.
+        let to_u32 = |sectors: u64| {
+            u32::try_from(sectors).map_err(|_| Error::InvalidFormatOption {
+                option: "sectors_per_cluster",
+                reason: "FAT too large for this volume; use a larger cluster size",
+            })
+        };
+        return Ok((
+            to_u32(fat_offset)?,
+            to_u32(fat_length)?,
+            to_u32(cluster_heap_offset)?,
+            cluster_count as u32,
+        ));
+    }
 }
 
 /// Generate a pseudo-random volume serial number.
@@ -340,7 +489,7 @@ fn generate_serial() -> u32 {
 ///
 /// # Returns
 /// An opened `ExFatVolume` handle to the newly formatted filesystem.
-pub fn format_exfat<DATA>(
+pub async fn format_exfat<DATA>(
     mut data: DATA,
     volume_size: u64,
     options: &ExFatFormatOptions,
@@ -351,36 +500,37 @@ where
     let params = calculate_layout(volume_size, options)?;
 
     // Write main and backup boot regions
-    write_boot_region(&mut data, &params, 0)?;
-    write_boot_region(&mut data, &params, BOOT_REGION_SECTORS as u64)?;
+    write_boot_region(&mut data, &params, 0).await?;
+    write_boot_region(&mut data, &params, BOOT_REGION_SECTORS as u64).await?;
 
     // Initialize FAT
-    initialize_fat(&mut data, &params)?;
+    initialize_fat(&mut data, &params).await?;
 
     // Initialize root directory with system entries
     let (bitmap_cluster, _bitmap_size, upcase_cluster, upcase_size) =
-        initialize_root_directory(&mut data, &params, &options.label)?;
+        initialize_root_directory(&mut data, &params, &options.label).await?;
 
-    // Initialize allocation bitmap (mark used clusters)
+    // Initialize allocation bitmaps (mark used clusters)
     initialize_allocation_bitmap(
         &mut data,
         &params,
         bitmap_cluster,
         upcase_cluster,
         upcase_size,
-    )?;
+    )
+    .await?;
 
     // Write boot region checksums
-    write_boot_checksum(&mut data, &params, 0)?;
-    write_boot_checksum(&mut data, &params, BOOT_REGION_SECTORS as u64)?;
+    write_boot_checksum(&mut data, &params, 0).await?;
+    write_boot_checksum(&mut data, &params, BOOT_REGION_SECTORS as u64).await?;
 
     // Seek back to start and open the filesystem
-    data.seek(SeekFrom::Start(0))?;
-    ExFatVolume::open(data)
+    data.seek(SeekFrom::Start(0)).await?;
+    ExFatVolume::open(data).await
 }
 
 /// Write the boot region (main or backup).
-fn write_boot_region<DATA: Write + Seek>(
+async fn write_boot_region<DATA: Write + Seek>(
     data: &mut DATA,
     params: &ExFatLayoutParams,
     sector_offset: u64,
@@ -392,30 +542,35 @@ fn write_boot_region<DATA: Write + Seek>(
     let boot_bytes = bytemuck::bytes_of(&boot_sector);
 
     // Write boot sector (sector 0 or 12)
-    data.seek(SeekFrom::Start(sector_offset * sector_size as u64))?;
-    data.write_all(boot_bytes)?;
+    data.seek(SeekFrom::Start(sector_offset * sector_size as u64))
+        .await?;
+    data.write_all(boot_bytes).await?;
     // Pad to sector size if needed
     if boot_bytes.len() < sector_size {
-        data.write_all(&vec![0u8; sector_size - boot_bytes.len()])?;
+        data.write_all(&vec![0u8; sector_size - boot_bytes.len()])
+            .await?;
     }
 
     // Write extended boot sectors (sectors 1-8 or 13-20) - all zeros with 0xAA55 signature
     for i in 1..=8 {
-        data.seek(SeekFrom::Start((sector_offset + i) * sector_size as u64))?;
+        data.seek(SeekFrom::Start((sector_offset + i) * sector_size as u64))
+            .await?;
         let mut extended = vec![0u8; sector_size];
         // Extended boot signature at end
         extended[sector_size - 2] = 0x55;
         extended[sector_size - 1] = 0xAA;
-        data.write_all(&extended)?;
+        data.write_all(&extended).await?;
     }
 
     // Write OEM parameters sector (sector 9 or 21) - zeros
-    data.seek(SeekFrom::Start((sector_offset + 9) * sector_size as u64))?;
-    data.write_all(&vec![0u8; sector_size])?;
+    data.seek(SeekFrom::Start((sector_offset + 9) * sector_size as u64))
+        .await?;
+    data.write_all(&vec![0u8; sector_size]).await?;
 
     // Write reserved sector (sector 10 or 22) - zeros
-    data.seek(SeekFrom::Start((sector_offset + 10) * sector_size as u64))?;
-    data.write_all(&vec![0u8; sector_size])?;
+    data.seek(SeekFrom::Start((sector_offset + 10) * sector_size as u64))
+        .await?;
+    data.write_all(&vec![0u8; sector_size]).await?;
 
     // Boot checksum sector (sector 11 or 23) will be written later
 
@@ -428,7 +583,7 @@ fn create_boot_sector(params: &ExFatLayoutParams) -> RawExFatBootSector {
         jump_boot: [0xEB, 0x76, 0x90], // Standard jump instruction
         fs_name: EXFAT_SIGNATURE,
         must_be_zero: [0; 53],
-        partition_offset: U64::<LittleEndian>::new(0),
+        partition_offset: U64::<LittleEndian>::new(params.partition_offset),
         volume_length: U64::<LittleEndian>::new(params.volume_length),
         fat_offset: U32::<LittleEndian>::new(params.fat_offset),
         fat_length: U32::<LittleEndian>::new(params.fat_length),
@@ -450,7 +605,7 @@ fn create_boot_sector(params: &ExFatLayoutParams) -> RawExFatBootSector {
 }
 
 /// Write the boot region checksum.
-fn write_boot_checksum<DATA: Read + Write + Seek>(
+async fn write_boot_checksum<DATA: Read + Write + Seek>(
     data: &mut DATA,
     params: &ExFatLayoutParams,
     sector_offset: u64,
@@ -463,11 +618,12 @@ fn write_boot_checksum<DATA: Read + Write + Seek>(
     for sector in 0..11 {
         data.seek(SeekFrom::Start(
             (sector_offset + sector) * sector_size as u64,
-        ))?;
+        ))
+        .await?;
 
         for byte_idx in 0..sector_size {
             let mut byte = [0u8; 1];
-            data.read_exact(&mut byte)?;
+            data.read_exact(&mut byte).await?;
 
             // Skip VolumeFlags (bytes 106-107) and PercentInUse (byte 112) in sector 0
             if sector == 0 && (byte_idx == 106 || byte_idx == 107 || byte_idx == 112) {
@@ -479,53 +635,91 @@ fn write_boot_checksum<DATA: Read + Write + Seek>(
     }
 
     // Write checksum sector (sector 11) - checksum repeated to fill sector
-    data.seek(SeekFrom::Start((sector_offset + 11) * sector_size as u64))?;
+    data.seek(SeekFrom::Start((sector_offset + 11) * sector_size as u64))
+        .await?;
     let checksum_bytes = checksum.to_le_bytes();
     let repeat_count = sector_size / 4;
     for _ in 0..repeat_count {
-        data.write_all(&checksum_bytes)?;
+        data.write_all(&checksum_bytes).await?;
     }
 
     Ok(())
 }
 
 /// Initialize the FAT with reserved entries.
-fn initialize_fat<DATA: Write + Seek>(data: &mut DATA, params: &ExFatLayoutParams) -> Result<()> {
-    let sector_size = params.bytes_per_sector;
-    let fat_offset_bytes = params.fat_offset as u64 * sector_size as u64;
+async fn initialize_fat<DATA: Write + Seek>(
+    data: &mut DATA,
+    params: &ExFatLayoutParams,
+) -> Result<()> {
+    let sector_size = params.bytes_per_sector as u64;
+    let fat_size = params.fat_length as u64 * sector_size;
 
     // FAT entry values:
     // 0: Media type (0xFFFFFFF8)
     // 1: Reserved (0xFFFFFFFF)
     // 2+: Cluster entries
+    for fat_index in 0..params.fat_count as u64 {
+        let fat_offset_bytes = params.fat_offset as u64 * sector_size + fat_index * fat_size;
+
+        // Initialize FAT with zeros first
+        write_zeros(data, fat_offset_bytes, fat_size).await?;
 
-    // Initialize FAT with zeros first
-    data.seek(SeekFrom::Start(fat_offset_bytes))?;
-    let fat_size = params.fat_length as usize * sector_size;
-    data.write_all(&vec![0u8; fat_size])?;
+        // Write reserved entries
+        data.seek(SeekFrom::Start(fat_offset_bytes)).await?;
+        data.write_all(&0xFFFFFFF8u32.to_le_bytes()).await?; // Entry 0: Media type
+        data.write_all(&0xFFFFFFFFu32.to_le_bytes()).await?; // Entry 1: Reserved
+    }
 
-    // Write reserved entries
-    data.seek(SeekFrom::Start(fat_offset_bytes))?;
-    data.write_all(&0xFFFFFFF8u32.to_le_bytes())?; // Entry 0: Media type
-    data.write_all(&0xFFFFFFFFu32.to_le_bytes())?; // Entry 1: Reserved
+    Ok(())
+}
 
-    // If there's a second FAT, copy the entries
-    if params.fat_count == 2 {
-        let fat2_offset = fat_offset_bytes + params.fat_length as u64 * sector_size as u64;
-        data.seek(SeekFrom::Start(fat2_offset))?;
-        data.write_all(&vec![0u8; fat_size])?;
-        data.seek(SeekFrom::Start(fat2_offset))?;
-        data.write_all(&0xFFFFFFF8u32.to_le_bytes())?;
-        data.write_all(&0xFFFFFFFFu32.to_le_bytes())?;
+/// Write a run of zeros without buffering it all at once; a multi-terabyte
+/// volume has a FAT and bitmap of hundreds of megabytes.
+async fn write_zeros<DATA: Write + Seek>(data: &mut DATA, offset: u64, len: u64) -> Result<()> {
+    const CHUNK: u64 = 64 * 1024;
+    let zeros = vec![0u8; len.min(CHUNK) as usize];
+
+    data.seek(SeekFrom::Start(offset)).await?;
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(CHUNK) as usize;
+        data.write_all(&zeros[..chunk]).await?;
+        remaining -= chunk as u64;
     }
+    Ok(())
+}
 
+/// Record a contiguous chain of `count` clusters from `first` in every FAT.
+async fn write_fat_chain<DATA: Write + Seek>(
+    data: &mut DATA,
+    params: &ExFatLayoutParams,
+    first: u32,
+    count: u32,
+) -> Result<()> {
+    let sector_size = params.bytes_per_sector as u64;
+    let fat_size = params.fat_length as u64 * sector_size;
+
+    for fat_index in 0..params.fat_count as u64 {
+        let fat_offset_bytes = params.fat_offset as u64 * sector_size + fat_index * fat_size;
+        data.seek(SeekFrom::Start(fat_offset_bytes + first as u64 * 4))
+            .await?;
+        for cluster in first..first + count {
+            // The last cluster ends the chain
+            let next = if cluster + 1 == first + count {
+                0xFFFFFFFF
+            } else {
+                cluster + 1
+            };
+            data.write_all(&next.to_le_bytes()).await?;
+        }
+    }
     Ok(())
 }
 
 /// Initialize the root directory with system entries.
 ///
 /// Returns (bitmap_cluster, bitmap_size, upcase_cluster, upcase_size)
-fn initialize_root_directory<DATA: Write + Seek>(
+async fn initialize_root_directory<DATA: Write + Seek>(
     data: &mut DATA,
     params: &ExFatLayoutParams,
     label: &Option<String>,
@@ -540,56 +734,46 @@ fn initialize_root_directory<DATA: Write + Seek>(
     let upcase_clusters = (upcase_size as usize).div_ceil(cluster_size) as u32;
 
     // Allocation bitmap size: 1 bit per cluster
-    let bitmap_size = (params.cluster_count as usize).div_ceil(8) as u64;
-    let bitmap_clusters = (bitmap_size as usize).div_ceil(cluster_size) as u32;
+    let bitmap_size = (params.cluster_count as u64).div_ceil(8);
+    let bitmap_clusters = bitmap_size.div_ceil(cluster_size as u64) as u32;
 
     // Layout:
-    // Cluster 2: Allocation Bitmap
-    // Cluster 2 + bitmap_clusters: Upcase Table
-    // Cluster 2 + bitmap_clusters + upcase_clusters: Root Directory
+    // Cluster 2: Allocation Bitmap (one per FAT, back to back)
+    // Cluster 2 + fat_count * bitmap_clusters: Upcase Table
+    // Then the Root Directory
     let bitmap_cluster = 2u32;
-    let upcase_cluster = bitmap_cluster + bitmap_clusters;
+    let upcase_cluster = bitmap_cluster + params.fat_count as u32 * bitmap_clusters;
     let root_cluster = upcase_cluster + upcase_clusters;
 
     // Update boot sector with correct root cluster
     // (We need to re-write it since we now know the root cluster)
     let mut boot_sector = create_boot_sector(params);
     boot_sector.first_cluster_of_root = U32::<LittleEndian>::new(root_cluster);
-    data.seek(SeekFrom::Start(0))?;
-    data.write_all(bytemuck::bytes_of(&boot_sector))?;
+    data.seek(SeekFrom::Start(0)).await?;
+    data.write_all(bytemuck::bytes_of(&boot_sector)).await?;
     // Also update backup
     data.seek(SeekFrom::Start(
         BOOT_REGION_SECTORS as u64 * sector_size as u64,
-    ))?;
-    data.write_all(bytemuck::bytes_of(&boot_sector))?;
+    ))
+    .await?;
+    data.write_all(bytemuck::bytes_of(&boot_sector)).await?;
 
     // Write upcase table to cluster heap
     let upcase_offset = cluster_heap_offset + (upcase_cluster as u64 - 2) * cluster_size as u64;
-    data.seek(SeekFrom::Start(upcase_offset))?;
-    data.write_all(&upcase_data)?;
+    data.seek(SeekFrom::Start(upcase_offset)).await?;
+    data.write_all(&upcase_data).await?;
     // Pad to cluster boundary
     let upcase_padding = (upcase_clusters as usize * cluster_size) - upcase_data.len();
     if upcase_padding > 0 {
-        data.write_all(&vec![0u8; upcase_padding])?;
+        data.write_all(&vec![0u8; upcase_padding]).await?;
     }
 
     // Update FAT entries for upcase table
-    let fat_offset_bytes = params.fat_offset as u64 * sector_size as u64;
-    if upcase_clusters > 1 {
-        for i in 0..upcase_clusters - 1 {
-            let entry_offset = fat_offset_bytes + (upcase_cluster + i) as u64 * 4;
-            data.seek(SeekFrom::Start(entry_offset))?;
-            data.write_all(&(upcase_cluster + i + 1).to_le_bytes())?;
-        }
-    }
-    // End of chain for last upcase cluster
-    let entry_offset = fat_offset_bytes + (upcase_cluster + upcase_clusters - 1) as u64 * 4;
-    data.seek(SeekFrom::Start(entry_offset))?;
-    data.write_all(&0xFFFFFFFFu32.to_le_bytes())?;
+    write_fat_chain(data, params, upcase_cluster, upcase_clusters).await?;
 
     // Initialize root directory
     let root_offset = cluster_heap_offset + (root_cluster as u64 - 2) * cluster_size as u64;
-    data.seek(SeekFrom::Start(root_offset))?;
+    data.seek(SeekFrom::Start(root_offset)).await?;
 
     // Create directory entries
     let mut entries: Vec<RawDirectoryEntry> = Vec::new();
@@ -600,9 +784,11 @@ fn initialize_root_directory<DATA: Write + Seek>(
         entries.push(label_entry);
     }
 
-    // 2. Allocation Bitmap Entry
-    let bitmap_entry = create_bitmap_entry(bitmap_cluster, bitmap_size);
-    entries.push(bitmap_entry);
+    // 2. Allocation Bitmap Entries, identified by their FAT
+    for identifier in 0..params.fat_count {
+        let first_cluster = bitmap_cluster + identifier as u32 * bitmap_clusters;
+        entries.push(create_bitmap_entry(first_cluster, bitmap_size, identifier));
+    }
 
     // 3. Upcase Table Entry
     let upcase_entry = create_upcase_entry(upcase_cluster, upcase_size, upcase_checksum);
@@ -610,18 +796,16 @@ fn initialize_root_directory<DATA: Write + Seek>(
 
     // Write entries
     for entry in &entries {
-        data.write_all(unsafe { &entry.bytes })?;
+        data.write_all(unsafe { &entry.bytes }).await?;
     }
 
     // Fill rest of cluster with zeros (end of directory markers)
     let entries_written = entries.len() * 32;
     let remaining = cluster_size - entries_written;
-    data.write_all(&vec![0u8; remaining])?;
+    data.write_all(&vec![0u8; remaining]).await?;
 
     // Update FAT entry for root directory (end of chain)
-    let root_fat_offset = fat_offset_bytes + root_cluster as u64 * 4;
-    data.seek(SeekFrom::Start(root_fat_offset))?;
-    data.write_all(&0xFFFFFFFFu32.to_le_bytes())?;
+    write_fat_chain(data, params, root_cluster, 1).await?;
 
     Ok((bitmap_cluster, bitmap_size, upcase_cluster, upcase_size))
 }
@@ -654,10 +838,10 @@ fn create_volume_label_entry(label: &str) -> RawDirectoryEntry {
 }
 
 /// Create an allocation bitmap directory entry.
-fn create_bitmap_entry(first_cluster: u32, size: u64) -> RawDirectoryEntry {
+fn create_bitmap_entry(first_cluster: u32, size: u64, identifier: u8) -> RawDirectoryEntry {
     let entry = RawAllocationBitmapEntry {
         entry_type: entry_type::ALLOCATION_BITMAP,
-        bitmap_flags: 0, // First (and only) bitmap
+        bitmap_flags: identifier, // BitmapIdentifier: the FAT it belongs to
         reserved: [0; 18],
         first_cluster: U32::<LittleEndian>::new(first_cluster),
         data_length: U64::<LittleEndian>::new(size),
@@ -691,7 +875,7 @@ fn create_upcase_entry(first_cluster: u32, size: u64, checksum: u32) -> RawDirec
 }
 
 /// Initialize the allocation bitmap.
-fn initialize_allocation_bitmap<DATA: Write + Seek>(
+async fn initialize_allocation_bitmap<DATA: Write + Seek>(
     data: &mut DATA,
     params: &ExFatLayoutParams,
     bitmap_cluster: u32,
@@ -703,23 +887,25 @@ fn initialize_allocation_bitmap<DATA: Write + Seek>(
     let cluster_heap_offset = params.cluster_heap_offset as u64 * sector_size as u64;
 
     // Calculate bitmap size
-    let bitmap_size = (params.cluster_count as usize).div_ceil(8);
-    let bitmap_clusters = bitmap_size.div_ceil(cluster_size);
+    let bitmap_size = (params.cluster_count as u64).div_ceil(8);
+    let bitmap_clusters = bitmap_size.div_ceil(cluster_size as u64) as u32;
 
     // Calculate upcase clusters
     let upcase_clusters = (upcase_size as usize).div_ceil(cluster_size) as u32;
     let root_cluster = upcase_cluster + upcase_clusters;
+    let bitmaps_clusters = params.fat_count as u32 * bitmap_clusters;
 
-    // Create bitmap with system clusters marked as used
-    let mut bitmap = vec![0u8; bitmap_size];
+    // Only the leading system clusters are in use, so only the head of the
+    // bitmap has bits set; the rest is written as zeros.
+    let mut bitmap = vec![0u8; ((root_cluster - 2) as u64 / 8 + 1).min(bitmap_size) as usize];
 
     // Mark clusters as used:
-    // - Cluster 2 to 2 + bitmap_clusters - 1: Bitmap itself
+    // - Cluster 2 to upcase_cluster - 1: The bitmaps themselves
     // - Cluster upcase_cluster to upcase_cluster + upcase_clusters - 1: Upcase table
     // - Cluster root_cluster: Root directory
 
     // Mark bitmap clusters
-    for i in 0..bitmap_clusters as u32 {
+    for i in 0..bitmaps_clusters {
         let cluster = bitmap_cluster + i;
         set_bitmap_bit(&mut bitmap, cluster - 2);
     }
@@ -733,29 +919,22 @@ fn initialize_allocation_bitmap<DATA: Write + Seek>(
     // Mark root directory cluster
     set_bitmap_bit(&mut bitmap, root_cluster - 2);
 
-    // Update FAT entries for bitmap
-    let fat_offset_bytes = params.fat_offset as u64 * sector_size as u64;
-    if bitmap_clusters > 1 {
-        for i in 0..bitmap_clusters - 1 {
-            let entry_offset = fat_offset_bytes + (bitmap_cluster + i as u32) as u64 * 4;
-            data.seek(SeekFrom::Start(entry_offset))?;
-            data.write_all(&(bitmap_cluster + i as u32 + 1).to_le_bytes())?;
-        }
-    }
-    // End of chain for last bitmap cluster
-    let entry_offset = fat_offset_bytes + (bitmap_cluster + bitmap_clusters as u32 - 1) as u64 * 4;
-    data.seek(SeekFrom::Start(entry_offset))?;
-    data.write_all(&0xFFFFFFFFu32.to_le_bytes())?;
-
-    // Write bitmap to cluster heap
-    let bitmap_offset = cluster_heap_offset + (bitmap_cluster as u64 - 2) * cluster_size as u64;
-    data.seek(SeekFrom::Start(bitmap_offset))?;
-    data.write_all(&bitmap)?;
-
-    // Pad to cluster boundary
-    let bitmap_padding = (bitmap_clusters * cluster_size) - bitmap_size;
-    if bitmap_padding > 0 {
-        data.write_all(&vec![0u8; bitmap_padding])?;
+    // Each bitmap gets its own FAT chain and an identical copy of the data,
+    // padded to the cluster boundary
+    for identifier in 0..params.fat_count as u32 {
+        let first_cluster = bitmap_cluster + identifier * bitmap_clusters;
+        write_fat_chain(data, params, first_cluster, bitmap_clusters).await?;
+
+        let bitmap_offset = cluster_heap_offset + (first_cluster as u64 - 2) * cluster_size as u64;
+        data.seek(SeekFrom::Start(bitmap_offset)).await?;
+        data.write_all(&bitmap).await?;
+        let padded_size = bitmap_clusters as u64 * cluster_size as u64;
+        write_zeros(
+            data,
+            bitmap_offset + bitmap.len() as u64,
+            padded_size - bitmap.len() as u64,
+        )
+        .await?;
     }
 
     Ok(())
@@ -772,6 +951,12 @@ fn set_bitmap_bit(bitmap: &mut [u8], cluster_index: u32) {
 
 use alloc::string::ToString;
 
+} // end io_transform!
+
+// The tests drive the formatter through `std::io::Cursor`, which only
+// implements the sync traits.
+sync_only! {
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -798,6 +983,34 @@ mod tests {
         assert!(params.cluster_count > 0);
     }
 
+    #[test]
+    fn test_calculate_layout_multi_terabyte_volume() {
+        // 8 TB of 512-byte sectors overflows a u32 sector count
+        let size = 8 * 1024 * 1024 * 1024 * 1024u64;
+        let options = ExFatFormatOptions::new().sd_card_layout(true);
+        let params = calculate_layout(size, &options).unwrap();
+
+        assert_eq!(params.volume_length, size / 512);
+        assert_eq!(params.sectors_per_cluster, 256);
+        assert_eq!(params.fat_offset, 32768);
+        assert_eq!(params.cluster_heap_offset % 65536, 0);
+        assert_eq!(
+            params.cluster_count as u64,
+            (params.volume_length - params.cluster_heap_offset as u64) / 256
+        );
+        assert!(params.fat_length as u64 * 128 >= params.cluster_count as u64 + 2);
+    }
+
+    #[test]
+    fn test_cluster_size_overrides_sectors_per_cluster() {
+        let options = ExFatFormatOptions::new()
+            .sectors_per_cluster(8)
+            .cluster_size(64 * 1024);
+        let params = calculate_layout(64 * 1024 * 1024, &options).unwrap();
+        assert_eq!(params.sectors_per_cluster, 128);
+        assert_eq!(params.sectors_per_cluster_shift, 7);
+    }
+
     #[test]
     fn test_format_exfat_writes_boot_sector() {
         // Test that formatting writes a valid boot sector
@@ -842,6 +1055,20 @@ mod tests {
         options.fat_count = 3;
         assert!(options.validate().is_err());
 
+        // Cluster sizes must be powers of two
+        let options = ExFatFormatOptions::new().sectors_per_cluster(3);
+        assert!(options.validate().is_err());
+        let options = ExFatFormatOptions::new().cluster_size(48 * 1024);
+        assert!(options.validate().is_err());
+        let options = ExFatFormatOptions::new().cluster_size(64 * 1024 * 1024);
+        assert!(options.validate().is_err());
+
+        // Alignment and partition offset
+        let options = ExFatFormatOptions::new().boundary_alignment(Some(3 * 1024 * 1024));
+        assert!(options.validate().is_err());
+        let options = ExFatFormatOptions::new().partition_offset(100);
+        assert!(options.validate().is_err());
+
         // Valid options
         let options = ExFatFormatOptions::new();
         assert!(options.validate().is_ok());
@@ -870,3 +1097,5 @@ mod tests {
         assert!(info.root_cluster >= 2);
     }
 }
+
+} // end sync_only!
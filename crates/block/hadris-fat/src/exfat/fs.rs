@@ -2,26 +2,30 @@
 //!
 //! The main entry point for working with exFAT filesystems.
 
+io_transform! {
+
 #[cfg(feature = "write")]
 use alloc::string::ToString;
-#[cfg(feature = "write")]
 use alloc::vec::Vec;
+use core::mem::size_of;
 
 use hadris_common::types::endian::Endian;
 use hadris_path::{Component, VPath};
 use spin::Mutex;
 
-use crate::error::{Error, Result};
 #[cfg(feature = "write")]
-use crate::io::Write;
-use crate::io::{Read, ReadExt, SectorCursor, Seek, SeekFrom};
+use super::super::io::Write;
+use super::super::io::{Read, ReadExt, SectorCursor, Seek, SeekFrom};
+use crate::error::{Error, Result};
 
 use super::bitmap::AllocationBitmap;
-use super::boot::{ExFatBootSector, ExFatInfo};
+#[cfg(feature = "write")]
+use super::boot::VOLUME_FLAG_DIRTY;
+use super::boot::{ExFatBootSector, ExFatInfo, VOLUME_FLAG_ACTIVE_FAT};
 use super::dir::ExFatDir;
 #[cfg(feature = "write")]
 use super::entry::FileAttributes;
-use super::entry::{ExFatFileEntry, RawDirectoryEntry, entry_type};
+use super::entry::{EntrySetLocation, ExFatFileEntry, RawDirectoryEntry, entry_type};
 #[cfg(feature = "write")]
 use super::entry_writer::EntrySetBuilder;
 use super::fat::ExFatTable;
@@ -30,6 +34,10 @@ use super::file::ExFatFileReader;
 use super::file::ExFatFileWriter;
 use super::upcase::UpcaseTable;
 
+/// The largest DataLength a directory may have (256 MiB).
+#[cfg(feature = "write")]
+const MAX_DIRECTORY_SIZE: u64 = 256 * 1024 * 1024;
+
 /// exFAT filesystem handle.
 ///
 /// This is the main type for interacting with an exFAT filesystem.
@@ -50,6 +58,26 @@ pub struct ExFatVolume<DATA: Seek> {
     root_contiguous: bool,
     /// Size of the root directory
     root_size: u64,
+    /// Clusters of each allocation bitmap, by BitmapIdentifier; the second
+    /// is empty unless the volume has two FATs
+    #[cfg(feature = "write")]
+    bitmap_copies: [Vec<u32>; 2],
+    /// Transaction-safe (TexFAT) update state
+    #[cfg(feature = "write")]
+    transaction: Mutex<Transaction>,
+}
+
+/// TexFAT state of a volume.
+#[cfg(feature = "write")]
+struct Transaction {
+    /// Whether updates are shadowed into the inactive FAT and bitmap
+    enabled: bool,
+    /// VolumeFlags as last written to the boot sector
+    volume_flags: u16,
+    /// Whether an update is under way (VolumeDirty is set on disk)
+    open: bool,
+    /// Whether opening the volume rolled back an interrupted transaction
+    recovered: bool,
 }
 
 impl<DATA> ExFatVolume<DATA>
@@ -57,40 +85,63 @@ where
     DATA: Read + Seek,
 {
     /// Open an exFAT filesystem from a data source.
-    pub fn open(mut data: DATA) -> Result<Self> {
+    pub async fn open(mut data: DATA) -> Result<Self> {
         // Read and validate the boot sector
-        let boot = ExFatBootSector::read(&mut data)?;
+        let boot = ExFatBootSector::read(&mut data).await?;
         let info = boot.info().clone();
-        ExFatBootSector::validate_checksum(&mut data, info.bytes_per_sector)?;
+        let volume_flags = boot.raw().volume_flags.get();
+        ExFatBootSector::validate_checksum(&mut data, info.bytes_per_sector).await?;
 
         // Create sector cursor
         let cursor = SectorCursor::new(data, info.bytes_per_sector, info.bytes_per_cluster);
         let data = Mutex::new(cursor);
 
-        // Create FAT accessor
+        // Create FAT accessor, reading the FAT that VolumeFlags marks active
         let fat = ExFatTable::new(&info);
+        let active = if info.fat_count == 2 {
+            (volume_flags & VOLUME_FLAG_ACTIVE_FAT) as usize
+        } else {
+            0
+        };
+        fat.set_working_copy(active as u8);
 
-        // Create placeholder bitmap and upcase table
+        // Allocation bitmaps by BitmapIdentifier: (first cluster, size, contiguous)
+        let mut bitmaps: [Option<(u32, u64, bool)>; 2] = [None, None];
         let mut bitmap = AllocationBitmap::new(0, 0, info.cluster_count, true);
         let mut upcase = UpcaseTable::new();
+        #[cfg(feature = "write")]
+        let mut bitmap_copies: [Vec<u32>; 2] = [Vec::new(), Vec::new()];
 
-        // Scan root directory for system entries
+        // Scan root directory for system entries. The root has no stream
+        // extension to carry NoFatChain, so it is always a FAT chain.
         let root_cluster = info.root_cluster;
-        let root_contiguous = true; // Assume contiguous initially
+        let root_contiguous = false;
         let root_size = 0u64;
 
         {
             let mut guard = data.lock();
 
-            // Read root directory entries to find bitmap and upcase table
-            let mut offset = info.cluster_to_offset(root_cluster);
+            // Read root directory entries to find bitmap and upcase table,
+            // following the root's FAT chain across cluster boundaries
+            let entry_size = size_of::<RawDirectoryEntry>();
+            let entries_per_cluster = info.bytes_per_cluster / entry_size;
+            let mut cluster = root_cluster;
+            let mut index = 0;
             let mut entries_read = 0;
             const MAX_SYSTEM_ENTRIES: usize = 100;
 
             while entries_read < MAX_SYSTEM_ENTRIES {
-                guard.seek(SeekFrom::Start(offset))?;
+                if index == entries_per_cluster {
+                    match fat.next_cluster(&mut guard.data, cluster).await? {
+                        Some(next) => cluster = next,
+                        None => break,
+                    }
+                    index = 0;
+                }
+                let offset = info.cluster_to_offset(cluster) + (index * entry_size) as u64;
+                guard.seek(SeekFrom::Start(offset)).await?;
 
-                let entry: RawDirectoryEntry = guard.data.read_struct()?;
+                let entry: RawDirectoryEntry = guard.data.read_struct().await?;
                 let entry_type_byte = unsafe { entry.entry_type };
 
                 if entry_type_byte == entry_type::END_OF_DIRECTORY {
@@ -100,43 +151,71 @@ where
                 match entry_type_byte {
                     entry_type::ALLOCATION_BITMAP => {
                         let bitmap_entry = unsafe { &entry.bitmap };
-                        bitmap = AllocationBitmap::new(
-                            bitmap_entry.first_cluster.get(),
+                        let first_cluster = bitmap_entry.first_cluster.get();
+                        let identifier = (bitmap_entry.bitmap_flags & 0x01) as usize;
+                        bitmaps[identifier] = Some((
+                            first_cluster,
                             bitmap_entry.data_length.get(),
-                            info.cluster_count,
-                            true, // Assume contiguous
-                        );
+                            Self::system_file_contiguous(&fat, &mut guard.data, first_cluster)
+                                .await?,
+                        ));
                     }
                     entry_type::UPCASE_TABLE => {
                         let upcase_entry = unsafe { &entry.upcase };
-                        // We'll load the upcase table after this loop
                         let first_cluster = upcase_entry.first_cluster.get();
                         let size = upcase_entry.data_length.get();
                         let checksum = upcase_entry.table_checksum.get();
-
-                        // Load immediately since we have the guard
-                        drop(guard);
-                        let mut guard2 = data.lock();
-                        upcase.load_checked(
-                            &mut guard2.data,
-                            &info,
-                            first_cluster,
-                            size,
-                            true, // Assume contiguous
-                            checksum,
-                        )?;
-                        guard = guard2;
+                        let is_contiguous =
+                            Self::system_file_contiguous(&fat, &mut guard.data, first_cluster)
+                                .await?;
+
+                        upcase
+                            .load_checked(
+                                &mut guard.data,
+                                &info,
+                                first_cluster,
+                                size,
+                                is_contiguous,
+                                checksum,
+                            )
+                            .await?;
                     }
                     _ => {}
                 }
 
-                offset += size_of::<RawDirectoryEntry>() as u64;
+                index += 1;
                 entries_read += 1;
             }
 
-            // Load the allocation bitmap
-            if bitmap.first_cluster() != 0 {
-                bitmap.load(&mut guard.data, &info)?;
+            // Load the active allocation bitmap; a volume formatted with a
+            // single bitmap has only the first, whatever its FAT count
+            let loaded = if bitmaps[active].is_some() {
+                active
+            } else {
+                1 - active
+            };
+            if let Some((first_cluster, size, is_contiguous)) = bitmaps[loaded] {
+                bitmap =
+                    AllocationBitmap::new(first_cluster, size, info.cluster_count, is_contiguous);
+                bitmap.load(&mut guard.data, &info).await?;
+            }
+
+            // Updates are written to every bitmap, so locate the other one too
+            #[cfg(feature = "write")]
+            if let Some((first_cluster, size, is_contiguous)) = bitmaps[1 - loaded] {
+                if size != bitmap.size() {
+                    return Err(Error::ExFatInvalidEntry {
+                        reason: "allocation bitmaps differ in size",
+                    });
+                }
+                let count = size.div_ceil(info.bytes_per_cluster as u64) as u32;
+                bitmap_copies[1 - loaded] = fat
+                    .chain_clusters(&mut guard.data, first_cluster, count, is_contiguous)
+                    .await?;
+            }
+            #[cfg(feature = "write")]
+            {
+                bitmap_copies[loaded] = bitmap.clusters().to_vec();
             }
         }
 
@@ -154,9 +233,37 @@ where
             root_cluster,
             root_contiguous,
             root_size,
+            #[cfg(feature = "write")]
+            bitmap_copies,
+            #[cfg(feature = "write")]
+            transaction: Mutex::new(Transaction {
+                enabled: false,
+                volume_flags,
+                open: false,
+                recovered: false,
+            }),
         })
     }
 
+    /// Whether the system file (allocation bitmap or up-case table) starting
+    /// at `first_cluster` should be read as one contiguous run.
+    ///
+    /// These entries carry no NoFatChain flag, so the FAT describes their
+    /// clusters and may send them anywhere on fragmented media. Some
+    /// formatters never record the chain; a free FAT entry for the first
+    /// cluster means the data was laid out contiguously instead.
+    async fn system_file_contiguous(
+        fat: &ExFatTable,
+        data: &mut DATA,
+        first_cluster: u32,
+    ) -> Result<bool> {
+        // Out-of-range clusters are left for the loader to reject.
+        if !(ExFatTable::FIRST_DATA_CLUSTER..=fat.max_cluster()).contains(&first_cluster) {
+            return Ok(true);
+        }
+        Ok(fat.read_entry(data, first_cluster).await? == ExFatTable::FREE_CLUSTER)
+    }
+
     /// Get filesystem information.
     pub fn info(&self) -> &ExFatInfo {
         &self.info
@@ -169,33 +276,29 @@ where
             first_cluster: self.root_cluster,
             is_contiguous: self.root_contiguous,
             size: self.root_size,
+            location: None,
         }
     }
 
     /// Open a file by path.
-    pub fn open_file(&self, path: &str) -> Result<ExFatFileReader<'_, DATA>> {
-        let entry = self.open_path(path)?;
+    pub async fn open_file(&self, path: &str) -> Result<ExFatFileReader<'_, DATA>> {
+        let entry = self.open_path(path).await?;
         ExFatFileReader::new(self, &entry)
     }
 
     /// Open a directory by path.
-    pub fn open_dir(&self, path: &str) -> Result<ExFatDir<'_, DATA>> {
-        let entry = self.open_path(path)?;
+    pub async fn open_dir(&self, path: &str) -> Result<ExFatDir<'_, DATA>> {
+        let entry = self.open_path(path).await?;
 
         if !entry.is_directory() {
             return Err(Error::NotADirectory);
         }
 
-        Ok(ExFatDir {
-            fs: self,
-            first_cluster: entry.first_cluster,
-            is_contiguous: entry.no_fat_chain,
-            size: entry.data_length,
-        })
+        Ok(ExFatDir::from_entry(self, &entry))
     }
 
     /// Open a file or directory by path.
-    pub fn open_path(&self, path: &str) -> Result<ExFatFileEntry> {
+    pub async fn open_path(&self, path: &str) -> Result<ExFatFileEntry> {
         let mut current_dir = self.root_dir();
         let mut components = VPath::new(path)
             .components()
@@ -211,19 +314,17 @@ where
 
         while let Some(component) = components.next() {
             let component = component?;
-            let entry = current_dir.find(component)?.ok_or(Error::EntryNotFound)?;
+            let entry = current_dir
+                .find(component)
+                .await?
+                .ok_or(Error::EntryNotFound)?;
 
             if components.peek().is_some() {
                 // Not the last component, must be a directory
                 if !entry.is_directory() {
                     return Err(Error::NotADirectory);
                 }
-                current_dir = ExFatDir {
-                    fs: self,
-                    first_cluster: entry.first_cluster,
-                    is_contiguous: entry.no_fat_chain,
-                    size: entry.data_length,
-                };
+                current_dir = ExFatDir::from_entry(self, &entry);
             } else {
                 return Ok(entry);
             }
@@ -233,27 +334,107 @@ where
     }
 
     /// Get the next cluster in a chain.
-    pub(crate) fn next_cluster(&self, cluster: u32) -> Result<Option<u32>> {
+    pub(crate) async fn next_cluster(&self, cluster: u32) -> Result<Option<u32>> {
         let mut guard = self.data.lock();
-        self.fat.next_cluster(&mut guard.data, cluster)
+        self.fat.next_cluster(&mut guard.data, cluster).await
     }
 
     /// Read data at a specific offset.
-    pub(crate) fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
+    pub(crate) async fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<()> {
         let mut guard = self.data.lock();
-        guard.seek(SeekFrom::Start(offset))?;
-        guard.read_exact(buf)?;
+        guard.seek(SeekFrom::Start(offset)).await?;
+        guard.read_exact(buf).await?;
         Ok(())
     }
 
     /// Read a directory entry at a specific offset.
-    pub(crate) fn read_entry_at(&self, offset: u64) -> Result<RawDirectoryEntry> {
+    pub(crate) async fn read_entry_at(&self, offset: u64) -> Result<RawDirectoryEntry> {
         let mut guard = self.data.lock();
-        guard.seek(SeekFrom::Start(offset))?;
-        let entry: RawDirectoryEntry = guard.data.read_struct()?;
+        guard.seek(SeekFrom::Start(offset)).await?;
+        let entry: RawDirectoryEntry = guard.data.read_struct().await?;
         Ok(entry)
     }
 
+    /// The cluster containing the byte at `offset`.
+    fn offset_to_cluster(&self, offset: u64) -> u32 {
+        ((offset - self.info.cluster_heap_offset) / self.info.bytes_per_cluster as u64) as u32 + 2
+    }
+
+    /// Absolute offsets of the `count` slots of the entry set at `location`.
+    ///
+    /// An entry set may cross a cluster boundary; it then continues in the
+    /// parent directory's next cluster, which is the adjacent cluster for a
+    /// contiguous parent and the FAT successor otherwise.
+    pub(crate) async fn slot_offsets(
+        &self,
+        location: EntrySetLocation,
+        count: usize,
+    ) -> Result<Vec<u64>> {
+        let cluster_size = self.info.bytes_per_cluster as u64;
+        let mut offsets = Vec::with_capacity(count);
+        let mut offset = location.offset;
+        for index in 0..count {
+            if index > 0 && (offset - self.info.cluster_heap_offset) % cluster_size == 0 {
+                let cluster = self.offset_to_cluster(offset - cluster_size);
+                let next = if location.parent_contiguous {
+                    Some(cluster + 1).filter(|&next| self.info.is_valid_cluster(next))
+                } else {
+                    self.next_cluster(cluster).await?
+                };
+                let next = next.ok_or(Error::ExFatInvalidEntry {
+                    reason: "entry set runs past the end of its directory",
+                })?;
+                offset = self.info.cluster_to_offset(next);
+            }
+            offsets.push(offset);
+            offset += size_of::<RawDirectoryEntry>() as u64;
+        }
+        Ok(offsets)
+    }
+
+    /// Read the complete entry set at `location`, with the slot offsets.
+    pub(crate) async fn read_entry_set(
+        &self,
+        location: EntrySetLocation,
+    ) -> Result<(Vec<RawDirectoryEntry>, Vec<u64>)> {
+        let primary = self.read_entry_at(location.offset).await?;
+        if unsafe { primary.entry_type } != entry_type::FILE_DIRECTORY {
+            return Err(Error::ExFatInvalidEntry {
+                reason: "expected a file directory entry",
+            });
+        }
+        let secondary_count = unsafe { primary.file.secondary_count } as usize;
+        if !(2..=18).contains(&secondary_count) {
+            return Err(Error::ExFatInvalidEntry {
+                reason: "entry set secondary count out of range",
+            });
+        }
+        let offsets = self.slot_offsets(location, 1 + secondary_count).await?;
+        let mut entries = Vec::with_capacity(offsets.len());
+        entries.push(primary);
+        for &offset in &offsets[1..] {
+            entries.push(self.read_entry_at(offset).await?);
+        }
+        Ok((entries, offsets))
+    }
+
+    /// First cluster, NoFatChain flag, and data length of the directory
+    /// whose entry set is at `location`, as currently on disk.
+    pub(crate) async fn dir_stream(&self, location: EntrySetLocation) -> Result<(u32, bool, u64)> {
+        let (entries, _) = self.read_entry_set(location).await?;
+        let stream = unsafe { &entries[1].stream };
+        if stream.entry_type != entry_type::STREAM_EXTENSION {
+            return Err(Error::ExFatInvalidEntry {
+                reason: "expected a stream extension entry",
+            });
+        }
+        Ok((
+            stream.first_cluster.get(),
+            stream.is_contiguous(),
+            stream.data_length.get(),
+        ))
+    }
+
     /// Compare two names using the up-case table (case-insensitive).
     pub(crate) fn names_equal(&self, name1: &str, name2: &str) -> Result<bool> {
         Ok(self.upcase.names_equal(name1, name2))
@@ -278,6 +459,16 @@ where
     pub fn volume_serial(&self) -> u32 {
         self.info.volume_serial
     }
+
+    /// Index of the active FAT and allocation bitmap (0 or 1).
+    pub fn active_fat(&self) -> u8 {
+        #[cfg(feature = "write")]
+        if self.transaction.lock().enabled {
+            // The working copy is the inactive one
+            return 1 - self.fat.working_copy();
+        }
+        self.fat.working_copy()
+    }
 }
 
 #[cfg(feature = "write")]
@@ -285,24 +476,134 @@ impl<DATA> ExFatVolume<DATA>
 where
     DATA: Read + Write + Seek,
 {
+    /// Open an exFAT filesystem in transaction-safe (TexFAT) mode.
+    ///
+    /// The volume must have two FATs and two allocation bitmaps (see
+    /// [`ExFatFormatOptions::fat_count`](super::ExFatFormatOptions::fat_count)).
+    /// Allocation changes are written to the inactive FAT and bitmap, and
+    /// [`sync_bitmap`](Self::sync_bitmap) commits them by flipping the
+    /// ActiveFat flag in VolumeFlags, a single sector write. A power loss
+    /// before the flip leaves the previous FAT and bitmap in charge.
+    ///
+    /// If VolumeDirty shows that an update was interrupted, the inactive
+    /// copies are overwritten with the active ones, rolling back whatever
+    /// reached them; [`recovered_transaction`](Self::recovered_transaction)
+    /// then reports `true`. Directory entries are written in place, ordered
+    /// so that an interrupted update can at worst leave clusters allocated
+    /// that no entry references.
+    pub async fn open_transaction_safe(data: DATA) -> Result<Self> {
+        let mut fs = Self::open(data).await?;
+        if fs.info.fat_count != 2 || fs.bitmap_copies.iter().any(Vec::is_empty) {
+            return Err(Error::ExFatInvalidBootSector {
+                reason: "transaction-safe mode needs two FATs and allocation bitmaps",
+            });
+        }
+
+        let active = fs.fat.working_copy();
+        let inactive = 1 - active;
+        let transaction = fs.transaction.get_mut();
+        let data = &mut fs.data.get_mut().data;
+        if transaction.volume_flags & VOLUME_FLAG_DIRTY != 0 {
+            fs.fat.mirror_all(data, active, inactive).await?;
+            fs.bitmap
+                .get_mut()
+                .flush_to(data, &fs.info, &fs.bitmap_copies[inactive as usize])
+                .await?;
+            data.flush().await?;
+            transaction.volume_flags &= !VOLUME_FLAG_DIRTY;
+            ExFatBootSector::write_volume_flags(data, transaction.volume_flags).await?;
+            data.flush().await?;
+            transaction.recovered = true;
+        }
+        fs.fat.shadow_into(inactive);
+        transaction.enabled = true;
+        Ok(fs)
+    }
+
+    /// Whether the volume was opened with
+    /// [`open_transaction_safe`](Self::open_transaction_safe).
+    pub fn is_transaction_safe(&self) -> bool {
+        self.transaction.lock().enabled
+    }
+
+    /// Whether opening the volume rolled back an interrupted transaction.
+    pub fn recovered_transaction(&self) -> bool {
+        self.transaction.lock().recovered
+    }
+
+    /// Mark the start of a metadata update on a transaction-safe volume by
+    /// setting VolumeDirty, before the first write to the inactive FAT.
+    async fn begin_update(&self) -> Result<()> {
+        let mut transaction = self.transaction.lock();
+        if !transaction.enabled || transaction.open {
+            return Ok(());
+        }
+        let mut data = self.data.lock();
+        transaction.volume_flags |= VOLUME_FLAG_DIRTY;
+        ExFatBootSector::write_volume_flags(&mut data.data, transaction.volume_flags).await?;
+        data.data.flush().await?;
+        transaction.open = true;
+        Ok(())
+    }
+
+    /// Commit a transaction-safe update.
+    ///
+    /// The bitmap is written to the inactive copy, then ActiveFat flips so
+    /// the updated FAT and bitmap take over. The copies that were active are
+    /// brought up to date afterwards and become the target of the next
+    /// update; VolumeDirty stays set until they are.
+    async fn commit_transaction(&self, transaction: &mut Transaction) -> Result<()> {
+        if !transaction.open {
+            return Ok(()); // Nothing changed since the last commit
+        }
+        let bitmap = self.bitmap.lock();
+        let mut guard = self.data.lock();
+        let data = &mut guard.data;
+        let working = self.fat.working_copy();
+        let previous = 1 - working;
+
+        bitmap
+            .flush_to(data, &self.info, &self.bitmap_copies[working as usize])
+            .await?;
+        data.flush().await?;
+
+        transaction.volume_flags ^= VOLUME_FLAG_ACTIVE_FAT;
+        ExFatBootSector::write_volume_flags(data, transaction.volume_flags).await?;
+        data.flush().await?;
+
+        self.fat.mirror_dirty(data, working, previous).await?;
+        bitmap
+            .flush_to(data, &self.info, &self.bitmap_copies[previous as usize])
+            .await?;
+        self.fat.shadow_into(previous);
+        data.flush().await?;
+
+        transaction.volume_flags &= !VOLUME_FLAG_DIRTY;
+        ExFatBootSector::write_volume_flags(data, transaction.volume_flags).await?;
+        data.flush().await?;
+        transaction.open = false;
+        Ok(())
+    }
+
     /// Write data at a specific offset.
-    pub(crate) fn write_at(&self, offset: u64, buf: &[u8]) -> Result<()> {
+    pub(crate) async fn write_at(&self, offset: u64, buf: &[u8]) -> Result<()> {
         let mut guard = self.data.lock();
-        guard.seek(SeekFrom::Start(offset))?;
-        guard.write_all(buf)?;
+        guard.seek(SeekFrom::Start(offset)).await?;
+        guard.write_all(buf).await?;
         Ok(())
     }
 
     /// Flush any pending writes.
-    pub(crate) fn flush(&self) -> crate::io::IoResult<()> {
+    pub(crate) async fn flush(&self) -> super::super::io::IoResult<()> {
         let mut guard = self.data.lock();
-        guard.flush().map_err(hadris_io::Error::erase)
+        guard.flush().await.map_err(hadris_io::Error::erase)
     }
 
     /// Allocate a single cluster.
     ///
     /// The cluster is marked as allocated in the bitmap and as end-of-chain in the FAT.
-    pub fn allocate_cluster(&self, hint: u32) -> Result<u32> {
+    pub async fn allocate_cluster(&self, hint: u32) -> Result<u32> {
+        self.begin_update().await?;
         let mut bitmap = self.bitmap.lock();
         let cluster = bitmap.find_free_cluster(hint)?.ok_or(Error::NoFreeSpace)?;
 
@@ -312,20 +613,29 @@ where
         // Mark as end-of-chain in FAT
         let mut data = self.data.lock();
         self.fat
-            .write_entry(&mut data.data, cluster, ExFatTable::END_OF_CHAIN)?;
+            .write_entry(&mut data.data, cluster, ExFatTable::END_OF_CHAIN)
+            .await?;
 
         Ok(cluster)
     }
 
+    /// Point the FAT entry of `cluster` at `next`.
+    pub(crate) async fn link_cluster(&self, cluster: u32, next: u32) -> Result<()> {
+        self.begin_update().await?;
+        let mut data = self.data.lock();
+        self.fat.write_entry(&mut data.data, cluster, next).await
+    }
+
     /// Allocate contiguous clusters for an exFAT file.
     ///
     /// Returns (first_cluster, is_contiguous). If contiguous allocation fails,
     /// falls back to FAT chain allocation.
-    pub fn allocate_clusters(&self, count: u32, hint: u32) -> Result<(u32, bool)> {
+    pub async fn allocate_clusters(&self, count: u32, hint: u32) -> Result<(u32, bool)> {
         if count == 0 {
             return Ok((0, true));
         }
 
+        self.begin_update().await?;
         let mut bitmap = self.bitmap.lock();
 
         // Try to allocate contiguously first
@@ -341,7 +651,7 @@ where
         drop(bitmap); // Release bitmap lock before FAT operations
 
         let mut data = self.data.lock();
-        let first = self.fat.allocate_chain(&mut data.data, count, hint)?;
+        let first = self.fat.allocate_chain(&mut data.data, count, hint).await?;
 
         // Also mark in bitmap
         drop(data);
@@ -350,7 +660,7 @@ where
         let mut data = self.data.lock();
         for _ in 0..count {
             bitmap.set_allocated(current, true)?;
-            if let Some(next) = self.fat.next_cluster(&mut data.data, current)? {
+            if let Some(next) = self.fat.next_cluster(&mut data.data, current).await? {
                 current = next;
             } else {
                 break;
@@ -364,11 +674,12 @@ where
     ///
     /// If `is_contiguous` is true, frees `count` contiguous clusters starting from `first`.
     /// Otherwise, follows the FAT chain to free all clusters.
-    pub fn free_clusters(&self, first: u32, count: u32, is_contiguous: bool) -> Result<()> {
+    pub async fn free_clusters(&self, first: u32, count: u32, is_contiguous: bool) -> Result<()> {
         if first < 2 {
             return Ok(());
         }
 
+        self.begin_update().await?;
         let mut bitmap = self.bitmap.lock();
 
         if is_contiguous {
@@ -383,9 +694,10 @@ where
 
             loop {
                 bitmap.set_allocated(current, false)?;
-                let next = self.fat.read_entry(&mut data.data, current)?;
+                let next = self.fat.read_entry(&mut data.data, current).await?;
                 self.fat
-                    .write_entry(&mut data.data, current, ExFatTable::FREE_CLUSTER)?;
+                    .write_entry(&mut data.data, current, ExFatTable::FREE_CLUSTER)
+                    .await?;
 
                 if next == ExFatTable::END_OF_CHAIN || next >= ExFatTable::MEDIA_DESCRIPTOR {
                     break;
@@ -398,111 +710,219 @@ where
     }
 
     /// Sync the allocation bitmap to disk.
-    pub fn sync_bitmap(&self) -> Result<()> {
+    ///
+    /// Every allocation bitmap is updated in place. On a transaction-safe
+    /// volume this commits the pending update instead; see
+    /// [`open_transaction_safe`](Self::open_transaction_safe).
+    pub async fn sync_bitmap(&self) -> Result<()> {
+        let mut transaction = self.transaction.lock();
+        if transaction.enabled {
+            return self.commit_transaction(&mut transaction).await;
+        }
+        drop(transaction);
+
         let bitmap = self.bitmap.lock();
         let mut data = self.data.lock();
-        bitmap.flush(&mut data.data, &self.info)
+        for clusters in self.bitmap_copies.iter().filter(|copy| !copy.is_empty()) {
+            bitmap
+                .flush_to(&mut data.data, &self.info, clusters)
+                .await?;
+        }
+        Ok(())
     }
 
-    /// Find free entry slots in a directory.
+    /// The directory `dir` as it is on disk now.
     ///
-    /// Returns (cluster, offset_within_cluster) for the first slot.
-    fn find_free_entry_slots(
+    /// A handle opened before the directory grew carries a stale size and
+    /// chain flag; the entry set is the source of truth.
+    async fn current_dir<'a>(&'a self, dir: &ExFatDir<'_, DATA>) -> Result<ExFatDir<'a, DATA>> {
+        let Some(location) = dir.location else {
+            return Ok(self.root_dir());
+        };
+        let (first_cluster, is_contiguous, size) = self.dir_stream(location).await?;
+        Ok(ExFatDir {
+            fs: self,
+            first_cluster,
+            is_contiguous,
+            size,
+            location: Some(location),
+        })
+    }
+
+    /// Find `slots_needed` consecutive unused slots in a directory, growing
+    /// it a cluster at a time once its existing slots run out.
+    ///
+    /// Returns the absolute offset of each slot (the run may continue from
+    /// one cluster into the next) and whether the directory is contiguous
+    /// afterwards.
+    async fn find_free_entry_slots(
         &self,
         dir: &ExFatDir<'_, DATA>,
         slots_needed: usize,
-    ) -> Result<(u32, u64)> {
+    ) -> Result<(Vec<u64>, bool)> {
         let cluster_size = self.info.bytes_per_cluster;
+        let entry_size = size_of::<RawDirectoryEntry>();
+        let mut dir = self.current_dir(dir).await?;
+        let mut run = Vec::with_capacity(slots_needed);
+        let mut buf = alloc::vec![0u8; cluster_size];
         let mut current_cluster = dir.first_cluster;
-        let mut consecutive_free = 0;
-        let mut first_free_cluster = current_cluster;
-        let mut first_free_offset = 0u64;
+        let mut clusters_scanned = 0u32;
 
         loop {
             let cluster_offset = self.info.cluster_to_offset(current_cluster);
-
-            // Scan this cluster for free entries
-            for entry_idx in 0..(cluster_size / 32) {
-                let offset = cluster_offset + (entry_idx as u64 * 32);
-                let entry = self.read_entry_at(offset)?;
-                let entry_type_byte = unsafe { entry.entry_type };
-
-                // Check if this is a free entry (0x00 = end, 0x05 = deleted)
-                if entry_type_byte == entry_type::END_OF_DIRECTORY
-                    || entry_type_byte == entry_type::DELETED_FILE
-                    || entry_type_byte == 0x00
-                {
-                    if consecutive_free == 0 {
-                        first_free_cluster = current_cluster;
-                        first_free_offset = entry_idx as u64 * 32;
-                    }
-                    consecutive_free += 1;
-
-                    if consecutive_free >= slots_needed {
-                        return Ok((first_free_cluster, first_free_offset));
+            self.read_at(cluster_offset, &mut buf).await?;
+            for (index, slot) in buf.chunks_exact(entry_size).enumerate() {
+                // Unused slots (end of directory, deleted entries) have the
+                // InUse bit clear.
+                if slot[0] & 0x80 == 0 {
+                    run.push(cluster_offset + (index * entry_size) as u64);
+                    if run.len() == slots_needed {
+                        return Ok((run, dir.is_contiguous));
                     }
                 } else {
-                    consecutive_free = 0;
+                    run.clear();
                 }
             }
 
-            // Move to next cluster
-            if dir.is_contiguous {
-                current_cluster += 1;
-                // Check if we've exceeded the directory size
-                if (current_cluster - dir.first_cluster) as u64 * cluster_size as u64 >= dir.size
-                    && dir.size > 0
-                {
-                    break;
+            clusters_scanned += 1;
+            let next = if dir.is_contiguous {
+                let scanned = clusters_scanned as u64 * cluster_size as u64;
+                (scanned < dir.size).then_some(dir.first_cluster + clusters_scanned)
+            } else {
+                if clusters_scanned > self.info.cluster_count {
+                    return Err(Error::ClusterLoop {
+                        cluster: current_cluster,
+                    });
+                }
+                self.next_cluster(current_cluster).await?
+            };
+            current_cluster = match next {
+                Some(next) => next,
+                None => {
+                    let (grown, new_cluster) = self.grow_dir(&dir, current_cluster).await?;
+                    dir = grown;
+                    new_cluster
                 }
+            };
+        }
+    }
+
+    /// Append a zeroed cluster to `dir`, whose last cluster is `last`.
+    ///
+    /// A contiguous directory takes the adjacent cluster when it is free.
+    /// Otherwise its existing clusters are linked in the FAT and it becomes a
+    /// FAT chain. The directory's stream extension records the new size (the
+    /// root has none). Returns the grown directory and the new cluster.
+    async fn grow_dir<'a>(
+        &'a self,
+        dir: &ExFatDir<'_, DATA>,
+        last: u32,
+    ) -> Result<(ExFatDir<'a, DATA>, u32)> {
+        let cluster_size = self.info.bytes_per_cluster;
+        let new_size = dir.size + cluster_size as u64;
+        if dir.location.is_some() && new_size > MAX_DIRECTORY_SIZE {
+            return Err(Error::DirectoryFull);
+        }
+
+        let adjacent = last + 1;
+        self.begin_update().await?;
+        let extend_in_place = dir.is_contiguous && {
+            let mut bitmap = self.bitmap.lock();
+            if self.info.is_valid_cluster(adjacent) && !bitmap.is_allocated(adjacent)? {
+                bitmap.set_allocated(adjacent, true)?;
+                true
             } else {
-                match self.next_cluster(current_cluster)? {
-                    Some(next) => current_cluster = next,
-                    None => break,
+                false
+            }
+        };
+        let new_cluster = if extend_in_place {
+            adjacent
+        } else {
+            self.allocate_cluster(adjacent).await?
+        };
+        // Zero the cluster before it becomes reachable, so it reads as the
+        // end of the directory.
+        let zeros = alloc::vec![0u8; cluster_size];
+        self.write_at(self.info.cluster_to_offset(new_cluster), &zeros)
+            .await?;
+
+        let is_contiguous = extend_in_place;
+        if !extend_in_place {
+            let mut data = self.data.lock();
+            if dir.is_contiguous {
+                // NoFatChain directories have no FAT links; describe the
+                // existing run before dropping the flag.
+                for cluster in dir.first_cluster..last {
+                    self.fat
+                        .write_entry(&mut data.data, cluster, cluster + 1)
+                        .await?;
                 }
             }
+            self.fat
+                .write_entry(&mut data.data, last, new_cluster)
+                .await?;
+        }
+        // Commit the allocation before the stream extension records it
+        self.sync_bitmap().await?;
+        if let Some(location) = dir.location {
+            self.update_entry_size(
+                location,
+                new_size,
+                new_size,
+                dir.first_cluster,
+                is_contiguous,
+            )
+            .await?;
         }
 
-        // Need to extend the directory
-        // For now, return an error - directory extension is more complex
-        Err(Error::DirectoryFull)
+        Ok((
+            ExFatDir {
+                fs: self,
+                first_cluster: dir.first_cluster,
+                is_contiguous,
+                size: new_size,
+                location: dir.location,
+            },
+            new_cluster,
+        ))
     }
 
-    /// Write a directory entry set to disk.
-    fn write_entry_set(
-        &self,
-        cluster: u32,
-        offset_in_cluster: u64,
-        entries: &[RawDirectoryEntry],
-    ) -> Result<()> {
-        let cluster_offset = self.info.cluster_to_offset(cluster);
-        let base_offset = cluster_offset + offset_in_cluster;
-
-        for (i, entry) in entries.iter().enumerate() {
-            let entry_offset = base_offset + (i as u64 * 32);
-            self.write_at(entry_offset, unsafe { &entry.bytes })?;
+    /// Write a directory entry set to the slots at `offsets`.
+    ///
+    /// The primary entry goes last, so an interrupted write leaves unused
+    /// slots rather than a partial entry set.
+    async fn write_entry_set(&self, offsets: &[u64], entries: &[RawDirectoryEntry]) -> Result<()> {
+        for (entry, &offset) in entries.iter().zip(offsets).skip(1) {
+            self.write_at(offset, unsafe { &entry.bytes }).await?;
         }
-
-        Ok(())
+        self.write_at(offsets[0], unsafe { &entries[0].bytes })
+            .await
     }
 
     /// Create a new file in the given directory.
-    pub fn create_file(&self, parent: &ExFatDir<'_, DATA>, name: &str) -> Result<ExFatFileEntry> {
+    ///
+    /// The directory grows as needed, and the new entry set may span a
+    /// cluster boundary.
+    pub async fn create_file(
+        &self,
+        parent: &ExFatDir<'_, DATA>,
+        name: &str,
+    ) -> Result<ExFatFileEntry> {
         // Check if entry already exists
-        if parent.find(name)?.is_some() {
+        if parent.find(name).await?.is_some() {
             return Err(Error::AlreadyExists);
         }
 
         // Build the entry set
         let builder = EntrySetBuilder::file(name)?;
         let entries = builder.build(&self.upcase);
-        let entry_count = entries.len();
 
-        // Find free slots in the directory
-        let (slot_cluster, slot_offset) = self.find_free_entry_slots(parent, entry_count)?;
+        // Find free slots in the directory, growing it if needed
+        let (offsets, parent_contiguous) =
+            self.find_free_entry_slots(parent, entries.len()).await?;
 
         // Write the entry set
-        self.write_entry_set(slot_cluster, slot_offset, &entries)?;
+        self.write_entry_set(&offsets, &entries).await?;
 
         // Return the new entry
         let now = super::time::ExFatTimestamp::now();
@@ -517,87 +937,102 @@ where
             created: now,
             modified: now,
             accessed: now,
-            parent_cluster: slot_cluster,
-            entry_offset: self.info.cluster_to_offset(slot_cluster) + slot_offset,
+            parent_cluster: parent.first_cluster,
+            entry_offset: offsets[0],
+            parent_contiguous,
         })
     }
 
     /// Create a new directory.
     ///
     /// Note: Unlike FAT, exFAT directories don't have . and .. entries.
-    pub fn create_dir(
+    pub async fn create_dir(
         &self,
         parent: &ExFatDir<'_, DATA>,
         name: &str,
     ) -> Result<ExFatDir<'_, DATA>> {
         // Check if entry already exists
-        if parent.find(name)?.is_some() {
+        if parent.find(name).await?.is_some() {
             return Err(Error::AlreadyExists);
         }
 
         // Allocate a cluster for the directory contents
-        let dir_cluster = self.allocate_cluster(2)?;
+        let dir_cluster = self.allocate_cluster(2).await?;
 
         // Zero out the directory cluster
         let cluster_offset = self.info.cluster_to_offset(dir_cluster);
         let zeros = alloc::vec![0u8; self.info.bytes_per_cluster];
-        self.write_at(cluster_offset, &zeros)?;
+        self.write_at(cluster_offset, &zeros).await?;
 
         // Build the entry set
         let builder = EntrySetBuilder::directory(name)?
             .with_cluster(dir_cluster)
-            .with_size(0, self.info.bytes_per_cluster as u64)
+            .with_size(
+                self.info.bytes_per_cluster as u64,
+                self.info.bytes_per_cluster as u64,
+            )
             .with_contiguous(true);
         let entries = builder.build(&self.upcase);
-        let entry_count = entries.len();
 
-        // Find free slots in parent directory
-        let (slot_cluster, slot_offset) = self.find_free_entry_slots(parent, entry_count)?;
+        // Find free slots in parent directory, growing it if needed
+        let (offsets, parent_contiguous) =
+            self.find_free_entry_slots(parent, entries.len()).await?;
 
-        // Write the entry set
-        self.write_entry_set(slot_cluster, slot_offset, &entries)?;
+        // Commit the allocation, then write the entry set that references it
+        self.sync_bitmap().await?;
+        self.write_entry_set(&offsets, &entries).await?;
 
         Ok(ExFatDir {
             fs: self,
             first_cluster: dir_cluster,
             is_contiguous: true,
             size: self.info.bytes_per_cluster as u64,
+            location: Some(EntrySetLocation {
+                offset: offsets[0],
+                parent_contiguous,
+            }),
         })
     }
 
     /// Delete a file or empty directory.
-    pub fn delete(&self, entry: &ExFatFileEntry) -> Result<()> {
+    pub async fn delete(&self, entry: &ExFatFileEntry) -> Result<()> {
         // If it's a directory, check if it's empty
         if entry.is_directory() {
-            let dir = ExFatDir {
-                fs: self,
-                first_cluster: entry.first_cluster,
-                is_contiguous: entry.no_fat_chain,
-                size: entry.data_length,
-            };
+            let dir = ExFatDir::from_entry(self, entry);
 
             // Check for any entries in the directory
-            if let Some(item) = dir.entries().next() {
+            if let Some(item) = dir.entries().next_entry().await {
                 let _ = item?;
                 return Err(Error::DirectoryNotEmpty);
             }
         }
 
+        let (entries, offsets) = self.read_entry_set(entry.location()).await?;
+        // The stream extension on disk is current even if `entry` is stale
+        let stream = unsafe { &entries[1].stream };
+        let data_length = stream.data_length.get();
+        let first_cluster = stream.first_cluster.get();
+        let no_fat_chain = stream.general_secondary_flags & 0x02 != 0;
+
         // Free the cluster chain if there is one
-        if entry.first_cluster >= 2 {
-            let cluster_count = if entry.no_fat_chain {
+        if first_cluster >= 2 {
+            let cluster_count = if no_fat_chain {
                 let cluster_size = self.info.bytes_per_cluster as u64;
-                entry.data_length.div_ceil(cluster_size) as u32
+                data_length.div_ceil(cluster_size) as u32
             } else {
                 0 // Will follow FAT chain
             };
 
-            self.free_clusters(entry.first_cluster, cluster_count, entry.no_fat_chain)?;
+            self.free_clusters(first_cluster, cluster_count, no_fat_chain)
+                .await?;
         }
 
-        // Mark the directory entry as deleted (0x05)
-        let deleted_marker = [entry_type::DELETED_FILE];
-        self.write_at(entry.entry_offset, &deleted_marker)?;
+        // Clear the InUse bit of every entry in the set (0x85 becomes 0x05)
+        for (raw, &offset) in entries.iter().zip(&offsets) {
+            let entry_type = unsafe { raw.entry_type };
+            self.write_at(offset, &[entry_type & !0x80]).await?;
+        }
+        self.sync_bitmap().await?;
 
         Ok(())
     }
@@ -612,9 +1047,9 @@ where
     /// Reads the entry set from disk, updates the stream extension's
     /// `valid_data_length`, `data_length`, and `first_cluster` fields,
     /// recalculates the entry set checksum, and writes everything back.
-    pub(crate) fn update_entry_size(
+    pub(crate) async fn update_entry_size(
         &self,
-        entry: &ExFatFileEntry,
+        location: EntrySetLocation,
         new_valid_data_length: u64,
         new_data_length: u64,
         new_first_cluster: u32,
@@ -624,58 +1059,37 @@ where
         use hadris_common::types::endian::LittleEndian;
         use hadris_common::types::number::{U16, U32, U64};
 
-        let mut guard = self.data.lock();
-
-        // Read the primary (File Directory) entry to get secondary_count
-        guard.seek(SeekFrom::Start(entry.entry_offset))?;
-        let primary: RawDirectoryEntry = guard.data.read_struct()?;
-        let secondary_count = unsafe { primary.file.secondary_count } as usize;
-
-        // Read the full entry set (primary + secondaries)
-        let entry_count = 1 + secondary_count;
-        let mut entries = Vec::with_capacity(entry_count);
-        entries.push(primary);
-
-        for i in 1..entry_count {
-            let offset = entry.entry_offset + (i as u64 * 32);
-            guard.seek(SeekFrom::Start(offset))?;
-            let e: RawDirectoryEntry = guard.data.read_struct()?;
-            entries.push(e);
-        }
+        let (mut entries, offsets) = self.read_entry_set(location).await?;
 
         // Update the stream extension entry (second entry, index 1)
-        if entry_count >= 2 {
-            let stream = unsafe { &mut entries[1].stream };
-            // Keep general_secondary_flags consistent with the new allocation
-            // state: AllocationPossible (bit 0) must be 0 when there are no
-            // clusters, and NoFatChain (bit 1) requires AllocationPossible=1.
-            let has_allocation = new_data_length > 0 || new_first_cluster != 0;
-            stream.general_secondary_flags = if has_allocation {
-                0x01 | if no_fat_chain { 0x02 } else { 0x00 }
-            } else {
-                0
-            };
-            stream.valid_data_length = U64::<LittleEndian>::new(new_valid_data_length);
-            stream.data_length = U64::<LittleEndian>::new(new_data_length);
-            stream.first_cluster = U32::<LittleEndian>::new(new_first_cluster);
-        }
+        let stream = unsafe { &mut entries[1].stream };
+        // Keep general_secondary_flags consistent with the new allocation
+        // state: AllocationPossible (bit 0) must be 0 when there are no
+        // clusters, and NoFatChain (bit 1) requires AllocationPossible=1.
+        let has_allocation = new_data_length > 0 || new_first_cluster != 0;
+        stream.general_secondary_flags = if has_allocation {
+            0x01 | if no_fat_chain { 0x02 } else { 0x00 }
+        } else {
+            0
+        };
+        stream.valid_data_length = U64::<LittleEndian>::new(new_valid_data_length);
+        stream.data_length = U64::<LittleEndian>::new(new_data_length);
+        stream.first_cluster = U32::<LittleEndian>::new(new_first_cluster);
 
         // Recalculate checksum and update primary entry
         let checksum = compute_entry_set_checksum(&entries);
         entries[0].file.set_checksum = U16::<LittleEndian>::new(checksum);
 
         // Write the updated entry set back
-        for (i, e) in entries.iter().enumerate() {
-            let offset = entry.entry_offset + (i as u64 * 32);
-            guard.seek(SeekFrom::Start(offset))?;
-            guard.write_all(unsafe { &e.bytes })?;
+        for (e, &offset) in entries.iter().zip(&offsets) {
+            self.write_at(offset, unsafe { &e.bytes }).await?;
         }
 
         Ok(())
     }
 
     /// Truncate a file to the specified size.
-    pub fn truncate(&self, entry: &ExFatFileEntry, new_size: u64) -> Result<()> {
+    pub async fn truncate(&self, entry: &ExFatFileEntry, new_size: u64) -> Result<()> {
         if entry.is_directory() {
             return Err(Error::NotAFile);
         }
@@ -683,6 +1097,7 @@ where
         if new_size >= entry.valid_data_length {
             return Ok(()); // Nothing to do
         }
+        self.begin_update().await?;
 
         let cluster_size = self.info.bytes_per_cluster as u64;
 
@@ -694,9 +1109,11 @@ where
                 } else {
                     0
                 };
-                self.free_clusters(entry.first_cluster, cluster_count, entry.no_fat_chain)?;
+                self.free_clusters(entry.first_cluster, cluster_count, entry.no_fat_chain)
+                    .await?;
             }
-            self.update_entry_size(entry, 0, 0, 0, false)?;
+            self.update_entry_size(entry.location(), 0, 0, 0, false)
+                .await?;
         } else {
             // Calculate clusters to keep
             let clusters_to_keep = new_size.div_ceil(cluster_size);
@@ -709,14 +1126,15 @@ where
 
                 if clusters_to_free > 0 {
                     let first_to_free = entry.first_cluster + clusters_to_keep as u32;
-                    self.free_clusters(first_to_free, clusters_to_free as u32, true)?;
+                    self.free_clusters(first_to_free, clusters_to_free as u32, true)
+                        .await?;
                 }
             } else {
                 // For fragmented files, walk the chain and truncate
                 let mut current = entry.first_cluster;
                 for _ in 1..clusters_to_keep {
                     let mut data = self.data.lock();
-                    if let Some(next) = self.fat.next_cluster(&mut data.data, current)? {
+                    if let Some(next) = self.fat.next_cluster(&mut data.data, current).await? {
                         current = next;
                     } else {
                         break;
@@ -725,18 +1143,27 @@ where
 
                 // Truncate after this cluster
                 let mut data = self.data.lock();
-                self.fat.truncate_chain(&mut data.data, current)?;
+                self.fat.truncate_chain(&mut data.data, current).await?;
             }
             self.update_entry_size(
-                entry,
+                entry.location(),
                 new_size,
                 new_data_length,
                 entry.first_cluster,
                 entry.no_fat_chain,
-            )?;
+            )
+            .await?;
         }
 
-        Ok(())
+        // The entry no longer references the freed clusters; commit
+        self.sync_bitmap().await
+    }
+}
+
+impl<DATA: Seek> ExFatVolume<DATA> {
+    /// Consumes the filesystem handle and returns its underlying data source.
+    pub fn into_inner(self) -> DATA {
+        self.data.into_inner().data
     }
 }
 
@@ -748,3 +1175,5 @@ impl<DATA: Seek> core::fmt::Debug for ExFatVolume<DATA> {
             .finish_non_exhaustive()
     }
 }
+
+} // end io_transform!
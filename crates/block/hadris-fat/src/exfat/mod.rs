@@ -17,13 +17,27 @@
 //! basic formatting, reading, traversal, and simple mutation on conventional
 //! layouts, but should not be used with irreplaceable data.
 //!
-//! The preview is synchronous and allocation-backed. Fragmented allocation
-//! bitmap and up-case metadata, directory growth, general cross-cluster
-//! directory entry-set placement, TexFAT, and repair workflows are not
-//! supported. The `hadris-block` facade reports
-//! `detect::FatVariant::ExFat` for format detection, but its stable
-//! block-volume opener intentionally rejects exFAT until this implementation
-//! is release-qualified.
+//! Like the FAT API, the preview is compiled into each enabled I/O mode:
+//! `sync::exfat` uses the blocking traits and `async::exfat` the async ones,
+//! with the same types and an `.await` on every operation that touches the
+//! device. In the async API, [`ExFatDirIter::next_entry`] replaces the
+//! `Iterator` implementation.
+//!
+//! The preview is allocation-backed. Directories grow a
+//! cluster at a time as entries are added, and entry sets may span cluster
+//! boundaries. The allocation bitmap and up-case table are read along their
+//! FAT chains, so volumes that scatter them across the heap open normally.
+//! The formatter handles multi-terabyte volumes and can follow the SD Card
+//! Association's cluster-size and alignment recommendations.
+//!
+//! Volumes formatted with two FATs carry two allocation bitmaps and can be
+//! opened with [`ExFatVolume::open_transaction_safe`]. Updates then go to the
+//! inactive FAT and bitmap, and each commit flips `ActiveFat` in the boot
+//! sector, so an interrupted update leaves the last committed state intact.
+//! Opening a volume left with `VolumeDirty` set rolls the inactive copy back.
+//! Repair workflows are not supported. The `hadris-block` facade detects
+//! exFAT, and its `exfat` feature lets `OpenVolume` open it as
+//! `OpenVolume::ExFat`.
 
 mod bitmap;
 mod boot;
@@ -40,7 +54,7 @@ mod time;
 mod upcase;
 
 pub use bitmap::AllocationBitmap;
-pub use boot::{ExFatBootSector, ExFatInfo};
+pub use boot::{ExFatBootSector, ExFatInfo, VOLUME_FLAG_ACTIVE_FAT, VOLUME_FLAG_DIRTY};
 pub use dir::{ExFatDir, ExFatDirIter};
 pub use entry::{
     ExFatFileEntry, FileAttributes, RawFileDirectoryEntry, RawFileNameEntry,
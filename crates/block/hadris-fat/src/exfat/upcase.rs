@@ -3,13 +3,16 @@
 //! The up-case table is used for case-insensitive filename comparisons.
 //! It maps Unicode code points to their uppercase equivalents.
 
+io_transform! {
+
 use alloc::vec;
 use alloc::vec::Vec;
 
+use super::super::io::{Read, Seek, SeekFrom};
 use crate::error::{Error, Result};
-use crate::io::{Read, Seek, SeekFrom};
 
 use super::ExFatInfo;
+use super::fat::ExFatTable;
 
 /// Up-case table for case-insensitive filename matching.
 ///
@@ -42,7 +45,7 @@ impl UpcaseTable {
     /// * `first_cluster` - First cluster of the up-case table
     /// * `size` - Size of the table in bytes
     /// * `is_contiguous` - Whether the table is stored contiguously
-    pub fn load<DATA: Read + Seek>(
+    pub async fn load<DATA: Read + Seek>(
         &mut self,
         data: &mut DATA,
         info: &ExFatInfo,
@@ -51,10 +54,11 @@ impl UpcaseTable {
         is_contiguous: bool,
     ) -> Result<()> {
         self.load_impl(data, info, first_cluster, size, is_contiguous, None)
+            .await
     }
 
     /// Load an up-case table and validate it against its directory-entry checksum.
-    pub fn load_checked<DATA: Read + Seek>(
+    pub async fn load_checked<DATA: Read + Seek>(
         &mut self,
         data: &mut DATA,
         info: &ExFatInfo,
@@ -71,9 +75,10 @@ impl UpcaseTable {
             is_contiguous,
             Some(expected_checksum),
         )
+        .await
     }
 
-    fn load_impl<DATA: Read + Seek>(
+    async fn load_impl<DATA: Read + Seek>(
         &mut self,
         data: &mut DATA,
         info: &ExFatInfo,
@@ -96,17 +101,16 @@ impl UpcaseTable {
         // Read the raw table data
         let mut raw_data = vec![0u8; size as usize];
 
-        if is_contiguous {
-            // Read contiguous data
-            let offset = info.cluster_to_offset(first_cluster);
-            data.seek(SeekFrom::Start(offset))?;
-            data.read_exact(&mut raw_data)?;
-        } else {
-            // Fragmented upcase tables require following the FAT chain, which is not yet
-            // implemented. Return an error rather than silently reading wrong data.
-            return Err(Error::UnsupportedFatType(
-                "fragmented exFAT upcase table not yet supported",
-            ));
+        // A fragmented table is read cluster by cluster along its FAT chain.
+        let cluster_size = info.bytes_per_cluster;
+        let count = size.div_ceil(cluster_size as u64) as u32;
+        let clusters = ExFatTable::new(info)
+            .chain_clusters(data, first_cluster, count, is_contiguous)
+            .await?;
+        for (chunk, &cluster) in raw_data.chunks_mut(cluster_size).zip(&clusters) {
+            data.seek(SeekFrom::Start(info.cluster_to_offset(cluster)))
+                .await?;
+            data.read_exact(chunk).await?;
         }
 
         if let Some(expected) = expected_checksum {
@@ -312,6 +316,8 @@ pub fn generate_compressed_upcase_table() -> (Vec<u8>, u32) {
     (data, checksum)
 }
 
+} // end io_transform!
+
 #[cfg(test)]
 mod tests {
     use super::*;
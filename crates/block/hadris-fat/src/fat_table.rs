@@ -414,6 +414,77 @@ impl Fat {
             Err(e) => Err(e),
         }
     }
+
+    /// `(start_byte, size_per_copy, copy_count)` of the on-disk tables.
+    #[cfg(feature = "write")]
+    pub(crate) fn layout(&self) -> (usize, usize, usize) {
+        match self {
+            Self::Fat12(f) => (f.start, f.size, f.count),
+            Self::Fat16(f) => (f.start, f.size, f.count),
+            Self::Fat32(f) => (f.start, f.size, f.count),
+        }
+    }
+
+    /// A descriptor of the same type and start, with a new per-copy size and
+    /// cluster bound. Used by volume resizing, which first allocates against
+    /// the shrunk bound and then remounts with the final geometry.
+    #[cfg(feature = "write")]
+    pub(crate) fn with_geometry(&self, size: usize, max_cluster: u32) -> Self {
+        match self {
+            Self::Fat12(f) => Self::Fat12(Fat12::new(f.start, size, f.count, max_cluster as u16)),
+            Self::Fat16(f) => Self::Fat16(Fat16::new(f.start, size, f.count, max_cluster as u16)),
+            Self::Fat32(f) => Self::Fat32(Fat32::new(f.start, size, f.count, max_cluster)),
+        }
+    }
+
+    /// Read the raw entry for `cluster` from the primary copy, with the
+    /// FAT32 reserved high nibble masked off.
+    #[cfg(feature = "write")]
+    pub(crate) async fn read_entry<T: Read + Seek>(&self, reader: &mut T, cluster: usize) -> Result<u32> {
+        match self {
+            Self::Fat12(f) => Ok((f.read_clus(reader, cluster).await? & Fat12::ENTRY_MASK) as u32),
+            Self::Fat16(f) => Ok(f.read_clus(reader, cluster).await? as u32),
+            Self::Fat32(f) => Ok(f.read_clus(reader, cluster).await? & Fat32::ENTRY_MASK),
+        }
+    }
+
+    /// Write `value` to the entry for `cluster` in every FAT copy.
+    #[cfg(feature = "write")]
+    pub(crate) async fn write_entry<T: Read + Write + Seek>(
+        &self,
+        rw: &mut T,
+        cluster: usize,
+        value: u32,
+    ) -> Result<()> {
+        match self {
+            Self::Fat12(f) => f.write_clus(rw, cluster, value as u16).await,
+            Self::Fat16(f) => f.write_clus(rw, cluster, value as u16).await,
+            Self::Fat32(f) => f.write_clus(rw, cluster, value).await,
+        }
+    }
+
+    /// Allocate a single cluster at or after `hint`, wrapping around to the
+    /// start of the data region.
+    #[cfg(feature = "write")]
+    pub(crate) async fn allocate_cluster<T: Read + Write + Seek>(&self, rw: &mut T, hint: u32) -> Result<u32> {
+        match self {
+            Self::Fat12(f) => f.allocate_cluster(rw, hint as u16).await.map(u32::from),
+            Self::Fat16(f) => f.allocate_cluster(rw, hint as u16).await.map(u32::from),
+            Self::Fat32(f) => f.allocate_cluster(rw, hint).await,
+        }
+    }
+
+    /// Whether a masked entry value marks its cluster as in use: neither
+    /// free nor the bad-cluster marker.
+    #[cfg(feature = "write")]
+    pub(crate) fn is_allocated(&self, value: u32) -> bool {
+        let bad = match self {
+            Self::Fat12(_) => Fat12::BAD_CLUSTER as u32,
+            Self::Fat16(_) => Fat16::BAD_CLUSTER as u32,
+            Self::Fat32(_) => Fat32::BAD_CLUSTER,
+        };
+        value != 0 && value != bad
+    }
 }
 
 /// FAT filesystem type
@@ -283,7 +283,7 @@ fn calculate_sectors_per_cluster(volume_size: u64, fat_type: FatType, sector_siz
 }
 
 /// Calculate the number of sectors per FAT and total cluster count.
-fn calculate_fat_size(
+pub(crate) fn calculate_fat_size(
     fat_type: FatType,
     total_sectors: u32,
     reserved_sectors: u32,
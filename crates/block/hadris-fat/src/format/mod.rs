@@ -30,7 +30,7 @@
 
 io_transform! {
 
-mod calc;
+pub(crate) mod calc;
 mod init;
 mod options;
 
@@ -89,9 +89,9 @@ pub(crate) struct FatInfo {
 #[derive(Debug)]
 pub(crate) struct Fat12_16FsExt {
     /// Root directory start byte offset
-    root_dir_start: usize,
+    pub(crate) root_dir_start: usize,
     /// Root directory size in bytes
-    root_dir_size: usize,
+    pub(crate) root_dir_size: usize,
 }
 
 #[derive(Debug)]
@@ -119,7 +119,7 @@ pub(crate) struct Fat32FsExt {
     /// Sector number of the FSInfo structure
     pub(crate) fs_info_sec: Sector<u16>,
     /// Root directory cluster
-    root_clus: Cluster<u32>,
+    pub(crate) root_clus: Cluster<u32>,
     /// Number of free clusters (from FSInfo, may be stale)
     pub(crate) free_count: Cell<u32>,
     /// Hint for next free cluster (from FSInfo)
@@ -157,6 +157,9 @@ pub struct FatVolume<DATA: Seek> {
     /// shared between read paths and write paths.
     #[cfg(feature = "cache")]
     pub(crate) fat_cache: Option<Mutex<crate::cache::FatSectorCache>>,
+    /// Write-ahead journal state; see [`super::journal`].
+    #[cfg(feature = "write")]
+    pub(crate) journal: Mutex<super::journal::JournalState>,
 }
 
 impl<DATA: Seek> FatVolume<DATA> {
@@ -367,13 +370,22 @@ where
         let root_entry_count = u16::from_le_bytes(bpb.root_entry_count);
         let sectors_per_fat_16 = u16::from_le_bytes(bpb.sectors_per_fat_16);
 
-        if root_entry_count == 0 && sectors_per_fat_16 == 0 {
+        let fs = if root_entry_count == 0 && sectors_per_fat_16 == 0 {
             // FAT32
-            Self::open_fat32(data, bpb, time_provider, oem_converter).await
+            Self::open_fat32(data, bpb, time_provider, oem_converter).await?
         } else {
             // FAT12 or FAT16
-            Self::open_fat12_16(data, bpb, time_provider, oem_converter).await
+            Self::open_fat12_16(data, bpb, time_provider, oem_converter).await?
+        };
+
+        // Replay a journal transaction left committed by an earlier session.
+        // A journal file that cannot be read is treated as absent.
+        #[cfg(feature = "write")]
+        if let Err(error) = fs.load_journal().await {
+            fs.journal.lock().load_error = Some(error);
         }
+
+        Ok(fs)
     }
 
     /// Open a FAT12/16 filesystem.
@@ -524,6 +536,8 @@ where
             oem_converter,
             #[cfg(feature = "cache")]
             fat_cache: None,
+            #[cfg(feature = "write")]
+            journal: Mutex::new(Default::default()),
         })
     }
 
@@ -690,6 +704,8 @@ where
             oem_converter,
             #[cfg(feature = "cache")]
             fat_cache: None,
+            #[cfg(feature = "write")]
+            journal: Mutex::new(Default::default()),
         })
     }
 
@@ -5,6 +5,9 @@ io_transform! {
 pub use super::super::{Read, Write, Seek, ReadExt, Error, ErrorKind, SeekFrom, Parsable, Writable};
 pub use super::super::IoResult;
 
+#[cfg(feature = "write")]
+use alloc::{collections::BTreeMap, vec::Vec};
+
 /// Create an I/O error from an ErrorKind.
 ///
 /// This helper works in both std and no-std modes.
@@ -75,11 +78,92 @@ cluster_impl!(u32);
 cluster_impl!(u64);
 cluster_impl!(usize);
 
+/// Writes held back from the device by the FAT journal.
+///
+/// While a journal transaction is open, writes through a [`SectorCursor`]
+/// are captured here instead of reaching the device, and reads see them laid
+/// over the device contents. The overlay also carries a transaction an
+/// earlier session committed to the journal but did not finish writing home.
+#[cfg(feature = "write")]
+#[derive(Debug)]
+pub(crate) struct Overlay {
+    /// Buffered bytes by device offset; extents never overlap or touch
+    pub(crate) extents: BTreeMap<u64, Vec<u8>>,
+    /// Whether writes are being captured
+    capture: bool,
+    /// Journal header offset of a committed transaction awaiting replay
+    pending: Option<u64>,
+    /// Device position, tracked while the overlay is active
+    pos: u64,
+}
+
+#[cfg(feature = "write")]
+impl Overlay {
+    const fn new() -> Self {
+        Self {
+            extents: BTreeMap::new(),
+            capture: false,
+            pending: None,
+            pos: 0,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.capture || !self.extents.is_empty()
+    }
+
+    /// Copy the buffered bytes that fall in `[offset, offset + buf.len())`
+    /// into `buf`.
+    fn patch(&self, offset: u64, buf: &mut [u8]) {
+        let end = offset + buf.len() as u64;
+        for (&start, bytes) in self.extents.range(..end).rev() {
+            let extent_end = start + bytes.len() as u64;
+            if extent_end <= offset {
+                break;
+            }
+            let from = start.max(offset);
+            let to = extent_end.min(end);
+            buf[(from - offset) as usize..(to - offset) as usize].copy_from_slice(
+                &bytes[(from - start) as usize..(to - start) as usize],
+            );
+        }
+    }
+
+    /// Buffer `buf` at `offset`, merging it with the extents it overlaps or
+    /// touches.
+    fn insert(&mut self, offset: u64, buf: &[u8]) {
+        let end = offset + buf.len() as u64;
+        let mut start = offset;
+        let mut merged_end = end;
+        let mut absorbed = Vec::new();
+        for (&extent, bytes) in self.extents.range(..=end).rev() {
+            let extent_end = extent + bytes.len() as u64;
+            if extent_end < offset {
+                break;
+            }
+            start = start.min(extent);
+            merged_end = merged_end.max(extent_end);
+            absorbed.push(extent);
+        }
+        let mut merged = alloc::vec![0; (merged_end - start) as usize];
+        for extent in absorbed {
+            let bytes = self.extents.remove(&extent).unwrap_or_default();
+            let at = (extent - start) as usize;
+            merged[at..at + bytes.len()].copy_from_slice(&bytes);
+        }
+        let at = (offset - start) as usize;
+        merged[at..at + buf.len()].copy_from_slice(buf);
+        self.extents.insert(start, merged);
+    }
+}
+
 /// Seekable data source with FAT sector and cluster geometry.
 pub struct SectorCursor<DATA: Seek> {
     pub(crate) data: DATA,
     pub(crate) sector_size: usize,
     pub(crate) cluster_size: usize,
+    #[cfg(feature = "write")]
+    pub(crate) overlay: Overlay,
 }
 
 impl<DATA: Seek> SectorCursor<DATA> {
@@ -89,6 +173,8 @@ impl<DATA: Seek> SectorCursor<DATA> {
             data,
             sector_size,
             cluster_size,
+            #[cfg(feature = "write")]
+            overlay: Overlay::new(),
         }
     }
 
@@ -100,6 +186,86 @@ impl<DATA: Seek> SectorCursor<DATA> {
     }
 }
 
+/// Journal support: capturing writes and replaying committed transactions.
+#[cfg(feature = "write")]
+impl<DATA: Seek> SectorCursor<DATA> {
+    /// Start tracking the device position; the overlay is about to become
+    /// active.
+    pub(crate) async fn track_position(&mut self) -> hadris_io::Result<()> {
+        self.overlay.pos = self
+            .data
+            .stream_position()
+            .await
+            .map_err(hadris_io::Error::erase)?;
+        Ok(())
+    }
+
+    /// Start capturing writes in the overlay.
+    pub(crate) async fn begin_capture(&mut self) -> hadris_io::Result<()> {
+        self.track_position().await?;
+        self.overlay.capture = true;
+        Ok(())
+    }
+
+    /// Stop capturing writes and hand back the ones captured.
+    pub(crate) fn end_capture(&mut self) -> BTreeMap<u64, Vec<u8>> {
+        self.overlay.capture = false;
+        core::mem::take(&mut self.overlay.extents)
+    }
+
+    /// Lay a transaction committed to the journal at `header` over the
+    /// device until it is replayed.
+    pub(crate) async fn set_pending(
+        &mut self,
+        header: u64,
+        extents: BTreeMap<u64, Vec<u8>>,
+    ) -> hadris_io::Result<()> {
+        self.overlay.extents = extents;
+        self.overlay.pending = Some(header);
+        self.track_position().await
+    }
+
+    /// Whether a committed transaction is waiting to be replayed.
+    pub(crate) fn has_pending(&self) -> bool {
+        self.overlay.pending.is_some()
+    }
+}
+
+#[cfg(feature = "write")]
+impl<DATA: Write + Seek> SectorCursor<DATA> {
+    /// Write a committed transaction held in the overlay to its home
+    /// locations, then clear its journal header. Returns whether there was
+    /// one.
+    pub(crate) async fn replay_pending(&mut self) -> hadris_io::Result<bool> {
+        let Some(header) = self.overlay.pending else {
+            return Ok(false);
+        };
+        for (&offset, bytes) in &self.overlay.extents {
+            self.data
+                .seek(SeekFrom::Start(offset))
+                .await
+                .map_err(hadris_io::Error::erase)?;
+            self.data.write_all(bytes).await?;
+        }
+        self.data.flush().await.map_err(hadris_io::Error::erase)?;
+        self.data
+            .seek(SeekFrom::Start(header))
+            .await
+            .map_err(hadris_io::Error::erase)?;
+        self.data
+            .write_all(&[0; super::journal::HEADER_LEN])
+            .await?;
+        self.data.flush().await.map_err(hadris_io::Error::erase)?;
+        self.overlay.pending = None;
+        self.overlay.extents.clear();
+        self.data
+            .seek(SeekFrom::Start(self.overlay.pos))
+            .await
+            .map_err(hadris_io::Error::erase)?;
+        Ok(true)
+    }
+}
+
 impl<T> Seek for SectorCursor<T>
 where
     T: Seek,
@@ -107,15 +273,30 @@ where
     type Error = <T as Seek>::Error;
 
     async fn seek(&mut self, pos: hadris_io::SeekFrom) -> hadris_io::Result<u64, Self::Error> {
-        self.data.seek(pos).await
+        let position = self.data.seek(pos).await?;
+        #[cfg(feature = "write")]
+        {
+            self.overlay.pos = position;
+        }
+        Ok(position)
     }
 
     async fn stream_position(&mut self) -> hadris_io::Result<u64, Self::Error> {
-        self.data.stream_position().await
+        let position = self.data.stream_position().await?;
+        #[cfg(feature = "write")]
+        {
+            self.overlay.pos = position;
+        }
+        Ok(position)
     }
 
     async fn seek_relative(&mut self, offset: i64) -> hadris_io::Result<(), Self::Error> {
-        self.data.seek_relative(offset).await
+        self.data.seek_relative(offset).await?;
+        #[cfg(feature = "write")]
+        {
+            self.overlay.pos = self.overlay.pos.wrapping_add_signed(offset);
+        }
+        Ok(())
     }
 }
 
@@ -126,11 +307,23 @@ where
     type Error = <T as Read>::Error;
 
     async fn read(&mut self, buf: &mut [u8]) -> hadris_io::Result<usize, Self::Error> {
-        self.data.read(buf).await
+        let read = self.data.read(buf).await?;
+        #[cfg(feature = "write")]
+        if self.overlay.is_active() {
+            self.overlay.patch(self.overlay.pos, &mut buf[..read]);
+            self.overlay.pos += read as u64;
+        }
+        Ok(read)
     }
 
     async fn read_exact(&mut self, buf: &mut [u8]) -> hadris_io::Result<()> {
-        self.data.read_exact(buf).await
+        self.data.read_exact(buf).await?;
+        #[cfg(feature = "write")]
+        if self.overlay.is_active() {
+            self.overlay.patch(self.overlay.pos, buf);
+            self.overlay.pos += buf.len() as u64;
+        }
+        Ok(())
     }
 }
 
@@ -142,6 +335,21 @@ where
     type Error = <T as Write>::Error;
 
     async fn write(&mut self, buf: &[u8]) -> hadris_io::Result<usize, Self::Error> {
+        if self.overlay.capture {
+            self.overlay.insert(self.overlay.pos, buf);
+            self.overlay.pos += buf.len() as u64;
+            // Keep the device position in step for reads that follow
+            self.data
+                .seek(SeekFrom::Start(self.overlay.pos))
+                .await
+                .map_err(|error| hadris_io::Error::new(error.kind(), "journal capture seek"))?;
+            return Ok(buf.len());
+        }
+        // Replay a transaction left by an earlier session before anything
+        // can overwrite the sectors it covers
+        self.replay_pending()
+            .await
+            .map_err(|error| hadris_io::Error::new(error.kind(), "journal replay"))?;
         self.data.write(buf).await
     }
 
@@ -150,6 +358,12 @@ where
     }
 
     async fn write_all(&mut self, buf: &[u8]) -> hadris_io::Result<()> {
+        if self.overlay.capture {
+            // A captured write always takes the whole buffer
+            self.write(buf).await.map_err(hadris_io::Error::erase)?;
+            return Ok(());
+        }
+        self.replay_pending().await?;
         self.data.write_all(buf).await
     }
 }
@@ -0,0 +1,514 @@
+//! Write-ahead journal for FAT metadata updates.
+//!
+//! FAT has no transactions of its own. Creating, renaming, deleting or
+//! truncating an entry updates directory sectors, every FAT copy and FSInfo
+//! in separate writes, so power loss part-way through can leave lost or
+//! cross-linked chains. With the journal enabled
+//! ([`FatVolume::enable_journal`]), each of those operations runs as a
+//! transaction:
+//!
+//! 1. Its writes are held in memory; reads made by the operation see them.
+//! 2. When the operation succeeds, the held writes are copied into the
+//!    journal file, then a header sector marking them committed is written.
+//! 3. The writes are applied to their home locations and the header is
+//!    cleared.
+//!
+//! An operation that fails discards its writes, so the volume is left as it
+//! was. A transaction that committed but was not fully applied is replayed
+//! when the volume is opened: [`FatVolume::open`] lays it over the device so
+//! reads see the committed state, and it is written home by
+//! [`FatVolume::enable_journal`], [`FatVolume::replay_journal`], or before
+//! the next write through the volume.
+//!
+//! The journal file is [`JOURNAL_FILE_NAME`], a hidden system file in the
+//! root directory, so other FAT implementations see an ordinary file. File
+//! contents written through [`FileWriter`](super::write::FileWriter) are not
+//! journaled; only the operations listed above are.
+//!
+//! # On-disk format
+//!
+//! The first sector of the journal file holds the header, all little-endian:
+//!
+//! | Offset | Size | Field |
+//! |--------|------|-------|
+//! | 0      | 8    | Magic, `HADRISJL` |
+//! | 8      | 4    | Format version, 1 |
+//! | 12     | 4    | Number of records |
+//! | 16     | 4    | Length of the record area in bytes |
+//! | 20     | 4    | Checksum of the record area |
+//! | 24     | 4    | Checksum of bytes 0..24 |
+//!
+//! The records follow from the second sector, each a device byte offset
+//! (8 bytes), a length (4 bytes) and that many bytes of data. A header with
+//! the wrong magic or a checksum mismatch means there is no committed
+//! transaction.
+
+io_transform! {
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use super::dir::FileEntry;
+use super::fs::{FatFsExt, FatVolume};
+use super::io::{Cluster, ClusterLike, Read, Seek, SeekFrom, Write};
+use super::write::FatVolumeWriteExt;
+use crate::error::{Error, Result};
+use crate::raw::DirEntryAttrFlags;
+
+/// Name of the journal file in the root directory.
+pub const JOURNAL_FILE_NAME: &str = "HADRIS.JNL";
+
+/// A journal size for [`FatVolume::enable_journal`]: 1 MiB, enough for
+/// deleting or truncating a file of some tens of thousands of clusters.
+pub const DEFAULT_JOURNAL_SIZE: usize = 1024 * 1024;
+
+/// Bytes of the header that mark a committed transaction; clearing them
+/// ends it.
+pub(crate) const HEADER_LEN: usize = 28;
+
+const MAGIC: [u8; 8] = *b"HADRISJL";
+const VERSION: u32 = 1;
+/// Offset and length preceding each record's data
+const RECORD_HEADER_LEN: usize = 12;
+
+/// Journal state of a mounted volume.
+#[derive(Debug, Default)]
+pub(crate) struct JournalState {
+    /// The journal file's clusters as (device offset, length) runs; empty
+    /// when the volume has no journal file
+    runs: Vec<(u64, usize)>,
+    /// Whether metadata operations run as transactions
+    enabled: bool,
+    /// Nesting depth of the open transaction
+    depth: u32,
+    /// FAT32 FSInfo free count and next-free hint when the open transaction
+    /// began, put back if it is discarded
+    fs_info: Option<(u32, Cluster<u32>)>,
+    /// Why the journal file could not be read when the volume was opened
+    pub(crate) load_error: Option<Error>,
+}
+
+/// The fields of a committed header.
+struct Header {
+    records: u32,
+    payload_len: u32,
+    payload_checksum: u32,
+}
+
+impl Header {
+    fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..8].copy_from_slice(&MAGIC);
+        bytes[8..12].copy_from_slice(&VERSION.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.records.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.payload_len.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.payload_checksum.to_le_bytes());
+        let checksum = checksum(&bytes[..24]);
+        bytes[24..28].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Parse a header, or `None` if it does not mark a committed
+    /// transaction.
+    fn decode(bytes: &[u8; HEADER_LEN]) -> Option<Self> {
+        let field = |at: usize| {
+            u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+        };
+        if bytes[0..8] != MAGIC || field(8) != VERSION || field(24) != checksum(&bytes[..24]) {
+            return None;
+        }
+        Some(Self {
+            records: field(12),
+            payload_len: field(16),
+            payload_checksum: field(20),
+        })
+    }
+}
+
+/// Rotate-and-add checksum, as exFAT uses for its boot region.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0u32, |sum, &byte| {
+        sum.rotate_right(1).wrapping_add(byte as u32)
+    })
+}
+
+/// Total length of the journal file.
+fn journal_len(runs: &[(u64, usize)]) -> usize {
+    runs.iter().map(|&(_, len)| len).sum()
+}
+
+/// Read `buf.len()` bytes at `offset` within the journal file.
+async fn read_journal<D: Read + Seek>(
+    data: &mut D,
+    runs: &[(u64, usize)],
+    mut offset: usize,
+    mut buf: &mut [u8],
+) -> Result<()> {
+    for &(start, len) in runs {
+        if buf.is_empty() {
+            break;
+        }
+        if offset >= len {
+            offset -= len;
+            continue;
+        }
+        let chunk = (len - offset).min(buf.len());
+        data.seek(SeekFrom::Start(start + offset as u64)).await?;
+        data.read_exact(&mut buf[..chunk]).await?;
+        buf = &mut buf[chunk..];
+        offset = 0;
+    }
+    if !buf.is_empty() {
+        return Err(Error::CorruptFilesystem {
+            context: "read past the end of the journal file",
+        });
+    }
+    Ok(())
+}
+
+/// Write `buf` at `offset` within the journal file.
+async fn write_journal<D: Write + Seek>(
+    data: &mut D,
+    runs: &[(u64, usize)],
+    mut offset: usize,
+    mut buf: &[u8],
+) -> Result<()> {
+    for &(start, len) in runs {
+        if buf.is_empty() {
+            break;
+        }
+        if offset >= len {
+            offset -= len;
+            continue;
+        }
+        let chunk = (len - offset).min(buf.len());
+        data.seek(SeekFrom::Start(start + offset as u64)).await?;
+        data.write_all(&buf[..chunk]).await?;
+        buf = &buf[chunk..];
+        offset = 0;
+    }
+    Ok(())
+}
+
+/// Write `extents` to their home locations.
+async fn apply<D: Write + Seek>(data: &mut D, extents: &BTreeMap<u64, Vec<u8>>) -> Result<()> {
+    for (&offset, bytes) in extents {
+        data.seek(SeekFrom::Start(offset)).await?;
+        data.write_all(bytes).await?;
+    }
+    data.flush().await?;
+    Ok(())
+}
+
+/// Read the transaction committed to the journal, if there is one.
+async fn read_committed<D: Read + Seek>(
+    data: &mut D,
+    runs: &[(u64, usize)],
+    sector_size: usize,
+) -> Result<Option<BTreeMap<u64, Vec<u8>>>> {
+    let mut bytes = [0; HEADER_LEN];
+    read_journal(data, runs, 0, &mut bytes).await?;
+    let Some(header) = Header::decode(&bytes) else {
+        return Ok(None);
+    };
+    let payload_len = header.payload_len as usize;
+    if payload_len > journal_len(runs).saturating_sub(sector_size) {
+        return Ok(None);
+    }
+    let mut payload = alloc::vec![0; payload_len];
+    read_journal(data, runs, sector_size, &mut payload).await?;
+    if checksum(&payload) != header.payload_checksum {
+        return Ok(None);
+    }
+
+    let mut extents = BTreeMap::new();
+    let mut rest = payload.as_slice();
+    for _ in 0..header.records {
+        if rest.len() < RECORD_HEADER_LEN {
+            return Ok(None);
+        }
+        let (record, tail) = rest.split_at(RECORD_HEADER_LEN);
+        let mut offset = [0; 8];
+        offset.copy_from_slice(&record[..8]);
+        let len = u32::from_le_bytes([record[8], record[9], record[10], record[11]]) as usize;
+        if tail.len() < len {
+            return Ok(None);
+        }
+        extents.insert(u64::from_le_bytes(offset), tail[..len].to_vec());
+        rest = &tail[len..];
+    }
+    Ok(Some(extents))
+}
+
+/// Journal discovery, shared by read-only mounts
+impl<DATA: Read + Seek> FatVolume<DATA> {
+    /// Find the journal file and lay any transaction committed to it over
+    /// the device.
+    pub(crate) async fn load_journal(&self) -> Result<()> {
+        // A volume whose root cannot be searched has no usable journal;
+        // leave reporting the damage to the caller's own lookups
+        let Ok(Some(entry)) = self.root_dir().find(JOURNAL_FILE_NAME).await else {
+            return Ok(());
+        };
+        if !entry.is_file() || entry.cluster().0 < 2 {
+            return Ok(());
+        }
+        let runs = self.journal_runs(&entry).await?;
+        {
+            let mut data = self.data.lock();
+            let sector_size = data.sector_size;
+            if let Some(extents) = read_committed(&mut data.data, &runs, sector_size).await? {
+                data.set_pending(runs[0].0, extents).await?;
+            }
+        }
+        self.journal.lock().runs = runs;
+        Ok(())
+    }
+
+    /// The clusters of the journal file, merged into contiguous runs.
+    async fn journal_runs(&self, entry: &FileEntry) -> Result<Vec<(u64, usize)>> {
+        let cluster_size = self.info.cluster_size;
+        let max_cluster = self.info.max_cluster;
+        let clusters = (entry.len() as usize)
+            .div_ceil(cluster_size)
+            .min(max_cluster as usize);
+        let mut runs: Vec<(u64, usize)> = Vec::new();
+        let mut cluster = entry.cluster().0 as u32;
+        for index in 0..clusters {
+            if !(2..=max_cluster).contains(&cluster) {
+                return Err(Error::ClusterOutOfBounds {
+                    cluster,
+                    max: max_cluster,
+                });
+            }
+            let offset = Cluster(cluster).to_bytes(self.info.data_start, cluster_size) as u64;
+            match runs.last_mut() {
+                Some((start, len)) if *start + *len as u64 == offset => *len += cluster_size,
+                _ => runs.push((offset, cluster_size)),
+            }
+            if index + 1 < clusters {
+                cluster = self
+                    .next_cluster_routed(cluster as usize)
+                    .await?
+                    .ok_or(Error::UnexpectedEndOfChain { cluster })?;
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Whether metadata operations run as journal transactions.
+    pub fn journal_enabled(&self) -> bool {
+        self.journal.lock().enabled
+    }
+
+    /// Whether a transaction committed by an earlier session is waiting to
+    /// be written home.
+    ///
+    /// Reads already see its effects.
+    pub fn journal_pending(&self) -> bool {
+        self.data.lock().has_pending()
+    }
+
+    /// Take the error that kept [`FatVolume::open`] from reading the
+    /// journal file, if there was one.
+    ///
+    /// Such a volume is opened as if it had no journal: a transaction
+    /// committed to the damaged file is neither shown nor replayed.
+    pub fn take_journal_error(&self) -> Option<Error> {
+        self.journal.lock().load_error.take()
+    }
+}
+
+/// Journaled updates
+impl<DATA: Read + Write + Seek> FatVolume<DATA> {
+    /// Run `create_file`, `create_dir`, `rename`, `delete` and `truncate`
+    /// as journal transactions from now on.
+    ///
+    /// Creates [`JOURNAL_FILE_NAME`] in the root directory with at least
+    /// `size` bytes (rounded up to two clusters or more) if the volume has
+    /// no journal yet; an existing journal keeps its size. A transaction
+    /// whose writes do not fit fails with [`Error::JournalFull`] and changes
+    /// nothing. A committed transaction left by an earlier session is
+    /// replayed first.
+    pub async fn enable_journal(&self, size: usize) -> Result<()> {
+        self.replay_journal().await?;
+        if self.journal.lock().runs.is_empty() {
+            let entry = self.create_journal_file(size).await?;
+            let runs = self.journal_runs(&entry).await?;
+            self.journal.lock().runs = runs;
+        }
+        self.journal.lock().enabled = true;
+        Ok(())
+    }
+
+    /// Stop journaling metadata operations. The journal file stays in place.
+    pub fn disable_journal(&self) {
+        self.journal.lock().enabled = false;
+    }
+
+    /// Write a transaction committed by an earlier session to its home
+    /// locations. Returns whether there was one.
+    pub async fn replay_journal(&self) -> Result<bool> {
+        let mut data = self.data.lock();
+        Ok(data.replay_pending().await?)
+    }
+
+    /// Create the journal file, zero-filled, as a hidden system file.
+    async fn create_journal_file(&self, size: usize) -> Result<FileEntry> {
+        let cluster_size = self.info.cluster_size;
+        let size = size.max(2 * cluster_size).next_multiple_of(cluster_size);
+        let root = self.root_dir();
+        let entry = self.create_file(&root, JOURNAL_FILE_NAME).await?;
+        let mut writer = self.write_file(&entry)?;
+        let zeros = [0; 512];
+        let mut remaining = size;
+        while remaining > 0 {
+            let written = writer.write(&zeros[..remaining.min(zeros.len())]).await?;
+            remaining -= written;
+        }
+        writer.finish().await?;
+        self.set_attributes(
+            &entry,
+            entry.attributes() | DirEntryAttrFlags::HIDDEN | DirEntryAttrFlags::SYSTEM,
+        )
+        .await?;
+        root.find(JOURNAL_FILE_NAME)
+            .await?
+            .ok_or(Error::EntryNotFound)
+    }
+
+    /// Open a transaction when the journal is enabled. A nested call joins
+    /// the transaction already open.
+    pub(crate) async fn begin_transaction(&self) -> Result<()> {
+        let mut journal = self.journal.lock();
+        if !journal.enabled {
+            return Ok(());
+        }
+        journal.depth += 1;
+        if journal.depth == 1 {
+            let mut data = self.data.lock();
+            data.replay_pending().await?;
+            data.begin_capture().await?;
+            journal.fs_info = match &self.ext {
+                FatFsExt::Fat32(ext) => Some((ext.free_count.get(), ext.next_free.get())),
+                FatFsExt::Fat12_16(_) => None,
+            };
+        }
+        Ok(())
+    }
+
+    /// Close the transaction [`begin_transaction`](Self::begin_transaction)
+    /// opened: commit it if the operation succeeded, discard it otherwise.
+    pub(crate) async fn end_transaction<T>(&self, result: Result<T>) -> Result<T> {
+        {
+            let mut journal = self.journal.lock();
+            if !journal.enabled || journal.depth == 0 {
+                return result;
+            }
+            journal.depth -= 1;
+            if journal.depth > 0 {
+                return result;
+            }
+        }
+        match result {
+            Ok(value) => {
+                self.commit_transaction().await?;
+                Ok(value)
+            }
+            Err(error) => {
+                self.discard_transaction()?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Drop a transaction that did not commit. Its writes never reached the
+    /// device, so the FAT sectors cached for it and the FSInfo counters it
+    /// moved are put back as well.
+    fn discard_transaction(&self) -> Result<()> {
+        self.data.lock().end_capture();
+        #[cfg(feature = "cache")]
+        sync_only! {
+            if let Some(cache) = &self.fat_cache {
+                cache.lock().clear::<super::io::SectorCursor<DATA>>(None)?;
+            }
+        }
+        let fs_info = self.journal.lock().fs_info.take();
+        if let (FatFsExt::Fat32(ext), Some((free_count, next_free))) = (&self.ext, fs_info) {
+            ext.free_count.set(free_count);
+            ext.next_free.set(next_free);
+        }
+        Ok(())
+    }
+
+    /// Journal the captured writes, then apply them.
+    async fn commit_transaction(&self) -> Result<()> {
+        let extents = match self.write_transaction().await {
+            Ok(Some(extents)) => extents,
+            Ok(None) => return Ok(()),
+            Err(error) => {
+                self.discard_transaction()?;
+                return Err(error);
+            }
+        };
+
+        // Committed: from here on an interrupted apply is replayed
+        let journal = self.journal.lock();
+        let mut data = self.data.lock();
+        let device = &mut data.data;
+        if let Err(error) = apply(device, &extents).await {
+            // Keep presenting the committed state until the replay
+            data.set_pending(journal.runs[0].0, extents).await?;
+            return Err(error);
+        }
+        write_journal(device, &journal.runs, 0, &[0; HEADER_LEN]).await?;
+        device.flush().await?;
+        Ok(())
+    }
+
+    /// Copy the captured writes into the journal and mark them committed.
+    /// Returns them, or `None` if the transaction wrote nothing.
+    async fn write_transaction(&self) -> Result<Option<BTreeMap<u64, Vec<u8>>>> {
+        // Dirty FAT sectors held by the cache belong to this transaction
+        #[cfg(feature = "cache")]
+        sync_only! {
+            self.flush()?;
+        }
+
+        let journal = self.journal.lock();
+        let mut data = self.data.lock();
+        let extents = data.end_capture();
+        if extents.is_empty() {
+            return Ok(None);
+        }
+        let sector_size = data.sector_size;
+
+        let mut payload = Vec::new();
+        for (offset, bytes) in &extents {
+            payload.extend_from_slice(&offset.to_le_bytes());
+            payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+            payload.extend_from_slice(bytes);
+        }
+        let capacity = journal_len(&journal.runs).saturating_sub(sector_size);
+        if payload.len() > capacity {
+            return Err(Error::JournalFull {
+                needed: payload.len(),
+                capacity,
+            });
+        }
+        let header = Header {
+            records: extents.len() as u32,
+            payload_len: payload.len() as u32,
+            payload_checksum: checksum(&payload),
+        };
+
+        let device = &mut data.data;
+        write_journal(device, &journal.runs, sector_size, &payload).await?;
+        device.flush().await?;
+        write_journal(device, &journal.runs, 0, &header.encode()).await?;
+        device.flush().await?;
+        Ok(Some(extents))
+    }
+}
+
+} // end io_transform!
@@ -67,18 +67,16 @@
 //! | `lfn`    | Yes     | Long filename (VFAT) support |
 //! | `cache`  | No      | FAT sector caching for reduced I/O |
 //! | `tool`   | No      | Analysis and diagnostic utilities |
-//! | `unstable-exfat` | No | Unstable, sync-only exFAT preview |
+//! | `unstable-exfat` | No | Unstable exFAT preview (`sync::exfat`, `async::exfat`) |
 //!
 //! ## Known Limitations
 //!
 //! - **async + cache:** The FAT-sector cache is sync-only; async operations
 //!   access the FAT directly.
 //! - **exFAT:** The `unstable-exfat` preview is outside the V2 API stability
-//!   promise and is not recommended for irreplaceable data. It is sync-only
-//!   and does not support fragmented allocation bitmap / upcase metadata,
-//!   directory growth, general cross-cluster entry-set placement, TexFAT, or
-//!   repair workflows. Enable the preview and see the `exfat` module for its
-//!   qualified scope.
+//!   promise and is not recommended for irreplaceable data. It does not
+//!   support repair workflows. Enable the preview and see the `exfat` module
+//!   for its qualified scope.
 //!
 //! ## Dual Sync/Async Architecture
 //!
@@ -105,7 +103,8 @@
 //! - `sync::fat_table` — FAT table access (FAT12/16/32)
 //! - `sync::cache` — Optional FAT sector caching
 //! - `sync::format` — Filesystem formatting (requires `write`)
-//! - `sync::tool` — Analysis and verification (requires `tool`)
+//! - `sync::journal` — Write-ahead journal for metadata updates (requires `write`)
+//! - `sync::tool` — Analysis, verification, repair, and defragmentation (requires `tool`)
 
 #![no_std]
 #![deny(missing_docs)]
@@ -131,10 +130,6 @@ pub mod oem;
 pub mod raw;
 pub mod time;
 
-// Unstable exFAT preview, intentionally outside the sync/async stable surface.
-#[cfg(feature = "unstable-exfat")]
-pub mod exfat;
-
 // ---------------------------------------------------------------------------
 // Sync module
 // ---------------------------------------------------------------------------
@@ -170,6 +165,9 @@ pub mod sync {
         pub mod cache;
         /// Directory traversal and directory-entry types.
         pub mod dir;
+        // Unstable exFAT preview, outside the V2 stability promise.
+        #[cfg(feature = "unstable-exfat")]
+        pub mod exfat;
         /// FAT12, FAT16, and FAT32 allocation-table access.
         pub mod fat_table;
         #[cfg(feature = "write")]
@@ -178,7 +176,11 @@ pub mod sync {
         pub mod fs;
         /// FAT-specific I/O positioning utilities.
         pub mod io;
+        #[cfg(feature = "write")]
+        pub mod journal;
         pub mod read;
+        #[cfg(feature = "write")]
+        mod resize;
         #[cfg(feature = "tool")]
         pub mod tool;
         pub mod write;
@@ -193,6 +195,10 @@ pub mod sync {
     pub use __inner::read::FatVolumeReadExt;
     #[cfg(feature = "tool")]
     pub use __inner::tool::analysis::FatAnalysisExt;
+    #[cfg(all(feature = "tool", feature = "write"))]
+    pub use __inner::tool::defrag::FatDefragExt;
+    #[cfg(all(feature = "tool", feature = "write"))]
+    pub use __inner::tool::repair::FatRepairExt;
     #[cfg(feature = "tool")]
     pub use __inner::tool::verify::FatVerifyExt;
     #[cfg(feature = "write")]
@@ -236,6 +242,9 @@ pub mod r#async {
         // exposes cache APIs.
         /// Directory traversal and directory-entry types.
         pub mod dir;
+        // Unstable exFAT preview, outside the V2 stability promise.
+        #[cfg(feature = "unstable-exfat")]
+        pub mod exfat;
         /// FAT12, FAT16, and FAT32 allocation-table access.
         pub mod fat_table;
         #[cfg(feature = "write")]
@@ -244,7 +253,11 @@ pub mod r#async {
         pub mod fs;
         /// FAT-specific I/O positioning utilities.
         pub mod io;
+        #[cfg(feature = "write")]
+        pub mod journal;
         pub mod read;
+        #[cfg(feature = "write")]
+        mod resize;
         // Note: `tool` is intentionally absent here. The analysis/verify
         // utilities iterate directories synchronously and are not
         // async-aware; the `tool` feature is gated to `sync` in Cargo.toml
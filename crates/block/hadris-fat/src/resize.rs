@@ -0,0 +1,487 @@
+//! Growing and shrinking FAT16/FAT32 volumes in place.
+//!
+//! [`FatVolume::resize`] rewrites the BPB, FAT tables, and FSInfo for a new
+//! sector count. Growing extends the FAT tables when the new cluster count no
+//! longer fits, shifting the root directory and data region up to make room.
+//! Shrinking relocates every allocated cluster past the new end into free
+//! space below it, then rewrites the FAT links and directory entries that
+//! referenced the old locations.
+
+io_transform! {
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::ops::DerefMut;
+
+use hadris_common::types::endian::{Endian, LittleEndian};
+use hadris_common::types::number::{U16, U32};
+
+use crate::error::{Error, Result};
+use crate::raw::{RawBpb, RawBpbExt32, RawDirectoryEntry};
+use super::dir::{DirectoryEntry, FatDir, FileEntry};
+use super::fat_table::FatType;
+use super::format::calc::calculate_fat_size;
+use super::fs::{FatFsExt, FatVolume};
+use super::io::{Cluster, ClusterLike, Read, ReadExt, Seek, SeekFrom, Write};
+
+/// Cluster counts that keep a FAT16 volume FAT16 (Microsoft FAT spec).
+const FAT16_CLUSTERS: (u32, u32) = (4085, 65524);
+/// Cluster counts that keep a FAT32 volume FAT32. The upper bound leaves
+/// the highest cluster number below the bad-cluster marker.
+const FAT32_CLUSTERS: (u32, u32) = (65525, 0x0FFF_FFF5);
+/// Bytes moved per read/write pair when copying metadata and cluster data.
+const COPY_CHUNK: usize = 64 * 1024;
+
+impl<DATA: Read + Write + Seek> FatVolume<DATA> {
+    /// Grow or shrink the volume to `total_sectors` sectors.
+    ///
+    /// The sector size, cluster size, reserved region, FAT count, and FAT16
+    /// root directory are kept. Growing needs a backing store at least
+    /// `total_sectors` sectors long; when the larger cluster count no longer
+    /// fits the FAT, the FAT tables are extended and the root directory and
+    /// data region are shifted up. Shrinking keeps the FAT size (an oversized
+    /// FAT is valid) and moves allocated clusters past the new end into free
+    /// clusters below it. The backing store can be truncated afterwards.
+    ///
+    /// The BPB (and the FAT32 backup boot sector) and the FAT32 FSInfo
+    /// free-cluster count are updated, and this handle is remounted with the
+    /// new geometry. Returns the number of clusters relocated.
+    ///
+    /// Resizing rewrites metadata in several steps and is not crash-safe;
+    /// take a backup of images you cannot recreate.
+    ///
+    /// # Errors
+    ///
+    /// - [`Error::UnsupportedFatType`] for FAT12 volumes.
+    /// - [`Error::VolumeTooSmall`] / [`Error::VolumeTooLarge`] if the new
+    ///   cluster count would change the FAT type, or the backing store is
+    ///   smaller than `total_sectors`.
+    /// - [`Error::NoFreeSpace`] if the clusters past the new end do not fit
+    ///   in the free space below it. Nothing is modified in that case.
+    pub async fn resize(&mut self, total_sectors: u32) -> Result<u32> {
+        let fat_type = self.fat.fat_type();
+        let (min_clusters, max_clusters) = match fat_type {
+            FatType::Fat12 => {
+                return Err(Error::UnsupportedFatType("resize requires FAT16 or FAT32"));
+            }
+            FatType::Fat16 => FAT16_CLUSTERS,
+            FatType::Fat32 => FAT32_CLUSTERS,
+        };
+        let width = if fat_type == FatType::Fat32 { 4 } else { 2 };
+
+        // The tables are rewritten directly below, so push out dirty cached
+        // sectors and drop the cache; it is rebuilt for the new layout.
+        #[cfg(feature = "cache")]
+        sync_only! {
+            self.flush()?;
+        }
+        #[cfg(feature = "cache")]
+        let cache_capacity = self.fat_cache.take().map(|cache| cache.into_inner().capacity());
+
+        let (mut bpb, mut bpb32, sector_size) = {
+            let mut data = self.data.lock();
+            data.seek(SeekFrom::Start(0)).await?;
+            let bpb = data.read_struct::<RawBpb>().await?;
+            let bpb32 = if fat_type == FatType::Fat32 {
+                Some(data.read_struct::<RawBpbExt32>().await?)
+            } else {
+                None
+            };
+            (bpb, bpb32, data.sector_size)
+        };
+        let reserved = bpb.reserved_sector_count.get() as u32;
+        let fat_count = bpb.fat_count as u32;
+        let sectors_per_cluster = bpb.sectors_per_cluster as u32;
+        let root_dir_sectors =
+            (u16::from_le_bytes(bpb.root_entry_count) as u32 * 32).div_ceil(sector_size as u32);
+        let (fat_start, old_fat_size, _) = self.fat.layout();
+        let old_fat_sectors = (old_fat_size / sector_size) as u32;
+        let old_max = self.fat.max_cluster();
+
+        let size = total_sectors as u64 * sector_size as u64;
+        let region_bytes = |fat_sectors: u32, clusters: u32| {
+            (reserved as u64
+                + root_dir_sectors as u64
+                + fat_count as u64 * fat_sectors as u64
+                + clusters as u64 * sectors_per_cluster as u64)
+                * sector_size as u64
+        };
+        let fat_sectors_for = |clusters: u32| {
+            ((clusters as u64 + 2) * width as u64).div_ceil(sector_size as u64) as u32
+        };
+        // Rule out sizes far past the type's limit before the FAT-size
+        // solver, whose entry-byte arithmetic is u32.
+        if total_sectors / sectors_per_cluster > max_clusters.saturating_mul(2) {
+            return Err(Error::VolumeTooLarge {
+                size,
+                max_size: region_bytes(fat_sectors_for(max_clusters).max(old_fat_sectors), max_clusters),
+            });
+        }
+
+        // Keep the current FAT if the new cluster count still fits in it.
+        let (needed_fat_sectors, _) = calculate_fat_size(
+            fat_type,
+            total_sectors,
+            reserved,
+            root_dir_sectors,
+            sectors_per_cluster,
+            fat_count,
+            sector_size,
+        )?;
+        let fat_sectors = needed_fat_sectors.max(old_fat_sectors);
+        let overhead = reserved as u64 + root_dir_sectors as u64 + fat_count as u64 * fat_sectors as u64;
+        let clusters = ((total_sectors as u64).saturating_sub(overhead) / sectors_per_cluster as u64) as u32;
+        if clusters < min_clusters {
+            return Err(Error::VolumeTooSmall {
+                size,
+                min_size: region_bytes(fat_sectors, min_clusters),
+            });
+        }
+        if clusters > max_clusters {
+            return Err(Error::VolumeTooLarge {
+                size,
+                max_size: region_bytes(fat_sectors_for(max_clusters).max(old_fat_sectors), max_clusters),
+            });
+        }
+        {
+            let mut data = self.data.lock();
+            let available = data.seek(SeekFrom::End(0)).await?;
+            if available < size {
+                return Err(Error::VolumeTooLarge {
+                    size,
+                    max_size: available,
+                });
+            }
+        }
+        let new_max = clusters + 1;
+        let new_fat_size = fat_sectors as usize * sector_size;
+
+        let relocated = if new_max < old_max {
+            self.relocate_tail(new_max).await?
+        } else {
+            0
+        };
+
+        if fat_sectors > old_fat_sectors {
+            // Everything after the first FAT moves up by the growth of all
+            // copies. Copy from the top down so no source is overwritten
+            // before it has been read.
+            let shift = (fat_sectors - old_fat_sectors) as usize * fat_count as usize * sector_size;
+            let mut used = Vec::new();
+            self.scan_entries(2, old_max, |cluster, value| {
+                if self.fat.is_allocated(value) {
+                    used.push(cluster);
+                }
+            })
+            .await?;
+
+            let mut data = self.data.lock();
+            let cluster_size = data.cluster_size;
+            let mut buf = alloc::vec![0u8; COPY_CHUNK];
+            for &cluster in used.iter().rev() {
+                let from = Cluster(cluster as usize).to_bytes(self.info.data_start, cluster_size);
+                move_bytes(data.deref_mut(), from, from + shift, cluster_size, &mut buf).await?;
+            }
+            if let FatFsExt::Fat12_16(ext) = &self.ext {
+                let root_start = ext.root_dir_start;
+                let root_len = root_dir_sectors as usize * sector_size;
+                move_bytes(data.deref_mut(), root_start, root_start + shift, root_len, &mut buf).await?;
+            }
+            for copy in (1..fat_count as usize).rev() {
+                let from = fat_start + copy * old_fat_size;
+                let to = fat_start + copy * new_fat_size;
+                move_bytes(data.deref_mut(), from, to, old_fat_size, &mut buf).await?;
+            }
+        }
+
+        // Entries past the surviving clusters, including any FAT extension,
+        // must read as free.
+        {
+            let mut data = self.data.lock();
+            let keep = (old_max.min(new_max) as usize + 1) * width;
+            for copy in 0..fat_count as usize {
+                let base = fat_start + copy * new_fat_size;
+                zero_bytes(data.deref_mut(), base + keep, new_fat_size - keep).await?;
+            }
+        }
+
+        if fat_type == FatType::Fat16 && total_sectors < 0x10000 {
+            bpb.total_sectors_16 = (total_sectors as u16).to_le_bytes();
+            bpb.total_sectors_32 = [0; 4];
+        } else {
+            bpb.total_sectors_16 = [0; 2];
+            bpb.total_sectors_32 = total_sectors.to_le_bytes();
+        }
+        if let Some(bpb32) = bpb32.as_mut() {
+            bpb32.sectors_per_fat_32 = U32::<LittleEndian>::new(fat_sectors);
+            if let FatFsExt::Fat32(ext) = &self.ext {
+                bpb32.root_cluster = U32::<LittleEndian>::new(ext.root_clus.0);
+            }
+        } else {
+            bpb.sectors_per_fat_16 = (fat_sectors as u16).to_le_bytes();
+        }
+        {
+            let mut data = self.data.lock();
+            let mut sectors = alloc::vec![0u32];
+            if let Some(bpb32) = &bpb32 {
+                let backup = u16::from_le_bytes(bpb32.boot_sector) as u32;
+                if backup != 0 && backup < reserved {
+                    sectors.push(backup);
+                }
+            }
+            for sector in sectors {
+                data.seek(SeekFrom::Start(sector as u64 * sector_size as u64)).await?;
+                data.write_all(bytemuck::bytes_of(&bpb)).await?;
+                if let Some(bpb32) = &bpb32 {
+                    data.write_all(bytemuck::bytes_of(bpb32)).await?;
+                }
+            }
+        }
+
+        // Remount with the new geometry.
+        self.fat = self.fat.with_geometry(new_fat_size, new_max);
+        let root_dir_start = fat_start + fat_count as usize * new_fat_size;
+        self.info.data_start = root_dir_start + root_dir_sectors as usize * sector_size;
+        self.info.max_cluster = new_max;
+        if let FatFsExt::Fat12_16(ext) = &mut self.ext {
+            ext.root_dir_start = root_dir_start;
+        }
+        if matches!(self.ext, FatFsExt::Fat32(_)) {
+            let mut free = 0u32;
+            self.scan_entries(2, new_max, |_, value| {
+                if value == 0 {
+                    free += 1;
+                }
+            })
+            .await?;
+            if let FatFsExt::Fat32(ext) = &self.ext {
+                ext.free_count.set(free);
+                if !(2..=new_max).contains(&ext.next_free.get().0) {
+                    ext.next_free.set(Cluster(2));
+                }
+            }
+        }
+        #[cfg(feature = "cache")]
+        if let Some(capacity) = cache_capacity {
+            let (start, size, count) = self.fat.layout();
+            self.fat_cache = Some(spin::Mutex::new(crate::cache::FatSectorCache::new(
+                start,
+                size,
+                count,
+                sector_size,
+                capacity,
+            )));
+        }
+        self.sync().await?;
+        Ok(relocated)
+    }
+
+    /// Move every allocated cluster above `new_max` into free clusters at or
+    /// below it, returning how many moved.
+    ///
+    /// Fails with [`Error::NoFreeSpace`] before writing anything if the free
+    /// space below `new_max` is too small.
+    async fn relocate_tail(&mut self, new_max: u32) -> Result<u32> {
+        let old_max = self.fat.max_cluster();
+        let mut moving = Vec::new();
+        self.scan_entries(new_max + 1, old_max, |cluster, value| {
+            if self.fat.is_allocated(value) {
+                moving.push(cluster);
+            }
+        })
+        .await?;
+        if moving.is_empty() {
+            return Ok(0);
+        }
+        let mut free = 0usize;
+        self.scan_entries(2, new_max, |_, value| {
+            if value == 0 {
+                free += 1;
+            }
+        })
+        .await?;
+        if free < moving.len() {
+            return Err(Error::NoFreeSpace);
+        }
+
+        // Allocating against the shrunk bound only hands out clusters that
+        // survive the resize.
+        let (_, fat_size, _) = self.fat.layout();
+        let target = self.fat.with_geometry(fat_size, new_max);
+        let mut relocation = BTreeMap::new();
+        {
+            let mut data = self.data.lock();
+            let cluster_size = data.cluster_size;
+            let mut buf = alloc::vec![0u8; cluster_size];
+            let mut hint = 2;
+            for &old in &moving {
+                let new = target.allocate_cluster(data.deref_mut(), hint).await?;
+                hint = new + 1;
+                let from = Cluster(old as usize).to_bytes(self.info.data_start, cluster_size);
+                let to = Cluster(new as usize).to_bytes(self.info.data_start, cluster_size);
+                data.seek(SeekFrom::Start(from as u64)).await?;
+                data.read_exact(&mut buf).await?;
+                data.seek(SeekFrom::Start(to as u64)).await?;
+                data.write_all(&buf).await?;
+                relocation.insert(old, new);
+            }
+            // Carry each link over, following successors that moved too.
+            for (&old, &new) in &relocation {
+                let next = self.fat.read_entry(data.deref_mut(), old as usize).await?;
+                let next = relocation.get(&next).copied().unwrap_or(next);
+                self.fat.write_entry(data.deref_mut(), new as usize, next).await?;
+            }
+        }
+
+        // Chains that run from a surviving cluster into a moved one.
+        let mut links = Vec::new();
+        self.scan_entries(2, new_max, |cluster, value| {
+            if let Some(&new) = relocation.get(&value) {
+                links.push((cluster, new));
+            }
+        })
+        .await?;
+        {
+            let mut data = self.data.lock();
+            for (cluster, new) in links {
+                self.fat.write_entry(data.deref_mut(), cluster as usize, new).await?;
+            }
+            for &old in relocation.keys() {
+                self.fat.write_entry(data.deref_mut(), old as usize, 0).await?;
+            }
+        }
+
+        if let FatFsExt::Fat32(ext) = &mut self.ext
+            && let Some(&new) = relocation.get(&ext.root_clus.0)
+        {
+            ext.root_clus = Cluster(new);
+        }
+        self.relink_entries(&relocation).await?;
+        Ok(relocation.len() as u32)
+    }
+
+    /// Rewrite the first cluster of every directory entry (including `.`
+    /// and `..`) that starts in a relocated cluster.
+    async fn relink_entries(&self, relocation: &BTreeMap<u32, u32>) -> Result<()> {
+        let mut pending = alloc::vec![self.root_dir()];
+        let mut visited = BTreeSet::new();
+        while let Some(dir) = pending.pop() {
+            let mut entries = dir.entries();
+            while let Some(entry) = entries.next_entry().await {
+                let DirectoryEntry::Entry(entry) = entry?;
+                let mut cluster = entry.cluster().0 as u32;
+                if let Some(&new) = relocation.get(&cluster) {
+                    self.set_first_cluster(&entry, new).await?;
+                    cluster = new;
+                }
+                let name = entry.name();
+                if entry.is_directory()
+                    && name != "."
+                    && name != ".."
+                    && cluster >= 2
+                    && visited.insert(cluster)
+                {
+                    pending.push(FatDir {
+                        data: self,
+                        cluster: Cluster(cluster as usize),
+                        fixed_root: None,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Point `entry`'s on-disk directory entry at `cluster`.
+    pub(crate) async fn set_first_cluster(&self, entry: &FileEntry, cluster: u32) -> Result<()> {
+        let mut data = self.data.lock();
+        let position = if entry.parent_clus.0 == 0 {
+            let (root_start, _) = self
+                .fixed_root_dir_info()
+                .expect("Fixed root info required for cluster 0");
+            root_start + entry.offset_within_cluster
+        } else {
+            entry
+                .parent_clus
+                .to_bytes(self.info.data_start, data.cluster_size)
+                + entry.offset_within_cluster
+        };
+        data.seek(SeekFrom::Start(position as u64)).await?;
+        let mut raw = data.read_struct::<RawDirectoryEntry>().await?;
+        let file = unsafe { &mut raw.file };
+        let high = if self.fat.fat_type() == FatType::Fat32 {
+            (cluster >> 16) as u16
+        } else {
+            0
+        };
+        file.first_cluster_high = U16::<LittleEndian>::new(high);
+        file.first_cluster_low = U16::<LittleEndian>::new(cluster as u16);
+        data.seek(SeekFrom::Start(position as u64)).await?;
+        data.write_all(bytemuck::bytes_of(&raw)).await?;
+        Ok(())
+    }
+
+    /// Call `visit(cluster, value)` for the primary-copy FAT entries
+    /// `first..=last`, reading the table in chunks. FAT16/FAT32 only.
+    async fn scan_entries<F: FnMut(u32, u32)>(&self, first: u32, last: u32, mut visit: F) -> Result<()> {
+        let (start, _, _) = self.fat.layout();
+        let width = if self.fat.fat_type() == FatType::Fat32 { 4 } else { 2 };
+        let mut buf = alloc::vec![0u8; COPY_CHUNK];
+        let mut data = self.data.lock();
+        let mut cluster = first;
+        while cluster <= last {
+            let count = ((last - cluster) as usize + 1).min(COPY_CHUNK / width);
+            let bytes = &mut buf[..count * width];
+            data.seek(SeekFrom::Start((start + cluster as usize * width) as u64)).await?;
+            data.read_exact(bytes).await?;
+            for (index, raw) in bytes.chunks_exact(width).enumerate() {
+                let value = if width == 4 {
+                    u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) & 0x0FFF_FFFF
+                } else {
+                    u16::from_le_bytes([raw[0], raw[1]]) as u32
+                };
+                visit(cluster + index as u32, value);
+            }
+            cluster += count as u32;
+        }
+        Ok(())
+    }
+}
+
+/// Copy `len` bytes from `from` to `to`, choosing the direction so an
+/// overlapping destination never clobbers unread source bytes.
+async fn move_bytes<T: Read + Write + Seek>(
+    data: &mut T,
+    from: usize,
+    to: usize,
+    len: usize,
+    buf: &mut [u8],
+) -> Result<()> {
+    let mut done = 0;
+    while done < len {
+        let count = (len - done).min(buf.len());
+        // Moving up: copy the highest chunk first.
+        let offset = if to > from { len - done - count } else { done };
+        data.seek(SeekFrom::Start((from + offset) as u64)).await?;
+        data.read_exact(&mut buf[..count]).await?;
+        data.seek(SeekFrom::Start((to + offset) as u64)).await?;
+        data.write_all(&buf[..count]).await?;
+        done += count;
+    }
+    Ok(())
+}
+
+/// Overwrite `len` bytes at `at` with zeros.
+async fn zero_bytes<T: Write + Seek>(data: &mut T, at: usize, len: usize) -> Result<()> {
+    let zeros = [0u8; 512];
+    data.seek(SeekFrom::Start(at as u64)).await?;
+    let mut remaining = len;
+    while remaining > 0 {
+        let count = remaining.min(zeros.len());
+        data.write_all(&zeros[..count]).await?;
+        remaining -= count;
+    }
+    Ok(())
+}
+
+} // end io_transform!
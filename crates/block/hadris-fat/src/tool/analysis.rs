@@ -513,7 +513,7 @@ fn classify_fat32_entry(entry: u32) -> ClusterState {
 }
 
 /// Count the number of contiguous fragments in a cluster chain.
-fn count_fragments(chain: &[u32]) -> u32 {
+pub(super) fn count_fragments(chain: &[u32]) -> u32 {
     if chain.is_empty() {
         return 0;
     }
@@ -0,0 +1,414 @@
+//! Defragmentation for FAT filesystems.
+//!
+//! [`FatDefragExt::defragment`] finds the fragmented chains that
+//! [`fragmentation_report`](crate::FatAnalysisExt::fragmentation_report)
+//! counts, plus fragmented directories, and moves each one into the first
+//! free run of clusters long enough to hold it. A chain is copied and linked
+//! at its new location before its directory entry is repointed, and its old
+//! clusters are freed last, so stopping between chains always leaves a
+//! consistent volume. That makes the pass safe to run in slices with
+//! [`DefragOptions::max_clusters`] or by stopping from the progress callback.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::{ControlFlow, DerefMut};
+
+use super::super::{
+    dir::{DirectoryEntry, FatDir, FileEntry},
+    fat_table::FatType,
+    fs::FatVolume,
+    io::{Cluster, ClusterLike, Read, Seek, SeekFrom, Write},
+};
+use super::analysis::{FileFragmentInfo, MAX_DIRECTORY_DEPTH, count_fragments};
+use super::repair::{DOT, DOTDOT, drop_fat_cache, end_of_chain, read_table, table_len};
+use crate::error::{Error, Result};
+
+/// Options for [`FatDefragExt::defragment`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefragOptions {
+    /// Stop once this many clusters have been moved. The chain in progress
+    /// is always finished, so every call moves at least one chain if it can.
+    /// `None` runs until every fragmented chain has been handled.
+    pub max_clusters: Option<u32>,
+    /// Move only file chains and leave directories alone.
+    pub files_only: bool,
+}
+
+impl DefragOptions {
+    /// Create options that defragment files and directories in one pass.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop after moving this many clusters.
+    pub fn max_clusters(mut self, clusters: u32) -> Self {
+        self.max_clusters = Some(clusters);
+        self
+    }
+
+    /// Move only file chains.
+    pub fn files_only(mut self, files_only: bool) -> Self {
+        self.files_only = files_only;
+        self
+    }
+}
+
+/// Progress reported after each chain is moved.
+#[derive(Debug, Clone, Copy)]
+pub struct DefragProgress<'a> {
+    /// The chain just moved, as it was before the move.
+    pub chain: &'a FileFragmentInfo,
+    /// Whether the chain belongs to a directory.
+    pub directory: bool,
+    /// Chains moved so far in this pass, including this one.
+    pub chains_moved: u32,
+    /// Fragmented chains found when the pass started.
+    pub chains_total: u32,
+    /// Clusters moved so far in this pass.
+    pub clusters_moved: u32,
+}
+
+/// Result of a defragmentation pass.
+#[derive(Debug, Clone, Default)]
+pub struct DefragReport {
+    /// Number of file chains made contiguous
+    pub files_moved: u32,
+    /// Number of directory chains made contiguous
+    pub directories_moved: u32,
+    /// Number of clusters copied to a new location
+    pub clusters_moved: u32,
+    /// Fragmented chains left in place because no free run was long enough
+    pub skipped: Vec<FileFragmentInfo>,
+    /// Fragmented chains not reached before the budget ran out or the
+    /// progress callback stopped the pass
+    pub remaining: u32,
+}
+
+impl DefragReport {
+    /// Whether the pass handled every fragmented chain it found.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+/// Extension trait for FatVolume providing defragmentation.
+pub trait FatDefragExt<DATA: Read + Write + Seek> {
+    /// Make fragmented file and directory chains contiguous.
+    ///
+    /// Files are moved first, most fragmented first, then directories from
+    /// the deepest up. Moving a directory also repoints its `.` entry and the
+    /// `..` entries of its subdirectories. The root directory stays where it
+    /// is. Chains with invalid links or loops are left alone; run
+    /// [`repair`](crate::FatRepairExt::repair) first on a damaged
+    /// volume.
+    ///
+    /// `progress` is called after each move; returning
+    /// [`ControlFlow::Break`] ends the pass there. A later call picks up the
+    /// chains that are still fragmented.
+    fn defragment<F>(&self, options: &DefragOptions, progress: F) -> Result<DefragReport>
+    where
+        F: FnMut(&DefragProgress<'_>) -> ControlFlow<()>;
+}
+
+impl<DATA: Read + Write + Seek> FatDefragExt<DATA> for FatVolume<DATA> {
+    fn defragment<F>(&self, options: &DefragOptions, mut progress: F) -> Result<DefragReport>
+    where
+        F: FnMut(&DefragProgress<'_>) -> ControlFlow<()>,
+    {
+        // The table is read and written directly below.
+        drop_fat_cache(self)?;
+        let fat_type = self.fat.fat_type();
+        let mut table = read_table(self, fat_type, 0, table_len(self))?;
+
+        let mut targets = Vec::new();
+        self.collect_targets(&table, options.files_only, &mut targets)?;
+        // Files before directories, so no file entry moves under us; deeper
+        // directories before their parents for the same reason.
+        targets.sort_by(|a, b| {
+            a.directory.cmp(&b.directory).then_with(|| {
+                if a.directory {
+                    b.depth.cmp(&a.depth)
+                } else {
+                    b.info.fragments.cmp(&a.info.fragments)
+                }
+            })
+        });
+
+        let chains_total = targets.len() as u32;
+        let mut report = DefragReport::default();
+        for (index, target) in targets.iter().enumerate() {
+            if let Some(budget) = options.max_clusters
+                && report.clusters_moved > 0
+                && report.clusters_moved >= budget
+            {
+                report.remaining = chains_total - index as u32;
+                break;
+            }
+            let Some(start) = find_free_run(&table, target.chain.len()) else {
+                report.skipped.push(target.info.clone());
+                continue;
+            };
+            self.move_chain(fat_type, &mut table, target, start)?;
+
+            if target.directory {
+                report.directories_moved += 1;
+            } else {
+                report.files_moved += 1;
+            }
+            report.clusters_moved += target.chain.len() as u32;
+            let flow = progress(&DefragProgress {
+                chain: &target.info,
+                directory: target.directory,
+                chains_moved: report.files_moved + report.directories_moved,
+                chains_total,
+                clusters_moved: report.clusters_moved,
+            });
+            if flow.is_break() {
+                report.remaining = chains_total - index as u32 - 1;
+                break;
+            }
+        }
+
+        self.sync()?;
+        Ok(report)
+    }
+}
+
+/// A fragmented chain and the directory entry that owns it.
+struct Target {
+    info: FileFragmentInfo,
+    entry: FileEntry,
+    directory: bool,
+    depth: u32,
+    chain: Vec<u32>,
+}
+
+impl<DATA: Read + Write + Seek> FatVolume<DATA> {
+    /// Walk the tree and collect every fragmented chain with a clean
+    /// end-of-chain marker.
+    fn collect_targets(
+        &self,
+        table: &[u32],
+        files_only: bool,
+        targets: &mut Vec<Target>,
+    ) -> Result<()> {
+        let end = end_of_chain(self.fat.fat_type());
+        let mut pending = alloc::vec![(self.root_dir(), String::new(), 0u32)];
+        let mut visited = BTreeSet::new();
+        while let Some((dir, prefix, depth)) = pending.pop() {
+            for entry in dir.entries() {
+                let DirectoryEntry::Entry(entry) = entry?;
+                let name = entry.name();
+                if name == "." || name == ".." {
+                    continue;
+                }
+                let first = entry.cluster().0 as u32;
+                if first < 2 {
+                    continue;
+                }
+                let path = format!("{prefix}/{name}");
+                let directory = entry.is_directory();
+                if directory && visited.insert(first) {
+                    if depth >= MAX_DIRECTORY_DEPTH {
+                        return Err(Error::CorruptFilesystem {
+                            context: "directory nesting depth limit exceeded",
+                        });
+                    }
+                    let subdir = FatDir {
+                        data: self,
+                        cluster: Cluster(first as usize),
+                        fixed_root: None,
+                    };
+                    pending.push((subdir, path.clone(), depth + 1));
+                }
+                if directory && files_only {
+                    continue;
+                }
+                let Some(chain) = chain_of(table, end, first) else {
+                    continue;
+                };
+                let fragments = count_fragments(&chain);
+                if fragments < 2 {
+                    continue;
+                }
+                let size = if directory {
+                    chain.len() * self.info.cluster_size
+                } else {
+                    entry.len() as usize
+                };
+                targets.push(Target {
+                    info: FileFragmentInfo {
+                        path,
+                        size,
+                        fragments,
+                        first_cluster: first,
+                    },
+                    entry,
+                    directory,
+                    depth,
+                    chain,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy `target`'s chain to the free run at `start`, repoint its entry,
+    /// and free the old clusters.
+    fn move_chain(
+        &self,
+        fat_type: FatType,
+        table: &mut [u32],
+        target: &Target,
+        start: u32,
+    ) -> Result<()> {
+        let len = target.chain.len() as u32;
+        {
+            let mut data = self.data.lock();
+            let cluster_size = data.cluster_size;
+            let mut buf = alloc::vec![0u8; cluster_size];
+            for (new, &old) in (start..).zip(&target.chain) {
+                let from = Cluster(old as usize).to_bytes(self.info.data_start, cluster_size);
+                let to = Cluster(new as usize).to_bytes(self.info.data_start, cluster_size);
+                data.seek(SeekFrom::Start(from as u64))?;
+                data.read_exact(&mut buf)?;
+                data.seek(SeekFrom::Start(to as u64))?;
+                data.write_all(&buf)?;
+            }
+            for new in start..start + len {
+                let next = if new + 1 < start + len {
+                    new + 1
+                } else {
+                    end_of_chain(fat_type)
+                };
+                self.fat.write_entry(data.deref_mut(), new as usize, next)?;
+                table[new as usize] = next;
+            }
+        }
+        // The directory walks below read the FAT through the cache.
+        drop_fat_cache(self)?;
+
+        self.set_first_cluster(&target.entry, start)?;
+        if target.directory {
+            self.relink_moved_directory(start)?;
+        }
+
+        {
+            let mut data = self.data.lock();
+            for &old in &target.chain {
+                self.fat.write_entry(data.deref_mut(), old as usize, 0)?;
+                table[old as usize] = 0;
+            }
+        }
+        drop_fat_cache(self)
+    }
+
+    /// Point the `.` entry of the directory now at `start`, and the `..`
+    /// entries of its subdirectories, at `start`.
+    fn relink_moved_directory(&self, start: u32) -> Result<()> {
+        let cluster_size = self.info.cluster_size;
+        let dot = Cluster(start as usize).to_bytes(self.info.data_start, cluster_size);
+        self.set_dot_entry(dot, &DOT, start)?;
+
+        let dir = FatDir {
+            data: self,
+            cluster: Cluster(start as usize),
+            fixed_root: None,
+        };
+        let mut children = Vec::new();
+        for entry in dir.entries() {
+            let DirectoryEntry::Entry(entry) = entry?;
+            let name = entry.name();
+            let child = entry.cluster().0;
+            if entry.is_directory() && name != "." && name != ".." && child >= 2 {
+                children.push(child);
+            }
+        }
+        for child in children {
+            let dotdot = Cluster(child).to_bytes(self.info.data_start, cluster_size) + 32;
+            self.set_dot_entry(dotdot, &DOTDOT, start)?;
+        }
+        Ok(())
+    }
+
+    /// Set the first cluster of the slot at `position` if it is the dot
+    /// entry named `name`.
+    fn set_dot_entry(&self, position: usize, name: &[u8; 11], cluster: u32) -> Result<()> {
+        let mut data = self.data.lock();
+        let mut slot = [0u8; 32];
+        data.seek(SeekFrom::Start(position as u64))?;
+        data.read_exact(&mut slot)?;
+        if &slot[..11] != name {
+            return Ok(());
+        }
+        let high = if self.fat.fat_type() == FatType::Fat32 {
+            (cluster >> 16) as u16
+        } else {
+            0
+        };
+        slot[20..22].copy_from_slice(&high.to_le_bytes());
+        slot[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+        data.seek(SeekFrom::Start(position as u64))?;
+        data.write_all(&slot)?;
+        Ok(())
+    }
+}
+
+/// The chain starting at `first`, or `None` if it runs off the table, into
+/// a free or reserved value, or around a loop.
+fn chain_of(table: &[u32], end: u32, first: u32) -> Option<Vec<u32>> {
+    let mut chain = Vec::new();
+    let mut current = first;
+    loop {
+        if current < 2 || current as usize >= table.len() || chain.len() >= table.len() {
+            return None;
+        }
+        chain.push(current);
+        let next = table[current as usize];
+        if next >= end {
+            return Some(chain);
+        }
+        current = next;
+    }
+}
+
+/// The first cluster of the lowest run of `len` free clusters.
+fn find_free_run(table: &[u32], len: usize) -> Option<u32> {
+    let mut run = 0;
+    for (cluster, &value) in table.iter().enumerate().skip(2) {
+        if value != 0 {
+            run = 0;
+            continue;
+        }
+        run += 1;
+        if run == len {
+            return Some((cluster + 1 - len) as u32);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_free_run() {
+        // Entries 0 and 1 are reserved; 3 and 6 are in use.
+        let table = [0xFFF8, 0xFFFF, 0, 0xFFFF, 0, 0, 0xFFFF, 0, 0, 0];
+        assert_eq!(find_free_run(&table, 1), Some(2));
+        assert_eq!(find_free_run(&table, 2), Some(4));
+        assert_eq!(find_free_run(&table, 3), Some(7));
+        assert_eq!(find_free_run(&table, 4), None);
+    }
+
+    #[test]
+    fn test_chain_of_rejects_loops() {
+        let table = [0xFFF8, 0xFFFF, 4, 0xFFFF, 3, 2];
+        assert_eq!(chain_of(&table, 0xFFF8, 2), Some(alloc::vec![2, 4, 3]));
+        let looped = [0xFFF8, 0xFFFF, 3, 2];
+        assert_eq!(chain_of(&looped, 0xFFF8, 2), None);
+    }
+}
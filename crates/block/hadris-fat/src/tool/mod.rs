@@ -1,10 +1,19 @@
 //! Analysis and diagnostic tools for FAT filesystems.
 //!
 //! This module provides utilities for examining and verifying FAT filesystems,
-//! including statistics gathering, fragmentation analysis, and integrity checking.
+//! including statistics gathering, fragmentation analysis, integrity checking,
+//! and (with `write`) repair and defragmentation.
 
 pub mod analysis;
+#[cfg(feature = "write")]
+pub mod defrag;
+#[cfg(feature = "write")]
+pub mod repair;
 pub mod verify;
 
 pub use analysis::{ClusterState, FatStatistics, FileFragmentInfo, FragmentationReport};
+#[cfg(feature = "write")]
+pub use defrag::{DefragOptions, DefragProgress, DefragReport};
+#[cfg(feature = "write")]
+pub use repair::{LostChainAction, RepairAction, RepairOptions, RepairReport};
 pub use verify::{VerificationIssue, VerificationReport};
@@ -0,0 +1,1266 @@
+//! Filesystem repair for FAT filesystems.
+//!
+//! [`FatRepairExt::repair`] walks the FAT and the directory tree, plans a fix
+//! for every inconsistency it can resolve, and applies the plan unless
+//! [`RepairOptions::dry_run`] is set. Planning works on an in-memory copy of
+//! the FAT, so the dry-run report lists exactly the changes a real run makes.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::DerefMut;
+
+use super::super::{
+    fat_table::FatType,
+    fs::{FatFsExt, FatVolume},
+    io::{Cluster, ClusterLike, Read, Seek, SeekFrom, Write},
+};
+use crate::error::{Error, Result};
+use crate::raw::DirEntryAttrFlags;
+
+/// Size of one directory slot.
+const SLOT_SIZE: usize = 32;
+/// First name byte of a deleted directory slot.
+const DELETED: u8 = 0xE5;
+/// Sequence-number bit marking the first (highest) LFN slot of a name.
+const LAST_LFN: u8 = 0x40;
+/// A long name spans at most 20 LFN slots (255 UTF-16 units).
+const MAX_LFN_SLOTS: u8 = 20;
+/// Raw names of the `.` and `..` entries.
+pub(super) const DOT: [u8; 11] = *b".          ";
+pub(super) const DOTDOT: [u8; 11] = *b"..         ";
+/// Lost chains recovered per `FOUND.nnn` directory (`FILE0000`-`FILE9999`);
+/// any beyond that are freed.
+const MAX_RECOVERED: usize = 10_000;
+/// Bytes moved per read/write pair when copying a FAT.
+const COPY_CHUNK: usize = 64 * 1024;
+
+/// What to do with cluster chains that are allocated in the FAT but not
+/// referenced by any directory entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LostChainAction {
+    /// Link each chain into a `FILEnnnn.CHK` file in a new `FOUND.nnn`
+    /// directory in the root, as `chkdsk` does.
+    #[default]
+    Recover,
+    /// Mark the chains free.
+    Free,
+}
+
+/// Options for [`FatRepairExt::repair`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RepairOptions {
+    /// Plan the repair and report it without writing anything.
+    pub dry_run: bool,
+    /// How lost cluster chains are resolved.
+    pub lost_chains: LostChainAction,
+}
+
+impl RepairOptions {
+    /// Options that apply every fix and recover lost chains.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only plan the repair.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Set how lost cluster chains are resolved.
+    pub fn lost_chains(mut self, action: LostChainAction) -> Self {
+        self.lost_chains = action;
+        self
+    }
+}
+
+/// A single change planned (or made) by a repair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairAction {
+    /// The FAT copies disagreed; `source` was copied over the others.
+    SyncFatCopies {
+        /// Index of the FAT copy kept (0 is the primary)
+        source: usize,
+        /// Number of entries that differed between the copies
+        mismatched_entries: u32,
+    },
+
+    /// A chain looped back on itself and was ended at `cluster`.
+    BreakClusterLoop {
+        /// File or directory path owning the chain
+        path: String,
+        /// The cluster that now ends the chain
+        cluster: u32,
+    },
+
+    /// A chain linked to a free, bad, or out-of-range cluster and was ended
+    /// at `cluster`.
+    TruncateBrokenChain {
+        /// File or directory path owning the chain
+        path: String,
+        /// The cluster that now ends the chain
+        cluster: u32,
+        /// The invalid link that followed it
+        next: u32,
+    },
+
+    /// A chain ran into a cluster owned by another entry. The later entry
+    /// loses the shared clusters, which stay with `owner`.
+    ResolveCrossLink {
+        /// Path of the entry that was cut
+        path: String,
+        /// The first shared cluster
+        cluster: u32,
+        /// Path of the entry that keeps the shared clusters
+        owner: String,
+    },
+
+    /// A file's first cluster was invalid; the file was emptied.
+    ClearFirstCluster {
+        /// File path
+        path: String,
+        /// The invalid cluster number
+        cluster: u32,
+    },
+
+    /// A directory entry had no usable cluster chain and was removed.
+    RemoveDirectory {
+        /// Directory path
+        path: String,
+        /// The invalid (or cross-linked) first cluster
+        cluster: u32,
+    },
+
+    /// A file's recorded size exceeded its chain and was reduced.
+    FixFileSize {
+        /// File path
+        path: String,
+        /// Size recorded in the directory entry
+        recorded_size: u32,
+        /// Size written back
+        new_size: u32,
+    },
+
+    /// A file's chain was longer than its size needs; the excess clusters
+    /// were freed.
+    TrimChain {
+        /// File path
+        path: String,
+        /// Clusters kept
+        kept: u32,
+        /// Clusters freed
+        freed: u32,
+    },
+
+    /// A `.` or `..` entry pointed at the wrong cluster.
+    FixDotEntry {
+        /// Path of the directory containing the entry
+        path: String,
+        /// `"."` or `".."`
+        name: &'static str,
+        /// Cluster stored in the entry
+        found: u32,
+        /// Cluster written back
+        expected: u32,
+    },
+
+    /// Long-filename slots not attached to a valid short entry were deleted.
+    RemoveOrphanedLfn {
+        /// Path of the directory containing the slots
+        path: String,
+        /// Number of slots deleted
+        entries: u32,
+    },
+
+    /// A lost chain was linked into a recovery file.
+    RecoverLostChain {
+        /// First cluster of the chain
+        start_cluster: u32,
+        /// Length of the chain in clusters
+        chain_length: u32,
+        /// Path of the recovery file
+        recovered_as: String,
+    },
+
+    /// A lost chain was freed.
+    FreeLostChain {
+        /// First cluster of the chain
+        start_cluster: u32,
+        /// Length of the chain in clusters
+        chain_length: u32,
+    },
+
+    /// The dirty and/or I/O-error volume flags in `FAT[1]` were cleared.
+    ClearVolumeFlags {
+        /// The volume was marked dirty
+        dirty: bool,
+        /// The volume was marked as having had I/O errors
+        io_errors: bool,
+    },
+}
+
+impl core::fmt::Display for RepairAction {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::SyncFatCopies {
+                source,
+                mismatched_entries,
+            } => {
+                write!(
+                    f,
+                    "Copy FAT {source} over the other copies ({mismatched_entries} entries differ)"
+                )
+            }
+            Self::BreakClusterLoop { path, cluster } => {
+                write!(f, "End looping chain of '{path}' at cluster {cluster}")
+            }
+            Self::TruncateBrokenChain {
+                path,
+                cluster,
+                next,
+            } => {
+                write!(
+                    f,
+                    "End chain of '{path}' at cluster {cluster} (invalid link to {next})"
+                )
+            }
+            Self::ResolveCrossLink {
+                path,
+                cluster,
+                owner,
+            } => {
+                write!(
+                    f,
+                    "Cut '{path}' before cluster {cluster}, which is shared with '{owner}'"
+                )
+            }
+            Self::ClearFirstCluster { path, cluster } => {
+                write!(f, "Empty '{path}' (invalid first cluster {cluster})")
+            }
+            Self::RemoveDirectory { path, cluster } => {
+                write!(
+                    f,
+                    "Remove directory '{path}' (unusable first cluster {cluster})"
+                )
+            }
+            Self::FixFileSize {
+                path,
+                recorded_size,
+                new_size,
+            } => {
+                write!(
+                    f,
+                    "Set size of '{path}' from {recorded_size} to {new_size} bytes"
+                )
+            }
+            Self::TrimChain { path, kept, freed } => {
+                write!(
+                    f,
+                    "Free {freed} excess clusters of '{path}' (keeping {kept})"
+                )
+            }
+            Self::FixDotEntry {
+                path,
+                name,
+                found,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "Point '{name}' in '{path}' at cluster {expected} (was {found})"
+                )
+            }
+            Self::RemoveOrphanedLfn { path, entries } => {
+                write!(f, "Delete {entries} orphaned long-name slots in '{path}'")
+            }
+            Self::RecoverLostChain {
+                start_cluster,
+                chain_length,
+                recovered_as,
+            } => {
+                write!(
+                    f,
+                    "Recover lost chain at {start_cluster} ({chain_length} clusters) as '{recovered_as}'"
+                )
+            }
+            Self::FreeLostChain {
+                start_cluster,
+                chain_length,
+            } => {
+                write!(
+                    f,
+                    "Free lost chain at {start_cluster} ({chain_length} clusters)"
+                )
+            }
+            Self::ClearVolumeFlags { dirty, io_errors } => match (dirty, io_errors) {
+                (true, true) => write!(f, "Clear the dirty and I/O-error volume flags"),
+                (true, false) => write!(f, "Clear the dirty volume flag"),
+                _ => write!(f, "Clear the I/O-error volume flag"),
+            },
+        }
+    }
+}
+
+/// Report from a repair.
+#[derive(Debug, Clone)]
+pub struct RepairReport {
+    /// Changes planned (dry run) or made, in the order they were found
+    pub actions: Vec<RepairAction>,
+    /// Whether this was a dry run and nothing was written
+    pub dry_run: bool,
+}
+
+impl RepairReport {
+    /// Check if the filesystem needed no repair.
+    pub fn is_clean(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Get the number of changes.
+    pub fn action_count(&self) -> usize {
+        self.actions.len()
+    }
+}
+
+/// Extension trait for FatVolume providing repair operations.
+pub trait FatRepairExt<DATA: Read + Write + Seek> {
+    /// Check the filesystem and fix what is inconsistent.
+    ///
+    /// This resolves:
+    /// - Mismatched FAT copies (the copy with fewer invalid links wins, the
+    ///   primary on a tie)
+    /// - Cluster loops, links to free/bad/out-of-range clusters, and
+    ///   cross-linked chains (the entry found first keeps shared clusters)
+    /// - Invalid first clusters and size/chain-length mismatches
+    /// - `.` and `..` entries pointing at the wrong cluster
+    /// - Long-filename slots not attached to a valid short entry
+    /// - Lost cluster chains, per [`RepairOptions::lost_chains`]
+    /// - The dirty and I/O-error flags in `FAT[1]`
+    ///
+    /// Repair writes metadata in several steps and is not crash-safe; take
+    /// a backup of images you cannot recreate, or start with a dry run.
+    fn repair(&self, options: &RepairOptions) -> Result<RepairReport>;
+}
+
+impl<DATA: Read + Write + Seek> FatRepairExt<DATA> for FatVolume<DATA> {
+    fn repair(&self, options: &RepairOptions) -> Result<RepairReport> {
+        // The FAT is read and written directly below; push out and drop any
+        // cached sectors so neither side sees stale data.
+        drop_fat_cache(self)?;
+
+        let mut repair = Repair::load(self)?;
+        repair.walk()?;
+        repair.collect_lost_chains(options.lost_chains)?;
+        repair.check_volume_flags();
+        if !options.dry_run && !repair.actions.is_empty() {
+            repair.apply()?;
+        }
+        Ok(RepairReport {
+            actions: repair.actions,
+            dry_run: options.dry_run,
+        })
+    }
+}
+
+/// A pending rewrite of one directory slot.
+#[derive(Debug, Clone, Copy, Default)]
+struct SlotEdit {
+    delete: bool,
+    first_cluster: Option<u32>,
+    size: Option<u32>,
+}
+
+/// Where a directory's slots live.
+enum DirLocation {
+    /// FAT12/16 fixed root region: `(start_byte, size_bytes)`.
+    Fixed(usize, usize),
+    /// The (already repaired) cluster chain of a cluster-based directory.
+    Chain(Vec<u32>),
+}
+
+/// A directory waiting to be scanned.
+struct PendingDir {
+    path: String,
+    /// First cluster, 0 for a FAT12/16 root.
+    cluster: u32,
+    /// First cluster of the parent, 0 when the parent is the root.
+    parent: u32,
+    location: DirLocation,
+    is_root: bool,
+}
+
+/// How a directory entry's chain could be claimed.
+enum Claim {
+    /// The chain's clusters, after truncating any loop or bad link.
+    Chain(Vec<u32>),
+    /// The first cluster is out of range, free, or bad.
+    Invalid,
+    /// The first cluster already belongs to the named entry.
+    CrossLinked(String),
+}
+
+/// An LFN run being collected ahead of its short entry.
+#[derive(Default)]
+struct LfnRun {
+    positions: Vec<u64>,
+    /// Name units per slot, in disk order (highest sequence number first).
+    units: Vec<[u16; 13]>,
+    next_seq: u8,
+    checksum: u8,
+    valid: bool,
+}
+
+/// Repair state: the working FAT plus the planned fixes.
+struct Repair<'a, DATA: Read + Write + Seek> {
+    fs: &'a FatVolume<DATA>,
+    fat_type: FatType,
+    /// Highest cluster number covered by the loaded table.
+    max_cluster: u32,
+    cluster_size: usize,
+    /// FAT copy the working table was loaded from.
+    source_copy: usize,
+    /// Entries on which the FAT copies disagree.
+    mismatched_entries: u32,
+    /// The source copy as read from disk.
+    original: Vec<u32>,
+    /// The source copy with every planned fix applied.
+    table: Vec<u32>,
+    /// Per cluster: 0 when unowned, else an index + 1 into `owners`.
+    owner: Vec<u32>,
+    owners: Vec<String>,
+    /// Raw short names in the root, used to pick a free `FOUND.nnn`.
+    root_names: BTreeSet<[u8; 11]>,
+    edits: BTreeMap<u64, SlotEdit>,
+    /// Lost chains to link into recovery files, with their file names.
+    recovered: Vec<(String, Vec<u32>)>,
+    found_dir: Option<String>,
+    actions: Vec<RepairAction>,
+}
+
+impl<'a, DATA: Read + Write + Seek> Repair<'a, DATA> {
+    /// Read every FAT copy and pick the one to work from.
+    fn load(fs: &'a FatVolume<DATA>) -> Result<Self> {
+        let fat_type = fs.fat.fat_type();
+        let (_, _, count) = fs.fat.layout();
+        let entries = table_len(fs);
+        if entries < 3 {
+            return Err(Error::CorruptFilesystem {
+                context: "FAT holds no data clusters",
+            });
+        }
+
+        let mut copies = Vec::with_capacity(count);
+        for copy in 0..count {
+            copies.push(read_table(fs, fat_type, copy, entries)?);
+        }
+        let mut mismatched_entries = 0u32;
+        let mut source_copy = 0;
+        if copies.len() > 1 {
+            for index in 0..entries {
+                if copies.iter().any(|copy| copy[index] != copies[0][index]) {
+                    mismatched_entries += 1;
+                }
+            }
+            if mismatched_entries > 0 {
+                let max = entries as u32 - 1;
+                let invalid = |table: &Vec<u32>| {
+                    table[2..]
+                        .iter()
+                        .filter(|&&value| !is_valid_value(fat_type, max, value))
+                        .count()
+                };
+                // Keeps the lowest index on a tie, so the primary wins.
+                source_copy = (0..copies.len())
+                    .min_by_key(|&copy| invalid(&copies[copy]))
+                    .unwrap_or(0);
+            }
+        }
+        let original = copies.swap_remove(source_copy);
+
+        let mut repair = Self {
+            fs,
+            fat_type,
+            max_cluster: entries as u32 - 1,
+            cluster_size: fs.info.cluster_size,
+            source_copy,
+            mismatched_entries,
+            table: original.clone(),
+            original,
+            owner: alloc::vec![0; entries],
+            owners: Vec::new(),
+            root_names: BTreeSet::new(),
+            edits: BTreeMap::new(),
+            recovered: Vec::new(),
+            found_dir: None,
+            actions: Vec::new(),
+        };
+        if mismatched_entries > 0 {
+            repair.actions.push(RepairAction::SyncFatCopies {
+                source: source_copy,
+                mismatched_entries,
+            });
+        }
+        Ok(repair)
+    }
+
+    /// Walk the directory tree from the root, claiming every chain.
+    fn walk(&mut self) -> Result<()> {
+        let root = match &self.fs.ext {
+            FatFsExt::Fat12_16(ext) => PendingDir {
+                path: String::new(),
+                cluster: 0,
+                parent: 0,
+                location: DirLocation::Fixed(ext.root_dir_start, ext.root_dir_size),
+                is_root: true,
+            },
+            FatFsExt::Fat32(ext) => {
+                let cluster = ext.root_clus.0;
+                let Claim::Chain(chain) = self.claim("/", cluster) else {
+                    return Err(Error::CorruptFilesystem {
+                        context: "root directory cluster is invalid",
+                    });
+                };
+                PendingDir {
+                    path: String::new(),
+                    cluster,
+                    parent: 0,
+                    location: DirLocation::Chain(chain),
+                    is_root: true,
+                }
+            }
+        };
+
+        let mut pending = alloc::vec![root];
+        while let Some(dir) = pending.pop() {
+            self.check_directory(&dir, &mut pending)?;
+        }
+        Ok(())
+    }
+
+    /// Check every slot of `dir`, queueing its subdirectories.
+    fn check_directory(&mut self, dir: &PendingDir, pending: &mut Vec<PendingDir>) -> Result<()> {
+        let slots = self.read_slots(&dir.location)?;
+        let mut run = LfnRun::default();
+        let mut orphans = Vec::new();
+
+        for (position, raw) in slots {
+            if raw[0] == 0 {
+                break;
+            }
+            if raw[0] == DELETED {
+                orphans.append(&mut run.positions);
+                run = LfnRun::default();
+                continue;
+            }
+
+            let attr = raw[11];
+            if attr == DirEntryAttrFlags::LONG_NAME.bits() {
+                let seq = raw[0];
+                if seq & LAST_LFN != 0 {
+                    orphans.append(&mut run.positions);
+                    let count = seq & !LAST_LFN;
+                    run = LfnRun {
+                        positions: alloc::vec![position],
+                        units: alloc::vec![lfn_units(&raw)],
+                        next_seq: count.wrapping_sub(1),
+                        checksum: raw[13],
+                        valid: (1..=MAX_LFN_SLOTS).contains(&count),
+                    };
+                } else if run.valid
+                    && !run.positions.is_empty()
+                    && run.next_seq != 0
+                    && seq == run.next_seq
+                    && raw[13] == run.checksum
+                {
+                    run.positions.push(position);
+                    run.units.push(lfn_units(&raw));
+                    run.next_seq -= 1;
+                } else {
+                    orphans.append(&mut run.positions);
+                    orphans.push(position);
+                    run = LfnRun::default();
+                }
+                continue;
+            }
+
+            let mut name = [0u8; 11];
+            name.copy_from_slice(&raw[..11]);
+            if attr & DirEntryAttrFlags::VOLUME_ID.bits() != 0 {
+                // Volume labels never carry a long name.
+                orphans.append(&mut run.positions);
+                run = LfnRun::default();
+                continue;
+            }
+
+            let complete = run.valid
+                && !run.positions.is_empty()
+                && run.next_seq == 0
+                && run.checksum == lfn_checksum(&name);
+            let long_name = if complete {
+                Some(decode_lfn(&run.units))
+            } else {
+                orphans.append(&mut run.positions);
+                None
+            };
+            let lfn_slots = core::mem::take(&mut run.positions);
+            run = LfnRun::default();
+
+            if dir.is_root {
+                self.root_names.insert(name);
+            }
+            self.check_entry(dir, position, &raw, long_name, &lfn_slots, pending);
+        }
+        orphans.append(&mut run.positions);
+
+        if !orphans.is_empty() {
+            self.actions.push(RepairAction::RemoveOrphanedLfn {
+                path: display_path(&dir.path),
+                entries: orphans.len() as u32,
+            });
+            for position in orphans {
+                self.edits.entry(position).or_default().delete = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Check one short entry of `dir` stored at `position`.
+    fn check_entry(
+        &mut self,
+        dir: &PendingDir,
+        position: u64,
+        raw: &[u8; 32],
+        long_name: Option<String>,
+        lfn_slots: &[u64],
+        pending: &mut Vec<PendingDir>,
+    ) {
+        let name = &raw[..11];
+        let first = self.first_cluster(raw);
+
+        if name == DOT || name == DOTDOT {
+            // Stray dot entries in the root are left alone.
+            if dir.is_root {
+                return;
+            }
+            let is_dot = name == DOT;
+            let expected = if is_dot { dir.cluster } else { dir.parent };
+            // A `..` to the FAT32 root should store 0, but some writers
+            // store the root cluster itself.
+            let root_alias = !is_dot
+                && expected == 0
+                && matches!(&self.fs.ext, FatFsExt::Fat32(ext) if ext.root_clus.0 == first);
+            if first != expected && !root_alias {
+                self.actions.push(RepairAction::FixDotEntry {
+                    path: display_path(&dir.path),
+                    name: if is_dot { "." } else { ".." },
+                    found: first,
+                    expected,
+                });
+                self.edits.entry(position).or_default().first_cluster = Some(expected);
+            }
+            return;
+        }
+
+        let display = long_name.unwrap_or_else(|| short_name_display(raw));
+        let path = format!("{}/{}", dir.path, display);
+
+        if raw[11] & DirEntryAttrFlags::DIRECTORY.bits() != 0 {
+            let claim = if first == 0 {
+                Claim::Invalid
+            } else {
+                self.claim(&path, first)
+            };
+            match claim {
+                Claim::Chain(chain) => pending.push(PendingDir {
+                    path,
+                    cluster: first,
+                    // `..` stores 0 for the root, FAT32 included.
+                    parent: if dir.is_root { 0 } else { dir.cluster },
+                    location: DirLocation::Chain(chain),
+                    is_root: false,
+                }),
+                Claim::Invalid | Claim::CrossLinked(_) => {
+                    self.actions.push(RepairAction::RemoveDirectory {
+                        path,
+                        cluster: first,
+                    });
+                    for &slot in lfn_slots.iter().chain(core::iter::once(&position)) {
+                        self.edits.entry(slot).or_default().delete = true;
+                    }
+                }
+            }
+            return;
+        }
+
+        let chain = if first == 0 {
+            Vec::new()
+        } else {
+            match self.claim(&path, first) {
+                Claim::Chain(chain) => chain,
+                Claim::Invalid => {
+                    self.actions.push(RepairAction::ClearFirstCluster {
+                        path: path.clone(),
+                        cluster: first,
+                    });
+                    self.edits.entry(position).or_default().first_cluster = Some(0);
+                    Vec::new()
+                }
+                Claim::CrossLinked(owner) => {
+                    self.actions.push(RepairAction::ResolveCrossLink {
+                        path: path.clone(),
+                        cluster: first,
+                        owner,
+                    });
+                    self.edits.entry(position).or_default().first_cluster = Some(0);
+                    Vec::new()
+                }
+            }
+        };
+        self.check_size(&path, position, raw, &chain);
+    }
+
+    /// Reconcile a file's recorded size with its (claimed) chain.
+    fn check_size(&mut self, path: &str, position: u64, raw: &[u8; 32], chain: &[u32]) {
+        let recorded_size = u32::from_le_bytes([raw[28], raw[29], raw[30], raw[31]]);
+        let cluster_size = self.cluster_size as u64;
+        let clusters = chain.len() as u64;
+        let size = recorded_size as u64;
+
+        if size > clusters * cluster_size {
+            let new_size = (clusters * cluster_size).min(u32::MAX as u64) as u32;
+            self.actions.push(RepairAction::FixFileSize {
+                path: path.into(),
+                recorded_size,
+                new_size,
+            });
+            self.edits.entry(position).or_default().size = Some(new_size);
+        } else if clusters > 0 && size <= (clusters - 1) * cluster_size {
+            let kept = size.div_ceil(cluster_size) as usize;
+            for &cluster in &chain[kept..] {
+                self.table[cluster as usize] = 0;
+                self.owner[cluster as usize] = 0;
+            }
+            if kept == 0 {
+                self.edits.entry(position).or_default().first_cluster = Some(0);
+            } else {
+                self.table[chain[kept - 1] as usize] = self.end_of_chain();
+            }
+            self.actions.push(RepairAction::TrimChain {
+                path: path.into(),
+                kept: kept as u32,
+                freed: (chain.len() - kept) as u32,
+            });
+        }
+    }
+
+    /// Claim the chain starting at `first` for `path`, ending it early at
+    /// any loop, invalid link, or cluster owned by another entry.
+    fn claim(&mut self, path: &str, first: u32) -> Claim {
+        if !self.is_allocated_cluster(first) {
+            return Claim::Invalid;
+        }
+        if let Some(owner) = self.owner_of(first) {
+            return Claim::CrossLinked(owner.into());
+        }
+
+        self.owners.push(path.into());
+        let id = self.owners.len() as u32;
+        let mut chain = Vec::new();
+        let mut current = first;
+        loop {
+            self.owner[current as usize] = id;
+            chain.push(current);
+            let next = self.table[current as usize];
+            if next >= self.end_of_chain() {
+                break;
+            }
+            if self.is_allocated_cluster(next) {
+                match self.owner[next as usize] {
+                    0 => {
+                        current = next;
+                        continue;
+                    }
+                    owner if owner == id => {
+                        self.actions.push(RepairAction::BreakClusterLoop {
+                            path: path.into(),
+                            cluster: current,
+                        });
+                    }
+                    owner => {
+                        self.actions.push(RepairAction::ResolveCrossLink {
+                            path: path.into(),
+                            cluster: next,
+                            owner: self.owners[owner as usize - 1].clone(),
+                        });
+                    }
+                }
+            } else {
+                self.actions.push(RepairAction::TruncateBrokenChain {
+                    path: path.into(),
+                    cluster: current,
+                    next,
+                });
+            }
+            self.table[current as usize] = self.end_of_chain();
+            break;
+        }
+        Claim::Chain(chain)
+    }
+
+    /// Find allocated clusters no entry claimed and plan their recovery.
+    fn collect_lost_chains(&mut self, action: LostChainAction) -> Result<()> {
+        let mut remaining: BTreeSet<u32> = (2..=self.max_cluster)
+            .filter(|&cluster| {
+                self.owner[cluster as usize] == 0
+                    && is_in_use(self.fat_type, self.table[cluster as usize])
+            })
+            .collect();
+        if remaining.is_empty() {
+            return Ok(());
+        }
+
+        // Chain heads are lost clusters no other lost cluster links to.
+        // Whatever is left once they are walked sits on cycles.
+        let linked: BTreeSet<u32> = remaining
+            .iter()
+            .map(|&cluster| self.table[cluster as usize])
+            .filter(|next| remaining.contains(next))
+            .collect();
+        let mut starts: Vec<u32> = remaining.difference(&linked).copied().collect();
+        let mut chains = Vec::new();
+        loop {
+            for start in starts {
+                if !remaining.contains(&start) {
+                    continue;
+                }
+                let mut chain = Vec::new();
+                let mut current = start;
+                loop {
+                    remaining.remove(&current);
+                    chain.push(current);
+                    let next = self.table[current as usize];
+                    if remaining.contains(&next) {
+                        current = next;
+                        continue;
+                    }
+                    if next < self.end_of_chain() {
+                        self.table[current as usize] = self.end_of_chain();
+                    }
+                    break;
+                }
+                chains.push(chain);
+            }
+            match remaining.first() {
+                Some(&cycle) => starts = alloc::vec![cycle],
+                None => break,
+            }
+        }
+
+        if action == LostChainAction::Recover {
+            let found = (0..1000)
+                .map(|n| format!("FOUND.{n:03}"))
+                .find(|name| {
+                    let mut raw = *b"FOUND      ";
+                    raw[8..].copy_from_slice(&name.as_bytes()[6..]);
+                    !self.root_names.contains(&raw)
+                })
+                .ok_or(Error::AlreadyExists)?;
+            self.found_dir = Some(found);
+        }
+        for chain in chains {
+            let start_cluster = chain[0];
+            let chain_length = chain.len() as u32;
+            match &self.found_dir {
+                Some(found) if self.recovered.len() < MAX_RECOVERED => {
+                    let name = format!("FILE{:04}.CHK", self.recovered.len());
+                    self.actions.push(RepairAction::RecoverLostChain {
+                        start_cluster,
+                        chain_length,
+                        recovered_as: format!("/{found}/{name}"),
+                    });
+                    self.recovered.push((name, chain));
+                }
+                _ => {
+                    for &cluster in &chain {
+                        self.table[cluster as usize] = 0;
+                    }
+                    self.actions.push(RepairAction::FreeLostChain {
+                        start_cluster,
+                        chain_length,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Plan clearing the `FAT[1]` status flags.
+    fn check_volume_flags(&mut self) {
+        let (dirty, io_errors) = self.volume_flags(self.table[1]);
+        if dirty || io_errors {
+            self.actions
+                .push(RepairAction::ClearVolumeFlags { dirty, io_errors });
+        }
+    }
+
+    /// Write the plan to disk.
+    fn apply(&mut self) -> Result<()> {
+        let fs = self.fs;
+        // Create the recovery directory and its (still empty) files before
+        // touching the FAT, so a full root directory or volume fails the
+        // repair without leaving it half applied.
+        let mut recovered = Vec::new();
+        if let Some(found) = &self.found_dir
+            && !self.recovered.is_empty()
+        {
+            let dir = fs.create_dir(&fs.root_dir(), found)?;
+            for (name, chain) in &self.recovered {
+                let entry = fs.create_file(&dir, name)?;
+                let position = entry
+                    .parent_clus
+                    .to_bytes(fs.info.data_start, self.cluster_size)
+                    + entry.offset_within_cluster;
+                recovered.push((position as u64, chain[0], chain.len()));
+            }
+            // The raw writes below bypass the cache.
+            drop_fat_cache(fs)?;
+        }
+
+        {
+            let mut data = fs.data.lock();
+            if self.mismatched_entries > 0 {
+                let (start, size, count) = fs.fat.layout();
+                let mut buf = alloc::vec![0u8; COPY_CHUNK];
+                let from = start + self.source_copy * size;
+                for copy in (0..count).filter(|&copy| copy != self.source_copy) {
+                    let to = start + copy * size;
+                    let mut done = 0;
+                    while done < size {
+                        let len = (size - done).min(buf.len());
+                        data.seek(SeekFrom::Start((from + done) as u64))?;
+                        data.read_exact(&mut buf[..len])?;
+                        data.seek(SeekFrom::Start((to + done) as u64))?;
+                        data.write_all(&buf[..len])?;
+                        done += len;
+                    }
+                }
+            }
+
+            for cluster in 2..self.table.len() {
+                if self.table[cluster] != self.original[cluster] {
+                    fs.fat
+                        .write_entry(data.deref_mut(), cluster, self.table[cluster])?;
+                }
+            }
+
+            for (&position, edit) in &self.edits {
+                let mut raw = [0u8; SLOT_SIZE];
+                data.seek(SeekFrom::Start(position))?;
+                data.read_exact(&mut raw)?;
+                if edit.delete {
+                    raw[0] = DELETED;
+                }
+                if let Some(cluster) = edit.first_cluster {
+                    let high = if self.fat_type == FatType::Fat32 {
+                        (cluster >> 16) as u16
+                    } else {
+                        0
+                    };
+                    raw[20..22].copy_from_slice(&high.to_le_bytes());
+                    raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+                }
+                if let Some(size) = edit.size {
+                    raw[28..32].copy_from_slice(&size.to_le_bytes());
+                }
+                data.seek(SeekFrom::Start(position))?;
+                data.write_all(&raw)?;
+            }
+
+            for &(position, start, length) in &recovered {
+                let size = (length as u64 * self.cluster_size as u64).min(u32::MAX as u64);
+                let high = if self.fat_type == FatType::Fat32 {
+                    (start >> 16) as u16
+                } else {
+                    0
+                };
+                data.seek(SeekFrom::Start(position + 20))?;
+                data.write_all(&high.to_le_bytes())?;
+                data.seek(SeekFrom::Start(position + 26))?;
+                data.write_all(&(start as u16).to_le_bytes())?;
+                data.write_all(&(size as u32).to_le_bytes())?;
+            }
+        }
+
+        drop_fat_cache(fs)?;
+        {
+            let mut data = fs.data.lock();
+            let status = fs.fat.read_entry(data.deref_mut(), 1)?;
+            let clean = match self.fat_type {
+                FatType::Fat12 => status,
+                FatType::Fat16 => status | 0xC000,
+                FatType::Fat32 => status | 0x0C00_0000,
+            };
+            if clean != status {
+                fs.fat.write_entry(data.deref_mut(), 1, clean)?;
+            }
+        }
+        if let FatFsExt::Fat32(ext) = &fs.ext {
+            let table = read_table(fs, self.fat_type, 0, self.table.len())?;
+            let free = table[2..].iter().filter(|&&value| value == 0).count();
+            ext.free_count.set(free as u32);
+        }
+        fs.sync()
+    }
+
+    /// Read every slot of a directory with its byte position.
+    fn read_slots(&self, location: &DirLocation) -> Result<Vec<(u64, [u8; 32])>> {
+        let mut regions = Vec::new();
+        match location {
+            DirLocation::Fixed(start, size) => regions.push((*start, *size)),
+            DirLocation::Chain(chain) => {
+                for &cluster in chain {
+                    let start = Cluster(cluster as usize)
+                        .to_bytes(self.fs.info.data_start, self.cluster_size);
+                    regions.push((start, self.cluster_size));
+                }
+            }
+        }
+
+        let mut slots = Vec::new();
+        let mut data = self.fs.data.lock();
+        let mut buf = Vec::new();
+        for (start, size) in regions {
+            buf.resize(size, 0);
+            data.seek(SeekFrom::Start(start as u64))?;
+            data.read_exact(&mut buf)?;
+            for (index, raw) in buf.chunks_exact(SLOT_SIZE).enumerate() {
+                let mut slot = [0u8; SLOT_SIZE];
+                slot.copy_from_slice(raw);
+                slots.push(((start + index * SLOT_SIZE) as u64, slot));
+            }
+        }
+        Ok(slots)
+    }
+
+    /// First cluster stored in a short entry; the high word is FAT32-only.
+    fn first_cluster(&self, raw: &[u8; 32]) -> u32 {
+        let low = u16::from_le_bytes([raw[26], raw[27]]) as u32;
+        if self.fat_type == FatType::Fat32 {
+            let high = u16::from_le_bytes([raw[20], raw[21]]) as u32;
+            ((high << 16) | low) & 0x0FFF_FFFF
+        } else {
+            low
+        }
+    }
+
+    /// Path of the entry owning `cluster`, if any.
+    fn owner_of(&self, cluster: u32) -> Option<&str> {
+        match self.owner[cluster as usize] {
+            0 => None,
+            id => Some(&self.owners[id as usize - 1]),
+        }
+    }
+
+    /// Whether `cluster` is a data cluster whose FAT entry is in use.
+    fn is_allocated_cluster(&self, cluster: u32) -> bool {
+        (2..=self.max_cluster).contains(&cluster)
+            && is_in_use(self.fat_type, self.table[cluster as usize])
+    }
+
+    fn end_of_chain(&self) -> u32 {
+        end_of_chain(self.fat_type)
+    }
+
+    /// `(dirty, io_errors)` from a `FAT[1]` value; FAT12 has no flags.
+    fn volume_flags(&self, status: u32) -> (bool, bool) {
+        match self.fat_type {
+            FatType::Fat12 => (false, false),
+            FatType::Fat16 => (status & 0x8000 == 0, status & 0x4000 == 0),
+            FatType::Fat32 => (status & 0x0800_0000 == 0, status & 0x0400_0000 == 0),
+        }
+    }
+}
+
+/// Flush and empty the FAT-sector cache, if one is installed.
+pub(super) fn drop_fat_cache<DATA: Read + Write + Seek>(fs: &FatVolume<DATA>) -> Result<()> {
+    #[cfg(feature = "cache")]
+    if let Some(cache) = &fs.fat_cache {
+        let mut cache = cache.lock();
+        let mut data = fs.data.lock();
+        cache.clear(Some(data.deref_mut()))?;
+    }
+    #[cfg(not(feature = "cache"))]
+    let _ = fs;
+    Ok(())
+}
+
+/// Number of FAT entries to load: one per cluster up to the maximum, or
+/// fewer if a corrupt BPB claims more clusters than the FAT holds.
+pub(super) fn table_len<DATA: Read + Write + Seek>(fs: &FatVolume<DATA>) -> usize {
+    let (_, size, _) = fs.fat.layout();
+    let fits = match fs.fat.fat_type() {
+        FatType::Fat12 => size * 2 / 3,
+        FatType::Fat16 => size / 2,
+        FatType::Fat32 => size / 4,
+    };
+    (fs.fat.max_cluster() as usize + 1).min(fits)
+}
+
+/// Decode the first `entries` entries of FAT copy `copy`, with the FAT32
+/// reserved high nibble masked off.
+pub(super) fn read_table<DATA: Read + Write + Seek>(
+    fs: &FatVolume<DATA>,
+    fat_type: FatType,
+    copy: usize,
+    entries: usize,
+) -> Result<Vec<u32>> {
+    let (start, size, _) = fs.fat.layout();
+    let len = match fat_type {
+        FatType::Fat12 => (entries * 3).div_ceil(2),
+        FatType::Fat16 => entries * 2,
+        FatType::Fat32 => entries * 4,
+    };
+    // Read in chunks so a truncated image fails before a large allocation.
+    let mut bytes = Vec::new();
+    let mut data = fs.data.lock();
+    data.seek(SeekFrom::Start((start + copy * size) as u64))?;
+    while bytes.len() < len {
+        let done = bytes.len();
+        bytes.resize((done + COPY_CHUNK).min(len), 0);
+        data.read_exact(&mut bytes[done..])?;
+    }
+
+    let table = (0..entries)
+        .map(|index| match fat_type {
+            FatType::Fat12 => {
+                let offset = index + index / 2;
+                let value = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+                (if index % 2 == 0 {
+                    value & 0x0FFF
+                } else {
+                    value >> 4
+                }) as u32
+            }
+            FatType::Fat16 => u16::from_le_bytes([bytes[index * 2], bytes[index * 2 + 1]]) as u32,
+            FatType::Fat32 => {
+                let raw = &bytes[index * 4..index * 4 + 4];
+                u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) & 0x0FFF_FFFF
+            }
+        })
+        .collect();
+    Ok(table)
+}
+
+pub(super) fn end_of_chain(fat_type: FatType) -> u32 {
+    match fat_type {
+        FatType::Fat12 => 0x0FF8,
+        FatType::Fat16 => 0xFFF8,
+        FatType::Fat32 => 0x0FFF_FFF8,
+    }
+}
+
+fn bad_cluster(fat_type: FatType) -> u32 {
+    match fat_type {
+        FatType::Fat12 => 0x0FF7,
+        FatType::Fat16 => 0xFFF7,
+        FatType::Fat32 => 0x0FFF_FFF7,
+    }
+}
+
+/// Whether an entry value marks its cluster as in use: neither free nor bad.
+fn is_in_use(fat_type: FatType, value: u32) -> bool {
+    value != 0 && value != bad_cluster(fat_type)
+}
+
+/// Whether an entry value is free, bad, end-of-chain, or a link to a data
+/// cluster no higher than `max_cluster`.
+fn is_valid_value(fat_type: FatType, max_cluster: u32, value: u32) -> bool {
+    value == 0
+        || value == bad_cluster(fat_type)
+        || value >= end_of_chain(fat_type)
+        || (2..=max_cluster).contains(&value)
+}
+
+/// The 13 UTF-16 name units of an LFN slot.
+fn lfn_units(raw: &[u8; 32]) -> [u16; 13] {
+    let mut units = [0u16; 13];
+    let offsets = (1..11)
+        .step_by(2)
+        .chain((14..26).step_by(2))
+        .chain((28..32).step_by(2));
+    for (unit, offset) in units.iter_mut().zip(offsets) {
+        *unit = u16::from_le_bytes([raw[offset], raw[offset + 1]]);
+    }
+    units
+}
+
+/// Assemble a long name from its slots in disk order.
+fn decode_lfn(slots: &[[u16; 13]]) -> String {
+    let units = slots
+        .iter()
+        .rev()
+        .flatten()
+        .copied()
+        .take_while(|&unit| unit != 0x0000 && unit != 0xFFFF);
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// The LFN checksum of a raw short name.
+fn lfn_checksum(name: &[u8; 11]) -> u8 {
+    name.iter()
+        .fold(0u8, |sum, &byte| sum.rotate_right(1).wrapping_add(byte))
+}
+
+/// `NAME.EXT` for a raw short entry, without padding.
+fn short_name_display(raw: &[u8; 32]) -> String {
+    let mut name = [0u8; 11];
+    name.copy_from_slice(&raw[..11]);
+    if name[0] == 0x05 {
+        name[0] = DELETED;
+    }
+    let base = String::from_utf8_lossy(&name[..8]);
+    let ext = String::from_utf8_lossy(&name[8..]);
+    let (base, ext) = (base.trim_end(), ext.trim_end());
+    if ext.is_empty() {
+        base.into()
+    } else {
+        format!("{base}.{ext}")
+    }
+}
+
+/// Paths are kept without the root's `/` so children can be appended.
+fn display_path(path: &str) -> String {
+    if path.is_empty() {
+        "/".into()
+    } else {
+        path.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_report_is_clean() {
+        let report = RepairReport {
+            actions: Vec::new(),
+            dry_run: true,
+        };
+        assert!(report.is_clean());
+
+        let report_with_actions = RepairReport {
+            actions: alloc::vec![RepairAction::FreeLostChain {
+                start_cluster: 5,
+                chain_length: 2,
+            }],
+            dry_run: false,
+        };
+        assert!(!report_with_actions.is_clean());
+        assert_eq!(report_with_actions.action_count(), 1);
+    }
+}
@@ -411,6 +411,8 @@ pub trait FatVolumeWriteExt<DATA: Read + Write + Seek> {
     /// # Errors
     ///
     /// Returns [`Error::NotAFile`] if the entry is a directory.
+    ///
+    /// With the journal enabled this is one transaction.
     async fn truncate(&self, entry: &FileEntry, new_size: usize) -> Result<()>;
 
     /// Patch the timestamps on an existing entry without rewriting its data.
@@ -439,6 +441,65 @@ impl<DATA: Read + Write + Seek> FatVolumeWriteExt<DATA> for FatVolume<DATA> {
     }
 
     async fn truncate(&self, entry: &FileEntry, new_size: usize) -> Result<()> {
+        self.begin_transaction().await?;
+        let result = self.truncate_unjournaled(entry, new_size).await;
+        self.end_transaction(result).await
+    }
+
+    async fn set_times(
+        &self,
+        entry: &FileEntry,
+        modified: Option<crate::time::FatDateTime>,
+        accessed_date: Option<u16>,
+        created: Option<crate::time::FatDateTime>,
+    ) -> Result<()> {
+        if modified.is_none() && accessed_date.is_none() && created.is_none() {
+            return Ok(());
+        }
+
+        let mut data = self.data.lock();
+        let cluster_size = data.cluster_size;
+
+        let entry_pos = if entry.parent_clus.0 == 0 {
+            let (root_start, _) = self
+                .fixed_root_dir_info()
+                .expect("Fixed root info required for cluster 0");
+            root_start + entry.offset_within_cluster
+        } else {
+            entry
+                .parent_clus
+                .to_bytes(self.info.data_start, cluster_size)
+                + entry.offset_within_cluster
+        };
+
+        data.seek(SeekFrom::Start(entry_pos as u64)).await?;
+        let mut raw_entry = data.read_struct::<RawDirectoryEntry>().await?;
+        let file_entry = unsafe { &mut raw_entry.file };
+
+        if let Some(m) = modified {
+            file_entry.last_write_date = m.date.to_le_bytes();
+            file_entry.last_write_time = m.time.to_le_bytes();
+        }
+        if let Some(date) = accessed_date {
+            file_entry.last_access_date = date.to_le_bytes();
+        }
+        if let Some(c) = created {
+            file_entry.creation_date = c.date.to_le_bytes();
+            file_entry.creation_time = c.time.to_le_bytes();
+            file_entry.creation_time_tenth = c.time_tenth;
+        }
+
+        data.seek(SeekFrom::Start(entry_pos as u64)).await?;
+        data.write_all(bytemuck::bytes_of(&raw_entry)).await?;
+        data.flush().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "write")]
+impl<DATA: Read + Write + Seek> FatVolume<DATA> {
+    async fn truncate_unjournaled(&self, entry: &FileEntry, new_size: usize) -> Result<()> {
         if !entry.is_file() {
             return Err(Error::NotAFile);
         }
@@ -505,56 +566,6 @@ impl<DATA: Read + Write + Seek> FatVolumeWriteExt<DATA> for FatVolume<DATA> {
 
         Ok(())
     }
-
-    async fn set_times(
-        &self,
-        entry: &FileEntry,
-        modified: Option<crate::time::FatDateTime>,
-        accessed_date: Option<u16>,
-        created: Option<crate::time::FatDateTime>,
-    ) -> Result<()> {
-        if modified.is_none() && accessed_date.is_none() && created.is_none() {
-            return Ok(());
-        }
-
-        let mut data = self.data.lock();
-        let cluster_size = data.cluster_size;
-
-        let entry_pos = if entry.parent_clus.0 == 0 {
-            let (root_start, _) = self
-                .fixed_root_dir_info()
-                .expect("Fixed root info required for cluster 0");
-            root_start + entry.offset_within_cluster
-        } else {
-            entry
-                .parent_clus
-                .to_bytes(self.info.data_start, cluster_size)
-                + entry.offset_within_cluster
-        };
-
-        data.seek(SeekFrom::Start(entry_pos as u64)).await?;
-        let mut raw_entry = data.read_struct::<RawDirectoryEntry>().await?;
-        let file_entry = unsafe { &mut raw_entry.file };
-
-        if let Some(m) = modified {
-            file_entry.last_write_date = m.date.to_le_bytes();
-            file_entry.last_write_time = m.time.to_le_bytes();
-        }
-        if let Some(date) = accessed_date {
-            file_entry.last_access_date = date.to_le_bytes();
-        }
-        if let Some(c) = created {
-            file_entry.creation_date = c.date.to_le_bytes();
-            file_entry.creation_time = c.time.to_le_bytes();
-            file_entry.creation_time_tenth = c.time_tenth;
-        }
-
-        data.seek(SeekFrom::Start(entry_pos as u64)).await?;
-        data.write_all(bytemuck::bytes_of(&raw_entry)).await?;
-        data.flush().await?;
-
-        Ok(())
-    }
 }
 
 /// Convert 0xE5 to 0x05 in the first byte of a short name for kanji compatibility.
@@ -1156,8 +1167,19 @@ impl<DATA: Read + Write + Seek> FatVolume<DATA> {
 
     /// Create a new file in the given directory.
     ///
-    /// Returns the FileEntry for the newly created file.
+    /// Returns the FileEntry for the newly created file. With the journal
+    /// enabled this is one transaction.
     pub async fn create_file(&self, parent: &FatDir<'_, DATA>, name: &str) -> Result<FileEntry> {
+        self.begin_transaction().await?;
+        let result = self.create_file_unjournaled(parent, name).await;
+        self.end_transaction(result).await
+    }
+
+    async fn create_file_unjournaled(
+        &self,
+        parent: &FatDir<'_, DATA>,
+        name: &str,
+    ) -> Result<FileEntry> {
         // Check if entry already exists
         if parent.find(name).await?.is_some() {
             return Err(Error::AlreadyExists);
@@ -1259,11 +1281,22 @@ impl<DATA: Read + Write + Seek> FatVolume<DATA> {
 
     /// Create a new directory.
     ///
-    /// Returns a FatDir handle for the newly created directory.
+    /// Returns a FatDir handle for the newly created directory. With the
+    /// journal enabled this is one transaction.
     pub async fn create_dir<'a>(
         &'a self,
         parent: &FatDir<'a, DATA>,
         name: &str,
+    ) -> Result<FatDir<'a, DATA>> {
+        self.begin_transaction().await?;
+        let result = self.create_dir_unjournaled(parent, name).await;
+        self.end_transaction(result).await
+    }
+
+    async fn create_dir_unjournaled<'a>(
+        &'a self,
+        parent: &FatDir<'a, DATA>,
+        name: &str,
     ) -> Result<FatDir<'a, DATA>> {
         // Check if entry already exists
         if parent.find(name).await?.is_some() {
@@ -1432,7 +1465,15 @@ impl<DATA: Read + Write + Seek> FatVolume<DATA> {
     }
 
     /// Delete a file or empty directory.
+    ///
+    /// With the journal enabled this is one transaction.
     pub async fn delete(&self, entry: &FileEntry) -> Result<()> {
+        self.begin_transaction().await?;
+        let result = self.delete_unjournaled(entry).await;
+        self.end_transaction(result).await
+    }
+
+    async fn delete_unjournaled(&self, entry: &FileEntry) -> Result<()> {
         // If it's a directory, check if it's empty (only . and ..)
         if entry.is_directory() {
             let dir = FatDir {
@@ -1484,12 +1525,24 @@ impl<DATA: Read + Write + Seek> FatVolume<DATA> {
     /// directory entry metadata changes.
     ///
     /// If moving a directory to a different parent, the `..` entry is updated
-    /// to point to the new parent.
+    /// to point to the new parent. With the journal enabled this is one
+    /// transaction.
     pub async fn rename(
         &self,
         entry: &FileEntry,
         dest_dir: &FatDir<'_, DATA>,
         new_name: &str,
+    ) -> Result<FileEntry> {
+        self.begin_transaction().await?;
+        let result = self.rename_unjournaled(entry, dest_dir, new_name).await;
+        self.end_transaction(result).await
+    }
+
+    async fn rename_unjournaled(
+        &self,
+        entry: &FileEntry,
+        dest_dir: &FatDir<'_, DATA>,
+        new_name: &str,
     ) -> Result<FileEntry> {
         // Check if destination already has this name
         if dest_dir.find(new_name).await?.is_some() {
@@ -0,0 +1,39 @@
+//! In-memory FAT volumes for the tool and journal tests.
+
+#![allow(dead_code)]
+
+use std::io::Cursor;
+
+use hadris_fat::format::{FatFormatOptions, FatTypeSelection, FatVolumeFormatter};
+use hadris_fat::{FatVolume, FatVolumeWriteExt};
+
+pub type Volume = FatVolume<Cursor<Vec<u8>>>;
+
+/// Format a zeroed in-memory image of `size` bytes labelled `HADRIS`.
+pub fn format(size: u64, fat_type: FatTypeSelection, sectors_per_cluster: u8) -> Volume {
+    let options = FatFormatOptions::new(size)
+        .volume_label("HADRIS")
+        .fat_type(fat_type)
+        .sectors_per_cluster(sectors_per_cluster);
+    FatVolumeFormatter::format(Cursor::new(vec![0u8; size as usize]), options).unwrap()
+}
+
+/// Deterministic file contents that differ per `seed`.
+pub fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u32).wrapping_mul(31).wrapping_add(seed as u32) as u8)
+        .collect()
+}
+
+/// Create `name` in the existing directory `dir` and write `contents` to it.
+pub fn write(fs: &Volume, dir: &str, name: &str, contents: &[u8]) {
+    let parent = if dir == "/" {
+        fs.root_dir()
+    } else {
+        fs.open_dir_path(dir).unwrap()
+    };
+    let entry = fs.create_file(&parent, name).unwrap();
+    let mut writer = fs.write_file(&entry).unwrap();
+    writer.write(contents).unwrap();
+    writer.finish().unwrap();
+}
@@ -0,0 +1,209 @@
+//! Defragmenting FAT volumes with `FatDefragExt::defragment`.
+
+#![cfg(all(feature = "tool", feature = "std", feature = "write"))]
+
+use std::io::Cursor;
+use std::ops::ControlFlow;
+
+use hadris_fat::format::FatTypeSelection;
+use hadris_fat::tool::{DefragOptions, RepairOptions};
+use hadris_fat::write::FileWriter;
+use hadris_fat::{FatAnalysisExt, FatDefragExt, FatRepairExt, FatVerifyExt, FatVolume};
+
+#[path = "common/volume.rs"]
+mod volume;
+use volume::{Volume, format, pattern, write};
+
+const MIB: u64 = 1024 * 1024;
+
+fn append(fs: &Volume, path: &str, contents: &[u8]) {
+    let entry = fs.open_path(path).unwrap();
+    let mut writer = FileWriter::new_append(fs, &entry).unwrap();
+    writer.write(contents).unwrap();
+    writer.finish().unwrap();
+}
+
+fn read(fs: &Volume, path: &str) -> Vec<u8> {
+    fs.open_file_path(path).unwrap().read_to_vec().unwrap()
+}
+
+/// Write `count` files that each end up in two fragments, interleaved with
+/// small spacer files.
+fn fragmented_files(fs: &Volume, count: u8) {
+    for index in 0..count {
+        write(fs, "/", &format!("FILE{index}.BIN"), &pattern(1000, index));
+        write(fs, "/", &format!("GAP{index}.BIN"), b"spacer");
+    }
+    for index in 0..count {
+        append(fs, &format!("/FILE{index}.BIN"), &pattern(700, index + 100));
+    }
+}
+
+fn file_contents(index: u8) -> Vec<u8> {
+    let mut contents = pattern(1000, index);
+    contents.extend(pattern(700, index + 100));
+    contents
+}
+
+fn assert_consistent(fs: &Volume) {
+    let verification = fs.verify().unwrap();
+    assert!(verification.is_valid(), "{:?}", verification.issues);
+    let repair = fs.repair(&RepairOptions::new().dry_run(true)).unwrap();
+    assert!(repair.is_clean(), "{:?}", repair.actions);
+}
+
+#[test]
+fn fragmented_files_become_contiguous() {
+    let fs = format(16 * MIB, FatTypeSelection::Fat16, 1);
+    fragmented_files(&fs, 3);
+    assert_eq!(fs.fragmentation_report(10).unwrap().fragmented_files, 3);
+
+    let report = fs
+        .defragment(&DefragOptions::new(), |_| ControlFlow::Continue(()))
+        .unwrap();
+    assert!(report.is_complete());
+    assert!(report.skipped.is_empty());
+    assert_eq!(report.files_moved, 3);
+    assert_eq!(report.directories_moved, 0);
+    assert_eq!(report.clusters_moved, 3 * 4);
+
+    assert_eq!(fs.fragmentation_report(10).unwrap().fragmented_files, 0);
+    for index in 0..3 {
+        assert_eq!(
+            read(&fs, &format!("/FILE{index}.BIN")),
+            file_contents(index)
+        );
+        assert_eq!(read(&fs, &format!("/GAP{index}.BIN")), b"spacer");
+    }
+    assert_consistent(&fs);
+
+    // Nothing is left to do, and the result survives a remount.
+    let report = fs
+        .defragment(&DefragOptions::new(), |_| ControlFlow::Continue(()))
+        .unwrap();
+    assert_eq!(report.clusters_moved, 0);
+    let fs = FatVolume::open(Cursor::new(fs.into_inner().into_inner())).unwrap();
+    assert_eq!(read(&fs, "/FILE0.BIN"), file_contents(0));
+    assert_consistent(&fs);
+}
+
+#[test]
+fn fragmented_directory_is_moved_and_relinked() {
+    let fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    fs.create_dir(&fs.root_dir(), "DIR").unwrap();
+    fs.create_dir(&fs.open_dir_path("/DIR").unwrap(), "SUB")
+        .unwrap();
+    write(&fs, "/DIR/SUB", "INNER.TXT", b"inner");
+    write(&fs, "/", "SPACER.BIN", &pattern(1500, 9));
+    // A 512-byte cluster holds 16 slots, so these spill into a second,
+    // non-adjacent cluster.
+    for index in 0..20u8 {
+        write(&fs, "/DIR", &format!("F{index:02}.TXT"), &[index]);
+    }
+    let before = fs.open_path("/DIR").unwrap().cluster();
+
+    let mut directories = Vec::new();
+    let report = fs
+        .defragment(&DefragOptions::new(), |progress| {
+            if progress.directory {
+                directories.push(progress.chain.path.clone());
+            }
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(directories, ["/DIR"]);
+    assert_eq!(report.directories_moved, 1);
+    assert_ne!(fs.open_path("/DIR").unwrap().cluster(), before);
+
+    for index in 0..20u8 {
+        assert_eq!(read(&fs, &format!("/DIR/F{index:02}.TXT")), [index]);
+    }
+    assert_eq!(read(&fs, "/DIR/SUB/INNER.TXT"), b"inner");
+    assert_consistent(&fs);
+}
+
+#[test]
+fn files_only_leaves_directories_alone() {
+    let fs = format(16 * MIB, FatTypeSelection::Fat16, 1);
+    fs.create_dir(&fs.root_dir(), "DIR").unwrap();
+    write(&fs, "/", "SPACER.BIN", b"spacer");
+    for index in 0..20u8 {
+        write(&fs, "/DIR", &format!("F{index:02}.TXT"), &[index]);
+    }
+    let before = fs.open_path("/DIR").unwrap().cluster();
+
+    let report = fs
+        .defragment(&DefragOptions::new().files_only(true), |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(report.directories_moved, 0);
+    assert_eq!(fs.open_path("/DIR").unwrap().cluster(), before);
+
+    let report = fs
+        .defragment(&DefragOptions::new(), |_| ControlFlow::Continue(()))
+        .unwrap();
+    assert_eq!(report.directories_moved, 1);
+    assert_consistent(&fs);
+}
+
+#[test]
+fn budget_splits_the_work_into_passes() {
+    let fs = format(16 * MIB, FatTypeSelection::Fat16, 1);
+    fragmented_files(&fs, 3);
+
+    let options = DefragOptions::new().max_clusters(1);
+    let mut passes = 0;
+    loop {
+        let report = fs
+            .defragment(&options, |_| ControlFlow::Continue(()))
+            .unwrap();
+        passes += 1;
+        assert!(report.files_moved <= 1);
+        assert_consistent(&fs);
+        if report.is_complete() {
+            break;
+        }
+        assert_eq!(report.remaining, 3 - passes);
+    }
+    assert_eq!(passes, 3);
+    assert_eq!(fs.fragmentation_report(10).unwrap().fragmented_files, 0);
+}
+
+#[test]
+fn zero_budget_still_moves_one_chain() {
+    let fs = format(16 * MIB, FatTypeSelection::Fat16, 1);
+    fragmented_files(&fs, 3);
+
+    let report = fs
+        .defragment(&DefragOptions::new().max_clusters(0), |_| {
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(report.files_moved, 1);
+    assert_eq!(report.remaining, 2);
+    assert_consistent(&fs);
+}
+
+#[test]
+fn progress_callback_can_stop_the_pass() {
+    let fs = format(16 * MIB, FatTypeSelection::Fat16, 1);
+    fragmented_files(&fs, 3);
+
+    let mut seen = 0;
+    let report = fs
+        .defragment(&DefragOptions::new(), |progress| {
+            seen += 1;
+            assert_eq!(progress.chains_total, 3);
+            assert_eq!(progress.chains_moved, 1);
+            assert_eq!(progress.chain.fragments, 2);
+            ControlFlow::Break(())
+        })
+        .unwrap();
+    assert_eq!(seen, 1);
+    assert_eq!(report.files_moved, 1);
+    assert_eq!(report.remaining, 2);
+    assert!(!report.is_complete());
+    assert_eq!(fs.fragmentation_report(10).unwrap().fragmented_files, 2);
+    assert_consistent(&fs);
+}
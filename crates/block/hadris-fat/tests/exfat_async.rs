@@ -0,0 +1,198 @@
+//! The exFAT preview through the async API.
+//!
+//! Images are formatted and written asynchronously, then read back with both
+//! the async and the sync API to check the two slices agree on the layout.
+
+#![cfg(all(
+    feature = "unstable-exfat",
+    feature = "std",
+    feature = "sync",
+    feature = "async",
+    feature = "write"
+))]
+
+use core::future::Future;
+use core::task::{Context, Poll};
+use std::sync::Arc;
+use std::task::{Wake, Waker};
+
+use hadris_fat::r#async::exfat::{ExFatFormatOptions, ExFatVolume, format_exfat};
+use hadris_io::SeekFrom;
+use hadris_io::r#async::{Read, Seek, Write};
+
+const IMAGE_SIZE: usize = 16 * 1024 * 1024;
+
+struct ThreadWaker(std::thread::Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => std::thread::park(),
+        }
+    }
+}
+
+/// A fixed-size in-memory image behind the async I/O traits.
+struct AsyncImage {
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+impl AsyncImage {
+    fn new(len: usize) -> Self {
+        Self {
+            bytes: vec![0; len],
+            position: 0,
+        }
+    }
+}
+
+impl Read for AsyncImage {
+    type Error = hadris_io::ErrorKind;
+
+    async fn read(&mut self, buf: &mut [u8]) -> hadris_io::Result<usize, Self::Error> {
+        let available = self.bytes.len().saturating_sub(self.position);
+        let len = available.min(buf.len());
+        buf[..len].copy_from_slice(&self.bytes[self.position..self.position + len]);
+        self.position += len;
+        Ok(len)
+    }
+}
+
+impl Write for AsyncImage {
+    type Error = hadris_io::ErrorKind;
+
+    async fn write(&mut self, buf: &[u8]) -> hadris_io::Result<usize, Self::Error> {
+        let end = self.position + buf.len();
+        if end > self.bytes.len() {
+            return Err(hadris_io::Error::from_kind(hadris_io::ErrorKind::WriteZero));
+        }
+        self.bytes[self.position..end].copy_from_slice(buf);
+        self.position = end;
+        Ok(buf.len())
+    }
+
+    async fn flush(&mut self) -> hadris_io::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Seek for AsyncImage {
+    type Error = hadris_io::ErrorKind;
+
+    async fn seek(&mut self, pos: SeekFrom) -> hadris_io::Result<u64, Self::Error> {
+        let next = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+            SeekFrom::End(offset) => self.bytes.len() as i64 + offset,
+        };
+        if next < 0 {
+            return Err(hadris_io::Error::from_kind(
+                hadris_io::ErrorKind::InvalidInput,
+            ));
+        }
+        self.position = next as usize;
+        Ok(self.position as u64)
+    }
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|index| (index % 251) as u8).collect()
+}
+
+async fn names_in(fs: &ExFatVolume<AsyncImage>, path: &str) -> Vec<String> {
+    let dir = fs.open_dir(path).await.unwrap();
+    let mut entries = dir.entries();
+    let mut names = Vec::new();
+    while let Some(entry) = entries.next_entry().await {
+        names.push(entry.unwrap().name);
+    }
+    names
+}
+
+#[test]
+fn async_format_write_and_read_back() {
+    let payload = pattern(3 * 4096 + 123);
+    let image = block_on(async {
+        let options = ExFatFormatOptions::default()
+            .volume_label("ASYNC")
+            .sectors_per_cluster(8);
+        let fs = format_exfat(AsyncImage::new(IMAGE_SIZE), IMAGE_SIZE as u64, &options)
+            .await
+            .unwrap();
+
+        let docs = fs.create_dir(&fs.root_dir(), "docs").await.unwrap();
+        let entry = fs.create_file(&docs, "payload.bin").await.unwrap();
+        let mut writer = fs.write_file(&entry).unwrap();
+        writer.write_all(&payload).await.unwrap();
+        writer.finish().await.unwrap();
+        fs.create_file(&docs, "empty.txt").await.unwrap();
+
+        assert_eq!(names_in(&fs, "/docs").await, ["payload.bin", "empty.txt"]);
+
+        let mut reader = fs.open_file("/DOCS/Payload.BIN").await.unwrap();
+        assert_eq!(reader.size(), payload.len() as u64);
+        reader.seek(SeekFrom::Start(4000)).await.unwrap();
+        let mut chunk = [0_u8; 200];
+        reader.read_exact(&mut chunk).await.unwrap();
+        assert_eq!(&chunk[..], &payload[4000..4200]);
+
+        fs.into_inner().bytes
+    });
+
+    // The sync slice reads the same bytes.
+    let mut cursor = std::io::Cursor::new(image);
+    let fs = hadris_fat::sync::exfat::ExFatVolume::open(&mut cursor).unwrap();
+    let mut reader = fs.open_file("/docs/payload.bin").unwrap();
+    let mut contents = vec![0_u8; payload.len()];
+    hadris_io::sync::Read::read_exact(&mut reader, &mut contents).unwrap();
+    assert_eq!(contents, payload);
+}
+
+#[test]
+fn async_delete_and_truncate() {
+    block_on(async {
+        let options = ExFatFormatOptions::default().sectors_per_cluster(1);
+        let fs = format_exfat(AsyncImage::new(IMAGE_SIZE), IMAGE_SIZE as u64, &options)
+            .await
+            .unwrap();
+        let root = fs.root_dir();
+        let free = fs.free_cluster_count();
+
+        let entry = fs.create_file(&root, "big.bin").await.unwrap();
+        let mut writer = fs.write_file(&entry).unwrap();
+        writer.write_all(&pattern(10 * 512)).await.unwrap();
+        writer.finish().await.unwrap();
+        assert_eq!(fs.free_cluster_count(), free - 10);
+
+        let entry = fs.open_path("/big.bin").await.unwrap();
+        fs.truncate(&entry, 1000).await.unwrap();
+        assert_eq!(fs.free_cluster_count(), free - 2);
+        assert_eq!(fs.open_path("/big.bin").await.unwrap().size(), 1000);
+
+        let dir = fs.create_dir(&root, "dir").await.unwrap();
+        fs.create_file(&dir, "inner").await.unwrap();
+        let dir_entry = fs.open_path("/dir").await.unwrap();
+        assert!(fs.delete(&dir_entry).await.is_err());
+        fs.delete(&fs.open_path("/dir/inner").await.unwrap())
+            .await
+            .unwrap();
+        fs.delete(&dir_entry).await.unwrap();
+        fs.delete(&fs.open_path("/big.bin").await.unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(fs.free_cluster_count(), free);
+        assert!(fs.root_dir().entries().next_entry().await.is_none());
+    });
+}
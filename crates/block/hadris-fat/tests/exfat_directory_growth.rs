@@ -0,0 +1,238 @@
+//! Directory growth for the exFAT write path.
+//!
+//! Images use 512-byte clusters, so a directory cluster holds just 16 entry
+//! slots: a few files fill it, and long names need entry sets that cross
+//! cluster boundaries. When `fsck.exfat` is available on the host, the final
+//! images are also validated externally.
+
+#![cfg(all(feature = "unstable-exfat", feature = "sync", feature = "write"))]
+
+use std::io::{Cursor, Seek as _, SeekFrom};
+
+use hadris_fat::exfat::{ExFatFormatOptions, ExFatVolume, format_exfat};
+use hadris_fat::io::{Read as HadrisRead, Write as HadrisWrite};
+use tempfile::TempDir;
+
+#[path = "common/exfat.rs"]
+mod exfat_helpers;
+use exfat_helpers::{fsck_check, fsck_exfat_available};
+
+const IMAGE_SIZE: u64 = 32 * 1024 * 1024;
+const CLUSTER_SIZE: u64 = 512;
+
+type Image = Cursor<Vec<u8>>;
+
+fn make_image() -> Image {
+    let mut image = Cursor::new(vec![0u8; IMAGE_SIZE as usize]);
+    let opts = ExFatFormatOptions::default()
+        .volume_label("GROWTH")
+        .sectors_per_cluster(1);
+    format_exfat(&mut image, IMAGE_SIZE, &opts).expect("format_exfat");
+    image
+}
+
+fn open(image: &mut Image) -> ExFatVolume<&mut Image> {
+    image.seek(SeekFrom::Start(0)).unwrap();
+    ExFatVolume::open(image).expect("open exFAT")
+}
+
+fn write_file<D>(fs: &ExFatVolume<D>, dir: &str, name: &str, contents: &[u8])
+where
+    D: HadrisRead + HadrisWrite + hadris_fat::io::Seek,
+{
+    let parent = if dir.is_empty() {
+        fs.root_dir()
+    } else {
+        fs.open_dir(dir).expect("open_dir")
+    };
+    let entry = fs.create_file(&parent, name).expect("create_file");
+    let mut writer = fs.write_file(&entry).expect("write_file");
+    writer.write_all(contents).expect("write_all");
+    writer.finish().expect("writer.finish");
+}
+
+fn read_file<D>(fs: &ExFatVolume<D>, path: &str) -> Vec<u8>
+where
+    D: HadrisRead + hadris_fat::io::Seek,
+{
+    let mut reader = fs.open_file(path).expect("open_file");
+    let mut out = Vec::new();
+    let mut buf = [0u8; 512];
+    loop {
+        let n = HadrisRead::read(&mut reader, &mut buf).expect("read");
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..n]);
+    }
+    out
+}
+
+fn names_in<D>(fs: &ExFatVolume<D>, dir: &str) -> Vec<String>
+where
+    D: HadrisRead + hadris_fat::io::Seek,
+{
+    let dir = if dir.is_empty() {
+        fs.root_dir()
+    } else {
+        fs.open_dir(dir).expect("open_dir")
+    };
+    dir.entries()
+        .map(|entry| entry.expect("directory entry").name)
+        .collect()
+}
+
+fn maybe_fsck(image: &Image) {
+    if !fsck_exfat_available() {
+        eprintln!("note: fsck.exfat not available, skipping external validation");
+        return;
+    }
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("growth.img");
+    std::fs::write(&path, image.get_ref()).unwrap();
+    if let Err(e) = fsck_check(&path) {
+        panic!("fsck.exfat rejected the image: {e}");
+    }
+}
+
+#[test]
+fn thousands_of_files_in_root() {
+    let mut image = make_image();
+    let count = 2000;
+    {
+        let fs = open(&mut image);
+        let root = fs.root_dir();
+        for index in 0..count {
+            fs.create_file(&root, &format!("file{index:04}.txt"))
+                .expect("create_file");
+        }
+    }
+
+    let fs = open(&mut image);
+    let names = names_in(&fs, "");
+    assert_eq!(names.len(), count);
+    for (index, name) in names.iter().enumerate() {
+        assert_eq!(name, &format!("file{index:04}.txt"));
+    }
+    drop(fs);
+    maybe_fsck(&image);
+}
+
+#[test]
+fn thousands_of_files_in_subdirectory() {
+    let mut image = make_image();
+    let count = 2000;
+    {
+        let fs = open(&mut image);
+        let dir = fs.create_dir(&fs.root_dir(), "many").expect("create_dir");
+        // Claim the cluster after the directory so it can't grow in place.
+        write_file(&fs, "", "spacer.bin", &[0xAA; 512]);
+        assert!(fs.open_path("/many").unwrap().no_fat_chain);
+
+        // Keep using the handle from `create_dir`; it must pick up the
+        // directory's new size and chain after each growth.
+        for index in 0..count {
+            let entry = fs
+                .create_file(&dir, &format!("entry{index:04}.dat"))
+                .expect("create_file");
+            let mut writer = fs.write_file(&entry).expect("write_file");
+            writer
+                .write_all(format!("payload {index}").as_bytes())
+                .expect("write_all");
+            writer.finish().expect("writer.finish");
+        }
+    }
+
+    let fs = open(&mut image);
+    let entry = fs.open_path("/many").unwrap();
+    assert!(!entry.no_fat_chain, "directory should now be a FAT chain");
+    assert_eq!(entry.data_length % CLUSTER_SIZE, 0);
+    assert!(entry.data_length >= count as u64 * 3 * 32);
+
+    let names = names_in(&fs, "/many");
+    assert_eq!(names.len(), count);
+    for index in (0..count).step_by(97) {
+        assert_eq!(
+            read_file(&fs, &format!("/many/entry{index:04}.dat")),
+            format!("payload {index}").as_bytes()
+        );
+    }
+    assert_eq!(read_file(&fs, "/spacer.bin"), [0xAA; 512]);
+    drop(fs);
+    maybe_fsck(&image);
+}
+
+#[test]
+fn contiguous_directory_grows_in_place() {
+    let mut image = make_image();
+    {
+        let fs = open(&mut image);
+        let dir = fs.create_dir(&fs.root_dir(), "dir").expect("create_dir");
+        // 16 slots per cluster and 3 slots per file: 20 files need 4 clusters.
+        for index in 0..20 {
+            fs.create_file(&dir, &format!("f{index:02}.txt"))
+                .expect("create_file");
+        }
+    }
+
+    let fs = open(&mut image);
+    let entry = fs.open_path("/dir").unwrap();
+    assert!(entry.no_fat_chain);
+    assert_eq!(entry.data_length, 4 * CLUSTER_SIZE);
+    assert_eq!(entry.valid_data_length, 4 * CLUSTER_SIZE);
+    assert_eq!(names_in(&fs, "/dir").len(), 20);
+    drop(fs);
+    maybe_fsck(&image);
+}
+
+#[test]
+fn long_names_span_cluster_boundaries() {
+    let mut image = make_image();
+    // A 255-character name needs 19 slots, more than one cluster holds.
+    let names: Vec<String> = (0..40)
+        .map(|index| format!("{index:02}{}", "n".repeat(253)))
+        .collect();
+    {
+        let fs = open(&mut image);
+        fs.create_dir(&fs.root_dir(), "long").expect("create_dir");
+        for (index, name) in names.iter().enumerate() {
+            write_file(&fs, "/long", name, &[index as u8; 100]);
+        }
+    }
+
+    let fs = open(&mut image);
+    assert_eq!(names_in(&fs, "/long"), names);
+    for (index, name) in names.iter().enumerate() {
+        assert_eq!(read_file(&fs, &format!("/long/{name}")), [index as u8; 100]);
+    }
+    drop(fs);
+    maybe_fsck(&image);
+}
+
+#[test]
+fn deleted_slots_are_reused_before_growing() {
+    let mut image = make_image();
+    let fs = open(&mut image);
+    let dir = fs.create_dir(&fs.root_dir(), "reuse").expect("create_dir");
+    for index in 0..200 {
+        fs.create_file(&dir, &format!("old{index:03}.txt"))
+            .expect("create_file");
+    }
+    let size = fs.open_path("/reuse").unwrap().data_length;
+
+    for index in 0..200 {
+        let entry = fs.open_path(&format!("/reuse/old{index:03}.txt")).unwrap();
+        fs.delete(&entry).expect("delete");
+    }
+    for index in 0..200 {
+        fs.create_file(&dir, &format!("new{index:03}.txt"))
+            .expect("create_file");
+    }
+    assert_eq!(fs.open_path("/reuse").unwrap().data_length, size);
+
+    let names = names_in(&fs, "/reuse");
+    assert_eq!(names.len(), 200);
+    assert!(names.iter().all(|name| name.starts_with("new")));
+    drop(fs);
+    maybe_fsck(&image);
+}
@@ -0,0 +1,482 @@
+//! exFAT volume layout: fragmented system files and large volumes.
+//!
+//! The allocation bitmap and up-case table are described by FAT chains, and
+//! media formatted elsewhere may scatter them across the cluster heap. The
+//! formatter must also lay out multi-terabyte volumes, which are tested on a
+//! sparse in-memory device.
+
+#![cfg(all(feature = "unstable-exfat", feature = "sync", feature = "write"))]
+
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+use hadris_fat::exfat::{ExFatFormatOptions, ExFatVolume, format_exfat};
+use hadris_fat::io::{Read as HadrisRead, Write as HadrisWrite};
+use tempfile::TempDir;
+
+#[path = "common/exfat.rs"]
+mod exfat_helpers;
+use exfat_helpers::{fsck_check, fsck_exfat_available};
+
+const MIB: u64 = 1024 * 1024;
+const GIB: u64 = 1024 * MIB;
+const TIB: u64 = 1024 * GIB;
+
+/// A device that only stores the pages written with non-zero data.
+#[derive(Default)]
+struct SparseDevice {
+    len: u64,
+    position: u64,
+    pages: HashMap<u64, Box<[u8; Self::PAGE]>>,
+}
+
+impl SparseDevice {
+    const PAGE: usize = 4096;
+
+    fn new(len: u64) -> Self {
+        Self {
+            len,
+            ..Self::default()
+        }
+    }
+}
+
+impl Read for SparseDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf
+            .len()
+            .min(self.len.saturating_sub(self.position) as usize);
+        let mut done = 0;
+        while done < len {
+            let page = self.position / Self::PAGE as u64;
+            let offset = (self.position % Self::PAGE as u64) as usize;
+            let n = (Self::PAGE - offset).min(len - done);
+            match self.pages.get(&page) {
+                Some(data) => buf[done..done + n].copy_from_slice(&data[offset..offset + n]),
+                None => buf[done..done + n].fill(0),
+            }
+            done += n;
+            self.position += n as u64;
+        }
+        Ok(len)
+    }
+}
+
+impl Write for SparseDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.position + buf.len() as u64 > self.len {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "past end of device",
+            ));
+        }
+        let mut done = 0;
+        while done < buf.len() {
+            let page = self.position / Self::PAGE as u64;
+            let offset = (self.position % Self::PAGE as u64) as usize;
+            let n = (Self::PAGE - offset).min(buf.len() - done);
+            let chunk = &buf[done..done + n];
+            if let Some(data) = self.pages.get_mut(&page) {
+                data[offset..offset + n].copy_from_slice(chunk);
+            } else if chunk.iter().any(|&byte| byte != 0) {
+                let mut data = Box::new([0u8; Self::PAGE]);
+                data[offset..offset + n].copy_from_slice(chunk);
+                self.pages.insert(page, data);
+            }
+            done += n;
+            self.position += n as u64;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SparseDevice {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta).unwrap(),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta).unwrap(),
+        };
+        Ok(self.position)
+    }
+}
+
+fn write_file<D>(fs: &ExFatVolume<D>, name: &str, contents: &[u8])
+where
+    D: HadrisRead + HadrisWrite + hadris_fat::io::Seek,
+{
+    let entry = fs.create_file(&fs.root_dir(), name).expect("create_file");
+    let mut writer = fs.write_file(&entry).expect("write_file");
+    writer.write_all(contents).expect("write_all");
+    writer.finish().expect("writer.finish");
+}
+
+fn read_file<D>(fs: &ExFatVolume<D>, path: &str) -> Vec<u8>
+where
+    D: HadrisRead + hadris_fat::io::Seek,
+{
+    let mut reader = fs.open_file(path).expect("open_file");
+    let mut out = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = HadrisRead::read(&mut reader, &mut buf).expect("read");
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..n]);
+    }
+    out
+}
+
+fn pattern(len: usize, seed: u8) -> Vec<u8> {
+    (0..len)
+        .map(|i| (i as u32).wrapping_mul(13).wrapping_add(seed as u32) as u8)
+        .collect()
+}
+
+/// Raw geometry of a formatted image, read back from its boot sector.
+struct Geometry {
+    fat_offset: usize,
+    heap_offset: usize,
+    cluster_size: usize,
+    root_offset: usize,
+}
+
+impl Geometry {
+    fn read(image: &[u8]) -> Self {
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(image[offset..offset + 4].try_into().unwrap());
+        let sector_size = 1usize << image[108];
+        let cluster_size = sector_size << image[109];
+        let heap_offset = u32_at(88) as usize * sector_size;
+        let root_cluster = u32_at(96) as usize;
+        Self {
+            fat_offset: u32_at(80) as usize * sector_size,
+            heap_offset,
+            cluster_size,
+            root_offset: heap_offset + (root_cluster - 2) * cluster_size,
+        }
+    }
+
+    fn cluster(&self, cluster: u32) -> usize {
+        self.heap_offset + (cluster as usize - 2) * self.cluster_size
+    }
+
+    fn set_fat(&self, image: &mut [u8], cluster: u32, value: u32) {
+        let offset = self.fat_offset + cluster as usize * 4;
+        image[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Offset of the root directory entry of `entry_type`.
+    fn system_entry(&self, image: &[u8], entry_type: u8) -> usize {
+        (0..self.cluster_size / 32)
+            .map(|index| self.root_offset + index * 32)
+            .find(|&offset| image[offset] == entry_type)
+            .expect("system entry")
+    }
+
+    /// First cluster and length of the system file described by the root
+    /// entry of `entry_type`.
+    fn system_file(&self, image: &[u8], entry_type: u8) -> (u32, u64) {
+        let entry = self.system_entry(image, entry_type);
+        let first = u32::from_le_bytes(image[entry + 20..entry + 24].try_into().unwrap());
+        let size = u64::from_le_bytes(image[entry + 24..entry + 32].try_into().unwrap());
+        (first, size)
+    }
+
+    /// Store `contents` as the system file of `entry_type`, one cluster per
+    /// target, chained in the FAT. Returns the clusters it occupied before.
+    fn place(
+        &self,
+        image: &mut [u8],
+        entry_type: u8,
+        contents: &[u8],
+        targets: &[u32],
+    ) -> Vec<u32> {
+        let (first, size) = self.system_file(image, entry_type);
+        let old_count = size.div_ceil(self.cluster_size as u64) as u32;
+        assert_eq!(contents.len().div_ceil(self.cluster_size), targets.len());
+
+        for (chunk, &to) in contents.chunks(self.cluster_size).zip(targets) {
+            image[self.cluster(to)..self.cluster(to) + chunk.len()].copy_from_slice(chunk);
+        }
+        let old: Vec<u32> = (first..first + old_count).collect();
+        for &cluster in &old {
+            self.set_fat(image, cluster, 0);
+        }
+        for pair in targets.windows(2) {
+            self.set_fat(image, pair[0], pair[1]);
+        }
+        self.set_fat(image, *targets.last().unwrap(), 0xFFFF_FFFF);
+
+        let entry = self.system_entry(image, entry_type);
+        image[entry + 20..entry + 24].copy_from_slice(&targets[0].to_le_bytes());
+        image[entry + 24..entry + 32].copy_from_slice(&(contents.len() as u64).to_le_bytes());
+        old
+    }
+
+    /// Flip allocation bits in the on-disk bitmap (wherever it lives).
+    fn mark(&self, image: &mut [u8], clusters: &[u32], allocated: bool) {
+        let entry = self.system_entry(image, 0x81);
+        let first = u32::from_le_bytes(image[entry + 20..entry + 24].try_into().unwrap());
+        let mut chain = vec![first];
+        loop {
+            let offset = self.fat_offset + *chain.last().unwrap() as usize * 4;
+            let next = u32::from_le_bytes(image[offset..offset + 4].try_into().unwrap());
+            if next == 0xFFFF_FFFF {
+                break;
+            }
+            chain.push(next);
+        }
+        for &cluster in clusters {
+            let bit = (cluster - 2) as usize;
+            let byte = bit / 8;
+            let host = chain[byte / self.cluster_size];
+            let offset = self.cluster(host) + byte % self.cluster_size;
+            if allocated {
+                image[offset] |= 1 << (bit % 8);
+            } else {
+                image[offset] &= !(1 << (bit % 8));
+            }
+        }
+    }
+}
+
+/// An up-case table spanning several 512-byte clusters: the first 1024
+/// code points are stored one by one, the rest as identity runs.
+fn large_upcase_table() -> (Vec<u8>, u32) {
+    let mut table = Vec::new();
+    for code_point in 0u16..0x400 {
+        let upper = match code_point {
+            0x61..=0x7A | 0xE0..=0xF6 | 0xF8..=0xFE => code_point - 0x20,
+            0xFF => 0x178,
+            _ => code_point,
+        };
+        table.extend_from_slice(&upper.to_le_bytes());
+    }
+    for run in [32768u16, (0x10000 - 0x400 - 32768) as u16] {
+        table.extend_from_slice(&0xFFFFu16.to_le_bytes());
+        table.extend_from_slice(&run.to_le_bytes());
+    }
+    let checksum = table.iter().fold(0u32, |sum, &byte| {
+        sum.rotate_right(1).wrapping_add(byte as u32)
+    });
+    (table, checksum)
+}
+
+/// Format a 32 MiB image with 512-byte clusters, then scatter its
+/// allocation bitmap and a multi-cluster up-case table across the heap.
+fn fragmented_image() -> (Vec<u8>, Vec<u32>, Vec<u32>) {
+    let mut image = Cursor::new(vec![0u8; 32 * MIB as usize]);
+    let options = ExFatFormatOptions::new()
+        .volume_label("FRAGMENT")
+        .sectors_per_cluster(1);
+    format_exfat(&mut image, 32 * MIB, &options).expect("format_exfat");
+    let mut image = image.into_inner();
+    let geometry = Geometry::read(&image);
+
+    let (bitmap_first, bitmap_size) = geometry.system_file(&image, 0x81);
+    let bitmap_start = geometry.cluster(bitmap_first);
+    let bitmap = image[bitmap_start..bitmap_start + bitmap_size as usize].to_vec();
+    let (upcase, checksum) = large_upcase_table();
+
+    // Every other cluster from 1000 up, and a descending run from 5000.
+    let bitmap_targets: Vec<u32> = (0..bitmap.len().div_ceil(geometry.cluster_size) as u32)
+        .map(|i| 1000 + 2 * i)
+        .collect();
+    let upcase_targets: Vec<u32> = (0..upcase.len().div_ceil(geometry.cluster_size) as u32)
+        .map(|i| 5000 - 3 * i)
+        .collect();
+    assert!(bitmap_targets.len() > 1 && upcase_targets.len() > 1);
+
+    let old_bitmap = geometry.place(&mut image, 0x81, &bitmap, &bitmap_targets);
+    let old_upcase = geometry.place(&mut image, 0x82, &upcase, &upcase_targets);
+    let entry = geometry.system_entry(&image, 0x82);
+    image[entry + 4..entry + 8].copy_from_slice(&checksum.to_le_bytes());
+
+    geometry.mark(&mut image, &old_bitmap, false);
+    geometry.mark(&mut image, &old_upcase, false);
+    geometry.mark(&mut image, &bitmap_targets, true);
+    geometry.mark(&mut image, &upcase_targets, true);
+
+    (image, bitmap_targets, upcase_targets)
+}
+
+fn maybe_fsck(image: &[u8]) {
+    if !fsck_exfat_available() {
+        eprintln!("note: fsck.exfat not available, skipping external validation");
+        return;
+    }
+    let tmp = TempDir::new().unwrap();
+    let path = tmp.path().join("layout.img");
+    std::fs::write(&path, image).unwrap();
+    if let Err(e) = fsck_check(&path) {
+        panic!("fsck.exfat rejected the image: {e}");
+    }
+}
+
+#[test]
+fn fragmented_system_files_are_followed() {
+    let (image, bitmap_clusters, upcase_clusters) = fragmented_image();
+    let fs = ExFatVolume::open(Cursor::new(image)).expect("open fragmented volume");
+
+    for &cluster in bitmap_clusters.iter().chain(&upcase_clusters) {
+        assert!(
+            fs.is_cluster_allocated(cluster).unwrap(),
+            "cluster {cluster}"
+        );
+    }
+    assert!(!fs.is_cluster_allocated(1001).unwrap());
+
+    // Case-insensitive lookup goes through the relocated up-case table.
+    write_file(&fs, "Café Menu.txt", b"fragmented");
+    assert_eq!(read_file(&fs, "CAFÉ MENU.TXT"), b"fragmented");
+}
+
+#[test]
+fn fragmented_bitmap_is_written_back_in_place() {
+    let (image, bitmap_clusters, _) = fragmented_image();
+    let mut image = Cursor::new(image);
+    let free_after = {
+        let fs = ExFatVolume::open(&mut image).expect("open fragmented volume");
+        let free_before = fs.free_cluster_count();
+        write_file(&fs, "big.bin", &pattern(300 * 1024, 1));
+        write_file(&fs, "small.bin", &pattern(100, 2));
+        // big.bin starts in the clusters the old bitmap left free and runs
+        // into the root directory, so it continues as a FAT chain.
+        assert!(!fs.open_path("big.bin").unwrap().no_fat_chain);
+        let free_after = fs.free_cluster_count();
+        assert!(free_before - free_after >= 600);
+        free_after
+    };
+
+    image.seek(SeekFrom::Start(0)).unwrap();
+    let fs = ExFatVolume::open(&mut image).expect("reopen");
+    assert_eq!(fs.free_cluster_count(), free_after);
+    for &cluster in &bitmap_clusters {
+        assert!(fs.is_cluster_allocated(cluster).unwrap());
+    }
+    assert_eq!(read_file(&fs, "big.bin"), pattern(300 * 1024, 1));
+    assert_eq!(read_file(&fs, "small.bin"), pattern(100, 2));
+    drop(fs);
+    maybe_fsck(image.get_ref());
+}
+
+#[test]
+fn truncated_system_file_chain_is_rejected() {
+    let (mut image, bitmap_clusters, _) = fragmented_image();
+    let geometry = Geometry::read(&image);
+    // End the bitmap's chain one cluster early.
+    let cut = bitmap_clusters[bitmap_clusters.len() - 2];
+    geometry.set_fat(&mut image, cut, 0xFFFF_FFFF);
+
+    assert!(matches!(
+        ExFatVolume::open(Cursor::new(image)),
+        Err(hadris_fat::Error::UnexpectedEndOfChain { .. })
+    ));
+}
+
+#[test]
+fn both_fats_describe_the_system_files() {
+    let mut image = Cursor::new(vec![0u8; 32 * MIB as usize]);
+    let options = ExFatFormatOptions::new().fat_count(2);
+    let fs = format_exfat(&mut image, 32 * MIB, &options).expect("format_exfat");
+    let info = fs.info().clone();
+    drop(fs);
+
+    let image = image.into_inner();
+    let fat = |index: u64| {
+        let start = (info.fat_offset + index * info.fat_length) as usize;
+        image[start..start + info.fat_length as usize].to_vec()
+    };
+    assert_eq!(fat(0), fat(1));
+    assert!(fat(0)[8..].iter().any(|&byte| byte != 0));
+}
+
+#[test]
+fn sd_card_layout_aligns_fat_and_heap() {
+    let size = 64 * GIB;
+    let options = ExFatFormatOptions::new().sd_card_layout(true);
+    let fs = format_exfat(SparseDevice::new(size), size, &options).expect("format_exfat");
+    let info = fs.info();
+
+    assert_eq!(info.bytes_per_cluster, 128 * 1024);
+    assert_eq!(info.fat_offset, 8 * MIB);
+    assert_eq!(info.cluster_heap_offset % (16 * MIB), 0);
+    assert!(info.fat_offset + info.fat_length <= info.cluster_heap_offset);
+}
+
+#[test]
+fn partition_offset_shifts_alignment() {
+    let size = 256 * MIB;
+    let options = ExFatFormatOptions::new()
+        .boundary_alignment(Some(4 * MIB))
+        .partition_offset(MIB);
+    let fs = format_exfat(SparseDevice::new(size), size, &options).expect("format_exfat");
+    let info = fs.info();
+
+    // Alignment is measured from the start of the media, one MiB earlier.
+    assert_eq!((MIB + info.fat_offset) % (2 * MIB), 0);
+    assert_eq!((MIB + info.cluster_heap_offset) % (4 * MIB), 0);
+}
+
+#[test]
+fn unaligned_layout_packs_after_boot_regions() {
+    let size = 64 * MIB;
+    let options = ExFatFormatOptions::new().boundary_alignment(None);
+    let fs = format_exfat(SparseDevice::new(size), size, &options).expect("format_exfat");
+    let info = fs.info();
+
+    assert_eq!(info.fat_offset, 24 * 512);
+    assert_eq!(info.cluster_heap_offset, info.fat_offset + info.fat_length);
+}
+
+#[test]
+fn multi_terabyte_volume_roundtrip() {
+    let size = 4 * TIB;
+    let options = ExFatFormatOptions::new()
+        .volume_label("HUGE")
+        .cluster_size(128 * 1024);
+    let mut device = SparseDevice::new(size);
+    {
+        let fs = format_exfat(&mut device, size, &options).expect("format_exfat");
+        let info = fs.info();
+        assert_eq!(info.bytes_per_cluster, 128 * 1024);
+        // More sectors than a u32 can count.
+        assert!(size / info.bytes_per_sector as u64 > u32::MAX as u64);
+        assert_eq!(
+            info.cluster_count as u64,
+            (size - info.cluster_heap_offset) / (128 * 1024)
+        );
+
+        write_file(&fs, "payload.bin", &pattern(1024 * 1024, 7));
+    }
+
+    device.seek(SeekFrom::Start(0)).unwrap();
+    let fs = ExFatVolume::open(&mut device).expect("reopen");
+    assert_eq!(read_file(&fs, "payload.bin"), pattern(1024 * 1024, 7));
+    // Bitmap, a one-cluster up-case table, the root, and the 1 MiB file.
+    let cluster_count = fs.info().cluster_count as u64;
+    let bitmap_clusters = cluster_count.div_ceil(8).div_ceil(128 * 1024);
+    assert_eq!(
+        fs.free_cluster_count() as u64,
+        cluster_count - bitmap_clusters - 1 - 1 - 8
+    );
+}
+
+#[test]
+fn too_many_clusters_is_rejected() {
+    let size = 4 * TIB;
+    let options = ExFatFormatOptions::new().cluster_size(512);
+    assert!(matches!(
+        format_exfat(SparseDevice::new(size), size, &options),
+        Err(hadris_fat::Error::InvalidFormatOption {
+            option: "sectors_per_cluster",
+            ..
+        })
+    ));
+}
@@ -0,0 +1,294 @@
+//! Transaction-safe (TexFAT) updates in the exFAT preview.
+//!
+//! A volume formatted with two FATs and opened with
+//! `ExFatVolume::open_transaction_safe` shadows allocation changes into the
+//! inactive FAT and bitmap and commits them by flipping ActiveFat. The
+//! power-loss test cuts writes off at every point of a workload and checks
+//! that the reopened volume is consistent.
+
+#![cfg(all(feature = "unstable-exfat", feature = "sync", feature = "write"))]
+
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+use hadris_fat::exfat::{
+    ExFatFormatOptions, ExFatVolume, VOLUME_FLAG_ACTIVE_FAT, VOLUME_FLAG_DIRTY, format_exfat,
+};
+use hadris_fat::io::{Read as HadrisRead, Write as HadrisWrite};
+
+const IMAGE_SIZE: usize = 2 * 1024 * 1024;
+const CLUSTER_SIZE: usize = 512;
+
+fn formatted(fat_count: u8) -> Vec<u8> {
+    let options = ExFatFormatOptions::new()
+        .fat_count(fat_count)
+        .sectors_per_cluster(1)
+        .volume_serial(0x1234_5678);
+    let fs = format_exfat(
+        Cursor::new(vec![0_u8; IMAGE_SIZE]),
+        IMAGE_SIZE as u64,
+        &options,
+    )
+    .unwrap();
+    fs.into_inner().into_inner()
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|index| (index % 241) as u8).collect()
+}
+
+/// Boot-sector geometry needed to inspect the FATs and bitmaps directly.
+struct Layout {
+    volume_flags: u16,
+    fat_offset: usize,
+    fat_length: usize,
+    heap_offset: usize,
+    cluster_count: usize,
+}
+
+impl Layout {
+    fn read(image: &[u8]) -> Self {
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes(image[offset..offset + 4].try_into().unwrap()) as usize
+        };
+        let sector = 1 << image[108];
+        Self {
+            volume_flags: u16::from_le_bytes([image[106], image[107]]),
+            fat_offset: u32_at(80) * sector,
+            fat_length: u32_at(84) * sector,
+            heap_offset: u32_at(88) * sector,
+            cluster_count: u32_at(92),
+        }
+    }
+
+    fn fat<'a>(&self, image: &'a [u8], copy: usize) -> &'a [u8] {
+        let start = self.fat_offset + copy * self.fat_length;
+        &image[start..start + self.fat_length]
+    }
+
+    /// The bitmap in copy `copy`; the formatter places them back to back
+    /// from cluster 2.
+    fn bitmap<'a>(&self, image: &'a [u8], copy: usize) -> &'a [u8] {
+        let size = self.cluster_count.div_ceil(8);
+        let start = self.heap_offset + copy * size.div_ceil(CLUSTER_SIZE) * CLUSTER_SIZE;
+        &image[start..start + size]
+    }
+
+    fn fat_entry(&self, image: &[u8], copy: usize, cluster: u32) -> u32 {
+        let offset = cluster as usize * 4;
+        u32::from_le_bytes(
+            self.fat(image, copy)[offset..offset + 4]
+                .try_into()
+                .unwrap(),
+        )
+    }
+}
+
+#[test]
+fn two_fat_format_has_two_bitmaps() {
+    let image = formatted(2);
+    let layout = Layout::read(&image);
+    assert_eq!(image[110], 2);
+    assert_eq!(layout.volume_flags, 0);
+    assert_eq!(layout.fat(&image, 0), layout.fat(&image, 1));
+    assert_eq!(layout.bitmap(&image, 0), layout.bitmap(&image, 1));
+
+    let mut cursor = Cursor::new(image);
+    let fs = ExFatVolume::open_transaction_safe(&mut cursor).unwrap();
+    assert!(fs.is_transaction_safe());
+    assert!(!fs.recovered_transaction());
+    assert_eq!(fs.active_fat(), 0);
+}
+
+#[test]
+fn single_fat_volume_is_not_transaction_safe() {
+    let mut cursor = Cursor::new(formatted(1));
+    assert!(ExFatVolume::open_transaction_safe(&mut cursor).is_err());
+}
+
+#[test]
+fn commit_flips_active_fat_and_keeps_copies_equal() {
+    let payload = pattern(5 * CLUSTER_SIZE + 17);
+    let mut cursor = Cursor::new(formatted(2));
+    {
+        let fs = ExFatVolume::open_transaction_safe(&mut cursor).unwrap();
+        let free = fs.free_cluster_count();
+        let entry = fs.create_file(&fs.root_dir(), "log.bin").unwrap();
+        let mut writer = fs.write_file(&entry).unwrap();
+        writer.write_all(&payload).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(fs.active_fat(), 1);
+        assert_eq!(fs.free_cluster_count(), free - 6);
+
+        fs.create_dir(&fs.root_dir(), "logs").unwrap();
+        assert_eq!(fs.active_fat(), 0);
+    }
+
+    let image = cursor.into_inner();
+    let layout = Layout::read(&image);
+    assert_eq!(layout.volume_flags & VOLUME_FLAG_DIRTY, 0);
+    assert_eq!(layout.volume_flags & VOLUME_FLAG_ACTIVE_FAT, 0);
+    assert_eq!(layout.fat(&image, 0), layout.fat(&image, 1));
+    assert_eq!(layout.bitmap(&image, 0), layout.bitmap(&image, 1));
+
+    // The ordinary opener reads the same volume
+    let mut cursor = Cursor::new(image);
+    let fs = ExFatVolume::open(&mut cursor).unwrap();
+    let mut reader = fs.open_file("/log.bin").unwrap();
+    let mut contents = vec![0_u8; payload.len()];
+    reader.read_exact(&mut contents).unwrap();
+    assert_eq!(contents, payload);
+    assert!(fs.open_path("/logs").unwrap().is_directory());
+}
+
+#[test]
+fn dirty_volume_rolls_back_inactive_copy() {
+    let mut image = formatted(2);
+    let layout = Layout::read(&image);
+    let pristine_fat = layout.fat(&image, 0).to_vec();
+
+    // An interrupted update: garbage in the inactive FAT, VolumeDirty set
+    let start = layout.fat_offset + layout.fat_length + 64;
+    image[start..start + 8].fill(0xAB);
+    image[106] = VOLUME_FLAG_DIRTY as u8;
+
+    let mut cursor = Cursor::new(image);
+    {
+        let fs = ExFatVolume::open_transaction_safe(&mut cursor).unwrap();
+        assert!(fs.recovered_transaction());
+        assert_eq!(fs.active_fat(), 0);
+    }
+    let image = cursor.into_inner();
+    let layout = Layout::read(&image);
+    assert_eq!(layout.volume_flags & VOLUME_FLAG_DIRTY, 0);
+    assert_eq!(layout.fat(&image, 1), pristine_fat);
+}
+
+/// A device that loses power after a fixed number of writes: later writes
+/// fail and change nothing.
+struct PowerCut {
+    inner: Cursor<Vec<u8>>,
+    writes_left: usize,
+}
+
+impl Read for PowerCut {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Read::read(&mut self.inner, buf)
+    }
+}
+
+impl Write for PowerCut {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.writes_left == 0 {
+            return Err(io::Error::other("power lost"));
+        }
+        self.writes_left -= 1;
+        Write::write(&mut self.inner, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for PowerCut {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+/// Appends to one file, shrinks a second and deletes a third; stops at the
+/// first error.
+fn workload(fs: &ExFatVolume<PowerCut>, payload: &[u8]) -> hadris_fat::Result<()> {
+    let root = fs.root_dir();
+    let entry = fs.create_file(&root, "log.bin")?;
+    let mut writer = fs.write_file(&entry)?;
+    writer.write_all(payload).map_err(hadris_fat::Error::Io)?;
+    writer.finish()?;
+    fs.truncate(&fs.open_path("/old.bin")?, 100)?;
+    fs.delete(&fs.open_path("/tmp.bin")?)
+}
+
+#[test]
+fn power_loss_at_any_write_leaves_a_consistent_volume() {
+    let payload = pattern(6 * CLUSTER_SIZE);
+    let base = {
+        let mut cursor = Cursor::new(formatted(2));
+        let fs = ExFatVolume::open_transaction_safe(&mut cursor).unwrap();
+        for name in ["old.bin", "tmp.bin"] {
+            let entry = fs.create_file(&fs.root_dir(), name).unwrap();
+            let mut writer = fs.write_file(&entry).unwrap();
+            writer.write_all(&pattern(4 * CLUSTER_SIZE)).unwrap();
+            writer.finish().unwrap();
+        }
+        drop(fs);
+        cursor.into_inner()
+    };
+
+    let mut cut = 0;
+    loop {
+        let device = PowerCut {
+            inner: Cursor::new(base.clone()),
+            writes_left: cut,
+        };
+        let fs = ExFatVolume::open_transaction_safe(device).unwrap();
+        let finished = workload(&fs, &payload).is_ok();
+        let image = fs.into_inner().inner.into_inner();
+        check_consistent(&image, &payload, cut);
+        if finished {
+            break;
+        }
+        cut += 1;
+    }
+    assert!(cut > 20, "the workload should take many writes, took {cut}");
+}
+
+fn check_consistent(image: &[u8], payload: &[u8], cut: usize) {
+    let mut cursor = Cursor::new(image.to_vec());
+    let fs = ExFatVolume::open_transaction_safe(&mut cursor).unwrap();
+    let active = fs.active_fat() as usize;
+    drop(fs);
+    let image = cursor.into_inner();
+    let layout = Layout::read(&image);
+    assert_eq!(layout.volume_flags & VOLUME_FLAG_DIRTY, 0, "cut {cut}");
+    assert_eq!(layout.fat(&image, 0), layout.fat(&image, 1), "cut {cut}");
+    assert_eq!(
+        layout.bitmap(&image, 0),
+        layout.bitmap(&image, 1),
+        "cut {cut}"
+    );
+
+    // No entry may reference a cluster the active bitmap calls free
+    let bitmap = layout.bitmap(&image, active);
+    let allocated = |cluster: u32| {
+        let index = cluster as usize - 2;
+        bitmap[index / 8] & (1 << (index % 8)) != 0
+    };
+    let mut cursor = Cursor::new(image.clone());
+    let fs = ExFatVolume::open(&mut cursor).unwrap();
+    for entry in fs.root_dir().entries() {
+        let entry = entry.unwrap();
+        let clusters = entry.data_length.div_ceil(CLUSTER_SIZE as u64) as u32;
+        let mut cluster = entry.first_cluster;
+        for index in 0..clusters {
+            assert!(
+                allocated(cluster),
+                "cut {cut}: {} cluster {cluster}",
+                entry.name
+            );
+            if index + 1 < clusters {
+                cluster = if entry.no_fat_chain {
+                    cluster + 1
+                } else {
+                    layout.fat_entry(&image, active, cluster)
+                };
+            }
+        }
+        if entry.name == "log.bin" && entry.size() > 0 {
+            assert_eq!(entry.size(), payload.len() as u64, "cut {cut}");
+            let mut reader = fs.open_file("/log.bin").unwrap();
+            let mut contents = vec![0_u8; payload.len()];
+            reader.read_exact(&mut contents).unwrap();
+            assert_eq!(contents, payload, "cut {cut}");
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! The write-ahead journal around FAT metadata operations.
+//!
+//! With the journal enabled, `create_file`, `create_dir`, `rename`, `delete`
+//! and `truncate` each commit as one transaction. The power-loss test cuts
+//! writes off at every point of a workload and checks that the reopened
+//! volume shows the state before or after each operation, never one in
+//! between.
+
+#![cfg(all(feature = "sync", feature = "std", feature = "write"))]
+
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+
+use hadris_fat::format::FatTypeSelection;
+use hadris_fat::journal::JOURNAL_FILE_NAME;
+use hadris_fat::raw::DirEntryAttrFlags;
+use hadris_fat::{Error, FatVolume, FatVolumeWriteExt};
+
+const IMAGE_SIZE: u64 = 8 * 1024 * 1024;
+const CLUSTER_SIZE: usize = 512;
+const JOURNAL_SIZE: usize = 16 * 1024;
+
+#[path = "common/volume.rs"]
+mod volume;
+use volume::{format, pattern, write};
+
+/// A FAT16 volume with a journal, two files in the root and a `logs`
+/// directory.
+fn base_image() -> Vec<u8> {
+    let fs = format(IMAGE_SIZE, FatTypeSelection::Fat16, 1);
+    fs.enable_journal(JOURNAL_SIZE).unwrap();
+    write(&fs, "/", "old.bin", &pattern(8 * CLUSTER_SIZE, 1));
+    write(&fs, "/", "tmp.bin", &pattern(5 * CLUSTER_SIZE, 2));
+    fs.create_dir(&fs.root_dir(), "logs").unwrap();
+    fs.into_inner().into_inner()
+}
+
+/// Byte offsets of the FATs, parsed from the BPB.
+struct Layout {
+    fat_start: usize,
+    fat_size: usize,
+}
+
+impl Layout {
+    fn of(bytes: &[u8]) -> Self {
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+        let sector = u16_at(11);
+        Self {
+            fat_start: u16_at(14) * sector,
+            fat_size: u16_at(22) * sector,
+        }
+    }
+
+    fn fat<'a>(&self, bytes: &'a [u8], copy: usize) -> &'a [u8] {
+        let start = self.fat_start + copy * self.fat_size;
+        &bytes[start..start + self.fat_size]
+    }
+
+    fn get(&self, bytes: &[u8], cluster: u32) -> u32 {
+        let at = self.fat_start + cluster as usize * 2;
+        u16::from_le_bytes([bytes[at], bytes[at + 1]]) as u32
+    }
+}
+
+#[test]
+fn enable_creates_a_hidden_journal_file() {
+    let image = base_image();
+    let fs = FatVolume::open(Cursor::new(image)).unwrap();
+    assert!(!fs.journal_enabled());
+    assert!(!fs.journal_pending());
+    let journal = fs.open_path(&format!("/{JOURNAL_FILE_NAME}")).unwrap();
+    assert_eq!(journal.len(), JOURNAL_SIZE as u64);
+    assert!(journal.attributes().contains(DirEntryAttrFlags::HIDDEN));
+    assert!(journal.attributes().contains(DirEntryAttrFlags::SYSTEM));
+
+    // Enabling again reuses the file
+    fs.enable_journal(JOURNAL_SIZE * 4).unwrap();
+    assert!(fs.journal_enabled());
+    let journal = fs.open_path(&format!("/{JOURNAL_FILE_NAME}")).unwrap();
+    assert_eq!(journal.len(), JOURNAL_SIZE as u64);
+}
+
+#[test]
+fn unreadable_journal_is_reported_not_fatal() {
+    let mut image = base_image();
+    let fs = FatVolume::open(Cursor::new(image.clone())).unwrap();
+    let first = fs
+        .open_path(&format!("/{JOURNAL_FILE_NAME}"))
+        .unwrap()
+        .cluster()
+        .0;
+    // Cut the journal's chain after its first cluster
+    let layout = Layout::of(&image);
+    for copy in 0..2 {
+        let at = layout.fat_start + copy * layout.fat_size + first * 2;
+        image[at..at + 2].copy_from_slice(&0xFFFF_u16.to_le_bytes());
+    }
+
+    let fs = FatVolume::open(Cursor::new(image)).unwrap();
+    assert!(matches!(
+        fs.take_journal_error(),
+        Some(Error::UnexpectedEndOfChain { .. })
+    ));
+    assert!(fs.take_journal_error().is_none());
+    assert!(!fs.journal_pending());
+    assert!(fs.open_path("/old.bin").unwrap().is_file());
+}
+
+#[test]
+fn journaled_operations_reach_the_disk() {
+    let fs = FatVolume::open(Cursor::new(base_image())).unwrap();
+    fs.enable_journal(JOURNAL_SIZE).unwrap();
+    let root = fs.root_dir();
+    let logs = fs.open_dir_path("/logs").unwrap();
+    fs.create_file(&logs, "today.log").unwrap();
+    let old = fs.open_path("/old.bin").unwrap();
+    let moved = fs.rename(&old, &logs, "old.log").unwrap();
+    fs.truncate(&moved, 1000).unwrap();
+    fs.delete(&fs.open_path("/tmp.bin").unwrap()).unwrap();
+    assert!(matches!(
+        fs.create_dir(&root, "logs"),
+        Err(Error::AlreadyExists)
+    ));
+
+    let image = fs.into_inner().into_inner();
+    let layout = Layout::of(&image);
+    assert_eq!(layout.fat(&image, 0), layout.fat(&image, 1));
+    let fs = FatVolume::open(Cursor::new(image)).unwrap();
+    assert!(!fs.journal_pending());
+    assert!(fs.open_path("/logs/today.log").unwrap().is_file());
+    assert!(fs.open_path("/old.bin").is_err());
+    assert!(fs.open_path("/tmp.bin").is_err());
+    let contents = fs
+        .open_file_path("/logs/old.log")
+        .unwrap()
+        .read_to_vec()
+        .unwrap();
+    assert_eq!(contents, pattern(8 * CLUSTER_SIZE, 1)[..1000]);
+}
+
+#[test]
+fn oversized_transaction_changes_nothing() {
+    let fs = format(IMAGE_SIZE, FatTypeSelection::Fat16, 1);
+    write(&fs, "/", "big.bin", &pattern(600 * CLUSTER_SIZE, 3));
+    // Two clusters: a header sector and one sector of records
+    fs.enable_journal(0).unwrap();
+    let before = fs.into_inner().into_inner();
+
+    let fs = FatVolume::open(Cursor::new(before.clone())).unwrap();
+    fs.enable_journal(0).unwrap();
+    let big = fs.open_path("/big.bin").unwrap();
+    assert!(matches!(
+        fs.delete(&big),
+        Err(Error::JournalFull { capacity: 512, .. })
+    ));
+    // Reads made after the failure see the volume as it was
+    assert_eq!(fs.open_path("/big.bin").unwrap().len(), big.len());
+    assert_eq!(fs.into_inner().into_inner(), before);
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn oversized_transaction_leaves_cache_and_fs_info_alone() {
+    let fs = format(40 * 1024 * 1024, FatTypeSelection::Fat32, 1);
+    write(&fs, "/", "big.bin", &pattern(600 * CLUSTER_SIZE, 3));
+    fs.enable_journal(0).unwrap();
+    let image = fs.into_inner().into_inner();
+
+    let fs = FatVolume::builder(Cursor::new(image))
+        .fat_cache(16)
+        .open()
+        .unwrap();
+    fs.enable_journal(0).unwrap();
+    let free = fs.free_cluster_count();
+    let hint = fs.next_free_cluster_hint();
+    let big = fs.open_path("/big.bin").unwrap();
+    assert!(matches!(fs.delete(&big), Err(Error::JournalFull { .. })));
+    assert_eq!(fs.free_cluster_count(), free);
+    assert_eq!(fs.next_free_cluster_hint(), hint);
+
+    // New clusters must not come from the chain the failed delete freed
+    fs.disable_journal();
+    write(&fs, "/", "new.bin", &pattern(50 * CLUSTER_SIZE, 4));
+    fs.flush().unwrap();
+    let contents = fs
+        .open_file_path("/big.bin")
+        .unwrap()
+        .read_to_vec()
+        .unwrap();
+    assert_eq!(contents, pattern(600 * CLUSTER_SIZE, 3));
+}
+
+/// A device that loses power after a fixed number of writes: later writes
+/// fail and change nothing.
+struct PowerCut {
+    inner: Cursor<Vec<u8>>,
+    writes_left: usize,
+}
+
+impl Read for PowerCut {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Read::read(&mut self.inner, buf)
+    }
+}
+
+impl Write for PowerCut {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.writes_left == 0 {
+            return Err(io::Error::other("power lost"));
+        }
+        self.writes_left -= 1;
+        Write::write(&mut self.inner, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for PowerCut {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn committed_transaction_is_replayed_on_open() {
+    // Count the writes a delete takes, then cut the last one: the header
+    // clear that ends the transaction
+    let writes = {
+        let device = PowerCut {
+            inner: Cursor::new(base_image()),
+            writes_left: usize::MAX,
+        };
+        let fs = FatVolume::open(device).unwrap();
+        fs.enable_journal(JOURNAL_SIZE).unwrap();
+        fs.delete(&fs.open_path("/tmp.bin").unwrap()).unwrap();
+        usize::MAX - fs.into_inner().writes_left
+    };
+    let device = PowerCut {
+        inner: Cursor::new(base_image()),
+        writes_left: writes - 1,
+    };
+    let fs = FatVolume::open(device).unwrap();
+    fs.enable_journal(JOURNAL_SIZE).unwrap();
+    assert!(fs.delete(&fs.open_path("/tmp.bin").unwrap()).is_err());
+    let image = fs.into_inner().inner.into_inner();
+
+    // Read-only: the committed delete is laid over the device
+    let fs = FatVolume::open(Cursor::new(image.clone())).unwrap();
+    assert!(fs.journal_pending());
+    assert!(fs.open_path("/tmp.bin").is_err());
+
+    // Writable: the replay reaches the disk and ends the transaction
+    let mut cursor = Cursor::new(image);
+    let fs = FatVolume::open(&mut cursor).unwrap();
+    assert!(fs.replay_journal().unwrap());
+    assert!(!fs.journal_pending());
+    assert!(!fs.replay_journal().unwrap());
+    cursor.set_position(0);
+    let fs = FatVolume::open(&mut cursor).unwrap();
+    assert!(!fs.journal_pending());
+    assert!(fs.open_path("/tmp.bin").is_err());
+}
+
+/// Creates, renames, shrinks and deletes; stops at the first error.
+fn workload(fs: &FatVolume<PowerCut>) -> hadris_fat::Result<()> {
+    let root = fs.root_dir();
+    let new = fs.create_dir(&root, "new")?;
+    fs.create_file(&new, "note.txt")?;
+    let logs = fs.open_dir_path("/logs")?;
+    let moved = fs.rename(&fs.open_path("/old.bin")?, &logs, "old.log")?;
+    fs.truncate(&moved, 1000)?;
+    fs.delete(&fs.open_path("/tmp.bin")?)
+}
+
+/// Every entry below the root as (path, is directory, size), sorted.
+fn tree(fs: &FatVolume<&mut Cursor<Vec<u8>>>) -> Vec<(String, bool, u64)> {
+    let mut entries = Vec::new();
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let handle = if dir.is_empty() {
+            fs.root_dir()
+        } else {
+            fs.open_dir_path(&dir).unwrap()
+        };
+        for entry in handle.entries() {
+            let entry = entry.unwrap();
+            let entry = entry.as_entry().unwrap();
+            let name = entry.name();
+            if name == "." || name == ".." || name == JOURNAL_FILE_NAME {
+                continue;
+            }
+            let path = format!("{dir}/{name}");
+            if entry.is_directory() {
+                pending.push(path.clone());
+            }
+            entries.push((path, entry.is_directory(), entry.len()));
+        }
+    }
+    entries.sort();
+    entries
+}
+
+/// Replay what the journal holds, then check the FATs agree and every
+/// allocated cluster belongs to exactly one entry. Returns the tree.
+fn recover_and_check(image: Vec<u8>, cut: usize) -> Vec<(String, bool, u64)> {
+    let mut cursor = Cursor::new(image);
+    let fs = FatVolume::open(&mut cursor).unwrap();
+    fs.replay_journal().unwrap();
+    let tree = tree(&fs);
+
+    let mut chains = vec![fs.open_path(&format!("/{JOURNAL_FILE_NAME}")).unwrap()];
+    for (path, _, _) in &tree {
+        chains.push(fs.open_path(path).unwrap());
+    }
+    let image = cursor.into_inner();
+    let layout = Layout::of(&image);
+    assert_eq!(layout.fat(&image, 0), layout.fat(&image, 1), "cut {cut}");
+
+    let mut owned = std::collections::BTreeSet::new();
+    for entry in chains {
+        let mut cluster = entry.cluster().0 as u32;
+        let mut length = 0;
+        while (2..0xFFF8).contains(&cluster) {
+            assert!(
+                owned.insert(cluster),
+                "cut {cut}: cluster {cluster} cross-linked"
+            );
+            length += 1;
+            cluster = layout.get(&image, cluster);
+        }
+        if entry.is_file() {
+            let expected = (entry.len() as usize).div_ceil(CLUSTER_SIZE);
+            assert_eq!(length, expected, "cut {cut}: {} chain length", entry.name());
+        }
+    }
+    let allocated: std::collections::BTreeSet<u32> = (2..(layout.fat_size / 2) as u32)
+        .filter(|&cluster| layout.get(&image, cluster) != 0)
+        .collect();
+    assert_eq!(allocated, owned, "cut {cut}: lost clusters");
+    tree
+}
+
+#[test]
+fn power_loss_at_any_write_leaves_a_consistent_volume() {
+    let base = base_image();
+
+    // The tree after each step of an uninterrupted run
+    let mut states = vec![recover_and_check(base.clone(), 0)];
+    {
+        let mut cursor = Cursor::new(base.clone());
+        let fs = FatVolume::open(&mut cursor).unwrap();
+        fs.enable_journal(JOURNAL_SIZE).unwrap();
+        let root = fs.root_dir();
+        let new = fs.create_dir(&root, "new").unwrap();
+        states.push(tree(&fs));
+        fs.create_file(&new, "note.txt").unwrap();
+        states.push(tree(&fs));
+        let logs = fs.open_dir_path("/logs").unwrap();
+        let moved = fs
+            .rename(&fs.open_path("/old.bin").unwrap(), &logs, "old.log")
+            .unwrap();
+        states.push(tree(&fs));
+        fs.truncate(&moved, 1000).unwrap();
+        states.push(tree(&fs));
+        fs.delete(&fs.open_path("/tmp.bin").unwrap()).unwrap();
+        states.push(tree(&fs));
+    }
+
+    let mut cut = 0;
+    loop {
+        let device = PowerCut {
+            inner: Cursor::new(base.clone()),
+            writes_left: cut,
+        };
+        let fs = FatVolume::open(device).unwrap();
+        fs.enable_journal(JOURNAL_SIZE).unwrap();
+        let finished = workload(&fs).is_ok();
+        let image = fs.into_inner().inner.into_inner();
+        let tree = recover_and_check(image, cut);
+        assert!(states.contains(&tree), "cut {cut}: torn state {tree:?}");
+        if finished {
+            assert_eq!(&tree, states.last().unwrap());
+            break;
+        }
+        cut += 1;
+    }
+    assert!(cut > 20, "the workload should take many writes, took {cut}");
+}
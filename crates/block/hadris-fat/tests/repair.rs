@@ -0,0 +1,498 @@
+//! Repairing damaged FAT volumes with `FatRepairExt::repair`.
+//!
+//! Each test formats an in-memory image, writes a small tree, corrupts the
+//! raw bytes, and remounts. After a repair the volume must pass `verify`,
+//! and a second repair must find nothing left to fix.
+
+#![cfg(all(feature = "tool", feature = "std", feature = "write"))]
+
+use std::io::Cursor;
+
+use hadris_fat::format::FatTypeSelection;
+use hadris_fat::tool::{LostChainAction, RepairAction, RepairOptions};
+use hadris_fat::{FatRepairExt, FatVerifyExt, FatVolume};
+
+#[path = "common/volume.rs"]
+mod volume;
+use volume::{Volume, format, pattern, write};
+
+const MIB: u64 = 1024 * 1024;
+
+fn fat16() -> Volume {
+    format(16 * MIB, FatTypeSelection::Fat16, 1)
+}
+
+fn mount(bytes: Vec<u8>) -> Volume {
+    FatVolume::open(Cursor::new(bytes)).unwrap()
+}
+
+fn read(fs: &Volume, path: &str) -> Vec<u8> {
+    fs.open_file_path(path).unwrap().read_to_vec().unwrap()
+}
+
+fn first_cluster(fs: &Volume, path: &str) -> u32 {
+    fs.open_path(path).unwrap().cluster().0 as u32
+}
+
+/// Byte offsets of the on-disk regions, parsed from the BPB.
+struct Layout {
+    fat_start: usize,
+    fat_size: usize,
+    root_start: usize,
+    data_start: usize,
+    cluster_size: usize,
+    fat32: bool,
+}
+
+impl Layout {
+    fn of(bytes: &[u8]) -> Self {
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize;
+        let sector = u16_at(11);
+        let reserved = u16_at(14);
+        let fats = bytes[16] as usize;
+        let root_entries = u16_at(17);
+        let fat32 = u16_at(22) == 0;
+        let fat_sectors = if fat32 {
+            u32::from_le_bytes(bytes[36..40].try_into().unwrap()) as usize
+        } else {
+            u16_at(22)
+        };
+        let fat_start = reserved * sector;
+        let fat_size = fat_sectors * sector;
+        let root_start = fat_start + fats * fat_size;
+        Self {
+            fat_start,
+            fat_size,
+            root_start,
+            data_start: root_start + (root_entries * 32).div_ceil(sector) * sector,
+            cluster_size: bytes[13] as usize * sector,
+            fat32,
+        }
+    }
+
+    fn entry(&self, copy: usize, cluster: u32) -> usize {
+        let width = if self.fat32 { 4 } else { 2 };
+        self.fat_start + copy * self.fat_size + cluster as usize * width
+    }
+
+    fn get(&self, bytes: &[u8], copy: usize, cluster: u32) -> u32 {
+        let at = self.entry(copy, cluster);
+        if self.fat32 {
+            u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+        } else {
+            u16::from_le_bytes([bytes[at], bytes[at + 1]]) as u32
+        }
+    }
+
+    fn set(&self, bytes: &mut [u8], copy: usize, cluster: u32, value: u32) {
+        let at = self.entry(copy, cluster);
+        if self.fat32 {
+            bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+        } else {
+            bytes[at..at + 2].copy_from_slice(&(value as u16).to_le_bytes());
+        }
+    }
+
+    /// Set `cluster`'s entry in both FAT copies.
+    fn link(&self, bytes: &mut [u8], cluster: u32, value: u32) {
+        self.set(bytes, 0, cluster, value);
+        self.set(bytes, 1, cluster, value);
+    }
+
+    fn cluster(&self, cluster: u32) -> usize {
+        self.data_start + (cluster as usize - 2) * self.cluster_size
+    }
+
+    /// Offset of the short entry named `name` in the region at `start`.
+    fn slot(&self, bytes: &[u8], start: usize, name: &[u8; 11]) -> usize {
+        (start..start + self.cluster_size)
+            .step_by(32)
+            .find(|&at| &bytes[at..at + 11] == name && bytes[at + 11] != 0x0F)
+            .expect("slot not found")
+    }
+}
+
+/// Repair, then check the volume verifies and needs no second repair.
+fn repair(fs: &Volume, options: RepairOptions) -> Vec<RepairAction> {
+    let report = fs.repair(&options).unwrap();
+    assert!(!report.dry_run);
+    let verification = fs.verify().unwrap();
+    assert!(verification.is_valid(), "{:?}", verification.issues);
+    assert!(fs.repair(&RepairOptions::new()).unwrap().is_clean());
+    report.actions
+}
+
+#[test]
+fn healthy_volumes_need_no_repair() {
+    for (size, fat_type) in [
+        (MIB, FatTypeSelection::Fat12),
+        (16 * MIB, FatTypeSelection::Fat16),
+        (40 * MIB, FatTypeSelection::Fat32),
+    ] {
+        let fs = format(size, fat_type, 1);
+        write(&fs, "/", "A long file name.txt", &pattern(3000, 1));
+        fs.create_dir(&fs.root_dir(), "Sub directory").unwrap();
+        fs.create_dir(&fs.open_dir_path("/Sub directory").unwrap(), "INNER")
+            .unwrap();
+        write(&fs, "/Sub directory/INNER", "B.BIN", &pattern(700, 2));
+        write(&fs, "/", "EMPTY.TXT", b"");
+
+        let report = fs.repair(&RepairOptions::new()).unwrap();
+        assert!(report.is_clean(), "{fat_type:?}: {:?}", report.actions);
+    }
+}
+
+#[test]
+fn dry_run_reports_without_writing() {
+    let fs = fat16();
+    write(&fs, "/", "A.BIN", &pattern(2000, 1));
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let slot = layout.slot(&bytes, layout.root_start, b"A       BIN");
+    bytes[slot + 28..slot + 32].copy_from_slice(&9000u32.to_le_bytes());
+
+    let fs = mount(bytes.clone());
+    let report = fs.repair(&RepairOptions::new().dry_run(true)).unwrap();
+    assert!(report.dry_run);
+    assert_eq!(
+        report.actions,
+        [RepairAction::FixFileSize {
+            path: "/A.BIN".into(),
+            recorded_size: 9000,
+            new_size: 2048,
+        }]
+    );
+    assert_eq!(fs.into_inner().into_inner(), bytes);
+}
+
+#[test]
+fn lost_chain_is_recovered_into_found_directory() {
+    let fs = fat16();
+    write(&fs, "/", "KEEP.TXT", b"kept");
+    write(&fs, "/", "LOST.BIN", &pattern(3000, 3));
+    let start = first_cluster(&fs, "/LOST.BIN");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let slot = layout.slot(&bytes, layout.root_start, b"LOST    BIN");
+    bytes[slot] = 0xE5;
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [RepairAction::RecoverLostChain {
+            start_cluster: start,
+            chain_length: 6,
+            recovered_as: "/FOUND.000/FILE0000.CHK".into(),
+        }]
+    );
+    let recovered = read(&fs, "/FOUND.000/FILE0000.CHK");
+    assert_eq!(recovered.len(), 6 * 512);
+    assert_eq!(&recovered[..3000], pattern(3000, 3));
+    assert_eq!(read(&fs, "/KEEP.TXT"), b"kept");
+}
+
+#[test]
+fn lost_chain_can_be_freed() {
+    let fs = fat16();
+    write(&fs, "/", "LOST.BIN", &pattern(3000, 3));
+    let start = first_cluster(&fs, "/LOST.BIN");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let slot = layout.slot(&bytes, layout.root_start, b"LOST    BIN");
+    bytes[slot] = 0xE5;
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new().lost_chains(LostChainAction::Free));
+    assert_eq!(
+        actions,
+        [RepairAction::FreeLostChain {
+            start_cluster: start,
+            chain_length: 6,
+        }]
+    );
+    assert!(fs.open_path("/FOUND.000").is_err());
+    let bytes = fs.into_inner().into_inner();
+    assert_eq!(Layout::of(&bytes).get(&bytes, 1, start), 0);
+}
+
+#[test]
+fn failed_recovery_leaves_volume_untouched() {
+    let fs = fat16();
+    write(&fs, "/", "KEEP.TXT", b"kept");
+    write(&fs, "/", "LOST.BIN", &pattern(3000, 3));
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let slot = layout.slot(&bytes, layout.root_start, b"LOST    BIN");
+    bytes[slot] = 0xE5;
+    let slot = layout.slot(&bytes, layout.root_start, b"KEEP    TXT");
+    bytes[slot + 28..slot + 32].copy_from_slice(&5000u32.to_le_bytes());
+    // Leave no cluster free for FOUND.000.
+    for cluster in 2..(layout.fat_size / 2) as u32 {
+        if layout.get(&bytes, 0, cluster) == 0 {
+            layout.link(&mut bytes, cluster, 0xFFF7);
+        }
+    }
+
+    let fs = mount(bytes.clone());
+    assert!(fs.repair(&RepairOptions::new()).is_err());
+    assert_eq!(fs.into_inner().into_inner(), bytes);
+}
+
+#[test]
+fn cross_linked_file_is_cut_at_shared_cluster() {
+    let fs = fat16();
+    write(&fs, "/", "A.BIN", &pattern(2000, 1));
+    write(&fs, "/", "B.BIN", &pattern(2000, 2));
+    let a = first_cluster(&fs, "/A.BIN");
+    let b = first_cluster(&fs, "/B.BIN");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    layout.link(&mut bytes, b, a + 1);
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions[..2],
+        [
+            RepairAction::ResolveCrossLink {
+                path: "/B.BIN".into(),
+                cluster: a + 1,
+                owner: "/A.BIN".into(),
+            },
+            RepairAction::FixFileSize {
+                path: "/B.BIN".into(),
+                recorded_size: 2000,
+                new_size: 512,
+            },
+        ]
+    );
+    // B's orphaned tail is recovered as a lost chain.
+    assert!(matches!(
+        actions[2],
+        RepairAction::RecoverLostChain { start_cluster, chain_length: 3, .. } if start_cluster == b + 1
+    ));
+    assert_eq!(read(&fs, "/A.BIN"), pattern(2000, 1));
+    assert_eq!(read(&fs, "/B.BIN"), pattern(512, 2));
+}
+
+#[test]
+fn cluster_loop_is_broken() {
+    let fs = fat16();
+    write(&fs, "/", "A.BIN", &pattern(2000, 1));
+    let a = first_cluster(&fs, "/A.BIN");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    layout.link(&mut bytes, a + 3, a);
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [RepairAction::BreakClusterLoop {
+            path: "/A.BIN".into(),
+            cluster: a + 3,
+        }]
+    );
+    assert_eq!(read(&fs, "/A.BIN"), pattern(2000, 1));
+}
+
+#[test]
+fn size_and_chain_length_mismatches_are_fixed() {
+    let fs = fat16();
+    write(&fs, "/", "SHORT.BIN", &pattern(2000, 1));
+    write(&fs, "/", "LONG.BIN", &pattern(600, 2));
+    write(&fs, "/", "BROKEN.BIN", &pattern(1500, 3));
+    let broken = first_cluster(&fs, "/BROKEN.BIN");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let slot = layout.slot(&bytes, layout.root_start, b"SHORT   BIN");
+    bytes[slot + 28..slot + 32].copy_from_slice(&100u32.to_le_bytes());
+    let slot = layout.slot(&bytes, layout.root_start, b"LONG    BIN");
+    bytes[slot + 28..slot + 32].copy_from_slice(&5000u32.to_le_bytes());
+    layout.link(&mut bytes, broken, 0xFFF0);
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions[..4],
+        [
+            RepairAction::TrimChain {
+                path: "/SHORT.BIN".into(),
+                kept: 1,
+                freed: 3,
+            },
+            RepairAction::FixFileSize {
+                path: "/LONG.BIN".into(),
+                recorded_size: 5000,
+                new_size: 1024,
+            },
+            RepairAction::TruncateBrokenChain {
+                path: "/BROKEN.BIN".into(),
+                cluster: broken,
+                next: 0xFFF0,
+            },
+            RepairAction::FixFileSize {
+                path: "/BROKEN.BIN".into(),
+                recorded_size: 1500,
+                new_size: 512,
+            },
+        ]
+    );
+    // The clusters cut off BROKEN.BIN are recovered as a lost chain.
+    assert!(matches!(
+        actions[4..],
+        [RepairAction::RecoverLostChain { start_cluster, chain_length: 2, .. }] if start_cluster == broken + 1
+    ));
+    assert_eq!(read(&fs, "/SHORT.BIN"), pattern(100, 1));
+    assert_eq!(read(&fs, "/LONG.BIN")[..600], pattern(600, 2));
+}
+
+#[test]
+fn mismatched_fat_copies_are_synced() {
+    let fs = fat16();
+    write(&fs, "/", "A.BIN", &pattern(2000, 1));
+    let a = first_cluster(&fs, "/A.BIN");
+    let clean = fs.into_inner().into_inner();
+    let layout = Layout::of(&clean);
+
+    // A stale backup is overwritten from the primary.
+    let mut bytes = clean.clone();
+    layout.set(&mut bytes, 1, a + 1, 0);
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [RepairAction::SyncFatCopies {
+            source: 0,
+            mismatched_entries: 1,
+        }]
+    );
+    let bytes = fs.into_inner().into_inner();
+    assert_eq!(bytes, clean);
+
+    // A primary with invalid links loses to the backup.
+    let mut bytes = clean.clone();
+    layout.set(&mut bytes, 0, a + 1, 0xFFF0);
+    layout.set(&mut bytes, 0, a + 2, 0xFFF1);
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [RepairAction::SyncFatCopies {
+            source: 1,
+            mismatched_entries: 2,
+        }]
+    );
+    assert_eq!(read(&fs, "/A.BIN"), pattern(2000, 1));
+}
+
+#[test]
+fn bad_dot_entries_are_repointed() {
+    let fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    fs.create_dir(&fs.root_dir(), "SUB").unwrap();
+    fs.create_dir(&fs.open_dir_path("/SUB").unwrap(), "INNER")
+        .unwrap();
+    let sub = first_cluster(&fs, "/SUB");
+    let inner = first_cluster(&fs, "/SUB/INNER");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let dot = layout.cluster(inner);
+    bytes[dot + 26..dot + 28].copy_from_slice(&77u16.to_le_bytes());
+    let dotdot = dot + 32;
+    bytes[dotdot + 26..dotdot + 28].copy_from_slice(&99u16.to_le_bytes());
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [
+            RepairAction::FixDotEntry {
+                path: "/SUB/INNER".into(),
+                name: ".",
+                found: 77,
+                expected: inner,
+            },
+            RepairAction::FixDotEntry {
+                path: "/SUB/INNER".into(),
+                name: "..",
+                found: 99,
+                expected: sub,
+            },
+        ]
+    );
+}
+
+#[test]
+fn orphaned_long_name_slots_are_removed() {
+    let fs = fat16();
+    write(&fs, "/", "A long file name.txt", b"contents");
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    // Break the checksum of the first LFN slot; the whole run is orphaned.
+    let lfn = (layout.root_start..)
+        .step_by(32)
+        .find(|&at| bytes[at + 11] == 0x0F)
+        .unwrap();
+    bytes[lfn + 13] ^= 0xFF;
+
+    let fs = mount(bytes);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [RepairAction::RemoveOrphanedLfn {
+            path: "/".into(),
+            entries: 2,
+        }]
+    );
+    // Only the short name is left.
+    let entry = fs.root_dir().entries().next().unwrap().unwrap();
+    assert_eq!(entry.name(), "ALONGFIL.TXT");
+}
+
+#[test]
+fn dirty_volume_flags_are_cleared() {
+    let fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    write(&fs, "/", "A.BIN", &pattern(2000, 1));
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let status = layout.get(&bytes, 0, 1) & !0x0C00_0000;
+    layout.link(&mut bytes, 1, status);
+
+    let fs = mount(bytes);
+    let flags = fs.read_status_flags().unwrap();
+    assert!(flags.dirty && flags.io_errors);
+    let actions = repair(&fs, RepairOptions::new());
+    assert_eq!(
+        actions,
+        [RepairAction::ClearVolumeFlags {
+            dirty: true,
+            io_errors: true,
+        }]
+    );
+    let flags = fs.read_status_flags().unwrap();
+    assert!(!flags.dirty && !flags.io_errors);
+}
+
+#[test]
+fn fat32_recovery_updates_free_count() {
+    let fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    write(&fs, "/", "LOST.BIN", &pattern(3000, 3));
+    let mut bytes = fs.into_inner().into_inner();
+    let layout = Layout::of(&bytes);
+    let root = layout.cluster(2);
+    let slot = layout.slot(&bytes, root, b"LOST    BIN");
+    bytes[slot] = 0xE5;
+
+    let fs = mount(bytes);
+    repair(&fs, RepairOptions::new());
+    assert_eq!(
+        &read(&fs, "/FOUND.000/FILE0000.CHK")[..3000],
+        pattern(3000, 3)
+    );
+    let free = fs.free_cluster_count().unwrap();
+    let fs = mount(fs.into_inner().into_inner());
+    let stats = hadris_fat::FatAnalysisExt::statistics(&fs).unwrap();
+    assert_eq!(free, stats.free_clusters);
+    assert_eq!(fs.free_cluster_count(), Some(free));
+}
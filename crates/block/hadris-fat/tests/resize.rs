@@ -0,0 +1,218 @@
+//! Growing and shrinking FAT16/FAT32 volumes with `FatVolume::resize`.
+//!
+//! Each test formats an in-memory image, writes a small tree, resizes, and
+//! checks the tree through the resized handle and again after remounting
+//! the raw bytes (truncated to the new size when shrinking).
+
+#![cfg(all(feature = "sync", feature = "std", feature = "write"))]
+
+use std::io::Cursor;
+
+use hadris_fat::format::FatTypeSelection;
+use hadris_fat::{DirectoryEntry, Error, FatType, FatVolume};
+
+#[path = "common/volume.rs"]
+mod volume;
+use volume::{Volume, format, pattern, write};
+
+const MIB: u64 = 1024 * 1024;
+
+/// Remount `fs` over its bytes, extended or truncated to `size`.
+fn remount(fs: Volume, size: u64) -> Volume {
+    let mut bytes = fs.into_inner().into_inner();
+    bytes.resize(size as usize, 0);
+    FatVolume::open(Cursor::new(bytes)).unwrap()
+}
+
+fn mkdir(fs: &Volume, dir: &str, name: &str) {
+    let parent = if dir == "/" {
+        fs.root_dir()
+    } else {
+        fs.open_dir_path(dir).unwrap()
+    };
+    fs.create_dir(&parent, name).unwrap();
+}
+
+fn delete(fs: &Volume, path: &str) {
+    let entry = fs.open_path(path).unwrap();
+    fs.delete(&entry).unwrap();
+}
+
+fn read(fs: &Volume, path: &str) -> Vec<u8> {
+    fs.open_file_path(path).unwrap().read_to_vec().unwrap()
+}
+
+/// The cluster a directory's `..` entry points at.
+fn parent_link(fs: &Volume, path: &str) -> usize {
+    let dir = fs.open_dir_path(path).unwrap();
+    for entry in dir.entries() {
+        let DirectoryEntry::Entry(entry) = entry.unwrap();
+        if entry.name() == ".." {
+            return entry.cluster().0;
+        }
+    }
+    panic!("{path} has no `..` entry")
+}
+
+/// A tree whose later files sit after `filler`, which is then deleted so
+/// they end up in the upper part of the volume.
+fn write_tree(fs: &Volume, filler: usize) {
+    write(fs, "/", "FILLER.BIN", &pattern(filler, 0));
+    write(fs, "/", "ROOT.TXT", b"root file");
+    mkdir(fs, "/", "DEEP");
+    mkdir(fs, "/DEEP", "INNER");
+    write(fs, "/DEEP", "A.BIN", &pattern(70_000, 1));
+    write(fs, "/DEEP/INNER", "B.BIN", &pattern(150_000, 2));
+    // Enough entries to spill INNER into a second cluster.
+    for i in 0..40 {
+        write(
+            fs,
+            "/DEEP/INNER",
+            &format!("F{i}.TXT"),
+            format!("file {i}").as_bytes(),
+        );
+    }
+    delete(fs, "/FILLER.BIN");
+}
+
+fn assert_tree(fs: &Volume) {
+    assert_eq!(read(fs, "/ROOT.TXT"), b"root file");
+    assert_eq!(read(fs, "/DEEP/A.BIN"), pattern(70_000, 1));
+    assert_eq!(read(fs, "/DEEP/INNER/B.BIN"), pattern(150_000, 2));
+    for i in 0..40 {
+        assert_eq!(
+            read(fs, &format!("/DEEP/INNER/F{i}.TXT")),
+            format!("file {i}").as_bytes()
+        );
+    }
+    let deep = fs.open_path("/DEEP").unwrap().cluster().0;
+    assert_eq!(parent_link(fs, "/DEEP/INNER"), deep);
+    assert_eq!(parent_link(fs, "/DEEP"), 0);
+}
+
+#[test]
+fn fat32_grow_extends_fat_and_shifts_data() {
+    let fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    write_tree(&fs, 100_000);
+    let old_max = fs.fat().max_cluster();
+    let old_free = fs.free_cluster_count().unwrap();
+
+    let mut fs = remount(fs, 80 * MIB);
+    assert_eq!(fs.resize((80 * MIB / 512) as u32).unwrap(), 0);
+    let new_max = fs.fat().max_cluster();
+    // 80 MiB of 512-byte clusters no longer fits the original FAT.
+    assert!(new_max > 2 * old_max - 2000);
+    assert_eq!(fs.free_cluster_count(), Some(old_free + new_max - old_max));
+    assert_tree(&fs);
+
+    // Newly added clusters are allocatable and the FSInfo count persisted.
+    write(&fs, "/", "NEW.BIN", &pattern(MIB as usize, 3));
+    let free = fs.free_cluster_count();
+    let fs = remount(fs, 80 * MIB);
+    assert_eq!(fs.fat_type(), FatType::Fat32);
+    assert_eq!(fs.fat().max_cluster(), new_max);
+    assert_eq!(fs.free_cluster_count(), free);
+    assert_eq!(read(&fs, "/NEW.BIN"), pattern(MIB as usize, 3));
+    assert_tree(&fs);
+}
+
+#[test]
+fn fat32_shrink_relocates_tail_clusters() {
+    let mut fs = format(80 * MIB, FatTypeSelection::Fat32, 1);
+    write_tree(&fs, (50 * MIB) as usize);
+    let tail = fs.open_path("/DEEP/INNER/B.BIN").unwrap().cluster().0;
+    assert!(
+        tail as u64 > 40 * MIB / 512,
+        "tree should sit past the new end"
+    );
+
+    let relocated = fs.resize((40 * MIB / 512) as u32).unwrap();
+    // The two directories, A.BIN, and B.BIN (plus small files) moved.
+    assert!(relocated > (70_000 + 150_000) / 512);
+    assert!(fs.fat().max_cluster() < (40 * MIB / 512) as u32);
+    assert_tree(&fs);
+
+    // Nothing references the truncated tail.
+    let fs = remount(fs, 40 * MIB);
+    assert_tree(&fs);
+    let fill = (fs.free_cluster_count().unwrap() as usize - 16) * 512;
+    write(&fs, "/", "FILL.BIN", &pattern(fill, 4));
+    assert_eq!(read(&fs, "/FILL.BIN"), pattern(fill, 4));
+    assert_tree(&fs);
+}
+
+#[test]
+fn fat16_grow_moves_root_directory_then_shrinks_back() {
+    let fs = format(20 * MIB, FatTypeSelection::Fat16, 4);
+    write_tree(&fs, 100_000);
+    let old_max = fs.fat().max_cluster();
+
+    let mut fs = remount(fs, 60 * MIB);
+    fs.resize((60 * MIB / 512) as u32).unwrap();
+    assert!(fs.fat().max_cluster() > 2 * old_max);
+    assert_tree(&fs);
+    assert_eq!(fs.read_root_label().unwrap(), Some(*b"HADRIS     "));
+
+    // Push a file past the old end, then shrink back under it.
+    write(&fs, "/", "PAD.BIN", &pattern(20 * MIB as usize, 5));
+    write(&fs, "/DEEP", "LATE.BIN", &pattern(300_000, 6));
+    delete(&fs, "/PAD.BIN");
+    assert!(fs.open_path("/DEEP/LATE.BIN").unwrap().cluster().0 as u32 > old_max);
+
+    assert!(fs.resize((20 * MIB / 512) as u32).unwrap() > 0);
+    let fs = remount(fs, 20 * MIB);
+    assert_eq!(fs.fat_type(), FatType::Fat16);
+    assert_tree(&fs);
+    assert_eq!(read(&fs, "/DEEP/LATE.BIN"), pattern(300_000, 6));
+}
+
+#[test]
+fn resize_rejects_impossible_sizes() {
+    let mut fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    write(&fs, "/", "BIG.BIN", &pattern(37 * MIB as usize, 7));
+
+    // Too few clusters for FAT32.
+    assert!(matches!(
+        fs.resize((30 * MIB / 512) as u32),
+        Err(Error::VolumeTooSmall { .. })
+    ));
+    // Larger than the backing store.
+    assert!(matches!(
+        fs.resize((48 * MIB / 512) as u32),
+        Err(Error::VolumeTooLarge { .. })
+    ));
+    // Still FAT32-sized, but the data no longer fits.
+    assert!(matches!(
+        fs.resize((36 * MIB / 512) as u32),
+        Err(Error::NoFreeSpace)
+    ));
+    assert_eq!(read(&fs, "/BIG.BIN"), pattern(37 * MIB as usize, 7));
+
+    let mut fat12 = format(2 * MIB, FatTypeSelection::Fat12, 1);
+    assert!(matches!(
+        fat12.resize((4 * MIB / 512) as u32),
+        Err(Error::UnsupportedFatType(_))
+    ));
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn resize_rebuilds_the_fat_cache() {
+    let fs = format(40 * MIB, FatTypeSelection::Fat32, 1);
+    write(&fs, "/", "KEEP.TXT", b"kept");
+    let mut bytes = fs.into_inner().into_inner();
+    bytes.resize((80 * MIB) as usize, 0);
+
+    let mut fs = FatVolume::builder(Cursor::new(bytes))
+        .fat_cache(16)
+        .open()
+        .unwrap();
+    fs.resize((80 * MIB / 512) as u32).unwrap();
+    assert!(fs.fat_cache().is_some());
+    write(&fs, "/", "AFTER.BIN", &pattern(200_000, 8));
+    fs.flush().unwrap();
+
+    let fs = remount(fs, 80 * MIB);
+    assert_eq!(read(&fs, "/KEEP.TXT"), b"kept");
+    assert_eq!(read(&fs, "/AFTER.BIN"), pattern(200_000, 8));
+}
@@ -21,7 +21,7 @@
 //! dd if=tests/fixtures/exfat_partition.img of=tests/fixtures/exfat_boot_sectors.bin bs=512 count=24
 //! ```
 
-#![cfg(feature = "unstable-exfat")]
+#![cfg(all(feature = "unstable-exfat", feature = "sync"))]
 
 use hadris_fat::Error;
 use hadris_fat::exfat::{ExFatBootSector, ExFatVolume};
@@ -34,6 +34,8 @@ pub const ATTR_INDEX_ROOT: u32 = 0x90;
 pub const ATTR_INDEX_ALLOCATION: u32 = 0xA0;
 /// `$BITMAP` attribute type code.
 pub const ATTR_BITMAP: u32 = 0xB0;
+/// `$REPARSE_POINT` attribute type code.
+pub const ATTR_REPARSE_POINT: u32 = 0xC0;
 /// End marker for an MFT record's attribute list.
 pub const ATTR_END: u32 = 0xFFFF_FFFF;
 
@@ -54,6 +56,9 @@ pub const MFT_RECORD_IS_DIRECTORY: u16 = 0x0002;
 pub const INDEX_ENTRY_SUBNODE: u32 = 0x0001;
 /// Index-entry flag marking the final entry in an index node.
 pub const INDEX_ENTRY_LAST: u32 = 0x0002;
+/// Index-node header flag indicating that the node's entries point at
+/// child nodes in `$INDEX_ALLOCATION`.
+pub const INDEX_NODE_HAS_CHILDREN: u32 = 0x0001;
 
 // ---------------------------------------------------------------------------
 // File name namespace values ($FILE_NAME offset 0x41)
@@ -74,10 +79,38 @@ pub const FILE_NAME_WIN32_AND_DOS: u8 = 3;
 
 /// MFT record number of the `$MFT` metadata file.
 pub const MFT_RECORD_MFT: u64 = 0;
+/// MFT record number of the `$MFTMirr` metadata file.
+pub const MFT_RECORD_MFT_MIRR: u64 = 1;
+/// MFT record number of the `$Volume` metadata file.
+pub const MFT_RECORD_VOLUME: u64 = 3;
 /// MFT record number of the root directory.
 pub const MFT_RECORD_ROOT_DIR: u64 = 5;
 /// MFT record number of the `$UpCase` metadata file.
 pub const MFT_RECORD_UPCASE: u64 = 10;
+/// First MFT record number not reserved for metadata files.
+pub const MFT_RECORD_FIRST_USER: u64 = 16;
+
+// ---------------------------------------------------------------------------
+// Volume flags ($VOLUME_INFORMATION offset 0x0A)
+// ---------------------------------------------------------------------------
+
+/// Volume flag set while the volume is mounted and cleared on a clean
+/// unmount; `chkdsk` also sets it to request a check.
+pub const VOLUME_IS_DIRTY: u16 = 0x0001;
+
+// ---------------------------------------------------------------------------
+// Reparse tags ($REPARSE_POINT offset 0x00)
+// ---------------------------------------------------------------------------
+
+/// Reparse tag of a mount point: a junction or a volume mount point.
+pub const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+/// Reparse tag of a Win32 symbolic link.
+pub const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+/// Reparse tag of a symbolic link created by WSL.
+pub const IO_REPARSE_TAG_LX_SYMLINK: u32 = 0xA000_001D;
+/// Symbolic-link flag: the substitute name is relative to the link's
+/// directory.
+pub const SYMLINK_FLAG_RELATIVE: u32 = 0x0000_0001;
 
 // ---------------------------------------------------------------------------
 // Attribute flags
@@ -90,6 +123,27 @@ pub const ATTR_FLAG_ENCRYPTED: u16 = 0x4000;
 /// Attribute flag indicating sparse storage.
 pub const ATTR_FLAG_SPARSE: u16 = 0x8000;
 
+// ---------------------------------------------------------------------------
+// File attribute flags ($STANDARD_INFORMATION offset 0x20)
+// ---------------------------------------------------------------------------
+
+/// File attribute flag for a read-only file.
+pub const FILE_ATTRIBUTE_READONLY: u32 = 0x0001;
+/// File attribute flag for a hidden file.
+pub const FILE_ATTRIBUTE_HIDDEN: u32 = 0x0002;
+/// File attribute flag for an operating-system file.
+pub const FILE_ATTRIBUTE_SYSTEM: u32 = 0x0004;
+/// File attribute flag for a file marked for backup.
+pub const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x0020;
+/// File attribute flag for a sparse file.
+pub const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x0200;
+/// File attribute flag for a file with a reparse point.
+pub const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x0400;
+/// File attribute flag for a compressed file.
+pub const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x0800;
+/// File attribute flag for an encrypted file.
+pub const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x4000;
+
 // ---------------------------------------------------------------------------
 // $I30 index name (UTF-16LE for "$I30")
 // ---------------------------------------------------------------------------
@@ -142,6 +196,57 @@ pub fn decode_record_size(
 /// @hadris-compliance unknown
 /// @hadris-tests compliance::fixups_restore_each_sector_trailer
 pub fn apply_fixups(record: &mut [u8], sector_size: usize) -> Result<()> {
+    let (uso, num_fixups) = fixup_layout(record, sector_size)?;
+    let usn = u16::from_le_bytes([record[uso], record[uso + 1]]);
+
+    for i in 0..num_fixups {
+        let sector_end = (i + 1) * sector_size - 2;
+
+        let on_disk = u16::from_le_bytes([record[sector_end], record[sector_end + 1]]);
+        if on_disk != usn {
+            return Err(NtfsError::FixupMismatch {
+                expected: usn,
+                found: on_disk,
+            });
+        }
+
+        let saved_offset = uso + 2 + i * 2;
+        record[sector_end] = record[saved_offset];
+        record[sector_end + 1] = record[saved_offset + 1];
+    }
+
+    Ok(())
+}
+
+/// Reverse [`apply_fixups`] before a record is written back.
+///
+/// Advances the update sequence number, skipping `0` and `0xFFFF`, saves
+/// the last two bytes of each sector in the update sequence array, and
+/// stamps the new number over them so a torn write is caught on the next
+/// read.
+#[cfg(feature = "write")]
+pub fn protect_fixups(record: &mut [u8], sector_size: usize) -> Result<()> {
+    let (uso, num_fixups) = fixup_layout(record, sector_size)?;
+    let usn = match u16::from_le_bytes([record[uso], record[uso + 1]]).wrapping_add(1) {
+        0 | 0xFFFF => 1,
+        usn => usn,
+    };
+    record[uso..uso + 2].copy_from_slice(&usn.to_le_bytes());
+
+    for i in 0..num_fixups {
+        let sector_end = (i + 1) * sector_size - 2;
+        let saved_offset = uso + 2 + i * 2;
+        record[saved_offset] = record[sector_end];
+        record[saved_offset + 1] = record[sector_end + 1];
+        record[sector_end..sector_end + 2].copy_from_slice(&usn.to_le_bytes());
+    }
+
+    Ok(())
+}
+
+/// Validate a record's update sequence header, returning the array offset
+/// and the number of sectors it protects.
+fn fixup_layout(record: &[u8], sector_size: usize) -> Result<(usize, usize)> {
     if record.len() < 8 || sector_size < 2 {
         return Err(NtfsError::InvalidFixup);
     }
@@ -168,26 +273,7 @@ pub fn apply_fixups(record: &mut [u8], sector_size: usize) -> Result<()> {
     if uso < 8 || array_end > record.len() {
         return Err(NtfsError::InvalidFixup);
     }
-
-    let usn = u16::from_le_bytes([record[uso], record[uso + 1]]);
-
-    for i in 0..num_fixups {
-        let sector_end = (i + 1) * sector_size - 2;
-
-        let on_disk = u16::from_le_bytes([record[sector_end], record[sector_end + 1]]);
-        if on_disk != usn {
-            return Err(NtfsError::FixupMismatch {
-                expected: usn,
-                found: on_disk,
-            });
-        }
-
-        let saved_offset = uso + 2 + i * 2;
-        record[sector_end] = record[saved_offset];
-        record[sector_end + 1] = record[saved_offset + 1];
-    }
-
-    Ok(())
+    Ok((uso, num_fixups))
 }
 
 // ---------------------------------------------------------------------------
@@ -226,15 +312,21 @@ pub enum AttrBody<'a> {
         allocated_size: u64,
         /// Number of initialized bytes; the remaining logical bytes read as zero.
         initialized_size: u64,
+        /// Log2 of the compression-unit size in clusters; zero when the
+        /// attribute is not compressed.
+        compression_unit: u8,
     },
 }
 
 /// Iterator over the attributes inside an MFT record buffer.
 ///
+/// Attributes moved to extension records by an `$ATTRIBUTE_LIST` are not
+/// visited; use [`FileRecord`] to iterate a whole file.
+///
 /// @hadris-spec NTFS:Attribute-Record
 /// @hadris-compliance partial
 /// @hadris-tests compliance::attributes_are_bounded_by_the_file_record_used_size
-/// @hadris-note Resident and non-resident headers are validated; attribute-list extension records are not resolved.
+/// @hadris-note Resident and non-resident headers are validated; iterates one record, with `FileRecord::attrs` spanning attribute-list extension records.
 pub struct AttrIter<'a> {
     data: &'a [u8],
     offset: usize,
@@ -265,6 +357,14 @@ impl<'a> AttrIter<'a> {
         })
     }
 
+    /// Like [`Iterator::next`], also returning the attribute's byte offset
+    /// within the record.
+    #[cfg(all(feature = "write", any(feature = "sync", feature = "async")))]
+    pub(crate) fn next_with_offset(&mut self) -> Option<(usize, Result<NtfsAttr<'a>>)> {
+        let offset = self.offset;
+        self.next().map(|attr| (offset, attr))
+    }
+
     fn invalid(&mut self) -> Option<Result<NtfsAttr<'a>>> {
         self.finished = true;
         Some(Err(NtfsError::InvalidAttribute))
@@ -346,6 +446,7 @@ impl<'a> Iterator for AttrIter<'a> {
             let start_vcn = read_u64_le(self.data, self.offset + 0x10);
             let last_vcn = read_u64_le(self.data, self.offset + 0x18);
             let runs_offset = read_u16_le(self.data, self.offset + 0x20) as usize;
+            let compression_unit = self.data[self.offset + 0x22];
             let allocated_size = read_u64_le(self.data, self.offset + 0x28);
             let data_size = read_u64_le(self.data, self.offset + 0x30);
             let initialized_size = read_u64_le(self.data, self.offset + 0x38);
@@ -364,6 +465,7 @@ impl<'a> Iterator for AttrIter<'a> {
                 data_size,
                 allocated_size,
                 initialized_size,
+                compression_unit,
             }
         };
 
@@ -377,6 +479,257 @@ impl<'a> Iterator for AttrIter<'a> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Attribute lists and extension records
+// ---------------------------------------------------------------------------
+
+/// One entry of an `$ATTRIBUTE_LIST` value.
+///
+/// Each entry names an attribute (or one extent of a non-resident attribute)
+/// and the MFT record that holds it.
+#[derive(Debug, Clone)]
+pub struct AttrListEntry {
+    /// On-disk attribute type code.
+    pub attr_type: u32,
+    /// Raw UTF-16LE name bytes; empty for unnamed attributes.
+    pub name: Vec<u8>,
+    /// First virtual cluster number of the extent (zero when resident).
+    pub start_vcn: u64,
+    /// MFT record number holding the attribute (lower 48 bits of the reference).
+    pub mft_index: u64,
+    /// Sequence number from the file reference.
+    pub mft_seq: u16,
+    /// Attribute instance number within the holding record.
+    pub attr_id: u16,
+}
+
+/// Parse every entry of an `$ATTRIBUTE_LIST` value.
+///
+/// @hadris-spec NTFS:Attribute-List
+/// @hadris-compliance partial
+/// @hadris-tests attribute_list::list_entries_parse_names_and_references
+/// @hadris-note Validates entry lengths and names; attribute instance numbers are reported but not cross-checked against the holding record.
+pub fn parse_attribute_list(data: &[u8]) -> Result<Vec<AttrListEntry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        if offset + 0x1A > data.len() {
+            return Err(NtfsError::InvalidAttributeList);
+        }
+        let length = read_u16_le(data, offset + 4) as usize;
+        let name_length = data[offset + 6] as usize;
+        let name_offset = data[offset + 7] as usize;
+        if length < 0x1A || offset + length > data.len() {
+            return Err(NtfsError::InvalidAttributeList);
+        }
+
+        let name_start = offset + name_offset;
+        let name_end = name_start + name_length * 2;
+        if name_length > 0 && (name_offset < 0x1A || name_end > offset + length) {
+            return Err(NtfsError::InvalidAttributeList);
+        }
+
+        let mft_ref = read_u64_le(data, offset + 0x10);
+        entries.push(AttrListEntry {
+            attr_type: read_u32_le(data, offset),
+            name: if name_length > 0 {
+                data[name_start..name_end].to_vec()
+            } else {
+                Vec::new()
+            },
+            start_vcn: read_u64_le(data, offset + 0x08),
+            mft_index: mft_ref & 0x0000_FFFF_FFFF_FFFF,
+            mft_seq: (mft_ref >> 48) as u16,
+            attr_id: read_u16_le(data, offset + 0x18),
+        });
+
+        offset += length;
+    }
+
+    Ok(entries)
+}
+
+/// A file's base MFT record together with the extension records that its
+/// `$ATTRIBUTE_LIST` names.
+///
+/// Files with many attributes or heavily fragmented streams spill attributes
+/// (and extents of non-resident attributes) into extension records. A
+/// `FileRecord` presents them as one attribute sequence, and
+/// [`FileRecord::data_runs`] joins the extents of a non-resident attribute
+/// into one runlist.
+#[derive(Debug, Clone)]
+pub struct FileRecord {
+    records: Vec<Vec<u8>>,
+}
+
+impl FileRecord {
+    /// Wrap a fixup-applied base record with no extension records.
+    pub fn new(base: Vec<u8>) -> Self {
+        Self {
+            records: alloc::vec![base],
+        }
+    }
+
+    /// Add a fixup-applied extension record.
+    pub fn push_extension(&mut self, record: Vec<u8>) {
+        self.records.push(record);
+    }
+
+    /// The base record buffer.
+    pub fn base(&self) -> &[u8] {
+        &self.records[0]
+    }
+
+    /// Every record buffer, base record first.
+    pub fn records(&self) -> &[Vec<u8>] {
+        &self.records
+    }
+
+    /// Hard-link count from the base record header.
+    ///
+    /// DOS names do not count as links, so a file with a Win32 name and its
+    /// DOS alias reports one.
+    pub fn link_count(&self) -> u16 {
+        self.base()
+            .get(0x12..0x14)
+            .map_or(0, |raw| u16::from_le_bytes([raw[0], raw[1]]))
+    }
+
+    /// Find the base record's `$ATTRIBUTE_LIST`, if any.
+    ///
+    /// Attributes are stored in type order, so the scan stops at the first
+    /// attribute past `$ATTRIBUTE_LIST` instead of walking the whole record.
+    pub fn attribute_list(&self) -> Result<Option<NtfsAttr<'_>>> {
+        for a in AttrIter::new(self.base())? {
+            let a = a?;
+            if a.attr_type == ATTR_ATTRIBUTE_LIST {
+                return Ok(Some(a));
+            }
+            if a.attr_type > ATTR_ATTRIBUTE_LIST {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Iterate the attributes of the base record, then of each extension record.
+    pub fn attrs(&self) -> FileRecordAttrs<'_> {
+        FileRecordAttrs {
+            records: self.records.iter(),
+            current: None,
+            finished: false,
+        }
+    }
+
+    /// Find an attribute by type and name.
+    ///
+    /// For non-resident attributes this returns the first extent (starting
+    /// at VCN 0), which carries the stream sizes.
+    pub fn find(&self, attr_type: u32, name: Option<&[u8]>) -> Result<Option<NtfsAttr<'_>>> {
+        for a in self.attrs() {
+            let a = a?;
+            if a.attr_type != attr_type || a.name != name {
+                continue;
+            }
+            match a.body {
+                AttrBody::NonResident { start_vcn, .. } if start_vcn != 0 => continue,
+                _ => return Ok(Some(a)),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Decode the runlist of a non-resident attribute, joining its extents
+    /// from every record in VCN order.
+    ///
+    /// Extents must cover the attribute without gaps or overlaps.
+    pub fn data_runs(&self, attr_type: u32, name: Option<&[u8]>) -> Result<Vec<DataRun>> {
+        if self.records.len() == 1 {
+            return match self.find(attr_type, name)?.map(|a| a.body) {
+                Some(AttrBody::NonResident { data_runs, .. }) => decode_data_runs(data_runs),
+                Some(AttrBody::Resident(_)) => Err(NtfsError::InvalidAttribute),
+                None => Ok(Vec::new()),
+            };
+        }
+
+        let mut extents = Vec::new();
+        for a in self.attrs() {
+            let a = a?;
+            if a.attr_type != attr_type || a.name != name {
+                continue;
+            }
+            match a.body {
+                AttrBody::NonResident {
+                    start_vcn,
+                    last_vcn,
+                    data_runs,
+                    ..
+                } => extents.push((start_vcn, last_vcn, data_runs)),
+                AttrBody::Resident(_) => return Err(NtfsError::InvalidAttribute),
+            }
+        }
+        extents.sort_unstable_by_key(|&(start_vcn, _, _)| start_vcn);
+
+        if let [(_, _, data_runs)] = extents.as_slice() {
+            return decode_data_runs(data_runs);
+        }
+
+        let mut runs = Vec::new();
+        let mut next_vcn = 0;
+        for (start_vcn, last_vcn, data_runs) in extents {
+            if start_vcn != next_vcn {
+                return Err(NtfsError::InvalidAttributeList);
+            }
+            let extent = decode_data_runs(data_runs)?;
+            let clusters = extent
+                .iter()
+                .try_fold(0_u64, |total, run| total.checked_add(run.length))
+                .ok_or(NtfsError::InvalidDataRun)?;
+            if last_vcn.checked_add(1) != start_vcn.checked_add(clusters) {
+                return Err(NtfsError::InvalidAttributeList);
+            }
+            runs.extend(extent);
+            next_vcn = last_vcn + 1;
+        }
+        Ok(runs)
+    }
+}
+
+/// Iterator over the attributes of every record in a [`FileRecord`].
+pub struct FileRecordAttrs<'a> {
+    records: core::slice::Iter<'a, Vec<u8>>,
+    current: Option<AttrIter<'a>>,
+    finished: bool,
+}
+
+impl<'a> Iterator for FileRecordAttrs<'a> {
+    type Item = Result<NtfsAttr<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            if let Some(attr) = self.current.as_mut().and_then(Iterator::next) {
+                if attr.is_err() {
+                    self.finished = true;
+                }
+                return Some(attr);
+            }
+            let Some(record) = self.records.next() else {
+                self.finished = true;
+                break;
+            };
+            match AttrIter::new(record) {
+                Ok(attrs) => self.current = Some(attrs),
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Data run decoding
 // ---------------------------------------------------------------------------
@@ -498,6 +851,262 @@ pub fn decode_data_runs(data: &[u8]) -> Result<Vec<DataRun>> {
     DataRunDecoder::new(data).collect()
 }
 
+// ---------------------------------------------------------------------------
+// Timestamps, $STANDARD_INFORMATION, and $OBJECT_ID parsing
+// ---------------------------------------------------------------------------
+
+/// The four NTFS timestamps, in 100-nanosecond intervals since
+/// 1601-01-01 UTC (Windows `FILETIME`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileTimes {
+    /// File creation time.
+    pub creation: u64,
+    /// Last data modification time.
+    pub modification: u64,
+    /// Last MFT record modification time.
+    pub mft_modification: u64,
+    /// Last access time.
+    pub access: u64,
+}
+
+impl FileTimes {
+    fn parse(data: &[u8]) -> Self {
+        Self {
+            creation: read_u64_le(data, 0x00),
+            modification: read_u64_le(data, 0x08),
+            mft_modification: read_u64_le(data, 0x10),
+            access: read_u64_le(data, 0x18),
+        }
+    }
+}
+
+/// Parsed `$STANDARD_INFORMATION` attribute value.
+///
+/// Volumes formatted before NTFS 3.0 store only the timestamps and flags;
+/// the ownership, security, and journal fields are `None` there.
+#[derive(Debug, Clone)]
+pub struct StandardInformation {
+    /// Timestamps maintained by the file system.
+    pub times: FileTimes,
+    /// Win32 file attribute flags (`FILE_ATTRIBUTE_*`).
+    pub file_attributes: u32,
+    /// Owner ID used for quota accounting.
+    pub owner_id: Option<u32>,
+    /// Key into the `$Secure` security-descriptor index.
+    pub security_id: Option<u32>,
+    /// Update sequence number of the file's last `$UsnJrnl` record.
+    pub usn: Option<u64>,
+}
+
+/// Parse a `$STANDARD_INFORMATION` attribute value.
+///
+/// @hadris-spec NTFS:Standard-Information
+/// @hadris-compliance partial
+/// @hadris-tests metadata::standard_information_accepts_the_short_v1_layout
+/// @hadris-note Parses timestamps, file attributes, and the NTFS 3.0 owner, security, and USN fields; version, class, and quota fields are not exposed.
+pub fn parse_standard_information(data: &[u8]) -> Result<StandardInformation> {
+    if data.len() < 0x30 {
+        return Err(NtfsError::InvalidAttribute);
+    }
+    let extended = data.len() >= 0x48;
+    Ok(StandardInformation {
+        times: FileTimes::parse(data),
+        file_attributes: read_u32_le(data, 0x20),
+        owner_id: extended.then(|| read_u32_le(data, 0x30)),
+        security_id: extended.then(|| read_u32_le(data, 0x34)),
+        usn: extended.then(|| read_u64_le(data, 0x40)),
+    })
+}
+
+/// Parsed `$OBJECT_ID` attribute value.
+///
+/// Only the object ID itself is mandatory; the birth IDs that link tracking
+/// uses are present when the value is long enough to hold them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectId {
+    /// The file's object GUID, in on-disk byte order.
+    pub object_id: [u8; 16],
+    /// GUID of the volume the file was created on.
+    pub birth_volume_id: Option<[u8; 16]>,
+    /// Object GUID the file was created with.
+    pub birth_object_id: Option<[u8; 16]>,
+    /// GUID of the domain the file was created in.
+    pub domain_id: Option<[u8; 16]>,
+}
+
+/// Parse an `$OBJECT_ID` attribute value.
+///
+/// @hadris-spec NTFS:Object-Id
+/// @hadris-compliance partial
+/// @hadris-tests metadata::object_id_birth_fields_are_optional
+/// @hadris-note Returns the object GUID and whichever birth GUIDs the value holds; the `$ObjId` index is not consulted.
+pub fn parse_object_id(data: &[u8]) -> Result<ObjectId> {
+    let guid = |index: usize| -> Option<[u8; 16]> {
+        data.get(index * 16..(index + 1) * 16)
+            .map(|raw| raw.try_into().unwrap())
+    };
+    Ok(ObjectId {
+        object_id: guid(0).ok_or(NtfsError::InvalidAttribute)?,
+        birth_volume_id: guid(1),
+        birth_object_id: guid(2),
+        domain_id: guid(3),
+    })
+}
+
+/// Parsed `$VOLUME_INFORMATION` attribute value from the `$Volume` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VolumeInformation {
+    /// NTFS major version (3 for Windows 2000 and later).
+    pub major_version: u8,
+    /// NTFS minor version (1 for Windows XP and later).
+    pub minor_version: u8,
+    /// Volume flags such as [`VOLUME_IS_DIRTY`].
+    pub flags: u16,
+}
+
+impl VolumeInformation {
+    /// Whether the volume was left mounted or flagged for `chkdsk`.
+    pub fn is_dirty(&self) -> bool {
+        self.flags & VOLUME_IS_DIRTY != 0
+    }
+}
+
+/// Parse a `$VOLUME_INFORMATION` attribute value.
+///
+/// @hadris-spec NTFS:Volume-Information
+/// @hadris-compliance partial
+/// @hadris-tests volume_health::volume_information_requires_the_flags_field
+/// @hadris-note Decodes the version and flags; only the dirty flag is interpreted, and `$LogFile` restart areas are not examined.
+pub fn parse_volume_information(data: &[u8]) -> Result<VolumeInformation> {
+    if data.len() < 0x0C {
+        return Err(NtfsError::InvalidAttribute);
+    }
+    Ok(VolumeInformation {
+        major_version: data[0x08],
+        minor_version: data[0x09],
+        flags: u16::from_le_bytes([data[0x0A], data[0x0B]]),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// $REPARSE_POINT parsing
+// ---------------------------------------------------------------------------
+
+/// Parsed `$REPARSE_POINT` attribute value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReparsePoint {
+    /// A junction or volume mount point ([`IO_REPARSE_TAG_MOUNT_POINT`]).
+    MountPoint {
+        /// NT path of the target, such as `\??\C:\Users`.
+        substitute_name: String,
+        /// Target path for display.
+        print_name: String,
+    },
+    /// A Win32 symbolic link ([`IO_REPARSE_TAG_SYMLINK`]).
+    Symlink {
+        /// NT path of the target, or a path relative to the link's
+        /// directory if `relative` is set.
+        substitute_name: String,
+        /// Target path for display.
+        print_name: String,
+        /// Whether [`SYMLINK_FLAG_RELATIVE`] is set.
+        relative: bool,
+    },
+    /// A WSL symbolic link ([`IO_REPARSE_TAG_LX_SYMLINK`]).
+    LxSymlink {
+        /// POSIX target path, as stored by WSL.
+        target: String,
+    },
+    /// Any other tag; the data is returned undecoded.
+    Other {
+        /// The reparse tag.
+        tag: u32,
+        /// Tag-specific reparse data, without the header or GUID.
+        data: Vec<u8>,
+    },
+}
+
+impl ReparsePoint {
+    /// The reparse tag this point was stored with.
+    pub fn tag(&self) -> u32 {
+        match self {
+            Self::MountPoint { .. } => IO_REPARSE_TAG_MOUNT_POINT,
+            Self::Symlink { .. } => IO_REPARSE_TAG_SYMLINK,
+            Self::LxSymlink { .. } => IO_REPARSE_TAG_LX_SYMLINK,
+            Self::Other { tag, .. } => *tag,
+        }
+    }
+}
+
+/// Parse a `$REPARSE_POINT` attribute value.
+///
+/// Tags without the Microsoft bit carry a 16-byte GUID before their data,
+/// which is skipped.
+///
+/// @hadris-spec NTFS:Reparse-Point
+/// @hadris-compliance partial
+/// @hadris-tests reparse::reparse_data_is_bounded_by_its_header
+/// @hadris-note Decodes mount-point, symbolic-link, and WSL symbolic-link data; other tags are returned undecoded.
+pub fn parse_reparse_point(data: &[u8]) -> Result<ReparsePoint> {
+    if data.len() < 8 {
+        return Err(NtfsError::InvalidReparsePoint);
+    }
+    let tag = read_u32_le(data, 0);
+    let data_len = read_u16_le(data, 4) as usize;
+    let header_len = if tag & 0x8000_0000 != 0 { 8 } else { 24 };
+    let body = data
+        .get(header_len..header_len + data_len)
+        .ok_or(NtfsError::InvalidReparsePoint)?;
+
+    // Mount points and symbolic links name two UTF-16 strings in a path
+    // buffer that follows their fixed fields.
+    let names = |buffer_start: usize| -> Result<(String, String)> {
+        let buffer = body
+            .get(buffer_start..)
+            .ok_or(NtfsError::InvalidReparsePoint)?;
+        let name = |field: usize| -> Result<String> {
+            let offset = read_u16_le(body, field) as usize;
+            let len = read_u16_le(body, field + 2) as usize;
+            let raw = buffer
+                .get(offset..offset + len)
+                .ok_or(NtfsError::InvalidReparsePoint)?;
+            decode_utf16le(raw).map_err(|_| NtfsError::InvalidReparsePoint)
+        };
+        Ok((name(0)?, name(4)?))
+    };
+
+    match tag {
+        IO_REPARSE_TAG_MOUNT_POINT => {
+            let (substitute_name, print_name) = names(8)?;
+            Ok(ReparsePoint::MountPoint {
+                substitute_name,
+                print_name,
+            })
+        }
+        IO_REPARSE_TAG_SYMLINK => {
+            let (substitute_name, print_name) = names(12)?;
+            Ok(ReparsePoint::Symlink {
+                substitute_name,
+                print_name,
+                relative: read_u32_le(body, 8) & SYMLINK_FLAG_RELATIVE != 0,
+            })
+        }
+        IO_REPARSE_TAG_LX_SYMLINK => {
+            // A version number precedes the UTF-8 target.
+            let target = body.get(4..).ok_or(NtfsError::InvalidReparsePoint)?;
+            let target =
+                core::str::from_utf8(target).map_err(|_| NtfsError::InvalidReparsePoint)?;
+            Ok(ReparsePoint::LxSymlink {
+                target: String::from(target),
+            })
+        }
+        _ => Ok(ReparsePoint::Other {
+            tag,
+            data: body.to_vec(),
+        }),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // $FILE_NAME parsing
 // ---------------------------------------------------------------------------
@@ -507,6 +1116,9 @@ pub fn decode_data_runs(data: &[u8]) -> Result<Vec<DataRun>> {
 pub struct FileNameInfo {
     /// Parent directory MFT reference (lower 48 bits = record number).
     pub parent_ref: u64,
+    /// Timestamps copied into the name when it was last updated; they are
+    /// usually older than those in `$STANDARD_INFORMATION`.
+    pub times: FileTimes,
     /// Decoded UTF-8 filename.
     pub name: String,
     /// File name namespace (POSIX / Win32 / DOS / Win32+DOS).
@@ -517,6 +1129,9 @@ pub struct FileNameInfo {
     pub data_size: u64,
     /// Allocated size as recorded in $FILE_NAME.
     pub allocated_size: u64,
+    /// Reparse tag of the file, if its flags include
+    /// [`FILE_ATTRIBUTE_REPARSE_POINT`].
+    pub reparse_tag: Option<u32>,
 }
 
 /// Parse a `$FILE_NAME` attribute value (the bytes after the attribute header).
@@ -524,16 +1139,20 @@ pub struct FileNameInfo {
 /// @hadris-spec NTFS:File-Name
 /// @hadris-compliance partial
 /// @hadris-tests compliance::filenames_decode_utf16_surrogate_pairs
-/// @hadris-note Parses references, sizes, flags, namespace, and full UTF-16 names; timestamps and reparse/EA data are not exposed.
+/// @hadris-note Parses references, timestamps, sizes, flags, reparse tags, namespace, and full UTF-16 names; extended-attribute sizes are not exposed.
 pub fn parse_file_name(data: &[u8]) -> Result<FileNameInfo> {
     if data.len() < 0x42 {
         return Err(NtfsError::InvalidFileName);
     }
 
     let parent_ref = read_u64_le(data, 0x00);
+    let times = FileTimes::parse(&data[0x08..]);
     let allocated_size = read_u64_le(data, 0x28);
     let data_size = read_u64_le(data, 0x30);
     let flags = read_u32_le(data, 0x38);
+    // The field after the flags holds the reparse tag of a reparse point
+    // and the extended-attribute size of anything else.
+    let reparse_tag = (flags & FILE_ATTRIBUTE_REPARSE_POINT != 0).then(|| read_u32_le(data, 0x3C));
     let name_length = data[0x40] as usize;
     let namespace = data[0x41];
 
@@ -546,11 +1165,13 @@ pub fn parse_file_name(data: &[u8]) -> Result<FileNameInfo> {
 
     Ok(FileNameInfo {
         parent_ref,
+        times,
         name,
         namespace,
         flags,
         data_size,
         allocated_size,
+        reparse_tag,
     })
 }
 
@@ -583,6 +1204,20 @@ pub struct IndexEntryInfo {
     pub mft_seq: u16,
     /// Parsed $FILE_NAME from the entry content.
     pub file_name: FileNameInfo,
+    /// VCN of the child node holding the keys that sort before this entry.
+    pub subnode_vcn: Option<u64>,
+}
+
+/// One node of a filename index B-tree.
+#[derive(Debug, Clone)]
+pub struct IndexNode {
+    /// Whether the node header sets [`INDEX_NODE_HAS_CHILDREN`].
+    pub has_children: bool,
+    /// Entries in collation order, DOS names included.
+    pub entries: Vec<IndexEntryInfo>,
+    /// VCN of the child node holding the keys that sort after every entry,
+    /// taken from the node's end marker.
+    pub end_subnode_vcn: Option<u64>,
 }
 
 /// Parse all non-sentinel index entries from an index node.
@@ -590,12 +1225,23 @@ pub struct IndexEntryInfo {
 /// `data` is the full buffer (either INDEX_ROOT value or INDX record).
 /// `node_header_offset` is the byte offset of the index node header
 /// within `data` (0x10 for INDEX_ROOT values, 0x18 for INDX records).
+/// DOS-only names are skipped so that each file is listed once.
+pub fn parse_index_entries(data: &[u8], node_header_offset: usize) -> Result<Vec<IndexEntryInfo>> {
+    let mut entries = parse_index_node(data, node_header_offset)?.entries;
+    entries.retain(|entry| entry.file_name.namespace != FILE_NAME_DOS);
+    Ok(entries)
+}
+
+/// Parse a filename index node, keeping every entry and the child-node
+/// pointers needed for a keyed descent.
+///
+/// Takes the same arguments as [`parse_index_entries`].
 ///
 /// @hadris-spec NTFS:Index-Entry
 /// @hadris-compliance partial
-/// @hadris-tests read::large_directory_uses_index_allocation
-/// @hadris-note Enumerates filename-index entries but does not expose child-node VCN pointers for keyed B-tree descent.
-pub fn parse_index_entries(data: &[u8], node_header_offset: usize) -> Result<Vec<IndexEntryInfo>> {
+/// @hadris-tests index_lookup::index_nodes_expose_child_pointers
+/// @hadris-note Parses filename-index entries and their child-node VCN pointers; view and security indexes are not decoded.
+pub fn parse_index_node(data: &[u8], node_header_offset: usize) -> Result<IndexNode> {
     let Some(header_end) = node_header_offset.checked_add(16) else {
         return Err(NtfsError::InvalidIndexEntry);
     };
@@ -605,6 +1251,7 @@ pub fn parse_index_entries(data: &[u8], node_header_offset: usize) -> Result<Vec
 
     let entries_offset = read_u32_le(data, node_header_offset) as usize;
     let total_size = read_u32_le(data, node_header_offset + 4) as usize;
+    let has_children = read_u32_le(data, node_header_offset + 12) & INDEX_NODE_HAS_CHILDREN != 0;
 
     let first_entry = node_header_offset
         .checked_add(entries_offset)
@@ -621,7 +1268,7 @@ pub fn parse_index_entries(data: &[u8], node_header_offset: usize) -> Result<Vec
         return Err(NtfsError::InvalidIndexEntry);
     }
 
-    let mut results = Vec::new();
+    let mut entries = Vec::new();
     let mut offset = first_entry;
 
     while offset + 16 <= entries_end {
@@ -633,31 +1280,41 @@ pub fn parse_index_entries(data: &[u8], node_header_offset: usize) -> Result<Vec
             return Err(NtfsError::InvalidIndexEntry);
         }
 
+        // The child VCN occupies the last eight bytes of the entry.
+        let subnode_vcn = if flags & INDEX_ENTRY_SUBNODE != 0 {
+            if entry_length < 24 {
+                return Err(NtfsError::InvalidIndexEntry);
+            }
+            Some(read_u64_le(data, offset + entry_length - 8))
+        } else {
+            None
+        };
+
         if flags & INDEX_ENTRY_LAST != 0 {
-            break;
+            return Ok(IndexNode {
+                has_children,
+                entries,
+                end_subnode_vcn: subnode_vcn,
+            });
         }
 
         if content_length > 0 {
             let content_start = offset + 16;
             let content_end = content_start + content_length;
-            if content_end > offset + entry_length || content_end > data.len() {
+            let key_end = offset + entry_length - if subnode_vcn.is_some() { 8 } else { 0 };
+            if content_end > key_end || content_end > data.len() {
                 return Err(NtfsError::InvalidIndexEntry);
             }
 
             let content = &data[content_start..content_end];
             let file_name = parse_file_name(content)?;
-            // Skip DOS-only names to avoid duplicate entries
-            if file_name.namespace != FILE_NAME_DOS {
-                let mft_ref_raw = read_u64_le(data, offset);
-                let mft_index = mft_ref_raw & 0x0000_FFFF_FFFF_FFFF;
-                let mft_seq = (mft_ref_raw >> 48) as u16;
-
-                results.push(IndexEntryInfo {
-                    mft_index,
-                    mft_seq,
-                    file_name,
-                });
-            }
+            let mft_ref_raw = read_u64_le(data, offset);
+            entries.push(IndexEntryInfo {
+                mft_index: mft_ref_raw & 0x0000_FFFF_FFFF_FFFF,
+                mft_seq: (mft_ref_raw >> 48) as u16,
+                file_name,
+                subnode_vcn,
+            });
         } else {
             return Err(NtfsError::InvalidIndexEntry);
         }
@@ -665,7 +1322,11 @@ pub fn parse_index_entries(data: &[u8], node_header_offset: usize) -> Result<Vec
         offset += entry_length;
     }
 
-    Ok(results)
+    Ok(IndexNode {
+        has_children,
+        entries,
+        end_subnode_vcn: None,
+    })
 }
 
 // ---------------------------------------------------------------------------
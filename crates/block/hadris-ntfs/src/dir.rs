@@ -2,13 +2,17 @@
 
 io_transform! {
 
+use core::cmp::Ordering;
+
+use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
 use crate::attr::{
-    apply_fixups, decode_data_runs, is_i30_name, parse_index_entries, AttrBody, AttrIter,
-    DataRun, IndexEntryInfo, ATTR_BITMAP, ATTR_INDEX_ALLOCATION, ATTR_INDEX_ROOT,
+    apply_fixups, is_i30_name, parse_index_node, AttrBody, DataRun, IndexEntryInfo,
+    ATTR_BITMAP, ATTR_INDEX_ALLOCATION, ATTR_INDEX_ROOT, FILE_NAME_DOS, FILE_NAME_POSIX,
+    I30_NAME,
 };
 use crate::error::{NtfsError, Result};
 use super::fs::NtfsFs;
@@ -32,6 +36,8 @@ pub struct NtfsEntry {
     mft_seq: u16,
     is_directory: bool,
     data_size: u64,
+    file_attributes: u32,
+    reparse_tag: Option<u32>,
     namespace: u8,
 }
 
@@ -57,6 +63,19 @@ impl NtfsEntry {
         self.data_size
     }
 
+    /// Win32 file attribute flags (`FILE_ATTRIBUTE_*`) from the index copy
+    /// of `$FILE_NAME`; see [`NtfsFs::metadata`] for the current values.
+    pub fn file_attributes(&self) -> u32 {
+        self.file_attributes
+    }
+
+    /// Reparse tag (`IO_REPARSE_TAG_*`) if this entry is a reparse point,
+    /// such as a symbolic link or junction; see [`NtfsFs::reparse_point`]
+    /// for its target.
+    pub fn reparse_tag(&self) -> Option<u32> {
+        self.reparse_tag
+    }
+
     /// MFT record number for this entry.
     pub fn mft_index(&self) -> u64 {
         self.mft_index
@@ -85,11 +104,17 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
     /// @hadris-spec NTFS:Directory-Index
     /// @hadris-compliance partial
     /// @hadris-tests read::large_directory_uses_index_allocation
-    /// @hadris-note Honors `$BITMAP`, update sequences, namespaces, and `$UpCase`; attribute-list index extents are not resolved.
+    /// @hadris-note Honors `$BITMAP`, update sequences, namespaces, `$UpCase`, and index extents in attribute-list extension records.
     pub async fn entries(&self) -> Result<Vec<NtfsEntry>> {
+        self.index_entries(false).await
+    }
+
+    /// Every entry of the `$I30` index, DOS-only names included when
+    /// `dos_names` is set.
+    async fn index_entries(&self, dos_names: bool) -> Result<Vec<NtfsEntry>> {
         let record = self
             .fs
-            .read_mft_record_ref(self.mft_index, self.mft_seq)
+            .read_file_record_ref(self.mft_index, self.mft_seq)
             .await?;
 
         let mut entries = Vec::new();
@@ -98,8 +123,7 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
         let mut bitmap_info: Option<(Vec<DataRun>, u64)> = None;
         let mut index_record_size = self.fs.index_record_size;
 
-        let attrs = AttrIter::new(&record)?;
-        for a in attrs {
+        for a in record.attrs() {
             let a = a?;
             match a.attr_type {
                 ATTR_INDEX_ROOT if is_i30_name(a.name) => {
@@ -115,18 +139,20 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
                         }
 
                         // Node header starts at offset 0x10 within the value
-                        let raw = parse_index_entries(value, 0x10)?;
-                        append_entries(&raw, &mut entries);
+                        let raw = parse_index_node(value, 0x10)?.entries;
+                        append_entries(raw, dos_names, &mut entries);
                     }
                 }
                 ATTR_INDEX_ALLOCATION if is_i30_name(a.name) => {
+                    // Later extents are joined by `data_runs`; the first
+                    // one carries the stream size.
                     if let AttrBody::NonResident {
-                        data_runs,
+                        start_vcn: 0,
                         data_size,
                         ..
                     } = a.body
                     {
-                        let runs = decode_data_runs(data_runs)?;
+                        let runs = record.data_runs(ATTR_INDEX_ALLOCATION, Some(I30_NAME))?;
                         alloc_info = Some((runs, data_size));
                     }
                 }
@@ -134,12 +160,14 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
                     match a.body {
                         AttrBody::Resident(value) => bitmap = Some(value.to_vec()),
                         AttrBody::NonResident {
-                            data_runs,
+                            start_vcn: 0,
                             data_size,
                             ..
                         } => {
-                            bitmap_info = Some((decode_data_runs(data_runs)?, data_size));
+                            let runs = record.data_runs(ATTR_BITMAP, Some(I30_NAME))?;
+                            bitmap_info = Some((runs, data_size));
                         }
+                        AttrBody::NonResident { .. } => {}
                     }
                 }
                 _ => {}
@@ -190,29 +218,11 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
                 }
 
                 let offset = (i * index_record_size) as u64;
-                let mut block = vec![0u8; index_record_size];
-
-                {
-                    let mut data = self.fs.data.lock();
-                    read_data_runs(
-                        &mut *data,
-                        &runs,
-                        offset,
-                        &mut block,
-                        self.fs.cluster_size as u64,
-                    )
-                    .await?;
-                }
-
-                if block.len() < 4 || &block[0..4] != b"INDX" {
-                    return Err(NtfsError::InvalidIndexMagic);
-                }
-
-                apply_fixups(&mut block, self.fs.sector_size)?;
+                let block = self.read_index_block(&runs, offset, index_record_size).await?;
 
                 // Node header is at offset 0x18 within the INDX record.
-                let raw = parse_index_entries(&block, 0x18)?;
-                append_entries(&raw, &mut entries);
+                let raw = parse_index_node(&block, 0x18)?.entries;
+                append_entries(raw, dos_names, &mut entries);
             }
         }
 
@@ -220,15 +230,143 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
     }
 
     /// Find an entry by name (case-insensitive for Win32/DOS names).
+    ///
+    /// Descends the `$I30` B-tree from `$INDEX_ROOT`, following child-node
+    /// pointers into `$INDEX_ALLOCATION` and ordering names with the
+    /// volume's `$UpCase` table, so only the index blocks on the path to
+    /// `name` are read. An index whose root has no child nodes is searched
+    /// through every entry. A DOS alias finds the same file as its long name.
     pub async fn find(&self, name: &str) -> Result<Option<NtfsEntry>> {
-        let entries = self.entries().await?;
-        Ok(entries.into_iter().find(|entry| {
-            if entry.namespace == crate::attr::FILE_NAME_POSIX {
-                entry.name() == name
-            } else {
-                self.fs.names_equal(entry.name(), name)
+        let record = self
+            .fs
+            .read_file_record_ref(self.mft_index, self.mft_seq)
+            .await?;
+        let Some(AttrBody::Resident(root)) = record
+            .find(ATTR_INDEX_ROOT, Some(I30_NAME))?
+            .map(|a| a.body)
+        else {
+            return Ok(None);
+        };
+        let root_node = parse_index_node(root, 0x10)?;
+        if !root_node.has_children {
+            let entries = self.index_entries(true).await?;
+            return Ok(entries
+                .into_iter()
+                .find(|entry| self.entry_matches(entry.namespace, entry.name(), name)));
+        }
+
+        let mut index_record_size = self.fs.index_record_size;
+        if root.len() >= 12 {
+            let irs = u32::from_le_bytes([root[8], root[9], root[10], root[11]]) as usize;
+            if irs > 0 {
+                index_record_size = irs;
             }
-        }))
+        }
+        // Child pointers count clusters, or 512-byte blocks when index
+        // records are smaller than a cluster.
+        let vcn_size = if index_record_size >= self.fs.cluster_size {
+            self.fs.cluster_size as u64
+        } else {
+            512
+        };
+        let allocation = match record
+            .find(ATTR_INDEX_ALLOCATION, Some(I30_NAME))?
+            .map(|a| a.body)
+        {
+            Some(AttrBody::NonResident { data_size, .. }) => Some((
+                record.data_runs(ATTR_INDEX_ALLOCATION, Some(I30_NAME))?,
+                data_size,
+            )),
+            _ => None,
+        };
+
+        // The root's record size is untrusted and sizes each block buffer.
+        if index_record_size as u64 > self.fs.data_len().await? {
+            return Err(NtfsError::InvalidAttribute);
+        }
+
+        // A valid tree reaches each child once, so a repeated VCN means the
+        // pointers form a cycle.
+        let mut pending = Vec::new();
+        let mut visited = BTreeSet::new();
+        let mut node = root_node;
+        loop {
+            let mut next = node.end_subnode_vcn;
+            for info in node.entries {
+                match self.fs.collate_names(name, &info.file_name.name) {
+                    Ordering::Greater => {}
+                    Ordering::Less => {
+                        next = info.subnode_vcn;
+                        break;
+                    }
+                    Ordering::Equal => {
+                        let info_name = &info.file_name.name;
+                        if self.entry_matches(info.file_name.namespace, info_name, name) {
+                            return Ok(Some(NtfsEntry::from_index(info)));
+                        }
+                        // POSIX names differing only in case collate equal,
+                        // so the one asked for may sort on either side.
+                        pending.extend(info.subnode_vcn);
+                    }
+                }
+            }
+            pending.extend(next);
+
+            let Some(vcn) = pending.pop() else {
+                return Ok(None);
+            };
+            let (runs, data_size) = allocation.as_ref().ok_or(NtfsError::InvalidIndexEntry)?;
+            let offset = vcn
+                .checked_mul(vcn_size)
+                .filter(|offset| {
+                    offset
+                        .checked_add(index_record_size as u64)
+                        .is_some_and(|end| end <= *data_size)
+                })
+                .ok_or(NtfsError::InvalidIndexEntry)?;
+            if !visited.insert(vcn) {
+                return Err(NtfsError::InvalidIndexEntry);
+            }
+            let block = self.read_index_block(runs, offset, index_record_size).await?;
+            node = parse_index_node(&block, 0x18)?;
+        }
+    }
+
+    /// Whether an index entry answers a lookup for `name`: POSIX names
+    /// compare exactly, Win32 and DOS names through `$UpCase`.
+    fn entry_matches(&self, namespace: u8, entry_name: &str, name: &str) -> bool {
+        if namespace == FILE_NAME_POSIX {
+            entry_name == name
+        } else {
+            self.fs.names_equal(entry_name, name)
+        }
+    }
+
+    /// Read one index record from `$INDEX_ALLOCATION` and apply its fixups.
+    async fn read_index_block(
+        &self,
+        runs: &[DataRun],
+        offset: u64,
+        index_record_size: usize,
+    ) -> Result<Vec<u8>> {
+        let mut block = vec![0u8; index_record_size];
+        {
+            let mut data = self.fs.data.lock();
+            read_data_runs(
+                &mut *data,
+                runs,
+                offset,
+                &mut block,
+                self.fs.cluster_size as u64,
+            )
+            .await?;
+        }
+
+        if block.len() < 4 || &block[0..4] != b"INDX" {
+            return Err(NtfsError::InvalidIndexMagic);
+        }
+        apply_fixups(&mut block, self.fs.sector_size)?;
+        Ok(block)
     }
 
     /// Open a subdirectory by name.
@@ -254,17 +392,27 @@ impl<'a, DATA: Read + Seek> NtfsDir<'a, DATA> {
 /// Directory flag from $FILE_NAME flags field.
 const FILE_ATTR_DIRECTORY: u32 = 0x1000_0000;
 
-fn append_entries(raw: &[IndexEntryInfo], out: &mut Vec<NtfsEntry>) {
-    for info in raw {
-        out.push(NtfsEntry {
-            name: info.file_name.name.clone(),
+impl NtfsEntry {
+    fn from_index(info: IndexEntryInfo) -> Self {
+        NtfsEntry {
             mft_index: info.mft_index,
             mft_seq: info.mft_seq,
             is_directory: info.file_name.flags & FILE_ATTR_DIRECTORY != 0,
             data_size: info.file_name.data_size,
+            file_attributes: info.file_name.flags,
+            reparse_tag: info.file_name.reparse_tag,
             namespace: info.file_name.namespace,
-        });
+            name: info.file_name.name,
+        }
     }
 }
 
+fn append_entries(raw: Vec<IndexEntryInfo>, dos_names: bool, out: &mut Vec<NtfsEntry>) {
+    out.extend(
+        raw.into_iter()
+            .filter(|info| dos_names || info.file_name.namespace != FILE_NAME_DOS)
+            .map(NtfsEntry::from_index),
+    );
+}
+
 } // end io_transform!
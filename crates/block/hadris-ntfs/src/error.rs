@@ -4,6 +4,7 @@ use core::fmt;
 
 /// Errors that can occur when working with NTFS filesystems.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum NtfsError {
     /// Invalid boot sector signature (expected 0xAA55)
     InvalidBootSignature {
@@ -62,6 +63,8 @@ pub enum NtfsError {
     InvalidAttribute,
     /// Malformed non-resident attribute data run
     InvalidDataRun,
+    /// Malformed `$ATTRIBUTE_LIST`, extension record, or attribute extents
+    InvalidAttributeList,
     /// Could not decode a UTF-16LE filename
     InvalidFileName,
     /// The `$UpCase` system file is missing or malformed
@@ -76,12 +79,26 @@ pub enum NtfsError {
     EntryNotFound,
     /// Path is invalid (empty or malformed)
     InvalidPath,
-    /// Compressed data streams are not supported
+    /// Compressed data stream uses an unsupported compression unit
     UnsupportedCompression,
+    /// Malformed LZNT1 compressed data
+    InvalidCompressedData,
     /// Encrypted data streams are not supported
     UnsupportedEncryption,
     /// Data read went past the end of the available data runs
     UnexpectedEndOfData,
+    /// The volume's dirty flag is set and the open policy refuses it
+    DirtyVolume,
+    /// Malformed `$REPARSE_POINT` data
+    InvalidReparsePoint,
+    /// A followed link points outside the volume
+    ReparseTargetOutsideVolume,
+    /// Path resolution followed too many links
+    TooManyReparseLinks,
+    /// A write would need clusters that are not allocated to the stream
+    WriteRequiresAllocation,
+    /// The write targets data this crate cannot modify in place
+    UnsupportedWrite,
     /// I/O error from the underlying storage
     Io(hadris_io::Error),
 }
@@ -131,6 +148,9 @@ impl fmt::Display for NtfsError {
             }
             Self::InvalidAttribute => write!(f, "malformed attribute header or value"),
             Self::InvalidDataRun => write!(f, "malformed non-resident attribute data run"),
+            Self::InvalidAttributeList => {
+                write!(f, "malformed attribute list or extension record")
+            }
             Self::InvalidFileName => write!(f, "could not decode UTF-16LE filename"),
             Self::InvalidUpcaseTable => write!(f, "missing or malformed NTFS $UpCase table"),
             Self::InvalidIndexEntry => write!(f, "malformed index entry"),
@@ -139,12 +159,30 @@ impl fmt::Display for NtfsError {
             Self::EntryNotFound => write!(f, "entry not found in directory"),
             Self::InvalidPath => write!(f, "path is invalid (empty or malformed)"),
             Self::UnsupportedCompression => {
-                write!(f, "compressed data streams are not supported")
+                write!(f, "unsupported compression unit in compressed data stream")
             }
+            Self::InvalidCompressedData => write!(f, "malformed LZNT1 compressed data"),
             Self::UnsupportedEncryption => {
                 write!(f, "encrypted data streams are not supported")
             }
             Self::UnexpectedEndOfData => write!(f, "unexpected end of data runs"),
+            Self::DirtyVolume => {
+                write!(f, "volume was not unmounted cleanly (dirty flag is set)")
+            }
+            Self::InvalidReparsePoint => write!(f, "malformed reparse point data"),
+            Self::ReparseTargetOutsideVolume => {
+                write!(f, "reparse point target is outside the volume")
+            }
+            Self::TooManyReparseLinks => {
+                write!(f, "too many reparse points while resolving a path")
+            }
+            Self::WriteRequiresAllocation => {
+                write!(
+                    f,
+                    "write needs clusters that are not allocated to the stream"
+                )
+            }
+            Self::UnsupportedWrite => write!(f, "data cannot be modified in place"),
             Self::Io(e) => write!(f, "I/O error: {e:?}"),
         }
     }
@@ -2,6 +2,10 @@
 
 io_transform! {
 
+use core::cmp::Ordering;
+
+use alloc::collections::BTreeSet;
+use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 
@@ -10,15 +14,127 @@ use spin::Mutex;
 use hadris_common::types::endian::Endian;
 
 use crate::attr::{
-    self, apply_fixups, decode_data_runs, decode_record_size, AttrBody, AttrIter, DataRun,
-    ATTR_DATA, MFT_RECORD_ROOT_DIR, MFT_RECORD_UPCASE,
+    self, apply_fixups, decode_data_runs, decode_record_size, parse_attribute_list,
+    parse_volume_information, AttrBody, AttrIter, DataRun, FileRecord, ReparsePoint,
+    VolumeInformation, ATTR_ATTRIBUTE_LIST, ATTR_DATA, ATTR_VOLUME_INFORMATION, MFT_RECORD_MFT,
+    MFT_RECORD_ROOT_DIR, MFT_RECORD_UPCASE, MFT_RECORD_VOLUME,
 };
 use crate::error::{NtfsError, Result};
 use crate::raw::RawNtfsBootSector;
-use super::dir::NtfsDir;
-use super::io::{Read, ReadExt, Seek, SeekFrom, read_data_runs};
+use super::dir::{NtfsDir, NtfsEntry};
+use super::io::{Read, ReadExt, Seek, SeekFrom, read_data_runs, read_non_resident_data};
 use super::read::FileReader;
 
+/// Number of leading MFT records duplicated in `$MFTMirr`.
+const MFT_MIRROR_RECORDS: u64 = 4;
+
+/// Most links [`NtfsFs::open_path`] follows for one path, matching the
+/// limit Windows applies to reparse points.
+const MAX_REPARSE_LINKS: usize = 63;
+
+/// How [`NtfsFsBuilder::open`] treats a volume whose dirty flag is set.
+///
+/// Windows sets the flag while a volume is mounted and clears it on a clean
+/// unmount, so a dirty volume may hold metadata that `$LogFile` has not yet
+/// replayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DirtyVolumePolicy {
+    /// Do not read `$Volume` while opening (the behaviour of
+    /// [`NtfsFs::open`]).
+    #[default]
+    Allow,
+    /// Open the volume, recording [`NtfsWarning::DirtyVolume`] if the flag
+    /// is set.
+    Warn,
+    /// Fail with [`NtfsError::DirtyVolume`] if the flag is set.
+    Refuse,
+}
+
+/// A condition the filesystem recovered from or was told to tolerate.
+///
+/// Collected by [`NtfsFs::warnings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NtfsWarning {
+    /// An MFT record was unreadable or corrupt and its copy in `$MFTMirr`
+    /// was used instead.
+    MftMirrorUsed {
+        /// The record that was read from the mirror.
+        index: u64,
+    },
+    /// The volume's dirty flag is set.
+    DirtyVolume,
+}
+
+/// Builder for opening an [`NtfsFs`] with non-default options.
+///
+/// Created via [`NtfsFs::builder`].
+pub struct NtfsFsBuilder<DATA> {
+    data: DATA,
+    dirty_volume: DirtyVolumePolicy,
+    follow_reparse_points: bool,
+    #[cfg(feature = "write")]
+    time_provider: &'static dyn crate::time::TimeProvider,
+}
+
+impl<DATA: Read + Seek> NtfsFsBuilder<DATA> {
+    /// Start a builder with the default options.
+    pub fn new(data: DATA) -> Self {
+        Self {
+            data,
+            dirty_volume: DirtyVolumePolicy::default(),
+            follow_reparse_points: false,
+            #[cfg(feature = "write")]
+            time_provider: &crate::time::DEFAULT_TIME_PROVIDER,
+        }
+    }
+
+    /// Choose how a volume with its dirty flag set is handled.
+    pub fn dirty_volume(mut self, policy: DirtyVolumePolicy) -> Self {
+        self.dirty_volume = policy;
+        self
+    }
+
+    /// Make [`NtfsFs::open_path`] follow symbolic links, junctions, and
+    /// WSL symbolic links that it meets along a path.
+    ///
+    /// Targets are resolved within this volume: an absolute Windows target
+    /// such as `\??\C:\Users` is taken from the volume root whatever its
+    /// drive letter, and so is an absolute WSL target. Off by default, so
+    /// a link is returned as an entry of its own.
+    pub fn follow_reparse_points(mut self, follow: bool) -> Self {
+        self.follow_reparse_points = follow;
+        self
+    }
+
+    /// Override the clock used to stamp `$STANDARD_INFORMATION` when file
+    /// data is written.
+    #[cfg(feature = "write")]
+    pub fn time_provider(mut self, provider: &'static dyn crate::time::TimeProvider) -> Self {
+        self.time_provider = provider;
+        self
+    }
+
+    /// Open the filesystem with the configured options.
+    pub async fn open(self) -> Result<NtfsFs<DATA>> {
+        let mut fs = NtfsFs::open(self.data).await?;
+        fs.follow_reparse_points = self.follow_reparse_points;
+        #[cfg(feature = "write")]
+        {
+            fs.time_provider = self.time_provider;
+        }
+        if self.dirty_volume != DirtyVolumePolicy::Allow
+            && fs.volume_information().await?.is_dirty()
+        {
+            if self.dirty_volume == DirtyVolumePolicy::Refuse {
+                return Err(NtfsError::DirtyVolume);
+            }
+            fs.warn(NtfsWarning::DirtyVolume);
+        }
+        Ok(fs)
+    }
+}
+
 /// Handle for a mounted NTFS filesystem.
 ///
 /// Wraps the underlying data source in a `Mutex` so it can be shared
@@ -33,6 +149,15 @@ pub struct NtfsFs<DATA: Seek> {
     /// Decoded data runs for the `$MFT` data stream.
     pub(crate) mft_runs: Vec<DataRun>,
     pub(crate) upcase: Vec<u16>,
+    /// Byte offset of `$MFTMirr`, if the boot sector places all of its
+    /// records inside the data source.
+    mft_mirror_offset: Option<u64>,
+    warnings: Mutex<Vec<NtfsWarning>>,
+    follow_reparse_points: bool,
+    /// Clock used to stamp modified files. Defaults to
+    /// [`crate::time::DEFAULT_TIME_PROVIDER`].
+    #[cfg(feature = "write")]
+    time_provider: &'static dyn crate::time::TimeProvider,
     volume_serial: u64,
     total_sectors: u64,
 }
@@ -41,12 +166,17 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
     /// Open an NTFS filesystem from a data source.
     ///
     /// Reads the boot sector, locates `$MFT`, and caches its data runs so
-    /// that any MFT record can subsequently be loaded on demand.
+    /// that any MFT record can subsequently be loaded on demand. If one of
+    /// the first four records is unreadable or corrupt, its copy in
+    /// `$MFTMirr` is used and an [`NtfsWarning`] is recorded.
+    ///
+    /// The volume's dirty flag is not checked; use [`NtfsFs::builder`] with
+    /// a [`DirtyVolumePolicy`] to warn about or refuse dirty volumes.
     ///
     /// @hadris-spec NTFS:Master-File-Table
     /// @hadris-compliance partial
-    /// @hadris-tests read::open_blank_volume
-    /// @hadris-note Reads the base `$MFT` extent and validates file references; attribute-list extents and `$MFTMirr` recovery are not supported.
+    /// @hadris-tests volume_health::damaged_mft_record_zero_is_read_from_the_mirror
+    /// @hadris-note Joins `$MFT` extents named by its attribute list, validates file references, and reads records 0-3 from `$MFTMirr` when the primary copy is damaged; the mirror is located by the boot sector alone.
     pub async fn open(mut data: DATA) -> Result<Self> {
         let boot = data.read_struct::<RawNtfsBootSector>().await?;
 
@@ -111,22 +241,50 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
             return Err(NtfsError::InvalidRecordSize);
         }
 
-        // Read MFT record 0 ($MFT itself) directly from the known LCN.
-        data.seek(SeekFrom::Start(mft_byte_offset)).await?;
-        let mut record0 = vec![0u8; mft_record_size];
-        data.read_exact(&mut record0).await?;
+        // The mirror is only usable if all of its records lie inside the
+        // data source.
+        let mft_mirr_lcn = boot.mft_mirr_lcn.get();
+        let mft_mirror_offset = mft_mirr_lcn
+            .checked_mul(cluster_size as u64)
+            .filter(|offset| {
+                mft_mirr_lcn < total_clusters
+                    && offset.saturating_add(MFT_MIRROR_RECORDS * mft_record_size as u64)
+                        <= image_len
+            });
 
-        if record0.len() < 4 || &record0[0..4] != b"FILE" {
-            return Err(NtfsError::InvalidMftMagic);
-        }
-        apply_fixups(&mut record0, sector_size)?;
+        // Read MFT record 0 ($MFT itself) directly from the known LCN,
+        // falling back to its mirror copy.
+        let mut warnings = Vec::new();
+        let record0 = match read_record_at(&mut data, mft_byte_offset, mft_record_size, sector_size)
+            .await
+        {
+            Ok(record) => record,
+            Err(e) => match mft_mirror_offset.filter(|_| is_damaged_record(&e)) {
+                Some(offset) => {
+                    let record = read_record_at(&mut data, offset, mft_record_size, sector_size)
+                        .await
+                        .map_err(|_| e)?;
+                    warnings.push(NtfsWarning::MftMirrorUsed {
+                        index: MFT_RECORD_MFT,
+                    });
+                    record
+                }
+                None => return Err(e),
+            },
+        };
 
-        // Find the unnamed $DATA attribute in record 0.
+        // Find the unnamed $DATA attribute in record 0. On a fragmented
+        // volume this is only the first extent; the rest live in extension
+        // records that the first extent is guaranteed to cover.
         let attrs = AttrIter::new(&record0)?;
         let mut mft_runs: Vec<DataRun> = Vec::new();
+        let mut has_attribute_list = false;
 
         for a in attrs {
             let a = a?;
+            if a.attr_type == ATTR_ATTRIBUTE_LIST {
+                has_attribute_list = true;
+            }
             if a.attr_type == ATTR_DATA && a.name.is_none() {
                 match a.body {
                     AttrBody::NonResident { data_runs, .. } => {
@@ -155,13 +313,27 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
             index_record_size,
             mft_runs,
             upcase: Vec::new(),
+            mft_mirror_offset,
+            warnings: Mutex::new(warnings),
+            follow_reparse_points: false,
+            #[cfg(feature = "write")]
+            time_provider: &crate::time::DEFAULT_TIME_PROVIDER,
             volume_serial: boot.volume_serial.get(),
             total_sectors,
         };
+        if has_attribute_list {
+            let record = fs.read_file_record(MFT_RECORD_MFT).await?;
+            fs.mft_runs = record.data_runs(ATTR_DATA, None)?;
+        }
         fs.load_upcase().await?;
         Ok(fs)
     }
 
+    /// Start an [`NtfsFsBuilder`] for opening with non-default options.
+    pub fn builder(data: DATA) -> NtfsFsBuilder<DATA> {
+        NtfsFsBuilder::new(data)
+    }
+
     async fn load_upcase(&mut self) -> Result<()> {
         const UPCASE_BYTES: u64 = (u16::MAX as u64 + 1) * size_of::<u16>() as u64;
         let bytes = {
@@ -193,6 +365,16 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
                 .map(|unit| self.upcase[unit as usize]))
     }
 
+    /// Order two names as the filename index does: by their `$UpCase`
+    /// code units.
+    pub(crate) fn collate_names(&self, left: &str, right: &str) -> Ordering {
+        left.encode_utf16()
+            .map(|unit| self.upcase[unit as usize])
+            .cmp(right
+                .encode_utf16()
+                .map(|unit| self.upcase[unit as usize]))
+    }
+
     /// Read an MFT record by its record number.
     ///
     /// The returned buffer has had fixups applied and is ready for
@@ -211,7 +393,7 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
             .ok_or(NtfsError::MftRecordOutOfBounds { index })?;
         let mut record = vec![0u8; self.mft_record_size];
 
-        {
+        let read = {
             let mut data = self.data.lock();
             read_data_runs(
                 &mut *data,
@@ -220,13 +402,12 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
                 &mut record,
                 self.cluster_size as u64,
             )
-            .await?;
-        }
-
-        if record.len() < 4 || &record[0..4] != b"FILE" {
-            return Err(NtfsError::InvalidMftMagic);
+            .await
+        };
+        let checked = read.and_then(|()| check_record(&mut record, self.sector_size));
+        if let Err(e) = checked {
+            record = self.read_mirror_record(index, e).await?;
         }
-        apply_fixups(&mut record, self.sector_size)?;
 
         // Verify the record is in use
         if record.len() >= 0x18 {
@@ -247,6 +428,119 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
         Ok(record)
     }
 
+    /// Read record `index` from `$MFTMirr` after its primary copy failed
+    /// with `error`, which is returned if the mirror cannot help.
+    async fn read_mirror_record(&self, index: u64, error: NtfsError) -> Result<Vec<u8>> {
+        let offset = match self.mft_mirror_offset {
+            Some(offset) if index < MFT_MIRROR_RECORDS && is_damaged_record(&error) => {
+                offset + index * self.mft_record_size as u64
+            }
+            _ => return Err(error),
+        };
+        let record = {
+            let mut data = self.data.lock();
+            read_record_at(&mut *data, offset, self.mft_record_size, self.sector_size).await
+        }
+        .map_err(|_| error)?;
+        self.warn(NtfsWarning::MftMirrorUsed { index });
+        Ok(record)
+    }
+
+    fn warn(&self, warning: NtfsWarning) {
+        let mut warnings = self.warnings.lock();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    /// Conditions recorded since the volume was opened, such as MFT records
+    /// read from `$MFTMirr` or a dirty volume opened under
+    /// [`DirtyVolumePolicy::Warn`]. Each warning is reported once.
+    pub fn warnings(&self) -> Vec<NtfsWarning> {
+        self.warnings.lock().clone()
+    }
+
+    /// The clock used to stamp modified files.
+    #[cfg(feature = "write")]
+    pub fn time_provider(&self) -> &'static dyn crate::time::TimeProvider {
+        self.time_provider
+    }
+
+    /// Read the `$VOLUME_INFORMATION` attribute of the `$Volume` file.
+    ///
+    /// Use [`VolumeInformation::is_dirty`] to check whether the volume was
+    /// unmounted cleanly.
+    pub async fn volume_information(&self) -> Result<VolumeInformation> {
+        let record = self.read_file_record(MFT_RECORD_VOLUME).await?;
+        for a in record.attrs() {
+            let a = a?;
+            if a.attr_type != ATTR_VOLUME_INFORMATION {
+                continue;
+            }
+            return match a.body {
+                AttrBody::Resident(value) => parse_volume_information(value),
+                AttrBody::NonResident { .. } => Err(NtfsError::InvalidAttribute),
+            };
+        }
+        Err(NtfsError::AttributeNotFound {
+            attr_type: ATTR_VOLUME_INFORMATION,
+        })
+    }
+
+    /// Read an MFT record together with the extension records named by its
+    /// `$ATTRIBUTE_LIST`.
+    ///
+    /// Records without an attribute list yield a [`FileRecord`] holding only
+    /// the base record.
+    pub async fn read_file_record(&self, index: u64) -> Result<FileRecord> {
+        self.read_file_record_ref(index, 0).await
+    }
+
+    pub(crate) async fn read_file_record_ref(
+        &self,
+        index: u64,
+        expected_sequence: u16,
+    ) -> Result<FileRecord> {
+        let mut record = FileRecord::new(self.read_mft_record_ref(index, expected_sequence).await?);
+        let list = match record.attribute_list()? {
+            None => return Ok(record),
+            Some(a) => match a.body {
+                AttrBody::Resident(value) => value.to_vec(),
+                AttrBody::NonResident {
+                    data_runs,
+                    data_size,
+                    ..
+                } => {
+                    let runs = decode_data_runs(data_runs)?;
+                    let mut data = self.data.lock();
+                    read_non_resident_data(&mut *data, &runs, data_size, self.cluster_size as u64)
+                        .await?
+                }
+            },
+        };
+
+        let mut loaded = BTreeSet::from([index]);
+        for entry in parse_attribute_list(&list)? {
+            if !loaded.insert(entry.mft_index) {
+                continue;
+            }
+            let extension = self
+                .read_mft_record_ref(entry.mft_index, entry.mft_seq)
+                .await?;
+            // An extension record points back at its base record; anything
+            // else is a dangling or cyclic list entry.
+            let base_ref = extension
+                .get(0x20..0x28)
+                .map(|raw| u64::from_le_bytes(raw.try_into().unwrap()) & 0x0000_FFFF_FFFF_FFFF)
+                .ok_or(NtfsError::InvalidAttributeList)?;
+            if base_ref != index {
+                return Err(NtfsError::InvalidAttributeList);
+            }
+            record.push_extension(extension);
+        }
+        Ok(record)
+    }
+
     /// Get a handle to the root directory (MFT record 5).
     pub fn root_dir(&self) -> NtfsDir<'_, DATA> {
         NtfsDir {
@@ -266,6 +560,11 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
         self.total_sectors
     }
 
+    /// Close the filesystem and return the underlying data source.
+    pub fn into_inner(self) -> DATA {
+        self.data.into_inner()
+    }
+
     /// Bytes per cluster.
     pub fn cluster_size(&self) -> usize {
         self.cluster_size
@@ -284,38 +583,178 @@ impl<DATA: Read + Seek> NtfsFs<DATA> {
     /// Open a file or directory by path (e.g., "/dir/subdir/file.txt").
     ///
     /// Paths can use forward or back slashes as separators.
-    /// Leading separators are optional.
-    pub async fn open_path(&self, path: &str) -> Result<super::dir::NtfsEntry> {
-        let path = path.trim_start_matches(['/', '\\']);
-        if path.is_empty() {
+    /// Leading separators are optional. Each component is looked up with
+    /// [`NtfsDir::find`], which descends the directory's index B-tree.
+    ///
+    /// If the filesystem was opened with
+    /// [`NtfsFsBuilder::follow_reparse_points`], links met along the path
+    /// (including the last component) are replaced by their targets. `.`
+    /// and `..` in a link target are resolved lexically, and a path that
+    /// resolves to the root directory yields [`NtfsError::InvalidPath`].
+    pub async fn open_path(&self, path: &str) -> Result<NtfsEntry> {
+        let mut components: Vec<String> = split_path(path).map(String::from).collect();
+        if components.is_empty() {
             return Err(NtfsError::InvalidPath);
         }
 
-        let mut components = path
-            .split(['/', '\\'])
-            .filter(|s| !s.is_empty())
-            .peekable();
+        let mut links = 0;
+        'resolve: loop {
+            let mut dir = self.root_dir();
+            for (i, component) in components.iter().enumerate() {
+                let entry = dir
+                    .find(component)
+                    .await?
+                    .ok_or(NtfsError::EntryNotFound)?;
+
+                if let Some(target) = self.link_target(&entry).await? {
+                    links += 1;
+                    if links > MAX_REPARSE_LINKS {
+                        return Err(NtfsError::TooManyReparseLinks);
+                    }
+                    let mut resolved = match target {
+                        LinkTarget::Absolute(path) => path,
+                        LinkTarget::Relative(path) => {
+                            let mut joined = components[..i].to_vec();
+                            joined.extend(path);
+                            joined
+                        }
+                    };
+                    resolved.extend_from_slice(&components[i + 1..]);
+                    components = normalize_path(resolved);
+                    if components.is_empty() {
+                        return Err(NtfsError::InvalidPath);
+                    }
+                    continue 'resolve;
+                }
 
-        if components.peek().is_none() {
-            return Err(NtfsError::InvalidPath);
+                if i + 1 == components.len() {
+                    return Ok(entry);
+                }
+                if !entry.is_directory() {
+                    return Err(NtfsError::NotADirectory);
+                }
+                dir = NtfsDir {
+                    fs: self,
+                    mft_index: entry.mft_index(),
+                    mft_seq: entry.mft_seq(),
+                };
+            }
+            unreachable!("the last component returns or restarts resolution");
         }
+    }
 
-        let mut current_dir = self.root_dir();
-        let mut last_component = None;
+    /// The in-volume target of `entry` if it is a link that
+    /// [`open_path`](Self::open_path) should follow.
+    async fn link_target(&self, entry: &NtfsEntry) -> Result<Option<LinkTarget>> {
+        if !self.follow_reparse_points || entry.reparse_tag().is_none() {
+            return Ok(None);
+        }
+        let target = match self.reparse_point(entry).await? {
+            Some(ReparsePoint::MountPoint {
+                substitute_name, ..
+            }) => LinkTarget::Absolute(nt_volume_path(&substitute_name)?),
+            Some(ReparsePoint::Symlink {
+                substitute_name,
+                relative: false,
+                ..
+            }) => LinkTarget::Absolute(nt_volume_path(&substitute_name)?),
+            Some(ReparsePoint::Symlink {
+                substitute_name,
+                relative: true,
+                ..
+            }) => LinkTarget::Relative(split_path(&substitute_name).map(String::from).collect()),
+            Some(ReparsePoint::LxSymlink { target }) => {
+                let path = split_path(&target).map(String::from).collect();
+                if target.starts_with('/') {
+                    LinkTarget::Absolute(path)
+                } else {
+                    LinkTarget::Relative(path)
+                }
+            }
+            Some(ReparsePoint::Other { .. }) | None => return Ok(None),
+        };
+        Ok(Some(target))
+    }
+}
+
+/// Where a followed link leads, as path components.
+enum LinkTarget {
+    /// From the volume root.
+    Absolute(Vec<String>),
+    /// From the directory holding the link.
+    Relative(Vec<String>),
+}
 
-        for component in components {
-            if let Some(prev) = last_component.take() {
-                current_dir = current_dir.open_dir(prev).await?;
+/// Split a path on either separator, skipping empty components.
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\']).filter(|s| !s.is_empty())
+}
+
+/// Resolve `.` and `..` components; `..` at the root stays at the root.
+fn normalize_path(components: Vec<String>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(components.len());
+    for component in components {
+        match component.as_str() {
+            "." => {}
+            ".." => {
+                out.pop();
             }
-            last_component = Some(component);
+            _ => out.push(component),
         }
+    }
+    out
+}
 
-        let final_name = last_component.unwrap();
-        current_dir
-            .find(final_name)
-            .await?
-            .ok_or(NtfsError::EntryNotFound)
+/// Translate the NT path of an absolute link target, `\??\X:\...`, into
+/// components from the volume root. Targets on other devices, such as
+/// `\??\UNC\...` or `\??\Volume{...}`, are rejected.
+fn nt_volume_path(substitute_name: &str) -> Result<Vec<String>> {
+    let path = substitute_name
+        .strip_prefix("\\??\\")
+        .unwrap_or(substitute_name);
+    let bytes = path.as_bytes();
+    let has_drive = bytes.len() >= 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && bytes.get(2).is_none_or(|&b| b == b'\\');
+    if !has_drive {
+        return Err(NtfsError::ReparseTargetOutsideVolume);
     }
+    Ok(split_path(&path[2..]).map(String::from).collect())
+}
+
+/// Check a freshly read MFT record's magic and apply its fixups.
+fn check_record(record: &mut [u8], sector_size: usize) -> Result<()> {
+    if record.len() < 4 || &record[0..4] != b"FILE" {
+        return Err(NtfsError::InvalidMftMagic);
+    }
+    apply_fixups(record, sector_size)
+}
+
+/// Read and check the MFT record stored at byte `offset`.
+async fn read_record_at<DATA: Read + Seek>(
+    data: &mut DATA,
+    offset: u64,
+    record_size: usize,
+    sector_size: usize,
+) -> Result<Vec<u8>> {
+    data.seek(SeekFrom::Start(offset)).await?;
+    let mut record = vec![0u8; record_size];
+    data.read_exact(&mut record).await?;
+    check_record(&mut record, sector_size)?;
+    Ok(record)
+}
+
+/// Whether `error` means a record's bytes were unreadable or corrupt, as
+/// opposed to a well-formed record that cannot be used.
+fn is_damaged_record(error: &NtfsError) -> bool {
+    matches!(
+        error,
+        NtfsError::InvalidMftMagic
+            | NtfsError::InvalidFixup
+            | NtfsError::FixupMismatch { .. }
+            | NtfsError::Io(_)
+    )
 }
 
 } // end io_transform!
@@ -2,7 +2,7 @@
 
 io_transform! {
 
-pub use super::super::{Read, ReadExt, Seek, Error, ErrorKind, SeekFrom, Parsable};
+pub use super::super::{Read, ReadExt, Seek, Write, Error, ErrorKind, SeekFrom, Parsable};
 pub use super::super::IoResult;
 
 use alloc::vec;
@@ -78,7 +78,6 @@ pub(crate) async fn read_data_runs<DATA: Read + Seek>(
 }
 
 /// Read an entire non-resident attribute's data using its data runs.
-#[allow(dead_code)]
 pub(crate) async fn read_non_resident_data<DATA: Read + Seek>(
     data: &mut DATA,
     runs: &[DataRun],
@@ -96,4 +95,66 @@ pub(crate) async fn read_non_resident_data<DATA: Read + Seek>(
     Ok(buf)
 }
 
+/// Write a contiguous byte range through a series of data runs.
+///
+/// The inverse of [`read_data_runs`]. Sparse runs have no clusters to write
+/// to, so reaching one fails with [`NtfsError::WriteRequiresAllocation`];
+/// callers check the whole range first so nothing is written in that case.
+#[cfg(feature = "write")]
+pub(crate) async fn write_data_runs<DATA: Write + Seek>(
+    data: &mut DATA,
+    runs: &[DataRun],
+    offset: u64,
+    buf: &[u8],
+    cluster_size: u64,
+) -> Result<()> {
+    if cluster_size == 0 {
+        return Err(NtfsError::InvalidDataRun);
+    }
+    let end = offset
+        .checked_add(buf.len() as u64)
+        .ok_or(NtfsError::InvalidDataRun)?;
+    let mut run_start: u64 = 0;
+    let mut written: usize = 0;
+
+    for run in runs {
+        let run_bytes = run
+            .length
+            .checked_mul(cluster_size)
+            .ok_or(NtfsError::InvalidDataRun)?;
+        let run_end = run_start
+            .checked_add(run_bytes)
+            .ok_or(NtfsError::InvalidDataRun)?;
+
+        if run_end <= offset {
+            run_start = run_end;
+            continue;
+        }
+        if run_start >= end {
+            break;
+        }
+        if run.lcn < 0 {
+            return Err(NtfsError::WriteRequiresAllocation);
+        }
+
+        let write_start = offset.max(run_start);
+        let write_end = end.min(run_end);
+        let bytes_to_write = (write_end - write_start) as usize;
+        let disk_pos = (run.lcn as u64)
+            .checked_mul(cluster_size)
+            .and_then(|start| start.checked_add(write_start - run_start))
+            .ok_or(NtfsError::InvalidDataRun)?;
+        data.seek(SeekFrom::Start(disk_pos)).await?;
+        data.write_all(&buf[written..written + bytes_to_write]).await?;
+
+        written += bytes_to_write;
+        run_start = run_end;
+    }
+
+    if written < buf.len() {
+        return Err(NtfsError::UnexpectedEndOfData);
+    }
+    Ok(())
+}
+
 } // end io_transform!
@@ -1,6 +1,7 @@
 //! # hadris-ntfs
 //!
-//! A `no_std`-compatible library for reading NTFS filesystems (read-only).
+//! A `no_std`-compatible library for reading NTFS filesystems, with optional
+//! in-place overwriting of existing file data.
 //!
 //! ## Quick Start
 //!
@@ -26,6 +27,7 @@
 //! | `sync`   | Yes     | Synchronous API via `hadris-io` sync traits |
 //! | `async`  | No      | Asynchronous API via `hadris-io` async traits |
 //! | `read`   | Yes     | Read operations (requires `alloc`) |
+//! | `write`  | No      | In-place overwrite of existing file data (requires `read`) |
 //!
 //! ## Dual Sync/Async Architecture
 //!
@@ -44,13 +46,21 @@
 //!
 //! The reader supports validated boot geometry, update-sequence-protected
 //! MFT/index records, resident and non-resident unnamed data, sparse runs,
-//! initialized-size zero filling, directory index allocation/bitmaps, NTFS
-//! filename namespaces, and `$UpCase` collation.
+//! initialized-size zero filling, directory index allocation/bitmaps, keyed
+//! `$I30` B-tree lookup, NTFS filename namespaces, `$UpCase` collation,
+//! `$ATTRIBUTE_LIST` extension
+//! records (including a fragmented `$MFT`), LZNT1-compressed streams, named
+//! alternate data streams, file metadata (timestamps, attributes, hard
+//! links, and object IDs), recovery of the first MFT records from
+//! `$MFTMirr`, dirty-volume detection, and symbolic links and junctions.
 //!
-//! It does not yet resolve `$ATTRIBUTE_LIST` extension records, recover from
-//! `$MFTMirr`, decode compressed or encrypted streams, expose named alternate
-//! data streams, or interpret reparse points. See the internal compliance
-//! matrix in `docs/spec-coverage.md`.
+//! With the `write` feature, `NtfsFsWriteExt::write_at` overwrites
+//! bytes inside the clusters already allocated to a file's unnamed non-resident
+//! `$DATA` stream, updating the stream sizes and `$STANDARD_INFORMATION`
+//! times. Nothing that needs cluster or MFT allocation is written.
+//!
+//! It does not yet replay `$LogFile` or decode encrypted streams. See the internal compliance matrix in
+//! `docs/spec-coverage.md`.
 
 #![no_std]
 #![allow(async_fn_in_trait)]
@@ -70,7 +80,10 @@ extern crate alloc;
 #[cfg(feature = "read")]
 pub mod attr;
 pub mod error;
+pub mod lznt1;
 pub mod raw;
+#[cfg(feature = "write")]
+pub mod time;
 
 // ---------------------------------------------------------------------------
 // Sync module
@@ -81,10 +94,10 @@ pub mod raw;
 pub mod sync {
     //! Synchronous NTFS filesystem API.
     //!
-    //! All I/O operations use synchronous `Read`/`Seek` traits.
+    //! All I/O operations use synchronous `Read`/`Write`/`Seek` traits.
 
     pub use hadris_io::Result as IoResult;
-    pub use hadris_io::sync::{Parsable, Read, ReadExt, Seek};
+    pub use hadris_io::sync::{Parsable, Read, ReadExt, Seek, Write};
     pub use hadris_io::{Error, ErrorKind, SeekFrom};
 
     macro_rules! io_transform {
@@ -106,13 +119,19 @@ pub mod sync {
         pub mod dir;
         pub mod fs;
         pub mod io;
+        pub mod meta;
         pub mod read;
+        #[cfg(feature = "write")]
+        pub mod write;
     }
     pub use __inner::*;
 
     pub use __inner::dir::{NtfsDir, NtfsEntry};
-    pub use __inner::fs::NtfsFs;
+    pub use __inner::fs::{DirtyVolumePolicy, NtfsFs, NtfsFsBuilder, NtfsWarning};
+    pub use __inner::meta::{NtfsMetadata, NtfsStream};
     pub use __inner::read::{FileReader, NtfsFsReadExt};
+    #[cfg(feature = "write")]
+    pub use __inner::write::NtfsFsWriteExt;
 }
 
 // ---------------------------------------------------------------------------
@@ -124,10 +143,10 @@ pub mod sync {
 pub mod r#async {
     //! Asynchronous NTFS filesystem API.
     //!
-    //! All I/O operations use async `Read`/`Seek` traits.
+    //! All I/O operations use async `Read`/`Write`/`Seek` traits.
 
     pub use hadris_io::Result as IoResult;
-    pub use hadris_io::r#async::{Parsable, Read, ReadExt, Seek};
+    pub use hadris_io::r#async::{Parsable, Read, ReadExt, Seek, Write};
     pub use hadris_io::{Error, ErrorKind, SeekFrom};
 
     macro_rules! io_transform {
@@ -149,9 +168,19 @@ pub mod r#async {
         pub mod dir;
         pub mod fs;
         pub mod io;
+        pub mod meta;
         pub mod read;
+        #[cfg(feature = "write")]
+        pub mod write;
     }
     pub use __inner::*;
+
+    pub use __inner::dir::{NtfsDir, NtfsEntry};
+    pub use __inner::fs::{DirtyVolumePolicy, NtfsFs, NtfsFsBuilder, NtfsWarning};
+    pub use __inner::meta::{NtfsMetadata, NtfsStream};
+    pub use __inner::read::{FileReader, NtfsFsReadExt};
+    #[cfg(feature = "write")]
+    pub use __inner::write::NtfsFsWriteExt;
 }
 
 // ---------------------------------------------------------------------------
@@ -0,0 +1,124 @@
+//! LZNT1 decompression for NTFS compressed data streams.
+//!
+//! A compressed stream is split into compression units (usually sixteen
+//! clusters). Each unit is stored as a sequence of LZNT1 chunks, and every
+//! chunk expands to at most [`CHUNK_SIZE`] bytes of output. Like
+//! [`attr`](crate::attr), this module performs no I/O.
+
+use crate::error::{NtfsError, Result};
+
+/// Uncompressed size of one LZNT1 chunk.
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Chunk-header flag: the chunk data is compressed.
+const CHUNK_COMPRESSED: u16 = 0x8000;
+/// Chunk-header signature bits (always `3`).
+const CHUNK_SIGNATURE_MASK: u16 = 0x7000;
+const CHUNK_SIGNATURE: u16 = 0x3000;
+
+/// Decompress an LZNT1 compression unit into `output`.
+///
+/// Decoding stops at a zero chunk header, at the end of `input`, or once
+/// `output` is full. A chunk that expands to less than [`CHUNK_SIZE`] bytes
+/// is followed by zeros up to the next chunk boundary, and any output past
+/// the final chunk is zero-filled, so `output` is always fully written.
+/// Returns the number of bytes produced by the chunks themselves.
+///
+/// @hadris-spec NTFS:LZNT1
+/// @hadris-compliance partial
+/// @hadris-tests compressed::lznt1_backreferences_widen_offsets_as_the_chunk_fills
+/// @hadris-note Decodes compressed and stored chunks with position-dependent back-reference splits; only the LZNT1 format used by NTFS compression units is supported.
+pub fn decompress(input: &[u8], output: &mut [u8]) -> Result<usize> {
+    let mut in_pos = 0;
+    let mut out_pos = 0;
+    let mut produced = 0;
+
+    while out_pos < output.len() && in_pos + 2 <= input.len() {
+        let header = u16::from_le_bytes([input[in_pos], input[in_pos + 1]]);
+        if header == 0 {
+            break;
+        }
+        if header & CHUNK_SIGNATURE_MASK != CHUNK_SIGNATURE {
+            return Err(NtfsError::InvalidCompressedData);
+        }
+        let data_len = (header & 0x0FFF) as usize + 1;
+        let data_start = in_pos + 2;
+        let data = input
+            .get(data_start..data_start + data_len)
+            .ok_or(NtfsError::InvalidCompressedData)?;
+        in_pos = data_start + data_len;
+
+        let chunk_end = (out_pos + CHUNK_SIZE).min(output.len());
+        let chunk = &mut output[out_pos..chunk_end];
+        let written = if header & CHUNK_COMPRESSED != 0 {
+            decompress_chunk(data, chunk)?
+        } else {
+            if data.len() > chunk.len() {
+                return Err(NtfsError::InvalidCompressedData);
+            }
+            chunk[..data.len()].copy_from_slice(data);
+            data.len()
+        };
+        chunk[written..].fill(0);
+        produced = out_pos + written;
+        out_pos = chunk_end;
+    }
+
+    output[out_pos..].fill(0);
+    Ok(produced)
+}
+
+/// Decode one compressed chunk into `out`, returning the bytes written.
+fn decompress_chunk(data: &[u8], out: &mut [u8]) -> Result<usize> {
+    let mut in_pos = 0;
+    let mut out_pos = 0;
+
+    while in_pos < data.len() {
+        let tags = data[in_pos];
+        in_pos += 1;
+        for bit in 0..8 {
+            if in_pos >= data.len() {
+                break;
+            }
+            if tags & (1 << bit) == 0 {
+                let byte = out
+                    .get_mut(out_pos)
+                    .ok_or(NtfsError::InvalidCompressedData)?;
+                *byte = data[in_pos];
+                in_pos += 1;
+                out_pos += 1;
+                continue;
+            }
+
+            let token = data
+                .get(in_pos..in_pos + 2)
+                .map(|raw| u16::from_le_bytes([raw[0], raw[1]]))
+                .ok_or(NtfsError::InvalidCompressedData)?;
+            in_pos += 2;
+
+            // The offset field widens as the chunk fills: it takes as many
+            // bits as are needed to reach back to the chunk start, at
+            // least four, and the length takes the rest.
+            let mut offset_bits = 4;
+            let mut reach = out_pos.saturating_sub(1) >> 4;
+            while reach > 0 {
+                offset_bits += 1;
+                reach >>= 1;
+            }
+            let length_bits = 16 - offset_bits;
+            let offset = (token >> length_bits) as usize + 1;
+            let length = (token & ((1 << length_bits) - 1)) as usize + 3;
+
+            if offset > out_pos || out_pos + length > out.len() {
+                return Err(NtfsError::InvalidCompressedData);
+            }
+            // Byte-by-byte: the source may overlap the destination.
+            for i in out_pos..out_pos + length {
+                out[i] = out[i - offset];
+            }
+            out_pos += length;
+        }
+    }
+
+    Ok(out_pos)
+}
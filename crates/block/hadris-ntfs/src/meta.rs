@@ -0,0 +1,236 @@
+//! File metadata for NTFS — timestamps, hard links, object IDs, streams,
+//! and reparse points.
+
+io_transform! {
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::attr::{
+    decode_utf16le, parse_file_name, parse_object_id, parse_reparse_point,
+    parse_standard_information, AttrBody, FileNameInfo, FileTimes, ObjectId, ReparsePoint,
+    StandardInformation, ATTR_DATA, ATTR_FILE_NAME, ATTR_FLAG_COMPRESSED, ATTR_FLAG_ENCRYPTED,
+    ATTR_FLAG_SPARSE, ATTR_OBJECT_ID, ATTR_REPARSE_POINT, ATTR_STANDARD_INFORMATION,
+    FILE_NAME_DOS,
+};
+use crate::error::{NtfsError, Result};
+use super::dir::NtfsEntry;
+use super::fs::NtfsFs;
+use super::io::{Read, Seek, read_non_resident_data};
+
+/// Largest reparse data Windows will store (`MAXIMUM_REPARSE_DATA_BUFFER_SIZE`).
+const MAX_REPARSE_DATA: u64 = 16 * 1024;
+
+/// Metadata of a file or directory, read from its MFT record.
+///
+/// Created via [`NtfsFs::metadata`].
+#[derive(Debug, Clone)]
+pub struct NtfsMetadata {
+    mft_index: u64,
+    link_count: u16,
+    standard_information: Option<StandardInformation>,
+    names: Vec<FileNameInfo>,
+    object_id: Option<ObjectId>,
+    streams: Vec<NtfsStream>,
+}
+
+impl NtfsMetadata {
+    /// MFT record number the metadata was read from.
+    pub fn mft_index(&self) -> u64 {
+        self.mft_index
+    }
+
+    /// The `$STANDARD_INFORMATION` attribute, if the record has one.
+    pub fn standard_information(&self) -> Option<&StandardInformation> {
+        self.standard_information.as_ref()
+    }
+
+    /// Timestamps from `$STANDARD_INFORMATION`.
+    pub fn times(&self) -> Option<FileTimes> {
+        self.standard_information.as_ref().map(|info| info.times)
+    }
+
+    /// Win32 file attribute flags (`FILE_ATTRIBUTE_*`), or zero when the
+    /// record has no `$STANDARD_INFORMATION`.
+    pub fn file_attributes(&self) -> u32 {
+        self.standard_information
+            .as_ref()
+            .map_or(0, |info| info.file_attributes)
+    }
+
+    /// Hard-link count from the MFT record header.
+    pub fn link_count(&self) -> u16 {
+        self.link_count
+    }
+
+    /// Every `$FILE_NAME` attribute, including DOS 8.3 aliases.
+    pub fn names(&self) -> &[FileNameInfo] {
+        &self.names
+    }
+
+    /// The names that are hard links, i.e. every name except DOS-only
+    /// aliases. Each carries the reference of the directory holding it.
+    pub fn hard_links(&self) -> impl Iterator<Item = &FileNameInfo> {
+        self.names
+            .iter()
+            .filter(|name| name.namespace != FILE_NAME_DOS)
+    }
+
+    /// The `$OBJECT_ID` attribute, if the file has one.
+    pub fn object_id(&self) -> Option<&ObjectId> {
+        self.object_id.as_ref()
+    }
+
+    /// The `$DATA` streams, unnamed stream first when present.
+    pub fn streams(&self) -> &[NtfsStream] {
+        &self.streams
+    }
+}
+
+/// A `$DATA` stream of a file or directory.
+///
+/// Open one with [`NtfsFsReadExt::read_stream`](super::read::NtfsFsReadExt::read_stream).
+#[derive(Debug, Clone)]
+pub struct NtfsStream {
+    name: String,
+    size: u64,
+    allocated_size: u64,
+    flags: u16,
+    resident: bool,
+}
+
+impl NtfsStream {
+    /// The stream name; empty for the unnamed (default) stream.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether this is the unnamed (default) stream.
+    pub fn is_unnamed(&self) -> bool {
+        self.name.is_empty()
+    }
+
+    /// Stream size in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Bytes allocated on disk; equal to the size for resident streams.
+    pub fn allocated_size(&self) -> u64 {
+        self.allocated_size
+    }
+
+    /// Whether the stream is stored inside the MFT record.
+    pub fn is_resident(&self) -> bool {
+        self.resident
+    }
+
+    /// Whether the stream is compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.flags & ATTR_FLAG_COMPRESSED != 0
+    }
+
+    /// Whether the stream is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.flags & ATTR_FLAG_ENCRYPTED != 0
+    }
+
+    /// Whether the stream is sparse.
+    pub fn is_sparse(&self) -> bool {
+        self.flags & ATTR_FLAG_SPARSE != 0
+    }
+}
+
+impl<DATA: Read + Seek> NtfsFs<DATA> {
+    /// Read the metadata of a file or directory.
+    ///
+    /// Loads the entry's MFT record (and any extension records) and parses
+    /// its `$STANDARD_INFORMATION`, `$FILE_NAME`, `$OBJECT_ID`, and `$DATA`
+    /// attributes. Unlike the [`NtfsEntry`] fields, which come from the
+    /// parent directory's index, these are current.
+    pub async fn metadata(&self, entry: &NtfsEntry) -> Result<NtfsMetadata> {
+        let record = self
+            .read_file_record_ref(entry.mft_index(), entry.mft_seq())
+            .await?;
+
+        let mut metadata = NtfsMetadata {
+            mft_index: entry.mft_index(),
+            link_count: record.link_count(),
+            standard_information: None,
+            names: Vec::new(),
+            object_id: None,
+            streams: Vec::new(),
+        };
+        for a in record.attrs() {
+            let a = a?;
+            match (a.attr_type, a.body) {
+                (ATTR_STANDARD_INFORMATION, AttrBody::Resident(value)) => {
+                    metadata.standard_information = Some(parse_standard_information(value)?);
+                }
+                (ATTR_FILE_NAME, AttrBody::Resident(value)) => {
+                    metadata.names.push(parse_file_name(value)?);
+                }
+                (ATTR_OBJECT_ID, AttrBody::Resident(value)) => {
+                    metadata.object_id = Some(parse_object_id(value)?);
+                }
+                (ATTR_DATA, body) => {
+                    let (size, allocated_size, resident) = match body {
+                        AttrBody::Resident(value) => {
+                            (value.len() as u64, value.len() as u64, true)
+                        }
+                        // Later extents of a stream repeat no sizes.
+                        AttrBody::NonResident {
+                            start_vcn: 0,
+                            data_size,
+                            allocated_size,
+                            ..
+                        } => (data_size, allocated_size, false),
+                        AttrBody::NonResident { .. } => continue,
+                    };
+                    let name = match a.name {
+                        Some(raw) => decode_utf16le(raw)?,
+                        None => String::new(),
+                    };
+                    metadata.streams.push(NtfsStream {
+                        name,
+                        size,
+                        allocated_size,
+                        flags: a.flags,
+                        resident,
+                    });
+                }
+                _ => {}
+            }
+        }
+        metadata.streams.sort_by_key(|stream| !stream.is_unnamed());
+        Ok(metadata)
+    }
+
+    /// Read and decode the `$REPARSE_POINT` attribute of a file or
+    /// directory.
+    ///
+    /// Returns `Ok(None)` if the entry is not a reparse point. Check
+    /// [`NtfsEntry::reparse_tag`] first to avoid loading the MFT record of
+    /// ordinary entries.
+    pub async fn reparse_point(&self, entry: &NtfsEntry) -> Result<Option<ReparsePoint>> {
+        let record = self
+            .read_file_record_ref(entry.mft_index(), entry.mft_seq())
+            .await?;
+        let value = match record.find(ATTR_REPARSE_POINT, None)?.map(|a| a.body) {
+            None => return Ok(None),
+            Some(AttrBody::Resident(value)) => value.to_vec(),
+            Some(AttrBody::NonResident { data_size, .. }) => {
+                if data_size > MAX_REPARSE_DATA {
+                    return Err(NtfsError::InvalidReparsePoint);
+                }
+                let runs = record.data_runs(ATTR_REPARSE_POINT, None)?;
+                let mut data = self.data.lock();
+                read_non_resident_data(&mut *data, &runs, data_size, self.cluster_size as u64)
+                    .await?
+            }
+        };
+        parse_reparse_point(&value).map(Some)
+    }
+}
+
+} // end io_transform!
@@ -5,31 +5,137 @@ io_transform! {
 use alloc::vec;
 use alloc::vec::Vec;
 
-use crate::attr::{decode_data_runs, AttrBody, AttrIter, DataRun, ATTR_DATA, ATTR_FLAG_COMPRESSED, ATTR_FLAG_ENCRYPTED};
+use crate::attr::{
+    decode_utf16le, AttrBody, DataRun, ATTR_DATA, ATTR_FLAG_COMPRESSED, ATTR_FLAG_ENCRYPTED,
+};
 use crate::error::{NtfsError, Result};
+use crate::lznt1;
 use super::dir::NtfsEntry;
 use super::fs::NtfsFs;
-use super::io::{Read, Seek, read_data_runs};
+use super::io::{Error, ErrorKind, Read, Seek, SeekFrom, read_data_runs};
 
 /// Backing storage for a file's data — either inline in the MFT record
-/// (resident) or spread across clusters (non-resident).
+/// (resident), spread across clusters (non-resident), or stored as
+/// LZNT1-compressed units.
 enum FileData {
     Resident(Vec<u8>),
     NonResident {
         runs: Vec<DataRun>,
         initialized_size: u64,
     },
+    Compressed(CompressedStream),
+}
+
+/// A compressed non-resident stream, decoded one compression unit at a time.
+///
+/// Within each unit the runs say how it is stored: no clusters on disk is a
+/// sparse unit of zeros, every cluster on disk is stored uncompressed, and
+/// anything in between holds LZNT1 chunks followed by sparse padding.
+struct CompressedStream {
+    runs: Vec<DataRun>,
+    initialized_size: u64,
+    unit_clusters: u64,
+    /// Index of the unit currently decoded into `buffer`.
+    unit: Option<u64>,
+    buffer: Vec<u8>,
+}
+
+impl CompressedStream {
+    async fn new<DATA: Read + Seek>(
+        fs: &NtfsFs<DATA>,
+        runs: Vec<DataRun>,
+        initialized_size: u64,
+        compression_unit: u8,
+    ) -> Result<Self> {
+        if compression_unit == 0 {
+            return Err(NtfsError::UnsupportedCompression);
+        }
+        let unit_clusters = 1_u64
+            .checked_shl(compression_unit as u32)
+            .ok_or(NtfsError::UnsupportedCompression)?;
+        let unit_size = unit_clusters
+            .checked_mul(fs.cluster_size as u64)
+            .ok_or(NtfsError::UnsupportedCompression)?;
+        // The unit size comes from an untrusted header byte and sizes the
+        // decode buffer, so bound it against the data source first.
+        if unit_size > fs.data_len().await? {
+            return Err(NtfsError::UnsupportedCompression);
+        }
+        Ok(Self {
+            runs,
+            initialized_size,
+            unit_clusters,
+            unit: None,
+            buffer: vec![0; unit_size as usize],
+        })
+    }
+
+    /// Decode compression unit `unit` into `buffer`, unless it already is.
+    async fn load_unit<DATA: Read + Seek>(&mut self, fs: &NtfsFs<DATA>, unit: u64) -> Result<()> {
+        if self.unit == Some(unit) {
+            return Ok(());
+        }
+        self.unit = None;
+
+        let cluster_size = fs.cluster_size as u64;
+        let first_vcn = unit
+            .checked_mul(self.unit_clusters)
+            .ok_or(NtfsError::InvalidDataRun)?;
+        let stored = stored_clusters(&self.runs, first_vcn, self.unit_clusters);
+        let offset = first_vcn * cluster_size;
+
+        if stored == 0 {
+            self.buffer.fill(0);
+        } else if stored == self.unit_clusters {
+            let mut data = fs.data.lock();
+            read_data_runs(&mut *data, &self.runs, offset, &mut self.buffer, cluster_size)
+                .await?;
+        } else {
+            let mut compressed = vec![0_u8; (stored * cluster_size) as usize];
+            {
+                let mut data = fs.data.lock();
+                read_data_runs(&mut *data, &self.runs, offset, &mut compressed, cluster_size)
+                    .await?;
+            }
+            lznt1::decompress(&compressed, &mut self.buffer)?;
+        }
+
+        self.unit = Some(unit);
+        Ok(())
+    }
+}
+
+/// Count the clusters in `first_vcn..first_vcn + count` that are stored on
+/// disk rather than sparse.
+fn stored_clusters(runs: &[DataRun], first_vcn: u64, count: u64) -> u64 {
+    let end = first_vcn.saturating_add(count);
+    let mut vcn = 0_u64;
+    let mut stored = 0;
+    for run in runs {
+        let run_end = vcn.saturating_add(run.length);
+        if run.lcn >= 0 {
+            let start = vcn.max(first_vcn);
+            let stop = run_end.min(end);
+            stored += stop.saturating_sub(start);
+        }
+        if run_end >= end {
+            break;
+        }
+        vcn = run_end;
+    }
+    stored
 }
 
 /// A reader for file content on an NTFS volume.
 ///
-/// Created via [`NtfsFsReadExt::read_file`] or
+/// Created via [`NtfsFsReadExt::read_file`],
+/// [`NtfsFsReadExt::read_stream`], or
 /// [`NtfsDir::open_file`](super::dir::NtfsDir::open_file).
 ///
 /// @hadris-spec NTFS:Data-Stream
 /// @hadris-compliance partial
 /// @hadris-tests read::read_large_nonresident_file
-/// @hadris-note Reads resident, non-resident, sparse, and uninitialized unnamed data; compressed, encrypted, named, and attribute-list streams are unsupported.
+/// @hadris-note Reads resident, non-resident, sparse, uninitialized, and LZNT1-compressed unnamed and named data, joining extents across attribute-list extension records; encrypted streams are unsupported.
 pub struct FileReader<'a, DATA: Read + Seek> {
     fs: &'a NtfsFs<DATA>,
     data: FileData,
@@ -40,44 +146,68 @@ pub struct FileReader<'a, DATA: Read + Seek> {
 impl<'a, DATA: Read + Seek> FileReader<'a, DATA> {
     /// Open a file for reading from an [`NtfsEntry`].
     ///
-    /// Loads the entry's MFT record and locates the unnamed `$DATA`
-    /// attribute.
+    /// Loads the entry's MFT record (and any extension records) and locates
+    /// the unnamed `$DATA` attribute.
     pub(crate) async fn open(fs: &'a NtfsFs<DATA>, entry: &NtfsEntry) -> Result<Self> {
         if entry.is_directory() {
             return Err(NtfsError::NotAFile);
         }
-        Self::open_by_mft_ref(fs, entry.mft_index(), entry.mft_seq()).await
+        Self::open_by_mft_ref(fs, entry.mft_index(), entry.mft_seq(), "").await
+    }
+
+    /// Open a named `$DATA` stream of a file or directory.
+    ///
+    /// Stream names compare case-insensitively through the volume's
+    /// `$UpCase` table; the empty name selects the unnamed stream.
+    pub(crate) async fn open_stream(
+        fs: &'a NtfsFs<DATA>,
+        entry: &NtfsEntry,
+        name: &str,
+    ) -> Result<Self> {
+        Self::open_by_mft_ref(fs, entry.mft_index(), entry.mft_seq(), name).await
     }
 
     /// Open a file for reading given its MFT record number directly.
     pub(crate) async fn open_by_mft(fs: &'a NtfsFs<DATA>, mft_index: u64) -> Result<Self> {
-        Self::open_by_mft_ref(fs, mft_index, 0).await
+        Self::open_by_mft_ref(fs, mft_index, 0, "").await
     }
 
     async fn open_by_mft_ref(
         fs: &'a NtfsFs<DATA>,
         mft_index: u64,
         expected_sequence: u16,
+        stream_name: &str,
     ) -> Result<Self> {
         let record = fs
-            .read_mft_record_ref(mft_index, expected_sequence)
+            .read_file_record_ref(mft_index, expected_sequence)
             .await?;
-        let attrs = AttrIter::new(&record)?;
 
-        for a in attrs {
-            let a = a?;
-            if a.attr_type != ATTR_DATA || a.name.is_some() {
-                continue;
+        let name = if stream_name.is_empty() {
+            None
+        } else {
+            // Use the on-disk spelling so every extent of the stream matches.
+            let mut found = None;
+            for a in record.attrs() {
+                let a = a?;
+                let Some(raw) = a.name else { continue };
+                if a.attr_type == ATTR_DATA && fs.names_equal(&decode_utf16le(raw)?, stream_name) {
+                    found = Some(raw);
+                    break;
+                }
             }
+            Some(found.ok_or(NtfsError::AttributeNotFound {
+                attr_type: ATTR_DATA,
+            })?)
+        };
 
-            if a.flags & ATTR_FLAG_COMPRESSED != 0 {
-                return Err(NtfsError::UnsupportedCompression);
-            }
+        if let Some(a) = record.find(ATTR_DATA, name)? {
             if a.flags & ATTR_FLAG_ENCRYPTED != 0 {
                 return Err(NtfsError::UnsupportedEncryption);
             }
+            let compressed = a.flags & ATTR_FLAG_COMPRESSED != 0;
 
             return match a.body {
+                AttrBody::Resident(_) if compressed => Err(NtfsError::UnsupportedCompression),
                 AttrBody::Resident(value) => Ok(Self {
                     fs,
                     data: FileData::Resident(value.to_vec()),
@@ -85,21 +215,29 @@ impl<'a, DATA: Read + Seek> FileReader<'a, DATA> {
                     position: 0,
                 }),
                 AttrBody::NonResident {
-                    data_runs,
                     data_size,
                     initialized_size,
+                    compression_unit,
                     ..
                 } => {
                     if initialized_size > data_size {
                         return Err(NtfsError::InvalidAttribute);
                     }
-                    let runs = decode_data_runs(data_runs)?;
-                    Ok(Self {
-                        fs,
-                        data: FileData::NonResident {
+                    let runs = record.data_runs(ATTR_DATA, name)?;
+                    let data = if compressed {
+                        FileData::Compressed(
+                            CompressedStream::new(fs, runs, initialized_size, compression_unit)
+                                .await?,
+                        )
+                    } else {
+                        FileData::NonResident {
                             runs,
                             initialized_size,
-                        },
+                        }
+                    };
+                    Ok(Self {
+                        fs,
+                        data,
                         data_size,
                         position: 0,
                     })
@@ -122,6 +260,24 @@ impl<'a, DATA: Read + Seek> FileReader<'a, DATA> {
         self.data_size.saturating_sub(self.position)
     }
 
+    /// Move the read position.
+    ///
+    /// Seeking past the end is allowed; reads there return no data. For a
+    /// compressed stream only the compression unit holding the new position
+    /// is decoded, on the next read.
+    pub fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.data_size.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            NtfsError::Io(Error::new(ErrorKind::InvalidInput, "invalid seek position"))
+        })?;
+        self.position = target;
+        Ok(target)
+    }
+
     /// Read up to `buf.len()` bytes from the current position.
     pub async fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
         let remaining = self.remaining();
@@ -131,7 +287,7 @@ impl<'a, DATA: Read + Seek> FileReader<'a, DATA> {
         let to_read = (buf.len() as u64).min(remaining) as usize;
         let buf = &mut buf[..to_read];
 
-        match &self.data {
+        match &mut self.data {
             FileData::Resident(resident) => {
                 let start = self.position as usize;
                 buf.copy_from_slice(&resident[start..start + to_read]);
@@ -155,6 +311,21 @@ impl<'a, DATA: Read + Seek> FileReader<'a, DATA> {
                 }
                 buf[stored_len..].fill(0);
             }
+            FileData::Compressed(stream) => {
+                let initialized_remaining = stream.initialized_size.saturating_sub(self.position);
+                let stored_len = (to_read as u64).min(initialized_remaining) as usize;
+                let unit_size = stream.buffer.len() as u64;
+                let mut filled = 0;
+                while filled < stored_len {
+                    let position = self.position + filled as u64;
+                    stream.load_unit(self.fs, position / unit_size).await?;
+                    let start = (position % unit_size) as usize;
+                    let len = (stored_len - filled).min(stream.buffer.len() - start);
+                    buf[filled..filled + len].copy_from_slice(&stream.buffer[start..start + len]);
+                    filled += len;
+                }
+                buf[stored_len..].fill(0);
+            }
         }
 
         self.position += to_read as u64;
@@ -206,6 +377,15 @@ pub trait NtfsFsReadExt<DATA: Read + Seek> {
     async fn read_file<'a>(&'a self, entry: &NtfsEntry) -> Result<FileReader<'a, DATA>>
     where
         DATA: 'a;
+
+    /// Create a reader for a named `$DATA` stream (alternate data stream)
+    /// of a file or directory, such as `Zone.Identifier`.
+    ///
+    /// Names compare case-insensitively; the empty name opens the unnamed
+    /// stream, even for a directory.
+    async fn read_stream<'a>(&'a self, entry: &NtfsEntry, name: &str) -> Result<FileReader<'a, DATA>>
+    where
+        DATA: 'a;
 }
 
 impl<DATA: Read + Seek> NtfsFsReadExt<DATA> for NtfsFs<DATA> {
@@ -215,6 +395,13 @@ impl<DATA: Read + Seek> NtfsFsReadExt<DATA> for NtfsFs<DATA> {
     {
         FileReader::open(self, entry).await
     }
+
+    async fn read_stream<'a>(&'a self, entry: &NtfsEntry, name: &str) -> Result<FileReader<'a, DATA>>
+    where
+        DATA: 'a,
+    {
+        FileReader::open_stream(self, entry, name).await
+    }
 }
 
 } // end io_transform!
@@ -0,0 +1,57 @@
+//! NTFS timestamps and the [`TimeProvider`] trait.
+//!
+//! NTFS stores times as the number of 100-nanosecond intervals since
+//! 1601-01-01 UTC. [`TimeProvider`] is the pluggable clock the writer uses
+//! to stamp `$STANDARD_INFORMATION` after modifying a file.
+
+/// 100-nanosecond intervals between the NTFS epoch (1601-01-01) and the
+/// Unix epoch (1970-01-01).
+pub const UNIX_EPOCH_OFFSET: u64 = 116_444_736_000_000_000;
+
+/// Pluggable clock used to stamp modified files.
+///
+/// The filesystem holds it as a `&'static dyn TimeProvider`, so the bound on
+/// `Sync` keeps `NtfsFs` `Send`.
+pub trait TimeProvider: core::fmt::Debug + Sync {
+    /// Return the current time in NTFS units.
+    fn now(&self) -> u64;
+}
+
+/// Time provider backed by the system clock.
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemTimeProvider;
+
+#[cfg(feature = "std")]
+impl TimeProvider for SystemTimeProvider {
+    fn now(&self) -> u64 {
+        let since_unix = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let ticks = since_unix.as_nanos() / 100;
+        UNIX_EPOCH_OFFSET.saturating_add(u64::try_from(ticks).unwrap_or(u64::MAX))
+    }
+}
+
+/// Time provider that always returns a fixed NTFS time.
+///
+/// Useful for deterministic tests and reproducible images.
+#[derive(Debug, Clone, Copy)]
+pub struct StaticTimeProvider(pub u64);
+
+impl TimeProvider for StaticTimeProvider {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Default provider used when none is set on the builder.
+///
+/// Resolves to [`SystemTimeProvider`] under `std` and to the NTFS epoch
+/// otherwise.
+#[cfg(feature = "std")]
+pub static DEFAULT_TIME_PROVIDER: SystemTimeProvider = SystemTimeProvider;
+/// Default provider for targets without the standard library: the NTFS
+/// epoch.
+#[cfg(not(feature = "std"))]
+pub static DEFAULT_TIME_PROVIDER: StaticTimeProvider = StaticTimeProvider(0);
@@ -0,0 +1,197 @@
+//! In-place modification of NTFS file data.
+
+io_transform! {
+
+use alloc::vec;
+
+use crate::attr::{
+    protect_fixups, AttrBody, AttrIter, DataRun, ATTR_DATA, ATTR_FLAG_COMPRESSED,
+    ATTR_FLAG_ENCRYPTED, ATTR_STANDARD_INFORMATION, MFT_RECORD_FIRST_USER,
+};
+use crate::error::{NtfsError, Result};
+use super::dir::NtfsEntry;
+use super::fs::NtfsFs;
+use super::io::{Read, Seek, Write, write_data_runs};
+
+/// Largest buffer used to zero the uninitialized gap before a write.
+const ZERO_CHUNK: usize = 64 * 1024;
+
+/// Extension trait for modifying file data through [`NtfsFs`].
+///
+/// @hadris-spec NTFS:Data-Stream-Write
+/// @hadris-compliance partial
+/// @hadris-tests write::overwrite_past_initialized_size_zeroes_the_gap
+/// @hadris-note Overwrites clusters already allocated to an unnamed non-resident stream and updates its sizes and `$STANDARD_INFORMATION` times; allocation, resident data, compressed or encrypted streams, and `$LogFile` records are not supported.
+pub trait NtfsFsWriteExt<DATA: Read + Write + Seek> {
+    /// Overwrite `data.len()` bytes of a file's unnamed `$DATA` stream,
+    /// starting at `offset`.
+    ///
+    /// The write may extend the stream up to its allocated size; the data
+    /// and initialized sizes grow to cover it, and any gap after the old
+    /// initialized size is zeroed on disk. The file's modification, MFT
+    /// change, and access times are set from the configured
+    /// [`TimeProvider`](crate::time::TimeProvider). The size copy in the
+    /// parent directory's index is left as it was, so
+    /// [`NtfsEntry::size`] may be stale afterwards.
+    ///
+    /// Everything is validated before the first byte is written.
+    ///
+    /// # Errors
+    ///
+    /// - [`NtfsError::WriteRequiresAllocation`] if the range ends past the
+    ///   allocated size or touches a sparse run.
+    /// - [`NtfsError::UnsupportedWrite`] for metadata files, resident data,
+    ///   or a size change that would have to be recorded in an extension
+    ///   record.
+    /// - [`NtfsError::UnsupportedCompression`] or
+    ///   [`NtfsError::UnsupportedEncryption`] for compressed or encrypted
+    ///   streams.
+    async fn write_at(&self, entry: &NtfsEntry, offset: u64, data: &[u8]) -> Result<()>;
+}
+
+impl<DATA: Read + Write + Seek> NtfsFsWriteExt<DATA> for NtfsFs<DATA> {
+    async fn write_at(&self, entry: &NtfsEntry, offset: u64, data: &[u8]) -> Result<()> {
+        if entry.is_directory() {
+            return Err(NtfsError::NotAFile);
+        }
+        if entry.mft_index() < MFT_RECORD_FIRST_USER {
+            return Err(NtfsError::UnsupportedWrite);
+        }
+
+        let record = self
+            .read_file_record_ref(entry.mft_index(), entry.mft_seq())
+            .await?;
+        let attr = record
+            .find(ATTR_DATA, None)?
+            .ok_or(NtfsError::AttributeNotFound {
+                attr_type: ATTR_DATA,
+            })?;
+        if attr.flags & ATTR_FLAG_ENCRYPTED != 0 {
+            return Err(NtfsError::UnsupportedEncryption);
+        }
+        if attr.flags & ATTR_FLAG_COMPRESSED != 0 {
+            return Err(NtfsError::UnsupportedCompression);
+        }
+        let AttrBody::NonResident {
+            allocated_size,
+            data_size,
+            initialized_size,
+            ..
+        } = attr.body
+        else {
+            return Err(NtfsError::UnsupportedWrite);
+        };
+        if initialized_size > data_size || data_size > allocated_size {
+            return Err(NtfsError::InvalidAttribute);
+        }
+
+        let end = offset
+            .checked_add(data.len() as u64)
+            .filter(|&end| end <= allocated_size)
+            .ok_or(NtfsError::WriteRequiresAllocation)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        // The gap between the old initialized size and `offset` is zeroed
+        // too, so it must be backed by clusters as well.
+        let cluster_size = self.cluster_size as u64;
+        let runs = record.data_runs(ATTR_DATA, None)?;
+        let gap_start = initialized_size.min(offset);
+        if !is_stored(&runs, gap_start, end, cluster_size) {
+            return Err(NtfsError::WriteRequiresAllocation);
+        }
+
+        let mut base = record.base().to_vec();
+        if end > initialized_size {
+            let header = unnamed_data_header(&base)?.ok_or(NtfsError::UnsupportedWrite)?;
+            let data_size = data_size.max(end);
+            base[header + 0x30..header + 0x38].copy_from_slice(&data_size.to_le_bytes());
+            base[header + 0x38..header + 0x40].copy_from_slice(&end.to_le_bytes());
+        }
+        let times = standard_information_value(&base)?;
+        let now = self.time_provider().now().to_le_bytes();
+        for field in [0x08, 0x10, 0x18] {
+            base[times + field..times + field + 8].copy_from_slice(&now);
+        }
+        protect_fixups(&mut base, self.sector_size)?;
+
+        let index = entry.mft_index();
+        let record_offset = index
+            .checked_mul(self.mft_record_size as u64)
+            .ok_or(NtfsError::MftRecordOutOfBounds { index })?;
+        let mut disk = self.data.lock();
+        if gap_start < offset {
+            let zeros = vec![0_u8; ((offset - gap_start) as usize).min(ZERO_CHUNK)];
+            let mut position = gap_start;
+            while position < offset {
+                let len = ((offset - position) as usize).min(zeros.len());
+                write_data_runs(&mut *disk, &runs, position, &zeros[..len], cluster_size).await?;
+                position += len as u64;
+            }
+        }
+        write_data_runs(&mut *disk, &runs, offset, data, cluster_size).await?;
+        write_data_runs(&mut *disk, &self.mft_runs, record_offset, &base, cluster_size).await?;
+        Ok(())
+    }
+}
+
+/// Whether every byte in `start..end` is backed by a non-sparse run.
+fn is_stored(runs: &[DataRun], start: u64, end: u64, cluster_size: u64) -> bool {
+    let mut run_start = 0_u64;
+    for run in runs {
+        let Some(run_end) = run
+            .length
+            .checked_mul(cluster_size)
+            .and_then(|bytes| run_start.checked_add(bytes))
+        else {
+            return false;
+        };
+        if run_end > start && run.lcn < 0 {
+            return false;
+        }
+        if run_end >= end {
+            return true;
+        }
+        run_start = run_end;
+    }
+    false
+}
+
+/// Byte offset of the unnamed `$DATA` attribute's first extent in a base
+/// record, if the base record holds it.
+fn unnamed_data_header(record: &[u8]) -> Result<Option<usize>> {
+    let mut attrs = AttrIter::new(record)?;
+    while let Some((offset, attr)) = attrs.next_with_offset() {
+        let attr = attr?;
+        if attr.attr_type != ATTR_DATA || attr.name.is_some() {
+            continue;
+        }
+        if let AttrBody::NonResident { start_vcn: 0, .. } = attr.body {
+            return Ok(Some(offset));
+        }
+    }
+    Ok(None)
+}
+
+/// Byte offset of the `$STANDARD_INFORMATION` value in a base record.
+fn standard_information_value(record: &[u8]) -> Result<usize> {
+    let mut attrs = AttrIter::new(record)?;
+    while let Some((offset, attr)) = attrs.next_with_offset() {
+        let attr = attr?;
+        if attr.attr_type != ATTR_STANDARD_INFORMATION {
+            continue;
+        }
+        return match attr.body {
+            AttrBody::Resident(value) if value.len() >= 0x20 => {
+                Ok(offset + u16::from_le_bytes([record[offset + 0x14], record[offset + 0x15]]) as usize)
+            }
+            _ => Err(NtfsError::InvalidAttribute),
+        };
+    }
+    Err(NtfsError::AttributeNotFound {
+        attr_type: ATTR_STANDARD_INFORMATION,
+    })
+}
+
+} // end io_transform!
@@ -0,0 +1,278 @@
+//! `$ATTRIBUTE_LIST` resolution on crafted images: a fragmented `$MFT`, a
+//! file whose `$DATA` extents span two records, and a directory whose index
+//! allocation continues in an extension record.
+
+use std::io::Cursor;
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr::{self, parse_attribute_list};
+use hadris_ntfs::sync::{NtfsFs, NtfsFsReadExt};
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{
+    I30, REC, extension_record, extent_attr, file_name_value, file_record, index_node, index_root,
+    indx_block, leaf_entries, put_at, put_record, resident_attr, volume,
+};
+
+const MFT_TAIL_LCN: usize = 100; // records 16..23
+const IMAGE_LEN: usize = 65536;
+
+fn list_entry(attr_type: u32, name: Option<&[u8]>, start_vcn: u64, mft_ref: u64) -> Vec<u8> {
+    let name_bytes = name.map_or(0, |n| n.len());
+    let length = (0x1A + name_bytes + 7) & !7;
+    let mut e = vec![0_u8; length];
+    e[0..4].copy_from_slice(&attr_type.to_le_bytes());
+    e[4..6].copy_from_slice(&(length as u16).to_le_bytes());
+    if let Some(n) = name {
+        e[6] = (n.len() / 2) as u8;
+        e[7] = 0x1A;
+        e[0x1A..0x1A + n.len()].copy_from_slice(n);
+    }
+    e[0x08..0x10].copy_from_slice(&start_vcn.to_le_bytes());
+    e[0x10..0x18].copy_from_slice(&mft_ref.to_le_bytes());
+    e
+}
+
+fn list(entries: &[Vec<u8>]) -> Vec<u8> {
+    entries.concat()
+}
+
+/// A POSIX name in the directory at `parent`.
+fn named(parent: u64, name: &str, is_dir: bool, data_size: u64) -> Vec<u8> {
+    let flags: u32 = if is_dir { 0x1000_0020 } else { 0x20 };
+    file_name_value(parent, name, attr::FILE_NAME_POSIX, flags, data_size)
+}
+
+/// A leaf `INDX` block at `vcn` listing `entries`.
+fn leaf_block(vcn: u64, entries: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    indx_block(vcn, &index_node(&leaf_entries(entries), 0x18, false))
+}
+
+fn frag_contents() -> Vec<u8> {
+    (0..2000_u32).map(|i| (i * 7 + i / 256) as u8).collect()
+}
+
+/// Records 0..15 sit at LCN 4 and records 16..23 at LCN 100; the second
+/// `$MFT` extent is described by extension record 11.
+///
+/// - `FAR.TXT` (16) lives in the second `$MFT` extent.
+/// - `FRAG.BIN` (6) has a non-resident attribute list and `$DATA` extents
+///   in records 6 (LCN 60) and 12 (LCN 70).
+/// - `DIR` (7) keeps its second index-allocation extent (LCN 48) and its
+///   bitmap in extension record 13.
+fn fragmented_image() -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 16, 0);
+
+    // $MFT
+    let mft_list = list(&[
+        list_entry(attr::ATTR_DATA, None, 0, 0),
+        list_entry(attr::ATTR_DATA, None, 32, 11 | 1 << 48),
+    ]);
+    let mft_head = extent_attr(
+        attr::ATTR_DATA,
+        None,
+        0,
+        31,
+        24 * REC as u64,
+        &[0x11, 0x20, 0x04],
+    );
+    let mft_list = resident_attr(attr::ATTR_ATTRIBUTE_LIST, None, &mft_list);
+    put_record(&mut image, 0, &file_record(1, &[mft_list, mft_head]));
+    let mft_tail = extent_attr(attr::ATTR_DATA, None, 32, 47, 0, &[0x11, 0x10, 0x64]);
+    put_record(&mut image, 11, &extension_record(0, &[mft_tail]));
+
+    // Root directory
+    let root = index_root(&[
+        (6, named(5, "FRAG.BIN", false, 2000)),
+        (7, named(5, "DIR", true, 0)),
+        (16, named(5, "FAR.TXT", false, 8)),
+    ]);
+    put_record(&mut image, 5, &file_record(3, &[root]));
+
+    let far = resident_attr(attr::ATTR_DATA, None, b"far away");
+    put_at(&mut image, MFT_TAIL_LCN, &file_record(1, &[far]));
+
+    // FRAG.BIN
+    let frag_list = list(&[
+        list_entry(attr::ATTR_DATA, None, 0, 6),
+        list_entry(attr::ATTR_DATA, None, 2, 12 | 1 << 48),
+    ]);
+    put_at(&mut image, 80, &frag_list);
+    let frag_list = extent_attr(
+        attr::ATTR_ATTRIBUTE_LIST,
+        None,
+        0,
+        0,
+        frag_list.len() as u64,
+        &[0x11, 0x01, 0x50],
+    );
+    let frag_head = extent_attr(attr::ATTR_DATA, None, 0, 1, 2000, &[0x11, 0x02, 0x3C]);
+    put_record(&mut image, 6, &file_record(1, &[frag_list, frag_head]));
+    let frag_tail = extent_attr(attr::ATTR_DATA, None, 2, 3, 0, &[0x11, 0x02, 0x46]);
+    put_record(&mut image, 12, &extension_record(6, &[frag_tail]));
+    let contents = frag_contents();
+    put_at(&mut image, 60, &contents[..1024]);
+    put_at(&mut image, 70, &contents[1024..]);
+
+    // DIR
+    let dir_list = list(&[
+        list_entry(attr::ATTR_INDEX_ROOT, Some(I30), 0, 7),
+        list_entry(attr::ATTR_INDEX_ALLOCATION, Some(I30), 0, 7),
+        list_entry(attr::ATTR_INDEX_ALLOCATION, Some(I30), 2, 13),
+        list_entry(attr::ATTR_BITMAP, Some(I30), 0, 13),
+    ]);
+    let dir_list = resident_attr(attr::ATTR_ATTRIBUTE_LIST, None, &dir_list);
+    let dir_root = index_root(&[]);
+    let dir_head = extent_attr(
+        attr::ATTR_INDEX_ALLOCATION,
+        Some(I30),
+        0,
+        1,
+        2 * REC as u64,
+        &[0x11, 0x02, 0x2C],
+    );
+    put_record(
+        &mut image,
+        7,
+        &file_record(3, &[dir_list, dir_root, dir_head]),
+    );
+    let dir_tail = extent_attr(
+        attr::ATTR_INDEX_ALLOCATION,
+        Some(I30),
+        2,
+        3,
+        0,
+        &[0x11, 0x02, 0x30],
+    );
+    let bitmap = resident_attr(attr::ATTR_BITMAP, Some(I30), &[0b11, 0, 0, 0, 0, 0, 0, 0]);
+    put_record(&mut image, 13, &extension_record(7, &[dir_tail, bitmap]));
+    put_at(
+        &mut image,
+        44,
+        &leaf_block(0, &[(9, named(7, "A.TXT", false, 1))]),
+    );
+    put_at(
+        &mut image,
+        48,
+        &leaf_block(2, &[(14, named(7, "B.TXT", false, 1))]),
+    );
+    put_record(
+        &mut image,
+        9,
+        &file_record(1, &[resident_attr(attr::ATTR_DATA, None, b"a")]),
+    );
+    put_record(
+        &mut image,
+        14,
+        &file_record(1, &[resident_attr(attr::ATTR_DATA, None, b"b")]),
+    );
+
+    image
+}
+
+fn read_path(fs: &NtfsFs<Cursor<Vec<u8>>>, path: &str) -> Result<Vec<u8>, NtfsError> {
+    let entry = fs.open_path(path)?;
+    fs.read_file(&entry)?.read_to_vec()
+}
+
+#[test]
+fn list_entries_parse_names_and_references() {
+    let value = list(&[
+        list_entry(attr::ATTR_STANDARD_INFORMATION, None, 0, 40 | 3 << 48),
+        list_entry(attr::ATTR_INDEX_ALLOCATION, Some(I30), 16, 41 | 7 << 48),
+    ]);
+    let entries = parse_attribute_list(&value).unwrap();
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].attr_type, attr::ATTR_STANDARD_INFORMATION);
+    assert!(entries[0].name.is_empty());
+    assert_eq!((entries[0].mft_index, entries[0].mft_seq), (40, 3));
+    assert_eq!(entries[1].attr_type, attr::ATTR_INDEX_ALLOCATION);
+    assert_eq!(entries[1].name, I30);
+    assert_eq!(entries[1].start_vcn, 16);
+    assert_eq!((entries[1].mft_index, entries[1].mft_seq), (41, 7));
+}
+
+#[test]
+fn list_entries_reject_truncated_or_zero_length_entries() {
+    let entry = list_entry(attr::ATTR_DATA, None, 0, 6);
+    assert!(matches!(
+        parse_attribute_list(&entry[..0x10]),
+        Err(NtfsError::InvalidAttributeList)
+    ));
+
+    let mut zero_length = entry.clone();
+    zero_length[4..6].copy_from_slice(&0_u16.to_le_bytes());
+    assert!(matches!(
+        parse_attribute_list(&zero_length),
+        Err(NtfsError::InvalidAttributeList)
+    ));
+
+    let mut long_name = list_entry(attr::ATTR_DATA, Some(I30), 0, 6);
+    long_name[6] = 8;
+    assert!(matches!(
+        parse_attribute_list(&long_name),
+        Err(NtfsError::InvalidAttributeList)
+    ));
+}
+
+#[test]
+fn fragmented_mft_reaches_records_in_later_extents() {
+    let fs = NtfsFs::open(Cursor::new(fragmented_image())).unwrap();
+    assert_eq!(read_path(&fs, "FAR.TXT").unwrap(), b"far away");
+    assert!(fs.read_mft_record(23).is_err()); // in range but never written
+    assert!(matches!(
+        fs.read_mft_record(24),
+        Err(NtfsError::UnexpectedEndOfData)
+    ));
+}
+
+#[test]
+fn data_extents_in_extension_records_are_joined() {
+    let fs = NtfsFs::open(Cursor::new(fragmented_image())).unwrap();
+    let record = fs.read_file_record(6).unwrap();
+    assert_eq!(record.records().len(), 2);
+
+    let entry = fs.open_path("FRAG.BIN").unwrap();
+    let mut reader = fs.read_file(&entry).unwrap();
+    assert_eq!(reader.size(), 2000);
+    assert_eq!(reader.read_to_vec().unwrap(), frag_contents());
+}
+
+#[test]
+fn index_allocation_in_extension_records_lists_every_block() {
+    let fs = NtfsFs::open(Cursor::new(fragmented_image())).unwrap();
+    let dir = fs.root_dir().open_dir("DIR").unwrap();
+    let entries = dir.entries().unwrap();
+    let names: Vec<&str> = entries.iter().map(|e| e.name()).collect();
+    assert_eq!(names, ["A.TXT", "B.TXT"]);
+    assert_eq!(read_path(&fs, "DIR/B.TXT").unwrap(), b"b");
+}
+
+#[test]
+fn extension_record_of_another_file_is_rejected() {
+    let mut image = fragmented_image();
+    let frag_tail = extent_attr(attr::ATTR_DATA, None, 2, 3, 0, &[0x11, 0x02, 0x46]);
+    put_record(&mut image, 12, &extension_record(5, &[frag_tail]));
+
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert!(matches!(
+        read_path(&fs, "FRAG.BIN"),
+        Err(NtfsError::InvalidAttributeList)
+    ));
+}
+
+#[test]
+fn gaps_between_extents_are_rejected() {
+    let mut image = fragmented_image();
+    let frag_tail = extent_attr(attr::ATTR_DATA, None, 3, 4, 0, &[0x11, 0x02, 0x46]);
+    put_record(&mut image, 12, &extension_record(6, &[frag_tail]));
+
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert!(matches!(
+        read_path(&fs, "FRAG.BIN"),
+        Err(NtfsError::InvalidAttributeList)
+    ));
+}
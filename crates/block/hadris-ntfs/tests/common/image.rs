@@ -0,0 +1,299 @@
+//! Crafted NTFS images for the integration tests: 512-byte sectors,
+//! one-sector clusters, 1 KiB MFT and index records, and `$MFT` at LCN 4.
+
+#![allow(dead_code)]
+
+use hadris_ntfs::attr;
+
+pub const SECTOR: usize = 512;
+pub const REC: usize = 1024;
+pub const MFT_OFF: usize = 2048; // LCN 4
+
+pub const I30: &[u8] = &[0x24, 0x00, 0x49, 0x00, 0x33, 0x00, 0x30, 0x00];
+
+/// The boot sector of a 256 KiB volume; a `mirror_lcn` of 0 leaves
+/// `$MFTMirr` unset.
+pub fn boot_sector(mirror_lcn: u64) -> Vec<u8> {
+    let mut boot = vec![0_u8; SECTOR];
+    boot[3..11].copy_from_slice(b"NTFS    ");
+    boot[11..13].copy_from_slice(&512_u16.to_le_bytes());
+    boot[13] = 1; // sectors per cluster
+    boot[40..48].copy_from_slice(&512_u64.to_le_bytes()); // total sectors (256 KiB)
+    boot[48..56].copy_from_slice(&4_u64.to_le_bytes()); // $MFT LCN
+    boot[56..64].copy_from_slice(&mirror_lcn.to_le_bytes()); // $MFTMirr LCN
+    boot[64] = (-10_i8) as u8; // 1024-byte MFT records
+    boot[68] = (-10_i8) as u8; // 1024-byte index records
+    boot[510..512].copy_from_slice(&0xAA55_u16.to_le_bytes());
+    boot
+}
+
+/// A zeroed `len`-byte image with the boot sector, a contiguous `$MFT` of
+/// `mft_records` records, and a sparse all-zero `$UpCase` in record 10.
+pub fn volume(len: usize, mft_records: u64, mirror_lcn: u64) -> Vec<u8> {
+    let mut image = boot_sector(mirror_lcn);
+    image.resize(len, 0);
+
+    let clusters = mft_records * (REC / SECTOR) as u64;
+    let mft = non_resident_attr(
+        attr::ATTR_DATA,
+        None,
+        clusters - 1,
+        mft_records * REC as u64,
+        &[0x11, clusters as u8, 0x04],
+    );
+    put_record(&mut image, 0, &file_record(1, &[mft]));
+    let upcase = non_resident_attr(attr::ATTR_DATA, None, 255, 131072, &[0x02, 0x00, 0x01]);
+    put_record(&mut image, 10, &file_record(1, &[upcase]));
+    image
+}
+
+/// An `$UpCase` table that folds ASCII letters only.
+pub fn upcase_table() -> Vec<u8> {
+    (0..=u16::MAX)
+        .map(|unit| match unit {
+            0x61..=0x7A => unit - 0x20,
+            _ => unit,
+        })
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
+
+/// Replace the all-zero `$UpCase` of [`volume`] with [`upcase_table`]
+/// stored at `lcn`.
+pub fn put_upcase_table(image: &mut [u8], lcn: u16) {
+    let [low, high] = lcn.to_le_bytes();
+    let runs = [0x22, 0x00, 0x01, low, high]; // 256 clusters at `lcn`
+    let upcase = non_resident_attr(attr::ATTR_DATA, None, 255, 131072, &runs);
+    put_record(image, 10, &file_record(1, &[upcase]));
+    put_at(image, lcn.into(), &upcase_table());
+}
+
+pub fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+pub fn resident_attr(attr_type: u32, name: Option<&[u8]>, value: &[u8]) -> Vec<u8> {
+    let name = name.unwrap_or_default();
+    let value_off = (0x18 + name.len() + 7) & !7;
+    let total = (value_off + value.len() + 7) & !7;
+    let mut a = vec![0_u8; total];
+    a[0..4].copy_from_slice(&attr_type.to_le_bytes());
+    a[4..8].copy_from_slice(&(total as u32).to_le_bytes());
+    if !name.is_empty() {
+        a[9] = (name.len() / 2) as u8;
+        a[0x0A..0x0C].copy_from_slice(&0x18_u16.to_le_bytes());
+        a[0x18..0x18 + name.len()].copy_from_slice(name);
+    }
+    a[0x10..0x14].copy_from_slice(&(value.len() as u32).to_le_bytes());
+    a[0x14..0x16].copy_from_slice(&(value_off as u16).to_le_bytes());
+    a[value_off..value_off + value.len()].copy_from_slice(value);
+    a
+}
+
+/// A non-resident attribute whose runs cover `0..=last_vcn`.
+pub fn non_resident_attr(
+    attr_type: u32,
+    name: Option<&[u8]>,
+    last_vcn: u64,
+    data_size: u64,
+    runs: &[u8],
+) -> Vec<u8> {
+    let mut a = extent_attr(attr_type, name, 0, last_vcn, data_size, runs);
+    a[0x28..0x30].copy_from_slice(&((last_vcn + 1) * SECTOR as u64).to_le_bytes());
+    a
+}
+
+/// One extent of a non-resident attribute covering `start_vcn..=last_vcn`.
+/// Sizes are only meaningful on the first extent.
+pub fn extent_attr(
+    attr_type: u32,
+    name: Option<&[u8]>,
+    start_vcn: u64,
+    last_vcn: u64,
+    data_size: u64,
+    runs: &[u8],
+) -> Vec<u8> {
+    let name = name.unwrap_or_default();
+    let runs_off = (0x40 + name.len() + 7) & !7;
+    let total = (runs_off + runs.len() + 1 + 7) & !7;
+    let mut a = vec![0_u8; total];
+    a[0..4].copy_from_slice(&attr_type.to_le_bytes());
+    a[4..8].copy_from_slice(&(total as u32).to_le_bytes());
+    a[8] = 1;
+    if !name.is_empty() {
+        a[9] = (name.len() / 2) as u8;
+        a[0x0A..0x0C].copy_from_slice(&0x40_u16.to_le_bytes());
+        a[0x40..0x40 + name.len()].copy_from_slice(name);
+    }
+    a[0x10..0x18].copy_from_slice(&start_vcn.to_le_bytes());
+    a[0x18..0x20].copy_from_slice(&last_vcn.to_le_bytes());
+    a[0x20..0x22].copy_from_slice(&(runs_off as u16).to_le_bytes());
+    a[0x28..0x30].copy_from_slice(&data_size.to_le_bytes());
+    a[0x30..0x38].copy_from_slice(&data_size.to_le_bytes());
+    a[0x38..0x40].copy_from_slice(&data_size.to_le_bytes());
+    a[runs_off..runs_off + runs.len()].copy_from_slice(runs);
+    a
+}
+
+/// A `$FILE_NAME` value for `name` in the directory at record `parent`.
+pub fn file_name_value(parent: u64, name: &str, namespace: u8, flags: u32, size: u64) -> Vec<u8> {
+    let name_bytes = utf16le(name);
+    let mut v = vec![0_u8; 0x42 + name_bytes.len()];
+    v[0..8].copy_from_slice(&(parent | 1 << 48).to_le_bytes());
+    v[0x28..0x30].copy_from_slice(&size.to_le_bytes());
+    v[0x30..0x38].copy_from_slice(&size.to_le_bytes());
+    v[0x38..0x3C].copy_from_slice(&flags.to_le_bytes());
+    v[0x40] = (name_bytes.len() / 2) as u8;
+    v[0x41] = namespace;
+    v[0x42..].copy_from_slice(&name_bytes);
+    v
+}
+
+/// An index entry for record `mft_ref`; entries of a node with children
+/// carry the VCN of the child sorting before them.
+pub fn index_entry(mft_ref: u64, content: &[u8], subnode: Option<u64>) -> Vec<u8> {
+    let vcn_len = if subnode.is_some() { 8 } else { 0 };
+    let entry_len = ((16 + content.len() + 7) & !7) + vcn_len;
+    let mut e = vec![0_u8; entry_len];
+    e[0..8].copy_from_slice(&(mft_ref | 1 << 48).to_le_bytes());
+    e[8..10].copy_from_slice(&(entry_len as u16).to_le_bytes());
+    e[10..12].copy_from_slice(&(content.len() as u16).to_le_bytes());
+    e[16..16 + content.len()].copy_from_slice(content);
+    if let Some(vcn) = subnode {
+        e[12..16].copy_from_slice(&attr::INDEX_ENTRY_SUBNODE.to_le_bytes());
+        e[entry_len - 8..].copy_from_slice(&vcn.to_le_bytes());
+    }
+    e
+}
+
+pub fn end_entry(subnode: Option<u64>) -> Vec<u8> {
+    let Some(vcn) = subnode else {
+        let mut e = vec![0_u8; 16];
+        e[8..10].copy_from_slice(&16_u16.to_le_bytes());
+        e[12..16].copy_from_slice(&attr::INDEX_ENTRY_LAST.to_le_bytes());
+        return e;
+    };
+    let mut e = vec![0_u8; 24];
+    e[8..10].copy_from_slice(&24_u16.to_le_bytes());
+    let flags = attr::INDEX_ENTRY_LAST | attr::INDEX_ENTRY_SUBNODE;
+    e[12..16].copy_from_slice(&flags.to_le_bytes());
+    e[16..24].copy_from_slice(&vcn.to_le_bytes());
+    e
+}
+
+/// The entries of a leaf node listing `(MFT record, $FILE_NAME value)`
+/// pairs, closed by the end entry.
+pub fn leaf_entries(entries: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    let mut raw: Vec<u8> = entries
+        .iter()
+        .flat_map(|(mft_ref, content)| index_entry(*mft_ref, content, None))
+        .collect();
+    raw.extend(end_entry(None));
+    raw
+}
+
+/// A node header followed by `entries`, which start `entries_offset` bytes
+/// into the node.
+pub fn index_node(entries: &[u8], entries_offset: usize, has_children: bool) -> Vec<u8> {
+    let mut node = vec![0_u8; entries_offset + entries.len()];
+    node[0..4].copy_from_slice(&(entries_offset as u32).to_le_bytes());
+    let total = node.len() as u32;
+    node[4..8].copy_from_slice(&total.to_le_bytes());
+    node[8..12].copy_from_slice(&total.to_le_bytes());
+    if has_children {
+        node[12..16].copy_from_slice(&attr::INDEX_NODE_HAS_CHILDREN.to_le_bytes());
+    }
+    node[entries_offset..].copy_from_slice(entries);
+    node
+}
+
+/// The `$I30` `$INDEX_ROOT` attribute holding `node`.
+pub fn index_root_attr(node: &[u8]) -> Vec<u8> {
+    let mut v = vec![0_u8; 0x10];
+    v[0..4].copy_from_slice(&attr::ATTR_FILE_NAME.to_le_bytes());
+    v[4..8].copy_from_slice(&1_u32.to_le_bytes()); // collation: filename
+    v[8..12].copy_from_slice(&(REC as u32).to_le_bytes());
+    v[12] = 2; // clusters per index record
+    v.extend(node);
+    resident_attr(attr::ATTR_INDEX_ROOT, Some(I30), &v)
+}
+
+/// An `$INDEX_ROOT` with no index blocks below it.
+pub fn index_root(entries: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    index_root_attr(&index_node(&leaf_entries(entries), 16, false))
+}
+
+/// An `INDX` block at `vcn`. `node` starts 0x18 bytes in, so its entries
+/// must start at least 0x18 bytes into it to clear the update sequence array.
+pub fn indx_block(vcn: u64, node: &[u8]) -> Vec<u8> {
+    let mut b = vec![0_u8; REC];
+    b[0..4].copy_from_slice(b"INDX");
+    b[0x10..0x18].copy_from_slice(&vcn.to_le_bytes());
+    b[0x18..0x18 + node.len()].copy_from_slice(node);
+    seal_record(&mut b, 0x28);
+    b
+}
+
+/// Apply the update sequence array at `uso`, as the volume would on write.
+pub fn seal_record(buf: &mut [u8], uso: usize) {
+    let usn = 0xAAAA_u16.to_le_bytes();
+    buf[4..6].copy_from_slice(&(uso as u16).to_le_bytes());
+    let uss = (buf.len() / SECTOR + 1) as u16;
+    buf[6..8].copy_from_slice(&uss.to_le_bytes());
+    buf[uso..uso + 2].copy_from_slice(&usn);
+    for i in 0..buf.len() / SECTOR {
+        let end = (i + 1) * SECTOR - 2;
+        buf[uso + 2 + i * 2] = buf[end];
+        buf[uso + 3 + i * 2] = buf[end + 1];
+        buf[end..end + 2].copy_from_slice(&usn);
+    }
+}
+
+/// A FILE record with `flags` (1: in use, 3: directory) holding `attrs`.
+pub fn file_record(flags: u16, attrs: &[Vec<u8>]) -> Vec<u8> {
+    record(flags, 0, None, attrs)
+}
+
+/// A FILE record for a file with `link_count` hard links.
+pub fn linked_record(flags: u16, link_count: u16, attrs: &[Vec<u8>]) -> Vec<u8> {
+    record(flags, link_count, None, attrs)
+}
+
+/// An extension record of the file at record `base`.
+pub fn extension_record(base: u64, attrs: &[Vec<u8>]) -> Vec<u8> {
+    record(1, 0, Some(base), attrs)
+}
+
+fn record(flags: u16, link_count: u16, base: Option<u64>, attrs: &[Vec<u8>]) -> Vec<u8> {
+    let mut r = vec![0_u8; REC];
+    r[0..4].copy_from_slice(b"FILE");
+    r[0x10..0x12].copy_from_slice(&1_u16.to_le_bytes()); // sequence
+    r[0x12..0x14].copy_from_slice(&link_count.to_le_bytes());
+    r[0x14..0x16].copy_from_slice(&0x38_u16.to_le_bytes()); // first attr
+    r[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+    if let Some(base) = base {
+        r[0x20..0x28].copy_from_slice(&(base | 1 << 48).to_le_bytes());
+    }
+    let mut off = 0x38;
+    for a in attrs {
+        r[off..off + a.len()].copy_from_slice(a);
+        off += a.len();
+    }
+    r[off..off + 4].copy_from_slice(&attr::ATTR_END.to_le_bytes());
+    let used = (off + 8) & !7;
+    r[0x18..0x1C].copy_from_slice(&(used as u32).to_le_bytes());
+    seal_record(&mut r, 0x30);
+    r
+}
+
+/// Store `record` in the first, contiguous `$MFT` extent.
+pub fn put_record(image: &mut [u8], index: usize, record: &[u8]) {
+    let off = MFT_OFF + index * REC;
+    image[off..off + REC].copy_from_slice(record);
+}
+
+/// Store `bytes` at the start of cluster `lcn`.
+pub fn put_at(image: &mut [u8], lcn: usize, bytes: &[u8]) {
+    let off = lcn * SECTOR;
+    image[off..off + bytes.len()].copy_from_slice(bytes);
+}
@@ -0,0 +1,299 @@
+//! LZNT1 decoding and compressed `$DATA` streams on a crafted image.
+
+use std::cell::Cell;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::rc::Rc;
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr;
+use hadris_ntfs::lznt1::{self, CHUNK_SIZE};
+use hadris_ntfs::sync::{NtfsFs, NtfsFsReadExt, SeekFrom as NtfsSeekFrom};
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{SECTOR, file_name_value, file_record, index_root, put_at, put_record, volume};
+
+const IMAGE_LEN: usize = 65536;
+const UNIT: usize = 16 * SECTOR; // compression unit of 16 one-sector clusters
+const FILE_SIZE: usize = 4 * UNIT - 1000;
+
+// ---------------------------------------------------------------------------
+// LZNT1
+// ---------------------------------------------------------------------------
+
+/// One compressed chunk: "abc" then a back-reference repeating it to fill
+/// the chunk.
+fn abc_chunk() -> Vec<u8> {
+    let token = (2_u16 << 12) | (CHUNK_SIZE as u16 - 3 - 3);
+    let mut chunk = vec![0x05, 0xB0, 0b1000, b'a', b'b', b'c'];
+    chunk.extend(token.to_le_bytes());
+    chunk
+}
+
+fn abc(len: usize) -> Vec<u8> {
+    b"abc".iter().copied().cycle().take(len).collect()
+}
+
+#[test]
+fn lznt1_backreferences_widen_offsets_as_the_chunk_fills() {
+    // After 17 literals the offset field takes five bits, so 0x8002 is
+    // offset 17, length 5.
+    let mut data = vec![0x00];
+    data.extend(b"abcdefgh");
+    data.push(0x00);
+    data.extend(b"ijklmnop");
+    data.extend([0x02, b'q', 0x02, 0x80]);
+    let mut input = (0xB000_u16 | (data.len() as u16 - 1))
+        .to_le_bytes()
+        .to_vec();
+    input.extend(data);
+
+    let mut output = [0xFF_u8; 32];
+    assert_eq!(lznt1::decompress(&input, &mut output).unwrap(), 22);
+    assert_eq!(&output[..22], b"abcdefghijklmnopqabcde");
+    assert_eq!(output[22..], [0; 10]);
+}
+
+#[test]
+fn lznt1_short_chunks_are_padded_to_the_chunk_size() {
+    let mut input = vec![0x03, 0xB0, 0x00, b'x', b'y', b'z'];
+    input.extend(abc_chunk());
+    input.extend([0, 0]);
+
+    let mut output = vec![0xFF_u8; 3 * CHUNK_SIZE];
+    assert_eq!(
+        lznt1::decompress(&input, &mut output).unwrap(),
+        2 * CHUNK_SIZE
+    );
+    assert_eq!(&output[..3], b"xyz");
+    assert!(output[3..CHUNK_SIZE].iter().all(|&b| b == 0));
+    assert_eq!(output[CHUNK_SIZE..2 * CHUNK_SIZE], abc(CHUNK_SIZE));
+    assert!(output[2 * CHUNK_SIZE..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn lznt1_rejects_malformed_chunks() {
+    let mut output = vec![0_u8; CHUNK_SIZE];
+
+    // Back-reference before the start of the chunk
+    let before_start = [0x03, 0xB0, 0b10, b'a', 0x00, 0x10];
+    assert!(matches!(
+        lznt1::decompress(&before_start, &mut output),
+        Err(NtfsError::InvalidCompressedData)
+    ));
+
+    // Bad signature bits
+    let mut bad_signature = abc_chunk();
+    bad_signature[1] = 0x80;
+    assert!(matches!(
+        lznt1::decompress(&bad_signature, &mut output),
+        Err(NtfsError::InvalidCompressedData)
+    ));
+
+    // Chunk data running past the input
+    let truncated = &abc_chunk()[..5];
+    assert!(matches!(
+        lznt1::decompress(truncated, &mut output),
+        Err(NtfsError::InvalidCompressedData)
+    ));
+
+    // Back-reference running past the chunk
+    let mut overrun = abc_chunk();
+    overrun[6..8].copy_from_slice(&((2_u16 << 12) | 0x0FFF).to_le_bytes());
+    assert!(matches!(
+        lznt1::decompress(&overrun, &mut output),
+        Err(NtfsError::InvalidCompressedData)
+    ));
+}
+
+// ---------------------------------------------------------------------------
+// Compressed streams
+// ---------------------------------------------------------------------------
+
+/// A compressed unnamed `$DATA`, whose longer header carries the
+/// compressed size.
+fn compressed_attr(last_vcn: u64, data_size: u64, compression_unit: u8, runs: &[u8]) -> Vec<u8> {
+    let total = (0x48 + runs.len() + 1 + 7) & !7;
+    let mut a = vec![0_u8; total];
+    a[0..4].copy_from_slice(&attr::ATTR_DATA.to_le_bytes());
+    a[4..8].copy_from_slice(&(total as u32).to_le_bytes());
+    a[8] = 1;
+    a[0x0C..0x0E].copy_from_slice(&attr::ATTR_FLAG_COMPRESSED.to_le_bytes());
+    a[0x18..0x20].copy_from_slice(&last_vcn.to_le_bytes());
+    a[0x20..0x22].copy_from_slice(&0x48_u16.to_le_bytes());
+    a[0x22] = compression_unit;
+    a[0x28..0x30].copy_from_slice(&((last_vcn + 1) * SECTOR as u64).to_le_bytes());
+    a[0x30..0x38].copy_from_slice(&data_size.to_le_bytes());
+    a[0x38..0x40].copy_from_slice(&data_size.to_le_bytes());
+    a[0x48..0x48 + runs.len()].copy_from_slice(runs);
+    a
+}
+
+fn stored_unit() -> Vec<u8> {
+    (0..UNIT as u32).map(|i| (i * 13 + i / 512) as u8).collect()
+}
+
+/// Unit 3 holds a stored (uncompressed) chunk and a short compressed one.
+fn mixed_unit_chunks() -> Vec<u8> {
+    let mut chunks = (0x3000_u16 | (CHUNK_SIZE as u16 - 1))
+        .to_le_bytes()
+        .to_vec();
+    chunks.extend(stored_unit()[..CHUNK_SIZE].iter().rev());
+    chunks.extend([0x03, 0xB0, 0x00, b't', b'a', b'i']);
+    chunks
+}
+
+fn expected_contents() -> Vec<u8> {
+    let mut contents = abc(CHUNK_SIZE);
+    contents.extend(abc(CHUNK_SIZE));
+    contents.extend(vec![0; UNIT]);
+    contents.extend(stored_unit());
+    contents.extend(stored_unit()[..CHUNK_SIZE].iter().rev());
+    contents.extend(b"tai");
+    contents.resize(FILE_SIZE, 0);
+    contents
+}
+
+/// `COMP.BIN` (record 6) is a compressed stream of four 8 KiB units:
+///
+/// 0. compressed, one cluster at LCN 60;
+/// 1. sparse;
+/// 2. stored uncompressed at LCN 64..79;
+/// 3. compressed, nine clusters at LCN 80..88.
+fn compressed_image(compression_unit: u8) -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 16, 0);
+
+    let name = file_name_value(
+        5,
+        "COMP.BIN",
+        attr::FILE_NAME_POSIX,
+        0x820, // archive, compressed
+        FILE_SIZE as u64,
+    );
+    put_record(&mut image, 5, &file_record(3, &[index_root(&[(6, name)])]));
+
+    let runs = [
+        0x11, 0x01, 0x3C, // 1 cluster at LCN 60
+        0x01, 0x1F, // 31 sparse clusters
+        0x11, 0x10, 0x04, // 16 clusters at LCN 64
+        0x11, 0x09, 0x10, // 9 clusters at LCN 80
+        0x01, 0x07, // 7 sparse clusters
+    ];
+    let comp = compressed_attr(63, FILE_SIZE as u64, compression_unit, &runs);
+    put_record(&mut image, 6, &file_record(1, &[comp]));
+
+    let mut unit0 = abc_chunk();
+    unit0.extend(abc_chunk());
+    put_at(&mut image, 60, &unit0);
+    put_at(&mut image, 64, &stored_unit());
+    put_at(&mut image, 80, &mixed_unit_chunks());
+
+    image
+}
+
+#[test]
+fn compressed_file_reads_every_unit_kind() {
+    let fs = NtfsFs::open(Cursor::new(compressed_image(4))).unwrap();
+    let entry = fs.open_path("COMP.BIN").unwrap();
+    let mut reader = fs.read_file(&entry).unwrap();
+    assert_eq!(reader.size(), FILE_SIZE as u64);
+    assert_eq!(reader.read_to_vec().unwrap(), expected_contents());
+}
+
+#[test]
+fn reads_across_unit_boundaries_in_small_pieces() {
+    let fs = NtfsFs::open(Cursor::new(compressed_image(4))).unwrap();
+    let entry = fs.open_path("COMP.BIN").unwrap();
+    let mut reader = fs.read_file(&entry).unwrap();
+    let mut contents = Vec::new();
+    let mut buf = [0_u8; 1000];
+    loop {
+        let n = reader.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        contents.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(contents, expected_contents());
+}
+
+#[test]
+fn seeks_read_from_any_unit() {
+    let fs = NtfsFs::open(Cursor::new(compressed_image(4))).unwrap();
+    let entry = fs.open_path("COMP.BIN").unwrap();
+    let mut reader = fs.read_file(&entry).unwrap();
+    let expected = expected_contents();
+    let mut buf = [0_u8; 16];
+
+    for target in [3 * UNIT + CHUNK_SIZE - 8, UNIT + 5, 2 * UNIT - 8, 7] {
+        reader.seek(NtfsSeekFrom::Start(target as u64)).unwrap();
+        reader.read(&mut buf).unwrap();
+        assert_eq!(buf, expected[target..target + 16], "offset {target}");
+    }
+
+    let end = reader.seek(NtfsSeekFrom::End(-4)).unwrap();
+    assert_eq!(end, FILE_SIZE as u64 - 4);
+    assert_eq!(reader.read(&mut buf).unwrap(), 4);
+    let back = reader.seek(NtfsSeekFrom::Current(-(UNIT as i64))).unwrap();
+    assert_eq!(back, (FILE_SIZE - UNIT) as u64);
+    assert!(
+        reader
+            .seek(NtfsSeekFrom::Current(-(FILE_SIZE as i64)))
+            .is_err()
+    );
+}
+
+/// Counts the bytes read from the image.
+struct CountingReader {
+    inner: Cursor<Vec<u8>>,
+    bytes_read: Rc<Cell<usize>>,
+}
+
+impl Read for CountingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = Read::read(&mut self.inner, buf)?;
+        self.bytes_read.set(self.bytes_read.get() + n);
+        Ok(n)
+    }
+}
+
+impl Seek for CountingReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn seeking_decodes_only_the_unit_holding_the_offset() {
+    let bytes_read = Rc::new(Cell::new(0));
+    let device = CountingReader {
+        inner: Cursor::new(compressed_image(4)),
+        bytes_read: bytes_read.clone(),
+    };
+    let fs = NtfsFs::open(device).unwrap();
+    let entry = fs.open_path("COMP.BIN").unwrap();
+    let mut reader = fs.read_file(&entry).unwrap();
+
+    bytes_read.set(0);
+    reader
+        .seek(NtfsSeekFrom::Start(3 * UNIT as u64 + 10))
+        .unwrap();
+    let mut buf = [0_u8; 4];
+    reader.read(&mut buf).unwrap();
+    assert_eq!(bytes_read.get(), 9 * SECTOR);
+
+    // The decoded unit is reused for further reads inside it
+    reader.read(&mut buf).unwrap();
+    assert_eq!(bytes_read.get(), 9 * SECTOR);
+}
+
+#[test]
+fn compressed_stream_without_a_unit_size_is_rejected() {
+    let fs = NtfsFs::open(Cursor::new(compressed_image(0x40))).unwrap();
+    let entry = fs.open_path("COMP.BIN").unwrap();
+    assert!(matches!(
+        fs.read_file(&entry),
+        Err(NtfsError::UnsupportedCompression)
+    ));
+}
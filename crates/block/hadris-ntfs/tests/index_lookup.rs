@@ -0,0 +1,204 @@
+//! Keyed `$I30` B-tree lookup on a crafted image whose root directory holds
+//! its names in three index blocks below the index root.
+
+use std::io::Cursor;
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr::{self, parse_index_node};
+use hadris_ntfs::sync::{NtfsFs, NtfsFsReadExt};
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{
+    I30, REC, end_entry, file_name_value, file_record, index_node, index_root_attr, indx_block,
+    non_resident_attr, put_at, put_record, put_upcase_table, resident_attr, volume,
+};
+
+const INDEX_LCN: usize = 40; // three index blocks, VCNs 0, 2, and 4
+const UPCASE_LCN: u16 = 200;
+const IMAGE_LEN: usize = 262144;
+
+/// Names in collation order, with their MFT records and the index node
+/// holding them (`None` for the index root).
+const FILES: &[(&str, u64, u8, Option<u64>)] = &[
+    ("A.TXT", 7, attr::FILE_NAME_WIN32, Some(0)),
+    ("B.TXT", 8, attr::FILE_NAME_WIN32, Some(0)),
+    ("LONGNA~1.TXT", 15, attr::FILE_NAME_DOS, Some(0)),
+    ("M.TXT", 6, attr::FILE_NAME_WIN32, None),
+    ("N.TXT", 14, attr::FILE_NAME_WIN32, Some(2)),
+    ("P.TXT", 11, attr::FILE_NAME_WIN32, Some(2)),
+    ("README", 9, attr::FILE_NAME_POSIX, None),
+    ("readme", 12, attr::FILE_NAME_POSIX, Some(4)),
+    ("Z.TXT", 13, attr::FILE_NAME_WIN32, Some(4)),
+];
+
+/// An index entry for `name`; entries of a node with children carry the
+/// VCN of the child sorting before them.
+fn index_entry(mft_ref: u64, name: &str, namespace: u8, subnode: Option<u64>) -> Vec<u8> {
+    let size = name.len() as u64;
+    let content = file_name_value(5, name, namespace, 0x20, size);
+    image::index_entry(mft_ref, &content, subnode)
+}
+
+fn put_block(image: &mut [u8], vcn: u64, block: &[u8]) {
+    put_at(image, INDEX_LCN + vcn as usize, block);
+}
+
+/// Entries of the node at `node`, each root entry pointing at the leaf
+/// that sorts before it.
+fn node_entries(node: Option<u64>) -> Vec<u8> {
+    let mut entries = Vec::new();
+    let mut leaf = 0;
+    for &(name, mft_ref, namespace, holder) in FILES {
+        if holder != node {
+            continue;
+        }
+        let subnode = node.is_none().then_some(leaf);
+        entries.extend(index_entry(mft_ref, name, namespace, subnode));
+        leaf += 2;
+    }
+    entries
+}
+
+fn leaf_block(vcn: u64, end: Option<u64>) -> Vec<u8> {
+    let mut entries = node_entries(Some(vcn));
+    entries.extend(end_entry(end));
+    // Entries follow the update sequence array at 0x28.
+    indx_block(vcn, &index_node(&entries, 0x28, end.is_some()))
+}
+
+fn tree_image() -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 16, 0);
+    put_upcase_table(&mut image, UPCASE_LCN);
+
+    let mut root_entries = node_entries(None);
+    root_entries.extend(end_entry(Some(4)));
+    let root = [
+        index_root_attr(&index_node(&root_entries, 16, true)),
+        non_resident_attr(
+            attr::ATTR_INDEX_ALLOCATION,
+            Some(I30),
+            5,
+            3 * REC as u64,
+            &[0x11, 0x06, INDEX_LCN as u8],
+        ),
+        resident_attr(attr::ATTR_BITMAP, Some(I30), &[0b111, 0, 0, 0, 0, 0, 0, 0]),
+    ];
+    put_record(&mut image, 5, &file_record(3, &root));
+    for vcn in [0, 2, 4] {
+        put_block(&mut image, vcn, &leaf_block(vcn, None));
+    }
+
+    for &(name, mft_ref, _, _) in FILES {
+        let data = resident_attr(attr::ATTR_DATA, None, name.as_bytes());
+        put_record(&mut image, mft_ref as usize, &file_record(1, &[data]));
+    }
+
+    image
+}
+
+fn read_path(fs: &NtfsFs<Cursor<Vec<u8>>>, path: &str) -> Result<Vec<u8>, NtfsError> {
+    let entry = fs.open_path(path)?;
+    fs.read_file(&entry)?.read_to_vec()
+}
+
+#[test]
+fn index_nodes_expose_child_pointers() {
+    let mut entries = node_entries(None);
+    entries.extend(end_entry(Some(4)));
+    let node = parse_index_node(&index_node(&entries, 16, true), 0).unwrap();
+
+    assert!(node.has_children);
+    let keys: Vec<_> = node
+        .entries
+        .iter()
+        .map(|e| (e.file_name.name.as_str(), e.subnode_vcn))
+        .collect();
+    assert_eq!(keys, [("M.TXT", Some(0)), ("README", Some(2))]);
+    assert_eq!(node.end_subnode_vcn, Some(4));
+
+    // The child VCN must not overlap the key.
+    let mut short = index_entry(6, "M.TXT", attr::FILE_NAME_WIN32, Some(0));
+    let len = short.len() as u16 - 8;
+    short[8..10].copy_from_slice(&len.to_le_bytes());
+    short.truncate(len as usize);
+    short.extend(end_entry(None));
+    assert!(matches!(
+        parse_index_node(&index_node(&short, 16, true), 0),
+        Err(NtfsError::InvalidIndexEntry)
+    ));
+}
+
+#[test]
+fn keyed_lookup_reaches_every_node() {
+    let fs = NtfsFs::open(Cursor::new(tree_image())).unwrap();
+    for &(name, mft_ref, _, _) in FILES {
+        let entry = fs.root_dir().find(name).unwrap().unwrap();
+        assert_eq!(entry.mft_index(), mft_ref, "{name}");
+        assert_eq!(read_path(&fs, name).unwrap(), name.as_bytes());
+    }
+    let listed = FILES
+        .iter()
+        .filter(|&&(_, _, namespace, _)| namespace != attr::FILE_NAME_DOS)
+        .count();
+    assert_eq!(fs.root_dir().entries().unwrap().len(), listed);
+    assert_eq!(read_path(&fs, "/longna~1.txt").unwrap(), b"LONGNA~1.TXT");
+
+    assert_eq!(read_path(&fs, "/p.txt").unwrap(), b"P.TXT");
+    assert!(fs.root_dir().find("C.TXT").unwrap().is_none());
+    assert!(fs.root_dir().find("ZZ.TXT").unwrap().is_none());
+    assert!(matches!(
+        fs.open_path("O.TXT"),
+        Err(NtfsError::EntryNotFound)
+    ));
+}
+
+#[test]
+fn keyed_lookup_reads_only_the_blocks_on_its_path() {
+    let mut image = tree_image();
+    put_block(&mut image, 2, &[0_u8; REC]);
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+
+    assert!(matches!(
+        fs.root_dir().entries(),
+        Err(NtfsError::InvalidIndexMagic)
+    ));
+    assert_eq!(read_path(&fs, "A.TXT").unwrap(), b"A.TXT");
+    assert_eq!(read_path(&fs, "M.TXT").unwrap(), b"M.TXT");
+    assert_eq!(read_path(&fs, "Z.TXT").unwrap(), b"Z.TXT");
+    assert!(matches!(
+        fs.open_path("N.TXT"),
+        Err(NtfsError::InvalidIndexMagic)
+    ));
+}
+
+#[test]
+fn posix_names_differing_in_case_are_told_apart() {
+    let fs = NtfsFs::open(Cursor::new(tree_image())).unwrap();
+    let root = fs.root_dir();
+    assert_eq!(root.find("README").unwrap().unwrap().mft_index(), 9);
+    assert_eq!(root.find("readme").unwrap().unwrap().mft_index(), 12);
+    assert!(root.find("ReadMe").unwrap().is_none());
+}
+
+#[test]
+fn corrupt_child_pointers_are_rejected() {
+    // A leaf pointing back at itself
+    let mut image = tree_image();
+    put_block(&mut image, 4, &leaf_block(4, Some(4)));
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert!(matches!(
+        fs.root_dir().find("ZZ.TXT"),
+        Err(NtfsError::InvalidIndexEntry)
+    ));
+
+    // A child past the end of $INDEX_ALLOCATION
+    let mut image = tree_image();
+    put_block(&mut image, 4, &leaf_block(4, Some(6)));
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert!(matches!(
+        fs.root_dir().find("ZZ.TXT"),
+        Err(NtfsError::InvalidIndexEntry)
+    ));
+}
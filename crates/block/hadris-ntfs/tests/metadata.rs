@@ -0,0 +1,289 @@
+//! Named data streams and file metadata on a crafted image: a file with two
+//! hard links, a DOS alias, an object ID, and alternate data streams.
+
+use std::io::Cursor;
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr::{
+    self, FILE_ATTRIBUTE_ARCHIVE, FILE_ATTRIBUTE_HIDDEN, FileTimes, parse_object_id,
+    parse_standard_information,
+};
+use hadris_ntfs::sync::{NtfsFs, NtfsFsReadExt};
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{
+    file_record, index_root, linked_record, non_resident_attr, put_at, put_record, resident_attr,
+    utf16le, volume,
+};
+
+const IMAGE_LEN: usize = 65536;
+
+const SI_TIMES: FileTimes = FileTimes {
+    creation: 0x01DA_0000_0000_0001,
+    modification: 0x01DA_0000_0000_0002,
+    mft_modification: 0x01DA_0000_0000_0003,
+    access: 0x01DA_0000_0000_0004,
+};
+const FN_TIMES: FileTimes = FileTimes {
+    creation: 0x01D9_0000_0000_0001,
+    modification: 0x01D9_0000_0000_0002,
+    mft_modification: 0x01D9_0000_0000_0003,
+    access: 0x01D9_0000_0000_0004,
+};
+const ZONE_IDENTIFIER: &[u8] = b"[ZoneTransfer]\r\nZoneId=3\r\n";
+
+fn times_bytes(times: FileTimes) -> Vec<u8> {
+    [
+        times.creation,
+        times.modification,
+        times.mft_modification,
+        times.access,
+    ]
+    .iter()
+    .flat_map(|t| t.to_le_bytes())
+    .collect()
+}
+
+/// A `$FILE_NAME` value stamped with [`FN_TIMES`].
+fn file_name_value(parent: u64, name: &str, namespace: u8, flags: u32, size: u64) -> Vec<u8> {
+    let mut v = image::file_name_value(parent, name, namespace, flags, size);
+    v[0x08..0x28].copy_from_slice(&times_bytes(FN_TIMES));
+    v
+}
+
+fn standard_information() -> Vec<u8> {
+    let mut v = vec![0_u8; 0x48];
+    v[0..0x20].copy_from_slice(&times_bytes(SI_TIMES));
+    let flags = FILE_ATTRIBUTE_ARCHIVE | FILE_ATTRIBUTE_HIDDEN;
+    v[0x20..0x24].copy_from_slice(&flags.to_le_bytes());
+    v[0x30..0x34].copy_from_slice(&7_u32.to_le_bytes()); // owner
+    v[0x34..0x38].copy_from_slice(&0x102_u32.to_le_bytes()); // security
+    v[0x40..0x48].copy_from_slice(&0x4_0000_u64.to_le_bytes()); // USN
+    v
+}
+
+fn object_id() -> Vec<u8> {
+    (0..64).collect()
+}
+
+fn big_stream() -> Vec<u8> {
+    (0..1000_u32).map(|i| (i * 3 + i / 256) as u8).collect()
+}
+
+/// `Quarterly Report.txt` (record 6) sits in the root with a DOS alias and
+/// in `DIR` (record 7) as the POSIX hard link `report-link.txt`. Besides its
+/// unnamed data it has a resident `Zone.Identifier` stream and a
+/// non-resident `big` stream at LCN 60; `DIR` has a `tag` stream.
+fn metadata_image() -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 16, 0);
+
+    let dir_flags = 0x1000_0000;
+    let root = index_root(&[
+        (
+            6,
+            file_name_value(5, "Quarterly Report.txt", attr::FILE_NAME_WIN32, 0x22, 17),
+        ),
+        (
+            6,
+            file_name_value(5, "QUARTE~1.TXT", attr::FILE_NAME_DOS, 0x22, 17),
+        ),
+        (
+            7,
+            file_name_value(5, "DIR", attr::FILE_NAME_WIN32_AND_DOS, dir_flags, 0),
+        ),
+    ]);
+    put_record(&mut image, 5, &file_record(3, &[root]));
+
+    let dir_root = index_root(&[(
+        6,
+        file_name_value(7, "report-link.txt", attr::FILE_NAME_POSIX, 0x22, 17),
+    )]);
+    let dir_name = file_name_value(5, "DIR", attr::FILE_NAME_WIN32_AND_DOS, dir_flags, 0);
+    let dir_attrs = [
+        resident_attr(attr::ATTR_FILE_NAME, None, &dir_name),
+        resident_attr(attr::ATTR_DATA, Some(&utf16le("tag")), b"blue"),
+        dir_root,
+    ];
+    put_record(&mut image, 7, &file_record(3, &dir_attrs));
+
+    let file_attrs = [
+        resident_attr(
+            attr::ATTR_STANDARD_INFORMATION,
+            None,
+            &standard_information(),
+        ),
+        resident_attr(
+            attr::ATTR_FILE_NAME,
+            None,
+            &file_name_value(5, "Quarterly Report.txt", attr::FILE_NAME_WIN32, 0x22, 17),
+        ),
+        resident_attr(
+            attr::ATTR_FILE_NAME,
+            None,
+            &file_name_value(5, "QUARTE~1.TXT", attr::FILE_NAME_DOS, 0x22, 17),
+        ),
+        resident_attr(
+            attr::ATTR_FILE_NAME,
+            None,
+            &file_name_value(7, "report-link.txt", attr::FILE_NAME_POSIX, 0x22, 17),
+        ),
+        resident_attr(attr::ATTR_OBJECT_ID, None, &object_id()),
+        resident_attr(attr::ATTR_DATA, None, b"quarterly numbers"),
+        resident_attr(
+            attr::ATTR_DATA,
+            Some(&utf16le("Zone.Identifier")),
+            ZONE_IDENTIFIER,
+        ),
+        non_resident_attr(
+            attr::ATTR_DATA,
+            Some(&utf16le("big")),
+            1,
+            1000,
+            &[0x11, 0x02, 0x3C],
+        ),
+    ];
+    put_record(&mut image, 6, &linked_record(1, 2, &file_attrs));
+    put_at(&mut image, 60, &big_stream());
+
+    image
+}
+
+#[test]
+fn standard_information_exposes_times_and_flags() {
+    let fs = NtfsFs::open(Cursor::new(metadata_image())).unwrap();
+    let entry = fs.open_path("Quarterly Report.txt").unwrap();
+    assert_eq!(entry.file_attributes(), 0x22);
+
+    let metadata = fs.metadata(&entry).unwrap();
+    assert_eq!(metadata.mft_index(), 6);
+    assert_eq!(metadata.times(), Some(SI_TIMES));
+    assert_eq!(
+        metadata.file_attributes(),
+        FILE_ATTRIBUTE_ARCHIVE | FILE_ATTRIBUTE_HIDDEN
+    );
+    let info = metadata.standard_information().unwrap();
+    assert_eq!(info.owner_id, Some(7));
+    assert_eq!(info.security_id, Some(0x102));
+    assert_eq!(info.usn, Some(0x4_0000));
+}
+
+#[test]
+fn standard_information_accepts_the_short_v1_layout() {
+    let info = parse_standard_information(&standard_information()[..0x30]).unwrap();
+    assert_eq!(info.times, SI_TIMES);
+    assert_eq!(info.owner_id, None);
+    assert_eq!(info.security_id, None);
+    assert_eq!(info.usn, None);
+
+    assert!(matches!(
+        parse_standard_information(&[0; 0x2F]),
+        Err(NtfsError::InvalidAttribute)
+    ));
+}
+
+#[test]
+fn hard_links_name_every_parent_directory() {
+    let fs = NtfsFs::open(Cursor::new(metadata_image())).unwrap();
+    let entry = fs.open_path("DIR/report-link.txt").unwrap();
+    assert_eq!(entry.mft_index(), 6);
+
+    let metadata = fs.metadata(&entry).unwrap();
+    assert_eq!(metadata.link_count(), 2);
+    assert_eq!(metadata.names().len(), 3);
+    let links: Vec<_> = metadata
+        .hard_links()
+        .map(|name| (name.parent_ref & 0x0000_FFFF_FFFF_FFFF, name.name.as_str()))
+        .collect();
+    assert_eq!(links, [(5, "Quarterly Report.txt"), (7, "report-link.txt")]);
+    assert!(metadata.names().iter().all(|name| name.times == FN_TIMES));
+
+    // The DOS alias finds the same record
+    assert_eq!(fs.open_path("quarte~1.txt").unwrap().mft_index(), 6);
+}
+
+#[test]
+fn object_id_is_exposed() {
+    let fs = NtfsFs::open(Cursor::new(metadata_image())).unwrap();
+    let entry = fs.open_path("Quarterly Report.txt").unwrap();
+    let id = fs.metadata(&entry).unwrap().object_id().cloned().unwrap();
+    let guid = |index: u8| core::array::from_fn::<u8, 16, _>(|i| index * 16 + i as u8);
+    assert_eq!(id.object_id, guid(0));
+    assert_eq!(id.birth_volume_id, Some(guid(1)));
+    assert_eq!(id.birth_object_id, Some(guid(2)));
+    assert_eq!(id.domain_id, Some(guid(3)));
+}
+
+#[test]
+fn object_id_birth_fields_are_optional() {
+    let id = parse_object_id(&object_id()[..16]).unwrap();
+    assert_eq!(id.birth_volume_id, None);
+    assert_eq!(id.domain_id, None);
+    assert!(matches!(
+        parse_object_id(&[0; 15]),
+        Err(NtfsError::InvalidAttribute)
+    ));
+}
+
+#[test]
+fn named_streams_are_enumerated_and_opened() {
+    let fs = NtfsFs::open(Cursor::new(metadata_image())).unwrap();
+    let entry = fs.open_path("Quarterly Report.txt").unwrap();
+
+    let metadata = fs.metadata(&entry).unwrap();
+    let streams: Vec<_> = metadata
+        .streams()
+        .iter()
+        .map(|s| (s.name(), s.size(), s.is_resident()))
+        .collect();
+    assert_eq!(
+        streams,
+        [
+            ("", 17, true),
+            ("Zone.Identifier", ZONE_IDENTIFIER.len() as u64, true),
+            ("big", 1000, false),
+        ]
+    );
+    assert_eq!(metadata.streams()[2].allocated_size(), 1024);
+
+    let zone = fs.read_stream(&entry, "zone.identifier").unwrap();
+    assert_eq!(zone.size(), ZONE_IDENTIFIER.len() as u64);
+    assert_eq!(
+        fs.read_stream(&entry, "Zone.Identifier")
+            .unwrap()
+            .read_to_vec()
+            .unwrap(),
+        ZONE_IDENTIFIER
+    );
+    assert_eq!(
+        fs.read_stream(&entry, "big")
+            .unwrap()
+            .read_to_vec()
+            .unwrap(),
+        big_stream()
+    );
+    assert_eq!(
+        fs.read_stream(&entry, "").unwrap().read_to_vec().unwrap(),
+        b"quarterly numbers"
+    );
+    assert!(matches!(
+        fs.read_stream(&entry, "missing"),
+        Err(NtfsError::AttributeNotFound { .. })
+    ));
+}
+
+#[test]
+fn directories_can_carry_named_streams() {
+    let fs = NtfsFs::open(Cursor::new(metadata_image())).unwrap();
+    let dir = fs.open_path("DIR").unwrap();
+    assert!(matches!(fs.read_file(&dir), Err(NtfsError::NotAFile)));
+
+    let streams = fs.metadata(&dir).unwrap().streams().to_vec();
+    assert_eq!(streams.len(), 1);
+    assert_eq!(streams[0].name(), "tag");
+    assert_eq!(
+        fs.read_stream(&dir, "TAG").unwrap().read_to_vec().unwrap(),
+        b"blue"
+    );
+}
@@ -550,8 +550,7 @@ fn block_on<F: core::future::Future>(fut: F) -> F::Output {
 #[cfg(feature = "async")]
 #[test]
 fn poc_async_twin_mounts_walks_and_rejects_corruption() {
-    use hadris_ntfs::r#async::fs::NtfsFs as AsyncNtfsFs;
-    use hadris_ntfs::r#async::read::NtfsFsReadExt as _;
+    use hadris_ntfs::r#async::{NtfsFs as AsyncNtfsFs, NtfsFsReadExt as _};
 
     let image = base_image();
     block_on(async {
@@ -0,0 +1,336 @@
+//! Reparse points on a crafted image: a junction, Win32 symbolic links, and
+//! WSL symbolic links that lead to `docs/readme.txt`, plus links that loop
+//! or leave the volume.
+
+use std::io::Cursor;
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr::{
+    self, FILE_ATTRIBUTE_REPARSE_POINT, IO_REPARSE_TAG_LX_SYMLINK, IO_REPARSE_TAG_MOUNT_POINT,
+    IO_REPARSE_TAG_SYMLINK, ReparsePoint, SYMLINK_FLAG_RELATIVE, parse_reparse_point,
+};
+use hadris_ntfs::sync::{NtfsFs, NtfsFsReadExt};
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{
+    file_record, index_root, put_record, put_upcase_table, resident_attr, utf16le, volume,
+};
+
+const UPCASE_LCN: u16 = 200;
+const IMAGE_LEN: usize = 262144;
+
+const DIRECTORY: u32 = 0x1000_0000;
+const README: &[u8] = b"read me";
+
+/// A `$FILE_NAME` value; `reparse_tag` sets the reparse-point attribute
+/// and the tag field that follows the flags.
+fn file_name_value(parent: u64, name: &str, flags: u32, reparse_tag: Option<u32>) -> Vec<u8> {
+    let Some(tag) = reparse_tag else {
+        return image::file_name_value(parent, name, attr::FILE_NAME_WIN32, flags, 0);
+    };
+    let flags = flags | FILE_ATTRIBUTE_REPARSE_POINT;
+    let mut v = image::file_name_value(parent, name, attr::FILE_NAME_WIN32, flags, 0);
+    v[0x3C..0x40].copy_from_slice(&tag.to_le_bytes()); // follows the flags
+    v
+}
+
+/// Reparse data for a mount point (`flags: None`) or a symbolic link.
+fn name_pair_reparse(substitute: &str, print: &str, flags: Option<u32>) -> Vec<u8> {
+    let (substitute, print) = (utf16le(substitute), utf16le(print));
+    let mut body = Vec::new();
+    body.extend(0_u16.to_le_bytes());
+    body.extend((substitute.len() as u16).to_le_bytes());
+    body.extend((substitute.len() as u16).to_le_bytes());
+    body.extend((print.len() as u16).to_le_bytes());
+    let tag = match flags {
+        Some(flags) => {
+            body.extend(flags.to_le_bytes());
+            IO_REPARSE_TAG_SYMLINK
+        }
+        None => IO_REPARSE_TAG_MOUNT_POINT,
+    };
+    body.extend(substitute);
+    body.extend(print);
+    reparse_value(tag, &body)
+}
+
+fn lx_symlink_reparse(target: &str) -> Vec<u8> {
+    let mut body = 2_u32.to_le_bytes().to_vec(); // version
+    body.extend(target.as_bytes());
+    reparse_value(IO_REPARSE_TAG_LX_SYMLINK, &body)
+}
+
+fn reparse_value(tag: u32, body: &[u8]) -> Vec<u8> {
+    let mut v = tag.to_le_bytes().to_vec();
+    v.extend((body.len() as u16).to_le_bytes());
+    v.extend([0, 0]);
+    v.extend(body);
+    v
+}
+
+/// Root entries: name, MFT record, directory flag, and reparse data.
+fn root_links() -> Vec<(&'static str, u64, bool, Vec<u8>)> {
+    vec![
+        (
+            "junction",
+            7,
+            true,
+            name_pair_reparse("\\??\\C:\\docs", "C:\\docs", None),
+        ),
+        (
+            "rel-link",
+            8,
+            false,
+            name_pair_reparse(
+                "docs\\readme.txt",
+                "docs\\readme.txt",
+                Some(SYMLINK_FLAG_RELATIVE),
+            ),
+        ),
+        (
+            "abs-link",
+            9,
+            false,
+            name_pair_reparse(
+                "\\??\\D:\\docs\\readme.txt",
+                "D:\\docs\\readme.txt",
+                Some(0),
+            ),
+        ),
+        ("wsl-link", 11, false, lx_symlink_reparse("docs/readme.txt")),
+        (
+            "loop",
+            12,
+            false,
+            name_pair_reparse("loop", "loop", Some(SYMLINK_FLAG_RELATIVE)),
+        ),
+        (
+            "share",
+            13,
+            false,
+            name_pair_reparse("\\??\\UNC\\server\\share", "\\\\server\\share", Some(0)),
+        ),
+        (
+            "root-link",
+            4,
+            true,
+            name_pair_reparse("\\??\\C:\\", "C:\\", None),
+        ),
+    ]
+}
+
+/// `docs` (record 6) holds `readme.txt` (record 14) and the WSL link `up`
+/// (record 15) to `../docs/readme.txt`; the root holds [`root_links`].
+fn reparse_image() -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 16, 0);
+    put_upcase_table(&mut image, UPCASE_LCN);
+
+    let mut root_entries = vec![(6, file_name_value(5, "docs", DIRECTORY, None))];
+    for (name, record, is_dir, reparse) in root_links() {
+        let tag = u32::from_le_bytes(reparse[0..4].try_into().unwrap());
+        let flags = if is_dir { DIRECTORY } else { 0x20 };
+        root_entries.push((record, file_name_value(5, name, flags, Some(tag))));
+        let record_flags = if is_dir { 3 } else { 1 };
+        let attrs = [resident_attr(attr::ATTR_REPARSE_POINT, None, &reparse)];
+        put_record(
+            &mut image,
+            record as usize,
+            &file_record(record_flags, &attrs),
+        );
+    }
+    put_record(&mut image, 5, &file_record(3, &[index_root(&root_entries)]));
+
+    let up = lx_symlink_reparse("../docs/readme.txt");
+    let docs_root = index_root(&[
+        (14, file_name_value(6, "readme.txt", 0x20, None)),
+        (
+            15,
+            file_name_value(6, "up", 0x20, Some(IO_REPARSE_TAG_LX_SYMLINK)),
+        ),
+    ]);
+    put_record(&mut image, 6, &file_record(3, &[docs_root]));
+    put_record(
+        &mut image,
+        14,
+        &file_record(1, &[resident_attr(attr::ATTR_DATA, None, README)]),
+    );
+    put_record(
+        &mut image,
+        15,
+        &file_record(1, &[resident_attr(attr::ATTR_REPARSE_POINT, None, &up)]),
+    );
+
+    image
+}
+
+fn following_fs() -> NtfsFs<Cursor<Vec<u8>>> {
+    NtfsFs::builder(Cursor::new(reparse_image()))
+        .follow_reparse_points(true)
+        .open()
+        .unwrap()
+}
+
+#[test]
+fn entries_expose_reparse_tags() {
+    let fs = NtfsFs::open(Cursor::new(reparse_image())).unwrap();
+    let entries = fs.root_dir().entries().unwrap();
+    let tag_of = |name: &str| {
+        entries
+            .iter()
+            .find(|e| e.name() == name)
+            .unwrap()
+            .reparse_tag()
+    };
+    assert_eq!(tag_of("docs"), None);
+    assert_eq!(tag_of("junction"), Some(IO_REPARSE_TAG_MOUNT_POINT));
+    assert_eq!(tag_of("rel-link"), Some(IO_REPARSE_TAG_SYMLINK));
+    assert_eq!(tag_of("wsl-link"), Some(IO_REPARSE_TAG_LX_SYMLINK));
+
+    let junction = fs.open_path("junction").unwrap();
+    assert!(junction.is_directory());
+    assert_eq!(
+        fs.reparse_point(&junction).unwrap(),
+        Some(ReparsePoint::MountPoint {
+            substitute_name: "\\??\\C:\\docs".into(),
+            print_name: "C:\\docs".into(),
+        })
+    );
+    let docs = fs.open_path("docs").unwrap();
+    assert_eq!(fs.reparse_point(&docs).unwrap(), None);
+}
+
+#[test]
+fn symlink_and_wsl_targets_are_decoded() {
+    let fs = NtfsFs::open(Cursor::new(reparse_image())).unwrap();
+
+    let link = fs.open_path("rel-link").unwrap();
+    let point = fs.reparse_point(&link).unwrap().unwrap();
+    assert_eq!(point.tag(), IO_REPARSE_TAG_SYMLINK);
+    assert_eq!(
+        point,
+        ReparsePoint::Symlink {
+            substitute_name: "docs\\readme.txt".into(),
+            print_name: "docs\\readme.txt".into(),
+            relative: true,
+        }
+    );
+
+    let link = fs.open_path("share").unwrap();
+    assert!(matches!(
+        fs.reparse_point(&link).unwrap(),
+        Some(ReparsePoint::Symlink { relative: false, ref print_name, .. })
+            if print_name == "\\\\server\\share"
+    ));
+
+    let link = fs.open_path("docs/up").unwrap();
+    assert_eq!(
+        fs.reparse_point(&link).unwrap(),
+        Some(ReparsePoint::LxSymlink {
+            target: "../docs/readme.txt".into()
+        })
+    );
+}
+
+#[test]
+fn links_are_not_followed_by_default() {
+    let fs = NtfsFs::open(Cursor::new(reparse_image())).unwrap();
+    let link = fs.open_path("wsl-link").unwrap();
+    assert_eq!(link.mft_index(), 11);
+    assert!(matches!(
+        fs.open_path("junction/readme.txt"),
+        Err(NtfsError::EntryNotFound)
+    ));
+    assert_eq!(fs.open_path("share").unwrap().mft_index(), 13);
+}
+
+#[test]
+fn path_resolution_follows_links_within_the_volume() {
+    let fs = following_fs();
+    for path in [
+        "junction/readme.txt",
+        "JUNCTION\\README.TXT",
+        "rel-link",
+        "abs-link",
+        "wsl-link",
+        "docs/up",
+        "root-link/docs/readme.txt",
+        "root-link/junction/up",
+    ] {
+        let entry = fs.open_path(path).unwrap();
+        assert_eq!(entry.mft_index(), 14, "{path}");
+        let data = fs.read_file(&entry).unwrap().read_to_vec().unwrap();
+        assert_eq!(data, README, "{path}");
+    }
+
+    // Ordinary entries resolve as before.
+    assert!(fs.open_path("docs").unwrap().is_directory());
+    assert!(matches!(
+        fs.open_path("docs/readme.txt/x"),
+        Err(NtfsError::NotADirectory)
+    ));
+    // The root directory has no entry of its own.
+    assert!(matches!(
+        fs.open_path("root-link"),
+        Err(NtfsError::InvalidPath)
+    ));
+}
+
+#[test]
+fn link_loops_and_foreign_targets_are_rejected() {
+    let fs = following_fs();
+    assert!(matches!(
+        fs.open_path("loop"),
+        Err(NtfsError::TooManyReparseLinks)
+    ));
+    assert!(matches!(
+        fs.open_path("share"),
+        Err(NtfsError::ReparseTargetOutsideVolume)
+    ));
+}
+
+#[test]
+fn reparse_data_is_bounded_by_its_header() {
+    let value = name_pair_reparse("\\??\\C:\\docs", "C:\\docs", None);
+    assert!(parse_reparse_point(&value).is_ok());
+
+    // The declared data length runs past the value.
+    let mut long = value.clone();
+    long[4..6].copy_from_slice(&(value.len() as u16).to_le_bytes());
+    assert!(matches!(
+        parse_reparse_point(&long),
+        Err(NtfsError::InvalidReparsePoint)
+    ));
+
+    // The print name runs past the path buffer.
+    let mut bad_name = value.clone();
+    bad_name[8 + 6..8 + 8].copy_from_slice(&0x100_u16.to_le_bytes());
+    assert!(matches!(
+        parse_reparse_point(&bad_name),
+        Err(NtfsError::InvalidReparsePoint)
+    ));
+
+    assert!(matches!(
+        parse_reparse_point(&lx_symlink_reparse("x")[..9]),
+        Err(NtfsError::InvalidReparsePoint)
+    ));
+    assert!(matches!(
+        parse_reparse_point(&[0; 7]),
+        Err(NtfsError::InvalidReparsePoint)
+    ));
+
+    // Third-party tags carry a GUID before their data.
+    let mut third_party = 0x0000_1234_u32.to_le_bytes().to_vec();
+    third_party.extend(3_u16.to_le_bytes());
+    third_party.extend([0, 0]);
+    third_party.extend([0xEE; 16]);
+    third_party.extend([1, 2, 3]);
+    assert_eq!(
+        parse_reparse_point(&third_party).unwrap(),
+        ReparsePoint::Other {
+            tag: 0x1234,
+            data: vec![1, 2, 3],
+        }
+    );
+}
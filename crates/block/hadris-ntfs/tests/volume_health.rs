@@ -0,0 +1,210 @@
+//! `$MFTMirr` recovery and dirty-volume handling on a crafted image whose
+//! mirror holds copies of MFT records 0-3.
+
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr::{self, VOLUME_IS_DIRTY, parse_volume_information};
+use hadris_ntfs::sync::{DirtyVolumePolicy, NtfsFs, NtfsWarning};
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{
+    MFT_OFF, REC, SECTOR, boot_sector, file_name_value, file_record, index_root, put_record,
+    resident_attr, volume,
+};
+
+const MIRROR_LCN: u64 = 100;
+const MIRROR_OFF: usize = MIRROR_LCN as usize * SECTOR;
+const IMAGE_LEN: usize = 65536;
+
+fn hello_name() -> Vec<u8> {
+    file_name_value(5, "HELLO.TXT", attr::FILE_NAME_WIN32, 0x20, 5)
+}
+
+fn volume_information(flags: u16) -> Vec<u8> {
+    let mut v = vec![0_u8; 0x0C];
+    v[0x08] = 3;
+    v[0x09] = 1;
+    v[0x0A..0x0C].copy_from_slice(&flags.to_le_bytes());
+    v
+}
+
+/// `$MFT`, `$Volume`, a root holding `HELLO.TXT` (record 6), and a zero
+/// `$UpCase`; records 0-3 are copied to `$MFTMirr` at LCN 100.
+fn health_image(volume_flags: u16) -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 16, MIRROR_LCN);
+
+    let info = resident_attr(
+        attr::ATTR_VOLUME_INFORMATION,
+        None,
+        &volume_information(volume_flags),
+    );
+    put_record(&mut image, 3, &file_record(1, &[info]));
+
+    let root = index_root(&[(6, hello_name())]);
+    put_record(&mut image, 5, &file_record(3, &[root]));
+    let hello = [
+        resident_attr(attr::ATTR_FILE_NAME, None, &hello_name()),
+        resident_attr(attr::ATTR_DATA, None, b"hello"),
+    ];
+    put_record(&mut image, 6, &file_record(1, &hello));
+
+    image.copy_within(MFT_OFF..MFT_OFF + 4 * REC, MIRROR_OFF);
+    image
+}
+
+fn smash_magic(image: &mut [u8], off: usize) {
+    image[off..off + 4].copy_from_slice(b"BAAD");
+}
+
+/// Fails every read that touches `[start, end)`, like a disk with bad
+/// sectors.
+struct BadSectors {
+    inner: Cursor<Vec<u8>>,
+    start: u64,
+    end: u64,
+}
+
+impl Read for BadSectors {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let pos = self.inner.position();
+        if pos < self.end && pos + buf.len() as u64 > self.start {
+            return Err(io::Error::other("unreadable sector"));
+        }
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for BadSectors {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[test]
+fn damaged_mft_record_zero_is_read_from_the_mirror() {
+    let mut image = health_image(0);
+    smash_magic(&mut image, MFT_OFF);
+
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert_eq!(fs.warnings(), [NtfsWarning::MftMirrorUsed { index: 0 }]);
+    let entries = fs.root_dir().entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].name(), "HELLO.TXT");
+}
+
+#[test]
+fn unreadable_mft_sectors_are_read_from_the_mirror() {
+    let image = health_image(0);
+    let data = BadSectors {
+        inner: Cursor::new(image),
+        start: MFT_OFF as u64,
+        end: (MFT_OFF + 4 * REC) as u64,
+    };
+
+    let fs = NtfsFs::open(data).unwrap();
+    assert!(!fs.volume_information().unwrap().is_dirty());
+    assert_eq!(
+        fs.warnings(),
+        [
+            NtfsWarning::MftMirrorUsed { index: 0 },
+            NtfsWarning::MftMirrorUsed { index: 3 },
+        ]
+    );
+}
+
+#[test]
+fn torn_volume_record_is_read_from_the_mirror() {
+    let mut image = health_image(VOLUME_IS_DIRTY);
+    // Break the update sequence at the end of the record's second sector.
+    let tail = MFT_OFF + 3 * REC + 2 * SECTOR - 2;
+    image[tail] ^= 0xFF;
+
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert!(fs.warnings().is_empty());
+    let info = fs.volume_information().unwrap();
+    assert!(info.is_dirty());
+    assert_eq!(fs.warnings(), [NtfsWarning::MftMirrorUsed { index: 3 }]);
+
+    // Each recovered record is reported once.
+    fs.volume_information().unwrap();
+    assert_eq!(fs.warnings().len(), 1);
+}
+
+#[test]
+fn mirror_recovery_is_limited_to_the_first_four_records() {
+    let mut image = health_image(0);
+    smash_magic(&mut image, MFT_OFF + 5 * REC);
+    let fs = NtfsFs::open(Cursor::new(image)).unwrap();
+    assert!(matches!(
+        fs.root_dir().entries(),
+        Err(NtfsError::InvalidMftMagic)
+    ));
+    assert!(fs.warnings().is_empty());
+
+    // A damaged mirror copy leaves the primary error in place.
+    let mut image = health_image(0);
+    smash_magic(&mut image, MFT_OFF);
+    image[MIRROR_OFF..MIRROR_OFF + 4].copy_from_slice(b"MIRR");
+    assert!(matches!(
+        NtfsFs::open(Cursor::new(image)),
+        Err(NtfsError::InvalidMftMagic)
+    ));
+
+    // So does a mirror that runs past the end of the data source.
+    let mut image = health_image(0);
+    smash_magic(&mut image, MFT_OFF);
+    image[..SECTOR].copy_from_slice(&boot_sector(126));
+    assert!(matches!(
+        NtfsFs::open(Cursor::new(image)),
+        Err(NtfsError::InvalidMftMagic)
+    ));
+}
+
+#[test]
+fn volume_information_requires_the_flags_field() {
+    let info = parse_volume_information(&volume_information(VOLUME_IS_DIRTY)).unwrap();
+    assert_eq!((info.major_version, info.minor_version), (3, 1));
+    assert!(info.is_dirty());
+    assert!(
+        !parse_volume_information(&volume_information(0x0004))
+            .unwrap()
+            .is_dirty()
+    );
+
+    assert!(matches!(
+        parse_volume_information(&[0; 0x0B]),
+        Err(NtfsError::InvalidAttribute)
+    ));
+}
+
+#[test]
+fn dirty_volume_policy_controls_open() {
+    let dirty = health_image(VOLUME_IS_DIRTY);
+
+    let fs = NtfsFs::open(Cursor::new(dirty.clone())).unwrap();
+    assert!(fs.warnings().is_empty());
+    assert!(fs.volume_information().unwrap().is_dirty());
+
+    let fs = NtfsFs::builder(Cursor::new(dirty.clone()))
+        .dirty_volume(DirtyVolumePolicy::Warn)
+        .open()
+        .unwrap();
+    assert_eq!(fs.warnings(), [NtfsWarning::DirtyVolume]);
+
+    assert!(matches!(
+        NtfsFs::builder(Cursor::new(dirty))
+            .dirty_volume(DirtyVolumePolicy::Refuse)
+            .open(),
+        Err(NtfsError::DirtyVolume)
+    ));
+
+    let fs = NtfsFs::builder(Cursor::new(health_image(0)))
+        .dirty_volume(DirtyVolumePolicy::Refuse)
+        .open()
+        .unwrap();
+    assert!(fs.warnings().is_empty());
+    assert_eq!(fs.root_dir().entries().unwrap().len(), 1);
+}
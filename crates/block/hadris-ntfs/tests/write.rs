@@ -0,0 +1,289 @@
+//! In-place writes to non-resident file data on a crafted image whose
+//! user files start at MFT record 16.
+
+#![cfg(feature = "write")]
+
+use std::io::Cursor;
+
+use hadris_ntfs::NtfsError;
+use hadris_ntfs::attr::{self, apply_fixups, protect_fixups};
+use hadris_ntfs::sync::{NtfsEntry, NtfsFs, NtfsFsReadExt, NtfsFsWriteExt};
+use hadris_ntfs::time::StaticTimeProvider;
+
+#[path = "common/image.rs"]
+mod image;
+
+use image::{
+    SECTOR, file_name_value, file_record, index_root, non_resident_attr, put_record, resident_attr,
+    volume,
+};
+
+const IMAGE_LEN: usize = 262144;
+
+const DATA_LCN: usize = 100;
+const SPARSE_LCN: usize = 110;
+const STALE: u8 = 0xEE;
+const NOW: u64 = 133_000_000_000_000_000;
+
+static CLOCK: StaticTimeProvider = StaticTimeProvider(NOW);
+
+/// A non-resident unnamed `$DATA` with its own initialized size and flags.
+fn data_attr(
+    last_vcn: u64,
+    data_size: u64,
+    initialized_size: u64,
+    runs: &[u8],
+    flags: u16,
+) -> Vec<u8> {
+    let mut a = non_resident_attr(attr::ATTR_DATA, None, last_vcn, data_size, runs);
+    a[0x0C..0x0E].copy_from_slice(&flags.to_le_bytes());
+    if flags & attr::ATTR_FLAG_COMPRESSED != 0 {
+        a[0x22] = 4;
+    }
+    a[0x38..0x40].copy_from_slice(&initialized_size.to_le_bytes());
+    a
+}
+
+fn standard_information() -> Vec<u8> {
+    let mut v = vec![0_u8; 0x48];
+    v[0x20..0x24].copy_from_slice(&0x20_u32.to_le_bytes());
+    v
+}
+
+fn win32_name(name: &str) -> Vec<u8> {
+    file_name_value(5, name, attr::FILE_NAME_WIN32, 0x20, 0)
+}
+
+fn user_file(name: &str, data: Vec<u8>) -> Vec<u8> {
+    file_record(
+        1,
+        &[
+            resident_attr(
+                attr::ATTR_STANDARD_INFORMATION,
+                None,
+                &standard_information(),
+            ),
+            resident_attr(attr::ATTR_FILE_NAME, None, &win32_name(name)),
+            data,
+        ],
+    )
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+/// A 32-record `$MFT`, a zero `$UpCase`, and a root holding:
+///
+/// - `DATA.BIN` (record 16): four clusters at LCN 100, 1000 bytes of data of
+///   which 600 are initialized; the rest of the clusters hold stale bytes.
+/// - `SPARSE.BIN` (record 17): one cluster at LCN 110, then three sparse.
+/// - `SMALL.TXT` (record 18): resident data.
+/// - `PACKED.BIN` (record 19): a compressed stream.
+/// - `LOW.BIN` (record 7): non-resident data in a reserved record.
+fn write_image() -> Vec<u8> {
+    let mut image = volume(IMAGE_LEN, 32, 2);
+
+    let files = [
+        (16, "DATA.BIN"),
+        (17, "SPARSE.BIN"),
+        (18, "SMALL.TXT"),
+        (19, "PACKED.BIN"),
+        (7, "LOW.BIN"),
+    ];
+    let entries: Vec<_> = files
+        .iter()
+        .map(|&(index, name)| (index, win32_name(name)))
+        .collect();
+    put_record(&mut image, 5, &file_record(3, &[index_root(&entries)]));
+
+    let data_off = DATA_LCN * SECTOR;
+    image[data_off..data_off + 4 * SECTOR].fill(STALE);
+    image[data_off..data_off + 600].copy_from_slice(&pattern(600));
+    let data = data_attr(3, 1000, 600, &[0x11, 0x04, DATA_LCN as u8], 0);
+    put_record(&mut image, 16, &user_file("DATA.BIN", data));
+
+    let sparse_runs = [0x11, 0x01, SPARSE_LCN as u8, 0x01, 0x03];
+    let sparse = data_attr(3, 2048, 2048, &sparse_runs, attr::ATTR_FLAG_SPARSE);
+    put_record(&mut image, 17, &user_file("SPARSE.BIN", sparse));
+
+    let small = resident_attr(attr::ATTR_DATA, None, b"small");
+    put_record(&mut image, 18, &user_file("SMALL.TXT", small));
+
+    let packed = data_attr(
+        15,
+        8192,
+        8192,
+        &[0x11, 0x10, 0x78],
+        attr::ATTR_FLAG_COMPRESSED,
+    );
+    put_record(&mut image, 19, &user_file("PACKED.BIN", packed));
+
+    let low = data_attr(0, 512, 512, &[0x11, 0x01, 0x82], 0);
+    put_record(&mut image, 7, &user_file("LOW.BIN", low));
+
+    image
+}
+
+fn open(image: &mut Cursor<Vec<u8>>) -> NtfsFs<&mut Cursor<Vec<u8>>> {
+    NtfsFs::builder(image).time_provider(&CLOCK).open().unwrap()
+}
+
+fn entry<DATA: hadris_ntfs::sync::Read + hadris_ntfs::sync::Seek>(
+    fs: &NtfsFs<DATA>,
+    name: &str,
+) -> NtfsEntry {
+    fs.root_dir()
+        .entries()
+        .unwrap()
+        .into_iter()
+        .find(|e| e.name() == name)
+        .unwrap()
+}
+
+fn contents<DATA: hadris_ntfs::sync::Read + hadris_ntfs::sync::Seek>(
+    fs: &NtfsFs<DATA>,
+    entry: &NtfsEntry,
+) -> Vec<u8> {
+    fs.read_file(entry).unwrap().read_to_vec().unwrap()
+}
+
+#[test]
+fn overwrite_inside_initialized_data_stamps_times() {
+    let mut image = Cursor::new(write_image());
+    let fs = open(&mut image);
+    let file = entry(&fs, "DATA.BIN");
+
+    fs.write_at(&file, 100, b"written").unwrap();
+
+    let mut expected = pattern(600);
+    expected.resize(1000, 0);
+    expected[100..107].copy_from_slice(b"written");
+    assert_eq!(contents(&fs, &file), expected);
+
+    let meta = fs.metadata(&file).unwrap();
+    let times = meta.times().unwrap();
+    assert_eq!(times.creation, 0);
+    assert_eq!(times.modification, NOW);
+    assert_eq!(times.mft_modification, NOW);
+    assert_eq!(times.access, NOW);
+    assert_eq!(meta.streams()[0].size(), 1000);
+
+    // The record was re-protected with the next update sequence number.
+    let record = fs.read_mft_record(16).unwrap();
+    assert_eq!(&record[0x30..0x32], &0xAAAB_u16.to_le_bytes());
+}
+
+#[test]
+fn overwrite_past_initialized_size_zeroes_the_gap() {
+    let mut image = Cursor::new(write_image());
+    {
+        let fs = open(&mut image);
+        let file = entry(&fs, "DATA.BIN");
+
+        fs.write_at(&file, 800, b"xyz").unwrap();
+
+        let mut expected = pattern(600);
+        expected.resize(1000, 0);
+        expected[800..803].copy_from_slice(b"xyz");
+        assert_eq!(contents(&fs, &file), expected);
+    }
+
+    // The gap is zeroed on disk; bytes past the new initialized size are not
+    // touched.
+    let disk = &image.get_ref()[DATA_LCN * SECTOR..];
+    assert!(disk[600..800].iter().all(|&b| b == 0));
+    assert_eq!(&disk[800..803], b"xyz");
+    assert!(disk[803..4 * SECTOR].iter().all(|&b| b == STALE));
+}
+
+#[test]
+fn write_may_grow_the_stream_up_to_its_allocated_size() {
+    let mut image = Cursor::new(write_image());
+    let fs = open(&mut image);
+    let file = entry(&fs, "DATA.BIN");
+
+    let tail = vec![0x5A; 148];
+    fs.write_at(&file, 1900, &tail).unwrap();
+
+    let data = contents(&fs, &file);
+    assert_eq!(data.len(), 2048);
+    assert!(data[600..1900].iter().all(|&b| b == 0));
+    assert_eq!(&data[1900..], tail.as_slice());
+
+    assert!(matches!(
+        fs.write_at(&file, 2000, &[0; 49]),
+        Err(NtfsError::WriteRequiresAllocation)
+    ));
+}
+
+#[test]
+fn writes_that_need_allocation_change_nothing() {
+    let mut image = Cursor::new(write_image());
+    let before = image.get_ref().clone();
+    {
+        let fs = open(&mut image);
+        let file = entry(&fs, "SPARSE.BIN");
+
+        assert!(matches!(
+            fs.write_at(&file, 600, b"hole"),
+            Err(NtfsError::WriteRequiresAllocation)
+        ));
+        assert!(matches!(
+            fs.write_at(&file, 500, &[1; 20]),
+            Err(NtfsError::WriteRequiresAllocation)
+        ));
+        let data = entry(&fs, "DATA.BIN");
+        assert!(matches!(
+            fs.write_at(&data, 2048, b"x"),
+            Err(NtfsError::WriteRequiresAllocation)
+        ));
+    }
+    assert!(image.get_ref() == &before);
+
+    image.set_position(0);
+    let fs = open(&mut image);
+    let file = entry(&fs, "SPARSE.BIN");
+    fs.write_at(&file, 10, b"stored").unwrap();
+    assert_eq!(&contents(&fs, &file)[10..16], b"stored");
+}
+
+#[test]
+fn unsupported_streams_are_refused() {
+    let mut image = Cursor::new(write_image());
+    let fs = open(&mut image);
+
+    assert!(matches!(
+        fs.write_at(&entry(&fs, "SMALL.TXT"), 0, b"x"),
+        Err(NtfsError::UnsupportedWrite)
+    ));
+    assert!(matches!(
+        fs.write_at(&entry(&fs, "PACKED.BIN"), 0, b"x"),
+        Err(NtfsError::UnsupportedCompression)
+    ));
+    assert!(matches!(
+        fs.write_at(&entry(&fs, "LOW.BIN"), 0, b"x"),
+        Err(NtfsError::UnsupportedWrite)
+    ));
+}
+
+#[test]
+fn protected_records_round_trip_through_fixups() {
+    let original = file_record(1, &[]);
+    let mut record = original.clone();
+    apply_fixups(&mut record, SECTOR).unwrap();
+    let clean = record.clone();
+
+    protect_fixups(&mut record, SECTOR).unwrap();
+    assert_eq!(&record[0x30..0x32], &0xAAAB_u16.to_le_bytes());
+    assert_eq!(&record[SECTOR - 2..SECTOR], &0xAAAB_u16.to_le_bytes());
+    apply_fixups(&mut record, SECTOR).unwrap();
+    assert_eq!(&record[0x32..], &clean[0x32..]);
+
+    // The sequence number skips 0xFFFF and 0 when it wraps.
+    record[0x30..0x32].copy_from_slice(&0xFFFE_u16.to_le_bytes());
+    protect_fixups(&mut record, SECTOR).unwrap();
+    assert_eq!(&record[0x30..0x32], &1_u16.to_le_bytes());
+    apply_fixups(&mut record, SECTOR).unwrap();
+    assert_eq!(&record[0x32..], &clean[0x32..]);
+}
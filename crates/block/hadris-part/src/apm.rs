@@ -0,0 +1,525 @@
+//! Apple Partition Map (APM) types.
+//!
+//! APM is the partition scheme of classic Mac OS and PowerPC Macs, and the
+//! one older Intel Macs fall back to for optical and hybrid media. A disk
+//! starts with:
+//! - a Driver Descriptor Map (DDM) in block 0, carrying the `ER` signature
+//!   and the block size every other APM field is expressed in
+//! - one partition map entry per block from block 1 on, each carrying the
+//!   `PM` signature and the total number of map entries
+//!
+//! The first entry conventionally describes the map itself
+//! (`Apple_partition_map`). All multi-byte fields are big-endian.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use core::fmt::Debug;
+
+use endian_num::Be;
+
+#[cfg(feature = "alloc")]
+use crate::error::{Error, Result};
+
+/// Driver Descriptor Map, stored in block 0 of an APM disk (512 bytes).
+///
+/// The DDM shares block 0 with an MBR on hybrid media: its fields end well
+/// before the MBR partition table at byte 446.
+///
+/// @hadris-spec APM:Block0
+/// @hadris-compliance unknown
+/// @hadris-tests apm::tests::ddm_layout
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DriverDescriptorMap {
+    /// Signature: must be `ER`.
+    pub signature: [u8; 2],
+    /// Block size in bytes used by the partition map, big-endian on disk.
+    pub block_size: Be<u16>,
+    /// Number of blocks on the device, big-endian on disk.
+    pub block_count: Be<u32>,
+    /// Device type (reserved), big-endian on disk.
+    pub device_type: Be<u16>,
+    /// Device ID (reserved), big-endian on disk.
+    pub device_id: Be<u16>,
+    /// Reserved, big-endian on disk.
+    pub data: Be<u32>,
+    /// Number of driver descriptors that follow, big-endian on disk.
+    pub driver_count: Be<u16>,
+    /// Driver descriptor entries and padding, kept verbatim.
+    pub drivers: [u8; 494],
+}
+
+impl DriverDescriptorMap {
+    /// The required DDM signature.
+    pub const SIGNATURE: [u8; 2] = *b"ER";
+    /// Bytes occupied by the DDM fields before the driver descriptors.
+    pub const HEADER_SIZE: usize = 18;
+
+    /// Creates a DDM with no drivers.
+    pub fn new(block_size: u16, block_count: u32) -> Self {
+        Self {
+            signature: Self::SIGNATURE,
+            block_size: Be::<u16>::from_ne(block_size),
+            block_count: Be::<u32>::from_ne(block_count),
+            device_type: Be::<u16>::from_ne(0),
+            device_id: Be::<u16>::from_ne(0),
+            data: Be::<u32>::from_ne(0),
+            driver_count: Be::<u16>::from_ne(0),
+            drivers: [0; 494],
+        }
+    }
+
+    /// Returns whether the signature is valid.
+    pub const fn has_valid_signature(&self) -> bool {
+        self.signature[0] == Self::SIGNATURE[0] && self.signature[1] == Self::SIGNATURE[1]
+    }
+
+    /// Returns whether the block size is one APM readers accept: a power of
+    /// two from 512 to 4096 bytes.
+    pub fn has_valid_block_size(&self) -> bool {
+        let size = self.block_size.to_ne();
+        size.is_power_of_two() && (512..=4096).contains(&size)
+    }
+}
+
+impl Debug for DriverDescriptorMap {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DriverDescriptorMap")
+            .field("signature", &self.signature)
+            .field("block_size", &self.block_size.to_ne())
+            .field("block_count", &self.block_count.to_ne())
+            .field("driver_count", &self.driver_count.to_ne())
+            .finish()
+    }
+}
+
+/// An APM partition map entry (512 bytes, at the start of its block).
+///
+/// Block addresses and counts are in units of the DDM block size.
+///
+/// @hadris-spec APM:PartitionMapEntry
+/// @hadris-compliance unknown
+/// @hadris-tests apm::tests::entry_layout
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ApmEntry {
+    /// Signature: must be `PM`.
+    pub signature: [u8; 2],
+    /// Reserved, big-endian on disk.
+    pub reserved: Be<u16>,
+    /// Number of entries in the partition map, big-endian on disk.
+    pub map_block_count: Be<u32>,
+    /// First block of the partition, big-endian on disk.
+    pub start_block: Be<u32>,
+    /// Number of blocks in the partition, big-endian on disk.
+    pub block_count: Be<u32>,
+    /// NUL-padded partition name.
+    pub name: [u8; 32],
+    /// NUL-padded partition type, such as `Apple_HFS`.
+    pub partition_type: [u8; 32],
+    /// First data block relative to the partition start, big-endian on disk.
+    pub data_start: Be<u32>,
+    /// Number of data blocks, big-endian on disk.
+    pub data_count: Be<u32>,
+    /// Partition status flags (`STATUS_*`), big-endian on disk.
+    pub status: Be<u32>,
+    /// First boot code block relative to the partition start, big-endian on disk.
+    pub boot_start: Be<u32>,
+    /// Boot code size in bytes, big-endian on disk.
+    pub boot_size: Be<u32>,
+    /// Boot code load address, big-endian on disk.
+    pub boot_address: Be<u32>,
+    /// Reserved, big-endian on disk.
+    pub boot_address2: Be<u32>,
+    /// Boot code entry point, big-endian on disk.
+    pub boot_entry: Be<u32>,
+    /// Reserved, big-endian on disk.
+    pub boot_entry2: Be<u32>,
+    /// Boot code checksum, big-endian on disk.
+    pub boot_checksum: Be<u32>,
+    /// NUL-padded processor type, such as `powerpc`.
+    pub processor: [u8; 16],
+    /// Reserved padding to the end of the 512-byte entry.
+    pub padding: [u8; 376],
+}
+
+impl ApmEntry {
+    /// The required partition map entry signature.
+    pub const SIGNATURE: [u8; 2] = *b"PM";
+
+    /// Type of the entry describing the partition map itself.
+    pub const TYPE_PARTITION_MAP: &'static [u8] = b"Apple_partition_map";
+    /// Type of an unallocated region.
+    pub const TYPE_FREE: &'static [u8] = b"Apple_Free";
+    /// Type of an HFS/HFS+ volume; Intel Mac firmware also boots FAT EFI
+    /// images described with it.
+    pub const TYPE_HFS: &'static [u8] = b"Apple_HFS";
+    /// Type libisofs uses for ISO 9660 data on hybrid media.
+    pub const TYPE_ISO9660_DATA: &'static [u8] = b"ISO9660_data";
+
+    /// The entry is valid.
+    pub const STATUS_VALID: u32 = 0x01;
+    /// The partition is allocated.
+    pub const STATUS_ALLOCATED: u32 = 0x02;
+    /// The partition is in use.
+    pub const STATUS_IN_USE: u32 = 0x04;
+    /// The partition contains valid boot information.
+    pub const STATUS_BOOTABLE: u32 = 0x08;
+    /// The partition is readable.
+    pub const STATUS_READABLE: u32 = 0x10;
+    /// The partition is writable.
+    pub const STATUS_WRITABLE: u32 = 0x20;
+
+    /// Creates an entry for a readable, writable data partition.
+    ///
+    /// `name` and `partition_type` are truncated to 32 bytes. The map block
+    /// count is filled in when the entry is added to an [`ApmDisk`].
+    pub fn new(name: &[u8], partition_type: &[u8], start_block: u32, block_count: u32) -> Self {
+        let mut entry: Self = bytemuck::Zeroable::zeroed();
+        entry.signature = Self::SIGNATURE;
+        entry.start_block = Be::<u32>::from_ne(start_block);
+        entry.block_count = Be::<u32>::from_ne(block_count);
+        copy_padded(&mut entry.name, name);
+        copy_padded(&mut entry.partition_type, partition_type);
+        entry.data_count = Be::<u32>::from_ne(block_count);
+        entry.status = Be::<u32>::from_ne(
+            Self::STATUS_VALID
+                | Self::STATUS_ALLOCATED
+                | Self::STATUS_READABLE
+                | Self::STATUS_WRITABLE,
+        );
+        entry
+    }
+
+    /// Returns whether the signature is valid.
+    pub const fn has_valid_signature(&self) -> bool {
+        self.signature[0] == Self::SIGNATURE[0] && self.signature[1] == Self::SIGNATURE[1]
+    }
+
+    /// Returns the partition name without NUL padding.
+    pub fn name(&self) -> &[u8] {
+        trim_padded(&self.name)
+    }
+
+    /// Returns the partition type without NUL padding.
+    pub fn partition_type(&self) -> &[u8] {
+        trim_padded(&self.partition_type)
+    }
+
+    /// Returns whether this entry describes the partition map itself.
+    pub fn is_partition_map(&self) -> bool {
+        self.partition_type() == Self::TYPE_PARTITION_MAP
+    }
+
+    /// Returns whether this entry describes unallocated space.
+    pub fn is_free(&self) -> bool {
+        self.partition_type() == Self::TYPE_FREE
+    }
+
+    /// Returns the last block of the partition (inclusive), or `None` for an
+    /// empty or overflowing partition.
+    pub fn end_block(&self) -> Option<u64> {
+        let count = self.block_count.to_ne();
+        (count > 0).then(|| self.start_block.to_ne() as u64 + count as u64 - 1)
+    }
+}
+
+impl Debug for ApmEntry {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ApmEntry")
+            .field("name", &core::str::from_utf8(self.name()).unwrap_or("?"))
+            .field(
+                "partition_type",
+                &core::str::from_utf8(self.partition_type()).unwrap_or("?"),
+            )
+            .field("map_block_count", &self.map_block_count.to_ne())
+            .field("start_block", &self.start_block.to_ne())
+            .field("block_count", &self.block_count.to_ne())
+            .field("status", &format_args!("0x{:08X}", self.status.to_ne()))
+            .finish()
+    }
+}
+
+fn copy_padded(field: &mut [u8], value: &[u8]) {
+    let len = value.len().min(field.len());
+    field[..len].copy_from_slice(&value[..len]);
+}
+
+fn trim_padded(field: &[u8]) -> &[u8] {
+    let len = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    &field[..len]
+}
+
+/// A complete Apple Partition Map: the DDM and every map entry, starting
+/// with the entry that describes the map itself.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct ApmDisk {
+    /// The Driver Descriptor Map in block 0.
+    pub ddm: DriverDescriptorMap,
+    /// Partition map entries, in map order (entry `i` is in block `i + 1`).
+    pub entries: Vec<ApmEntry>,
+}
+
+#[cfg(feature = "alloc")]
+impl ApmDisk {
+    /// Number of blocks Mac OS reserves for a partition map by default.
+    pub const DEFAULT_MAP_BLOCKS: u32 = 63;
+
+    /// Creates a map holding only its own `Apple_partition_map` entry, which
+    /// reserves [`DEFAULT_MAP_BLOCKS`](Self::DEFAULT_MAP_BLOCKS) blocks.
+    pub fn new(block_size: u16, block_count: u32) -> Self {
+        let map = ApmEntry::new(
+            b"Apple",
+            ApmEntry::TYPE_PARTITION_MAP,
+            1,
+            Self::DEFAULT_MAP_BLOCKS,
+        );
+        let mut disk = Self {
+            ddm: DriverDescriptorMap::new(block_size, block_count),
+            entries: alloc::vec![map],
+        };
+        disk.update_map_block_counts();
+        disk
+    }
+
+    /// Sets how many blocks (and therefore entries) the map may occupy.
+    ///
+    /// Media with little room before their data, such as the 32 KiB ISO 9660
+    /// system area, reserve only the blocks they need.
+    pub fn with_map_blocks(mut self, blocks: u32) -> Self {
+        self.entries[0].block_count = Be::<u32>::from_ne(blocks);
+        self.entries[0].data_count = Be::<u32>::from_ne(blocks);
+        self
+    }
+
+    /// Returns the block size of the map in bytes.
+    pub fn block_size(&self) -> u32 {
+        self.ddm.block_size.to_ne() as u32
+    }
+
+    /// Returns the number of blocks reserved for the map.
+    pub fn map_blocks(&self) -> u32 {
+        self.entries
+            .first()
+            .filter(|entry| entry.is_partition_map())
+            .map(|entry| entry.block_count.to_ne())
+            .unwrap_or(self.entries.len() as u32)
+    }
+
+    /// Converts an entry's block range to the inclusive range of disk sectors
+    /// of `sector_size` bytes it covers.
+    ///
+    /// APM counts in the block size recorded in the DDM, which need not match
+    /// the disk's sector size (CD-ROM maps commonly use 2048-byte blocks).
+    /// Returns `None` for an empty entry or a zero sector size.
+    pub fn sector_range(&self, entry: &ApmEntry, sector_size: u32) -> Option<(u64, u64)> {
+        if sector_size == 0 {
+            return None;
+        }
+        let block_size = u64::from(self.block_size());
+        let sector_size = u64::from(sector_size);
+        let end = entry.end_block()?;
+        let start_byte = u64::from(entry.start_block.to_ne()).checked_mul(block_size)?;
+        let end_byte = (end + 1).checked_mul(block_size)?;
+        Some((start_byte / sector_size, (end_byte - 1) / sector_size))
+    }
+
+    /// Returns the number of partitions, excluding the map itself and free space.
+    pub fn partition_count(&self) -> usize {
+        self.partitions().count()
+    }
+
+    /// Returns an iterator over partitions, excluding the map itself and free
+    /// space, with their index in the map.
+    pub fn partitions(&self) -> impl Iterator<Item = (usize, &ApmEntry)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_partition_map() && !e.is_free())
+    }
+
+    /// Appends an entry to the map.
+    ///
+    /// Returns the index of the new entry, or an error if the map has no
+    /// free block left.
+    pub fn add_partition(&mut self, entry: ApmEntry) -> Result<usize> {
+        let max = self.map_blocks() as usize;
+        if self.entries.len() >= max {
+            return Err(Error::TooManyPartitions {
+                max,
+                requested: self.entries.len() + 1,
+            });
+        }
+        self.entries.push(entry);
+        self.update_map_block_counts();
+        Ok(self.entries.len() - 1)
+    }
+
+    /// Validates the DDM, the map entries, and that partitions neither
+    /// overlap nor extend past the device.
+    pub fn validate(&self) -> Result<()> {
+        if !self.ddm.has_valid_signature() {
+            return Err(Error::invalid_data(
+                "invalid Apple Partition Map DDM signature",
+            ));
+        }
+        if !self.ddm.has_valid_block_size() {
+            return Err(Error::InvalidBlockSize {
+                size: self.block_size(),
+                minimum: 512,
+            });
+        }
+        if self.entries.is_empty() || self.entries.len() > self.map_blocks() as usize {
+            return Err(Error::TooManyPartitions {
+                max: self.map_blocks() as usize,
+                requested: self.entries.len(),
+            });
+        }
+
+        let disk_blocks = self.ddm.block_count.to_ne() as u64;
+        for (index, entry) in self.entries.iter().enumerate() {
+            if !entry.has_valid_signature() {
+                return Err(Error::invalid_data(
+                    "invalid Apple Partition Map entry signature",
+                ));
+            }
+            if entry.map_block_count.to_ne() as usize != self.entries.len() {
+                return Err(Error::invalid_data(
+                    "Apple Partition Map entries disagree on the map size",
+                ));
+            }
+            if let Some(end) = entry.end_block()
+                && disk_blocks != 0
+                && end >= disk_blocks
+            {
+                return Err(Error::PartitionOutOfBounds {
+                    index,
+                    partition_end: end,
+                    disk_end: disk_blocks - 1,
+                });
+            }
+        }
+
+        let used: Vec<_> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_free())
+            .filter_map(|(i, e)| {
+                e.end_block()
+                    .map(|end| (i, e.start_block.to_ne() as u64, end))
+            })
+            .collect();
+        for (i, &(index1, start1, end1)) in used.iter().enumerate() {
+            for &(index2, start2, end2) in &used[i + 1..] {
+                if start1 <= end2 && start2 <= end1 {
+                    return Err(Error::PartitionOverlap {
+                        index1,
+                        index2,
+                        overlap_start: start1.max(start2),
+                        overlap_end: end1.min(end2),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_map_block_counts(&mut self) {
+        let count = Be::<u32>::from_ne(self.entries.len() as u32);
+        for entry in &mut self.entries {
+            entry.map_block_count = count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ddm_layout() {
+        assert_eq!(core::mem::size_of::<DriverDescriptorMap>(), 512);
+        let ddm = DriverDescriptorMap::new(2048, 0x0102_0304);
+        let bytes = bytemuck::bytes_of(&ddm);
+        assert_eq!(&bytes[..8], b"ER\x08\x00\x01\x02\x03\x04");
+        assert!(ddm.has_valid_signature());
+        assert!(ddm.has_valid_block_size());
+    }
+
+    #[test]
+    fn entry_layout() {
+        assert_eq!(core::mem::size_of::<ApmEntry>(), 512);
+        let entry = ApmEntry::new(b"EFI", ApmEntry::TYPE_HFS, 0x10, 0x20);
+        let bytes = bytemuck::bytes_of(&entry);
+        assert_eq!(&bytes[..2], b"PM");
+        assert_eq!(&bytes[8..16], &[0, 0, 0, 0x10, 0, 0, 0, 0x20]);
+        assert_eq!(&bytes[16..19], b"EFI");
+        assert_eq!(&bytes[48..57], b"Apple_HFS");
+        assert_eq!(entry.name(), b"EFI");
+        assert_eq!(entry.end_block(), Some(0x2F));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn map_tracks_entry_count() {
+        let mut disk = ApmDisk::new(512, 10_000).with_map_blocks(3);
+        assert_eq!(
+            disk.add_partition(ApmEntry::new(b"A", ApmEntry::TYPE_HFS, 4, 100))
+                .unwrap(),
+            1
+        );
+        disk.add_partition(ApmEntry::new(b"B", ApmEntry::TYPE_HFS, 104, 100))
+            .unwrap();
+        assert!(matches!(
+            disk.add_partition(ApmEntry::new(b"C", ApmEntry::TYPE_HFS, 204, 1)),
+            Err(Error::TooManyPartitions { max: 3, .. })
+        ));
+        assert!(disk.entries.iter().all(|e| e.map_block_count.to_ne() == 3));
+        assert_eq!(disk.partition_count(), 2);
+        disk.validate().unwrap();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn sector_range_converts_map_blocks() {
+        let disk = ApmDisk::new(2048, 1_000);
+        let entry = ApmEntry::new(b"A", ApmEntry::TYPE_HFS, 16, 4);
+        assert_eq!(disk.sector_range(&entry, 512), Some((64, 79)));
+        assert_eq!(disk.sector_range(&entry, 2048), Some((16, 19)));
+        assert_eq!(disk.sector_range(&entry, 4096), Some((8, 9)));
+        assert_eq!(disk.sector_range(&entry, 0), None);
+        let empty = ApmEntry::new(b"B", ApmEntry::TYPE_HFS, 16, 0);
+        assert_eq!(disk.sector_range(&empty, 512), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn validate_rejects_overlap_and_overflow() {
+        let mut disk = ApmDisk::new(512, 1_000).with_map_blocks(4);
+        disk.add_partition(ApmEntry::new(b"A", ApmEntry::TYPE_HFS, 2, 10))
+            .unwrap();
+        assert!(matches!(
+            disk.validate(),
+            Err(Error::PartitionOverlap {
+                index1: 0,
+                index2: 1,
+                ..
+            })
+        ));
+
+        let mut disk = ApmDisk::new(512, 1_000).with_map_blocks(4);
+        disk.add_partition(ApmEntry::new(b"A", ApmEntry::TYPE_HFS, 900, 200))
+            .unwrap();
+        assert!(matches!(
+            disk.validate(),
+            Err(Error::PartitionOutOfBounds { index: 1, .. })
+        ));
+    }
+}
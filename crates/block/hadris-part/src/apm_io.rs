@@ -0,0 +1,173 @@
+io_transform! {
+
+#[cfg(all(feature = "alloc", feature = "read"))]
+use super::super::Read;
+#[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
+use super::super::{Seek, SeekFrom};
+#[cfg(all(feature = "alloc", feature = "write"))]
+use super::super::Write;
+#[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
+use crate::apm::ApmDisk;
+#[cfg(all(feature = "alloc", feature = "read"))]
+use crate::apm::{ApmEntry, DriverDescriptorMap};
+#[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
+use crate::error::{Error, Result};
+
+/// Reads the 512-byte structure at `offset`.
+#[cfg(all(feature = "alloc", feature = "read"))]
+async fn read_block<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<[u8; 512]> {
+    let mut buf = [0u8; 512];
+    reader
+        .seek(SeekFrom::Start(offset))
+        .await
+        .map_err(Error::from)?;
+    reader.read_exact(&mut buf).await.map_err(Error::from)?;
+    Ok(buf)
+}
+
+/// Returns whether the disk starts with a DDM followed by a map entry.
+///
+/// Short or unreadable disks are reported as not carrying an APM.
+#[cfg(all(feature = "alloc", feature = "read"))]
+pub(crate) async fn probe<R: Read + Seek>(reader: &mut R) -> Result<bool> {
+    let Ok(block) = read_block(reader, 0).await else {
+        return Ok(false);
+    };
+    let ddm: DriverDescriptorMap = bytemuck::cast(block);
+    if !ddm.has_valid_signature() || !ddm.has_valid_block_size() {
+        return Ok(false);
+    }
+    let Ok(block) = read_block(reader, u64::from(ddm.block_size.to_ne())).await else {
+        return Ok(false);
+    };
+    let entry: ApmEntry = bytemuck::cast(block);
+    Ok(entry.has_valid_signature())
+}
+
+/// Extension trait for reading [`ApmDisk`] from I/O sources.
+#[cfg(all(feature = "alloc", feature = "read"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "read"))))]
+pub trait ApmDiskReadExt: Sized {
+    /// Reads an Apple Partition Map from the start of a disk.
+    ///
+    /// Reads the DDM in block 0, then every map entry announced by the
+    /// first entry, using the block size recorded in the DDM.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, a signature is invalid, or the map
+    /// does not fit the disk.
+    async fn read_from<R: Read + Seek>(reader: &mut R) -> Result<Self>;
+}
+
+#[cfg(all(feature = "alloc", feature = "read"))]
+impl ApmDiskReadExt for ApmDisk {
+    async fn read_from<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        let ddm: DriverDescriptorMap = bytemuck::cast(read_block(reader, 0).await?);
+        if !ddm.has_valid_signature() {
+            return Err(Error::invalid_data("invalid Apple Partition Map DDM signature"));
+        }
+        if !ddm.has_valid_block_size() {
+            return Err(Error::InvalidBlockSize {
+                size: u32::from(ddm.block_size.to_ne()),
+                minimum: 512,
+            });
+        }
+        let block_size = u64::from(ddm.block_size.to_ne());
+
+        let first: ApmEntry = bytemuck::cast(read_block(reader, block_size).await?);
+        if !first.has_valid_signature() {
+            return Err(Error::invalid_data(
+                "invalid Apple Partition Map entry signature",
+            ));
+        }
+
+        // `map_block_count` is an untrusted on-disk value; bound it against
+        // the image size before allocating.
+        let count = u64::from(first.map_block_count.to_ne());
+        let image_len = reader
+            .seek(SeekFrom::End(0))
+            .await
+            .map_err(Error::from)?;
+        let available = image_len / block_size;
+        if count == 0 {
+            return Err(Error::invalid_data("Apple Partition Map has no entries"));
+        }
+        if count >= available {
+            return Err(Error::DiskTooSmall {
+                required: count + 1,
+                available,
+            });
+        }
+
+        let mut entries = alloc::vec::Vec::with_capacity(count as usize);
+        entries.push(first);
+        for block in 2..=count {
+            let entry: ApmEntry = bytemuck::cast(read_block(reader, block * block_size).await?);
+            if !entry.has_valid_signature() {
+                return Err(Error::invalid_data(
+                    "invalid Apple Partition Map entry signature",
+                ));
+            }
+            entries.push(entry);
+        }
+
+        Ok(Self { ddm, entries })
+    }
+}
+
+/// Extension trait for writing [`ApmDisk`] to I/O sinks.
+#[cfg(all(feature = "alloc", feature = "write"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "write"))))]
+pub trait ApmDiskWriteExt {
+    /// Writes the DDM to block 0 and each map entry to the start of its
+    /// block.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    async fn write_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()>;
+
+    /// Writes only the map entries, leaving block 0 untouched.
+    ///
+    /// Media that share block 0 with an MBR place the DDM fields (the first
+    /// [`DriverDescriptorMap::HEADER_SIZE`](crate::apm::DriverDescriptorMap::HEADER_SIZE)
+    /// bytes) into the MBR bootstrap area themselves.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    async fn write_entries_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()>;
+}
+
+#[cfg(all(feature = "alloc", feature = "write"))]
+impl ApmDiskWriteExt for ApmDisk {
+    async fn write_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+        writer
+            .seek(SeekFrom::Start(0))
+            .await
+            .map_err(Error::from)?;
+        writer
+            .write_all(bytemuck::bytes_of(&self.ddm))
+            .await
+            .map_err(Error::from)?;
+        self.write_entries_to(writer).await
+    }
+
+    async fn write_entries_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+        let block_size = u64::from(self.block_size());
+        for (index, entry) in self.entries.iter().enumerate() {
+            writer
+                .seek(SeekFrom::Start((index as u64 + 1) * block_size))
+                .await
+                .map_err(Error::from)?;
+            writer
+                .write_all(bytemuck::bytes_of(entry))
+                .await
+                .map_err(Error::from)?;
+        }
+        Ok(())
+    }
+}
+
+} // io_transform!
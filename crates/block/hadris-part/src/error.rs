@@ -265,6 +265,28 @@ impl Error {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl Error {
+    /// An on-disk structure that is malformed in a way no dedicated variant
+    /// describes.
+    pub(crate) fn invalid_data(reason: &'static str) -> Self {
+        Self::Io(hadris_io::Error::new(
+            hadris_io::ErrorKind::InvalidData,
+            reason,
+        ))
+    }
+}
+
+impl Error {
+    /// A request that does not fit the structure being edited.
+    pub(crate) fn invalid_input(reason: &'static str) -> Self {
+        Self::Io(hadris_io::Error::new(
+            hadris_io::ErrorKind::InvalidInput,
+            reason,
+        ))
+    }
+}
+
 #[cfg(feature = "std")]
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
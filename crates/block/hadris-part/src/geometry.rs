@@ -233,6 +233,23 @@ impl DiskGeometry {
     }
 }
 
+/// A partition relocation planned by a resize or move operation.
+///
+/// Partition tables only record where partitions live; the caller copies
+/// `sectors` blocks from `from_lba` to `to_lba` to carry the contents along.
+/// Moves towards higher LBAs overlap their source, so copy from the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionMove {
+    /// Index of the moved partition.
+    pub index: usize,
+    /// Previous first LBA.
+    pub from_lba: u64,
+    /// New first LBA.
+    pub to_lba: u64,
+    /// Length of the partition in sectors.
+    pub sectors: u64,
+}
+
 /// Validates that a partition is properly aligned.
 ///
 /// # Arguments
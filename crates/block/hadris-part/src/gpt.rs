@@ -4,6 +4,7 @@
 //! - GUID (Globally Unique Identifier)
 //! - GPT partition table header
 //! - GPT partition entries
+//! - Primary/backup copy health for recovery
 //! - Well-known partition type GUIDs
 
 use core::fmt::{Debug, Display};
@@ -773,6 +774,134 @@ impl GptHeader {
     }
 }
 
+impl GptHeader {
+    /// Returns the number of blocks the partition entry array occupies, or
+    /// `None` if the size overflows or `block_size` is zero.
+    pub fn entry_array_blocks(&self, block_size: u32) -> Option<u64> {
+        if block_size == 0 {
+            return None;
+        }
+        u64::from(self.num_partition_entries.to_ne())
+            .checked_mul(u64::from(self.size_of_partition_entry.to_ne()))
+            .map(|bytes| bytes.div_ceil(u64::from(block_size)))
+    }
+
+    /// Returns the header of the other GPT copy.
+    ///
+    /// `my_lba` and `alternate_lba` are swapped and the entry array is
+    /// placed at `partition_entry_lba`; with the `crc` feature the header
+    /// CRC is recomputed.
+    pub fn to_alternate(&self, partition_entry_lba: u64) -> Self {
+        #[cfg_attr(not(feature = "crc"), allow(unused_mut))]
+        let mut header = Self {
+            my_lba: self.alternate_lba,
+            alternate_lba: self.my_lba,
+            partition_entry_lba: Le::<u64>::from_ne(partition_entry_lba),
+            ..*self
+        };
+        #[cfg(feature = "crc")]
+        header.update_crc32();
+        header
+    }
+}
+
+/// One of the two copies of a GPT.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GptCopy {
+    /// The primary header at LBA 1 and its entry array.
+    Primary,
+    /// The backup header at the last LBA and its entry array.
+    Backup,
+}
+
+impl Display for GptCopy {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Primary => write!(f, "primary"),
+            Self::Backup => write!(f, "backup"),
+        }
+    }
+}
+
+/// The state of one GPT copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GptCopyStatus {
+    /// The copy is intact.
+    Valid,
+    /// The header or entry array could not be read.
+    Unreadable,
+    /// The header signature is not `EFI PART`.
+    InvalidSignature,
+    /// The header CRC does not match (checked with the `crc` feature).
+    HeaderCrcMismatch,
+    /// The entry array CRC does not match the header (checked with the
+    /// `crc` feature).
+    EntriesCrcMismatch,
+    /// The header records the wrong LBA for itself, an unsupported entry
+    /// size, or an entry array outside the disk.
+    InvalidLayout,
+    /// The copy is intact on its own but does not mirror the primary.
+    Mismatch,
+}
+
+impl GptCopyStatus {
+    /// Returns whether the copy is intact.
+    pub const fn is_valid(&self) -> bool {
+        matches!(self, Self::Valid)
+    }
+}
+
+impl Display for GptCopyStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Valid => write!(f, "valid"),
+            Self::Unreadable => write!(f, "unreadable"),
+            Self::InvalidSignature => write!(f, "invalid signature"),
+            Self::HeaderCrcMismatch => write!(f, "header CRC mismatch"),
+            Self::EntriesCrcMismatch => write!(f, "entry array CRC mismatch"),
+            Self::InvalidLayout => write!(f, "invalid layout"),
+            Self::Mismatch => write!(f, "does not match the primary"),
+        }
+    }
+}
+
+/// The state of both GPT copies of a disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GptHealth {
+    /// State of the primary copy.
+    pub primary: GptCopyStatus,
+    /// State of the backup copy.
+    pub backup: GptCopyStatus,
+}
+
+impl GptHealth {
+    /// Returns the copy to trust: the primary when intact, otherwise the
+    /// backup, or `None` when neither is.
+    pub const fn authoritative(&self) -> Option<GptCopy> {
+        if self.primary.is_valid() {
+            Some(GptCopy::Primary)
+        } else if self.backup.is_valid() {
+            Some(GptCopy::Backup)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the copy that must be rewritten from the authoritative one.
+    pub const fn damaged(&self) -> Option<GptCopy> {
+        match (self.primary.is_valid(), self.backup.is_valid()) {
+            (true, false) => Some(GptCopy::Backup),
+            (false, true) => Some(GptCopy::Primary),
+            _ => None,
+        }
+    }
+
+    /// Returns whether both copies are intact and agree.
+    pub const fn is_healthy(&self) -> bool {
+        self.primary.is_valid() && self.backup.is_valid()
+    }
+}
+
 /// On-disk GPT header representation (92 bytes, packed).
 ///
 /// This struct matches the exact on-disk layout of the GPT header.
@@ -823,12 +952,9 @@ unsafe impl bytemuck::Pod for GptHeaderRaw {}
 ))]
 unsafe impl bytemuck::Zeroable for GptHeaderRaw {}
 
-#[cfg(any(
-    feature = "crc",
-    all(
-        any(feature = "read", feature = "write"),
-        any(feature = "sync", feature = "async")
-    )
+#[cfg(all(
+    any(feature = "read", feature = "write"),
+    any(feature = "sync", feature = "async")
 ))]
 impl GptHeaderRaw {
     /// Size of the raw header on disk.
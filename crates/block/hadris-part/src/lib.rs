@@ -3,7 +3,8 @@
 //! This crate provides types and utilities for working with disk partition tables:
 //!
 //! - **MBR (Master Boot Record)**: Legacy BIOS partition table format supporting up to 4 primary
-//!   partitions. See the [`mbr`] module.
+//!   partitions, plus logical partitions chained through EBRs inside an extended partition.
+//!   See the [`mbr`] module.
 //!
 //! - **GPT (GUID Partition Table)**: Modern UEFI partition table format supporting up to 128
 //!   partitions with GUIDs for type identification. See the [`gpt`] module.
@@ -11,6 +12,9 @@
 //! - **Hybrid MBR**: A non-standard configuration that combines GPT with MBR entries for
 //!   dual BIOS/UEFI compatibility. See the [`hybrid`] module.
 //!
+//! - **APM (Apple Partition Map)**: The big-endian partition map used by classic Macs and
+//!   by older Intel Macs booting optical or hybrid media. See the [`apm`] module.
+//!
 //! # Features
 //!
 //! - `std` (default): Enables standard library support and includes `alloc`.
@@ -73,6 +77,7 @@ extern crate std;
 // Shared types (compiled once, not duplicated by sync/async modules)
 // ---------------------------------------------------------------------------
 
+pub mod apm;
 pub mod error;
 pub mod geometry;
 pub mod gpt;
@@ -111,6 +116,8 @@ pub mod sync {
 
     #[path = "."]
     mod __inner {
+        /// APM parsing and serialization extensions.
+        pub mod apm_io;
         /// GPT parsing and serialization extensions.
         pub mod gpt_io;
         /// MBR parsing and serialization extensions.
@@ -156,6 +163,8 @@ pub mod r#async {
 
     #[path = "."]
     mod __inner {
+        /// APM parsing and serialization extensions.
+        pub mod apm_io;
         /// GPT parsing and serialization extensions.
         pub mod gpt_io;
         /// MBR parsing and serialization extensions.
@@ -178,18 +187,33 @@ pub mod r#async {
 pub use sync::*;
 
 // Re-export commonly used types at the crate root
+pub use apm::{ApmEntry, DriverDescriptorMap};
 pub use endian_num::Le;
 pub use error::{Error, Result};
-pub use geometry::{DiskGeometry, validate_partition_alignment};
-pub use gpt::{GptHeader, GptPartitionEntry, Guid};
-pub use mbr::{Chs, MasterBootRecord, MbrPartition, MbrPartitionTable, MbrPartitionType};
+pub use geometry::{DiskGeometry, PartitionMove, validate_partition_alignment};
+pub use gpt::{GptCopy, GptCopyStatus, GptHeader, GptHealth, GptPartitionEntry, Guid};
+pub use mbr::{
+    Chs, LogicalPartition, MasterBootRecord, MbrPartition, MbrPartitionTable, MbrPartitionType,
+};
 pub use scheme::{PartitionInfo, PartitionSchemeType, PartitionType};
 
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use apm::ApmDisk;
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use mbr::MbrDisk;
 #[cfg(feature = "alloc")]
 #[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
 pub use scheme::{GptDisk, PartitionTable};
 
 // Flatten I/O extension traits to the crate root for discoverability
+#[cfg(all(feature = "sync", feature = "alloc", feature = "read"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "read"))))]
+pub use sync::apm_io::ApmDiskReadExt;
+#[cfg(all(feature = "sync", feature = "alloc", feature = "write"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "write"))))]
+pub use sync::apm_io::ApmDiskWriteExt;
 #[cfg(all(feature = "sync", feature = "read"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "read")))]
 pub use sync::gpt_io::GptHeaderReadExt;
@@ -204,6 +228,12 @@ pub use sync::mbr_io::MasterBootRecordReadExt;
 pub use sync::mbr_io::MasterBootRecordWriteExt;
 #[cfg(all(feature = "sync", feature = "alloc", feature = "read"))]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "read"))))]
+pub use sync::mbr_io::MbrDiskReadExt;
+#[cfg(all(feature = "sync", feature = "alloc", feature = "write"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "write"))))]
+pub use sync::mbr_io::MbrDiskWriteExt;
+#[cfg(all(feature = "sync", feature = "alloc", feature = "read"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "read"))))]
 pub use sync::scheme_io::{GptDiskReadExt, PartitionTableReadExt};
 #[cfg(all(feature = "sync", feature = "alloc", feature = "write"))]
 #[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "write"))))]
@@ -212,7 +242,7 @@ pub use sync::scheme_io::{GptDiskWriteExt, PartitionTableWriteExt};
 /// Trait for types that represent partition information.
 ///
 /// This trait provides a common interface for accessing basic partition properties
-/// regardless of the underlying partition table format (MBR or GPT).
+/// regardless of the underlying partition table format (MBR, GPT, or APM).
 pub trait PartitionInfoTrait {
     /// Returns the starting LBA of the partition.
     fn start_lba(&self) -> u64;
@@ -281,6 +311,18 @@ impl PartitionInfoTrait for GptPartitionEntry {
     }
 }
 
+/// Reports blocks of the map's block size, not disk sectors; `ApmDisk::sector_range`
+/// converts between the two.
+impl PartitionInfoTrait for ApmEntry {
+    fn start_lba(&self) -> u64 {
+        self.start_block.to_ne() as u64
+    }
+
+    fn size_sectors(&self) -> u64 {
+        self.block_count.to_ne() as u64
+    }
+}
+
 impl PartitionInfoTrait for PartitionInfo {
     fn start_lba(&self) -> u64 {
         self.start_lba
@@ -4,13 +4,19 @@
 //! - CHS (Cylinder-Head-Sector) addressing
 //! - MBR partition entries
 //! - MBR partition table (4 primary partitions)
+//! - Logical partitions chained through Extended Boot Records (EBRs)
 //! - Partition type definitions
 
 use core::fmt::Debug;
 use core::ops::{Index, IndexMut};
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use endian_num::Le;
 
+use crate::error::{Error, Result};
+use crate::geometry::{DiskGeometry, PartitionMove};
+
 /// A simplified enum for common MBR partition types.
 ///
 /// For a complete list of partition types, see [`MbrPartitionTypeFull`].
@@ -108,6 +114,16 @@ impl MbrPartitionType {
     pub const fn is_protective(&self) -> bool {
         matches!(self, Self::ProtectiveMbr)
     }
+
+    /// Returns whether this is an extended partition holding an EBR chain.
+    ///
+    /// Covers the DOS (`0x05`), LBA (`0x0F`), and Linux (`0x85`) variants.
+    pub const fn is_extended(&self) -> bool {
+        matches!(
+            self,
+            Self::Extended | Self::ExtendedLba | Self::Unknown(0x85)
+        )
+    }
 }
 
 impl core::fmt::Display for MbrPartitionType {
@@ -301,6 +317,11 @@ impl MbrPartition {
         MbrPartitionType::from_u8(self.part_type)
     }
 
+    /// Returns whether this entry describes an extended partition.
+    pub const fn is_extended(&self) -> bool {
+        self.partition_type().is_extended()
+    }
+
     /// Returns whether this partition is marked as bootable.
     pub const fn is_bootable(&self) -> bool {
         self.boot_indicator == 0x80
@@ -410,6 +431,164 @@ impl MbrPartitionTable {
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut MbrPartition> {
         self.partitions.iter_mut()
     }
+
+    /// Checks that every non-empty entry fits on a disk of
+    /// `geometry.total_blocks` blocks and that no two entries overlap.
+    ///
+    /// Logical partitions inside an extended entry are not checked; see
+    /// [`MbrDisk::validate`].
+    pub fn validate_layout(&self, geometry: &DiskGeometry) -> Result<()> {
+        let disk_end = geometry.total_blocks.saturating_sub(1);
+        let range = |p: &MbrPartition| {
+            let start = u64::from(p.start_lba.to_ne());
+            (start, start + u64::from(p.sector_count.to_ne()).max(1) - 1)
+        };
+        for (i, p) in self.partitions.iter().enumerate() {
+            if p.is_empty() {
+                continue;
+            }
+            let (start1, end1) = range(p);
+            if end1 > disk_end {
+                return Err(Error::PartitionOutOfBounds {
+                    index: i,
+                    partition_end: end1,
+                    disk_end,
+                });
+            }
+            for (j, other) in self.partitions.iter().enumerate().skip(i + 1) {
+                if other.is_empty() {
+                    continue;
+                }
+                let (start2, end2) = range(other);
+                if start1 <= end2 && start2 <= end1 {
+                    return Err(Error::PartitionOverlap {
+                        index1: i,
+                        index2: j,
+                        overlap_start: start1.max(start2),
+                        overlap_end: end1.min(end2),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Grows entry `index` up to the next entry or the last aligned LBA of
+    /// the disk, whichever comes first.
+    ///
+    /// Returns the new last LBA. An entry that already reaches that far is
+    /// left unchanged.
+    pub fn grow_to_fill(&mut self, index: usize, geometry: &DiskGeometry) -> Result<u64> {
+        let entry = self.used_entry(index)?;
+        let first = u64::from(entry.start_lba.to_ne());
+        let last = u64::from(entry.end_lba());
+
+        let aligned_end = geometry.align_down(geometry.total_blocks, geometry.default_alignment());
+        let mut limit = aligned_end.saturating_sub(1).min(u64::from(u32::MAX));
+        for (i, other) in self.partitions.iter().enumerate() {
+            let other_first = u64::from(other.start_lba.to_ne());
+            if i != index && !other.is_empty() && other_first > first {
+                limit = limit.min(other_first - 1);
+            }
+        }
+        if limit <= last {
+            return Ok(last);
+        }
+        self.replace(index, entry, first, limit - first + 1, geometry)?;
+        Ok(limit)
+    }
+
+    /// Grows or shrinks entry `index` to `size_sectors`, keeping its start.
+    ///
+    /// The size is rounded up so the entry ends on the geometry's default
+    /// alignment boundary. Returns the new last LBA.
+    pub fn resize_partition(
+        &mut self,
+        index: usize,
+        size_sectors: u64,
+        geometry: &DiskGeometry,
+    ) -> Result<u64> {
+        let entry = self.used_entry(index)?;
+        let first = u64::from(entry.start_lba.to_ne());
+        let end = geometry.align_up(
+            first.saturating_add(size_sectors.max(1)),
+            geometry.default_alignment(),
+        );
+        self.replace(index, entry, first, end - first, geometry)?;
+        Ok(end - 1)
+    }
+
+    /// Moves entry `index` to start at `first_lba`, keeping its size.
+    ///
+    /// `first_lba` must be aligned to the geometry's default alignment.
+    /// Returns the data move the caller must perform; moving an extended
+    /// entry also invalidates the EBR chain inside it.
+    pub fn move_partition(
+        &mut self,
+        index: usize,
+        first_lba: u64,
+        geometry: &DiskGeometry,
+    ) -> Result<PartitionMove> {
+        let entry = self.used_entry(index)?;
+        let alignment = geometry.default_alignment();
+        if !geometry.is_aligned(first_lba, alignment) {
+            return Err(Error::MisalignedPartition {
+                lba: first_lba,
+                required_alignment: alignment,
+            });
+        }
+        let sectors = u64::from(entry.sector_count.to_ne());
+        self.replace(index, entry, first_lba, sectors, geometry)?;
+        Ok(PartitionMove {
+            index,
+            from_lba: u64::from(entry.start_lba.to_ne()),
+            to_lba: first_lba,
+            sectors,
+        })
+    }
+
+    /// Returns the non-empty entry at `index`.
+    fn used_entry(&self, index: usize) -> Result<MbrPartition> {
+        self.partitions
+            .get(index)
+            .filter(|p| !p.is_empty())
+            .copied()
+            .ok_or(Error::invalid_input("no partition at the given index"))
+    }
+
+    /// Rewrites entry `index` with a new extent, keeping its type and boot
+    /// flag, if the resulting table validates.
+    fn replace(
+        &mut self,
+        index: usize,
+        entry: MbrPartition,
+        start_lba: u64,
+        sector_count: u64,
+        geometry: &DiskGeometry,
+    ) -> Result<()> {
+        let end = start_lba.saturating_add(sector_count).saturating_sub(1);
+        let (Ok(start), Ok(count)) = (u32::try_from(start_lba), u32::try_from(sector_count)) else {
+            return Err(Error::PartitionOutOfBounds {
+                index,
+                partition_end: end,
+                disk_end: u64::from(u32::MAX),
+            });
+        };
+        if u64::from(start) + u64::from(count) > u64::from(u32::MAX) + 1 {
+            return Err(Error::PartitionOutOfBounds {
+                index,
+                partition_end: end,
+                disk_end: u64::from(u32::MAX),
+            });
+        }
+        let mut next = *self;
+        let mut replacement = MbrPartition::new(entry.partition_type(), start, count);
+        replacement.boot_indicator = entry.boot_indicator;
+        next.partitions[index] = replacement;
+        next.validate_layout(geometry)?;
+        *self = next;
+        Ok(())
+    }
 }
 
 impl Index<usize> for MbrPartitionTable {
@@ -531,6 +710,321 @@ impl Debug for MasterBootRecord {
     }
 }
 
+/// A logical partition inside an extended partition.
+///
+/// Each logical partition is described by its own Extended Boot Record
+/// (EBR), whose first entry stores the start relative to the EBR and whose
+/// second entry links to the next EBR relative to the extended partition.
+/// `partition.start_lba` here is already absolute.
+///
+/// @hadris-spec MBR:EBR
+/// @hadris-compliance unknown
+/// @hadris-tests io_roundtrip::mbr_logical_partitions_roundtrip
+#[derive(Debug, Clone, Copy)]
+pub struct LogicalPartition {
+    /// LBA of the EBR describing this partition.
+    pub ebr_lba: u32,
+    /// The partition entry, with an absolute `start_lba`.
+    pub partition: MbrPartition,
+}
+
+/// An MBR disk: the primary table plus the logical partitions of its
+/// extended partition.
+///
+/// Partition indices 0-3 are the primary slots; logical partitions follow
+/// from [`MbrDisk::FIRST_LOGICAL_INDEX`] in EBR chain order.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct MbrDisk {
+    /// The master boot record at LBA 0.
+    pub mbr: MasterBootRecord,
+    /// Logical partitions in EBR chain order.
+    pub logical: Vec<LogicalPartition>,
+    /// Logical block size in bytes.
+    pub block_size: u32,
+}
+
+#[cfg(feature = "alloc")]
+impl MbrDisk {
+    /// Index of the first logical partition.
+    pub const FIRST_LOGICAL_INDEX: usize = 4;
+
+    /// Maximum number of EBRs followed or written.
+    ///
+    /// Bounds the work done on hostile chains.
+    pub const MAX_LOGICAL_PARTITIONS: usize = 256;
+
+    /// Creates an empty MBR disk.
+    pub fn new(block_size: u32) -> Self {
+        Self::from_mbr(MasterBootRecord::default(), block_size)
+    }
+
+    /// Creates an MBR disk without logical partitions from an existing MBR.
+    pub fn from_mbr(mbr: MasterBootRecord, block_size: u32) -> Self {
+        Self {
+            mbr,
+            logical: Vec::new(),
+            block_size,
+        }
+    }
+
+    /// Returns the slot and entry of the first extended partition.
+    pub fn extended(&self) -> Option<(usize, MbrPartition)> {
+        let table = self.mbr.get_partition_table();
+        table
+            .partitions
+            .into_iter()
+            .enumerate()
+            .find(|(_, p)| p.is_extended())
+    }
+
+    /// Returns the partition at `index`, with an absolute start LBA.
+    ///
+    /// Empty primary slots and missing logical partitions yield `None`.
+    pub fn partition(&self, index: usize) -> Option<MbrPartition> {
+        if index < Self::FIRST_LOGICAL_INDEX {
+            let entry = self.mbr.get_partition_table()[index];
+            (!entry.is_empty()).then_some(entry)
+        } else {
+            self.logical
+                .get(index - Self::FIRST_LOGICAL_INDEX)
+                .map(|l| l.partition)
+        }
+    }
+
+    /// Returns an iterator over non-empty primary entries followed by the
+    /// logical partitions.
+    pub fn partitions(&self) -> impl Iterator<Item = (usize, MbrPartition)> + '_ {
+        let primary = self.mbr.get_partition_table().partitions;
+        primary
+            .into_iter()
+            .enumerate()
+            .filter(|(_, p)| !p.is_empty())
+            .chain(
+                self.logical
+                    .iter()
+                    .enumerate()
+                    .map(|(i, l)| (Self::FIRST_LOGICAL_INDEX + i, l.partition)),
+            )
+    }
+
+    /// Creates an extended partition in primary `slot`.
+    ///
+    /// The slot must be empty and the disk must not already have an
+    /// extended partition. Any logical partitions are discarded.
+    pub fn create_extended(
+        &mut self,
+        slot: usize,
+        start_lba: u32,
+        sector_count: u32,
+    ) -> Result<()> {
+        if slot >= Self::FIRST_LOGICAL_INDEX {
+            return Err(Error::TooManyPartitions {
+                max: Self::FIRST_LOGICAL_INDEX,
+                requested: slot + 1,
+            });
+        }
+        if self.extended().is_some() {
+            return Err(Error::invalid_input(
+                "disk already has an extended partition",
+            ));
+        }
+        if sector_count < 2 {
+            return Err(Error::invalid_input(
+                "extended partition has no room for a logical partition",
+            ));
+        }
+        let mut table = self.mbr.get_partition_table();
+        if !table[slot].is_empty() {
+            return Err(Error::invalid_input("primary slot is already in use"));
+        }
+        table[slot] = MbrPartition::new(MbrPartitionType::ExtendedLba, start_lba, sector_count);
+        self.mbr.set_partition_table(table);
+        self.logical.clear();
+        Ok(())
+    }
+
+    /// Appends a logical partition to the extended partition.
+    ///
+    /// Its EBR is placed at the start of the extended partition for the
+    /// first logical partition, and right after the previous logical
+    /// partition otherwise; `start_lba` must lie after that EBR. Returns the
+    /// index of the new partition.
+    pub fn add_logical(
+        &mut self,
+        part_type: MbrPartitionType,
+        start_lba: u32,
+        sector_count: u32,
+    ) -> Result<usize> {
+        let Some((_, extended)) = self.extended() else {
+            return Err(Error::invalid_input("disk has no extended partition"));
+        };
+        if self.logical.len() >= Self::MAX_LOGICAL_PARTITIONS {
+            return Err(Error::TooManyPartitions {
+                max: Self::MAX_LOGICAL_PARTITIONS,
+                requested: self.logical.len() + 1,
+            });
+        }
+        if part_type.is_empty() || part_type.is_extended() || sector_count == 0 {
+            return Err(Error::invalid_input(
+                "logical partition must be a non-empty, non-extended partition",
+            ));
+        }
+
+        let index = Self::FIRST_LOGICAL_INDEX + self.logical.len();
+        let ebr_lba = match self.logical.last() {
+            Some(previous) => u64::from(previous.partition.end_lba()) + 1,
+            None => u64::from(extended.start_lba.to_ne()),
+        };
+        let extended_end = u64::from(extended.end_lba());
+        let end = u64::from(start_lba) + u64::from(sector_count) - 1;
+        if u64::from(start_lba) <= ebr_lba {
+            return Err(Error::invalid_input(
+                "logical partition must start after its EBR",
+            ));
+        }
+        if end > extended_end {
+            return Err(Error::PartitionOutOfBounds {
+                index,
+                partition_end: end,
+                disk_end: extended_end,
+            });
+        }
+
+        self.logical.push(LogicalPartition {
+            ebr_lba: ebr_lba as u32,
+            partition: MbrPartition::new(part_type, start_lba, sector_count),
+        });
+        Ok(index)
+    }
+
+    /// Builds the EBRs of the chain as `(lba, record)` pairs.
+    ///
+    /// An extended partition without logical partitions still gets an
+    /// empty EBR at its start, terminating the chain.
+    pub fn ebrs(&self) -> Vec<(u32, MasterBootRecord)> {
+        let Some((_, extended)) = self.extended() else {
+            return Vec::new();
+        };
+        let extended_start = extended.start_lba.to_ne();
+        if self.logical.is_empty() {
+            return alloc::vec![(extended_start, MasterBootRecord::default())];
+        }
+
+        self.logical
+            .iter()
+            .enumerate()
+            .map(|(i, logical)| {
+                let mut table = MbrPartitionTable::new();
+                let start = logical.partition.start_lba.to_ne();
+                let mut data = logical.partition;
+                data.start_lba = Le::<u32>::from_ne(start.saturating_sub(logical.ebr_lba));
+                table[0] = data;
+                if let Some(next) = self.logical.get(i + 1) {
+                    let length = next
+                        .partition
+                        .end_lba()
+                        .saturating_sub(next.ebr_lba)
+                        .saturating_add(1);
+                    let mut link =
+                        MbrPartition::new(MbrPartitionType::Extended, next.ebr_lba, length);
+                    link.start_lba =
+                        Le::<u32>::from_ne(next.ebr_lba.saturating_sub(extended_start));
+                    table[1] = link;
+                }
+                (logical.ebr_lba, MasterBootRecord::new(table))
+            })
+            .collect()
+    }
+
+    /// Validates the MBR and the logical partition layout.
+    pub fn validate(&self) -> Result<()> {
+        if !self.mbr.has_valid_signature() {
+            return Err(Error::InvalidMbrSignature {
+                found: self.mbr.signature,
+            });
+        }
+        if !self.mbr.get_partition_table().is_valid() {
+            return Err(Error::invalid_data("invalid MBR partition table"));
+        }
+        self.validate_logical()
+    }
+
+    /// Checks that every logical partition and EBR lies inside the extended
+    /// partition and that none of them overlap.
+    pub(crate) fn validate_logical(&self) -> Result<()> {
+        let Some((_, extended)) = self.extended() else {
+            return if self.logical.is_empty() {
+                Ok(())
+            } else {
+                Err(Error::invalid_data(
+                    "logical partitions without an extended partition",
+                ))
+            };
+        };
+        let extended_start = u64::from(extended.start_lba.to_ne());
+        let extended_end = u64::from(extended.end_lba());
+
+        let mut ranges = Vec::with_capacity(self.logical.len());
+        for (i, logical) in self.logical.iter().enumerate() {
+            let index = Self::FIRST_LOGICAL_INDEX + i;
+            let ebr_lba = u64::from(logical.ebr_lba);
+            if ebr_lba < extended_start || ebr_lba > extended_end {
+                return Err(Error::invalid_data(
+                    "EBR lies outside the extended partition",
+                ));
+            }
+            let partition = &logical.partition;
+            if partition.is_empty()
+                || partition.is_extended()
+                || partition.sector_count.to_ne() == 0
+            {
+                return Err(Error::invalid_data(
+                    "EBR describes an empty or nested extended partition",
+                ));
+            }
+            let start = u64::from(partition.start_lba.to_ne());
+            let end = start + u64::from(partition.sector_count.to_ne()) - 1;
+            if start < extended_start || end > extended_end {
+                return Err(Error::PartitionOutOfBounds {
+                    index,
+                    partition_end: end,
+                    disk_end: extended_end,
+                });
+            }
+            ranges.push((index, start, end));
+        }
+
+        for (i, &(index1, start1, end1)) in ranges.iter().enumerate() {
+            for &(index2, start2, end2) in &ranges[i + 1..] {
+                if start1 <= end2 && start2 <= end1 {
+                    return Err(Error::PartitionOverlap {
+                        index1,
+                        index2,
+                        overlap_start: start1.max(start2),
+                        overlap_end: end1.min(end2),
+                    });
+                }
+            }
+        }
+        for (i, logical) in self.logical.iter().enumerate() {
+            let ebr_lba = u64::from(logical.ebr_lba);
+            if self.logical[..i]
+                .iter()
+                .any(|l| l.ebr_lba == logical.ebr_lba)
+                || ranges
+                    .iter()
+                    .any(|&(_, start, end)| (start..=end).contains(&ebr_lba))
+            {
+                return Err(Error::invalid_data(
+                    "EBR overlaps another EBR or a logical partition",
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// An enum representing the full list of MBR partition types.
 ///
 /// Based on the comprehensive list at <https://thestarman.pcministry.com/asm/mbr/PartTypes.htm>
@@ -1118,6 +1612,85 @@ mod tests {
         assert_eq!(pt[0].sector_count.to_ne(), 999);
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn ebr_chain_links_are_relative() {
+        let mut disk = MbrDisk::new(512);
+        disk.create_extended(1, 2048, 8192).unwrap();
+        assert_eq!(
+            disk.add_logical(MbrPartitionType::Fat32, 2049, 1000)
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            disk.add_logical(MbrPartitionType::LinuxNative, 3100, 2000)
+                .unwrap(),
+            5
+        );
+        disk.validate().unwrap();
+
+        let ebrs = disk.ebrs();
+        assert_eq!(ebrs.len(), 2);
+        let (first_lba, first) = ebrs[0];
+        assert_eq!(first_lba, 2048);
+        let table = first.get_partition_table();
+        assert_eq!(table[0].start_lba.to_ne(), 1);
+        assert!(table[1].is_extended());
+        // The link is relative to the extended partition and spans the next
+        // EBR through the end of its partition.
+        assert_eq!(table[1].start_lba.to_ne(), 3049 - 2048);
+        assert_eq!(table[1].sector_count.to_ne(), 5099 - 3049 + 1);
+        let (second_lba, second) = ebrs[1];
+        assert_eq!(second_lba, 3049);
+        assert_eq!(
+            second.get_partition_table()[0].start_lba.to_ne(),
+            3100 - 3049
+        );
+        assert!(second.get_partition_table()[1].is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn logical_partitions_stay_inside_extended() {
+        let mut disk = MbrDisk::new(512);
+        assert!(disk.add_logical(MbrPartitionType::Fat32, 10, 10).is_err());
+        disk.create_extended(0, 100, 100).unwrap();
+        assert!(disk.create_extended(1, 300, 100).is_err());
+        // Must start after the EBR at the start of the extended partition.
+        assert!(disk.add_logical(MbrPartitionType::Fat32, 100, 10).is_err());
+        assert!(matches!(
+            disk.add_logical(MbrPartitionType::Fat32, 150, 100),
+            Err(Error::PartitionOutOfBounds { index: 4, .. })
+        ));
+        disk.add_logical(MbrPartitionType::Fat32, 101, 10).unwrap();
+
+        disk.logical.push(LogicalPartition {
+            ebr_lba: 105,
+            partition: MbrPartition::new(MbrPartitionType::Fat32, 106, 10),
+        });
+        assert!(matches!(
+            disk.validate(),
+            Err(Error::PartitionOverlap {
+                index1: 4,
+                index2: 5,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn invalid_primary_table_is_not_a_hybrid_error() {
+        let mut table = MbrPartitionTable::new();
+        table[0] = MbrPartition::new(MbrPartitionType::Fat32, 2048, 100);
+        table[0].boot_indicator = 0x01;
+        let disk = MbrDisk::from_mbr(MasterBootRecord::new(table), 512);
+        let Err(Error::Io(error)) = disk.validate() else {
+            panic!("expected an invalid-data error");
+        };
+        assert_eq!(error.kind(), hadris_io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_partition_type_full_transmute() {
         // Verify all 256 values are valid
@@ -6,6 +6,14 @@ use super::super::Read;
 use super::super::Write;
 #[cfg(any(feature = "read", feature = "write"))]
 use crate::mbr::MasterBootRecord;
+#[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
+use super::super::{Seek, SeekFrom};
+#[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
+use crate::error::{Error, Result};
+#[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
+use crate::mbr::MbrDisk;
+#[cfg(all(feature = "alloc", feature = "read"))]
+use crate::mbr::LogicalPartition;
 
 // I/O operations
 
@@ -61,4 +69,144 @@ impl MasterBootRecordWriteExt for MasterBootRecord {
     }
 }
 
+/// Follows the EBR chain of `mbr`'s extended partition.
+///
+/// Loops, chains longer than [`MbrDisk::MAX_LOGICAL_PARTITIONS`], and
+/// logical partitions that escape the extended partition or overlap are
+/// rejected rather than followed.
+#[cfg(all(feature = "alloc", feature = "read"))]
+pub(crate) async fn read_ebr_chain<R: Read + Seek>(
+    reader: &mut R,
+    mbr: MasterBootRecord,
+    block_size: u32,
+) -> Result<MbrDisk> {
+    let mut disk = MbrDisk::from_mbr(mbr, block_size);
+    let Some((_, extended)) = disk.extended() else {
+        return Ok(disk);
+    };
+    if block_size < 512 {
+        return Err(Error::InvalidBlockSize {
+            size: block_size,
+            minimum: 512,
+        });
+    }
+    let extended_start = u64::from(extended.start_lba.to_ne());
+    let extended_end = u64::from(extended.end_lba());
+
+    let mut visited = alloc::vec::Vec::new();
+    let mut ebr_lba = extended_start;
+    loop {
+        if visited.contains(&ebr_lba) {
+            return Err(Error::invalid_data("EBR chain loops"));
+        }
+        if visited.len() >= MbrDisk::MAX_LOGICAL_PARTITIONS {
+            return Err(Error::TooManyPartitions {
+                max: MbrDisk::MAX_LOGICAL_PARTITIONS,
+                requested: visited.len() + 1,
+            });
+        }
+        if ebr_lba > extended_end {
+            return Err(Error::invalid_data("EBR lies outside the extended partition"));
+        }
+        visited.push(ebr_lba);
+
+        reader
+            .seek(SeekFrom::Start(ebr_lba * u64::from(block_size)))
+            .await
+            .map_err(Error::from)?;
+        let ebr = MasterBootRecord::read_from(reader)
+            .await
+            .map_err(|error| match error {
+                Error::InvalidMbrSignature { .. } => {
+                    Error::invalid_data("invalid EBR signature")
+                }
+                error => error,
+            })?;
+        let table = ebr.get_partition_table();
+
+        // An empty first entry is legal (e.g. the head EBR of an extended
+        // partition whose first logical partition was deleted).
+        let data = table[0];
+        if !data.is_empty() {
+            let start = ebr_lba + u64::from(data.start_lba.to_ne());
+            let Ok(start) = u32::try_from(start) else {
+                return Err(Error::invalid_data(
+                    "logical partition starts beyond the 32-bit LBA range",
+                ));
+            };
+            let mut partition = data;
+            partition.start_lba = endian_num::Le::<u32>::from_ne(start);
+            disk.logical.push(LogicalPartition {
+                ebr_lba: ebr_lba as u32,
+                partition,
+            });
+        }
+
+        let link = table[1];
+        if !link.is_extended() {
+            break;
+        }
+        ebr_lba = extended_start + u64::from(link.start_lba.to_ne());
+    }
+
+    disk.validate_logical()?;
+    Ok(disk)
+}
+
+/// Extension trait for reading [`MbrDisk`] from I/O sources.
+#[cfg(all(feature = "alloc", feature = "read"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "read"))))]
+pub trait MbrDiskReadExt: Sized {
+    /// Reads the MBR at LBA 0 and the EBR chain of its extended partition.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, the MBR signature is invalid, or
+    /// the EBR chain is malformed.
+    async fn read_from<R: Read + Seek>(reader: &mut R, block_size: u32) -> Result<Self>;
+}
+
+#[cfg(all(feature = "alloc", feature = "read"))]
+impl MbrDiskReadExt for MbrDisk {
+    async fn read_from<R: Read + Seek>(reader: &mut R, block_size: u32) -> Result<Self> {
+        reader
+            .seek(SeekFrom::Start(0))
+            .await
+            .map_err(Error::from)?;
+        let mbr = MasterBootRecord::read_from(reader).await?;
+        read_ebr_chain(reader, mbr, block_size).await
+    }
+}
+
+/// Extension trait for writing [`MbrDisk`] to I/O sinks.
+#[cfg(all(feature = "alloc", feature = "write"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "alloc", feature = "write"))))]
+pub trait MbrDiskWriteExt {
+    /// Writes the MBR to LBA 0 and every EBR of the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    async fn write_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()>;
+}
+
+#[cfg(all(feature = "alloc", feature = "write"))]
+impl MbrDiskWriteExt for MbrDisk {
+    async fn write_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
+        writer
+            .seek(SeekFrom::Start(0))
+            .await
+            .map_err(Error::from)?;
+        self.mbr.write_to(writer).await?;
+        for (lba, ebr) in self.ebrs() {
+            writer
+                .seek(SeekFrom::Start(u64::from(lba) * u64::from(self.block_size)))
+                .await
+                .map_err(Error::from)?;
+            ebr.write_to(writer).await?;
+        }
+        Ok(())
+    }
+}
+
 } // io_transform!
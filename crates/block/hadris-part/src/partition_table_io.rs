@@ -1,6 +1,7 @@
 io_transform! {
 
 use super::super::{Read, Seek, SeekFrom};
+use super::apm_io::probe as probe_apm;
 use super::mbr_io::MasterBootRecordReadExt;
 use super::scheme_io::PartitionTableReadExt;
 use crate::{PartitionTable, MasterBootRecord, Error, PartitionSchemeType, Result};
@@ -20,6 +21,24 @@ where
     detected
 }
 
+/// Reports whether the disk also carries an Apple Partition Map and restores
+/// the source position.
+///
+/// Hybrid media put the APM driver descriptor in the MBR bootstrap area, so
+/// this is independent of [`detect`]: an image can report an MBR, GPT, or
+/// hybrid table and still carry an APM, which
+/// [`ApmDiskReadExt`](super::apm_io::ApmDiskReadExt) reads.
+#[cfg(all(feature = "alloc", feature = "read"))]
+pub async fn detect_apm<R>(source: &mut R) -> Result<bool>
+where
+    R: Read + Seek<Error = <R as Read>::Error>,
+{
+    let original = source.stream_position().await.map_err(Error::from)?;
+    let detected = probe_apm(source).await;
+    source.seek(SeekFrom::Start(original)).await.map_err(Error::from)?;
+    detected
+}
+
 /// Opens and validates an MBR, GPT, or hybrid partition table.
 #[cfg(all(feature = "alloc", feature = "read"))]
 pub async fn open<R>(source: &mut R, logical_block_size: u32) -> Result<PartitionTable>
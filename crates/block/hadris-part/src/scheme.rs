@@ -13,9 +13,11 @@ use alloc::vec::Vec;
 
 #[cfg(feature = "alloc")]
 use crate::error::{Error, Result};
+#[cfg(feature = "alloc")]
+use crate::geometry::{DiskGeometry, PartitionMove};
 use crate::gpt::Guid;
 #[cfg(feature = "alloc")]
-use crate::gpt::{GptHeader, GptPartitionEntry};
+use crate::gpt::{GptCopy, GptHeader, GptPartitionEntry};
 use crate::hybrid::is_hybrid_mbr;
 use crate::mbr::MasterBootRecord;
 #[cfg(feature = "alloc")]
@@ -176,6 +178,73 @@ impl GptDisk {
         }
     }
 
+    /// Builds a disk from one intact GPT copy, regenerating the other
+    /// header from it.
+    ///
+    /// A regenerated backup keeps its entry array directly before the
+    /// header at `alternate_lba`; a regenerated primary places its entry
+    /// array at LBA 2. Fails if the regenerated entry array would overlap
+    /// the usable LBA range.
+    pub fn from_copy(
+        copy: GptCopy,
+        header: GptHeader,
+        entries: Vec<GptPartitionEntry>,
+        block_size: u32,
+    ) -> Result<Self> {
+        let entry_blocks =
+            header
+                .entry_array_blocks(block_size)
+                .ok_or(Error::InvalidPartitionEntrySize {
+                    size: header.size_of_partition_entry.to_ne(),
+                })?;
+        let (primary_header, backup_header) = match copy {
+            GptCopy::Primary => {
+                let entries_lba = header
+                    .alternate_lba
+                    .to_ne()
+                    .checked_sub(entry_blocks)
+                    .filter(|&lba| lba > header.last_usable_lba.to_ne())
+                    .ok_or(Error::BackupHeaderMismatch)?;
+                (header, header.to_alternate(entries_lba))
+            }
+            GptCopy::Backup => {
+                if entry_blocks.saturating_add(2) > header.first_usable_lba.to_ne() {
+                    return Err(Error::invalid_data(
+                        "primary GPT entry array would overlap the first usable LBA",
+                    ));
+                }
+                (header.to_alternate(2), header)
+            }
+        };
+        Ok(Self {
+            primary_header,
+            backup_header,
+            entries,
+            block_size,
+        })
+    }
+
+    /// Returns whether `backup` is the mirror of `primary`: same disk
+    /// geometry, GUID, and entry array, with swapped LBAs and the backup
+    /// entry array directly before the backup header.
+    #[cfg(all(feature = "read", any(feature = "sync", feature = "async")))]
+    pub(crate) fn headers_mirror(primary: &GptHeader, backup: &GptHeader, block_size: u32) -> bool {
+        let expected_backup_entries_lba = primary
+            .entry_array_blocks(block_size)
+            .and_then(|blocks| primary.alternate_lba.to_ne().checked_sub(blocks));
+        backup.my_lba == primary.alternate_lba
+            && backup.alternate_lba == primary.my_lba
+            && backup.revision == primary.revision
+            && backup.header_size == primary.header_size
+            && backup.first_usable_lba == primary.first_usable_lba
+            && backup.last_usable_lba == primary.last_usable_lba
+            && backup.disk_guid == primary.disk_guid
+            && backup.num_partition_entries == primary.num_partition_entries
+            && backup.size_of_partition_entry == primary.size_of_partition_entry
+            && backup.partition_entry_array_crc32 == primary.partition_entry_array_crc32
+            && Some(backup.partition_entry_lba.to_ne()) == expected_backup_entries_lba
+    }
+
     /// Returns the number of used (non-empty) partition entries.
     pub fn partition_count(&self) -> usize {
         self.entries.iter().filter(|e| !e.is_unused()).count()
@@ -292,8 +361,270 @@ impl GptDisk {
 
     /// Creates a protective MBR for this GPT disk.
     pub fn create_protective_mbr(&self) -> MasterBootRecord {
-        let disk_sectors = self.backup_header.my_lba.to_ne().saturating_add(1);
-        MasterBootRecord::protective(disk_sectors)
+        MasterBootRecord::protective(self.disk_blocks())
+    }
+
+    /// Returns the disk size in blocks, as implied by the backup header
+    /// sitting at the last LBA.
+    pub fn disk_blocks(&self) -> u64 {
+        self.backup_header.my_lba.to_ne().saturating_add(1)
+    }
+
+    /// Moves the backup GPT to the end of a disk of `geometry.total_blocks`
+    /// blocks.
+    ///
+    /// Growing a disk image leaves the backup GPT in the middle of it. This
+    /// places the backup header at the new last LBA with its entry array
+    /// directly before it, and moves `last_usable_lba` to match. Shrinking
+    /// fails if a partition would end past the new last usable LBA.
+    pub fn resize_disk(&mut self, geometry: &DiskGeometry) -> Result<()> {
+        if geometry.block_size != self.block_size {
+            return Err(Error::invalid_input(
+                "geometry block size differs from the disk's",
+            ));
+        }
+        let num_entries = self.primary_header.num_partition_entries.to_ne();
+        let entry_size = self.primary_header.size_of_partition_entry.to_ne();
+        let entry_blocks = self
+            .primary_header
+            .entry_array_blocks(self.block_size)
+            .unwrap_or(u64::MAX);
+        let first_usable = self.primary_header.first_usable_lba.to_ne();
+        let required = first_usable.saturating_add(entry_blocks).saturating_add(2);
+        if geometry.total_blocks < required {
+            return Err(Error::DiskTooSmall {
+                required,
+                available: geometry.total_blocks,
+            });
+        }
+
+        let last_lba = geometry.total_blocks - 1;
+        let mut next = self.clone();
+        next.primary_header.alternate_lba = Le::<u64>::from_ne(last_lba);
+        next.primary_header.last_usable_lba =
+            Le::<u64>::from_ne(geometry.gpt_last_usable_lba(num_entries, entry_size));
+        next.backup_header = next.primary_header.to_alternate(last_lba - entry_blocks);
+        self.commit(next)
+    }
+
+    /// Grows partition `index` up to the next partition or the aligned end
+    /// of the usable area ([`DiskGeometry::gpt_last_usable_lba_aligned`]),
+    /// whichever comes first.
+    ///
+    /// Returns the new last LBA. A partition that already reaches that far
+    /// is left unchanged.
+    pub fn grow_to_fill(&mut self, index: usize, geometry: &DiskGeometry) -> Result<u64> {
+        self.check_geometry(geometry)?;
+        let entry = self.used_entry(index)?;
+        let first = entry.first_lba.to_ne();
+        let last = entry.last_lba.to_ne();
+
+        let mut limit = geometry
+            .gpt_last_usable_lba_aligned(
+                self.primary_header.num_partition_entries.to_ne(),
+                self.primary_header.size_of_partition_entry.to_ne(),
+            )
+            .min(self.primary_header.last_usable_lba.to_ne());
+        for (i, other) in self.partitions() {
+            let other_first = other.first_lba.to_ne();
+            if i != index && other_first > first {
+                limit = limit.min(other_first - 1);
+            }
+        }
+        if limit <= last {
+            return Ok(last);
+        }
+
+        let mut next = self.clone();
+        next.entries[index].last_lba = Le::<u64>::from_ne(limit);
+        self.commit(next)?;
+        Ok(limit)
+    }
+
+    /// Grows or shrinks partition `index` to `size_sectors`, keeping its
+    /// first LBA.
+    ///
+    /// The size is rounded up so the partition ends on the geometry's
+    /// default alignment boundary. Returns the new last LBA.
+    pub fn resize_partition(
+        &mut self,
+        index: usize,
+        size_sectors: u64,
+        geometry: &DiskGeometry,
+    ) -> Result<u64> {
+        self.check_geometry(geometry)?;
+        let entry = self.used_entry(index)?;
+        let first = entry.first_lba.to_ne();
+        let end = geometry.align_up(
+            first.saturating_add(size_sectors.max(1)),
+            geometry.default_alignment(),
+        );
+        let last = end - 1;
+
+        let mut next = self.clone();
+        next.entries[index].last_lba = Le::<u64>::from_ne(last);
+        self.commit(next)?;
+        Ok(last)
+    }
+
+    /// Moves partition `index` to start at `first_lba`, keeping its size.
+    ///
+    /// `first_lba` must be aligned to the geometry's default alignment.
+    /// Returns the data move the caller must perform.
+    pub fn move_partition(
+        &mut self,
+        index: usize,
+        first_lba: u64,
+        geometry: &DiskGeometry,
+    ) -> Result<PartitionMove> {
+        self.check_geometry(geometry)?;
+        let entry = self.used_entry(index)?;
+        let alignment = geometry.default_alignment();
+        if !geometry.is_aligned(first_lba, alignment) {
+            return Err(Error::MisalignedPartition {
+                lba: first_lba,
+                required_alignment: alignment,
+            });
+        }
+        let sectors = entry.size_sectors();
+        let Some(last_lba) = first_lba.checked_add(sectors - 1) else {
+            return Err(Error::PartitionOutOfBounds {
+                index,
+                partition_end: u64::MAX,
+                disk_end: self.primary_header.last_usable_lba.to_ne(),
+            });
+        };
+
+        let mut next = self.clone();
+        next.entries[index].first_lba = Le::<u64>::from_ne(first_lba);
+        next.entries[index].last_lba = Le::<u64>::from_ne(last_lba);
+        self.commit(next)?;
+        Ok(PartitionMove {
+            index,
+            from_lba: entry.first_lba.to_ne(),
+            to_lba: first_lba,
+            sectors,
+        })
+    }
+
+    /// Changes the number of partition entries in both entry arrays.
+    ///
+    /// The usable area is recomputed around the new arrays, and partitions
+    /// the larger primary array now covers are shifted up, in LBA order, to
+    /// the next aligned free position. Returns the planned data moves in
+    /// ascending LBA order; apply them from last to first, since each one
+    /// may overlap the partition after it.
+    pub fn set_entry_count(
+        &mut self,
+        count: u32,
+        geometry: &DiskGeometry,
+    ) -> Result<Vec<PartitionMove>> {
+        self.check_geometry(geometry)?;
+        if let Some(highest) = self.entries.iter().rposition(|e| !e.is_unused())
+            && highest >= count as usize
+        {
+            return Err(Error::TooManyPartitions {
+                max: count as usize,
+                requested: highest + 1,
+            });
+        }
+
+        for (index, _) in self.partitions() {
+            self.used_entry(index)?;
+        }
+
+        let mut next = self.clone();
+        next.entries
+            .resize(count as usize, GptPartitionEntry::default());
+        next.primary_header.num_partition_entries = Le::<u32>::from_ne(count);
+        let entry_blocks = next
+            .primary_header
+            .entry_array_blocks(self.block_size)
+            .unwrap_or(u64::MAX);
+        let first_usable = next
+            .primary_header
+            .partition_entry_lba
+            .to_ne()
+            .saturating_add(entry_blocks);
+        let last_usable = geometry
+            .gpt_last_usable_lba(count, next.primary_header.size_of_partition_entry.to_ne());
+        if last_usable < first_usable {
+            return Err(Error::DiskTooSmall {
+                required: first_usable.saturating_add(entry_blocks).saturating_add(2),
+                available: geometry.total_blocks,
+            });
+        }
+        next.primary_header.first_usable_lba = Le::<u64>::from_ne(first_usable);
+        next.primary_header.last_usable_lba = Le::<u64>::from_ne(last_usable);
+        next.backup_header = next
+            .primary_header
+            .to_alternate(self.disk_blocks() - 1 - entry_blocks);
+
+        let mut order: Vec<usize> = next.partitions().map(|(i, _)| i).collect();
+        order.sort_by_key(|&i| next.entries[i].first_lba.to_ne());
+        let alignment = geometry.default_alignment();
+        let mut moves = Vec::new();
+        let mut free = first_usable;
+        for index in order {
+            let entry = &mut next.entries[index];
+            let first = entry.first_lba.to_ne();
+            let sectors = entry.size_sectors();
+            if first < free {
+                let to_lba = geometry.align_up(free, alignment);
+                entry.first_lba = Le::<u64>::from_ne(to_lba);
+                entry.last_lba = Le::<u64>::from_ne(to_lba.saturating_add(sectors - 1));
+                moves.push(PartitionMove {
+                    index,
+                    from_lba: first,
+                    to_lba,
+                    sectors,
+                });
+            }
+            free = entry.last_lba.to_ne().saturating_add(1);
+        }
+
+        self.commit(next)?;
+        Ok(moves)
+    }
+
+    /// Checks that `geometry` describes this disk.
+    fn check_geometry(&self, geometry: &DiskGeometry) -> Result<()> {
+        if geometry.block_size != self.block_size {
+            return Err(Error::invalid_input(
+                "geometry block size differs from the disk's",
+            ));
+        }
+        if geometry.total_blocks != self.disk_blocks() {
+            return Err(Error::invalid_input(
+                "geometry disk size differs from the GPT's; call resize_disk first",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the used entry at `index`.
+    ///
+    /// An entry that ends before it starts is rejected as invalid data.
+    fn used_entry(&self, index: usize) -> Result<GptPartitionEntry> {
+        let entry = self
+            .entries
+            .get(index)
+            .filter(|e| !e.is_unused())
+            .copied()
+            .ok_or(Error::invalid_input("no partition at the given index"))?;
+        if entry.last_lba.to_ne() < entry.first_lba.to_ne() {
+            return Err(Error::invalid_data("partition ends before it starts"));
+        }
+        Ok(entry)
+    }
+
+    /// Replaces this disk with `next` once its CRCs are refreshed and its
+    /// layout validates.
+    fn commit(&mut self, mut next: Self) -> Result<()> {
+        next.update_crcs();
+        next.validate()?;
+        *self = next;
+        Ok(())
     }
 }
 
@@ -302,6 +633,9 @@ impl GptDisk {
 #[derive(Debug, Clone)]
 pub enum PartitionTable {
     /// Pure MBR partitioning.
+    ///
+    /// Only the primary table is held; read logical partitions with
+    /// [`MbrDisk`](crate::mbr::MbrDisk).
     Mbr(MasterBootRecord),
     /// GPT partitioning with protective MBR.
     Gpt {
@@ -460,6 +794,26 @@ mod tests {
         assert_eq!(disk.partition_count(), 0);
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_gpt_from_backup_copy_keeps_entries_before_first_usable() {
+        let disk = GptDisk::new(1000000, 512);
+        let rebuilt = GptDisk::from_copy(
+            GptCopy::Backup,
+            disk.backup_header,
+            disk.entries.clone(),
+            512,
+        )
+        .unwrap();
+        assert_eq!(rebuilt.primary_header.partition_entry_lba.to_ne(), 2);
+        assert_eq!(rebuilt.primary_header.my_lba.to_ne(), 1);
+
+        // 128 entries need 32 blocks at LBA 2, running into LBA 20.
+        let mut header = disk.backup_header;
+        header.first_usable_lba = Le::<u64>::from_ne(20);
+        assert!(GptDisk::from_copy(GptCopy::Backup, header, disk.entries, 512).is_err());
+    }
+
     #[cfg(feature = "alloc")]
     #[test]
     fn test_scheme_detection() {
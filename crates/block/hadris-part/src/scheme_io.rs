@@ -12,7 +12,9 @@ use super::super::Write;
 #[cfg(all(feature = "alloc", any(feature = "read", feature = "write")))]
 use crate::error::{Error, Result};
 #[cfg(all(feature = "alloc", feature = "read"))]
-use crate::gpt::{GptHeader, GptPartitionEntry};
+use crate::gpt::{GptCopyStatus, GptHeader, GptHealth, GptPartitionEntry};
+#[cfg(all(feature = "alloc", feature = "read"))]
+use crate::gpt::GptCopy;
 #[cfg(all(feature = "alloc", feature = "read"))]
 use crate::mbr::MasterBootRecord;
 #[cfg(all(feature = "alloc", feature = "read"))]
@@ -56,6 +58,85 @@ pub trait GptDiskReadExt: Sized {
         reader: &mut R,
         block_size: u32,
     ) -> Result<Self>;
+
+    /// Reads a GPT disk from whichever copy is intact.
+    ///
+    /// Checks the primary copy at LBA 1 and the backup at the primary's
+    /// `alternate_lba` (or the last LBA when the primary is damaged):
+    /// signature, header and entry-array CRCs (with the `crc` feature), and
+    /// layout. The disk is built from the authoritative copy reported in
+    /// the returned [`GptHealth`], and can be passed to
+    /// [`GptDiskWriteExt::repair`] to rewrite the damaged one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error::Io`] of kind `InvalidData` if neither copy is
+    /// intact, or an error if the disk size cannot be determined or the
+    /// damaged copy cannot be rebuilt from the intact one.
+    async fn read_checked<R: Read + Seek>(
+        reader: &mut R,
+        block_size: u32,
+    ) -> Result<(Self, GptHealth)>;
+}
+
+/// Reads and checks the GPT copy whose header is at `lba`.
+#[cfg(all(feature = "alloc", feature = "read"))]
+async fn read_copy<R: Read + Seek>(
+    reader: &mut R,
+    lba: u64,
+    block_size: u32,
+    image_len: u64,
+) -> core::result::Result<(GptHeader, alloc::vec::Vec<GptPartitionEntry>), GptCopyStatus> {
+    let header = match GptHeader::read_from_lba(reader, lba, block_size).await {
+        Ok(header) => header,
+        Err(Error::InvalidGptSignature { .. }) => return Err(GptCopyStatus::InvalidSignature),
+        Err(_) => return Err(GptCopyStatus::Unreadable),
+    };
+    #[cfg(feature = "crc")]
+    if !header.verify_crc32() {
+        return Err(GptCopyStatus::HeaderCrcMismatch);
+    }
+
+    let entry_size = header.size_of_partition_entry.to_ne();
+    let num_entries = header.num_partition_entries.to_ne();
+    let entries_start = header
+        .partition_entry_lba
+        .to_ne()
+        .checked_mul(u64::from(block_size));
+    let entries_end = entries_start
+        .and_then(|start| start.checked_add(u64::from(num_entries) * u64::from(entry_size)));
+    let (Some(entries_start), Some(entries_end)) = (entries_start, entries_end) else {
+        return Err(GptCopyStatus::InvalidLayout);
+    };
+    if header.my_lba.to_ne() != lba
+        || entry_size != GptHeader::STANDARD_ENTRY_SIZE
+        || entries_end > image_len
+    {
+        return Err(GptCopyStatus::InvalidLayout);
+    }
+
+    reader
+        .seek(SeekFrom::Start(entries_start))
+        .await
+        .map_err(|_| GptCopyStatus::Unreadable)?;
+    let mut entries = alloc::vec::Vec::with_capacity(num_entries as usize);
+    for _ in 0..num_entries {
+        let mut buf = [0u8; 128];
+        reader
+            .read_exact(&mut buf)
+            .await
+            .map_err(|_| GptCopyStatus::Unreadable)?;
+        entries.push(bytemuck::cast(buf));
+    }
+
+    #[cfg(feature = "crc")]
+    if header.partition_entry_array_crc32.to_ne()
+        != crate::gpt::calculate_partition_array_crc32(&entries)
+    {
+        return Err(GptCopyStatus::EntriesCrcMismatch);
+    }
+
+    Ok((header, entries))
 }
 
 #[cfg(all(feature = "alloc", feature = "read"))]
@@ -173,27 +254,7 @@ impl GptDiskReadExt for GptDisk {
             });
         }
 
-        let entry_array_bytes = u64::from(primary_header.num_partition_entries.to_ne())
-            .checked_mul(u64::from(primary_header.size_of_partition_entry.to_ne()))
-            .ok_or(Error::BackupHeaderMismatch)?;
-        let entry_array_blocks = entry_array_bytes.div_ceil(u64::from(block_size));
-        let expected_backup_entries_lba = backup_lba
-            .checked_sub(entry_array_blocks)
-            .ok_or(Error::BackupHeaderMismatch)?;
-
-        if backup_header.my_lba != primary_header.alternate_lba
-            || backup_header.alternate_lba != primary_header.my_lba
-            || backup_header.revision != primary_header.revision
-            || backup_header.header_size != primary_header.header_size
-            || backup_header.first_usable_lba != primary_header.first_usable_lba
-            || backup_header.last_usable_lba != primary_header.last_usable_lba
-            || backup_header.disk_guid != primary_header.disk_guid
-            || backup_header.num_partition_entries != primary_header.num_partition_entries
-            || backup_header.size_of_partition_entry != primary_header.size_of_partition_entry
-            || backup_header.partition_entry_array_crc32
-                != primary_header.partition_entry_array_crc32
-            || backup_header.partition_entry_lba.to_ne() != expected_backup_entries_lba
-        {
+        if !GptDisk::headers_mirror(&primary_header, &backup_header, block_size) {
             return Err(Error::BackupHeaderMismatch);
         }
 
@@ -204,6 +265,66 @@ impl GptDiskReadExt for GptDisk {
             block_size,
         })
     }
+
+    async fn read_checked<R: Read + Seek>(
+        reader: &mut R,
+        block_size: u32,
+    ) -> Result<(Self, GptHealth)> {
+        if block_size < GptHeader::STANDARD_HEADER_SIZE {
+            return Err(Error::InvalidBlockSize {
+                size: block_size,
+                minimum: GptHeader::STANDARD_HEADER_SIZE,
+            });
+        }
+        let image_len = reader
+            .seek(SeekFrom::End(0))
+            .await
+            .map_err(Error::from)?;
+        let disk_blocks = image_len / u64::from(block_size);
+        if disk_blocks < 3 {
+            return Err(Error::DiskTooSmall {
+                required: 3,
+                available: disk_blocks,
+            });
+        }
+
+        let primary = read_copy(reader, 1, block_size, image_len).await;
+        let backup_lba = match &primary {
+            Ok((header, _)) => header.alternate_lba.to_ne(),
+            Err(_) => disk_blocks - 1,
+        };
+        let mut backup = read_copy(reader, backup_lba, block_size, image_len).await;
+        if let (Ok((primary_header, _)), Ok((backup_header, _))) = (&primary, &backup)
+            && !Self::headers_mirror(primary_header, backup_header, block_size)
+        {
+            backup = Err(GptCopyStatus::Mismatch);
+        }
+
+        let status = |copy: &core::result::Result<_, GptCopyStatus>| match copy {
+            Ok(_) => GptCopyStatus::Valid,
+            Err(status) => *status,
+        };
+        let health = GptHealth {
+            primary: status(&primary),
+            backup: status(&backup),
+        };
+        let disk = match (primary, backup) {
+            (Ok((primary_header, entries)), Ok((backup_header, _))) => Self {
+                primary_header,
+                backup_header,
+                entries,
+                block_size,
+            },
+            (Ok((header, entries)), Err(_)) => {
+                Self::from_copy(GptCopy::Primary, header, entries, block_size)?
+            }
+            (Err(_), Ok((header, entries))) => {
+                Self::from_copy(GptCopy::Backup, header, entries, block_size)?
+            }
+            (Err(_), Err(_)) => return Err(Error::invalid_data("neither GPT copy is intact")),
+        };
+        Ok((disk, health))
+    }
 }
 
 /// Extension trait for writing [`GptDisk`] to I/O sinks.
@@ -245,72 +366,36 @@ pub trait GptDiskWriteExt {
         writer: &mut W,
         mbr: &MasterBootRecord,
     ) -> Result<()>;
+
+    /// Writes one GPT copy: its header and partition entry array.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    async fn write_copy_to<W: Write + Seek>(&self, writer: &mut W, copy: GptCopy) -> Result<()>;
+
+    /// Rewrites the copy `health` reports as damaged from this disk.
+    ///
+    /// `self` and `health` are the result of
+    /// [`GptDiskReadExt::read_checked`]. Returns the rewritten copy, or
+    /// `None` if there was nothing to repair. The MBR at LBA 0 is left
+    /// untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    async fn repair<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        health: GptHealth,
+    ) -> Result<Option<GptCopy>>;
 }
 
 #[cfg(all(feature = "alloc", feature = "write"))]
 impl GptDiskWriteExt for GptDisk {
     async fn write_to<W: Write + Seek>(&self, writer: &mut W) -> Result<()> {
-        // Write protective MBR at LBA 0
-        writer
-            .seek(SeekFrom::Start(0))
-            .await
-            .map_err(Error::from)?;
-        let protective_mbr = self.create_protective_mbr();
-        protective_mbr.write_to(writer).await?;
-
-        // Write primary header at LBA 1
-        self.primary_header
-            .write_to_lba(writer, 1, self.block_size)
-            .await?;
-
-        // Write primary partition entries starting at partition_entry_lba
-        let Some(primary_entries_offset) = self
-            .primary_header
-            .partition_entry_lba
-            .to_ne()
-            .checked_mul(u64::from(self.block_size))
-        else {
-            return Err(Error::lba_offset_overflow());
-        };
-        writer
-            .seek(SeekFrom::Start(primary_entries_offset))
+        self.write_to_with_mbr(writer, &self.create_protective_mbr())
             .await
-            .map_err(Error::from)?;
-
-        for entry in &self.entries {
-            writer
-                .write_all(bytemuck::bytes_of(entry))
-                .await
-                .map_err(Error::from)?;
-        }
-
-        // Write backup partition entries
-        let Some(backup_entries_offset) = self
-            .backup_header
-            .partition_entry_lba
-            .to_ne()
-            .checked_mul(u64::from(self.block_size))
-        else {
-            return Err(Error::lba_offset_overflow());
-        };
-        writer
-            .seek(SeekFrom::Start(backup_entries_offset))
-            .await
-            .map_err(Error::from)?;
-
-        for entry in &self.entries {
-            writer
-                .write_all(bytemuck::bytes_of(entry))
-                .await
-                .map_err(Error::from)?;
-        }
-
-        // Write backup header at last LBA
-        self.backup_header
-            .write_to_lba(writer, self.backup_header.my_lba.to_ne(), self.block_size)
-            .await?;
-
-        Ok(())
     }
 
     async fn write_to_with_mbr<W: Write + Seek>(
@@ -325,35 +410,18 @@ impl GptDiskWriteExt for GptDisk {
             .map_err(Error::from)?;
         mbr.write_to(writer).await?;
 
-        // Write primary header at LBA 1
-        self.primary_header
-            .write_to_lba(writer, 1, self.block_size)
-            .await?;
+        self.write_copy_to(writer, GptCopy::Primary).await?;
+        self.write_copy_to(writer, GptCopy::Backup).await
+    }
 
-        // Write primary partition entries
-        let Some(primary_entries_offset) = self
-            .primary_header
-            .partition_entry_lba
-            .to_ne()
-            .checked_mul(u64::from(self.block_size))
-        else {
-            return Err(Error::lba_offset_overflow());
+    async fn write_copy_to<W: Write + Seek>(&self, writer: &mut W, copy: GptCopy) -> Result<()> {
+        let header = match copy {
+            GptCopy::Primary => &self.primary_header,
+            GptCopy::Backup => &self.backup_header,
         };
-        writer
-            .seek(SeekFrom::Start(primary_entries_offset))
-            .await
-            .map_err(Error::from)?;
 
-        for entry in &self.entries {
-            writer
-                .write_all(bytemuck::bytes_of(entry))
-                .await
-                .map_err(Error::from)?;
-        }
-
-        // Write backup partition entries
-        let Some(backup_entries_offset) = self
-            .backup_header
+        // Write the partition entries starting at partition_entry_lba
+        let Some(entries_offset) = header
             .partition_entry_lba
             .to_ne()
             .checked_mul(u64::from(self.block_size))
@@ -361,7 +429,7 @@ impl GptDiskWriteExt for GptDisk {
             return Err(Error::lba_offset_overflow());
         };
         writer
-            .seek(SeekFrom::Start(backup_entries_offset))
+            .seek(SeekFrom::Start(entries_offset))
             .await
             .map_err(Error::from)?;
 
@@ -372,12 +440,22 @@ impl GptDiskWriteExt for GptDisk {
                 .map_err(Error::from)?;
         }
 
-        // Write backup header at last LBA
-        self.backup_header
-            .write_to_lba(writer, self.backup_header.my_lba.to_ne(), self.block_size)
-            .await?;
+        // Write the header at its own LBA
+        header
+            .write_to_lba(writer, header.my_lba.to_ne(), self.block_size)
+            .await
+    }
 
-        Ok(())
+    async fn repair<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        health: GptHealth,
+    ) -> Result<Option<GptCopy>> {
+        let Some(copy) = health.damaged() else {
+            return Ok(None);
+        };
+        self.write_copy_to(writer, copy).await?;
+        Ok(Some(copy))
     }
 }
 
@@ -10,8 +10,10 @@ use hadris_io::Cursor;
 use hadris_io::ErrorKind;
 use hadris_part::hybrid::HybridMbrBuilder;
 use hadris_part::{
-    Error, GptHeader, GptHeaderReadExt, GptHeaderWriteExt, GptPartitionEntry, Guid,
-    MasterBootRecord, MasterBootRecordReadExt, MasterBootRecordWriteExt, MbrPartition,
+    ApmDisk, ApmDiskReadExt, ApmDiskWriteExt, ApmEntry, DriverDescriptorMap, Error, GptCopy,
+    GptCopyStatus, GptDisk, GptDiskReadExt, GptDiskWriteExt, GptHeader, GptHeaderReadExt,
+    GptHeaderWriteExt, GptPartitionEntry, Guid, MasterBootRecord, MasterBootRecordReadExt,
+    MasterBootRecordWriteExt, MbrDisk, MbrDiskReadExt, MbrDiskWriteExt, MbrPartition,
     MbrPartitionType, PartitionSchemeType, PartitionTable, PartitionTableReadExt,
     PartitionTableWriteExt,
 };
@@ -71,6 +73,129 @@ fn disk_partition_scheme_reads_mbr() {
     assert_eq!(parts[0].size_sectors, 204800);
 }
 
+fn logical_disk() -> MbrDisk {
+    let mut disk = MbrDisk::new(512);
+    disk.mbr.with_partition_table(|table| {
+        table[0] = MbrPartition::new(MbrPartitionType::Fat32, 64, 64);
+    });
+    disk.create_extended(1, 128, 384).unwrap();
+    disk.add_logical(MbrPartitionType::Fat32, 160, 64).unwrap();
+    disk.add_logical(MbrPartitionType::LinuxNative, 256, 64)
+        .unwrap();
+    disk.add_logical(MbrPartitionType::LinuxSwap, 400, 100)
+        .unwrap();
+    disk
+}
+
+#[test]
+fn mbr_logical_partitions_roundtrip() {
+    let disk = logical_disk();
+    let mut image = StdCursor::new(vec![0_u8; 512 * 512]);
+    disk.write_to(&mut image).unwrap();
+
+    let read = MbrDisk::read_from(&mut image, 512).unwrap();
+    read.validate().unwrap();
+    assert_eq!(read.logical.len(), 3);
+    for (written, read) in disk.logical.iter().zip(&read.logical) {
+        assert_eq!(written.ebr_lba, read.ebr_lba);
+        assert_eq!(
+            bytemuck::bytes_of(&written.partition),
+            bytemuck::bytes_of(&read.partition)
+        );
+    }
+    assert_eq!(read.partition(5).unwrap().start_lba.to_ne(), 256);
+    assert!(read.partition(7).is_none());
+
+    let summary: Vec<_> = read
+        .partitions()
+        .map(|(i, p)| (i, p.start_lba.to_ne(), p.sector_count.to_ne()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (0, 64, 64),
+            (1, 128, 384),
+            (4, 160, 64),
+            (5, 256, 64),
+            (6, 400, 100)
+        ]
+    );
+
+    // The unified table lists only the primary slots.
+    let scheme = PartitionTable::read_from(&mut image, 512).unwrap();
+    let indices: Vec<_> = scheme.partitions().iter().map(|p| p.index).collect();
+    assert_eq!(indices, [0, 1]);
+}
+
+#[test]
+fn mbr_empty_extended_partition_has_terminating_ebr() {
+    let mut disk = MbrDisk::new(512);
+    disk.create_extended(0, 8, 56).unwrap();
+    let mut image = StdCursor::new(vec![0_u8; 64 * 512]);
+    disk.write_to(&mut image).unwrap();
+    assert_eq!(image.get_ref()[8 * 512 + 510..8 * 512 + 512], [0x55, 0xAA]);
+    assert!(
+        MbrDisk::read_from(&mut image, 512)
+            .unwrap()
+            .logical
+            .is_empty()
+    );
+}
+
+#[test]
+fn mbr_hostile_ebr_chains_are_rejected() {
+    let disk = logical_disk();
+    let mut image = StdCursor::new(vec![0_u8; 512 * 512]);
+    disk.write_to(&mut image).unwrap();
+    let link_offset = |ebr_lba: usize| ebr_lba * 512 + 446 + 16 + 8;
+
+    // The last EBR links back to the head of the chain.
+    let mut looped = image.clone();
+    let last = disk.logical[2].ebr_lba as usize;
+    looped.get_mut()[last * 512 + 446 + 16 + 4] = MbrPartitionType::Extended.to_u8();
+    looped.get_mut()[link_offset(last)..link_offset(last) + 4]
+        .copy_from_slice(&0_u32.to_le_bytes());
+    assert!(matches!(
+        MbrDisk::read_from(&mut looped, 512),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+    // The primary table stays readable through the unified API.
+    let scheme = PartitionTable::read_from(&mut looped, 512).unwrap();
+    assert_eq!(scheme.partitions().len(), 2);
+
+    // A link pointing past the end of the extended partition.
+    let mut escaped = image.clone();
+    let head = disk.logical[0].ebr_lba as usize;
+    escaped.get_mut()[link_offset(head)..link_offset(head) + 4]
+        .copy_from_slice(&1000_u32.to_le_bytes());
+    assert!(matches!(
+        MbrDisk::read_from(&mut escaped, 512),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+
+    // A logical partition grown over the next EBR.
+    let mut overlapping = image.clone();
+    let count = head * 512 + 446 + 12;
+    overlapping.get_mut()[count..count + 4].copy_from_slice(&200_u32.to_le_bytes());
+    assert!(MbrDisk::read_from(&mut overlapping, 512).is_err());
+
+    // A link to an EBR without a signature.
+    let mut unsigned = image.clone();
+    let second = disk.logical[1].ebr_lba as usize;
+    unsigned.get_mut()[second * 512 + 510] = 0;
+    assert!(matches!(
+        MbrDisk::read_from(&mut unsigned, 512),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+
+    // A chain cut short by the end of the image keeps its I/O error.
+    let mut truncated = StdCursor::new(image.get_ref()[..second * 512 + 100].to_vec());
+    assert!(matches!(
+        MbrDisk::read_from(&mut truncated, 512),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::UnexpectedEof
+    ));
+}
+
 #[test]
 fn v2_partition_table_detect_and_open_restore_clear_lifecycle() {
     let mut mbr = MasterBootRecord::default();
@@ -134,6 +259,81 @@ fn gpt_scheme_sync_write_open_and_detect_roundtrip() {
     assert_eq!(partitions[1].size_sectors, 1648);
 }
 
+fn written_gpt() -> Vec<u8> {
+    let mut disk = StdCursor::new(vec![0_u8; 4096 * 512]);
+    populated_gpt().write_to(&mut disk).unwrap();
+    disk.into_inner()
+}
+
+#[test]
+fn gpt_repair_rebuilds_trashed_primary_from_backup() {
+    let pristine = written_gpt();
+    let mut disk = StdCursor::new(pristine.clone());
+    disk.get_mut()[512..1024].fill(0xFF);
+    assert!(GptDisk::read_from(&mut disk, 512).is_err());
+
+    let (gpt, health) = GptDisk::read_checked(&mut disk, 512).unwrap();
+    assert_eq!(health.primary, GptCopyStatus::InvalidSignature);
+    assert_eq!(health.backup, GptCopyStatus::Valid);
+    assert_eq!(health.authoritative(), Some(GptCopy::Backup));
+    assert_eq!(health.damaged(), Some(GptCopy::Primary));
+    assert_eq!(gpt.partition_count(), 2);
+    gpt.validate().unwrap();
+
+    assert_eq!(
+        gpt.repair(&mut disk, health).unwrap(),
+        Some(GptCopy::Primary)
+    );
+    assert_eq!(disk.get_ref(), &pristine);
+    let (_, health) = GptDisk::read_checked(&mut disk, 512).unwrap();
+    assert!(health.is_healthy());
+    assert_eq!(gpt.repair(&mut disk, health).unwrap(), None);
+}
+
+#[test]
+fn gpt_repair_rewrites_damaged_backup() {
+    let pristine = written_gpt();
+
+    // A flipped byte in the primary entry array fails its CRC.
+    let mut entries = StdCursor::new(pristine.clone());
+    entries.get_mut()[1024 + 56] ^= 0xFF;
+    let (_, health) = GptDisk::read_checked(&mut entries, 512).unwrap();
+    assert_eq!(health.primary, GptCopyStatus::EntriesCrcMismatch);
+    assert_eq!(health.authoritative(), Some(GptCopy::Backup));
+
+    // A zeroed backup header.
+    let mut backup = StdCursor::new(pristine.clone());
+    let last = pristine.len() - 512;
+    backup.get_mut()[last..].fill(0);
+    let (gpt, health) = GptDisk::read_checked(&mut backup, 512).unwrap();
+    assert_eq!(health.backup, GptCopyStatus::InvalidSignature);
+    assert_eq!(health.authoritative(), Some(GptCopy::Primary));
+    assert_eq!(
+        gpt.repair(&mut backup, health).unwrap(),
+        Some(GptCopy::Backup)
+    );
+    assert_eq!(backup.get_ref(), &pristine);
+
+    // An intact backup that describes a different disk GUID.
+    let mut mismatched = StdCursor::new(pristine.clone());
+    let mut header = GptHeader::read_from_lba(&mut mismatched, 4095, 512).unwrap();
+    header.disk_guid = Guid::from_bytes([0x33; 16]);
+    header.update_crc32();
+    header.write_to_lba(&mut mismatched, 4095, 512).unwrap();
+    let (_, health) = GptDisk::read_checked(&mut mismatched, 512).unwrap();
+    assert_eq!(health.backup, GptCopyStatus::Mismatch);
+    assert_eq!(health.damaged(), Some(GptCopy::Backup));
+
+    // With both copies gone there is nothing to recover from.
+    let mut lost = StdCursor::new(pristine);
+    lost.get_mut()[512..1024].fill(0);
+    lost.get_mut()[last + 8] ^= 0xFF;
+    assert!(matches!(
+        GptDisk::read_checked(&mut lost, 512),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+}
+
 #[test]
 fn sync_open_rejects_truncated_and_corrupt_gpt() {
     let scheme = populated_gpt();
@@ -219,3 +419,99 @@ fn hybrid_scheme_sync_write_open_roundtrip() {
     opened.validate().unwrap();
     assert_eq!(opened.partitions().len(), 2);
 }
+
+#[test]
+fn apm_sync_write_read_and_detect_roundtrip() {
+    let mut apm = ApmDisk::new(512, 4096);
+    apm.add_partition(ApmEntry::new(b"Mac", ApmEntry::TYPE_HFS, 64, 2000))
+        .unwrap();
+    apm.add_partition(ApmEntry::new(b"Extra", ApmEntry::TYPE_FREE, 2064, 2032))
+        .unwrap();
+
+    let mut disk = StdCursor::new(vec![0_u8; 4096 * 512]);
+    apm.write_to(&mut disk).unwrap();
+    assert_eq!(&disk.get_ref()[..2], b"ER");
+    assert_eq!(&disk.get_ref()[512..514], b"PM");
+    assert_eq!(&disk.get_ref()[3 * 512..3 * 512 + 2], b"PM");
+
+    disk.seek(SeekFrom::Start(1024)).unwrap();
+    assert!(hadris_part::sync::partition_table::detect_apm(&mut disk).unwrap());
+    assert_eq!(disk.stream_position().unwrap(), 1024);
+
+    let read = ApmDisk::read_from(&mut disk).unwrap();
+    read.validate().unwrap();
+    let parts: Vec<_> = read.partitions().collect();
+    assert_eq!(parts.len(), 1);
+    assert_eq!(read.sector_range(parts[0].1, 512), Some((64, 2063)));
+}
+
+#[test]
+fn apm_sharing_block_zero_with_mbr_is_detected() {
+    // ISO hybrid layout: 2048-byte APM blocks, DDM fields in the MBR
+    // bootstrap area, and an ordinary (non-protective) MBR partition.
+    let mut apm = ApmDisk::new(2048, 1024).with_map_blocks(2);
+    apm.add_partition(ApmEntry::new(
+        b"ISO9660",
+        ApmEntry::TYPE_ISO9660_DATA,
+        3,
+        1021,
+    ))
+    .unwrap();
+    let mut mbr = MasterBootRecord::default();
+    mbr.with_partition_table(|table| {
+        table[0] = MbrPartition::new(MbrPartitionType::Iso9660, 0, 4096);
+    });
+    mbr.bootstrap[..DriverDescriptorMap::HEADER_SIZE]
+        .copy_from_slice(&bytemuck::bytes_of(&apm.ddm)[..DriverDescriptorMap::HEADER_SIZE]);
+
+    let mut disk = StdCursor::new(vec![0_u8; 1024 * 2048]);
+    mbr.write_to(&mut disk).unwrap();
+    apm.write_entries_to(&mut disk).unwrap();
+
+    // BIOS firmware and partition-table detection still see the MBR...
+    assert_eq!(
+        hadris_part::sync::partition_table::detect(&mut disk).unwrap(),
+        PartitionSchemeType::Mbr
+    );
+    let opened = hadris_part::sync::partition_table::open(&mut disk, 512).unwrap();
+    assert_eq!(opened.scheme_type(), PartitionSchemeType::Mbr);
+    assert_eq!(opened.partitions().len(), 1);
+    // ...while the Apple map is reported and read separately.
+    assert!(hadris_part::sync::partition_table::detect_apm(&mut disk).unwrap());
+    let read = ApmDisk::read_from(&mut disk).unwrap();
+    assert_eq!(read.block_size(), 2048);
+    assert_eq!(read.entries.len(), 2);
+    assert_eq!(read.entries[1].name(), b"ISO9660");
+    assert_eq!(read.sector_range(&read.entries[1], 512), Some((12, 4095)));
+    read.validate().unwrap();
+}
+
+#[test]
+fn apm_open_rejects_corrupt_maps() {
+    let mut apm = ApmDisk::new(512, 128).with_map_blocks(4);
+    apm.add_partition(ApmEntry::new(b"A", ApmEntry::TYPE_HFS, 8, 16))
+        .unwrap();
+    let mut disk = StdCursor::new(vec![0_u8; 128 * 512]);
+    apm.write_to(&mut disk).unwrap();
+
+    let mut bad_entry = disk.clone();
+    bad_entry.get_mut()[2 * 512] = b'X';
+    assert!(matches!(
+        ApmDisk::read_from(&mut bad_entry),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+
+    // A map claiming more entries than the disk holds is refused before
+    // allocating.
+    let mut huge = disk.clone();
+    huge.get_mut()[512 + 4..512 + 8].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(matches!(
+        ApmDisk::read_from(&mut huge),
+        Err(Error::DiskTooSmall { .. })
+    ));
+
+    // Without the DDM the disk carries no APM.
+    let mut no_ddm = disk.clone();
+    no_ddm.get_mut()[0] = 0;
+    assert!(!hadris_part::sync::partition_table::detect_apm(&mut no_ddm).unwrap());
+}
@@ -1,5 +1,6 @@
 //! Integration tests for hadris-part: roundtrip, geometry, edge cases.
 
+use hadris_io::ErrorKind;
 use hadris_part::error::Error;
 use hadris_part::geometry::{DiskGeometry, validate_partition_alignment};
 use hadris_part::gpt::{GptPartitionEntry, Guid};
@@ -168,6 +169,197 @@ fn validate_alignment_misaligned() {
     ));
 }
 
+// ---------------------------------------------------------------------------
+// Resize / move planning
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "alloc")]
+fn gpt_with(extents: &[(u64, u64)]) -> hadris_part::GptDisk {
+    let mut gpt = hadris_part::GptDisk::new(8192, 512);
+    for (i, &(first, last)) in extents.iter().enumerate() {
+        gpt.add_partition(GptPartitionEntry::new(
+            Guid::LINUX_FILESYSTEM,
+            Guid::from_bytes([i as u8 + 1; 16]),
+            first,
+            last,
+        ))
+        .unwrap();
+    }
+    gpt
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn gpt_grow_disk_then_fill_last_partition() {
+    let mut gpt = gpt_with(&[(2048, 4095)]);
+    let small = DiskGeometry::standard(8192);
+    let large = DiskGeometry::standard(16384);
+
+    // The geometry must describe the disk the GPT was written for.
+    assert!(matches!(
+        gpt.grow_to_fill(0, &large),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidInput
+    ));
+
+    gpt.resize_disk(&large).unwrap();
+    assert_eq!(gpt.disk_blocks(), 16384);
+    assert_eq!(gpt.primary_header.alternate_lba.to_ne(), 16383);
+    assert_eq!(gpt.backup_header.my_lba.to_ne(), 16383);
+    assert_eq!(gpt.backup_header.partition_entry_lba.to_ne(), 16383 - 32);
+    assert_eq!(
+        gpt.primary_header.last_usable_lba.to_ne(),
+        large.gpt_last_usable_lba(128, 128)
+    );
+
+    let last = gpt.grow_to_fill(0, &large).unwrap();
+    assert_eq!(last, large.gpt_last_usable_lba_aligned(128, 128));
+    assert_eq!(last, 14335);
+    gpt.validate().unwrap();
+
+    // Shrinking back would cut the grown partition.
+    assert!(matches!(
+        gpt.resize_disk(&small),
+        Err(Error::PartitionOutOfBounds { index: 0, .. })
+    ));
+    assert_eq!(gpt.disk_blocks(), 16384);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn gpt_resize_and_move_respect_alignment_and_neighbours() {
+    let geometry = DiskGeometry::standard(8192);
+    let mut gpt = gpt_with(&[(2048, 2999), (4096, 6143)]);
+
+    assert!(matches!(
+        gpt.move_partition(0, 5000, &geometry),
+        Err(Error::MisalignedPartition { lba: 5000, .. })
+    ));
+    assert!(matches!(
+        gpt.move_partition(0, 4096, &geometry),
+        Err(Error::PartitionOverlap { .. })
+    ));
+    let planned = gpt.move_partition(0, 6144, &geometry).unwrap();
+    assert_eq!(
+        planned,
+        hadris_part::PartitionMove {
+            index: 0,
+            from_lba: 2048,
+            to_lba: 6144,
+            sectors: 952,
+        }
+    );
+    assert_eq!(gpt.entries[0].last_lba.to_ne(), 7095);
+
+    // Growth stops at the next partition and at the aligned end of the
+    // usable area; neither partition can grow here.
+    assert_eq!(gpt.grow_to_fill(1, &geometry).unwrap(), 6143);
+    assert_eq!(gpt.grow_to_fill(0, &geometry).unwrap(), 7095);
+    assert_eq!(gpt.resize_partition(1, 1, &geometry).unwrap(), 6143);
+    assert!(matches!(
+        gpt.resize_partition(1, 2049, &geometry),
+        Err(Error::PartitionOverlap { .. })
+    ));
+    assert!(matches!(
+        gpt.resize_partition(7, 1, &geometry),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidInput
+    ));
+    gpt.validate().unwrap();
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn gpt_entry_count_change_shifts_covered_partitions() {
+    let geometry = DiskGeometry::standard(8192);
+    let mut gpt = gpt_with(&[(3000, 3999), (34, 1033)]);
+
+    let moves = gpt.set_entry_count(256, &geometry).unwrap();
+    assert_eq!(gpt.primary_header.num_partition_entries.to_ne(), 256);
+    assert_eq!(gpt.entries.len(), 256);
+    assert_eq!(gpt.primary_header.first_usable_lba.to_ne(), 2 + 64);
+    assert_eq!(gpt.backup_header.partition_entry_lba.to_ne(), 8191 - 64);
+    assert_eq!(
+        moves,
+        [
+            hadris_part::PartitionMove {
+                index: 1,
+                from_lba: 34,
+                to_lba: 2048,
+                sectors: 1000,
+            },
+            hadris_part::PartitionMove {
+                index: 0,
+                from_lba: 3000,
+                to_lba: 4096,
+                sectors: 1000,
+            },
+        ]
+    );
+    gpt.validate().unwrap();
+
+    // Shrinking the array is refused while a higher slot is in use.
+    assert!(matches!(
+        gpt.set_entry_count(1, &geometry),
+        Err(Error::TooManyPartitions {
+            max: 1,
+            requested: 2
+        })
+    ));
+    assert!(gpt.set_entry_count(128, &geometry).unwrap().is_empty());
+    assert_eq!(gpt.primary_header.first_usable_lba.to_ne(), 34);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn gpt_resize_and_move_reject_inverted_entries() {
+    let geometry = DiskGeometry::standard(8192);
+    let mut gpt = gpt_with(&[(4096, 4096)]);
+    gpt.entries[0].last_lba = hadris_part::Le::<u64>::from_ne(2048);
+    gpt.update_crcs();
+
+    assert!(matches!(
+        gpt.move_partition(0, 6144, &geometry),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+    assert!(matches!(
+        gpt.set_entry_count(256, &geometry),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::InvalidData
+    ));
+    assert_eq!(gpt.entries[0].last_lba.to_ne(), 2048);
+    assert_eq!(gpt.primary_header.num_partition_entries.to_ne(), 128);
+}
+
+#[test]
+fn mbr_table_resize_and_move() {
+    let geometry = DiskGeometry::standard(16384);
+    let mut table = MbrPartitionTable::new();
+    table[0] = MbrPartition::new(MbrPartitionType::Fat32, 2048, 2048);
+    table[0].set_bootable(true);
+    table[1] = MbrPartition::new(MbrPartitionType::LinuxNative, 8192, 2048);
+
+    assert_eq!(table.grow_to_fill(0, &geometry).unwrap(), 8191);
+    assert!(table[0].is_bootable());
+    assert_eq!(table.grow_to_fill(1, &geometry).unwrap(), 16383);
+    assert_eq!(table[1].sector_count.to_ne(), 8192);
+
+    assert_eq!(table.resize_partition(0, 1, &geometry).unwrap(), 4095);
+    let planned = table.move_partition(1, 4096, &geometry).unwrap();
+    assert_eq!(
+        (planned.from_lba, planned.to_lba, planned.sectors),
+        (8192, 4096, 8192)
+    );
+    assert_eq!(table[1].start_lba.to_ne(), 4096);
+    assert_eq!(table[1].start_chs.as_lba(), 4096);
+    assert!(matches!(
+        table.move_partition(1, 2048, &geometry),
+        Err(Error::PartitionOverlap { .. })
+    ));
+    assert!(matches!(
+        table.resize_partition(1, 20000, &geometry),
+        Err(Error::PartitionOutOfBounds { index: 1, .. })
+    ));
+    table.validate_layout(&geometry).unwrap();
+}
+
 // ---------------------------------------------------------------------------
 // Edge cases
 // ---------------------------------------------------------------------------
@@ -24,6 +24,10 @@
 //! independently to enabled leaves. The default set is the hosted synchronous
 //! read/write configuration with `fat`, `iso`, and `cpio`.
 //!
+//! The `ntfs` leaf enables the experimental read-only NTFS support in
+//! [`block`]. It is outside the stability promise and is not part of the
+//! `block` category feature.
+//!
 //! Hybrid CD image creation is currently sync-only. Enabling `cd`—directly or
 //! through `optical`—therefore enables the CD writer's sync API, even when the
 //! umbrella `async` feature is also selected. ISO and UDF still expose their
@@ -43,7 +47,12 @@
 #![deny(missing_docs)]
 
 /// Block-oriented storage, filesystems, and disk-layout formats.
-#[cfg(any(feature = "storage", feature = "fat", feature = "part"))]
+#[cfg(any(
+    feature = "storage",
+    feature = "fat",
+    feature = "part",
+    feature = "ntfs"
+))]
 pub use hadris_block as block;
 
 /// Optical filesystems and disc-image composition.
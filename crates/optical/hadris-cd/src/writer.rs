@@ -10,6 +10,7 @@
 use super::super::{Borrowed, Read, Seek, SeekFrom, Write};
 
 use hadris_iso::read::PathSeparator;
+use hadris_iso::write::options::PartitionScheme;
 use hadris_udf::descriptor::{
     ExtentDescriptor, LongAllocationDescriptor, ShortAllocationDescriptor,
 };
@@ -74,6 +75,19 @@ impl<W: Read + Write + Seek> OpticalImageWriter<W> {
         // UDF metadata points at the already-written ISO payloads.
         if self.options.udf.enabled {
             self.write_udf_structures(&tree, &layout_info).await?;
+
+            // The UDF structures extend the image past the ISO volume, whose
+            // last blocks hold the backup GPT; move it to the new end.
+            if self.options.iso.enabled
+                && self.options.hybrid_boot.as_ref().is_some_and(|hybrid| {
+                    matches!(
+                        hybrid.partition_scheme,
+                        PartitionScheme::Gpt | PartitionScheme::Hybrid
+                    )
+                })
+            {
+                hadris_iso::write::relocate_backup_gpt(&mut Borrowed::new(&mut self.writer))?;
+            }
         }
 
         Ok(self.writer)
@@ -218,13 +232,22 @@ impl<W: Read + Write + Seek> OpticalImageWriter<W> {
 
     /// Write ISO 9660 structures
     async fn write_iso_structures(&mut self, tree: &FileTree, layout_info: &LayoutInfo) -> Result<()> {
-        use hadris_iso::write::options::{CreationFeatures, IsoFormatOptions};
-        use hadris_iso::write::{InputTree, IsoImageWriter};
+        use hadris_iso::write::options::{CreationExtensions, CreationFeatures, IsoFormatOptions};
+        use hadris_iso::write::{InputTree, IsoImageWriter, SourcedInputTree};
 
         // Convert our tree to ISO's InputFiles format
-        let iso_files = Self::tree_to_iso_files(&tree.root)?;
-
-        let input_files = InputTree::new(PathSeparator::ForwardSlash, iso_files);
+        let mut sources = Vec::new();
+        let iso_files = Self::tree_to_iso_files(&tree.root, "", &mut sources)?;
+
+        // Host files are streamed while their sectors are written
+        let input_files = sources.into_iter().try_fold(
+            SourcedInputTree::new(InputTree::new(PathSeparator::ForwardSlash, iso_files)),
+            |files, (path, source)| {
+                files
+                    .with_source(&path, source)
+                    .map_err(|error| crate::error::Error::InvalidPath(error.to_string()))
+            },
+        )?;
 
         // Build ISO format options from our options
         let features = CreationFeatures {
@@ -254,40 +277,50 @@ impl<W: Read + Write + Seek> OpticalImageWriter<W> {
             .seek(SeekFrom::Start(0))
             .await
             .map_err(hadris_io::Error::erase)?;
-        IsoImageWriter::create_with_allocation_floor(
+        IsoImageWriter::create_with_sources(
             Borrowed::new(&mut self.writer),
             input_files,
             format_options,
-            self.options
-                .udf
-                .enabled
-                .then_some(layout_info.file_data_start),
+            CreationExtensions::default().with_allocation_floor(
+                self.options
+                    .udf
+                    .enabled
+                    .then_some(layout_info.file_data_start),
+            ),
         )?;
 
         Ok(())
     }
 
     /// Convert our tree to ISO's file format
-    fn tree_to_iso_files(dir: &Directory) -> Result<Vec<hadris_iso::write::InputEntry>> {
+    ///
+    /// Files backed by a host path become empty placeholders; their sources
+    /// are collected into `sources` under their path from the root.
+    fn tree_to_iso_files(
+        dir: &Directory,
+        prefix: &str,
+        sources: &mut Vec<(String, hadris_iso::write::FileSource)>,
+    ) -> Result<Vec<hadris_iso::write::InputEntry>> {
         let mut files = Vec::new();
 
         for file in &dir.files {
+            let name = file.name.as_ref().clone();
             let data = match &file.data {
                 FileData::Buffer(b) => b.clone(),
-                FileData::Path(p) => std::fs::read(p)
-                    .map_err(|error| hadris_io::Error::from_source(error).erase())?,
+                FileData::Path(p) => {
+                    let source = hadris_iso::write::FileSource::from_path(p)
+                        .map_err(|error| hadris_io::Error::from_source(error).erase())?;
+                    sources.push((format!("{prefix}{name}"), source));
+                    Vec::new()
+                }
             };
-            files.push(hadris_iso::write::InputEntry::file(
-                file.name.as_ref().clone(),
-                data,
-            ));
+            files.push(hadris_iso::write::InputEntry::file(name, data));
         }
 
         for subdir in &dir.subdirs {
-            files.push(hadris_iso::write::InputEntry::directory(
-                subdir.name.as_ref().clone(),
-                Self::tree_to_iso_files(subdir)?,
-            ));
+            let name = subdir.name.as_ref().clone();
+            let children = Self::tree_to_iso_files(subdir, &format!("{prefix}{name}/"), sources)?;
+            files.push(hadris_iso::write::InputEntry::directory(name, children));
         }
 
         Ok(files)
@@ -4,6 +4,7 @@ use std::io::{Cursor, Seek, SeekFrom};
 
 use hadris_cd::{Directory, FileEntry, FileTree, OpticalImageOptions, OpticalImageWriter};
 use hadris_iso::sync::read::IsoImage;
+use hadris_iso::write::options::HybridBootOptions;
 use hadris_optical::detect::sync::detect;
 use hadris_udf::dir::UdfDirEntry;
 use hadris_udf::sync::UdfVolume;
@@ -140,6 +141,36 @@ fn bridge_extends_a_compact_image_to_reserve_the_trailing_anchor() {
     verify_udf(&bytes, &large);
 }
 
+#[test]
+fn bridge_moves_the_backup_gpt_past_the_udf_structures() {
+    let (tree, large) = fixture();
+    let options = OpticalImageOptions::default()
+        .volume_id(VOLUME_ID)
+        .hybrid_boot(HybridBootOptions::gpt());
+    let bytes = OpticalImageWriter::create(Cursor::new(Vec::new()), tree, options)
+        .expect("create GPT bridge")
+        .into_inner();
+    let last_lba = (bytes.len() / 512 - 1) as u64;
+    let lba_field = |lba: u64, offset: usize| {
+        let start = lba as usize * 512 + offset;
+        u64::from_le_bytes(bytes[start..start + 8].try_into().unwrap())
+    };
+
+    assert_eq!(&bytes[512..520], b"EFI PART");
+    assert_eq!(lba_field(1, 32), last_lba, "primary alternate_lba");
+    let backup = last_lba as usize * 512;
+    assert_eq!(&bytes[backup..backup + 8], b"EFI PART");
+    assert_eq!(lba_field(last_lba, 24), last_lba, "backup my_lba");
+    // The copy the ISO writer left at the end of the volume is gone.
+    let headers = bytes
+        .chunks_exact(512)
+        .filter(|block| block.starts_with(b"EFI PART"))
+        .count();
+    assert_eq!(headers, 2);
+    verify_iso(&bytes, &large);
+    verify_udf(&bytes, &large);
+}
+
 #[test]
 fn bridge_uses_udf_102_anchor_and_vds_layout() {
     let bytes = create(OpticalImageOptions::default().volume_id(VOLUME_ID));
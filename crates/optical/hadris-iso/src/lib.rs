@@ -138,6 +138,7 @@
 //! | `std` | Full standard library support | `std`, `alloc`, `thiserror`, `tracing`, `chrono` |
 //! | `write` | ISO creation/formatting | `std`, `alloc` |
 //! | `joliet` | Allocating Joliet encode/write helpers (`read` already supports Joliet lookup) | `alloc` |
+//! | `zisofs` | zisofs (RRIP `ZF`) decompression; enabled by `write` for compression | `alloc`, `miniz_oxide` |
 //!
 //! ### Feature Combinations
 //!
@@ -267,16 +268,22 @@
 //!   writer does not synthesize a filesystem inside them.
 //! - **High-level `IsoImage`:** Requires the `alloc` feature. `read` alone
 //!   exposes low-level modules suitable for no-alloc bootloaders.
-//! - **Not supported (writer rejects or does not emit):** files larger than
-//!   4 GiB / multi-extent write; Extended Attribute Record contents; the Volume
-//!   Partition Descriptor body; interleaved files; associated-file write; the
-//!   optional secondary path tables; RRIP `SF` (sparse) and `RR` (legacy
-//!   presence) entries; zisofs compression; and the Apple Partition Map.
+//! - **Files larger than 4 GiB:** the writer splits them into multi-extent
+//!   records, which ECMA-119 only permits at interchange level 3, so they
+//!   are rejected unless `BaseIsoLevel::Level3` is selected.
+//! - **Not supported (writer rejects or does not emit):** Extended Attribute
+//!   Record contents; the Volume Partition Descriptor body; interleaved
+//!   files; associated-file write; the optional secondary path tables; RRIP
+//!   `SF` (sparse) and `RR` (legacy presence) entries; and HFS+ hybrid
+//!   trees (the optional Apple Partition Map describes the ISO 9660 data
+//!   and the UEFI boot image only).
 //! - **Non-2048 logical block size:** `IsoImage` requires a 2048-byte logical
 //!   block and rejects other sizes; the allocation-free `IsoReader` honors the
 //!   declared block size.
-//! - **Hybrid GPT backup:** the embedded GPT writes the primary header/entries
-//!   only; a spec-valid backup GPT at end-of-disk is not written.
+//! - **GPT backup placement:** GPT and hybrid images end with the backup
+//!   GPT, which the volume space size covers. Appending data after the ISO
+//!   volume (as `hadris-cd` does for UDF) leaves the backup short of the
+//!   last block until the image is written to a disk and repaired.
 
 #![no_std]
 #![deny(missing_docs)]
@@ -326,6 +333,14 @@ pub mod types;
 #[cfg(any(feature = "read", feature = "alloc"))]
 pub mod joliet;
 
+/// zisofs transparent compression.
+///
+/// Linux images built by mkisofs/xorriso may store regular files as zisofs
+/// streams, marked by a Rock Ridge `ZF` entry. The reader decompresses them
+/// while streaming and the writer can produce them on request.
+#[cfg(feature = "zisofs")]
+pub mod zisofs;
+
 // ---------------------------------------------------------------------------
 // Sync module
 // ---------------------------------------------------------------------------
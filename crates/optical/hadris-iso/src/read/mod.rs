@@ -401,7 +401,8 @@ impl<DATA: Read + Seek> IsoImage<DATA> {
     ///
     /// For single-extent files, this reads from the entry's extent.
     /// For multi-extent files (using `NOT_FINAL` flag), this reads and
-    /// concatenates all extents in order.
+    /// concatenates all extents in order. zisofs files are decompressed as
+    /// described in [`Self::open_file`].
     #[cfg(feature = "alloc")]
     pub async fn read_file(&self, entry: &directory::DirEntry) -> io::Result<alloc::vec::Vec<u8>> {
         if entry.header().file_unit_size != 0 || entry.header().interleave_gap_size != 0 {
@@ -410,19 +411,30 @@ impl<DATA: Read + Seek> IsoImage<DATA> {
                 "interleaved ISO files are not supported",
             ));
         }
+        let zf = self.zisofs_entry(entry).await?;
+        if zf.is_some() {
+            // `open_stream` bounds the header against the image, and the
+            // stream ends at the header's uncompressed size; grow the buffer
+            // as blocks decode, never past that size.
+            let mut stream = self.open_stream(entry, zf).await?;
+            let mut contents = alloc::vec::Vec::new();
+            let mut chunk = alloc::vec![0u8; 64 * 1024];
+            while (contents.len() as u64) < stream.len() {
+                let n = stream.read_chunk(&mut chunk).await?;
+                if n == 0 {
+                    break;
+                }
+                contents.extend_from_slice(&chunk[..n]);
+            }
+            return Ok(contents);
+        }
         let total = entry.total_size();
         // `total` comes from on-disk directory-record data-length fields (u32 each,
         // summed across extents) and is untrusted. Bound it against the actual
         // image size before allocating, otherwise a tiny image whose record claims
         // ~4 GiB would force that allocation up front — a DoS that aborts the
         // process on no-overcommit / embedded targets.
-        let image_len = {
-            let mut data = self.data.lock();
-            data.seek(super::io::SeekFrom::End(0))
-                .await
-                .map_err(super::io::Error::erase)?
-        };
-        if total > image_len {
+        if total > self.image_len().await? {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 "directory entry claims more data than the image contains",
@@ -449,6 +461,262 @@ impl<DATA: Read + Seek> IsoImage<DATA> {
 
         Ok(buf)
     }
+
+    /// Returns the length of the underlying image in bytes.
+    async fn image_len(&self) -> io::Result<u64> {
+        let mut data = self.data.lock();
+        data.seek(super::io::SeekFrom::End(0))
+            .await
+            .map_err(super::io::Error::erase)
+    }
+
+    /// Returns the Rock Ridge `ZF` entry of `entry` if its data is stored as a
+    /// zisofs stream.
+    ///
+    /// [`RripMetadata`] does not carry `ZF`, so the entry's system use area
+    /// (and any continuation areas) is read again on each call.
+    pub async fn zisofs_entry(
+        &self,
+        entry: &directory::DirEntry,
+    ) -> io::Result<Option<super::rrip::ZfEntry>> {
+        if !self.info.susp_info.rrip_detected {
+            return Ok(None);
+        }
+        let fields =
+            rrip::collect_su_entries(&entry.record, self, self.info.susp_info.bytes_skipped)
+                .await?;
+        Ok(fields.iter().find_map(super::rrip::ZfEntry::from_field))
+    }
+
+    /// Opens a file for streaming reads without buffering its contents.
+    ///
+    /// Multi-extent files (`NOT_FINAL` records, ECMA-119 9.1.6) are presented
+    /// as one contiguous stream. Files carrying a Rock Ridge `ZF` entry are
+    /// decompressed on the fly with the `zisofs` feature and rejected as
+    /// unsupported without it.
+    pub async fn open_file(&self, entry: &directory::DirEntry) -> io::Result<IsoFileStream<'_, DATA>> {
+        let zf = self.zisofs_entry(entry).await?;
+        self.open_stream(entry, zf).await
+    }
+
+    /// Opens `entry` for streaming given its `ZF` entry, as returned by
+    /// [`Self::zisofs_entry`].
+    async fn open_stream(
+        &self,
+        entry: &directory::DirEntry,
+        zf: Option<super::rrip::ZfEntry>,
+    ) -> io::Result<IsoFileStream<'_, DATA>> {
+        if entry.header().file_unit_size != 0 || entry.header().interleave_gap_size != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "interleaved ISO files are not supported",
+            ));
+        }
+        #[cfg_attr(not(feature = "zisofs"), allow(unused_mut))]
+        let mut stream = IsoFileStream {
+            image: self,
+            extents: entry.extents().collect(),
+            position: 0,
+            len: entry.total_size(),
+            #[cfg(feature = "zisofs")]
+            zisofs: None,
+        };
+        if let Some(zf) = zf {
+            if !zf.is_zisofs() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "unknown ZF compression algorithm",
+                ));
+            }
+            #[cfg(not(feature = "zisofs"))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "zisofs-compressed files require the `zisofs` feature",
+            ));
+            #[cfg(feature = "zisofs")]
+            {
+                let mut bytes = [0u8; crate::zisofs::HEADER_LEN];
+                stream.read_stored(0, &mut bytes).await?;
+                let header = crate::zisofs::ZisofsHeader::parse(&bytes)?;
+                if header.uncompressed_size != zf.uncompressed_size.read() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "zisofs header does not match its ZF entry",
+                    ));
+                }
+                // The pointer table must be stored within the image, which
+                // bounds the size a small image can claim to expand to.
+                if header.data_offset() > stream.len || stream.len > self.image_len().await? {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "zisofs block count exceeds the stored data",
+                    ));
+                }
+                stream.len = header.uncompressed_size as u64;
+                stream.zisofs = Some(ZisofsState {
+                    header,
+                    stored_len: entry.total_size(),
+                    block: None,
+                });
+            }
+        }
+        Ok(stream)
+    }
+}
+} // io_transform!
+
+/// Streaming reader over a file's contents, returned by
+/// [`IsoImage::open_file`].
+pub struct IsoFileStream<'a, DATA: Seek> {
+    image: &'a IsoImage<DATA>,
+    extents: alloc::vec::Vec<Extent>,
+    position: u64,
+    len: u64,
+    #[cfg(feature = "zisofs")]
+    zisofs: Option<ZisofsState>,
+}
+
+/// Decoding state for a zisofs-compressed file.
+#[cfg(feature = "zisofs")]
+struct ZisofsState {
+    header: crate::zisofs::ZisofsHeader,
+    /// Length of the stored (compressed) data.
+    stored_len: u64,
+    /// Index and contents of the most recently decompressed block.
+    block: Option<(usize, alloc::vec::Vec<u8>)>,
+}
+
+impl<DATA: Seek> IsoFileStream<'_, DATA> {
+    /// Returns the complete logical file length, after decompression.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns whether the file contains no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the current logical read position.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Moves the logical read position; positions past the end are rejected.
+    pub fn set_position(&mut self, position: u64) -> io::Result<()> {
+        if position > self.len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "position is past the end of the file",
+            ));
+        }
+        self.position = position;
+        Ok(())
+    }
+}
+
+io_transform! {
+impl<DATA: Read + Seek> IsoFileStream<'_, DATA> {
+    /// Reads the next chunk into a caller-provided buffer, crossing extent
+    /// boundaries as needed. Returns `0` at the end of the file.
+    pub async fn read_chunk(&mut self, output: &mut [u8]) -> io::Result<usize> {
+        let wanted = output.len().min((self.len - self.position) as usize);
+        let output = &mut output[..wanted];
+        #[cfg(feature = "zisofs")]
+        if self.zisofs.is_some() {
+            return self.read_zisofs(output).await;
+        }
+        self.read_stored(self.position, output).await?;
+        self.position += wanted as u64;
+        Ok(wanted)
+    }
+
+    /// Reads stored bytes starting at `offset`, concatenating extents.
+    async fn read_stored(&self, mut offset: u64, mut output: &mut [u8]) -> io::Result<()> {
+        let mut start = 0u64;
+        for extent in &self.extents {
+            if output.is_empty() {
+                break;
+            }
+            let end = start + extent.length as u64;
+            if offset < end {
+                let take = ((end - offset) as usize).min(output.len());
+                let (chunk, rest) = core::mem::take(&mut output).split_at_mut(take);
+                let byte_offset = extent.sector.0 as u64 * self.image.info.block_size as u64
+                    + (offset - start);
+                self.image.read_bytes_at(byte_offset, chunk).await?;
+                output = rest;
+                offset += take as u64;
+            }
+            start = end;
+        }
+        if !output.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "read past the end of the file's extents",
+            ));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "zisofs")]
+    async fn read_zisofs(&mut self, output: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < output.len() {
+            let Some(block_size_log2) = self.zisofs.as_ref().map(|state| state.header.block_size_log2)
+            else {
+                break;
+            };
+            let index = (self.position >> block_size_log2) as usize;
+            let cached = self
+                .zisofs
+                .as_ref()
+                .and_then(|state| state.block.as_ref())
+                .is_some_and(|(cached, _)| *cached == index);
+            if !cached {
+                let block = self.load_zisofs_block(index).await?;
+                if let Some(state) = &mut self.zisofs {
+                    state.block = Some((index, block));
+                }
+            }
+            let Some((_, block)) = self.zisofs.as_ref().and_then(|state| state.block.as_ref())
+            else {
+                break;
+            };
+            let within = (self.position - ((index as u64) << block_size_log2)) as usize;
+            let take = (block.len() - within).min(output.len() - written);
+            output[written..written + take].copy_from_slice(&block[within..within + take]);
+            written += take;
+            self.position += take as u64;
+        }
+        Ok(written)
+    }
+
+    #[cfg(feature = "zisofs")]
+    async fn load_zisofs_block(&self, index: usize) -> io::Result<alloc::vec::Vec<u8>> {
+        let Some(state) = &self.zisofs else {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "file is not zisofs"));
+        };
+        let (header, stored_len) = (state.header, state.stored_len);
+        let mut pointers = [0u8; 8];
+        self.read_stored((crate::zisofs::HEADER_LEN + 4 * index) as u64, &mut pointers)
+            .await?;
+        let start = u32::from_le_bytes([pointers[0], pointers[1], pointers[2], pointers[3]]) as u64;
+        let end = u32::from_le_bytes([pointers[4], pointers[5], pointers[6], pointers[7]]) as u64;
+        if end < start || end > stored_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "zisofs block pointer is out of range",
+            ));
+        }
+        let block_len = header.block_len(index);
+        if start == end {
+            return Ok(alloc::vec![0u8; block_len]);
+        }
+        let mut compressed = alloc::vec![0u8; (end - start) as usize];
+        self.read_stored(start, &mut compressed).await?;
+        crate::zisofs::decompress_block(&compressed, block_len)
+    }
 }
 } // io_transform!
 
@@ -6,6 +6,7 @@
 //! - Symbolic links (SL entries)
 //! - Device files (PN entries)
 //! - Deep directory relocation (CL, PL, RE entries)
+//! - zisofs compressed files (ZF entries)
 
 #[cfg(feature = "std")]
 use super::io::{self, Read, Writable, Write};
@@ -513,6 +514,67 @@ impl ReEntry {
     }
 }
 
+/// ZF - zisofs compressed file entry
+///
+/// Marks a regular file whose data is stored as a zisofs stream. Linux
+/// defines this extension; it is not part of RRIP 1.12.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+pub struct ZfEntry {
+    /// Compression algorithm; `pz` for zisofs
+    pub algorithm: [u8; 2],
+    /// Size of the zisofs file header in 4-byte units
+    pub header_size_div4: u8,
+    /// Base-2 logarithm of the uncompressed block size
+    pub block_size_log2: u8,
+    /// Uncompressed file size (both-endian)
+    pub uncompressed_size: U32LsbMsb,
+}
+
+impl ZfEntry {
+    /// Create a zisofs ZF entry
+    pub fn new(block_size_log2: u8, uncompressed_size: u32) -> Self {
+        Self {
+            algorithm: *b"pz",
+            header_size_div4: 4,
+            block_size_log2,
+            uncompressed_size: U32LsbMsb::new(uncompressed_size),
+        }
+    }
+
+    /// Returns true if the entry describes zisofs ("paged zlib") data
+    pub fn is_zisofs(&self) -> bool {
+        &self.algorithm == b"pz"
+    }
+
+    /// Decodes a `ZF` entry from a parsed system use field
+    ///
+    /// `ZF` is not an RRIP 1.12 entry, so [`SystemUseIter`](super::susp::SystemUseIter)
+    /// yields it as [`SystemUseField::Unknown`](super::susp::SystemUseField::Unknown).
+    pub fn from_field(field: &super::susp::SystemUseField) -> Option<Self> {
+        match field {
+            super::susp::SystemUseField::Unknown(header, data)
+                if header.sig == *b"ZF" && header.length >= 16 =>
+            {
+                Some(bytemuck::pod_read_unaligned(&data[..12]))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ZfEntry {
+    /// Performs the `header` operation.
+    pub fn header(&self) -> SystemUseHeader {
+        SystemUseHeader {
+            sig: *b"ZF",
+            length: 16,
+            version: 1,
+        }
+    }
+}
+
 io_transform! {
 #[cfg(feature = "std")]
 impl Writable for PxEntry {
@@ -737,6 +799,24 @@ pub struct RripOptions {
     pub preserve_devices: bool,
 }
 
+/// zisofs compression settings for the writer's `CreationExtensions::zisofs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "alloc")]
+pub struct ZisofsOptions {
+    /// Base-2 logarithm of the uncompressed block size: 15, 16, or 17
+    /// (32, 64, or 128 KiB)
+    pub block_size_log2: u8,
+}
+
+#[cfg(feature = "alloc")]
+impl Default for ZisofsOptions {
+    fn default() -> Self {
+        Self {
+            block_size_log2: 15,
+        }
+    }
+}
+
 #[cfg(feature = "alloc")]
 impl Default for RripOptions {
     fn default() -> Self {
@@ -956,6 +1036,18 @@ impl RripBuilder {
         self
     }
 
+    /// Add a ZF entry marking zisofs-compressed file data
+    pub fn add_zf(&mut self, block_size_log2: u8, uncompressed_size: u32) -> &mut Self {
+        let zf = ZfEntry::new(block_size_log2, uncompressed_size);
+        let mut buf = alloc::vec![0u8; 16];
+        buf[0..2].copy_from_slice(b"ZF");
+        buf[2] = 16;
+        buf[3] = 1;
+        buf[4..16].copy_from_slice(bytemuck::bytes_of(&zf));
+        self.builder.add_raw(buf);
+        self
+    }
+
     /// Add a CE entry pointing to a continuation area
     pub fn add_ce(&mut self, ce: super::susp::ContinuationArea) -> &mut Self {
         self.builder.add_ce(ce);
@@ -1031,6 +1123,7 @@ mod tests {
     static_assertions::const_assert_eq!(size_of::<PnEntry>(), 16);
     static_assertions::const_assert_eq!(size_of::<ClEntry>(), 8);
     static_assertions::const_assert_eq!(size_of::<PlEntry>(), 8);
+    static_assertions::const_assert_eq!(size_of::<ZfEntry>(), 12);
 
     #[test]
     fn test_px_entry_new() {
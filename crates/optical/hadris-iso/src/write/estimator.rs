@@ -3,16 +3,22 @@
 //! Provides a way to estimate the output size of an ISO image before actually
 //! writing it, which is useful for pre-allocating buffers or reporting progress.
 
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::vec::Vec;
 
-use super::options::{CreationFeatures, IsoFormatOptions};
-use super::{File, InputEntry, InputEntryKind, InputFiles, InputTree};
+use super::options::{CreationExtensions, CreationFeatures, IsoFormatOptions, PartitionScheme};
+use super::{
+    File, FileSource, GPT_BACKUP_BLOCKS_PADDED, InputEntry, InputEntryKind, InputFiles, InputTree,
+    MAX_SINGLE_EXTENT_FILE_LEN, MULTI_EXTENT_LEN, SourcedInputTree,
+};
 use crate::file::EntryType;
 
 /// Breakdown of the estimated size by component.
 #[derive(Debug, Clone, Default)]
 pub struct SizeBreakdown {
-    /// System area: 16 sectors (32 KiB for 2048-byte sectors).
+    /// System area: 16 sectors (32 KiB for 2048-byte sectors), plus the
+    /// backup GPT written after the volume for GPT and hybrid boot.
     pub system_area: u64,
     /// Volume descriptors (PVD, SVD, Boot Record VD, Terminator).
     pub volume_descriptors: u64,
@@ -49,16 +55,42 @@ struct TreeStats {
     dir_count: u64,
     file_count: u64,
     total_file_bytes: u64,
+    /// Sector-aligned bytes of the files small enough for zisofs, which get a
+    /// second, uncompressed extent when a tree lacks Rock Ridge.
+    zisofs_file_bytes: u64,
     /// Sum of converted name lengths for directories (for path table estimation).
     dir_name_bytes: u64,
     /// Sum of directory record sizes across all directories.
     dir_record_bytes: u64,
 }
 
+impl TreeStats {
+    /// Counts the data of a regular file of `len` bytes.
+    fn add_file(&mut self, len: u64, sector_size: u64) {
+        if len == 0 {
+            return;
+        }
+        let aligned = align_to_sector(len, sector_size) * sector_size;
+        self.total_file_bytes += aligned;
+        if len <= u32::MAX as u64 {
+            self.zisofs_file_bytes += aligned;
+        }
+    }
+}
+
 fn align_to_sector(bytes: u64, sector_size: u64) -> u64 {
     bytes.div_ceil(sector_size)
 }
 
+/// Number of directory records a file of `len` bytes is written with.
+fn extent_records(len: u64) -> u64 {
+    if len <= MAX_SINGLE_EXTENT_FILE_LEN {
+        1
+    } else {
+        len.div_ceil(MULTI_EXTENT_LEN)
+    }
+}
+
 fn walk_files_stats(
     files: &[File],
     entry_types: &[EntryType],
@@ -83,11 +115,9 @@ fn walk_files_stats(
     for file in files {
         match file {
             File::File { name, contents } => {
+                let len = contents.len() as u64;
                 stats.file_count += 1;
-                if !contents.is_empty() {
-                    stats.total_file_bytes +=
-                        align_to_sector(contents.len() as u64, sector_size) * sector_size;
-                }
+                stats.add_file(len, sector_size);
                 // Directory record for a file: 33 + name_len (padded to even)
                 // + RRIP SU area if applicable
                 let name_len = estimate_converted_name_len(name, entry_types);
@@ -102,7 +132,7 @@ fn walk_files_stats(
                     let base = 33 + name_len;
                     (base + 1) & !1
                 };
-                dir_total += record_size;
+                dir_total += record_size * extent_records(len);
             }
             File::Directory { name, children } => {
                 stats.dir_count += 1;
@@ -131,6 +161,8 @@ fn walk_files_stats(
 
 fn walk_entries_stats(
     entries: &[InputEntry],
+    sources: &BTreeMap<Vec<String>, FileSource>,
+    path: &mut Vec<String>,
     entry_types: &[EntryType],
     sector_size: u64,
     stats: &mut TreeStats,
@@ -139,6 +171,7 @@ fn walk_entries_stats(
     let mut dir_total = if has_rrip { 256 + 128 } else { 34 + 34 };
     for entry in entries {
         let name = &entry.name;
+        path.push(name.as_ref().clone());
         let name_len = estimate_converted_name_len(name, entry_types);
         let extra = match &entry.kind {
             InputEntryKind::Symlink(target) => target.len() as u64 + 8,
@@ -152,24 +185,30 @@ fn walk_entries_stats(
         } else {
             (33 + name_len + 1) & !1
         };
-        dir_total += record_size;
         match &entry.kind {
-            InputEntryKind::File(contents) => {
+            InputEntryKind::File(_) => {
+                // Streamed sources contribute their declared length.
+                let len = sources
+                    .get(path)
+                    .map_or(entry.kind.data_len().unwrap_or(0), FileSource::len);
+                dir_total += record_size * extent_records(len);
                 stats.file_count += 1;
-                if !contents.is_empty() {
-                    stats.total_file_bytes +=
-                        align_to_sector(contents.len() as u64, sector_size) * sector_size;
-                }
+                stats.add_file(len, sector_size);
             }
             InputEntryKind::Directory(children) => {
+                dir_total += record_size;
                 stats.dir_count += 1;
                 stats.dir_name_bytes += name_len;
-                walk_entries_stats(children, entry_types, sector_size, stats);
+                walk_entries_stats(children, sources, path, entry_types, sector_size, stats);
             }
             InputEntryKind::Symlink(_)
             | InputEntryKind::CharacterDevice { .. }
-            | InputEntryKind::BlockDevice { .. } => stats.file_count += 1,
+            | InputEntryKind::BlockDevice { .. } => {
+                dir_total += record_size;
+                stats.file_count += 1;
+            }
         }
+        path.pop();
     }
     stats.dir_record_bytes += align_to_sector(dir_total, sector_size) * sector_size;
 }
@@ -212,20 +251,56 @@ fn estimate_converted_name_len(name: &str, entry_types: &[EntryType]) -> u64 {
 /// The estimate is conservative (may slightly overestimate) but will never
 /// underestimate the required size.
 pub fn estimate(files: &InputFiles, options: &IsoFormatOptions) -> IsoSizeEstimate {
-    estimate_impl(options, |entry_types, sector_size, stats| {
+    estimate_impl(options, false, |entry_types, sector_size, stats| {
         walk_files_stats(&files.files, entry_types, sector_size, stats);
     })
 }
 
 /// Estimate an image created from the metadata-aware input model.
 pub fn estimate_tree(files: &InputTree, options: &IsoFormatOptions) -> IsoSizeEstimate {
-    estimate_impl(options, |entry_types, sector_size, stats| {
-        walk_entries_stats(&files.entries, entry_types, sector_size, stats);
+    estimate_impl(options, false, |entry_types, sector_size, stats| {
+        walk_entries_stats(
+            &files.entries,
+            &BTreeMap::new(),
+            &mut Vec::new(),
+            entry_types,
+            sector_size,
+            stats,
+        );
     })
 }
 
+/// Estimate an image whose files may be streamed from
+/// [`FileSource`](super::FileSource)s, using their declared lengths.
+///
+/// `extensions` are the ones passed to
+/// [`IsoImageWriter::create_with_sources`](super::IsoImageWriter::create_with_sources);
+/// with zisofs, compressed files are counted at their uncompressed size, plus
+/// the uncompressed copy written for trees without Rock Ridge.
+pub fn estimate_sourced(
+    files: &SourcedInputTree,
+    options: &IsoFormatOptions,
+    extensions: &CreationExtensions,
+) -> IsoSizeEstimate {
+    estimate_impl(
+        options,
+        extensions.compresses_files(),
+        |entry_types, sector_size, stats| {
+            walk_entries_stats(
+                &files.tree.entries,
+                &files.sources,
+                &mut Vec::new(),
+                entry_types,
+                sector_size,
+                stats,
+            );
+        },
+    )
+}
+
 fn estimate_impl(
     options: &IsoFormatOptions,
+    zisofs: bool,
     walk: impl FnOnce(&[EntryType], u64, &mut TreeStats),
 ) -> IsoSizeEstimate {
     let sector_size = options.sector_size as u64;
@@ -258,6 +333,7 @@ fn estimate_impl(
         dir_count: 0,
         file_count: 0,
         total_file_bytes: 0,
+        zisofs_file_bytes: 0,
         dir_name_bytes: 0,
         dir_record_bytes: 0,
     };
@@ -301,14 +377,33 @@ fn estimate_impl(
         breakdown.continuation_areas = stats.dir_count * sector_size;
     }
 
-    // 7. File data
+    // 7. File data. A zisofs file never takes more than its uncompressed
+    // size, but trees without Rock Ridge read an uncompressed copy of it.
     breakdown.file_data = stats.total_file_bytes;
+    if zisofs
+        && features.rock_ridge.is_some_and(|rrip| rrip.enabled)
+        && has_rrip
+        && entry_types.iter().any(|ty| !ty.supports_rrip())
+    {
+        breakdown.file_data += stats.zisofs_file_bytes;
+    }
 
     // 8. Boot catalog
     if features.el_torito.is_some() {
         breakdown.boot_catalog = sector_size;
     }
 
+    // 9. Backup GPT after the volume, counted with the other partitioning
+    // structures in the system area
+    if features.hybrid_boot.as_ref().is_some_and(|hybrid| {
+        matches!(
+            hybrid.partition_scheme,
+            PartitionScheme::Gpt | PartitionScheme::Hybrid
+        )
+    }) {
+        breakdown.system_area += GPT_BACKUP_BLOCKS_PADDED * 512;
+    }
+
     let total_bytes = breakdown.system_area
         + breakdown.volume_descriptors
         + breakdown.path_tables
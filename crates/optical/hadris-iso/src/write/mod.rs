@@ -2,11 +2,14 @@ use alloc::{collections::BTreeMap, sync::Arc};
 use core::fmt;
 
 pub mod estimator;
+pub mod source;
 /// APIs for writer.
 pub mod writer;
+#[cfg(feature = "zisofs")]
+mod zisofs;
 
 use super::super::boot::{
-    BootCatalog, BootInfoTable, BootSectionEntry, ElToritoWriter, Grub2BootInfoTable,
+    BootCatalog, BootInfoTable, BootSectionEntry, ElToritoWriter, Grub2BootInfoTable, PlatformId,
 };
 use super::super::directory::{DirectoryRecord, DirectoryRef, FileFlags};
 use super::super::io::{self, Read, Seek, SeekFrom, Write};
@@ -22,18 +25,26 @@ use super::super::volume::{
 use crate::file::EntryType;
 use crate::joliet::JolietLevel;
 use crate::types::{Charset, IsoStr};
+#[cfg(feature = "zisofs")]
+use crate::zisofs::ZisofsHeader;
 use hadris_common::types::{
     endian::{Endian, EndianType},
     number::U32,
 };
 use hadris_part::{
-    Le,
+    ApmDisk, ApmDiskWriteExt, ApmEntry, DiskGeometry, DriverDescriptorMap, GptDisk, GptDiskReadExt,
+    GptDiskWriteExt, Le, MasterBootRecordReadExt,
     gpt::{GptPartitionEntry, Guid},
     hybrid::HybridMbrBuilder,
     mbr::{Chs, MasterBootRecord, MbrPartition, MbrPartitionType},
 };
-use options::PartitionScheme;
+use options::{BaseIsoLevel, CreationExtensions, PartitionScheme};
+use source::FileContents;
 use writer::{DirectoryRelocation, PathTableWriter, WrittenDirectory, WrittenFile, WrittenFiles};
+#[cfg(feature = "zisofs")]
+use zisofs::ZisofsPlan;
+
+pub use source::{FileSource, SourcedInputTree};
 
 use alloc::{collections::VecDeque, string::String, vec, vec::Vec};
 
@@ -163,6 +174,16 @@ pub enum InputEntryKind {
     },
 }
 
+impl InputEntryKind {
+    /// Returns the data length of a regular file, or `None` for other kinds.
+    pub(crate) fn data_len(&self) -> Option<u64> {
+        match self {
+            Self::File(contents) => Some(contents.len() as u64),
+            _ => None,
+        }
+    }
+}
+
 /// A named input entry and its optional host metadata.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InputEntry {
@@ -242,7 +263,7 @@ impl InputTree {
         }
         Ok(Self::new(
             path_separator,
-            read_input_directory_recursively(root_path)?,
+            read_input_directory_recursively(root_path, &[], None)?,
         ))
     }
 }
@@ -278,8 +299,15 @@ fn system_time_seconds(value: std::io::Result<std::time::SystemTime>) -> Option<
         .and_then(|duration| i64::try_from(duration.as_secs()).ok())
 }
 
+/// Reads a host directory into input entries.
+///
+/// With `sources`, regular files become empty placeholders whose host paths
+/// are recorded under their tree path (`parent` plus the file name) instead of
+/// being read into memory.
 fn read_input_directory_recursively(
     current_path: &std::path::Path,
+    parent: &[String],
+    mut sources: Option<&mut BTreeMap<Vec<String>, FileSource>>,
 ) -> core::result::Result<Vec<InputEntry>, FileConversionError> {
     use alloc::string::ToString;
     let mut children = Vec::new();
@@ -306,10 +334,22 @@ fn read_input_directory_recursively(
             metadata.uid = Some(fs_metadata.uid());
             metadata.gid = Some(fs_metadata.gid());
         }
+        let mut tree_path = parent.to_vec();
+        tree_path.push(name.clone());
         let kind = if file_type.is_file() {
-            InputEntryKind::File(std::fs::read(&path)?)
+            match sources.as_deref_mut() {
+                Some(sources) => {
+                    sources.insert(tree_path, FileSource::from_path(&path)?);
+                    InputEntryKind::File(Vec::new())
+                }
+                None => InputEntryKind::File(std::fs::read(&path)?),
+            }
         } else if file_type.is_dir() {
-            InputEntryKind::Directory(read_input_directory_recursively(&path)?)
+            InputEntryKind::Directory(read_input_directory_recursively(
+                &path,
+                &tree_path,
+                sources.as_deref_mut(),
+            )?)
         } else if file_type.is_symlink() {
             let target = std::fs::read_link(&path)?;
             InputEntryKind::Symlink(
@@ -352,14 +392,22 @@ fn read_input_directory_recursively(
     Ok(children)
 }
 
-fn validate_input_tree(tree: &InputTree, rrip: Option<&RripOptions>) -> io::Result<()> {
+fn validate_input_tree(
+    files: &SourcedInputTree,
+    rrip: Option<&RripOptions>,
+    multi_extent: bool,
+) -> io::Result<()> {
     fn visit(
+        files: &SourcedInputTree,
         entries: &[InputEntry],
+        path: &mut Vec<String>,
         rrip: Option<&RripOptions>,
+        multi_extent: bool,
         depth: usize,
         path_len: usize,
     ) -> io::Result<()> {
         for entry in entries {
+            path.push(entry.name.as_ref().clone());
             match &entry.kind {
                 InputEntryKind::Directory(children) => {
                     let child_path_len = if path_len == 0 {
@@ -376,7 +424,15 @@ fn validate_input_tree(tree: &InputTree, rrip: Option<&RripOptions>) -> io::Resu
                             "directory depth or path length exceeds ISO 9660 limits and RRIP relocation is disabled",
                         ));
                     }
-                    visit(children, rrip, depth + 1, child_path_len)?;
+                    visit(
+                        files,
+                        children,
+                        path,
+                        rrip,
+                        multi_extent,
+                        depth + 1,
+                        child_path_len,
+                    )?;
                 }
                 InputEntryKind::Symlink(_) => {
                     if !rrip.is_some_and(|options| options.enabled && options.preserve_symlinks) {
@@ -394,28 +450,116 @@ fn validate_input_tree(tree: &InputTree, rrip: Option<&RripOptions>) -> io::Resu
                         ));
                     }
                 }
-                InputEntryKind::File(contents) => {
-                    if contents.len() as u64 > MAX_SINGLE_EXTENT_FILE_LEN {
+                InputEntryKind::File(_) => {
+                    if !multi_extent && files.file_len(path, entry) > MAX_SINGLE_EXTENT_FILE_LEN {
                         return Err(io::Error::new(
                             io::ErrorKind::InvalidInput,
-                            "file exceeds 4 GiB; the ISO writer stores each file in a single \
-                             extent and cannot yet emit multi-extent records",
+                            "file exceeds 4 GiB; multi-extent files require ISO 9660 \
+                             interchange level 3 (BaseIsoLevel::Level3)",
                         ));
                     }
                 }
             }
+            path.pop();
         }
         Ok(())
     }
-    visit(&tree.entries, rrip, 1, 0)
+    visit(
+        files,
+        &files.tree.entries,
+        &mut Vec::new(),
+        rrip,
+        multi_extent,
+        1,
+        0,
+    )
 }
 
 /// Largest file size a single ISO 9660 (ECMA-119 9.1) directory record can
-/// address through its 32-bit `data_len` field. Larger files require
-/// multi-extent records, which the writer does not yet emit — attempting to
-/// write one is rejected up front rather than silently truncated to `u32`.
+/// address through its 32-bit `data_len` field. Larger files are split into
+/// multi-extent records (ECMA-119 9.1.6), which interchange levels 1 and 2
+/// forbid, so they are rejected up front unless level 3 is selected.
 pub(crate) const MAX_SINGLE_EXTENT_FILE_LEN: u64 = u32::MAX as u64;
 
+/// Length of every extent but the last in a multi-extent file: the largest
+/// whole number of 2048-byte sectors a 32-bit `data_len` can describe. Only
+/// the final extent may end mid-sector (ECMA-119 6.5.1).
+pub(crate) const MULTI_EXTENT_LEN: u64 = MAX_SINGLE_EXTENT_FILE_LEN & !2047;
+
+/// The zisofs-compressed files of an image, keyed by directory id and file
+/// index.
+#[cfg(feature = "zisofs")]
+type ZisofsPlans = BTreeMap<(usize, usize), ZisofsPlan>;
+
+/// Without the `zisofs` feature no file is ever compressed.
+#[cfg(not(feature = "zisofs"))]
+type ZisofsPlans = BTreeMap<(usize, usize), core::convert::Infallible>;
+
+/// Number of bytes stored at the extent of file `index` of `dir`: the
+/// compressed length when it is zisofs-compressed.
+fn stored_file_len(dir: &WrittenDirectory, index: usize, zisofs_plans: &ZisofsPlans) -> u64 {
+    match zisofs_plans.get(&(dir.id, index)) {
+        #[cfg(feature = "zisofs")]
+        Some(plan) => plan.stored_len(),
+        #[cfg(not(feature = "zisofs"))]
+        Some(never) => match *never {},
+        None => dir.file_contents(index).len(),
+    }
+}
+
+/// Start and length of the data a directory tree records for file `index`
+/// of `dir`. Trees without Rock Ridge cannot recognize zisofs data, so they
+/// point at the uncompressed copy of a compressed file.
+#[cfg_attr(not(feature = "zisofs"), allow(unused_variables))]
+fn recorded_file_data(
+    dir: &WrittenDirectory,
+    index: usize,
+    zisofs_plans: &ZisofsPlans,
+    has_rrip: bool,
+) -> (LogicalSector, u64) {
+    #[cfg(feature = "zisofs")]
+    if !has_rrip && let Some(plan) = zisofs_plans.get(&(dir.id, index)) {
+        return (
+            plan.plain_extent.unwrap_or_default(),
+            dir.file_contents(index).len(),
+        );
+    }
+    (
+        dir.files[index].entry.extent,
+        stored_file_len(dir, index, zisofs_plans),
+    )
+}
+
+/// Splits `len` bytes of contiguous data starting at `start` into the
+/// per-record extents written for it. Files up to
+/// [`MAX_SINGLE_EXTENT_FILE_LEN`] keep a single extent.
+///
+/// The length is a `u64` so files over 4 GiB split correctly on targets where
+/// `usize` (and therefore [`DirectoryRef::size`]) is 32 bits wide; every
+/// returned extent fits in 32 bits.
+fn file_extents(start: LogicalSector, len: u64) -> Vec<DirectoryRef> {
+    if len <= MAX_SINGLE_EXTENT_FILE_LEN {
+        return vec![DirectoryRef {
+            extent: start,
+            size: len as usize,
+        }];
+    }
+    let mut extents = Vec::new();
+    let mut offset = 0;
+    while offset < len {
+        let size = (len - offset).min(MULTI_EXTENT_LEN);
+        extents.push(DirectoryRef {
+            extent: LogicalSector(start.0 + (offset / 2048) as usize),
+            size: size as usize,
+        });
+        offset += size;
+    }
+    extents
+}
+
+/// Chunk size used to copy [`FileSource`] contents into the image.
+const SOURCE_COPY_BUFFER_LEN: usize = 256 * 1024;
+
 fn relocate_deep_directories(files: &mut WrittenFiles) {
     fn visit(
         dir: &mut WrittenDirectory,
@@ -509,6 +653,7 @@ pub struct IsoImageWriter<DATA: Read + Write + Seek> {
     path_tables: BTreeMap<EntryType, PathTableRef>,
     inode_counter: u32,
     rrip_time: [u8; 7],
+    extensions: CreationExtensions,
 }
 
 /// The kind of directory entry, used to select which RRIP entries to emit.
@@ -532,6 +677,9 @@ enum RripEntryKind<'a> {
         original_name: &'a str,
         metadata: InputMetadata,
         kind: &'a InputEntryKind,
+        /// Set when the file's data is stored zisofs-compressed
+        #[cfg(feature = "zisofs")]
+        zisofs: Option<ZisofsHeader>,
     },
 }
 
@@ -631,6 +779,8 @@ fn build_rrip_entries(
             original_name,
             metadata,
             kind,
+            #[cfg(feature = "zisofs")]
+            zisofs,
         } => {
             let (type_mode, default_permissions) = match kind {
                 InputEntryKind::File(_) => (0o100000, 0o644),
@@ -651,6 +801,10 @@ fn build_rrip_entries(
                 }
                 _ => {}
             }
+            #[cfg(feature = "zisofs")]
+            if let Some(header) = zisofs {
+                builder.add_zf(header.block_size_log2, header.uncompressed_size);
+            }
         }
     }
 
@@ -735,6 +889,9 @@ struct PendingRecord {
     split: SplitSu,
     dir_ref: DirectoryRef,
     flags: FileFlags,
+    /// Set on the second and later records of a multi-extent file, which
+    /// share the first record's File Identifier.
+    continuation: bool,
 }
 
 io_transform! {
@@ -761,7 +918,24 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         ops: IsoFormatOptions,
         allocation_floor: Option<u32>,
     ) -> Result<DATA> {
-        let mut files = files.into();
+        Self::create_with_sources(
+            data,
+            SourcedInputTree::new(files),
+            ops,
+            CreationExtensions::default().with_allocation_floor(allocation_floor),
+        )
+        .await
+    }
+
+    /// Creates an ISO image whose regular files may stream their contents
+    /// from [`FileSource`]s while their data sectors are written, applying
+    /// the writer settings in `extensions`.
+    pub async fn create_with_sources(
+        data: DATA,
+        mut files: SourcedInputTree,
+        ops: IsoFormatOptions,
+        extensions: CreationExtensions,
+    ) -> Result<DATA> {
         if ops.sector_size != 2048 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -769,10 +943,32 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
             )
             .into());
         }
-        validate_input_tree(&files, ops.features.rock_ridge.as_ref())?;
+        #[cfg(feature = "zisofs")]
+        if let Some(zisofs) = extensions.zisofs {
+            ZisofsHeader::new(0, zisofs.block_size_log2)?;
+        }
+        if let Some(hybrid) = &ops.features.hybrid_boot
+            && extensions.apple_partition_map
+            && hybrid
+                .mbr_bootstrap
+                .as_ref()
+                .is_some_and(|bootstrap| !bootstrap.starts_with(&DriverDescriptorMap::SIGNATURE))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "MBR bootstrap must start with the APM driver descriptor signature \"ER\"",
+            )
+            .into());
+        }
+        validate_input_tree(
+            &files,
+            ops.features.rock_ridge.as_ref(),
+            matches!(ops.features.filenames, BaseIsoLevel::Level3 { .. }),
+        )?;
         let mut writer = Self::new(data, ops);
-        writer.write_volume_descriptors(&mut files).await?;
-        if let Some(sector) = allocation_floor {
+        writer.extensions = extensions;
+        writer.write_volume_descriptors(&mut files.tree).await?;
+        if let Some(sector) = extensions.allocation_floor {
             let current = writer
                 .data
                 .stream_position()
@@ -826,6 +1022,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
             path_tables: BTreeMap::new(),
             inode_counter: 1,
             rrip_time,
+            extensions: CreationExtensions::default(),
         }
     }
 
@@ -1056,7 +1253,18 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
             .stream_position()
             .await
             .map_err(io::Error::erase)?;
-        let end_sector = self.data.pad_align_sector().await?;
+        let mut end_sector = self.data.pad_align_sector().await?;
+        // The backup GPT lives in the last blocks of the disk, so reserve it
+        // inside the volume: tools that size the image from the volume
+        // space (and `dd` copies of it) keep both GPT copies.
+        if self.ops.features.hybrid_boot.as_ref().is_some_and(|hybrid| {
+            matches!(
+                hybrid.partition_scheme,
+                PartitionScheme::Gpt | PartitionScheme::Hybrid
+            )
+        }) {
+            end_sector.0 += (GPT_BACKUP_BLOCKS_PADDED * 512 / self.ops.sector_size as u64) as usize;
+        }
         let image_len = end_sector.0 as u64 * self.ops.sector_size as u64;
         if alignment_requires_materialization(end_position, image_len) {
             self.data
@@ -1206,16 +1414,18 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         Ok(())
     }
 
-    async fn write_files(&mut self, files: &InputTree) -> io::Result<BTreeMap<EntryType, DirectoryRef>> {
+    async fn write_files(&mut self, files: &SourcedInputTree) -> io::Result<BTreeMap<EntryType, DirectoryRef>> {
         let mut next_directory_id = 1usize;
         {
-            let walker = FileTreeWalker::new(files);
+            let walker = FileTreeWalker::new(&files.tree);
             let mut current_dir = self.written_files.root_dir();
+            let mut current_path: Vec<String> = Vec::new();
             for file in walker {
                 match file {
                     TreeWalkerItem::EnterDirectory(dir) => {
                         let name = dir.name();
                         let metadata = dir.metadata;
+                        current_path.push(name.as_ref().clone());
                         let written_dir = self.written_files.get_mut(&current_dir);
                         let index = written_dir.push_dir(name, metadata);
                         written_dir.dirs[index].id = next_directory_id;
@@ -1223,6 +1433,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                         current_dir.push(index);
                     }
                     TreeWalkerItem::ExitDirectory(_dir) => {
+                        current_path.pop();
                         current_dir.pop();
                     }
                     TreeWalkerItem::File(file) => {
@@ -1230,6 +1441,11 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                         // Empty files keep extent 0 (per ISO 9660 they have no
                         // data to reference).
                         let dir = self.written_files.get_mut(&current_dir);
+                        current_path.push(file.name.as_ref().clone());
+                        if let Some(source) = files.sources.get(&current_path) {
+                            dir.sources.insert(dir.files.len(), source.clone());
+                        }
+                        current_path.pop();
                         dir.files.push(WrittenFile {
                             name: file.name.clone(),
                             entry: DirectoryRef {
@@ -1281,8 +1497,8 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         let mut file_order = Vec::new();
         for directory_id in &order {
             let dir = self.written_files.get(directory_id);
-            for (index, file) in dir.files.iter().enumerate() {
-                if matches!(&file.kind, InputEntryKind::File(contents) if !contents.is_empty()) {
+            for index in 0..dir.files.len() {
+                if dir.file_contents(index).len() > 0 {
                     file_order.push((directory_id.clone(), index));
                 }
             }
@@ -1294,6 +1510,28 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         let entry_types = self.entry_types.clone();
         let mut inode_counter = self.inode_counter;
 
+        // zisofs data is only recognizable through the `ZF` entry, so files
+        // are compressed only when at least one tree carries Rock Ridge, and
+        // trees without it get an uncompressed copy of each compressed file.
+        #[cfg_attr(not(feature = "zisofs"), allow(unused_mut))]
+        let mut zisofs_plans = ZisofsPlans::new();
+        #[cfg(feature = "zisofs")]
+        let plain_copies = entry_types.iter().any(|ty| !ty.supports_rrip());
+        #[cfg(feature = "zisofs")]
+        if let Some(zisofs) = self
+            .extensions
+            .zisofs
+            .filter(|_| rrip_options.is_some_and(|options| options.enabled))
+            .filter(|_| entry_types.iter().any(|ty| ty.supports_rrip()))
+        {
+            for (directory_id, index) in &file_order {
+                let dir = self.written_files.get(directory_id);
+                if let Some(plan) = ZisofsPlan::plan(dir.file_contents(*index), zisofs.block_size_log2)? {
+                    zisofs_plans.insert((dir.id, *index), plan);
+                }
+            }
+        }
+
         // ── Planning pass ──
         //
         // Build every directory's records once to measure its size, then
@@ -1337,6 +1575,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                     rrip_options.as_ref(),
                     &rrip_time,
                     &default_refs,
+                    &zisofs_plans,
                 )?;
                 let (extent, size_sectors) =
                     Self::layout_directory_records(cursor, sector_size, &records);
@@ -1361,16 +1600,21 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         for (directory_id, index) in &file_order {
             let aligned = (cursor + sector_size - 1) & !(sector_size - 1);
             let dir = self.written_files.get_mut(directory_id);
+            let len = stored_file_len(dir, *index, &zisofs_plans);
             let file = &mut dir.files[*index];
-            let len = match &file.kind {
-                InputEntryKind::File(contents) => contents.len() as u64,
-                _ => 0,
-            };
+            // Records are built from the full `u64` length; the public
+            // `usize` size saturates on 32-bit targets.
             file.entry = DirectoryRef {
                 extent: LogicalSector((aligned / sector_size) as usize),
-                size: len as usize,
+                size: usize::try_from(len).unwrap_or(usize::MAX),
             };
             cursor = aligned + len;
+            #[cfg(feature = "zisofs")]
+            if plain_copies && let Some(plan) = zisofs_plans.get_mut(&(dir.id, *index)) {
+                let aligned = (cursor + sector_size - 1) & !(sector_size - 1);
+                plan.plain_extent = Some(LogicalSector((aligned / sector_size) as usize));
+                cursor = aligned + dir.file_contents(*index).len();
+            }
         }
 
         // ── Write pass ──
@@ -1391,6 +1635,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                     rrip_options.as_ref(),
                     &rrip_time,
                     &relocation_refs,
+                    &zisofs_plans,
                 )?;
                 Self::write_directory_records(&mut self.data, sector_size, expected, &mut records)
                     .await?;
@@ -1411,8 +1656,39 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                 ));
             }
             let dir = self.written_files.get(directory_id);
-            if let InputEntryKind::File(contents) = &dir.files[*index].kind {
-                self.data.write_all(contents).await?;
+            #[cfg(feature = "zisofs")]
+            if let Some(plan) = zisofs_plans.get(&(dir.id, *index)) {
+                self.data.write_all(&plan.prefix()).await?;
+                self.data.write_all(&plan.data).await?;
+                // Trees without Rock Ridge read the uncompressed copy that
+                // follows the compressed data.
+                let Some(plain_extent) = plan.plain_extent else {
+                    continue;
+                };
+                if self.data.pad_align_sector().await? != plain_extent {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "file extent prediction did not match the written layout",
+                    ));
+                }
+            }
+            match dir.file_contents(*index) {
+                FileContents::Memory(contents) => self.data.write_all(contents).await?,
+                FileContents::Source(source) => {
+                    let mut stream = source
+                        .open()
+                        .map_err(|error| io::Error::from_source(error).erase())?;
+                    let mut buffer = vec![0u8; SOURCE_COPY_BUFFER_LEN];
+                    loop {
+                        let n = stream
+                            .fill(&mut buffer)
+                            .map_err(|error| io::Error::from_source(error).erase())?;
+                        if n == 0 {
+                            break;
+                        }
+                        self.data.write_all(&buffer[..n]).await?;
+                    }
+                }
             }
         }
 
@@ -1534,16 +1810,88 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                 self.write_mbr_boot(end_sector).await?;
             }
             Some(PartitionScheme::Gpt) => {
-                self.write_gpt_boot(end_sector, disk_size_512).await?;
+                self.write_gpt_boot(disk_size_512).await?;
             }
             Some(PartitionScheme::Hybrid) => {
-                self.write_hybrid_boot(end_sector, disk_size_512).await?;
+                self.write_hybrid_boot(disk_size_512).await?;
             }
         }
 
+        if self.extensions.apple_partition_map {
+            self.write_apple_partition_map(end_sector).await?;
+        }
+
         Ok(())
     }
 
+    /// Writes an Apple Partition Map with 2048-byte blocks: the DDM fields
+    /// over the start of the MBR bootstrap area and one map entry per block
+    /// from sector 1 on.
+    ///
+    /// The ISO 9660 data after the map is described as `ISO9660_data`
+    /// partitions, split around the UEFI El Torito image (if any), which is
+    /// listed as `Apple_HFS` so Intel Mac firmware can boot it.
+    async fn write_apple_partition_map(&mut self, end_sector: LogicalSector) -> io::Result<()> {
+        let block_count = u32::try_from(end_sector.0).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "image too large for an Apple Partition Map",
+            )
+        })?;
+        let mut apm = ApmDisk::new(2048, block_count).with_map_blocks(APM_MAP_BLOCKS);
+
+        let efi_image = self.ops.features.el_torito.as_ref().and_then(|boot| {
+            boot.sections().into_iter().find_map(|(section, entry)| {
+                section
+                    .filter(|section| matches!(section.platform, PlatformId::UEFI))
+                    .and_then(|_| {
+                        self.written_files
+                            .find_file(&entry.boot_image_path, self.ops.path_separator)
+                    })
+                    .map(|file| (file.extent.0 as u32, file.size.div_ceil(2048) as u32))
+                    .filter(|&(_, blocks)| blocks > 0)
+            })
+        });
+
+        let mut data_start = 1 + APM_MAP_BLOCKS;
+        if let Some((start, blocks)) = efi_image {
+            if start > data_start {
+                apm.add_partition(ApmEntry::new(
+                    b"ISO9660",
+                    ApmEntry::TYPE_ISO9660_DATA,
+                    data_start,
+                    start - data_start,
+                ))
+                .map_err(partition_error)?;
+            }
+            apm.add_partition(ApmEntry::new(b"EFI", ApmEntry::TYPE_HFS, start, blocks))
+                .map_err(partition_error)?;
+            data_start = start + blocks;
+        }
+        if block_count > data_start {
+            apm.add_partition(ApmEntry::new(
+                b"ISO9660",
+                ApmEntry::TYPE_ISO9660_DATA,
+                data_start,
+                block_count - data_start,
+            ))
+            .map_err(partition_error)?;
+        }
+
+        // Block 0 may already hold an MBR; only the DDM fields are written
+        // over the start of its bootstrap area.
+        self.data
+            .seek(SeekFrom::Start(0))
+            .await
+            .map_err(io::Error::erase)?;
+        self.data
+            .write_all(&bytemuck::bytes_of(&apm.ddm)[..DriverDescriptorMap::HEADER_SIZE])
+            .await?;
+        apm.write_entries_to(&mut self.data)
+            .await
+            .map_err(partition_error)
+    }
+
     /// Writes an MBR partition table for BIOS USB boot (isohybrid-style).
     async fn write_mbr_boot(&mut self, end_sector: LogicalSector) -> io::Result<()> {
         let end_block = (end_sector.0 * (self.data.sector_size / 512)) as u32;
@@ -1583,103 +1931,28 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
     }
 
     /// Writes a GPT partition table for UEFI boot.
-    async fn write_gpt_boot(&mut self, _end_sector: LogicalSector, disk_size_512: u64) -> io::Result<()> {
-        // For GPT, we need:
-        // 1. Protective MBR at sector 0
-        // 2. Primary GPT header at sector 1
-        // 3. GPT partition entries at sectors 2-33 (128 entries * 128 bytes = 32 sectors)
-        // 4. Backup GPT entries and header at end of disk
-
-        // Write protective MBR
-        let mbr = MasterBootRecord::protective(disk_size_512);
-        self.data
-            .seek(SeekFrom::Start(0))
-            .await
-            .map_err(io::Error::erase)?;
-        self.data.write_all(bytemuck::bytes_of(&mbr)).await?;
-
-        // Create GPT partition entry for the ISO data
-        // Start after GPT structures (sector 34 in 512-byte sectors)
-        let iso_start_lba = 34u64;
-        let iso_end_lba = disk_size_512.saturating_sub(34); // Leave room for backup GPT
-
-        // Create a deterministic partition GUID based on the volume name
-        let partition_guid = Self::generate_guid_from_string(&self.ops.volume_name);
-        let disk_guid =
-            Self::generate_guid_from_string(&alloc::format!("disk-{}", self.ops.volume_name));
-
-        let mut entries = [GptPartitionEntry::default(); 4];
-        entries[0] = GptPartitionEntry::new(
-            Guid::BASIC_DATA, // or could use a custom ISO GUID
-            partition_guid,
-            iso_start_lba,
-            iso_end_lba,
-        );
-
-        // Calculate CRC32 of partition entries
-        let entries_bytes = bytemuck::bytes_of(&entries);
-        let entries_crc = Self::crc32(entries_bytes);
-
-        // Create and write primary GPT header
-        let header_bytes = Self::write_gpt_header_bytes(
-            disk_guid,
-            1,                 // my_lba (primary is at sector 1)
-            disk_size_512 - 1, // alternate_lba (backup at last sector)
-            iso_start_lba,     // first_usable_lba
-            iso_end_lba,       // last_usable_lba
-            2,                 // partition_entry_lba
-            4,                 // num_partition_entries
-            entries_crc,
-        );
-
-        // Write primary GPT header
-        self.data
-            .seek(SeekFrom::Start(512))
-            .await
-            .map_err(io::Error::erase)?; // Sector 1
-        self.data.write_all(&header_bytes).await?;
-
-        // Write partition entries (starting at sector 2)
-        self.data
-            .seek(SeekFrom::Start(1024))
+    ///
+    /// `disk_size_512` includes the tail reserved at the end of the volume
+    /// for the backup entry array and header.
+    async fn write_gpt_boot(&mut self, disk_size_512: u64) -> io::Result<()> {
+        let gpt = self.build_gpt(disk_size_512)?;
+        let mbr = gpt.create_protective_mbr();
+        gpt.write_to_with_mbr(&mut self.data, &mbr)
             .await
-            .map_err(io::Error::erase)?; // Sector 2
-        self.data.write_all(entries_bytes).await?;
-
-        // Note: In a full implementation, we'd also write the backup GPT at the end
-        // For now, we skip this as ISOs are typically read-only
-
-        Ok(())
+            .map_err(partition_error)
     }
 
     /// Writes a Hybrid MBR + GPT for dual BIOS/UEFI boot.
-    async fn write_hybrid_boot(
-        &mut self,
-        _end_sector: LogicalSector,
-        disk_size_512: u64,
-    ) -> io::Result<()> {
+    async fn write_hybrid_boot(&mut self, disk_size_512: u64) -> io::Result<()> {
         let hybrid_opts = self.ops.features.hybrid_boot.as_ref();
         let bootable = hybrid_opts.map(|h| h.bootable).unwrap_or(true);
-
-        // Create GPT partition entry for the ISO
-        let iso_start_lba = 34u64;
-        let iso_end_lba = disk_size_512.saturating_sub(34);
-
-        // Create deterministic GUIDs
-        let partition_guid = Self::generate_guid_from_string(&self.ops.volume_name);
-        let disk_guid =
-            Self::generate_guid_from_string(&alloc::format!("disk-{}", self.ops.volume_name));
-
-        let gpt_entries = [
-            GptPartitionEntry::new(Guid::BASIC_DATA, partition_guid, iso_start_lba, iso_end_lba),
-            GptPartitionEntry::default(),
-        ];
+        let gpt = self.build_gpt(disk_size_512)?;
 
         // Build hybrid MBR using hadris-part
         let mut mbr = HybridMbrBuilder::new(disk_size_512)
             .protective_slot(0)
             .mirror_partition(0, MbrPartitionType::Iso9660, bootable)
-            .build(&gpt_entries)
+            .build(&gpt.entries)
             .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid hybrid MBR"))?;
 
         // Inject bootstrap code if provided
@@ -1690,61 +1963,44 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
             mbr.bootstrap[..len].copy_from_slice(&bootstrap[..len]);
         }
 
-        // Write hybrid MBR
-        self.data
-            .seek(SeekFrom::Start(0))
+        gpt.write_to_with_mbr(&mut self.data, &mbr)
             .await
-            .map_err(io::Error::erase)?;
-        self.data.write_all(bytemuck::bytes_of(&mbr)).await?;
-
-        // Calculate CRC32 of partition entries
-        let entries_bytes = bytemuck::bytes_of(&gpt_entries);
-        let entries_crc = Self::crc32(entries_bytes);
-
-        // Create and write primary GPT header
-        let header_bytes = Self::write_gpt_header_bytes(
-            disk_guid,
-            1,
-            disk_size_512 - 1,
-            iso_start_lba,
-            iso_end_lba,
-            2,
-            2, // Only 2 entries in our case
-            entries_crc,
-        );
-
-        // Write primary GPT header
-        self.data
-            .seek(SeekFrom::Start(512))
-            .await
-            .map_err(io::Error::erase)?;
-        self.data.write_all(&header_bytes).await?;
-
-        // Write partition entries
-        self.data
-            .seek(SeekFrom::Start(1024))
-            .await
-            .map_err(io::Error::erase)?;
-        self.data.write_all(entries_bytes).await?;
-
-        Ok(())
+            .map_err(partition_error)
     }
 
-    /// Simple CRC32 calculation for GPT.
-    fn crc32(data: &[u8]) -> u32 {
-        // Using the standard CRC-32 polynomial
-        let mut crc = !0u32;
-        for &byte in data {
-            crc ^= byte as u32;
-            for _ in 0..8 {
-                crc = if crc & 1 != 0 {
-                    (crc >> 1) ^ 0xEDB88320
-                } else {
-                    crc >> 1
-                };
-            }
+    /// Builds the GPT for a hybrid image: one partition spanning the usable
+    /// area, with the primary copy at LBA 1-33 (the entry array moves past
+    /// an Apple Partition Map) and the backup entry array and header in the
+    /// last 33 blocks of the disk.
+    fn build_gpt(&self, disk_size_512: u64) -> io::Result<GptDisk> {
+        let mut gpt = GptDisk::new(disk_size_512, 512);
+
+        // An Apple Partition Map occupies 2048-byte sectors 1-4, where the
+        // primary entry array would start; move the array past it.
+        if self.extensions.apple_partition_map {
+            let entries_lba = (1 + APM_MAP_BLOCKS as u64) * 4;
+            let shift = entries_lba - gpt.primary_header.partition_entry_lba.to_ne();
+            let first_usable = gpt.primary_header.first_usable_lba.to_ne() + shift;
+            gpt.primary_header.partition_entry_lba = Le::<u64>::from_ne(entries_lba);
+            gpt.primary_header.first_usable_lba = Le::<u64>::from_ne(first_usable);
+            gpt.backup_header.first_usable_lba = Le::<u64>::from_ne(first_usable);
         }
-        !crc
+
+        // Create deterministic GUIDs based on the volume name
+        let disk_guid =
+            Self::generate_guid_from_string(&alloc::format!("disk-{}", self.ops.volume_name));
+        gpt.primary_header.disk_guid = disk_guid;
+        gpt.backup_header.disk_guid = disk_guid;
+
+        let partition_guid = Self::generate_guid_from_string(&self.ops.volume_name);
+        gpt.add_partition(GptPartitionEntry::new(
+            Guid::BASIC_DATA,
+            partition_guid,
+            gpt.primary_header.first_usable_lba.to_ne(),
+            gpt.primary_header.last_usable_lba.to_ne(),
+        ))
+        .map_err(partition_error)?;
+        Ok(gpt)
     }
 
     /// Generates a deterministic GUID from a string (simple hash-based).
@@ -1771,56 +2027,6 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         Guid::from_bytes(bytes)
     }
 
-    /// Writes a GPT header to the given buffer (92 bytes).
-    #[allow(clippy::too_many_arguments)]
-    fn write_gpt_header_bytes(
-        disk_guid: Guid,
-        my_lba: u64,
-        alternate_lba: u64,
-        first_usable_lba: u64,
-        last_usable_lba: u64,
-        partition_entry_lba: u64,
-        num_partition_entries: u32,
-        partition_entry_array_crc32: u32,
-    ) -> [u8; 92] {
-        let mut buf = [0u8; 92];
-
-        // Signature: "EFI PART"
-        buf[0..8].copy_from_slice(b"EFI PART");
-        // Revision: 1.0
-        buf[8..12].copy_from_slice(&0x00010000u32.to_le_bytes());
-        // Header size: 92
-        buf[12..16].copy_from_slice(&92u32.to_le_bytes());
-        // Header CRC32: placeholder, will be calculated
-        buf[16..20].copy_from_slice(&0u32.to_le_bytes());
-        // Reserved
-        buf[20..24].copy_from_slice(&0u32.to_le_bytes());
-        // My LBA
-        buf[24..32].copy_from_slice(&my_lba.to_le_bytes());
-        // Alternate LBA
-        buf[32..40].copy_from_slice(&alternate_lba.to_le_bytes());
-        // First usable LBA
-        buf[40..48].copy_from_slice(&first_usable_lba.to_le_bytes());
-        // Last usable LBA
-        buf[48..56].copy_from_slice(&last_usable_lba.to_le_bytes());
-        // Disk GUID
-        buf[56..72].copy_from_slice(&disk_guid.to_bytes());
-        // Partition entry LBA
-        buf[72..80].copy_from_slice(&partition_entry_lba.to_le_bytes());
-        // Number of partition entries
-        buf[80..84].copy_from_slice(&num_partition_entries.to_le_bytes());
-        // Size of partition entry: 128
-        buf[84..88].copy_from_slice(&128u32.to_le_bytes());
-        // Partition entry array CRC32
-        buf[88..92].copy_from_slice(&partition_entry_array_crc32.to_le_bytes());
-
-        // Calculate and set header CRC32
-        let crc = Self::crc32(&buf);
-        buf[16..20].copy_from_slice(&crc.to_le_bytes());
-
-        buf
-    }
-
     async fn update_directory(
         &mut self,
         parent: DirectoryRef,
@@ -1930,6 +2136,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         rrip_options: Option<&RripOptions>,
         fallback_time: &[u8; 7],
         relocation_refs: &BTreeMap<(usize, EntryType), DirectoryRef>,
+        zisofs_plans: &ZisofsPlans,
     ) -> io::Result<Vec<PendingRecord>> {
         let rrip_options = rrip_options.filter(|options| options.enabled);
         let has_rrip = ty.supports_rrip() && rrip_options.is_some();
@@ -1961,6 +2168,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
             split: dot_split,
             dir_ref: DirectoryRef::default(),
             flags: FileFlags::DIRECTORY,
+            continuation: false,
         });
 
         // Dotdot entry ("..")
@@ -1994,6 +2202,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
             split: dotdot_split,
             dir_ref: DirectoryRef::default(),
             flags: FileFlags::DIRECTORY,
+            continuation: false,
         });
 
         // Directory entries
@@ -2052,42 +2261,57 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                     _ => *entries.get(&ty).unwrap(),
                 },
                 flags: FileFlags::DIRECTORY,
+                continuation: false,
             });
         }
 
         // File entries
-        for file in &dir.files {
+        for (index, file) in dir.files.iter().enumerate() {
             let WrittenFile {
                 name,
-                entry,
                 kind,
                 metadata,
+                ..
             } = file;
             let converted_name = ty.convert_name(name);
-            let split = if has_rrip {
+            let max = available_su_space(converted_name.as_bytes().len());
+            let builder = has_rrip.then(|| {
                 let inode = *inode_counter;
                 *inode_counter += 1;
-                let max = available_su_space(converted_name.as_bytes().len());
                 build_rrip_entries(
                     RripEntryKind::Entry {
                         original_name: name,
                         metadata: *metadata,
                         kind,
+                        #[cfg(feature = "zisofs")]
+                        zisofs: zisofs_plans.get(&(dir.id, index)).map(|plan| plan.header),
                     },
                     inode,
                     &options,
                     fallback_time,
                 )
-                .build_split(max)
-            } else {
-                SplitSu::empty()
-            };
-            records.push(PendingRecord {
-                name: converted_name.as_bytes().to_vec(),
-                split,
-                dir_ref: *entry,
-                flags: FileFlags::empty(),
             });
+            // Every record of a multi-extent file carries the same Rock Ridge
+            // fields: Linux reads attributes from the first record and the
+            // NM name from the last.
+            let (start, len) = recorded_file_data(dir, index, zisofs_plans, has_rrip);
+            let extents = file_extents(start, len);
+            let last = extents.len() - 1;
+            for (index, extent) in extents.into_iter().enumerate() {
+                records.push(PendingRecord {
+                    name: converted_name.as_bytes().to_vec(),
+                    split: builder
+                        .as_ref()
+                        .map_or_else(SplitSu::empty, |builder| builder.build_split(max)),
+                    dir_ref: extent,
+                    flags: if index == last {
+                        FileFlags::empty()
+                    } else {
+                        FileFlags::NOT_FINAL
+                    },
+                    continuation: index != 0,
+                });
+            }
         }
 
         // ── Phase 1.5: Deduplicate names ──
@@ -2097,12 +2321,19 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         {
             use std::collections::HashSet;
             let mut seen: HashSet<Vec<u8>> = HashSet::new();
+            let mut previous: Vec<u8> = Vec::new();
             for record in &mut records {
                 // Skip dot/dotdot entries
                 if record.name.len() == 1 && (record.name[0] == 0x00 || record.name[0] == 0x01) {
                     continue;
                 }
+                // Multi-extent continuations follow their first record's name.
+                if record.continuation {
+                    record.name = previous.clone();
+                    continue;
+                }
                 if seen.insert(record.name.clone()) {
+                    previous = record.name.clone();
                     continue;
                 }
                 let original = record.name.clone();
@@ -2112,6 +2343,7 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
                     suffix += 1;
                     if seen.insert(candidate.clone()) {
                         record.name = candidate;
+                        previous = record.name.clone();
                         break;
                     }
                 }
@@ -2124,7 +2356,8 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         // byte-wise sort is insufficient for Joliet because every UTF-16BE ASCII
         // name begins with 0x00 and would therefore sort before the 0x01 ".."
         // identifier. Sort ordinary entries byte-wise only after ranking the two
-        // special records explicitly.
+        // special records explicitly. The sort is stable and names are unique
+        // per file, so multi-extent records stay adjacent and in extent order.
         records.sort_by(|a, b| {
             let rank = |name: &[u8]| match name {
                 [0x00] => 0,
@@ -2231,8 +2464,80 @@ impl<DATA: Read + Write + Seek> IsoImageWriter<DATA> {
         Ok(())
     }
 }
+
+/// Moves the backup GPT of a GPT or hybrid-boot image to the end of `data`.
+///
+/// The writer places the backup GPT in the last blocks of the ISO volume.
+/// When more data is appended after the volume, such as a UDF bridge, call
+/// this once the image has its final length: the backup header moves to the
+/// new last LBA with its entry array before it, and a protective MBR entry
+/// covering the old disk is extended to the new one. The old backup header
+/// is cleared. Images without a GPT are rejected.
+pub async fn relocate_backup_gpt<DATA: Read + Write + Seek>(data: &mut DATA) -> io::Result<()> {
+    let len = data.seek(SeekFrom::End(0)).await.map_err(io::Error::erase)?;
+    data.seek(SeekFrom::Start(0))
+        .await
+        .map_err(io::Error::erase)?;
+    let mut mbr = MasterBootRecord::read_from(data)
+        .await
+        .map_err(partition_error)?;
+    let mut gpt = GptDisk::read_from(data, 512).await.map_err(partition_error)?;
+    let old_blocks = gpt.disk_blocks();
+    let new_blocks = len / 512;
+    if new_blocks == old_blocks {
+        return Ok(());
+    }
+    gpt.resize_disk(&DiskGeometry::standard(new_blocks))
+        .map_err(partition_error)?;
+
+    let old_protective = MbrPartition::protective(old_blocks);
+    mbr.with_partition_table(|table| {
+        for partition in table.partitions.iter_mut() {
+            if partition.partition_type() == MbrPartitionType::ProtectiveMbr
+                && partition.start_lba == old_protective.start_lba
+                && partition.sector_count == old_protective.sector_count
+            {
+                *partition = MbrPartition::protective(new_blocks);
+            }
+        }
+    });
+    gpt.write_to_with_mbr(data, &mbr)
+        .await
+        .map_err(partition_error)?;
+
+    // A stale header would still be found by tools scanning for the backup.
+    data.seek(SeekFrom::Start((old_blocks - 1) * 512))
+        .await
+        .map_err(io::Error::erase)?;
+    data.write_all(&[0u8; 512]).await?;
+    Ok(())
+}
 } // io_transform!
 
+/// 512-byte blocks taken by the backup GPT at the end of a hybrid image:
+/// the default 128-entry array (16 KiB, the UEFI minimum) and the header.
+const GPT_BACKUP_BLOCKS: u64 = GptDisk::DEFAULT_ENTRY_COUNT as u64 * 128 / 512 + 1;
+
+/// [`GPT_BACKUP_BLOCKS`] rounded up to whole 2048-byte sectors, reserved
+/// at the end of the volume so the image stays sector-aligned.
+pub(crate) const GPT_BACKUP_BLOCKS_PADDED: u64 = GPT_BACKUP_BLOCKS.div_ceil(4) * 4;
+
+/// 2048-byte blocks reserved for the Apple Partition Map after the DDM. The
+/// map never needs more than four entries (the map itself, the EFI image,
+/// and the ISO data before and after it), and a GPT entry array still fits
+/// before the volume descriptors once moved past it.
+const APM_MAP_BLOCKS: u32 = 4;
+
+fn partition_error(error: hadris_part::Error) -> io::Error {
+    match error {
+        hadris_part::Error::Io(error) => error,
+        _ => io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "invalid partition table layout",
+        ),
+    }
+}
+
 fn alignment_requires_materialization(current_position: u64, aligned_position: u64) -> bool {
     aligned_position > current_position
 }
@@ -2297,19 +2602,46 @@ mod tests {
     use alloc::vec;
 
     /// The single-extent ceiling is exactly the 32-bit `data_len` range, and
-    /// ordinary files validate. The >4 GiB rejection path itself cannot be
-    /// exercised in a unit test without allocating a >4 GiB buffer (files are
-    /// held in memory as `Vec<u8>`); it is guarded by inspection and the
-    /// constant below.
+    /// ordinary files validate. A streamed source declares a >4 GiB length
+    /// without allocating it; it is only accepted at interchange level 3.
     #[test]
     fn single_extent_ceiling_and_normal_files_validate() {
         assert_eq!(MAX_SINGLE_EXTENT_FILE_LEN, u32::MAX as u64);
 
-        let tree = InputTree::new(
+        let tree = SourcedInputTree::new(InputTree::new(
             PathSeparator::ForwardSlash,
             vec![InputEntry::file("hello.txt", vec![0u8; 4096])],
+        ));
+        assert!(validate_input_tree(&tree, None, false).is_ok());
+
+        let huge = FileSource::from_fn(MAX_SINGLE_EXTENT_FILE_LEN + 1, |_, buf| {
+            buf.fill(0);
+            Ok(())
+        });
+        let tree = SourcedInputTree::new(InputTree::new(PathSeparator::ForwardSlash, Vec::new()))
+            .with_source("huge.bin", huge)
+            .unwrap();
+        assert!(validate_input_tree(&tree, None, false).is_err());
+        assert!(validate_input_tree(&tree, None, true).is_ok());
+    }
+
+    #[test]
+    fn large_files_split_into_sector_aligned_extents() {
+        let extents = file_extents(LogicalSector(100), MAX_SINGLE_EXTENT_FILE_LEN);
+        assert_eq!(extents.len(), 1);
+        assert_eq!(extents[0].size as u64, MAX_SINGLE_EXTENT_FILE_LEN);
+
+        let len = 2 * MULTI_EXTENT_LEN + 10;
+        let extents = file_extents(LogicalSector(100), len);
+        assert_eq!(extents.len(), 3);
+        assert_eq!(extents[0].extent, LogicalSector(100));
+        assert_eq!(extents[0].size as u64, MULTI_EXTENT_LEN);
+        assert_eq!(extents[1].extent.0 as u64, 100 + MULTI_EXTENT_LEN / 2048);
+        assert_eq!(extents[2].size, 10);
+        assert_eq!(
+            extents.iter().map(|extent| extent.size as u64).sum::<u64>(),
+            len
         );
-        assert!(validate_input_tree(&tree, None).is_ok());
     }
 
     #[test]
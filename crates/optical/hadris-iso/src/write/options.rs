@@ -3,6 +3,8 @@ use alloc::string::String;
 use super::super::boot::options::BootOptions;
 use super::super::read::PathSeparator;
 use super::super::rrip::RripOptions;
+#[cfg(feature = "zisofs")]
+use super::super::rrip::ZisofsOptions;
 use crate::joliet::JolietLevel;
 
 /// Hybrid boot options for creating bootable ISO images from USB/disk.
@@ -211,6 +213,70 @@ impl CreationFeatures {
     }
 }
 
+/// Writer settings that are not part of [`IsoFormatOptions`], passed to
+/// [`IsoImageWriter::create_with_sources`](super::IsoImageWriter::create_with_sources).
+///
+/// Every setting is off by default, which writes the same image as
+/// [`IsoImageWriter::create`](super::IsoImageWriter::create).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct CreationExtensions {
+    /// Keep file and directory allocations at or after this logical sector,
+    /// as in [`IsoImageWriter::create_with_allocation_floor`](super::IsoImageWriter::create_with_allocation_floor).
+    pub allocation_floor: Option<u32>,
+    /// Store regular files zisofs-compressed, advertised through Rock Ridge
+    /// `ZF` entries.
+    ///
+    /// Only trees carrying Rock Ridge see the compressed data. When the image
+    /// also has a tree without Rock Ridge (plain ISO 9660 or Joliet), each
+    /// compressed file gets a second, uncompressed extent for that tree.
+    /// Files that would not shrink are stored uncompressed.
+    ///
+    /// Each file is compressed once while the image is planned, and its
+    /// compressed blocks stay in memory until its data sectors are written.
+    #[cfg(feature = "zisofs")]
+    pub zisofs: Option<ZisofsOptions>,
+    /// Also write an Apple Partition Map (2048-byte blocks) in the system
+    /// area, for older Intel Macs.
+    ///
+    /// The map describes the ISO 9660 data and, when El Torito has a UEFI
+    /// entry, the EFI boot image as an `Apple_HFS` partition, as libisofs
+    /// does. Its Driver Descriptor Map occupies the first 18 bytes of the
+    /// MBR bootstrap area, so a [`HybridBootOptions::mbr_bootstrap`] must be
+    /// built for that and start with `ER`. A GPT entry array moves past the
+    /// map.
+    pub apple_partition_map: bool,
+}
+
+impl CreationExtensions {
+    /// Sets the allocation floor.
+    pub fn with_allocation_floor(mut self, sector: Option<u32>) -> Self {
+        self.allocation_floor = sector;
+        self
+    }
+
+    /// Sets the zisofs compression settings.
+    #[cfg(feature = "zisofs")]
+    pub fn with_zisofs(mut self, zisofs: Option<ZisofsOptions>) -> Self {
+        self.zisofs = zisofs;
+        self
+    }
+
+    /// Returns whether regular files are to be zisofs-compressed.
+    pub(crate) fn compresses_files(&self) -> bool {
+        #[cfg(feature = "zisofs")]
+        return self.zisofs.is_some();
+        #[cfg(not(feature = "zisofs"))]
+        false
+    }
+
+    /// Enables or disables the Apple Partition Map.
+    pub fn with_apple_partition_map(mut self, enabled: bool) -> Self {
+        self.apple_partition_map = enabled;
+        self
+    }
+}
+
 impl From<BaseIsoLevel> for crate::file::EntryType {
     fn from(value: BaseIsoLevel) -> Self {
         match value {
@@ -0,0 +1,408 @@
+//! Lazily-read file contents for ISO creation.
+//!
+//! [`FileSource`] lets the writer pull a regular file's payload while it emits
+//! that file's data sectors instead of requiring the caller to hold every
+//! payload in memory. Sources are attached to an [`InputTree`] through
+//! [`SourcedInputTree`]. Every source declares its length up front, so layout
+//! planning and [`estimator`](super::estimator) work without touching the data.
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use hadris_path::{Component, Separators, VPath};
+
+use super::super::read::PathSeparator;
+use super::{
+    FileConversionError, InputEntry, InputEntryKind, InputFiles, InputTree,
+    read_input_directory_recursively,
+};
+
+/// A seekable byte source that can be shared with the writer.
+///
+/// Blanket-implemented for every `std::io::Read + std::io::Seek + Send` type.
+pub trait SourceReader: std::io::Read + std::io::Seek + Send {}
+
+impl<T: std::io::Read + std::io::Seek + Send + ?Sized> SourceReader for T {}
+
+/// Callback that fills `buf` with the file bytes starting at `offset`.
+///
+/// The writer never asks for bytes beyond the declared length, and the
+/// callback must fill the whole buffer.
+pub type SourceFn = dyn Fn(u64, &mut [u8]) -> std::io::Result<()> + Send + Sync;
+
+/// Where the writer reads a regular file's contents from.
+///
+/// Sources are cheap to clone: readers and callbacks are shared, and host
+/// paths are only opened while the file's data sectors are being written.
+#[derive(Clone)]
+pub enum FileSource {
+    /// A host file, opened when its data sectors are written.
+    Path {
+        /// Location of the file on the host filesystem.
+        path: PathBuf,
+        /// Number of bytes taken from the start of the file.
+        len: u64,
+    },
+    /// A seekable reader, rewound to its start before the data is copied.
+    Reader {
+        /// The shared reader.
+        reader: Arc<Mutex<dyn SourceReader>>,
+        /// Number of bytes taken from the start of the reader.
+        len: u64,
+    },
+    /// A callback producing the file contents on demand.
+    Callback {
+        /// Declared file length in bytes.
+        len: u64,
+        /// The fill callback.
+        read: Arc<SourceFn>,
+    },
+}
+
+impl FileSource {
+    /// Creates a source for a host file, recording its current length.
+    pub fn from_path(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let len = std::fs::metadata(&path)?.len();
+        Ok(Self::Path { path, len })
+    }
+
+    /// Creates a source that reads the first `len` bytes of `reader`.
+    pub fn from_reader<R: SourceReader + 'static>(reader: R, len: u64) -> Self {
+        Self::Reader {
+            reader: Arc::new(Mutex::new(reader)),
+            len,
+        }
+    }
+
+    /// Creates a source of `len` bytes produced by `read`.
+    ///
+    /// See [`SourceFn`] for the callback contract.
+    pub fn from_fn<F>(len: u64, read: F) -> Self
+    where
+        F: Fn(u64, &mut [u8]) -> std::io::Result<()> + Send + Sync + 'static,
+    {
+        Self::Callback {
+            len,
+            read: Arc::new(read),
+        }
+    }
+
+    /// Returns the declared length in bytes.
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Path { len, .. } | Self::Reader { len, .. } | Self::Callback { len, .. } => *len,
+        }
+    }
+
+    /// Returns `true` if the source declares no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Opens the source for a single sequential pass over its declared bytes.
+    pub(crate) fn open(&self) -> std::io::Result<SourceStream<'_>> {
+        let inner = match self {
+            Self::Path { path, .. } => StreamInner::File(std::fs::File::open(path)?),
+            Self::Reader { reader, .. } => {
+                let mut guard = reader.lock().map_err(|_| {
+                    std::io::Error::other("file source reader was poisoned by a panic")
+                })?;
+                guard.seek(std::io::SeekFrom::Start(0))?;
+                StreamInner::Reader(guard)
+            }
+            Self::Callback { read, .. } => StreamInner::Callback(read.as_ref()),
+        };
+        Ok(SourceStream {
+            inner,
+            offset: 0,
+            len: self.len(),
+        })
+    }
+}
+
+impl fmt::Debug for FileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path { path, len } => f
+                .debug_struct("Path")
+                .field("path", path)
+                .field("len", len)
+                .finish(),
+            Self::Reader { len, .. } => f.debug_struct("Reader").field("len", len).finish(),
+            Self::Callback { len, .. } => f.debug_struct("Callback").field("len", len).finish(),
+        }
+    }
+}
+
+/// Paths compare by value; readers and callbacks compare by identity.
+impl PartialEq for FileSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Path { path: a, len: x }, Self::Path { path: b, len: y }) => a == b && x == y,
+            (Self::Reader { reader: a, len: x }, Self::Reader { reader: b, len: y }) => {
+                Arc::ptr_eq(a, b) && x == y
+            }
+            (Self::Callback { read: a, len: x }, Self::Callback { read: b, len: y }) => {
+                core::ptr::addr_eq(Arc::as_ptr(a), Arc::as_ptr(b)) && x == y
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for FileSource {}
+
+/// An [`InputTree`] whose regular files may take their contents from
+/// [`FileSource`]s instead of in-memory buffers.
+///
+/// The wrapped tree still describes every entry and its metadata; a sourced
+/// file appears in it as a regular file, and its contents are read from the
+/// source while the writer emits its data sectors. Pass the result to
+/// `IsoImageWriter::create_with_sources` or size it with
+/// [`estimator::estimate_sourced`](super::estimator::estimate_sourced).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourcedInputTree {
+    pub(crate) tree: InputTree,
+    /// Sources keyed by the names along each file's path from the root.
+    pub(crate) sources: BTreeMap<Vec<String>, FileSource>,
+}
+
+impl SourcedInputTree {
+    /// Wraps `tree` without any sourced files.
+    pub fn new(tree: impl Into<InputTree>) -> Self {
+        Self {
+            tree: tree.into(),
+            sources: BTreeMap::new(),
+        }
+    }
+
+    /// Reads a host directory tree, streaming regular files from their host
+    /// paths instead of reading them up front like [`InputTree::from_fs`].
+    pub fn from_fs(
+        root_path: &std::path::Path,
+        path_separator: PathSeparator,
+    ) -> core::result::Result<Self, FileConversionError> {
+        if !root_path.is_dir() {
+            return Err(FileConversionError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                alloc::format!("Root path '{root_path:?}' is not a directory"),
+            )));
+        }
+        let mut sources = BTreeMap::new();
+        let entries = read_input_directory_recursively(root_path, &[], Some(&mut sources))?;
+        Ok(Self {
+            tree: InputTree::new(path_separator, entries),
+            sources,
+        })
+    }
+
+    /// Adds a regular file at `path` whose contents are read from `source`.
+    ///
+    /// `path` is relative to the root and may use `/` or `\` separators.
+    /// Missing parent directories are created. An existing entry at `path`
+    /// keeps its metadata but becomes a regular file backed by `source`.
+    ///
+    /// Returns an [`InvalidInput`](std::io::ErrorKind::InvalidInput) error if
+    /// `path` is empty, contains `..`, or passes through an entry that is not
+    /// a directory.
+    pub fn with_source(
+        mut self,
+        path: &str,
+        source: FileSource,
+    ) -> core::result::Result<Self, FileConversionError> {
+        let invalid = |reason: &str| {
+            FileConversionError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                alloc::format!("file source path {path:?} {reason}"),
+            ))
+        };
+        let mut components = Vec::new();
+        for component in VPath::with_separators(path, Separators::SlashOrBackslash).components() {
+            match component {
+                Component::Root | Component::Current => {}
+                Component::Parent => return Err(invalid("must not contain `..`")),
+                Component::Normal(name) => components.push(String::from(name)),
+            }
+        }
+        let Some((name, parents)) = components.split_last() else {
+            return Err(invalid("names no file"));
+        };
+        let mut entries = &mut self.tree.entries;
+        for parent in parents {
+            let index = match entries
+                .iter()
+                .position(|entry| entry.name.as_str() == parent)
+            {
+                Some(index) => index,
+                None => {
+                    entries.push(InputEntry::directory(parent.clone(), Vec::new()));
+                    entries.len() - 1
+                }
+            };
+            entries = match &mut entries[index].kind {
+                InputEntryKind::Directory(children) => children,
+                _ => return Err(invalid("passes through a non-directory")),
+            };
+        }
+        match entries.iter_mut().find(|entry| entry.name.as_str() == name) {
+            Some(entry) => entry.kind = InputEntryKind::File(Vec::new()),
+            None => entries.push(InputEntry::file(name.clone(), Vec::new())),
+        }
+        self.sources.insert(components, source);
+        Ok(self)
+    }
+
+    /// Returns the wrapped tree, in which sourced files appear empty.
+    pub fn tree(&self) -> &InputTree {
+        &self.tree
+    }
+
+    /// Returns the data length of the regular file `entry` found at `path`.
+    pub(crate) fn file_len(&self, path: &[String], entry: &InputEntry) -> u64 {
+        match self.sources.get(path) {
+            Some(source) => source.len(),
+            None => entry.kind.data_len().unwrap_or(0),
+        }
+    }
+}
+
+impl From<InputTree> for SourcedInputTree {
+    fn from(value: InputTree) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<InputFiles> for SourcedInputTree {
+    fn from(value: InputFiles) -> Self {
+        Self::new(value)
+    }
+}
+
+/// The contents written for a regular file: an in-memory buffer or a
+/// [`FileSource`] streamed while its data sectors are written.
+#[derive(Clone, Copy)]
+pub(crate) enum FileContents<'a> {
+    Memory(&'a [u8]),
+    Source(&'a FileSource),
+}
+
+impl FileContents<'_> {
+    /// Returns the number of data bytes.
+    pub(crate) fn len(&self) -> u64 {
+        match self {
+            Self::Memory(contents) => contents.len() as u64,
+            Self::Source(source) => source.len(),
+        }
+    }
+}
+
+enum StreamInner<'a> {
+    File(std::fs::File),
+    Reader(MutexGuard<'a, dyn SourceReader + 'static>),
+    Callback(&'a SourceFn),
+}
+
+/// A single sequential pass over a [`FileSource`], bounded by its declared
+/// length.
+pub(crate) struct SourceStream<'a> {
+    inner: StreamInner<'a>,
+    offset: u64,
+    len: u64,
+}
+
+impl SourceStream<'_> {
+    /// Fills as much of `buf` as the remaining declared length allows and
+    /// returns the number of bytes produced; `0` means the source is done.
+    ///
+    /// A reader or host file that ends before the declared length is an
+    /// error: the directory records already advertise that length.
+    pub(crate) fn fill(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.len - self.offset;
+        let n = (buf.len() as u64).min(remaining) as usize;
+        if n == 0 {
+            return Ok(0);
+        }
+        let buf = &mut buf[..n];
+        let result = match &mut self.inner {
+            StreamInner::File(file) => std::io::Read::read_exact(file, buf),
+            StreamInner::Reader(reader) => std::io::Read::read_exact(&mut **reader, buf),
+            StreamInner::Callback(read) => read(self.offset, buf),
+        };
+        result.map_err(|error| {
+            if error.kind() == std::io::ErrorKind::UnexpectedEof {
+                std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "file source ended before its declared length",
+                )
+            } else {
+                error
+            }
+        })?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn drain(source: &FileSource) -> std::io::Result<Vec<u8>> {
+        let mut stream = source.open()?;
+        let mut output = Vec::new();
+        let mut buf = [0u8; 3];
+        loop {
+            let n = stream.fill(&mut buf)?;
+            if n == 0 {
+                return Ok(output);
+            }
+            output.extend_from_slice(&buf[..n]);
+        }
+    }
+
+    #[test]
+    fn reader_source_is_rewound_and_bounded() {
+        let mut cursor = std::io::Cursor::new(b"hello, world".to_vec());
+        std::io::Seek::seek(&mut cursor, std::io::SeekFrom::End(0)).unwrap();
+        let source = FileSource::from_reader(cursor, 5);
+        assert_eq!(drain(&source).unwrap(), b"hello");
+        // A second pass sees the same bytes.
+        assert_eq!(drain(&source).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn callback_source_receives_offsets() {
+        let source = FileSource::from_fn(8, |offset, buf| {
+            for (index, byte) in buf.iter_mut().enumerate() {
+                *byte = (offset as usize + index) as u8;
+            }
+            Ok(())
+        });
+        assert_eq!(drain(&source).unwrap(), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn short_reader_is_an_error() {
+        let source = FileSource::from_reader(std::io::Cursor::new(vec![1u8; 4]), 16);
+        let error = drain(&source).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn path_source_records_length() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        std::io::Write::write_all(&mut file, b"from disk").unwrap();
+        let source = FileSource::from_path(file.path()).unwrap();
+        assert_eq!(source.len(), 9);
+        assert_eq!(drain(&source).unwrap(), b"from disk");
+        assert_eq!(source, FileSource::from_path(file.path()).unwrap());
+    }
+}
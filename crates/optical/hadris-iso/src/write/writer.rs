@@ -11,6 +11,7 @@ use crate::file::EntryType;
 use crate::file::{convert_joliet3, convert_l1, convert_l2, convert_l3};
 
 use super::super::directory::DirectoryRef;
+use super::source::{FileContents, FileSource};
 use super::{InputEntryKind, InputMetadata};
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -133,6 +134,8 @@ pub struct WrittenDirectory {
     /// The `metadata` field.
     pub metadata: InputMetadata,
     pub(crate) relocation: DirectoryRelocation,
+    /// Streamed contents of entries in `files`, keyed by their index.
+    pub(crate) sources: BTreeMap<usize, FileSource>,
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -160,6 +163,7 @@ impl WrittenDirectory {
             files: Vec::new(),
             metadata: InputMetadata::default(),
             relocation: DirectoryRelocation::None,
+            sources: BTreeMap::new(),
         }
     }
 
@@ -170,6 +174,17 @@ impl WrittenDirectory {
         self.dirs.push(directory);
         self.dirs.len() - 1
     }
+
+    /// Returns the contents written for the file at `index` in `files`.
+    pub(crate) fn file_contents(&self, index: usize) -> FileContents<'_> {
+        if let Some(source) = self.sources.get(&index) {
+            return FileContents::Source(source);
+        }
+        match &self.files[index].kind {
+            InputEntryKind::File(contents) => FileContents::Memory(contents),
+            _ => FileContents::Memory(&[]),
+        }
+    }
 }
 
 #[derive(Debug)]
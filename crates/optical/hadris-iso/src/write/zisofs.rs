@@ -0,0 +1,172 @@
+//! zisofs compression for files written with `CreationExtensions::zisofs`
+//! set.
+//!
+//! Planning compresses every eligible file once, one block at a time, and
+//! keeps the compressed blocks until the file's data sectors are written.
+
+use alloc::vec::Vec;
+
+use super::source::{FileContents, SourceStream};
+use crate::io::LogicalSector;
+use crate::zisofs::{HEADER_LEN, ZisofsHeader, compress_block};
+use hadris_io::{Error, ErrorKind, Result};
+
+fn source_error(error: std::io::Error) -> Error {
+    Error::from_source(error).erase()
+}
+
+/// Sequential reader handing out one uncompressed zisofs block at a time.
+struct BlockReader<'a> {
+    inner: BlockSource<'a>,
+    buffer: Vec<u8>,
+}
+
+enum BlockSource<'a> {
+    Memory(&'a [u8]),
+    Source(SourceStream<'a>),
+}
+
+impl<'a> BlockReader<'a> {
+    /// Opens the contents of a regular file.
+    fn open(contents: FileContents<'a>, header: &ZisofsHeader) -> Result<Self> {
+        let inner = match contents {
+            FileContents::Memory(contents) => BlockSource::Memory(contents),
+            FileContents::Source(source) => {
+                BlockSource::Source(source.open().map_err(source_error)?)
+            }
+        };
+        Ok(Self {
+            inner,
+            buffer: alloc::vec![0u8; header.block_size()],
+        })
+    }
+
+    /// Returns the next block, or `None` once the file is exhausted.
+    fn next_block(&mut self) -> Result<Option<&[u8]>> {
+        match &mut self.inner {
+            BlockSource::Memory(remaining) => {
+                if remaining.is_empty() {
+                    return Ok(None);
+                }
+                let (block, rest) = remaining.split_at(remaining.len().min(self.buffer.len()));
+                *remaining = rest;
+                Ok(Some(block))
+            }
+            BlockSource::Source(stream) => {
+                let n = stream.fill(&mut self.buffer).map_err(source_error)?;
+                Ok((n > 0).then(|| &self.buffer[..n]))
+            }
+        }
+    }
+}
+
+/// The compressed form of one file, computed in the planning pass.
+pub(crate) struct ZisofsPlan {
+    /// Header advertising the uncompressed size and block size.
+    pub header: ZisofsHeader,
+    /// The `block_count + 1` block offsets, relative to the file start.
+    pub pointers: Vec<u32>,
+    /// The compressed blocks, back to back; zero blocks take no bytes.
+    pub data: Vec<u8>,
+    /// Where the uncompressed copy read by trees without Rock Ridge starts,
+    /// assigned once the image has such a tree.
+    pub plain_extent: Option<LogicalSector>,
+}
+
+impl ZisofsPlan {
+    /// Compresses `contents`, returning `None` when the file is not eligible
+    /// (empty or over 4 GiB) or would not shrink.
+    pub(crate) fn plan(contents: FileContents<'_>, block_size_log2: u8) -> Result<Option<Self>> {
+        let len = contents.len();
+        if len == 0 || len > u32::MAX as u64 {
+            return Ok(None);
+        }
+        let header = ZisofsHeader::new(len as u32, block_size_log2)?;
+        let mut pointers = Vec::with_capacity(header.block_count() + 1);
+        let mut data = Vec::new();
+        pointers.push(header.data_offset() as u32);
+        let mut blocks = BlockReader::open(contents, &header)?;
+        while let Some(block) = blocks.next_block()? {
+            data.extend_from_slice(&compress_block(block));
+            let offset = header.data_offset() + data.len() as u64;
+            if offset >= len {
+                return Ok(None);
+            }
+            pointers.push(offset as u32);
+        }
+        if pointers.len() != header.block_count() + 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "zisofs source is shorter than its declared length",
+            ));
+        }
+        Ok(Some(Self {
+            header,
+            pointers,
+            data,
+            plain_extent: None,
+        }))
+    }
+
+    /// Returns the number of bytes stored in the image.
+    pub(crate) fn stored_len(&self) -> u64 {
+        self.pointers.last().copied().unwrap_or(0) as u64
+    }
+
+    /// Serializes the header and block pointer table that precede the
+    /// compressed blocks.
+    pub(crate) fn prefix(&self) -> Vec<u8> {
+        let mut prefix = Vec::with_capacity(HEADER_LEN + 4 * self.pointers.len());
+        prefix.extend_from_slice(&self.header.to_bytes());
+        for pointer in &self.pointers {
+            prefix.extend_from_slice(&pointer.to_le_bytes());
+        }
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn plan_skips_files_that_do_not_shrink() {
+        let mut state = 0x2545_f491u32;
+        let noise = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            ZisofsPlan::plan(FileContents::Memory(&noise), 15)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            ZisofsPlan::plan(FileContents::Memory(&[]), 15)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn plan_records_block_offsets() {
+        let contents = vec![b'a'; 40_000];
+        let plan = ZisofsPlan::plan(FileContents::Memory(&contents), 15)
+            .unwrap()
+            .unwrap();
+        assert_eq!(plan.header.block_count(), 2);
+        assert_eq!(plan.pointers.len(), 3);
+        assert_eq!(plan.pointers[0] as u64, plan.header.data_offset());
+        assert!(plan.pointers.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(plan.prefix().len() as u64, plan.header.data_offset());
+        assert_eq!(
+            plan.header.data_offset() + plan.data.len() as u64,
+            plan.stored_len()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! zisofs transparent compression (RRIP `ZF`).
+//!
+//! A zisofs file is stored as a 16-byte header, a table of `block_count + 1`
+//! little-endian `u32` offsets (relative to the start of the file), and one
+//! zlib stream per uncompressed block. Two equal consecutive offsets mark a
+//! block of zeros that is not stored at all. The file's `ZF` entry repeats
+//! the uncompressed size and block size, so a reader can size the file
+//! without touching its data.
+//!
+//! This module holds the format itself; [`IsoImage::open_file`] decompresses
+//! on the fly and `IsoImageWriter` compresses when
+//! `CreationExtensions::zisofs` is set.
+//!
+//! [`IsoImage::open_file`]: crate::read::IsoImage::open_file
+
+use alloc::vec::Vec;
+use hadris_io::{Error, ErrorKind, Result};
+
+/// Magic number at the start of every zisofs file.
+pub const MAGIC: [u8; 8] = [0x37, 0xE4, 0x53, 0x96, 0xC9, 0xDB, 0xD6, 0x07];
+
+/// Length of the zisofs file header in bytes.
+pub const HEADER_LEN: usize = 16;
+
+/// The `ZF` algorithm identifier for zisofs ("paged zlib").
+pub const ALGORITHM: [u8; 2] = *b"pz";
+
+/// Smallest block size exponent accepted by Linux (32 KiB blocks).
+pub const MIN_BLOCK_SIZE_LOG2: u8 = 15;
+
+/// Largest block size exponent accepted by Linux (128 KiB blocks).
+pub const MAX_BLOCK_SIZE_LOG2: u8 = 17;
+
+/// zlib level used when compressing blocks (the zlib default).
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// The fixed header at the start of a zisofs file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZisofsHeader {
+    /// Size of the file once decompressed.
+    pub uncompressed_size: u32,
+    /// Base-2 logarithm of the uncompressed block size.
+    pub block_size_log2: u8,
+}
+
+impl ZisofsHeader {
+    /// Creates a header, rejecting block sizes outside 32-128 KiB.
+    pub fn new(uncompressed_size: u32, block_size_log2: u8) -> Result<Self> {
+        if !(MIN_BLOCK_SIZE_LOG2..=MAX_BLOCK_SIZE_LOG2).contains(&block_size_log2) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "zisofs block size must be 32, 64, or 128 KiB",
+            ));
+        }
+        Ok(Self {
+            uncompressed_size,
+            block_size_log2,
+        })
+    }
+
+    /// Parses and validates the header bytes.
+    pub fn parse(bytes: &[u8; HEADER_LEN]) -> Result<Self> {
+        if bytes[..8] != MAGIC {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "zisofs file is missing its magic number",
+            ));
+        }
+        if bytes[12] as usize * 4 != HEADER_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "unsupported zisofs header size",
+            ));
+        }
+        let uncompressed_size = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        Self::new(uncompressed_size, bytes[13])
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "zisofs block size is out of range"))
+    }
+
+    /// Serializes the header.
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[..8].copy_from_slice(&MAGIC);
+        bytes[8..12].copy_from_slice(&self.uncompressed_size.to_le_bytes());
+        bytes[12] = (HEADER_LEN / 4) as u8;
+        bytes[13] = self.block_size_log2;
+        bytes
+    }
+
+    /// Returns the uncompressed block size in bytes.
+    pub fn block_size(&self) -> usize {
+        1 << self.block_size_log2
+    }
+
+    /// Returns the number of blocks the file is split into.
+    pub fn block_count(&self) -> usize {
+        (self.uncompressed_size as usize).div_ceil(self.block_size())
+    }
+
+    /// Returns the uncompressed length of block `index`; only the last block
+    /// may be shorter than [`Self::block_size`].
+    pub fn block_len(&self, index: usize) -> usize {
+        let start = index * self.block_size();
+        (self.uncompressed_size as usize - start).min(self.block_size())
+    }
+
+    /// Returns the offset of the first compressed block, just past the
+    /// header and the `block_count + 1` entry pointer table.
+    pub fn data_offset(&self) -> u64 {
+        (HEADER_LEN + 4 * (self.block_count() + 1)) as u64
+    }
+}
+
+/// Decompresses one block's zlib stream, which must inflate to exactly
+/// `block_len` bytes.
+pub fn decompress_block(compressed: &[u8], block_len: usize) -> Result<Vec<u8>> {
+    let block = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(compressed, block_len)
+        .map_err(|_| Error::new(ErrorKind::InvalidData, "corrupt zisofs block"))?;
+    if block.len() != block_len {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "zisofs block decompressed to the wrong length",
+        ));
+    }
+    Ok(block)
+}
+
+/// Compresses one uncompressed block. All-zero blocks compress to nothing,
+/// which readers expand from the pointer table alone.
+pub fn compress_block(block: &[u8]) -> Vec<u8> {
+    if block.iter().all(|&byte| byte == 0) {
+        return Vec::new();
+    }
+    miniz_oxide::deflate::compress_to_vec_zlib(block, COMPRESSION_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn header_roundtrip() {
+        let header = ZisofsHeader::new(100_000, 15).unwrap();
+        let parsed = ZisofsHeader::parse(&header.to_bytes()).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(parsed.block_count(), 4);
+        assert_eq!(parsed.block_len(3), 100_000 - 3 * 32768);
+        assert_eq!(parsed.data_offset(), 16 + 4 * 5);
+    }
+
+    #[test]
+    fn header_rejects_bad_magic_and_block_size() {
+        let mut bytes = ZisofsHeader::new(10, 16).unwrap().to_bytes();
+        bytes[13] = 20;
+        assert!(ZisofsHeader::parse(&bytes).is_err());
+        bytes[13] = 16;
+        bytes[0] ^= 1;
+        assert!(ZisofsHeader::parse(&bytes).is_err());
+        assert!(ZisofsHeader::new(10, 14).is_err());
+    }
+
+    #[test]
+    fn blocks_roundtrip_and_zero_blocks_are_empty() {
+        let block = (0..4096u32)
+            .map(|value| (value % 7) as u8)
+            .collect::<Vec<_>>();
+        let compressed = compress_block(&block);
+        assert!(compressed.len() < block.len());
+        assert_eq!(decompress_block(&compressed, block.len()).unwrap(), block);
+        assert!(decompress_block(&compressed, block.len() - 1).is_err());
+        assert!(compress_block(&vec![0u8; 4096]).is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+#![cfg(all(feature = "std", feature = "sync", feature = "write"))]
+
+//! `CreationExtensions::apple_partition_map` adds an Apple Partition Map with
+//! 2048-byte blocks next to the MBR and GPT, in the layout libisofs uses.
+
+use std::io::Cursor;
+use std::num::NonZeroU16;
+
+use hadris_iso::boot::options::{BootEntryOptions, BootOptions, BootSectionOptions};
+use hadris_iso::boot::{EmulationType, PlatformId};
+use hadris_iso::read::{IsoImage, PathSeparator};
+use hadris_iso::write::options::{
+    CreationExtensions, CreationFeatures, HybridBootOptions, IsoFormatOptions,
+};
+use hadris_iso::write::{InputEntry, InputTree, IsoImageWriter, SourcedInputTree};
+use hadris_part::{
+    ApmDisk, ApmDiskReadExt, GptDisk, GptDiskReadExt, MasterBootRecord, MasterBootRecordReadExt,
+};
+
+fn boot_options() -> BootOptions {
+    let entry = |path: &str| BootEntryOptions {
+        load_size: Some(NonZeroU16::new(4).unwrap()),
+        boot_image_path: path.to_string(),
+        boot_info_table: false,
+        grub2_boot_info: false,
+        emulation: EmulationType::NoEmulation,
+    };
+    BootOptions {
+        write_boot_catalog: true,
+        default: entry("bios.img"),
+        entries: vec![(
+            BootSectionOptions {
+                platform: PlatformId::UEFI,
+            },
+            entry("efi.img"),
+        )],
+    }
+}
+
+fn apm() -> CreationExtensions {
+    CreationExtensions::default().with_apple_partition_map(true)
+}
+
+fn create(
+    hybrid_boot: HybridBootOptions,
+    el_torito: bool,
+    extensions: CreationExtensions,
+) -> Vec<u8> {
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![
+            InputEntry::file("bios.img", vec![0x11; 2048]),
+            InputEntry::file("efi.img", vec![0x33; 5000]),
+            InputEntry::file("DATA.BIN", vec![0xA5; 100_000]),
+        ],
+    );
+    let options = IsoFormatOptions {
+        volume_name: "APM_HYBRID".to_owned(),
+        system_id: None,
+        volume_set_id: None,
+        publisher_id: None,
+        preparer_id: None,
+        application_id: None,
+        sector_size: 2048,
+        features: CreationFeatures {
+            el_torito: el_torito.then(boot_options),
+            hybrid_boot: Some(hybrid_boot),
+            ..CreationFeatures::default()
+        },
+        path_separator: PathSeparator::ForwardSlash,
+        strict_charset: false,
+    };
+    IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        SourcedInputTree::new(tree),
+        options,
+        extensions,
+    )
+    .unwrap()
+    .into_inner()
+}
+
+#[test]
+fn hybrid_image_carries_mbr_gpt_and_apm() {
+    let image = create(HybridBootOptions::hybrid(), true, apm());
+    let blocks = (image.len() / 2048) as u32;
+
+    // Block 0 is a DDM and an MBR at once.
+    assert_eq!(&image[..4], b"ER\x08\x00");
+    let mbr = MasterBootRecord::read_from(&mut Cursor::new(&image[..])).unwrap();
+    assert!(mbr.get_partition_table().count() >= 2);
+
+    let apm = ApmDisk::read_from(&mut Cursor::new(&image[..])).unwrap();
+    apm.validate().unwrap();
+    assert_eq!(apm.block_size(), 2048);
+    assert_eq!(apm.ddm.block_count.to_ne(), blocks);
+    let names: Vec<_> = apm.entries.iter().map(|e| e.name().to_vec()).collect();
+    assert_eq!(
+        names,
+        [&b"Apple"[..], b"ISO9660", b"EFI", b"ISO9660"].map(<[u8]>::to_vec)
+    );
+
+    // The EFI entry covers exactly the UEFI boot image.
+    let iso = IsoImage::open(Cursor::new(&image[..])).unwrap();
+    let efi = iso.find_path("efi.img").unwrap().unwrap();
+    let entry = &apm.entries[2];
+    assert_eq!(entry.partition_type(), b"Apple_HFS");
+    assert_eq!(entry.start_block.to_ne(), efi.header().extent.read());
+    assert_eq!(entry.block_count.to_ne(), 3);
+    // The data partitions run up to the end of the image.
+    assert_eq!(apm.entries[3].end_block(), Some(blocks as u64 - 1));
+
+    // The GPT moved its entry array past the map and stays valid.
+    let gpt = GptDisk::read_from(&mut Cursor::new(&image[..]), 512).unwrap();
+    gpt.validate().unwrap();
+    assert_eq!(gpt.primary_header.partition_entry_lba.to_ne(), 20);
+    assert_eq!(gpt.primary_header.first_usable_lba.to_ne(), 52);
+
+    let data = iso.find_path("DATA.BIN").unwrap().unwrap();
+    assert_eq!(iso.read_file(&data).unwrap(), vec![0xA5; 100_000]);
+}
+
+#[test]
+fn apm_without_boot_image_describes_iso_data() {
+    let image = create(HybridBootOptions::mbr(), false, apm());
+    let apm = ApmDisk::read_from(&mut Cursor::new(&image[..])).unwrap();
+    apm.validate().unwrap();
+    assert_eq!(apm.entries.len(), 2);
+    assert_eq!(apm.entries[1].partition_type(), b"ISO9660_data");
+    assert_eq!(apm.entries[1].start_block.to_ne(), 5);
+    // The MBR sharing block 0 stays the detected scheme; the map is found
+    // through its own probe.
+    let mut cursor = Cursor::new(&image[..]);
+    assert_eq!(
+        hadris_part::sync::partition_table::detect(&mut cursor).unwrap(),
+        hadris_part::PartitionSchemeType::Mbr
+    );
+    assert!(hadris_part::sync::partition_table::detect_apm(&mut cursor).unwrap());
+
+    // Without the option, block 0 stays a plain MBR.
+    let image = create(
+        HybridBootOptions::mbr(),
+        false,
+        CreationExtensions::default(),
+    );
+    assert_ne!(&image[..2], b"ER");
+    assert!(!hadris_part::sync::partition_table::detect_apm(&mut Cursor::new(&image[..])).unwrap());
+}
+
+#[test]
+fn bootstrap_must_leave_room_for_the_ddm() {
+    let tree = InputTree::new(PathSeparator::ForwardSlash, vec![]);
+    let options = IsoFormatOptions {
+        volume_name: "APM".to_owned(),
+        system_id: None,
+        volume_set_id: None,
+        publisher_id: None,
+        preparer_id: None,
+        application_id: None,
+        sector_size: 2048,
+        features: CreationFeatures {
+            hybrid_boot: Some(HybridBootOptions::mbr().bootstrap(vec![0xEB, 0x63, 0x90])),
+            ..CreationFeatures::default()
+        },
+        path_separator: PathSeparator::ForwardSlash,
+        strict_charset: false,
+    };
+    assert!(
+        IsoImageWriter::create_with_sources(
+            Cursor::new(Vec::new()),
+            SourcedInputTree::new(tree),
+            options,
+            apm(),
+        )
+        .is_err()
+    );
+}
@@ -0,0 +1,205 @@
+#![cfg(all(feature = "std", feature = "sync", feature = "write"))]
+
+//! Lazily-read `FileSource` inputs attached through `SourcedInputTree`: host
+//! paths, shared readers, and callbacks are streamed into the data sectors and
+//! sized from their declared lengths.
+
+use std::io::Cursor;
+use std::sync::Arc;
+
+use hadris_iso::read::PathSeparator;
+use hadris_iso::sync::read::IsoImage;
+use hadris_iso::sync::write::options::{CreationExtensions, CreationFeatures, IsoFormatOptions};
+use hadris_iso::sync::write::{
+    File as IsoFile, FileConversionError, FileSource, InputEntry, InputEntryKind, InputFiles,
+    InputTree, IsoImageWriter, SourcedInputTree, estimator,
+};
+
+fn options() -> IsoFormatOptions {
+    IsoFormatOptions {
+        volume_name: "SOURCES".to_owned(),
+        system_id: None,
+        volume_set_id: None,
+        publisher_id: None,
+        preparer_id: None,
+        application_id: None,
+        sector_size: 2048,
+        features: CreationFeatures::default(),
+        path_separator: PathSeparator::ForwardSlash,
+        strict_charset: false,
+    }
+}
+
+fn pattern(offset: u64, buf: &mut [u8]) {
+    for (index, byte) in buf.iter_mut().enumerate() {
+        *byte = ((offset + index as u64) % 251) as u8;
+    }
+}
+
+fn expected_pattern(len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    pattern(0, &mut data);
+    data
+}
+
+#[test]
+fn reader_callback_and_path_sources_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let host_path = dir.path().join("host.bin");
+    std::fs::write(&host_path, vec![0x42; 5000]).unwrap();
+
+    let reader_data = (0..10_000u32).map(|value| value as u8).collect::<Vec<_>>();
+    let callback_len = 700 * 1024;
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![InputEntry::file("INLINE.TXT", b"inline".to_vec())],
+    );
+    let files = SourcedInputTree::new(tree)
+        .with_source("HOST.BIN", FileSource::from_path(&host_path).unwrap())
+        .unwrap()
+        .with_source(
+            "DIR/READER.BIN",
+            FileSource::from_reader(Cursor::new(reader_data.clone()), 9_000),
+        )
+        .unwrap()
+        .with_source(
+            "DIR/CALLBACK.BIN",
+            FileSource::from_fn(callback_len as u64, |offset, buf| {
+                pattern(offset, buf);
+                Ok(())
+            }),
+        )
+        .unwrap();
+
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        files,
+        options(),
+        CreationExtensions::default(),
+    )
+    .unwrap();
+    let image = IsoImage::open(Cursor::new(output.into_inner())).unwrap();
+
+    let host = image.find_path("HOST.BIN").unwrap().unwrap();
+    assert_eq!(image.read_file(&host).unwrap(), vec![0x42; 5000]);
+    let reader = image.find_path("DIR/READER.BIN").unwrap().unwrap();
+    assert_eq!(image.read_file(&reader).unwrap(), reader_data[..9_000]);
+    let callback = image.find_path("DIR/CALLBACK.BIN").unwrap().unwrap();
+    assert_eq!(
+        image.read_file(&callback).unwrap(),
+        expected_pattern(callback_len)
+    );
+    let inline = image.find_path("INLINE.TXT").unwrap().unwrap();
+    assert_eq!(image.read_file(&inline).unwrap(), b"inline");
+}
+
+#[test]
+fn source_replaces_existing_entry() {
+    let files = InputFiles {
+        path_separator: PathSeparator::ForwardSlash,
+        files: vec![IsoFile::File {
+            name: Arc::new("DATA.BIN".to_owned()),
+            contents: b"stale".to_vec(),
+        }],
+    };
+    let files = SourcedInputTree::new(files)
+        .with_source(
+            "DATA.BIN",
+            FileSource::from_reader(Cursor::new(vec![7u8; 3000]), 3000),
+        )
+        .unwrap();
+    assert_eq!(files.tree().entries.len(), 1);
+
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        files,
+        options(),
+        CreationExtensions::default(),
+    )
+    .unwrap();
+    let image = IsoImage::open(Cursor::new(output.into_inner())).unwrap();
+    let entry = image.find_path("DATA.BIN").unwrap().unwrap();
+    assert_eq!(image.read_file(&entry).unwrap(), vec![7u8; 3000]);
+}
+
+#[test]
+fn invalid_source_paths_are_rejected() {
+    let tree = || {
+        SourcedInputTree::new(InputTree::new(
+            PathSeparator::ForwardSlash,
+            vec![InputEntry::file("FILE.TXT", b"file".to_vec())],
+        ))
+    };
+    let source = || FileSource::from_reader(Cursor::new(vec![1u8; 10]), 10);
+    for path in ["", "/", "../ESCAPE.BIN", "FILE.TXT/CHILD.BIN"] {
+        match tree().with_source(path, source()) {
+            Err(FileConversionError::Io(error)) => {
+                assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput, "{path:?}");
+            }
+            _ => panic!("{path:?} was accepted"),
+        }
+    }
+}
+
+#[test]
+fn source_shorter_than_declared_length_fails() {
+    let files = SourcedInputTree::new(InputTree::new(PathSeparator::ForwardSlash, Vec::new()))
+        .with_source(
+            "SHORT.BIN",
+            FileSource::from_reader(Cursor::new(vec![1u8; 100]), 4096),
+        )
+        .unwrap();
+    assert!(
+        IsoImageWriter::create_with_sources(
+            Cursor::new(Vec::new()),
+            files,
+            options(),
+            CreationExtensions::default(),
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn estimator_uses_declared_source_lengths() {
+    let len = 3 * 1024 * 1024;
+    let source = FileSource::from_fn(len, |_, _| panic!("the estimator must not read data"));
+    let files = SourcedInputTree::new(InputTree::new(PathSeparator::ForwardSlash, Vec::new()))
+        .with_source("BIG.BIN", source)
+        .unwrap();
+    let estimate = estimator::estimate_sourced(&files, &options(), &CreationExtensions::default());
+    assert!(estimate.breakdown.file_data >= len);
+
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![InputEntry::file("BIG.BIN", vec![0u8; len as usize])],
+    );
+    assert_eq!(
+        estimator::estimate_tree(&tree, &options())
+            .breakdown
+            .file_data,
+        estimate.breakdown.file_data
+    );
+}
+
+#[test]
+fn from_fs_streams_regular_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), b"alpha").unwrap();
+    let files = SourcedInputTree::from_fs(dir.path(), PathSeparator::ForwardSlash).unwrap();
+    assert!(matches!(
+        &files.tree().entries[0].kind,
+        InputEntryKind::File(contents) if contents.is_empty()
+    ));
+
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        files,
+        options(),
+        CreationExtensions::default(),
+    )
+    .unwrap();
+    let image = IsoImage::open(Cursor::new(output.into_inner())).unwrap();
+    let entry = image.find_path("A.TXT").unwrap().unwrap();
+    assert_eq!(image.read_file(&entry).unwrap(), b"alpha");
+}
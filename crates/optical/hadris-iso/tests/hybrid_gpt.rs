@@ -0,0 +1,108 @@
+#![cfg(all(feature = "std", feature = "sync", feature = "write"))]
+
+//! GPT and hybrid MBR+GPT images carry both GPT copies: the primary at
+//! LBA 1 and the backup entry array and header in the last blocks of the
+//! volume, with CRCs `hadris_part` accepts.
+
+use std::io::Cursor;
+
+use hadris_iso::read::{IsoImage, PathSeparator};
+use hadris_iso::write::options::{CreationFeatures, HybridBootOptions, IsoFormatOptions};
+use hadris_iso::write::{InputEntry, InputTree, IsoImageWriter, estimator};
+use hadris_part::gpt::{GptPartitionEntry, calculate_partition_array_crc32};
+use hadris_part::{GptDisk, GptDiskReadExt};
+
+fn options(hybrid_boot: HybridBootOptions) -> IsoFormatOptions {
+    IsoFormatOptions {
+        volume_name: "BACKUP_GPT".to_owned(),
+        system_id: None,
+        volume_set_id: None,
+        publisher_id: None,
+        preparer_id: None,
+        application_id: None,
+        sector_size: 2048,
+        features: CreationFeatures {
+            hybrid_boot: Some(hybrid_boot),
+            ..CreationFeatures::default()
+        },
+        path_separator: PathSeparator::ForwardSlash,
+        strict_charset: false,
+    }
+}
+
+fn create(hybrid_boot: HybridBootOptions) -> Vec<u8> {
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![InputEntry::file("DATA.BIN", vec![0xA5; 100_000])],
+    );
+    let estimate = estimator::estimate_tree(&tree, &options(hybrid_boot.clone()));
+    let image = IsoImageWriter::create(Cursor::new(Vec::new()), tree, options(hybrid_boot))
+        .unwrap()
+        .into_inner();
+    assert!(estimate.minimum_bytes() >= image.len() as u64);
+    image
+}
+
+fn assert_both_copies_valid(image: &[u8]) {
+    let disk_blocks = image.len() as u64 / 512;
+    let gpt = GptDisk::read_from(&mut Cursor::new(image), 512).unwrap();
+    gpt.validate().unwrap();
+
+    assert_eq!(gpt.primary_header.alternate_lba.to_ne(), disk_blocks - 1);
+    assert_eq!(gpt.backup_header.my_lba.to_ne(), disk_blocks - 1);
+    assert_eq!(
+        gpt.backup_header.partition_entry_lba.to_ne(),
+        disk_blocks - 33
+    );
+    assert_eq!(gpt.primary_header.num_partition_entries.to_ne(), 128);
+
+    // The backup entry array is a byte-for-byte copy of the primary one.
+    let start = gpt.backup_header.partition_entry_lba.to_ne() as usize * 512;
+    let backup: Vec<GptPartitionEntry> = image[start..start + 128 * 128]
+        .chunks_exact(128)
+        .map(bytemuck::pod_read_unaligned)
+        .collect();
+    assert_eq!(
+        calculate_partition_array_crc32(&backup),
+        gpt.backup_header.partition_entry_array_crc32.to_ne()
+    );
+    assert_eq!(
+        image[start..start + 128 * 128],
+        image[1024..1024 + 128 * 128]
+    );
+
+    let (_, partition) = gpt.partitions().next().unwrap();
+    assert_eq!(gpt.partition_count(), 1);
+    assert_eq!(
+        partition.last_lba.to_ne(),
+        gpt.primary_header.last_usable_lba.to_ne()
+    );
+
+    // The volume covers the backup GPT, and the ISO still reads.
+    let iso = IsoImage::open(Cursor::new(image)).unwrap();
+    let pvd = iso.read_pvd().unwrap();
+    assert_eq!(pvd.volume_space_size.read() as usize * 2048, image.len());
+    let entry = iso.find_path("DATA.BIN").unwrap().unwrap();
+    assert_eq!(iso.read_file(&entry).unwrap(), vec![0xA5; 100_000]);
+}
+
+#[test]
+fn gpt_image_has_valid_backup() {
+    let image = create(HybridBootOptions::gpt());
+    assert_eq!(image[446 + 4], 0xEE);
+    assert_both_copies_valid(&image);
+}
+
+#[test]
+fn hybrid_image_has_valid_backup() {
+    let image = create(HybridBootOptions::hybrid());
+    assert_both_copies_valid(&image);
+}
+
+#[test]
+fn corrupt_backup_header_is_detected() {
+    let mut image = create(HybridBootOptions::gpt());
+    let last = image.len() - 512;
+    image[last + 40] ^= 0xFF;
+    assert!(GptDisk::read_from(&mut Cursor::new(&image[..]), 512).is_err());
+}
@@ -0,0 +1,232 @@
+#![cfg(all(feature = "std", feature = "sync", feature = "write"))]
+
+//! Files larger than 4 GiB are written as multi-extent records (ECMA-119
+//! 9.1.6) in every directory tree and read back as one contiguous stream.
+//!
+//! The image lives on a sparse in-memory device so the test never holds the
+//! payload: only sectors containing non-zero bytes are stored.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use hadris_iso::directory::FileFlags;
+use hadris_iso::joliet::JolietLevel;
+use hadris_iso::read::{IsoImage, PathSeparator};
+use hadris_iso::rrip::RripOptions;
+use hadris_iso::write::options::{
+    BaseIsoLevel, CreationExtensions, CreationFeatures, IsoFormatOptions,
+};
+use hadris_iso::write::{FileSource, InputEntry, InputTree, IsoImageWriter, SourcedInputTree};
+
+const SECTOR: u64 = 2048;
+/// First extent length used by the writer: `u32::MAX` rounded down to a sector.
+const EXTENT_LEN: u64 = 0xFFFF_F800;
+const FILE_LEN: u64 = EXTENT_LEN + 3 * SECTOR + 100;
+/// Offsets in the payload carrying a non-zero marker byte.
+const MARKERS: [u64; 4] = [0, EXTENT_LEN - 1, EXTENT_LEN, FILE_LEN - 1];
+
+static ZERO_SECTOR: [u8; SECTOR as usize] = [0; SECTOR as usize];
+
+#[derive(Debug, Default)]
+struct SparseDevice {
+    sectors: BTreeMap<u64, Box<[u8; SECTOR as usize]>>,
+    len: u64,
+    position: u64,
+}
+
+impl Read for SparseDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.len.saturating_sub(self.position);
+        let n = (buf.len() as u64).min(available) as usize;
+        let mut done = 0;
+        while done < n {
+            let sector = self.position / SECTOR;
+            let offset = (self.position % SECTOR) as usize;
+            let take = (SECTOR as usize - offset).min(n - done);
+            match self.sectors.get(&sector) {
+                Some(data) => buf[done..done + take].copy_from_slice(&data[offset..offset + take]),
+                None => buf[done..done + take].fill(0),
+            }
+            done += take;
+            self.position += take as u64;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for SparseDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut done = 0;
+        while done < buf.len() {
+            let sector = self.position / SECTOR;
+            let offset = (self.position % SECTOR) as usize;
+            let take = (SECTOR as usize - offset).min(buf.len() - done);
+            let chunk = &buf[done..done + take];
+            if let Some(data) = self.sectors.get_mut(&sector) {
+                data[offset..offset + take].copy_from_slice(chunk);
+            } else if chunk != &ZERO_SECTOR[..take] {
+                let mut data = Box::new([0u8; SECTOR as usize]);
+                data[offset..offset + take].copy_from_slice(chunk);
+                self.sectors.insert(sector, data);
+            }
+            done += take;
+            self.position += take as u64;
+        }
+        self.len = self.len.max(self.position);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SparseDevice {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset).unwrap(),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset).unwrap(),
+        };
+        Ok(self.position)
+    }
+}
+
+fn marker(offset: u64) -> u8 {
+    (MARKERS.iter().position(|&marker| marker == offset).unwrap() + 1) as u8
+}
+
+fn huge_source() -> FileSource {
+    FileSource::from_fn(FILE_LEN, |offset, buf| {
+        buf.fill(0);
+        let end = offset + buf.len() as u64;
+        for position in MARKERS {
+            if (offset..end).contains(&position) {
+                buf[(position - offset) as usize] = marker(position);
+            }
+        }
+        Ok(())
+    })
+}
+
+fn options(filenames: BaseIsoLevel) -> IsoFormatOptions {
+    IsoFormatOptions {
+        volume_name: "MULTI_EXTENT".to_owned(),
+        system_id: None,
+        volume_set_id: None,
+        publisher_id: None,
+        preparer_id: None,
+        application_id: None,
+        sector_size: 2048,
+        features: CreationFeatures {
+            filenames,
+            joliet: Some(JolietLevel::Level3),
+            rock_ridge: Some(RripOptions::default()),
+            ..CreationFeatures::default()
+        },
+        path_separator: PathSeparator::ForwardSlash,
+        strict_charset: false,
+    }
+}
+
+#[test]
+fn large_file_is_split_across_extents_in_every_tree() {
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![InputEntry::file("small.txt", b"small".to_vec())],
+    );
+    let files = SourcedInputTree::new(tree)
+        .with_source("huge.bin", huge_source())
+        .unwrap();
+    let level = BaseIsoLevel::Level3 {
+        supports_lowercase: false,
+        supports_rrip: true,
+    };
+    let device = IsoImageWriter::create_with_sources(
+        SparseDevice::default(),
+        files,
+        options(level),
+        CreationExtensions::default(),
+    )
+    .unwrap();
+    let image = IsoImage::open(device).unwrap();
+
+    assert_eq!(image.root_dirs().len(), 2);
+    for root in image.root_dirs() {
+        let entries: Vec<_> = root
+            .iter(&image)
+            .entries()
+            .map(Result::unwrap)
+            .filter(|entry| !entry.is_special())
+            .collect();
+        // The continuation record is folded into its file, not listed.
+        assert_eq!(entries.len(), 2, "{:?}", root.entry_type());
+        let huge = entries
+            .iter()
+            .find(|entry| entry.total_size() > 4096)
+            .unwrap();
+        assert!(huge.is_multi_extent());
+        assert!(FileFlags::from_bits_retain(huge.header().flags).contains(FileFlags::NOT_FINAL));
+        let extents: Vec<_> = huge.extents().collect();
+        assert_eq!(extents.len(), 2);
+        assert_eq!(extents[0].length as u64, EXTENT_LEN);
+        assert_eq!(extents[1].length as u64, FILE_LEN - EXTENT_LEN);
+        assert_eq!(
+            extents[1].sector.0 as u64,
+            extents[0].sector.0 as u64 + EXTENT_LEN / SECTOR
+        );
+        assert_eq!(huge.total_size(), FILE_LEN);
+
+        let small = entries
+            .iter()
+            .find(|entry| entry.total_size() == 5)
+            .unwrap();
+        assert_eq!(image.read_file(small).unwrap(), b"small");
+    }
+
+    // Rock Ridge keeps the original name on the multi-extent file.
+    let huge = image.find_path("huge.bin").unwrap().unwrap();
+    let rrip = huge.rrip.as_ref().unwrap();
+    assert_eq!(rrip.alternate_name.as_deref(), Some("huge.bin"));
+
+    // Streaming crosses the extent boundary transparently.
+    let mut stream = image.open_file(&huge).unwrap();
+    assert_eq!(stream.len(), FILE_LEN);
+    let mut buffer = [0u8; 8];
+    for offset in MARKERS {
+        let start = offset.saturating_sub(4).min(FILE_LEN - 8);
+        stream.set_position(start).unwrap();
+        assert_eq!(stream.read_chunk(&mut buffer).unwrap(), 8);
+        for (index, &byte) in buffer.iter().enumerate() {
+            let position = start + index as u64;
+            let expected = if MARKERS.contains(&position) {
+                marker(position)
+            } else {
+                0
+            };
+            assert_eq!(byte, expected, "offset {position}");
+        }
+    }
+    assert_eq!(stream.position(), FILE_LEN);
+    assert_eq!(stream.read_chunk(&mut buffer).unwrap(), 0);
+    assert!(stream.set_position(FILE_LEN + 1).is_err());
+}
+
+#[test]
+fn large_files_require_interchange_level_3() {
+    let files = SourcedInputTree::new(InputTree::new(PathSeparator::ForwardSlash, Vec::new()))
+        .with_source("huge.bin", huge_source())
+        .unwrap();
+    let level = BaseIsoLevel::Level2 {
+        supports_lowercase: false,
+        supports_rrip: true,
+    };
+    let error = IsoImageWriter::create_with_sources(
+        SparseDevice::default(),
+        files,
+        options(level),
+        CreationExtensions::default(),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("level 3"), "{error}");
+}
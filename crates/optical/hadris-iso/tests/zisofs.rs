@@ -0,0 +1,277 @@
+#![cfg(all(
+    feature = "std",
+    feature = "sync",
+    feature = "write",
+    feature = "zisofs"
+))]
+
+//! zisofs: `CreationExtensions::zisofs` compresses regular files at creation
+//! time, and the reader inflates `ZF`-tagged files transparently.
+
+use std::io::Cursor;
+
+use hadris_iso::joliet::JolietLevel;
+use hadris_iso::read::{IsoImage, PathSeparator};
+use hadris_iso::rrip::{RripOptions, ZisofsOptions};
+use hadris_iso::write::options::{
+    BaseIsoLevel, CreationExtensions, CreationFeatures, IsoFormatOptions,
+};
+use hadris_iso::write::{
+    FileSource, InputEntry, InputTree, IsoImageWriter, SourcedInputTree, estimator,
+};
+
+fn options() -> IsoFormatOptions {
+    IsoFormatOptions {
+        volume_name: "ZISOFS".to_owned(),
+        system_id: None,
+        volume_set_id: None,
+        publisher_id: None,
+        preparer_id: None,
+        application_id: None,
+        sector_size: 2048,
+        features: CreationFeatures {
+            // Rock Ridge on the primary tree makes it the preferred namespace.
+            filenames: BaseIsoLevel::Level3 {
+                supports_lowercase: false,
+                supports_rrip: true,
+            },
+            joliet: Some(JolietLevel::Level3),
+            rock_ridge: Some(RripOptions::default()),
+            ..CreationFeatures::default()
+        },
+        path_separator: PathSeparator::ForwardSlash,
+        strict_charset: false,
+    }
+}
+
+fn extensions(zisofs: Option<ZisofsOptions>) -> CreationExtensions {
+    CreationExtensions::default().with_zisofs(zisofs)
+}
+
+fn text(len: usize) -> Vec<u8> {
+    b"the quick brown fox jumps over the lazy dog\n"
+        .iter()
+        .copied()
+        .cycle()
+        .take(len)
+        .collect()
+}
+
+fn noise(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491u32;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+fn input_tree() -> (SourcedInputTree, Vec<u8>) {
+    // Text, then a run of zero blocks, then more text: exercises stored,
+    // zero (pointer-only), and short final blocks.
+    let mut sparse = text(70_000);
+    sparse.resize(70_000 + 3 * 32768, 0);
+    sparse.extend_from_slice(&text(1234));
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![
+            InputEntry::file("text.txt", text(200_000)),
+            InputEntry::file("noise.bin", noise(50_000)),
+            InputEntry::file("tiny.txt", b"tiny".to_vec()),
+        ],
+    );
+    let tree = SourcedInputTree::new(tree)
+        .with_source(
+            "sparse.bin",
+            FileSource::from_reader(Cursor::new(sparse.clone()), sparse.len() as u64),
+        )
+        .unwrap();
+    (tree, sparse)
+}
+
+#[test]
+fn compressed_files_roundtrip() {
+    let (tree, sparse) = input_tree();
+    let zisofs = ZisofsOptions::default();
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        tree,
+        options(),
+        extensions(Some(zisofs)),
+    )
+    .unwrap();
+    let image = IsoImage::open(Cursor::new(output.into_inner())).unwrap();
+
+    let entry = image.find_path("text.txt").unwrap().unwrap();
+    let zf = image.zisofs_entry(&entry).unwrap().unwrap();
+    assert!(zf.is_zisofs());
+    assert_eq!(zf.block_size_log2, zisofs.block_size_log2);
+    assert_eq!(zf.uncompressed_size.read(), 200_000);
+    assert!(entry.total_size() < 200_000 / 10);
+    assert_eq!(image.read_file(&entry).unwrap(), text(200_000));
+
+    let entry = image.find_path("sparse.bin").unwrap().unwrap();
+    assert!(image.zisofs_entry(&entry).unwrap().is_some());
+    let mut stream = image.open_file(&entry).unwrap();
+    assert_eq!(stream.len(), sparse.len() as u64);
+    // Random access into a zero block and across a block boundary.
+    let mut buffer = [0xFFu8; 64];
+    stream.set_position(100_000).unwrap();
+    assert_eq!(stream.read_chunk(&mut buffer).unwrap(), 64);
+    assert!(buffer.iter().all(|&byte| byte == 0));
+    stream.set_position(32768 - 32).unwrap();
+    assert_eq!(stream.read_chunk(&mut buffer).unwrap(), 64);
+    assert_eq!(buffer[..], sparse[32768 - 32..32768 + 32]);
+    assert_eq!(image.read_file(&entry).unwrap(), sparse);
+
+    // Files that would not shrink are stored as-is, without `ZF`.
+    for (name, contents) in [("noise.bin", noise(50_000)), ("tiny.txt", b"tiny".to_vec())] {
+        let entry = image.find_path(name).unwrap().unwrap();
+        assert!(image.zisofs_entry(&entry).unwrap().is_none(), "{name}");
+        assert_eq!(image.read_file(&entry).unwrap(), contents);
+    }
+}
+
+/// Joliet has no `ZF` entry, so its records point at an uncompressed copy of
+/// each compressed file and Joliet-only readers see the original bytes.
+#[test]
+fn joliet_tree_reads_uncompressed_copies() {
+    let (tree, _) = input_tree();
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        tree,
+        options(),
+        extensions(Some(ZisofsOptions::default())),
+    )
+    .unwrap();
+    let image = IsoImage::open(Cursor::new(output.into_inner())).unwrap();
+    assert_eq!(image.root_dirs().len(), 2);
+    let joliet = image.root_dirs().iter().nth(1).unwrap();
+    let entry = joliet
+        .iter(&image)
+        .entries()
+        .map(Result::unwrap)
+        .find(|entry| entry.matches_name("text.txt"))
+        .unwrap();
+    assert_eq!(entry.total_size(), 200_000);
+    assert_eq!(image.read_file(&entry).unwrap(), text(200_000));
+
+    let primary = image.find_path("text.txt").unwrap().unwrap();
+    assert!(primary.total_size() < 200_000 / 10);
+}
+
+/// The estimate counts each compressed file at its uncompressed size plus the
+/// uncompressed copy written for the Joliet tree.
+#[test]
+fn estimate_counts_uncompressed_copies() {
+    let (tree, _) = input_tree();
+    let zisofs = extensions(Some(ZisofsOptions::default()));
+    let estimate = estimator::estimate_sourced(&tree, &options(), &zisofs);
+    let plain = estimator::estimate_sourced(&tree, &options(), &extensions(None));
+    let copies =
+        (200_000u64 + 70_000 + 3 * 32768 + 1234).div_ceil(2048) + 50_000u64.div_ceil(2048) + 1;
+    assert_eq!(
+        estimate.breakdown.file_data,
+        plain.breakdown.file_data + copies * 2048
+    );
+
+    let output =
+        IsoImageWriter::create_with_sources(Cursor::new(Vec::new()), tree, options(), zisofs)
+            .unwrap();
+    assert!(estimate.minimum_bytes() >= output.into_inner().len() as u64);
+}
+
+#[test]
+fn compression_is_opt_in_and_validated() {
+    let (tree, _) = input_tree();
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        tree,
+        options(),
+        CreationExtensions::default(),
+    )
+    .unwrap();
+    let image = IsoImage::open(Cursor::new(output.into_inner())).unwrap();
+    let entry = image.find_path("text.txt").unwrap().unwrap();
+    assert!(image.zisofs_entry(&entry).unwrap().is_none());
+    assert_eq!(entry.total_size(), 200_000);
+
+    let (tree, _) = input_tree();
+    let invalid = ZisofsOptions {
+        block_size_log2: 12,
+    };
+    assert!(
+        IsoImageWriter::create_with_sources(
+            Cursor::new(Vec::new()),
+            tree,
+            options(),
+            extensions(Some(invalid))
+        )
+        .is_err()
+    );
+}
+
+/// A few KB of image whose header and directory record claim a 4 GiB file of
+/// zero blocks is rejected before anything is decompressed.
+#[test]
+fn oversized_header_is_rejected() {
+    let tree = InputTree::new(
+        PathSeparator::ForwardSlash,
+        vec![InputEntry::file("text.txt", text(200_000))],
+    );
+    let output = IsoImageWriter::create_with_sources(
+        Cursor::new(Vec::new()),
+        SourcedInputTree::new(tree),
+        options(),
+        extensions(Some(ZisofsOptions::default())),
+    )
+    .unwrap();
+    let mut bytes = output.into_inner();
+    let (extent, stored_len) = {
+        let image = IsoImage::open(Cursor::new(bytes.as_slice())).unwrap();
+        let entry = image.find_path("text.txt").unwrap().unwrap();
+        (entry.header().extent.read(), entry.header().data_len.read())
+    };
+    let both_endian = |value: u32| [value.to_le_bytes(), value.to_be_bytes()].concat();
+    let patch = |bytes: &mut Vec<u8>, from: &[u8], to: &[u8]| {
+        let at = bytes
+            .windows(from.len())
+            .position(|window| window == from)
+            .unwrap();
+        bytes[at..at + to.len()].copy_from_slice(to);
+    };
+
+    let claimed = u32::MAX;
+    let header = extent as usize * 2048;
+    bytes[header + 8..header + 12].copy_from_slice(&claimed.to_le_bytes());
+    let mut zf = b"ZF\x10\x01pz\x04\x0f".to_vec();
+    zf.extend_from_slice(&both_endian(200_000));
+    let mut forged = zf[..8].to_vec();
+    forged.extend_from_slice(&both_endian(claimed));
+    patch(&mut bytes, &zf, &forged);
+    // The record's data length covers the whole pointer table.
+    let record = [both_endian(extent), both_endian(stored_len)].concat();
+    let forged = [both_endian(extent), both_endian(0x7FFF_0000)].concat();
+    patch(&mut bytes, &record, &forged);
+
+    let image = IsoImage::open(Cursor::new(bytes)).unwrap();
+    let entry = image.find_path("text.txt").unwrap().unwrap();
+    assert_eq!(
+        image
+            .zisofs_entry(&entry)
+            .unwrap()
+            .unwrap()
+            .uncompressed_size
+            .read(),
+        claimed
+    );
+    for error in [
+        image.read_file(&entry).unwrap_err(),
+        image.open_file(&entry).err().unwrap(),
+    ] {
+        assert_eq!(error.kind(), hadris_iso::io::ErrorKind::InvalidData);
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::{
     CharSpec, DescriptorTag, EntityIdentifier, ExtentDescriptor, LongAllocationDescriptor,
-    TagIdentifier,
+    SPARABLE_PARTITION_ID, SparablePartitionMap, TagIdentifier,
 };
 use crate::error::Result;
 
@@ -88,32 +88,58 @@ impl LogicalVolumeDescriptor {
     ///
     /// Skips unknown / Type 2 maps and stops if the table is truncated or malformed.
     pub fn type1_partition_maps(&self) -> impl Iterator<Item = Type1PartitionMap> + '_ {
+        self.raw_partition_maps().filter_map(|(map_type, entry)| {
+            if map_type != 1 || entry.len() < core::mem::size_of::<Type1PartitionMap>() {
+                return None;
+            }
+            let mut map = bytemuck::pod_read_unaligned::<Type1PartitionMap>(
+                &entry[..core::mem::size_of::<Type1PartitionMap>()],
+            );
+            map.volume_sequence_number = map.volume_sequence_number.to_le();
+            map.partition_number = map.partition_number.to_le();
+            Some(map)
+        })
+    }
+
+    /// Yield Type 2 sparable partition maps (UDF 2.2.9) from the embedded
+    /// map table.
+    ///
+    /// Skips Type 1 maps and Type 2 maps with another partition type
+    /// identifier, and stops if the table is truncated or malformed.
+    pub fn sparable_partition_maps(&self) -> impl Iterator<Item = SparablePartitionMap> + '_ {
+        self.raw_partition_maps().filter_map(|(map_type, entry)| {
+            if map_type != 2 || entry.len() < SparablePartitionMap::SIZE {
+                return None;
+            }
+            let map = bytemuck::pod_read_unaligned::<SparablePartitionMap>(
+                &entry[..SparablePartitionMap::SIZE],
+            )
+            .into_native();
+            map.partition_type_identifier
+                .is(SPARABLE_PARTITION_ID)
+                .then_some(map)
+        })
+    }
+
+    /// Walk the map table, yielding each map's type and bytes.
+    fn raw_partition_maps(&self) -> impl Iterator<Item = (u8, &[u8])> + '_ {
         let maps = &self.partition_maps;
         let mut offset = 0usize;
         let mut remaining = self.num_partition_maps as usize;
         core::iter::from_fn(move || {
-            while remaining > 0 {
-                remaining -= 1;
-                if offset + 2 > maps.len() {
-                    return None;
-                }
-                let map_type = maps[offset];
-                let map_len = maps[offset + 1] as usize;
-                if map_len < 2 || offset + map_len > maps.len() {
-                    return None;
-                }
-                let entry = &maps[offset..offset + map_len];
-                offset += map_len;
-                if map_type == 1 && map_len >= core::mem::size_of::<Type1PartitionMap>() {
-                    let mut map = bytemuck::pod_read_unaligned::<Type1PartitionMap>(
-                        &entry[..core::mem::size_of::<Type1PartitionMap>()],
-                    );
-                    map.volume_sequence_number = map.volume_sequence_number.to_le();
-                    map.partition_number = map.partition_number.to_le();
-                    return Some(map);
-                }
+            if remaining == 0 || offset + 2 > maps.len() {
+                return None;
+            }
+            remaining -= 1;
+            let map_type = maps[offset];
+            let map_len = maps[offset + 1] as usize;
+            if map_len < 2 || offset + map_len > maps.len() {
+                remaining = 0;
+                return None;
             }
-            None
+            let entry = &maps[offset..offset + map_len];
+            offset += map_len;
+            Some((map_type, entry))
         })
     }
 }
@@ -178,4 +204,35 @@ mod tests {
         assert_eq!(first.volume_sequence_number, 1);
         assert_eq!(first.partition_number, 0);
     }
+
+    #[test]
+    fn sparable_partition_maps_parses_type2_entry() {
+        let mut lvd: LogicalVolumeDescriptor = bytemuck::Zeroable::zeroed();
+        lvd.num_partition_maps = 2;
+        lvd.map_table_length = 70;
+        lvd.partition_maps[..6].copy_from_slice(&[1, 6, 1, 0, 0, 0]);
+        let map = &mut lvd.partition_maps[6..70];
+        map[0] = 2;
+        map[1] = 64;
+        map[5..5 + SPARABLE_PARTITION_ID.len()].copy_from_slice(SPARABLE_PARTITION_ID);
+        map[36..38].copy_from_slice(&1u16.to_le_bytes());
+        map[38..40].copy_from_slice(&3u16.to_le_bytes());
+        map[40..42].copy_from_slice(&32u16.to_le_bytes());
+        map[42] = 2;
+        map[44..48].copy_from_slice(&72u32.to_le_bytes());
+        map[48..52].copy_from_slice(&290u32.to_le_bytes());
+        map[52..56].copy_from_slice(&291u32.to_le_bytes());
+
+        assert_eq!(lvd.type1_partition_maps().count(), 1);
+        assert_eq!(lvd.sparable_partition_maps().count(), 1);
+        let sparable = lvd.sparable_partition_maps().next().expect("sparable map");
+        assert_eq!(sparable.partition_number, 3);
+        assert_eq!(sparable.packet_length, 32);
+        assert_eq!(sparable.sparing_table_size, 72);
+        assert_eq!(sparable.table_locations(), &[290, 291]);
+
+        // A Type 2 map naming another partition type is not sparable.
+        lvd.partition_maps[6 + 5] = b'#';
+        assert_eq!(lvd.sparable_partition_maps().count(), 0);
+    }
 }
@@ -8,6 +8,7 @@ mod fileset;
 mod logical;
 mod partition;
 mod primary;
+mod sparing;
 mod tag;
 
 pub use anchor::AnchorVolumeDescriptorPointer;
@@ -15,6 +16,11 @@ pub use fileset::FileSetDescriptor;
 pub use logical::{LogicalVolumeDescriptor, Type1PartitionMap};
 pub use partition::{PartitionContents, PartitionDescriptor};
 pub use primary::PrimaryVolumeDescriptor;
+#[cfg(feature = "alloc")]
+pub use sparing::SparingTable;
+pub use sparing::{
+    SPARABLE_PARTITION_ID, SPARING_TABLE_ID, SparablePartitionMap, SparingEntry, SparingTableHeader,
+};
 pub use tag::{DescriptorTag, TagIdentifier};
 
 use super::super::{Read, Seek, SeekFrom};
@@ -0,0 +1,262 @@
+//! Sparable partitions (UDF 2.2.9) and sparing tables (UDF 2.2.12)
+//!
+//! Packet-written rewritable media (CD-RW, DVD-RW) record a Type 2 partition
+//! map that divides the partition into fixed-length packets. Packets that go
+//! bad are relocated to spare packets outside the partition, and the sparing
+//! table records each relocation.
+
+#[cfg(feature = "alloc")]
+use super::TagIdentifier;
+use super::{DescriptorTag, EntityIdentifier};
+#[cfg(feature = "alloc")]
+use crate::error::{Error, Result};
+
+/// Partition type identifier of a sparable partition map
+pub const SPARABLE_PARTITION_ID: &[u8] = b"*UDF Sparable Partition";
+
+/// Entity identifier of a sparing table
+pub const SPARING_TABLE_ID: &[u8] = b"*UDF Sparing Table";
+
+/// Type 2 Sparable Partition Map (UDF 2.2.9)
+///
+/// @hadris-spec UDF:2.2.9
+/// @hadris-compliance partial
+/// @hadris-note Sparable maps are parsed and applied to the partition they name; only one partition per logical volume is read.
+/// @hadris-tests descriptor::logical::tests::sparable_partition_maps_parses_type2_entry
+/// @hadris-fuzz udf_read
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+pub struct SparablePartitionMap {
+    /// Partition Map Type (2)
+    pub partition_map_type: u8,
+    /// Partition Map Length (64)
+    pub partition_map_length: u8,
+    /// Reserved
+    reserved: [u8; 2],
+    /// Partition Type Identifier (`*UDF Sparable Partition`)
+    pub partition_type_identifier: EntityIdentifier,
+    /// Volume Sequence Number
+    pub volume_sequence_number: u16,
+    /// Partition Number
+    pub partition_number: u16,
+    /// Packet Length (in blocks)
+    pub packet_length: u16,
+    /// Number of Sparing Tables (1-4)
+    pub num_sparing_tables: u8,
+    /// Reserved
+    reserved2: u8,
+    /// Size of each Sparing Table (in bytes)
+    pub sparing_table_size: u32,
+    /// Locations of the Sparing Tables (physical sectors)
+    pub sparing_table_locations: [u32; 4],
+}
+
+impl SparablePartitionMap {
+    /// Size of the map in bytes
+    pub const SIZE: usize = 64;
+
+    pub(crate) fn into_native(mut self) -> Self {
+        self.volume_sequence_number = self.volume_sequence_number.to_le();
+        self.partition_number = self.partition_number.to_le();
+        self.packet_length = self.packet_length.to_le();
+        self.sparing_table_size = self.sparing_table_size.to_le();
+        for location in &mut self.sparing_table_locations {
+            *location = location.to_le();
+        }
+        self
+    }
+
+    /// Locations of the recorded sparing tables
+    pub fn table_locations(&self) -> &[u32] {
+        let count = (self.num_sparing_tables as usize).min(self.sparing_table_locations.len());
+        &self.sparing_table_locations[..count]
+    }
+}
+
+/// Sparing Table header (UDF 2.2.12)
+///
+/// The header is followed by `reallocation_table_length` [`SparingEntry`]
+/// values.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+pub struct SparingTableHeader {
+    /// Descriptor tag (identifier 0)
+    pub tag: DescriptorTag,
+    /// Sparing Identifier (`*UDF Sparing Table`)
+    pub sparing_identifier: EntityIdentifier,
+    /// Number of map entries
+    pub reallocation_table_length: u16,
+    /// Reserved
+    reserved: [u8; 2],
+    /// Sequence Number, incremented whenever the table is rewritten
+    pub sequence_number: u32,
+}
+
+impl SparingTableHeader {
+    /// Size of the header in bytes
+    pub const SIZE: usize = 56;
+}
+
+/// Sparing table map entry (UDF 2.2.12)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bytemuck::Zeroable, bytemuck::Pod)]
+pub struct SparingEntry {
+    /// First logical block of the relocated packet, or one of the marker
+    /// values [`SparingEntry::AVAILABLE`] and [`SparingEntry::DEFECTIVE`]
+    pub original_location: u32,
+    /// First physical sector of the spare packet
+    pub mapped_location: u32,
+}
+
+impl SparingEntry {
+    /// Size of an entry in bytes
+    pub const SIZE: usize = 8;
+    /// Original location of a spare packet that is free for relocation
+    pub const AVAILABLE: u32 = 0xFFFF_FFF0;
+    /// Original location of a spare packet that is itself defective
+    pub const DEFECTIVE: u32 = 0xFFFF_FFF1;
+
+    /// Whether this entry relocates a packet of the partition
+    pub fn is_remapped(&self) -> bool {
+        self.original_location < Self::AVAILABLE
+    }
+}
+
+/// A parsed sparing table
+///
+/// @hadris-spec UDF:2.2.12
+/// @hadris-compliance partial
+/// @hadris-note Tables are validated and the copy with the highest sequence number remaps reads; the reader never updates a table.
+/// @hadris-tests descriptor::sparing::tests::parse_validates_and_remaps_packets, write::tests::sparable_partition_reads_through_relocated_packets
+/// @hadris-fuzz udf_read
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparingTable {
+    /// Sequence Number of this copy
+    pub sequence_number: u32,
+    /// Map entries in recorded order
+    pub entries: alloc::vec::Vec<SparingEntry>,
+}
+
+#[cfg(feature = "alloc")]
+impl SparingTable {
+    /// Parse and validate a sparing table recorded at sector `location`.
+    ///
+    /// `bytes` must hold the whole table as recorded.
+    pub fn parse(bytes: &[u8], location: u32) -> Result<Self> {
+        let tag = DescriptorTag::from_disk_bytes(bytes)?;
+        // UDF assigns sparing tables identifier 0, which ECMA-167 leaves
+        // undefined and `TagIdentifier` therefore reports as unknown.
+        if tag.tag_identifier != 0 {
+            return Err(Error::InvalidTag {
+                expected: 0,
+                found: tag.tag_identifier,
+            });
+        }
+        DescriptorTag::validate_bytes(bytes, TagIdentifier::Unknown, location)?;
+
+        let header = bytemuck::try_pod_read_unaligned::<SparingTableHeader>(
+            bytes
+                .get(..SparingTableHeader::SIZE)
+                .ok_or(Error::InvalidSparing("sparing table is truncated"))?,
+        )
+        .map_err(Error::PodCastError)?;
+        if !header.sparing_identifier.is(SPARING_TABLE_ID) {
+            return Err(Error::InvalidSparing("sparing table identifier mismatch"));
+        }
+
+        let count = u16::from_le(header.reallocation_table_length) as usize;
+        let end = SparingTableHeader::SIZE + count * SparingEntry::SIZE;
+        let map = bytes
+            .get(SparingTableHeader::SIZE..end)
+            .ok_or(Error::InvalidSparing("sparing table entries are truncated"))?;
+        let entries = map
+            .chunks_exact(SparingEntry::SIZE)
+            .map(|chunk| {
+                let entry = bytemuck::pod_read_unaligned::<SparingEntry>(chunk);
+                SparingEntry {
+                    original_location: u32::from_le(entry.original_location),
+                    mapped_location: u32::from_le(entry.mapped_location),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            sequence_number: u32::from_le(header.sequence_number),
+            entries,
+        })
+    }
+
+    /// Physical sector holding `block`, if its packet has been relocated.
+    pub fn remap(&self, block: u32, packet_length: u16) -> Option<u32> {
+        let packet_length = u32::from(packet_length);
+        if packet_length == 0 {
+            return None;
+        }
+        let offset = block % packet_length;
+        let packet = block - offset;
+        self.entries
+            .iter()
+            .find(|entry| entry.is_remapped() && entry.original_location == packet)
+            .and_then(|entry| entry.mapped_location.checked_add(offset))
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+
+    static_assertions::const_assert_eq!(
+        size_of::<SparablePartitionMap>(),
+        SparablePartitionMap::SIZE
+    );
+    static_assertions::const_assert_eq!(size_of::<SparingTableHeader>(), SparingTableHeader::SIZE);
+    static_assertions::const_assert_eq!(size_of::<SparingEntry>(), SparingEntry::SIZE);
+
+    fn table_bytes(location: u32, entries: &[(u32, u32)]) -> alloc::vec::Vec<u8> {
+        let mut bytes = alloc::vec![0u8; SparingTableHeader::SIZE + entries.len() * 8];
+        bytes[17..17 + SPARING_TABLE_ID.len()].copy_from_slice(SPARING_TABLE_ID);
+        bytes[48..50].copy_from_slice(&(entries.len() as u16).to_le_bytes());
+        bytes[52..56].copy_from_slice(&7u32.to_le_bytes());
+        for (i, (original, mapped)) in entries.iter().enumerate() {
+            let at = SparingTableHeader::SIZE + i * 8;
+            bytes[at..at + 4].copy_from_slice(&original.to_le_bytes());
+            bytes[at + 4..at + 8].copy_from_slice(&mapped.to_le_bytes());
+        }
+
+        let crc_length = (bytes.len() - 16) as u16;
+        let crc = super::super::tag::crc16_itu(&bytes[16..]);
+        bytes[2..4].copy_from_slice(&2u16.to_le_bytes());
+        bytes[8..10].copy_from_slice(&crc.to_le_bytes());
+        bytes[10..12].copy_from_slice(&crc_length.to_le_bytes());
+        bytes[12..16].copy_from_slice(&location.to_le_bytes());
+        bytes[4] = bytes[..16]
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| i != 4)
+            .fold(0u8, |sum, (_, &byte)| sum.wrapping_add(byte));
+        bytes
+    }
+
+    #[test]
+    fn parse_validates_and_remaps_packets() {
+        let bytes = table_bytes(300, &[(64, 1000), (SparingEntry::AVAILABLE, 1032)]);
+        let table = SparingTable::parse(&bytes, 300).unwrap();
+        assert_eq!(table.sequence_number, 7);
+        assert_eq!(table.entries.len(), 2);
+
+        assert_eq!(table.remap(64, 32), Some(1000));
+        assert_eq!(table.remap(95, 32), Some(1031));
+        assert_eq!(table.remap(96, 32), None);
+        assert_eq!(table.remap(63, 32), None);
+
+        assert!(SparingTable::parse(&bytes, 301).is_err());
+        assert!(SparingTable::parse(&bytes[..bytes.len() - 8], 300).is_err());
+        let mut corrupt = bytes.clone();
+        corrupt[60] ^= 1;
+        assert!(matches!(
+            SparingTable::parse(&corrupt, 300),
+            Err(Error::CrcMismatch { .. })
+        ));
+    }
+}
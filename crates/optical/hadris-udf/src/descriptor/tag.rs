@@ -287,7 +287,7 @@ impl core::fmt::Display for TagIdentifier {
 /// CRC-16-ITU (CCITT) used by UDF
 ///
 /// Polynomial: x^16 + x^12 + x^5 + 1 (0x1021)
-fn crc16_itu(data: &[u8]) -> u16 {
+pub(super) fn crc16_itu(data: &[u8]) -> u16 {
     let mut crc: u16 = 0;
     for &byte in data {
         let mut x = ((crc >> 8) ^ (byte as u16)) & 0xFF;
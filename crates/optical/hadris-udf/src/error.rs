@@ -47,6 +47,8 @@ pub enum Error {
     TooManyAllocationDescriptors,
     /// Directory nesting exceeds the supported depth
     DirectoryNestingTooDeep,
+    /// Invalid sparable partition map, sparing table, or sparing options
+    InvalidSparing(&'static str),
     /// byte casting failed - the data buffer size doesn't match the target struct size.
     PodCastError(bytemuck::PodCastError),
 }
@@ -90,6 +92,7 @@ impl core::fmt::Display for Error {
                 write!(f, "allocation descriptors exceed one File Entry sector")
             }
             Self::DirectoryNestingTooDeep => write!(f, "directory nesting too deep"),
+            Self::InvalidSparing(reason) => write!(f, "invalid sparable partition: {reason}"),
             Self::PodCastError(err) => write!(
                 f,
                 "byte casting failed - the data buffer size doesn't match the target struct size. {err}"
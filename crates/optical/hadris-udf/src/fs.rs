@@ -10,7 +10,8 @@ use super::descriptor::DescriptorTag;
 use super::descriptor::{
     self, AnchorVolumeDescriptorPointer, ExtentDescriptor, FileSetDescriptor,
     LogicalVolumeDescriptor, LongAllocationDescriptor, PartitionDescriptor,
-    PrimaryVolumeDescriptor, TagIdentifier, parse_vrs,
+    PrimaryVolumeDescriptor, SparablePartitionMap, SparingTable, SparingTableHeader, TagIdentifier,
+    parse_vrs,
 };
 use super::dir::{
     FileCharacteristics, FileIdentifierDescriptor, UdfDir, UdfDirEntry, decode_filename,
@@ -39,6 +40,7 @@ pub struct UdfVolumeInfo {
 pub struct UdfVolume<DATA: Read + Seek> {
     data: Mutex<DATA>,
     info: UdfVolumeInfo,
+    partition: PartitionMapping,
     root_icb: LongAllocationDescriptor,
 }
 
@@ -47,6 +49,59 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
     pub fn into_inner(self) -> DATA {
         self.data.into_inner()
     }
+
+    /// Packet length (in blocks) when the partition is sparable.
+    ///
+    /// Returns `None` for Type 1 partitions.
+    pub fn packet_length(&self) -> Option<u16> {
+        self.partition
+            .sparing
+            .as_ref()
+            .map(|sparing| sparing.packet_length)
+    }
+
+    /// Get the sparing table used to remap a sparable partition.
+    ///
+    /// Returns `None` for Type 1 partitions.
+    pub fn sparing_table(&self) -> Option<&SparingTable> {
+        self.partition
+            .sparing
+            .as_ref()
+            .map(|sparing| &sparing.table)
+    }
+}
+
+/// Translation from partition logical blocks to image sectors.
+struct PartitionMapping {
+    start: u32,
+    sparing: Option<Sparing>,
+}
+
+/// Packet relocation for a sparable partition.
+struct Sparing {
+    packet_length: u16,
+    table: SparingTable,
+}
+
+impl PartitionMapping {
+    /// Image sector holding logical block `block`.
+    fn sector(&self, block: u32) -> u64 {
+        self.run(block).0
+    }
+
+    /// Image sector holding `block`, and the number of blocks from `block`
+    /// that are contiguous on the image.
+    fn run(&self, block: u32) -> (u64, u64) {
+        let Some(sparing) = &self.sparing else {
+            return (self.start as u64 + block as u64, u64::MAX);
+        };
+        let packet_length = sparing.packet_length as u64;
+        let remaining = packet_length - block as u64 % packet_length;
+        match sparing.table.remap(block, sparing.packet_length) {
+            Some(sector) => (sector as u64, remaining),
+            None => (self.start as u64 + block as u64, remaining),
+        }
+    }
 }
 
 io_transform! {
@@ -61,7 +116,12 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         let avdp = AnchorVolumeDescriptorPointer::find(&mut data, None).await?;
 
         // Read the Main Volume Descriptor Sequence
-        let (pvd, partition, lvd, fsd) = Self::read_vds(&mut data, &avdp.main_vds_extent).await?;
+        let (pvd, partition, lvd) = Self::read_vds(&mut data, &avdp.main_vds_extent).await?;
+        let mapping = Self::read_partition_mapping(&mut data, &partition, &lvd).await?;
+
+        // Read File Set Descriptor from the location in LVD
+        let fsd_location = lvd.file_set_location();
+        let fsd = Self::read_file_set_descriptor(&mut data, &mapping, &fsd_location).await?;
 
         let fallback_revision = match vrs_type {
             descriptor::VrsType::Nsr02 => UdfRevision::V1_02,
@@ -82,6 +142,7 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         Ok(Self {
             data: Mutex::new(data),
             info,
+            partition: mapping,
             root_icb,
         })
     }
@@ -94,7 +155,6 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         PrimaryVolumeDescriptor,
         PartitionDescriptor,
         LogicalVolumeDescriptor,
-        FileSetDescriptor,
     )> {
         let start_sector = extent.location as u64;
         let num_sectors = (extent.length as u64).div_ceil(SECTOR_SIZE as u64);
@@ -168,20 +228,81 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         let partition = partition.ok_or(Error::InvalidPartition(0))?;
         let lvd = lvd.ok_or(Error::InvalidVds("LVD"))?;
 
-        // Read File Set Descriptor from the location in LVD
-        let fsd_location = lvd.file_set_location();
-        let fsd = Self::read_file_set_descriptor(data, &partition, &fsd_location).await?;
+        Ok((pvd, partition, lvd))
+    }
 
-        Ok((pvd, partition, lvd, fsd))
+    /// Build the block mapping for the partition, loading its sparing table
+    /// when the logical volume maps it through a sparable partition map.
+    async fn read_partition_mapping(
+        data: &mut DATA,
+        partition: &PartitionDescriptor,
+        lvd: &LogicalVolumeDescriptor,
+    ) -> Result<PartitionMapping> {
+        let start = partition.partition_starting_location;
+        let Some(map) = lvd
+            .sparable_partition_maps()
+            .find(|map| map.partition_number == partition.partition_number)
+        else {
+            return Ok(PartitionMapping {
+                start,
+                sparing: None,
+            });
+        };
+        if map.packet_length == 0 {
+            return Err(Error::InvalidSparing("packet length is zero"));
+        }
+
+        let table = Self::read_sparing_table(data, &map).await?;
+        Ok(PartitionMapping {
+            start,
+            sparing: Some(Sparing {
+                packet_length: map.packet_length,
+                table,
+            }),
+        })
+    }
+
+    /// Read every recorded copy of a sparing table and keep the valid copy
+    /// with the highest sequence number.
+    async fn read_sparing_table(
+        data: &mut DATA,
+        map: &SparablePartitionMap,
+    ) -> Result<SparingTable> {
+        // The reallocation table length is 16-bit, which bounds the table
+        // size before the untrusted map size drives an allocation.
+        const MAX_TABLE_SIZE: usize = SparingTableHeader::SIZE + u16::MAX as usize * 8;
+        let size = map.sparing_table_size as usize;
+        if !(SparingTableHeader::SIZE..=MAX_TABLE_SIZE).contains(&size) {
+            return Err(Error::InvalidSparing("sparing table size is out of range"));
+        }
+
+        let mut buffer = alloc::vec![0u8; size];
+        let mut newest: Option<SparingTable> = None;
+        for &location in map.table_locations() {
+            data.seek(SeekFrom::Start(location as u64 * SECTOR_SIZE as u64)).await?;
+            if data.read_exact(&mut buffer).await.is_err() {
+                continue;
+            }
+            let Ok(table) = SparingTable::parse(&buffer, location) else {
+                continue;
+            };
+            if newest
+                .as_ref()
+                .is_none_or(|newest| table.sequence_number > newest.sequence_number)
+            {
+                newest = Some(table);
+            }
+        }
+        newest.ok_or(Error::InvalidSparing("no valid sparing table"))
     }
 
     /// Read the File Set Descriptor
     async fn read_file_set_descriptor(
         data: &mut DATA,
-        partition: &PartitionDescriptor,
+        partition: &PartitionMapping,
         icb: &LongAllocationDescriptor,
     ) -> Result<FileSetDescriptor> {
-        let sector = partition.partition_starting_location as u64 + icb.logical_block_num as u64;
+        let sector = partition.sector(icb.logical_block_num);
         data.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64)).await?;
 
         let mut buffer = [0u8; SECTOR_SIZE];
@@ -271,7 +392,7 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         data: &mut DATA,
         icb: &LongAllocationDescriptor,
     ) -> Result<IcbMetadata> {
-        let sector = self.info.partition_start as u64 + icb.logical_block_num as u64;
+        let sector = self.partition.sector(icb.logical_block_num);
         data.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64))
             .await?;
 
@@ -363,9 +484,8 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
                     if sad.length() == 0 {
                         break;
                     }
-                    let sector = self.info.partition_start as u64 + sad.extent_position as u64;
                     let extent = self
-                        .read_extent(data, sector, sad.length() as usize)
+                        .read_extent(data, sad.extent_position, sad.length() as usize)
                         .await?;
                     out.extend_from_slice(&extent);
                 }
@@ -382,9 +502,8 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
                     if lad.length() == 0 {
                         break;
                     }
-                    let sector = self.info.partition_start as u64 + lad.logical_block_num as u64;
                     let extent = self
-                        .read_extent(data, sector, lad.length() as usize)
+                        .read_extent(data, lad.logical_block_num, lad.length() as usize)
                         .await?;
                     out.extend_from_slice(&extent);
                 }
@@ -412,9 +531,9 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         Ok(entries)
     }
 
-    /// Read an extent from disk
-    async fn read_extent(&self, data: &mut DATA, sector: u64, length: usize) -> Result<Vec<u8>> {
-        let start = sector * SECTOR_SIZE as u64;
+    /// Read an extent of `length` bytes starting at partition block `block`
+    async fn read_extent(&self, data: &mut DATA, block: u32, length: usize) -> Result<Vec<u8>> {
+        let start = (self.info.partition_start as u64 + block as u64) * SECTOR_SIZE as u64;
         // `length` is the untrusted extent length from an on-disk allocation
         // descriptor (30-bit, up to ~1 GiB per descriptor, and the caller chains
         // many). Bound it against the actual image size before allocating,
@@ -425,9 +544,30 @@ impl<DATA: Read + Seek> UdfVolume<DATA> {
         if start.saturating_add(length as u64) > image_len {
             return Err(Error::InvalidIcb);
         }
-        data.seek(SeekFrom::Start(start)).await?;
         let mut buffer = alloc::vec![0u8; length];
-        data.read_exact(&mut buffer).await?;
+        // A sparable partition may relocate any packet of the extent, so it
+        // is read one contiguous run at a time.
+        let mut filled = 0;
+        let mut block = block;
+        while filled < length {
+            let (sector, blocks) = self.partition.run(block);
+            let run = blocks
+                .saturating_mul(SECTOR_SIZE as u64)
+                .min((length - filled) as u64) as usize;
+            // A remapped run comes from the untrusted sparing table, so it
+            // is bounded on its own.
+            let offset = sector * SECTOR_SIZE as u64;
+            if offset.saturating_add(run as u64) > image_len {
+                return Err(Error::InvalidIcb);
+            }
+            data.seek(SeekFrom::Start(offset)).await?;
+            data.read_exact(&mut buffer[filled..filled + run]).await?;
+            filled += run;
+            if filled < length {
+                // Only a packet-bounded run can stop short of the extent end.
+                block = block.checked_add(blocks as u32).ok_or(Error::InvalidIcb)?;
+            }
+        }
         Ok(buffer)
     }
 
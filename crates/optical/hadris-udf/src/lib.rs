@@ -14,8 +14,8 @@
 //!
 //! This crate supports:
 //! - **UDF 1.02**: DVD-ROM (read-only)
-//! - **UDF 1.50**: DVD-RAM, packet writing (planned)
-//! - **UDF 2.01**: DVD-RW, streaming (planned)
+//! - **UDF 1.50**: DVD-RAM, packet writing with sparable partitions
+//! - **UDF 2.01**: DVD-RW, sparable partitions
 //!
 //! ## Quick Start
 //!
@@ -63,7 +63,9 @@
 //! ## Known Limitations
 //!
 //! - Extended allocation descriptors and stream directories are not supported.
-//! - Packet writing / sparing tables / Blu-ray-specific features are not implemented.
+//! - VAT, metadata partitions, and Blu-ray-specific features are not implemented.
+//!   Sparable partitions are read and written, but the reader never relocates
+//!   packets itself.
 //! - Directory listing reads each file ICB to populate
 //!   [`dir::UdfDirEntry::size`] (one extra seek per file).
 //!
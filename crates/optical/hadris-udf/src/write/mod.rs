@@ -37,11 +37,12 @@ use core::mem::size_of;
 
 use super::super::{Seek, SeekFrom, Write};
 use super::descriptor::{
-    DescriptorTag, ExtentDescriptor, LongAllocationDescriptor, ShortAllocationDescriptor,
+    DescriptorTag, ExtentDescriptor, LongAllocationDescriptor, SPARABLE_PARTITION_ID,
+    SPARING_TABLE_ID, ShortAllocationDescriptor, SparingEntry, SparingTableHeader,
     TagIdentifier,
 };
 use crate::dir::FileCharacteristics;
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::file::FileType;
 use crate::time::UdfTimestamp;
 use crate::{AVDP_LOCATION, SECTOR_SIZE, UdfRevision};
@@ -165,6 +166,31 @@ pub struct UdfWriteOptions {
     pub partition_length: u32,
 }
 
+/// Sparable partition layout for packet-written media (UDF 2.2.9)
+///
+/// [`UdfWriter::create_sparable`] places the sparing tables and spare packets
+/// between the Logical Volume Integrity Descriptor and the partition, and
+/// aligns the partition start and length to whole packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparingOptions {
+    /// Packet length in blocks (32 for CD-RW, 16 for DVD-RW)
+    pub packet_length: u16,
+    /// Number of sparing table copies to record (1-4)
+    pub table_count: u8,
+    /// Number of spare packets available for relocation
+    pub spare_packets: u16,
+}
+
+impl Default for SparingOptions {
+    fn default() -> Self {
+        Self {
+            packet_length: 32,
+            table_count: 2,
+            spare_packets: 16,
+        }
+    }
+}
+
 /// Result of creating a complete UDF image.
 pub struct UdfCreateOutput<W> {
     /// Recovered output target.
@@ -284,33 +310,117 @@ impl<W: Write + Seek> UdfWriter<W> {
         root: &SimpleDir,
         options: UdfWriteOptions,
     ) -> Result<UdfCreateOutput<W>> {
-        let mut formatter = UdfFormatter::new(writer, options);
+        Self::format(writer, root, options, None)
+    }
+
+    /// Creates a complete UDF filesystem on a sparable partition for
+    /// packet-written rewritable media (UDF 1.50 and later).
+    ///
+    /// The low-level descriptor methods always write a Type 1 partition map.
+    pub fn create_sparable(
+        writer: W,
+        root: &SimpleDir,
+        options: UdfWriteOptions,
+        sparing: SparingOptions,
+    ) -> Result<UdfCreateOutput<W>> {
+        Self::format(writer, root, options, Some(sparing))
+    }
+
+    fn format(
+        writer: W,
+        root: &SimpleDir,
+        options: UdfWriteOptions,
+        sparing: Option<SparingOptions>,
+    ) -> Result<UdfCreateOutput<W>> {
+        let mut formatter = UdfFormatter::new(writer, options, sparing);
         let sectors_written = formatter.format(root)?;
         Ok(UdfCreateOutput {
             target: formatter.into_inner(),
             sectors_written,
         })
     }
-
 }
 
 /// Maximum `SimpleDir` nesting accepted by the formatter; deeper trees would
 /// overflow the stack in the recursive allocation and write passes.
 const MAX_DIRECTORY_DEPTH: usize = 128;
 
+/// Planned placement of the sparing tables and spare packets
+#[derive(Debug)]
+struct SparingLayout {
+    options: SparingOptions,
+    table_size: u32,
+    table_locations: Vec<u32>,
+    spare_locations: Vec<u32>,
+}
+
+impl SparingLayout {
+    /// Plan the sparing area from `first_sector` and return it together with
+    /// the packet-aligned partition start that follows it.
+    fn plan(
+        options: SparingOptions,
+        revision: UdfRevision,
+        first_sector: u32,
+    ) -> Result<(Self, u32)> {
+        if revision < UdfRevision::V1_50 {
+            return Err(Error::InvalidSparing(
+                "sparable partitions require UDF 1.50 or later",
+            ));
+        }
+        if options.packet_length == 0 {
+            return Err(Error::InvalidSparing("packet length is zero"));
+        }
+        if !(1..=4).contains(&options.table_count) {
+            return Err(Error::InvalidSparing("sparing table count must be 1 to 4"));
+        }
+        // The descriptor CRC length is 16-bit and covers the whole table.
+        let table_size =
+            SparingTableHeader::SIZE + options.spare_packets as usize * SparingEntry::SIZE;
+        if table_size - DescriptorTag::SIZE > u16::MAX as usize {
+            return Err(Error::InvalidSparing("too many spare packets"));
+        }
+
+        let packet_length = options.packet_length as u32;
+        let table_sectors = table_size.div_ceil(SECTOR_SIZE) as u32;
+        let table_locations = (0..options.table_count as u32)
+            .map(|i| first_sector + i * table_sectors)
+            .collect::<Vec<_>>();
+        let spare_start = (first_sector + options.table_count as u32 * table_sectors)
+            .next_multiple_of(packet_length);
+        let spare_locations = (0..options.spare_packets as u32)
+            .map(|i| spare_start + i * packet_length)
+            .collect::<Vec<_>>();
+        let partition_start = spare_start + options.spare_packets as u32 * packet_length;
+
+        Ok((
+            Self {
+                options,
+                table_size: table_size as u32,
+                table_locations,
+                spare_locations,
+            },
+            partition_start,
+        ))
+    }
+}
+
 /// Internal formatter that handles the full UDF format process
 struct UdfFormatter<W: Write + Seek> {
     writer: W,
     options: UdfWriteOptions,
+    sparing_options: Option<SparingOptions>,
+    sparing: Option<SparingLayout>,
     next_block: u32,
     unique_id_counter: u64,
 }
 
 impl<W: Write + Seek> UdfFormatter<W> {
-    fn new(writer: W, options: UdfWriteOptions) -> Self {
+    fn new(writer: W, options: UdfWriteOptions, sparing_options: Option<SparingOptions>) -> Self {
         Self {
             writer,
             options,
+            sparing_options,
+            sparing: None,
             next_block: 0,
             unique_id_counter: 16, // UDF reserves IDs 0-15
         }
@@ -344,19 +454,32 @@ impl<W: Write + Seek> UdfFormatter<W> {
         // Sector 290+:    Partition starts here
         //   Block 0:     FSD
         //   Block 1+:    Root dir File Entry, FIDs, subdirs, file data
+        //
+        // A sparable partition instead starts after the sparing tables and
+        // spare packets that follow the LVID, on a packet boundary.
 
         let vds_start = 257u32;
         let vds_length = 16u32;
         let reserve_vds_start = vds_start + vds_length;
         let lvid_location = reserve_vds_start + vds_length;
-        let partition_start = lvid_location + 1;
+        let mut partition_start = lvid_location + 1;
+        if let Some(options) = self.sparing_options {
+            let (layout, start) =
+                SparingLayout::plan(options, self.options.revision, partition_start)?;
+            self.sparing = Some(layout);
+            partition_start = start;
+        }
 
         // Phase 2: Allocate all structures within the partition
         let fsd_block = self.allocate_block(); // 0
         let allocated_root = self.allocate_directory(root, fsd_block, 0)?;
 
-        // Calculate partition length
-        let partition_length = self.next_block;
+        // Calculate partition length, in whole packets when sparable
+        let mut partition_length = self.next_block;
+        if let Some(layout) = &self.sparing {
+            partition_length =
+                partition_length.next_multiple_of(layout.options.packet_length as u32);
+        }
 
         // Update options with calculated values
         self.options.partition_start = partition_start;
@@ -409,6 +532,9 @@ impl<W: Write + Seek> UdfFormatter<W> {
         // Write LVID
         self.write_lvid(lvid_location)?;
 
+        // Write sparing tables
+        self.write_sparing_tables()?;
+
         // Write FSD
         let root_icb = LongAllocationDescriptor {
             extent_length: SECTOR_SIZE as u32,
@@ -772,8 +898,10 @@ impl<W: Write + Seek> UdfFormatter<W> {
         let pc_offset = offset + 8;
         self.write_entity_identifier(&mut buffer[pc_offset..pc_offset + 32], nsr);
 
+        // Sparable partitions are overwritable; mastered ones are read-only.
+        let access_type: u32 = if self.sparing.is_some() { 4 } else { 1 };
         let at_offset = pc_offset + 32 + 128;
-        buffer[at_offset..at_offset + 4].copy_from_slice(&1u32.to_le_bytes());
+        buffer[at_offset..at_offset + 4].copy_from_slice(&access_type.to_le_bytes());
 
         let psl_offset = at_offset + 4;
         buffer[psl_offset..psl_offset + 4]
@@ -827,8 +955,9 @@ impl<W: Write + Seek> UdfFormatter<W> {
         let lvcu_offset = di_offset + 32;
         buffer[lvcu_offset..lvcu_offset + 16].copy_from_slice(bytemuck::bytes_of(&fsd_location));
 
+        let map_table_length: u32 = if self.sparing.is_some() { 64 } else { 6 };
         let mtl_offset = lvcu_offset + 16;
-        buffer[mtl_offset..mtl_offset + 4].copy_from_slice(&6u32.to_le_bytes());
+        buffer[mtl_offset..mtl_offset + 4].copy_from_slice(&map_table_length.to_le_bytes());
         buffer[mtl_offset + 4..mtl_offset + 8].copy_from_slice(&1u32.to_le_bytes());
 
         let impl_offset = mtl_offset + 8;
@@ -841,10 +970,14 @@ impl<W: Write + Seek> UdfFormatter<W> {
             .copy_from_slice(&integrity_extent.location.to_le_bytes());
 
         let pm_offset = ise_offset + 8;
-        buffer[pm_offset] = 1;
-        buffer[pm_offset + 1] = 6;
-        buffer[pm_offset + 2..pm_offset + 4].copy_from_slice(&1u16.to_le_bytes());
-        buffer[pm_offset + 4..pm_offset + 6].copy_from_slice(&0u16.to_le_bytes());
+        if let Some(layout) = &self.sparing {
+            self.write_sparable_partition_map(&mut buffer[pm_offset..pm_offset + 64], layout);
+        } else {
+            buffer[pm_offset] = 1;
+            buffer[pm_offset + 1] = 6;
+            buffer[pm_offset + 2..pm_offset + 4].copy_from_slice(&1u16.to_le_bytes());
+            buffer[pm_offset + 4..pm_offset + 6].copy_from_slice(&0u16.to_le_bytes());
+        }
 
         let tag = self.create_tag(
             TagIdentifier::LogicalVolumeDescriptor,
@@ -917,6 +1050,52 @@ impl<W: Write + Seek> UdfFormatter<W> {
         Ok(())
     }
 
+    /// Write a Type 2 sparable partition map (UDF 2.2.9) for partition 0.
+    fn write_sparable_partition_map(&self, buffer: &mut [u8], layout: &SparingLayout) {
+        buffer[0] = 2;
+        buffer[1] = 64;
+        self.write_entity_identifier(&mut buffer[4..36], SPARABLE_PARTITION_ID);
+        buffer[28..30].copy_from_slice(&self.options.revision.to_raw().to_le_bytes());
+        buffer[36..38].copy_from_slice(&1u16.to_le_bytes());
+        buffer[38..40].copy_from_slice(&0u16.to_le_bytes());
+        buffer[40..42].copy_from_slice(&layout.options.packet_length.to_le_bytes());
+        buffer[42] = layout.options.table_count;
+        buffer[44..48].copy_from_slice(&layout.table_size.to_le_bytes());
+        for (i, location) in layout.table_locations.iter().enumerate() {
+            let offset = 48 + i * 4;
+            buffer[offset..offset + 4].copy_from_slice(&location.to_le_bytes());
+        }
+    }
+
+    /// Write every copy of the sparing table (UDF 2.2.12), listing each
+    /// spare packet as available.
+    fn write_sparing_tables(&mut self) -> Result<()> {
+        let Some(layout) = &self.sparing else {
+            return Ok(());
+        };
+        let table_size = layout.table_size as usize;
+        let mut table = vec![0u8; table_size.div_ceil(SECTOR_SIZE) * SECTOR_SIZE];
+        self.write_entity_identifier(&mut table[16..48], SPARING_TABLE_ID);
+        table[40..42].copy_from_slice(&self.options.revision.to_raw().to_le_bytes());
+        table[48..50].copy_from_slice(&layout.options.spare_packets.to_le_bytes());
+        for (i, location) in layout.spare_locations.iter().enumerate() {
+            let offset = SparingTableHeader::SIZE + i * SparingEntry::SIZE;
+            table[offset..offset + 4].copy_from_slice(&SparingEntry::AVAILABLE.to_le_bytes());
+            table[offset + 4..offset + 8].copy_from_slice(&location.to_le_bytes());
+        }
+
+        let locations = layout.table_locations.clone();
+        for location in locations {
+            // Sparing tables carry tag identifier 0 and a CRC over the whole
+            // table, which may exceed one 512-byte descriptor.
+            let tag = descriptor_tag(0, location, &table[16..table_size]);
+            table[0..16].copy_from_slice(bytemuck::bytes_of(&tag));
+            self.seek_to_sector(location)?;
+            self.writer.write_all(&table)?;
+        }
+        Ok(())
+    }
+
     fn write_lvid(&mut self, location: u32) -> Result<()> {
         self.seek_to_sector(location)?;
         let mut buffer = [0u8; 512];
@@ -1151,30 +1330,7 @@ impl<W: Write + Seek> UdfFormatter<W> {
     }
 
     fn create_tag(&self, identifier: TagIdentifier, location: u32, data: &[u8]) -> DescriptorTag {
-        let crc_length = data.len().min(496) as u16;
-        let crc = crc16_itu(&data[..crc_length as usize]);
-
-        let mut tag = DescriptorTag {
-            tag_identifier: identifier.to_u16(),
-            descriptor_version: 2,
-            tag_checksum: 0,
-            reserved: 0,
-            tag_serial_number: 0,
-            descriptor_crc: crc,
-            descriptor_crc_length: crc_length,
-            tag_location: location,
-        };
-
-        let bytes = bytemuck::bytes_of(&tag);
-        let mut sum: u8 = 0;
-        for (i, &byte) in bytes.iter().enumerate() {
-            if i != 4 {
-                sum = sum.wrapping_add(byte);
-            }
-        }
-        tag.tag_checksum = sum;
-
-        tag
+        descriptor_tag(identifier.to_u16(), location, &data[..data.len().min(496)])
     }
 
     fn write_dstring(&self, buffer: &mut [u8], s: &str) {
@@ -2021,6 +2177,31 @@ mod cs0_tests {
     }
 }
 
+/// Build a version 2 descriptor tag whose CRC covers all of `data`.
+fn descriptor_tag(identifier: u16, location: u32, data: &[u8]) -> DescriptorTag {
+    let mut tag = DescriptorTag {
+        tag_identifier: identifier,
+        descriptor_version: 2,
+        tag_checksum: 0,
+        reserved: 0,
+        tag_serial_number: 0,
+        descriptor_crc: crc16_itu(data),
+        descriptor_crc_length: data.len() as u16,
+        tag_location: location,
+    };
+
+    let bytes = bytemuck::bytes_of(&tag);
+    let mut sum: u8 = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if i != 4 {
+            sum = sum.wrapping_add(byte);
+        }
+    }
+    tag.tag_checksum = sum;
+
+    tag
+}
+
 /// CRC-16-ITU (CCITT) used by UDF
 fn crc16_itu(data: &[u8]) -> u16 {
     let mut crc: u16 = 0;
@@ -2344,6 +2525,142 @@ mod tests {
         assert_eq!(bytes, payload);
     }
 
+    fn sparable_options() -> UdfWriteOptions {
+        UdfWriteOptions {
+            revision: crate::UdfRevision::V2_01,
+            ..Default::default()
+        }
+    }
+
+    const SPARING: SparingOptions = SparingOptions {
+        packet_length: 16,
+        table_count: 2,
+        spare_packets: 4,
+    };
+
+    #[test]
+    fn sparable_partition_reads_through_relocated_packets() {
+        let payload = (0..40 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut root = SimpleDir::root();
+        root.add_file(SimpleFile::new("archive.bin", payload.clone()));
+        let mut buffer = vec![0u8; 4 * 1024 * 1024];
+        UdfWriter::create_sparable(
+            Cursor::new(&mut buffer[..]),
+            &root,
+            sparable_options(),
+            SPARING,
+        )
+        .unwrap();
+
+        let (partition_start, spares) = {
+            let udf = crate::UdfVolume::open(Cursor::new(&buffer[..])).unwrap();
+            let info = udf.info();
+            assert_eq!(udf.packet_length(), Some(16));
+            assert_eq!(info.partition_start % 16, 0);
+            assert_eq!(info.partition_length % 16, 0);
+            let table = udf.sparing_table().expect("sparing table");
+            assert_eq!(table.entries.len(), 4);
+            assert!(table.entries.iter().all(|entry| !entry.is_remapped()));
+            let root = udf.root_dir().unwrap();
+            let entry = root.find("archive.bin").unwrap();
+            assert_eq!(udf.read_file(entry).unwrap(), payload);
+            let spares = table
+                .entries
+                .iter()
+                .map(|entry| entry.mapped_location)
+                .collect::<Vec<_>>();
+            (info.partition_start, spares)
+        };
+
+        // Relocate the packets holding the FSD, the ICBs, and the file data,
+        // leaving garbage in their original place.
+        let packet_bytes = 16 * SECTOR_SIZE;
+        for (packet, &spare) in spares.iter().take(2).enumerate() {
+            let original = (partition_start as usize + packet * 16) * SECTOR_SIZE;
+            let mapped = spare as usize * SECTOR_SIZE;
+            buffer.copy_within(original..original + packet_bytes, mapped);
+            buffer[original..original + packet_bytes].fill(0xEE);
+        }
+
+        // Record the relocation in the second table copy only, with a newer
+        // sequence number; the reader must prefer it over the first copy.
+        let location = 291u32;
+        let table_size = SparingTableHeader::SIZE + 4 * SparingEntry::SIZE;
+        let offset = location as usize * SECTOR_SIZE;
+        let table = &mut buffer[offset..offset + table_size];
+        table[52..56].copy_from_slice(&1u32.to_le_bytes());
+        table[56..60].copy_from_slice(&0u32.to_le_bytes());
+        table[64..68].copy_from_slice(&16u32.to_le_bytes());
+        let tag = descriptor_tag(0, location, &table[16..]);
+        table[..16].copy_from_slice(bytemuck::bytes_of(&tag));
+
+        let udf = crate::UdfVolume::open(Cursor::new(&buffer[..])).unwrap();
+        let table = udf.sparing_table().unwrap();
+        assert_eq!(table.sequence_number, 1);
+        assert_eq!(table.remap(17, 16), Some(spares[1] + 1));
+        let root = udf.root_dir().unwrap();
+        let entry = root.find("archive.bin").unwrap();
+        assert_eq!(udf.read_file(entry).unwrap(), payload);
+    }
+
+    #[test]
+    fn remapped_run_past_the_image_is_rejected() {
+        let payload = (0..40 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let mut root = SimpleDir::root();
+        root.add_file(SimpleFile::new("archive.bin", payload));
+        let mut buffer = vec![0u8; 4 * 1024 * 1024];
+        UdfWriter::create_sparable(
+            Cursor::new(&mut buffer[..]),
+            &root,
+            sparable_options(),
+            SPARING,
+        )
+        .unwrap();
+
+        // Relocate the second packet, which holds file data, far beyond the
+        // end of the image.
+        let location = 291u32;
+        let table_size = SparingTableHeader::SIZE + 4 * SparingEntry::SIZE;
+        let offset = location as usize * SECTOR_SIZE;
+        let table = &mut buffer[offset..offset + table_size];
+        table[52..56].copy_from_slice(&1u32.to_le_bytes());
+        table[56..60].copy_from_slice(&16u32.to_le_bytes());
+        table[60..64].copy_from_slice(&0x7000_0000u32.to_le_bytes());
+        let tag = descriptor_tag(0, location, &table[16..]);
+        table[..16].copy_from_slice(bytemuck::bytes_of(&tag));
+
+        let udf = crate::UdfVolume::open(Cursor::new(&buffer[..])).unwrap();
+        let root = udf.root_dir().unwrap();
+        let entry = root.find("archive.bin").unwrap();
+        assert!(matches!(udf.read_file(entry), Err(Error::InvalidIcb)));
+    }
+
+    #[test]
+    fn sparing_requires_udf_1_50() {
+        let mut buffer = vec![0u8; 2 * 1024 * 1024];
+        let result = UdfWriter::create_sparable(
+            Cursor::new(&mut buffer[..]),
+            &SimpleDir::root(),
+            UdfWriteOptions {
+                revision: crate::UdfRevision::V1_02,
+                ..sparable_options()
+            },
+            SPARING,
+        );
+        assert!(matches!(result, Err(Error::InvalidSparing(_))));
+
+        let result = UdfWriter::create_sparable(
+            Cursor::new(&mut buffer[..]),
+            &SimpleDir::root(),
+            sparable_options(),
+            SparingOptions {
+                table_count: 5,
+                ..SparingOptions::default()
+            },
+        );
+        assert!(matches!(result, Err(Error::InvalidSparing(_))));
+    }
+
     #[test]
     fn test_roundtrip_large_file_read() {
         let mut buffer = vec![0u8; 8 * 1024 * 1024];
@@ -11,7 +11,7 @@ use std::sync::Arc;
 use std::task::{Wake, Waker};
 
 use hadris_udf::r#async::UdfVolume;
-use hadris_udf::sync::write::{SimpleDir, SimpleFile, UdfWriteOptions, UdfWriter};
+use hadris_udf::sync::write::{SimpleDir, SimpleFile, SparingOptions, UdfWriteOptions, UdfWriter};
 
 struct ThreadWaker(std::thread::Thread);
 
@@ -59,3 +59,31 @@ fn async_leaf_opens_traverses_and_reads_nested_file() {
         assert!(volume.read_file(docs).await.is_err());
     });
 }
+
+#[test]
+fn async_leaf_reads_sparable_partition() {
+    let payload = vec![0x5A_u8; 24 * 2048];
+    let mut root = SimpleDir::root();
+    root.add_file(SimpleFile::new("PACKET.BIN", payload.clone()));
+
+    let options = UdfWriteOptions {
+        revision: hadris_udf::UdfRevision::V1_50,
+        ..UdfWriteOptions::default()
+    };
+    let image = std::io::Cursor::new(vec![0_u8; 4 * 1024 * 1024]);
+    let output =
+        UdfWriter::create_sparable(image, &root, options, SparingOptions::default()).unwrap();
+    let bytes = output.target.into_inner();
+
+    block_on(async {
+        let volume = UdfVolume::open(hadris_io::Cursor::new(bytes.as_slice()))
+            .await
+            .unwrap();
+        assert_eq!(volume.packet_length(), Some(32));
+        assert_eq!(volume.sparing_table().unwrap().entries.len(), 16);
+
+        let root = volume.root_dir().await.unwrap();
+        let file = root.find("PACKET.BIN").unwrap();
+        assert_eq!(volume.read_file(file).await.unwrap(), payload);
+    });
+}
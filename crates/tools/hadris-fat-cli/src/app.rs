@@ -2,14 +2,19 @@
 
 use std::fs::{self, File, OpenOptions};
 use std::io::{Read as StdRead, Write as StdWrite};
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result, bail};
 use clap::{Parser, Subcommand, ValueEnum};
 use hadris_fat::format::{FatFormatOptions, FatTypeSelection, FatVolumeFormatter};
 use hadris_fat::raw::DirEntryAttrFlags;
+use hadris_fat::tool::{DefragOptions, LostChainAction, RepairOptions};
 use hadris_fat::{DirectoryEntry, FatDir};
-use hadris_fat::{FatAnalysisExt, FatVerifyExt, FatVolume, FatVolumeWriteExt, Read as FatRead};
+use hadris_fat::{
+    FatAnalysisExt, FatDefragExt, FatRepairExt, FatVerifyExt, FatVolume, FatVolumeWriteExt,
+    Read as FatRead,
+};
 
 #[derive(Parser)]
 #[command(name = "hadris-fat")]
@@ -61,6 +66,17 @@ enum Commands {
         #[arg(short, long, default_value = "10")]
         top: usize,
     },
+    /// Move fragmented files and directories into contiguous clusters
+    Defrag {
+        /// Path to the FAT image file
+        image: PathBuf,
+        /// Stop after moving this many clusters; run again to continue
+        #[arg(long)]
+        max_clusters: Option<u32>,
+        /// Leave directories in place
+        #[arg(long)]
+        files_only: bool,
+    },
     /// Verify filesystem integrity
     Verify {
         /// Path to the FAT image file
@@ -69,6 +85,17 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
     },
+    /// Repair filesystem damage found by a consistency check
+    Repair {
+        /// Path to the FAT image file
+        image: PathBuf,
+        /// Report what would be repaired without writing to the image
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+        /// Free lost cluster chains instead of recovering them into FOUND.nnn
+        #[arg(long)]
+        free_lost: bool,
+    },
     /// Show cluster chain for a file
     Chain {
         /// Path to the FAT image file
@@ -132,7 +159,17 @@ pub fn run() -> Result<()> {
         Commands::Ls { image, path, long } => cmd_ls(image, &path, long),
         Commands::Tree { image, path, depth } => cmd_tree(image, &path, depth),
         Commands::Fragmentation { image, top } => cmd_fragmentation(image, top),
+        Commands::Defrag {
+            image,
+            max_clusters,
+            files_only,
+        } => cmd_defrag(image, max_clusters, files_only),
         Commands::Verify { image, verbose } => cmd_verify(image, verbose),
+        Commands::Repair {
+            image,
+            dry_run,
+            free_lost,
+        } => cmd_repair(image, dry_run, free_lost),
         Commands::Chain { image, file_path } => cmd_chain(image, &file_path),
         Commands::Cat { image, path } => cmd_cat(image, &path),
         Commands::Extract {
@@ -429,6 +466,99 @@ fn cmd_verify(image: PathBuf, verbose: bool) -> Result<()> {
     Ok(())
 }
 
+fn open_fat_fs_writable(path: PathBuf) -> Result<FatVolume<File>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open image file: {}", path.display()))?;
+    FatVolume::open(file).context("Failed to parse FAT filesystem")
+}
+
+fn cmd_defrag(image: PathBuf, max_clusters: Option<u32>, files_only: bool) -> Result<()> {
+    let fs = open_fat_fs_writable(image)?;
+    let mut options = DefragOptions::new().files_only(files_only);
+    options.max_clusters = max_clusters;
+
+    println!("Filesystem Defragmentation");
+    println!("==========================");
+    let report = fs
+        .defragment(&options, |progress| {
+            println!(
+                "[{}/{}] {}: {} fragments -> 1",
+                progress.chains_moved,
+                progress.chains_total,
+                progress.chain.path,
+                progress.chain.fragments
+            );
+            ControlFlow::Continue(())
+        })
+        .context("Failed to defragment filesystem")?;
+
+    println!();
+    println!("Files Moved:       {}", report.files_moved);
+    println!("Directories Moved: {}", report.directories_moved);
+    println!("Clusters Moved:    {}", report.clusters_moved);
+    if !report.skipped.is_empty() {
+        println!();
+        println!("Skipped (no contiguous free space):");
+        for chain in &report.skipped {
+            println!("  - {} ({} fragments)", chain.path, chain.fragments);
+        }
+    }
+    if !report.is_complete() {
+        println!();
+        println!(
+            "Stopped at the cluster budget; {} fragmented chain(s) remain. Run again to continue.",
+            report.remaining
+        );
+    }
+
+    Ok(())
+}
+
+fn cmd_repair(image: PathBuf, dry_run: bool, free_lost: bool) -> Result<()> {
+    let fs = if dry_run {
+        open_fat_fs(image)?
+    } else {
+        open_fat_fs_writable(image)?
+    };
+    let lost_chains = if free_lost {
+        LostChainAction::Free
+    } else {
+        LostChainAction::Recover
+    };
+    let options = RepairOptions::new()
+        .dry_run(dry_run)
+        .lost_chains(lost_chains);
+    let report = fs.repair(&options).context("Failed to repair filesystem")?;
+
+    println!("Filesystem Repair");
+    println!("=================");
+    if report.is_clean() {
+        println!("Result: CLEAN - Nothing to repair");
+    } else if report.dry_run {
+        println!(
+            "Result: DRY RUN - {} repair(s) needed",
+            report.action_count()
+        );
+    } else {
+        println!(
+            "Result: REPAIRED - {} repair(s) made",
+            report.action_count()
+        );
+    }
+    if !report.is_clean() {
+        println!();
+        println!("Actions:");
+        for action in &report.actions {
+            println!("  - {action}");
+        }
+    }
+
+    Ok(())
+}
+
 fn cmd_chain(image: PathBuf, file_path: &str) -> Result<()> {
     let fs = open_fat_fs(image)?;
 
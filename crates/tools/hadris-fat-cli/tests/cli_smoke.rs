@@ -15,6 +15,8 @@ fn help_succeeds() {
     let stdout = String::from_utf8_lossy(&output.stdout);
     assert!(stdout.contains("info"));
     assert!(stdout.contains("verify"));
+    assert!(stdout.contains("repair"));
+    assert!(stdout.contains("defrag"));
     assert!(stdout.contains("create"));
     assert!(stdout.contains("extract"));
 }
@@ -72,6 +74,71 @@ fn create_cat_and_extract_roundtrip() {
     );
 }
 
+#[test]
+fn repair_reports_clean_image() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::write(source.join("hello.txt"), b"hello from FAT").unwrap();
+    let image = temp.path().join("disk.img");
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_hadris-fat"))
+        .args([
+            "create",
+            source.to_str().unwrap(),
+            "--output",
+            image.to_str().unwrap(),
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    for args in [&["repair", "--dry-run"][..], &["repair"][..]] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_hadris-fat"))
+            .args(args)
+            .arg(&image)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).contains("CLEAN"));
+    }
+}
+
+#[test]
+fn defrag_leaves_image_consistent() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    std::fs::create_dir_all(source.join("nested")).unwrap();
+    std::fs::write(source.join("nested/hello.txt"), b"hello from FAT").unwrap();
+    let image = temp.path().join("disk.img");
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_hadris-fat"))
+        .args([
+            "create",
+            source.to_str().unwrap(),
+            "--output",
+            image.to_str().unwrap(),
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hadris-fat"))
+        .arg("defrag")
+        .arg(&image)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Clusters Moved:"));
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hadris-fat"))
+        .args(["cat", image.to_str().unwrap(), "/nested/hello.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"hello from FAT");
+}
+
 #[cfg(unix)]
 #[test]
 fn create_rejects_symbolic_links() {
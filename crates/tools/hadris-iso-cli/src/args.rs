@@ -124,6 +124,9 @@ pub struct CreateArgs {
     /// Enable GPT hybrid boot for UEFI USB booting
     #[arg(long)]
     pub hybrid_gpt: bool,
+    /// Also write an Apple Partition Map for older Intel Macs
+    #[arg(long)]
+    pub hybrid_apm: bool,
     /// System identifier (max 32 characters)
     #[arg(long, alias = "sysid")]
     pub system_id: Option<String>,
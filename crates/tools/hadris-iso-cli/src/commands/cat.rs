@@ -5,7 +5,7 @@ use hadris_iso::read::IsoImage;
 
 use super::super::args::CatArgs;
 
-use super::{Result, navigate_to_path};
+use super::{Result, copy_file, navigate_to_path};
 
 /// Print file contents to stdout
 pub fn cat(args: CatArgs) -> Result<()> {
@@ -32,14 +32,9 @@ pub fn cat(args: CatArgs) -> Result<()> {
             format!("File not found: {}", args.path).into()
         })?;
 
-    let extent = file_entry.header().extent.read() as u64;
-    let size = file_entry.header().data_len.read() as usize;
-
-    if size > 0 {
-        let mut buffer = vec![0u8; size];
-        iso.read_bytes_at(extent * 2048, &mut buffer)?;
-        io::stdout().write_all(&buffer)?;
-    }
+    let mut stdout = io::stdout().lock();
+    copy_file(&iso, &file_entry, &mut stdout)?;
+    stdout.flush()?;
 
     Ok(())
 }
@@ -7,8 +7,10 @@ use hadris_iso::boot::{EmulationType, PlatformId};
 use hadris_iso::joliet::JolietLevel;
 use hadris_iso::read::PathSeparator;
 use hadris_iso::rrip::RripOptions;
-use hadris_iso::write::options::{CreationFeatures, HybridBootOptions, IsoFormatOptions};
-use hadris_iso::write::{InputTree, IsoImageWriter, estimator};
+use hadris_iso::write::options::{
+    CreationExtensions, CreationFeatures, HybridBootOptions, IsoFormatOptions,
+};
+use hadris_iso::write::{InputTree, IsoImageWriter, SourcedInputTree, estimator};
 
 use super::super::args::CreateArgs;
 
@@ -73,6 +75,7 @@ pub fn create(args: CreateArgs) -> Result<()> {
     } else {
         None
     };
+    let extensions = CreationExtensions::default().with_apple_partition_map(args.hybrid_apm);
 
     // Configure Rock Ridge
     let mut filenames = args.level.0;
@@ -151,13 +154,21 @@ pub fn create(args: CreateArgs) -> Result<()> {
     let mut buffer = io::Cursor::new(vec![0u8; estimated_size as usize]);
 
     // Write ISO to buffer
-    IsoImageWriter::create(&mut buffer, input, format_options)?;
+    IsoImageWriter::create_with_sources(
+        &mut buffer,
+        SourcedInputTree::new(input),
+        format_options,
+        extensions,
+    )?;
 
     // Read volume_space_size from PVD (LE u32 at byte offset 32848)
     let data = buffer.into_inner();
     let vol_size_le = &data[32848..32852];
     let volume_sectors = u32::from_le_bytes(vol_size_le.try_into().unwrap()) as usize;
-    let actual_size = (volume_sectors * 2048).max(32 * 2048);
+    // Pad tiny images to 32 sectors, except when a backup GPT must stay in
+    // the last blocks of the image.
+    let min_size = if args.hybrid_gpt { 0 } else { 32 * 2048 };
+    let actual_size = (volume_sectors * 2048).max(min_size);
 
     // Write the ISO to file
     let mut file = File::create(&args.output)?;
@@ -1,5 +1,5 @@
 use std::fs::{self, File};
-use std::io::{self, BufReader, Read, Seek, Write};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Component, Path, PathBuf};
 
 use hadris_iso::directory::{DirectoryRef, FileFlags};
@@ -7,7 +7,7 @@ use hadris_iso::read::IsoImage;
 
 use super::super::args::ExtractArgs;
 
-use super::{Result, display_name, navigate_to_path};
+use super::{Result, copy_file, display_name, navigate_to_path};
 
 /// Extract files from an ISO image
 pub fn extract(args: ExtractArgs) -> Result<()> {
@@ -72,22 +72,15 @@ fn extract_dir<R: Read + Seek>(
             let child_ref = entry.as_dir_ref(iso)?;
             extract_dir(iso, child_ref, entry_type, &entry_path, verbose, count)?;
         } else {
-            let extent = entry.header().extent.read() as u64;
-            let size = entry.header().data_len.read() as usize;
+            let size = entry.total_size();
 
             if verbose {
                 println!("Extracting: {} ({} bytes)", entry_path.display(), size);
             }
 
-            if size > 0 {
-                let mut buffer = vec![0u8; size];
-                iso.read_bytes_at(extent * 2048, &mut buffer)?;
-                let mut output_file = File::create(&entry_path)?;
-                output_file.write_all(&buffer)?;
-            } else {
-                // Create empty file
-                File::create(&entry_path)?;
-            }
+            let mut output_file = BufWriter::new(File::create(&entry_path)?);
+            copy_file(iso, &entry, &mut output_file)?;
+            output_file.flush()?;
 
             *count += 1;
         }
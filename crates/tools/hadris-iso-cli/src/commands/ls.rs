@@ -45,7 +45,7 @@ pub fn ls(args: LsArgs) -> Result<()> {
             } else {
                 '-'
             };
-            let size = entry.header().data_len.read();
+            let size = entry.total_size();
             let extent = entry.header().extent.read();
 
             println!("{type_char}  {size:>10}  {extent:>8}  {display_name}");
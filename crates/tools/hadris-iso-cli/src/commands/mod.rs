@@ -18,7 +18,7 @@ pub use mkisofs::mkisofs;
 pub use tree::tree;
 pub use verify::verify;
 
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
 
 use hadris_iso::directory::DirectoryRef;
 use hadris_iso::file::EntryType;
@@ -63,6 +63,23 @@ fn display_name(entry: &DirEntry, entry_type: EntryType) -> String {
     }
 }
 
+/// Stream a file's contents, across every extent, into `output`.
+fn copy_file<R: Read + Seek>(
+    iso: &IsoImage<R>,
+    entry: &DirEntry,
+    output: &mut impl Write,
+) -> Result<()> {
+    let mut stream = iso.open_file(entry)?;
+    let mut buffer = vec![0u8; 256 * 1024];
+    loop {
+        let n = stream.read_chunk(&mut buffer)?;
+        if n == 0 {
+            return Ok(());
+        }
+        output.write_all(&buffer[..n])?;
+    }
+}
+
 /// Navigate into a directory path within the ISO, returning the target DirectoryRef.
 fn navigate_to_path<R: Read + Seek>(iso: &IsoImage<R>, path: &str) -> Result<DirectoryRef> {
     let root = iso.root_dir();
@@ -6,6 +6,8 @@ use hadris_iso::read::IsoImage;
 use hadris_iso::susp::SystemUseIter;
 use hadris_iso::types::Endian;
 use hadris_iso::volume::VolumeDescriptor;
+use hadris_part::gpt::{GptHeader, GptPartitionEntry, calculate_partition_array_crc32};
+use hadris_part::{GptHeaderReadExt, MasterBootRecord, MasterBootRecordReadExt};
 
 use super::super::args::VerifyArgs;
 
@@ -282,24 +284,26 @@ fn check_extent_bounds<R: Read + Seek>(
                 continue;
             }
 
-            let extent = entry.header().extent.read() as u64;
-            let data_len = entry.header().data_len.read() as u64;
-            if extent == 0 && data_len == 0 {
-                continue; // zero-size file
-            }
+            // Multi-extent files are checked extent by extent.
+            for extent in entry.extents() {
+                let data_len = extent.length as u64;
+                if extent.sector.0 == 0 && data_len == 0 {
+                    continue; // zero-size file
+                }
 
-            let end_byte = extent * 2048 + data_len;
-            if end_byte > volume_size {
-                let name = String::from_utf8_lossy(entry.name());
-                issues.push(VerifyIssue::error(format!(
-                    "Entry '{name}' extent end ({end_byte}) exceeds volume size ({volume_size})"
-                )));
-            }
-            if end_byte > file_size {
-                let name = String::from_utf8_lossy(entry.name());
-                issues.push(VerifyIssue::error(format!(
-                    "Entry '{name}' extent end ({end_byte}) exceeds file size ({file_size})"
-                )));
+                let end_byte = extent.sector.0 as u64 * 2048 + data_len;
+                if end_byte > volume_size {
+                    let name = String::from_utf8_lossy(entry.name());
+                    issues.push(VerifyIssue::error(format!(
+                        "Entry '{name}' extent end ({end_byte}) exceeds volume size ({volume_size})"
+                    )));
+                }
+                if end_byte > file_size {
+                    let name = String::from_utf8_lossy(entry.name());
+                    issues.push(VerifyIssue::error(format!(
+                        "Entry '{name}' extent end ({end_byte}) exceeds file size ({file_size})"
+                    )));
+                }
             }
 
             if entry.is_directory()
@@ -516,6 +520,170 @@ fn check_rrip_fields<R: Read + Seek>(iso: &IsoImage<R>, verbose: bool) -> Vec<Ve
     issues
 }
 
+/// Reads one GPT copy: the header at `lba` and the entry array it points
+/// to. Returns the header and the entries when both checksums hold.
+fn read_gpt_copy<R: Read + Seek>(
+    reader: &mut R,
+    lba: u64,
+    expected_alternate: Option<u64>,
+    file_size: u64,
+    label: &str,
+    issues: &mut Vec<VerifyIssue>,
+) -> Option<(GptHeader, Vec<GptPartitionEntry>)> {
+    let header_end = lba
+        .checked_add(1)
+        .and_then(|blocks| blocks.checked_mul(512));
+    if header_end.is_none_or(|end| end > file_size) {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT header at LBA {lba} lies beyond the end of the image"
+        )));
+        return None;
+    }
+    let header = match GptHeader::read_from_lba(reader, lba, 512) {
+        Ok(header) => header,
+        Err(error) => {
+            issues.push(VerifyIssue::error(format!(
+                "{label} GPT header at LBA {lba} is unreadable: {error}"
+            )));
+            return None;
+        }
+    };
+    if !header.verify_crc32() {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT header CRC mismatch (stored {:#010x}, computed {:#010x})",
+            header.header_crc32.to_ne(),
+            header.calculate_crc32()
+        )));
+        return None;
+    }
+    if header.my_lba.to_ne() != lba {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT header at LBA {lba} records its own LBA as {}",
+            header.my_lba.to_ne()
+        )));
+    }
+    if let Some(alternate) = expected_alternate
+        && header.alternate_lba.to_ne() != alternate
+    {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT header points to its alternate at LBA {} (expected {alternate})",
+            header.alternate_lba.to_ne()
+        )));
+    }
+
+    let entry_size = header.size_of_partition_entry.to_ne() as u64;
+    let array_len = header.num_partition_entries.to_ne() as u64 * entry_size;
+    let array_start = header.partition_entry_lba.to_ne().saturating_mul(512);
+    if entry_size != 128 || array_start.saturating_add(array_len) > file_size {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT entry array at LBA {} is outside the image or uses {entry_size}-byte entries",
+            header.partition_entry_lba.to_ne()
+        )));
+        return None;
+    }
+    let mut array = vec![0u8; array_len as usize];
+    if let Err(error) = reader
+        .seek(io::SeekFrom::Start(array_start))
+        .and_then(|_| reader.read_exact(&mut array))
+    {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT entry array is unreadable: {error}"
+        )));
+        return None;
+    }
+    let entries: Vec<GptPartitionEntry> = array
+        .chunks_exact(128)
+        .map(bytemuck::pod_read_unaligned)
+        .collect();
+    let crc = calculate_partition_array_crc32(&entries);
+    if crc != header.partition_entry_array_crc32.to_ne() {
+        issues.push(VerifyIssue::error(format!(
+            "{label} GPT entry array CRC mismatch (stored {:#010x}, computed {crc:#010x})",
+            header.partition_entry_array_crc32.to_ne()
+        )));
+        return None;
+    }
+    Some((header, entries))
+}
+
+/// Checks the primary and backup GPT of a hybrid image, if it has one.
+fn check_gpt(path: &std::path::Path, file_size: u64, verbose: bool) -> Vec<VerifyIssue> {
+    let mut issues = Vec::new();
+    let mut reader = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(error) => {
+            issues.push(VerifyIssue::error(format!(
+                "Failed to reopen image: {error}"
+            )));
+            return issues;
+        }
+    };
+    let Ok(mbr) = MasterBootRecord::read_from(&mut reader) else {
+        return issues;
+    };
+    let protective = mbr
+        .get_partition_table()
+        .iter()
+        .any(|partition| partition.part_type == 0xEE);
+    if !protective {
+        if verbose {
+            println!("  No GPT detected, skipping GPT checks");
+        }
+        return issues;
+    }
+
+    let last_lba = (file_size / 512).saturating_sub(1);
+    let primary = read_gpt_copy(&mut reader, 1, None, file_size, "Primary", &mut issues);
+    let backup_lba = primary
+        .as_ref()
+        .map_or(last_lba, |(header, _)| header.alternate_lba.to_ne());
+    if backup_lba != last_lba {
+        // Harmless once the image is copied to a larger disk, but an image
+        // file should end with its backup GPT.
+        issues.push(VerifyIssue::warning(format!(
+            "Backup GPT header is at LBA {backup_lba}, not the last LBA {last_lba}"
+        )));
+    }
+    let backup = read_gpt_copy(
+        &mut reader,
+        backup_lba,
+        Some(1),
+        file_size,
+        "Backup",
+        &mut issues,
+    );
+
+    if let (Some((primary, primary_entries)), Some((backup, backup_entries))) = (&primary, &backup)
+    {
+        let same_layout = primary.first_usable_lba == backup.first_usable_lba
+            && primary.last_usable_lba == backup.last_usable_lba
+            && primary.disk_guid == backup.disk_guid
+            && primary.num_partition_entries == backup.num_partition_entries
+            && primary.partition_entry_array_crc32 == backup.partition_entry_array_crc32;
+        if !same_layout {
+            issues.push(VerifyIssue::error(
+                "Primary and backup GPT headers describe different tables",
+            ));
+        }
+        let array_end =
+            backup.partition_entry_lba.to_ne() + (backup_entries.len() as u64 * 128).div_ceil(512);
+        if array_end != backup.my_lba.to_ne() {
+            issues.push(VerifyIssue::warning(
+                "Backup GPT entry array does not end just before the backup header",
+            ));
+        }
+        if verbose {
+            let used = primary_entries
+                .iter()
+                .filter(|entry| !entry.is_unused())
+                .count();
+            println!("  GPT: {used} partition(s), primary and backup copies checked");
+        }
+    }
+
+    issues
+}
+
 /// Verify ISO image integrity
 pub fn verify(args: VerifyArgs) -> Result<()> {
     let file = File::open(&args.input)?;
@@ -546,17 +714,20 @@ pub fn verify(args: VerifyArgs) -> Result<()> {
         all_issues.extend(check_boot_catalog(&iso, catalog_sector, args.verbose));
     }
 
-    // 5. Path table consistency (strict only)
+    // 5. Primary and backup GPT (always, if a protective MBR is present)
+    all_issues.extend(check_gpt(&args.input, file_size, args.verbose));
+
+    // 6. Path table consistency (strict only)
     if args.strict && found_pvd {
         all_issues.extend(check_path_table_consistency(&iso, args.verbose));
     }
 
-    // 6. Extent bounds (strict only)
+    // 7. Extent bounds (strict only)
     if args.strict && found_pvd {
         all_issues.extend(check_extent_bounds(&iso, file_size, args.verbose));
     }
 
-    // 7. RRIP fields (strict only, if Rock Ridge detected)
+    // 8. RRIP fields (strict only, if Rock Ridge detected)
     if args.strict && found_pvd {
         all_issues.extend(check_rrip_fields(&iso, args.verbose));
     }
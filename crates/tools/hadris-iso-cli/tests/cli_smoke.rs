@@ -102,3 +102,138 @@ fn joliet_names_are_decoded_by_listing_commands() {
     );
     assert!(!stdout.contains('\0'), "stdout contains NULs: {stdout:?}");
 }
+
+#[test]
+fn verify_checks_both_gpt_copies() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    let image = temp.path().join("hybrid.iso");
+    std::fs::create_dir(&source).unwrap();
+    std::fs::write(source.join("a.txt"), "payload").unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_hadris-iso-cli");
+    let create = std::process::Command::new(bin)
+        .args(["create", "--hybrid-mbr", "--hybrid-gpt", "--output"])
+        .arg(&image)
+        .arg(&source)
+        .output()
+        .expect("create hybrid test ISO");
+    assert!(
+        create.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&create.stderr)
+    );
+
+    let verify = std::process::Command::new(bin)
+        .args(["verify", "--verbose"])
+        .arg(&image)
+        .output()
+        .expect("verify hybrid test ISO");
+    let stdout = String::from_utf8_lossy(&verify.stdout);
+    assert!(verify.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("primary and backup"), "stdout: {stdout}");
+    assert!(stdout.contains("No issues found"), "stdout: {stdout}");
+
+    // Corrupt the backup header's last-usable-LBA field.
+    let mut data = std::fs::read(&image).unwrap();
+    let backup = data.len() - 512;
+    data[backup + 48] ^= 0xFF;
+    std::fs::write(&image, data).unwrap();
+    let verify = std::process::Command::new(bin)
+        .arg("verify")
+        .arg(&image)
+        .output()
+        .expect("verify corrupted ISO");
+    let stdout = String::from_utf8_lossy(&verify.stdout);
+    assert!(!verify.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("Backup GPT header CRC mismatch"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn verify_reports_an_out_of_range_backup_gpt_lba() {
+    use hadris_part::GptHeaderReadExt;
+    use hadris_part::gpt::GptHeader;
+
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    let image = temp.path().join("hybrid.iso");
+    std::fs::create_dir(&source).unwrap();
+    std::fs::write(source.join("a.txt"), "payload").unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_hadris-iso-cli");
+    let create = std::process::Command::new(bin)
+        .args(["create", "--hybrid-mbr", "--hybrid-gpt", "--output"])
+        .arg(&image)
+        .arg(&source)
+        .output()
+        .expect("create hybrid test ISO");
+    assert!(
+        create.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&create.stderr)
+    );
+
+    // Point the primary header at an unreachable backup, keeping its CRC valid.
+    let mut data = std::fs::read(&image).unwrap();
+    let mut header = GptHeader::read_from_lba(&mut std::io::Cursor::new(&data), 1, 512).unwrap();
+    header.alternate_lba = hadris_part::Le::<u64>::from_ne(u64::MAX);
+    header.update_crc32();
+    data[512 + 16..512 + 20].copy_from_slice(&header.header_crc32.to_ne().to_le_bytes());
+    data[512 + 32..512 + 40].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&image, data).unwrap();
+
+    let verify = std::process::Command::new(bin)
+        .arg("verify")
+        .arg(&image)
+        .output()
+        .expect("verify corrupted ISO");
+    let stdout = String::from_utf8_lossy(&verify.stdout);
+    assert!(!verify.status.success(), "stdout: {stdout}");
+    assert!(
+        stdout.contains("beyond the end of the image"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn apple_partition_map_images_pass_verify() {
+    let temp = tempfile::tempdir().unwrap();
+    let source = temp.path().join("source");
+    let image = temp.path().join("apm.iso");
+    std::fs::create_dir(&source).unwrap();
+    std::fs::write(source.join("a.txt"), "payload").unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_hadris-iso-cli");
+    let create = std::process::Command::new(bin)
+        .args([
+            "create",
+            "--hybrid-mbr",
+            "--hybrid-gpt",
+            "--hybrid-apm",
+            "--output",
+        ])
+        .arg(&image)
+        .arg(&source)
+        .output()
+        .expect("create APM test ISO");
+    assert!(
+        create.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&create.stderr)
+    );
+    let data = std::fs::read(&image).unwrap();
+    assert_eq!(&data[..2], b"ER");
+    assert_eq!(&data[2048..2050], b"PM");
+
+    let verify = std::process::Command::new(bin)
+        .arg("verify")
+        .arg(&image)
+        .output()
+        .expect("verify APM test ISO");
+    let stdout = String::from_utf8_lossy(&verify.stdout);
+    assert!(verify.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("No issues found"), "stdout: {stdout}");
+}
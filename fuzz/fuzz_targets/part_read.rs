@@ -1,5 +1,5 @@
 #![no_main]
-//! Fuzz the partition table reader: detect and parse MBR/GPT from arbitrary
+//! Fuzz the partition table reader: detect and parse MBR/GPT/APM from arbitrary
 //! bytes at common logical block sizes. Arbitrary bytes must never
 //! panic/abort/OOM.
 
@@ -10,7 +10,8 @@ use hadris_part::{PartitionTable, PartitionTableReadExt};
 
 fn drive(data: &[u8]) {
     // `PartitionTable::read_from` detects the scheme: it reads the MBR at
-    // LBA 0, then follows the protective/hybrid path into GPT parsing.
+    // LBA 0, then follows the protective/hybrid path into GPT parsing or a
+    // DDM into APM parsing.
     for logical_block_size in [512u32, 4096] {
         let mut cursor = Cursor::new(data);
         let Ok(table) = PartitionTable::read_from(&mut cursor, logical_block_size) else {
@@ -24,6 +24,7 @@ crates=(
   hadris-io
   hadris-iso
   hadris-macros
+  hadris-ntfs
   hadris-optical
   hadris-part
   hadris-path